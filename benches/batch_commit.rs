@@ -0,0 +1,57 @@
+//! Compares committing to the witness columns of a circuit in one batch, with the interleaved MSM
+//! of the Pedersen and multilinear KZG schemes, against committing to each column separately.
+
+use ark_bn254::{Bn254, Fr, G1Affine};
+use ark_std::{test_rng, UniformRand};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use sangria_impl::{
+    kzg::MultilinearKZG, pedersen::PedersenCommitment, HomomorphicCommitmentScheme,
+};
+
+/// The number of witness columns of a PLONK circuit.
+const NUMBER_OF_WIRES: usize = 3;
+
+const LOG_LENGTHS: [usize; 2] = [10, 14];
+
+/// Commits to `NUMBER_OF_WIRES` random vectors of each length with the scheme `C`, in one batch
+/// and one vector at a time.
+fn bench_scheme<C: HomomorphicCommitmentScheme<Fr>>(c: &mut Criterion, name: &str) {
+    let rng = &mut test_rng();
+
+    let mut group = c.benchmark_group(name);
+    group.sample_size(10);
+    for log_length in LOG_LENGTHS {
+        let length = 1 << log_length;
+        let commit_key = C::setup(rng, length);
+        let columns: Vec<Vec<Fr>> = (0..NUMBER_OF_WIRES)
+            .map(|_| (0..length).map(|_| Fr::rand(rng)).collect())
+            .collect();
+        let hidings: Vec<Fr> = (0..NUMBER_OF_WIRES).map(|_| Fr::rand(rng)).collect();
+
+        group.bench_with_input(BenchmarkId::new("batch", length), &columns, |b, columns| {
+            b.iter(|| C::batch_commit(&commit_key, black_box(columns), &hidings))
+        });
+        group.bench_with_input(
+            BenchmarkId::new("separate", length),
+            &columns,
+            |b, columns| {
+                b.iter(|| {
+                    columns
+                        .iter()
+                        .zip(&hidings)
+                        .map(|(column, &hiding)| C::commit(&commit_key, black_box(column), hiding))
+                        .collect::<Result<Vec<_>, _>>()
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn bench_batch_commit(c: &mut Criterion) {
+    bench_scheme::<PedersenCommitment<G1Affine>>(c, "pedersen");
+    bench_scheme::<MultilinearKZG<Bn254>>(c, "kzg");
+}
+
+criterion_group!(benches, bench_batch_commit);
+criterion_main!(benches);
@@ -0,0 +1,186 @@
+//! Measures the operations of the folding verifier natively and reports the cost of the same
+//! operations in-circuit, as the augmented circuit would pay it, from the gadget cost model: deriving
+//! the folding challenge with each transcript and folding the scalars of the instances. Transcripts
+//! without a gadget in the crate are only timed.
+
+use ark_bn254::Fr;
+use ark_crypto_primitives::sponge::{
+    poseidon::{find_poseidon_ark_and_mds, PoseidonConfig, PoseidonSponge},
+    FieldElementSize,
+};
+use ark_ff::PrimeField;
+use ark_std::{test_rng, UniformRand};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use sangria_impl::{
+    fold_scalars, poseidon_hash_gadget, Blake3Transcript, CostModel, GadgetCost, KeccakTranscript,
+    RowTag, SangriaError, TraceTable, Transcript,
+};
+
+/// The numbers of field elements absorbed before a challenge is squeezed, standing in for instances
+/// with few and many public inputs.
+const ABSORBED_ELEMENTS: [usize; 3] = [4, 16, 64];
+
+/// The sizes of the folding challenge, in bits.
+const CHALLENGE_BITS: [usize; 2] = [64, 128];
+
+/// The numbers of scalars folded: the scaling factor and the public inputs.
+const FOLDED_SCALARS: [usize; 3] = [2, 8, 32];
+
+/// Poseidon constants of rate 2 for BN254, with the round numbers of the crate's tests.
+fn poseidon_constants() -> PoseidonConfig<Fr> {
+    let (full_rounds, partial_rounds, alpha, rate) = (8, 57, 5, 2);
+    let (ark, mds) = find_poseidon_ark_and_mds::<Fr>(
+        Fr::MODULUS_BIT_SIZE as u64,
+        rate,
+        full_rounds,
+        partial_rounds,
+        0,
+    );
+
+    PoseidonConfig::new(
+        full_rounds as usize,
+        partial_rounds as usize,
+        alpha,
+        mds,
+        ark,
+        rate,
+        1,
+    )
+}
+
+/// Lays out `number_of_inputs` public input rows, whose wires are returned.
+fn public_inputs(trace: &mut TraceTable<Fr>, number_of_inputs: usize) -> Vec<(usize, usize)> {
+    let rng = &mut test_rng();
+    (0..number_of_inputs)
+        .map(|_| {
+            let row = trace.number_of_rows();
+            trace.push_row(
+                RowTag::PublicInput,
+                [Fr::rand(rng), Fr::from(0u64), Fr::from(0u64)],
+            );
+            (0, row)
+        })
+        .collect()
+}
+
+fn report(operation: &str, cost: Result<GadgetCost, SangriaError>) {
+    match cost {
+        Ok(cost) => println!(
+            "{operation}: {} gate rows, {} copy constraints in-circuit",
+            cost.gate_rows, cost.copy_constraints
+        ),
+        Err(error) => println!("{operation}: the gadget failed ({error:?})"),
+    }
+}
+
+/// Absorbs `elements` into a fresh transcript and squeezes a challenge of `challenge_bits` bits.
+fn derive_challenge<T: Transcript<Fr>>(
+    constants: &PoseidonConfig<Fr>,
+    elements: &[Fr],
+    challenge_bits: usize,
+) -> Fr {
+    let mut transcript = T::start(constants);
+    transcript.absorb(&elements);
+    transcript.squeeze_field_elements_with_sizes(&[FieldElementSize::Truncated(challenge_bits)])[0]
+}
+
+fn bench_challenge(c: &mut Criterion) {
+    let rng = &mut test_rng();
+    let constants = poseidon_constants();
+
+    let mut group = c.benchmark_group("challenge");
+    for number_of_elements in ABSORBED_ELEMENTS {
+        report(
+            &format!("challenge/Poseidon/{number_of_elements}"),
+            CostModel::new().measure(|trace| {
+                let inputs = public_inputs(trace, number_of_elements);
+                poseidon_hash_gadget(trace, &constants, &inputs).map(|_| ())
+            }),
+        );
+        println!("challenge/Blake3/{number_of_elements}: no gadget in the crate");
+        println!("challenge/Keccak/{number_of_elements}: no gadget in the crate");
+
+        let elements: Vec<Fr> = (0..number_of_elements).map(|_| Fr::rand(rng)).collect();
+        for challenge_bits in CHALLENGE_BITS {
+            let parameter = format!("{number_of_elements}/{challenge_bits} bits");
+            group.bench_with_input(
+                BenchmarkId::new("Poseidon", &parameter),
+                &elements,
+                |b, elements| {
+                    b.iter(|| {
+                        derive_challenge::<PoseidonSponge<Fr>>(
+                            &constants,
+                            black_box(elements),
+                            challenge_bits,
+                        )
+                    })
+                },
+            );
+            group.bench_with_input(
+                BenchmarkId::new("Blake3", &parameter),
+                &elements,
+                |b, elements| {
+                    b.iter(|| {
+                        derive_challenge::<Blake3Transcript>(
+                            &constants,
+                            black_box(elements),
+                            challenge_bits,
+                        )
+                    })
+                },
+            );
+            group.bench_with_input(
+                BenchmarkId::new("Keccak", &parameter),
+                &elements,
+                |b, elements| {
+                    b.iter(|| {
+                        derive_challenge::<KeccakTranscript>(
+                            &constants,
+                            black_box(elements),
+                            challenge_bits,
+                        )
+                    })
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+fn bench_fold_scalars(c: &mut Criterion) {
+    let rng = &mut test_rng();
+
+    let mut group = c.benchmark_group("fold_scalars");
+    for number_of_scalars in FOLDED_SCALARS {
+        report(
+            &format!("fold_scalars/{number_of_scalars}"),
+            CostModel::new().measure(|trace| {
+                let running = public_inputs(trace, number_of_scalars);
+                let fresh = public_inputs(trace, number_of_scalars);
+                let challenge = public_inputs(trace, 1)[0];
+                fold_scalars(trace, &running, &fresh, challenge).map(|_| ())
+            }),
+        );
+
+        let running: Vec<Fr> = (0..number_of_scalars).map(|_| Fr::rand(rng)).collect();
+        let fresh: Vec<Fr> = (0..number_of_scalars).map(|_| Fr::rand(rng)).collect();
+        let r = Fr::rand(rng);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(number_of_scalars),
+            &(running, fresh),
+            |b, (running, fresh)| {
+                b.iter(|| {
+                    running
+                        .iter()
+                        .zip(fresh)
+                        .map(|(&running, &fresh)| running + black_box(r) * fresh)
+                        .collect::<Vec<_>>()
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_challenge, bench_fold_scalars);
+criterion_main!(benches);
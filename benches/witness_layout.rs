@@ -0,0 +1,69 @@
+//! Compares the witness layouts on the two passes of the prover over the witness: reading it column
+//! by column (as the commitments do) and row by row (as the cross term does).
+
+use ark_bn254::Fr;
+use ark_std::{test_rng, UniformRand};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use sangria_impl::{
+    RelaxedPLONKWitness, RowTag, TraceTable, WitnessLayout, LEFT_SELECTOR_INDEX,
+    OUTPUT_SELECTOR_INDEX, RIGHT_SELECTOR_INDEX,
+};
+
+const LOG_GATES: [usize; 2] = [12, 16];
+
+/// A witness of `number_of_gates` random addition gates.
+fn random_witness(number_of_gates: usize, layout: WitnessLayout) -> RelaxedPLONKWitness<Fr> {
+    let rng = &mut test_rng();
+    let mut addition = [Fr::from(0u64); 6];
+    addition[LEFT_SELECTOR_INDEX] = Fr::from(1u64);
+    addition[RIGHT_SELECTOR_INDEX] = Fr::from(1u64);
+    addition[OUTPUT_SELECTOR_INDEX] = -Fr::from(1u64);
+
+    let mut trace = TraceTable::new();
+    for _ in 0..number_of_gates {
+        let (a, b) = (Fr::rand(rng), Fr::rand(rng));
+        trace.push_row(RowTag::Gate(addition), [a, b, a + b]);
+    }
+    trace.push_row(RowTag::Output, [Fr::from(0u64); 3]);
+
+    let (_, witness) = trace.into_circuit_and_witness(Vec::new(), rng).unwrap();
+    witness.with_layout(layout)
+}
+
+fn bench_witness_layout(c: &mut Criterion) {
+    let mut group = c.benchmark_group("witness_layout");
+    for log_gates in LOG_GATES {
+        let number_of_gates = 1 << log_gates;
+        for layout in [WitnessLayout::ColumnMajor, WitnessLayout::RowMajor] {
+            let witness = random_witness(number_of_gates, layout);
+            let parameter = format!("{layout:?}/2^{log_gates}");
+
+            group.bench_with_input(
+                BenchmarkId::new("columns", &parameter),
+                &witness,
+                |b, witness| {
+                    b.iter(|| {
+                        for index in 0..3 {
+                            black_box(witness.witness_column(index).unwrap());
+                        }
+                    })
+                },
+            );
+            group.bench_with_input(
+                BenchmarkId::new("rows", &parameter),
+                &witness,
+                |b, witness| {
+                    b.iter(|| {
+                        for index in 0..number_of_gates {
+                            black_box(witness.witness_row(index).unwrap());
+                        }
+                    })
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_witness_layout);
+criterion_main!(benches);
@@ -531,7 +531,7 @@ mod test {
         let blinding_factor = E::Fr::rand(rng);
         let (inner1, inner2) = BatchArgument::partial_verify::<T, UnivariateKzgPCS<E>>(
             beta_g_ref,
-            &open_key_ref.g,
+            &open_key_ref.g(),
             &merged_vks,
             &[shared_public_input],
             &batch_proof,
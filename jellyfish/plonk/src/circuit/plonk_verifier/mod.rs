@@ -539,7 +539,7 @@ mod test {
             let blinding_factor = E::Fr::rand(rng);
             let (inner1, inner2) = BatchArgument::partial_verify::<T, UnivariateKzgPCS<E>>(
                 beta_g_ref,
-                &open_key_ref.g,
+                &open_key_ref.g(),
                 &merged_vks,
                 &[shared_public_input],
                 &batch_proof,
@@ -562,7 +562,7 @@ mod test {
                     &merged_vks,
                     &batch_proof,
                     beta_g_ref,
-                    &open_key_ref.g,
+                    &open_key_ref.g(),
                     &blinding_factor,
                 )?;
 
@@ -622,7 +622,7 @@ mod test {
                     &wrong_merge_vks,
                     &batch_proof,
                     beta_g_ref,
-                    &open_key_ref.g,
+                    &open_key_ref.g(),
                     &blinding_factor,
                 )
                 .is_err());
@@ -636,7 +636,7 @@ mod test {
                     &wrong_merge_vks,
                     &batch_proof,
                     beta_g_ref,
-                    &open_key_ref.g,
+                    &open_key_ref.g(),
                     &blinding_factor,
                 )
                 .is_err());
@@ -655,7 +655,7 @@ mod test {
                     &merged_vks,
                     &batch_proof,
                     beta_g_ref,
-                    &open_key_ref.g,
+                    &open_key_ref.g(),
                     &blinding_factor,
                 )?;
 
@@ -840,7 +840,7 @@ mod test {
                     &merged_vks,
                     &batch_proof,
                     beta_g_ref,
-                    &open_key_ref.g,
+                    &open_key_ref.g(),
                     &blinding_factor,
                 )?;
 
@@ -307,11 +307,11 @@ mod tests {
         let mut transcript_var = RescueTranscriptVar::new(&mut circuit);
         let mut transcript = RescueTranscript::<F>::new(label);
 
-        let open_key: UnivariateVerifierParam<E> = UnivariateVerifierParam {
-            g: E::G1Affine::prime_subgroup_generator(),
-            h: E::G2Affine::prime_subgroup_generator(),
-            beta_h: E::G2Projective::rand(&mut rng).into_affine(),
-        };
+        let open_key: UnivariateVerifierParam<E> = UnivariateVerifierParam::new(
+            E::G1Affine::prime_subgroup_generator(),
+            E::G2Affine::prime_subgroup_generator(),
+            E::G2Projective::rand(&mut rng).into_affine(),
+        );
 
         let dummy_vk: VerifyingKey<_, UnivariateKzgPCS<E>> = VerifyingKey {
             domain_size: 512,
@@ -319,7 +319,7 @@ mod tests {
             sigma_comms: Vec::new(),
             selector_comms: Vec::new(),
             k: Vec::new(),
-            open_key,
+            open_key: open_key.clone(),
             is_merged: false,
             plookup_vk: None,
         };
@@ -378,7 +378,7 @@ mod tests {
                 sigma_comms,
                 selector_comms,
                 k,
-                open_key,
+                open_key: open_key.clone(),
                 is_merged: false,
                 plookup_vk: None,
             };
@@ -30,7 +30,6 @@ use jf_primitives::{
     scalars_n_bases::ScalarsAndBases,
 };
 use jf_relation::{gadgets::ecc::SWToTEConParam, Circuit, MergeableCircuitType, PlonkCircuit};
-use jf_utils::multi_pairing;
 
 /// A batching argument.
 pub struct BatchArgument<E: CommitmentGroup>(PhantomData<E>);
@@ -225,9 +224,7 @@ where
         inner2: E::G1Projective,
     ) -> Result<bool, PlonkError> {
         // check e(elem1, [beta]2) ?= e(elem2, [1]2)
-        let g1_elems: Vec<<E as PairingEngine>::G1Affine> = vec![inner1.into(), -inner2.into()];
-        let g2_elems = vec![open_key.beta_h, open_key.h];
-        Ok(multi_pairing::<E>(&g1_elems, &g2_elems) == E::Fqk::one())
+        Ok(open_key.check_pairing_product(inner1.into(), (-inner2).into()))
     }
 }
 
@@ -390,7 +387,7 @@ mod test {
         let blinding_factor = E::Fr::rand(rng);
         let (inner1, inner2) = BatchArgument::partial_verify::<T, UnivariateKzgPCS<E>>(
             beta_g_ref,
-            &open_key_ref.g,
+            &open_key_ref.g(),
             &merged_vks,
             &[shared_public_input],
             &batch_proof,
@@ -405,7 +402,7 @@ mod test {
         // empty merged_vks
         assert!(BatchArgument::partial_verify::<T, UnivariateKzgPCS<E>>(
             beta_g_ref,
-            &open_key_ref.g,
+            &open_key_ref.g(),
             &[],
             &[shared_public_input],
             &batch_proof,
@@ -415,7 +412,7 @@ mod test {
         // the number of vks is different the number of instances
         assert!(BatchArgument::partial_verify::<T, UnivariateKzgPCS<E>>(
             beta_g_ref,
-            &open_key_ref.g,
+            &open_key_ref.g(),
             &merged_vks[1..],
             &[shared_public_input],
             &batch_proof,
@@ -427,7 +424,7 @@ mod test {
         bad_merged_vks[0].domain_size /= 2;
         assert!(BatchArgument::partial_verify::<T, UnivariateKzgPCS<E>>(
             beta_g_ref,
-            &open_key_ref.g,
+            &open_key_ref.g(),
             &bad_merged_vks,
             &[shared_public_input],
             &batch_proof,
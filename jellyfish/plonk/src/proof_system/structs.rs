@@ -734,9 +734,9 @@ where
                 .concat(),
             vk.k.iter().map(|fr| fr_to_fq::<E::Fq, P1>(fr)).collect(),
             // NOTE: only adding g, h, beta_h since only these are used.
-            group1_to_fields::<E, P1>(vk.open_key.g),
-            group2_to_fields::<E, F, P2>(vk.open_key.h),
-            group2_to_fields::<E, F, P2>(vk.open_key.beta_h),
+            group1_to_fields::<E, P1>(vk.open_key.g()),
+            group2_to_fields::<E, F, P2>(vk.open_key.h()),
+            group2_to_fields::<E, F, P2>(vk.open_key.beta_h()),
         ]
         .concat()
     }
@@ -835,13 +835,13 @@ impl<E: CommitmentGroup, S: PolynomialCommitmentScheme<E>> VerifyingKey<E, S> {
             .sigma_comms
             .iter()
             .zip(other_vk.sigma_comms.iter())
-            .map(|(com1, com2)| Commitment(com1.0 + com2.0))
+            .map(|(&com1, &com2)| com1 + com2)
             .collect();
         let selector_comms: Vec<Commitment<E>> = self
             .selector_comms
             .iter()
             .zip(other_vk.selector_comms.iter())
-            .map(|(com1, com2)| Commitment(com1.0 + com2.0))
+            .map(|(&com1, &com2)| com1 + com2)
             .collect();
 
         Ok(Self {
@@ -15,7 +15,7 @@ pub use crate::pcs::{
     structs::Commitment,
     univariate_kzg::{
         srs::{UnivariateProverParam, UnivariateUniversalParams, UnivariateVerifierParam},
-        UnivariateKzgBatchProof, UnivariateKzgPCS, UnivariateKzgProof,
+        OpeningAccumulator, UnivariateKzgBatchProof, UnivariateKzgPCS, UnivariateKzgProof,
     },
     PolynomialCommitmentScheme, StructuredReferenceString, UVPCS,
 };
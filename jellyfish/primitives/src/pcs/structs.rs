@@ -4,7 +4,12 @@
 // You should have received a copy of the MIT License
 // along with the Jellyfish library. If not, see <https://mit-license.org/>.
 
+use ark_ec::{AffineCurve, ProjectiveCurve};
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use ark_std::{
+    iter::Sum,
+    ops::{Add, Mul, Neg, Sub},
+};
 
 use super::CommitmentGroup;
 
@@ -23,3 +28,49 @@ pub struct Commitment<E: CommitmentGroup>(
     /// the actual commitment is an affine point.
     pub E::G1Affine,
 );
+
+// The arithmetic only needs the group, so that curves without a pairing (e.g. the Pasta curves)
+// combine commitments the same way as pairing-friendly ones.
+
+impl<E: CommitmentGroup> Add for Commitment<E> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Commitment(self.0 + other.0)
+    }
+}
+
+impl<E: CommitmentGroup> Neg for Commitment<E> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Commitment(-self.0)
+    }
+}
+
+impl<E: CommitmentGroup> Sub for Commitment<E> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        self + (-other)
+    }
+}
+
+impl<E: CommitmentGroup> Mul<E::Fr> for Commitment<E> {
+    type Output = Self;
+
+    fn mul(self, scalar: E::Fr) -> Self {
+        Commitment(AffineCurve::mul(&self.0, scalar).into_affine())
+    }
+}
+
+impl<E: CommitmentGroup> Sum for Commitment<E> {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        Commitment(
+            iter.fold(E::G1Projective::default(), |sum, commitment| {
+                sum.add_mixed(&commitment.0)
+            })
+            .into_affine(),
+        )
+    }
+}
@@ -0,0 +1,169 @@
+// Copyright (c) 2022 Espresso Systems (espressosys.com)
+// This file is part of the Jellyfish library.
+
+// You should have received a copy of the MIT License
+// along with the Jellyfish library. If not, see <https://mit-license.org/>.
+
+//! Accumulation of univariate KZG openings into a single batched check.
+
+use crate::{
+    pcs::{
+        prelude::Commitment, univariate_kzg::UnivariateKzgProof, PCSError,
+        PolynomialCommitmentScheme,
+    },
+    scalars_n_bases::ScalarsAndBases,
+};
+use ark_ec::PairingEngine;
+use ark_std::{vec, vec::Vec, One};
+
+use super::{UnivariateKzgPCS, UnivariateVerifierParam};
+
+/// Collects univariate KZG openings from several proofs (e.g. the witness,
+/// selector and permutation openings of a compressed proof, plus those of a
+/// decider proof) and checks all of them with one batched opening, i.e. a
+/// single product of two pairings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpeningAccumulator<E: PairingEngine> {
+    commitments: Vec<Commitment<E>>,
+    points: Vec<E::Fr>,
+    values: Vec<E::Fr>,
+    proofs: Vec<UnivariateKzgProof<E>>,
+}
+
+impl<E: PairingEngine> Default for OpeningAccumulator<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E: PairingEngine> OpeningAccumulator<E> {
+    /// Create an empty accumulator.
+    pub fn new() -> Self {
+        Self {
+            commitments: Vec::new(),
+            points: Vec::new(),
+            values: Vec::new(),
+            proofs: Vec::new(),
+        }
+    }
+
+    /// Add the claim that the polynomial committed in `commitment` evaluates
+    /// to `value` at `point`, as attested by `proof`.
+    pub fn push(
+        &mut self,
+        commitment: Commitment<E>,
+        point: E::Fr,
+        value: E::Fr,
+        proof: UnivariateKzgProof<E>,
+    ) {
+        self.commitments.push(commitment);
+        self.points.push(point);
+        self.values.push(value);
+        self.proofs.push(proof);
+    }
+
+    /// Move every opening of `other` into this accumulator.
+    pub fn merge(&mut self, other: Self) {
+        self.commitments.extend(other.commitments);
+        self.points.extend(other.points);
+        self.values.extend(other.values);
+        self.proofs.extend(other.proofs);
+    }
+
+    /// Number of accumulated openings.
+    pub fn len(&self) -> usize {
+        self.commitments.len()
+    }
+
+    /// Whether no opening was accumulated.
+    pub fn is_empty(&self) -> bool {
+        self.commitments.is_empty()
+    }
+
+    /// Check every accumulated opening at once. The `randomizers` weight the
+    /// openings after the first one and must be unpredictable to the prover,
+    /// e.g. squeezed from the transcript after all openings were absorbed. An
+    /// empty accumulator is trivially valid.
+    pub fn verify<I: IntoIterator<Item = E::Fr>>(
+        &self,
+        verifier_param: &UnivariateVerifierParam<E>,
+        randomizers: I,
+    ) -> Result<bool, PCSError> {
+        if self.is_empty() {
+            return Ok(true);
+        }
+
+        let multi_commitment: Vec<ScalarsAndBases<E>> = self
+            .commitments
+            .iter()
+            .map(|commitment| {
+                let mut scalars_and_bases = ScalarsAndBases::new();
+                scalars_and_bases.push(E::Fr::one(), commitment.0);
+                scalars_and_bases
+            })
+            .collect();
+        let combiners = vec![E::Fr::one(); self.len()];
+
+        UnivariateKzgPCS::<E>::batch_verify_aggregated(
+            verifier_param,
+            &multi_commitment,
+            [self.points.as_slice()],
+            &self.values,
+            [&self.proofs],
+            [combiners.as_slice()],
+            randomizers,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pcs::StructuredReferenceString;
+    use ark_bls12_381::Bls12_381;
+    use ark_poly::{univariate::DensePolynomial, UVPolynomial};
+    use ark_std::{test_rng, UniformRand};
+
+    fn accumulator_test_template<E>() -> Result<(), PCSError>
+    where
+        E: PairingEngine,
+    {
+        let mut rng = &mut test_rng();
+        let degree = 16;
+        let pp = UnivariateKzgPCS::<E>::gen_srs_for_testing(rng, degree)?;
+        let (ck, vk) = pp.trim(degree)?;
+
+        // two proofs, e.g. a compression proof and a decider proof, each
+        // contributing openings
+        let mut accumulators = [OpeningAccumulator::<E>::new(), OpeningAccumulator::new()];
+        for accumulator in accumulators.iter_mut() {
+            for _ in 0..5 {
+                let p = <DensePolynomial<E::Fr> as UVPolynomial<E::Fr>>::rand(degree, rng);
+                let comm = UnivariateKzgPCS::<E>::commit(&ck, &p)?;
+                let point = E::Fr::rand(rng);
+                let (proof, value) = UnivariateKzgPCS::<E>::open(&ck, &p, &point)?;
+                accumulator.push(comm, point, value, proof);
+            }
+        }
+        let [mut accumulator, decider_accumulator] = accumulators;
+        accumulator.merge(decider_accumulator);
+        assert_eq!(accumulator.len(), 10);
+
+        let randomizers: Vec<E::Fr> = (0..accumulator.len())
+            .map(|_| u128::rand(&mut rng).into())
+            .collect();
+        assert!(accumulator.verify(&vk, randomizers.clone())?);
+
+        // a single wrong evaluation invalidates the whole accumulator
+        accumulator.values[7] += E::Fr::one();
+        assert!(!accumulator.verify(&vk, randomizers)?);
+
+        assert!(OpeningAccumulator::<E>::new().verify(&vk, Vec::new())?);
+        Ok(())
+    }
+
+    #[test]
+    fn accumulator_test() {
+        accumulator_test_template::<Bls12_381>().expect("test failed for bls12-381");
+    }
+}
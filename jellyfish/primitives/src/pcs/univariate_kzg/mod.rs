@@ -30,14 +30,16 @@ use ark_std::{
     vec::Vec,
     One, Zero,
 };
-use jf_utils::multi_pairing;
 use jf_utils::par_utils::parallelizable_slice_iter;
 #[cfg(feature = "parallel")]
 use rayon::prelude::*;
 use srs::{UnivariateProverParam, UnivariateUniversalParams, UnivariateVerifierParam};
 
+mod accumulator;
 pub(crate) mod srs;
 
+pub use accumulator::OpeningAccumulator;
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 /// KZG Polynomial Commitment Scheme on univariate polynomial.
 pub struct UnivariateKzgPCS<E> {
@@ -55,7 +57,7 @@ pub struct UnivariateKzgProof<E: CommitmentGroup> {
 /// batch proof
 pub type UnivariateKzgBatchProof<E> = Vec<UnivariateKzgProof<E>>;
 
-impl<E: PairingEngine> From<Commitment<E>> for UnivariateKzgProof<E> {
+impl<E: CommitmentGroup> From<Commitment<E>> for UnivariateKzgProof<E> {
     fn from(c: Commitment<E>) -> UnivariateKzgProof<E> {
         UnivariateKzgProof { proof: c.0 }
     }
@@ -222,19 +224,12 @@ impl<E: PairingEngine> PolynomialCommitmentScheme<E> for UnivariateKzgPCS<E> {
         proof: &Self::Proof,
     ) -> Result<bool, PCSError> {
         let check_time = start_timer!(|| "Checking evaluation");
-        let pairing_inputs: Vec<(E::G1Prepared, E::G2Prepared)> = vec![
-            (
-                (verifier_param.g.mul(value.into_repr())
-                    - proof.proof.mul(point.into_repr())
-                    - commitment.0.into_projective())
-                .into_affine()
-                .into(),
-                verifier_param.h.into(),
-            ),
-            (proof.proof.into(), verifier_param.beta_h.into()),
-        ];
-
-        let res = E::product_of_pairings(pairing_inputs.iter()).is_one();
+        let h_term = (verifier_param.g().mul(value.into_repr())
+            - proof.proof.mul(point.into_repr())
+            - commitment.0.into_projective())
+        .into_affine();
+
+        let res = verifier_param.check_pairing_product(proof.proof, h_term);
 
         end_timer!(check_time, || format!("Result: {res}"));
         Ok(res)
@@ -285,7 +280,7 @@ impl<E: PairingEngine> PolynomialCommitmentScheme<E> for UnivariateKzgPCS<E> {
             ))?;
         }
 
-        total_c -= &verifier_param.g.mul(g_multiplier);
+        total_c -= &verifier_param.g().mul(g_multiplier);
         end_timer!(combination_time);
 
         let to_affine_time = start_timer!(|| "Converting results to affine for pairing");
@@ -293,11 +288,7 @@ impl<E: PairingEngine> PolynomialCommitmentScheme<E> for UnivariateKzgPCS<E> {
         end_timer!(to_affine_time);
 
         let pairing_time = start_timer!(|| "Performing product of pairings");
-        let result = multi_pairing::<E>(
-            &affine_points[..],
-            &[verifier_param.beta_h, verifier_param.h],
-        )
-        .is_one();
+        let result = verifier_param.check_pairing_product(affine_points[0], affine_points[1]);
         end_timer!(pairing_time);
         end_timer!(check_time, || format!("Result: {result}"));
         Ok(result)
@@ -337,9 +328,7 @@ impl<E: PairingEngine> PolynomialCommitmentScheme<E> for UnivariateKzgPCS<E> {
                 inners.push(*r * combiner, proof.proof);
             }
         }
-        let inner = inners.multi_scalar_mul();
-        let mut g1_elems = vec![inner.into()];
-        let mut g2_elems = vec![verifier_param.beta_h];
+        let beta_h_term = inners.multi_scalar_mul();
 
         // We now compute the pipelined variant of the term total_c
         let mut inners = ScalarsAndBases::<E>::new();
@@ -364,13 +353,11 @@ impl<E: PairingEngine> PolynomialCommitmentScheme<E> for UnivariateKzgPCS<E> {
         for (value, r) in values.iter().zip(&randomizers) {
             sum_evals += *value * r;
         }
-        inners.push(-sum_evals, verifier_param.g);
+        inners.push(-sum_evals, verifier_param.g());
         let inner = inners.multi_scalar_mul();
         // enf of total_c computation
 
-        g1_elems.push(-inner.into());
-        g2_elems.push(verifier_param.h);
-        Ok(multi_pairing::<E>(&g1_elems, &g2_elems) == E::Fqk::one())
+        Ok(verifier_param.check_pairing_product(beta_h_term.into(), (-inner).into()))
     }
 }
 
@@ -495,6 +482,38 @@ mod tests {
         Ok(())
     }
 
+    fn verifier_param_serialization_test_template<E>() -> Result<(), PCSError>
+    where
+        E: PairingEngine,
+    {
+        let rng = &mut test_rng();
+        let degree = 16;
+        let pp = UnivariateKzgPCS::<E>::gen_srs_for_testing(rng, degree)?;
+        let (ck, vk) = pp.trim(degree)?;
+
+        let mut bytes = Vec::new();
+        vk.serialize(&mut bytes)?;
+        let vk_de = UnivariateVerifierParam::<E>::deserialize(&bytes[..])?;
+        assert_eq!(vk, vk_de);
+
+        // the prepared G2 elements are recomputed on deserialization
+        let p = <DensePolynomial<E::Fr> as UVPolynomial<E::Fr>>::rand(degree, rng);
+        let comm = UnivariateKzgPCS::<E>::commit(&ck, &p)?;
+        let point = E::Fr::rand(rng);
+        let (proof, value) = UnivariateKzgPCS::<E>::open(&ck, &p, &point)?;
+        assert!(UnivariateKzgPCS::<E>::verify(
+            &vk_de, &comm, &point, &value, &proof
+        )?);
+        assert!(!UnivariateKzgPCS::<E>::verify(
+            &vk_de,
+            &comm,
+            &point,
+            &(value + E::Fr::one()),
+            &proof
+        )?);
+        Ok(())
+    }
+
     #[test]
     fn end_to_end_test() {
         end_to_end_test_template::<Bls12_381>().expect("test failed for bls12-381");
@@ -508,4 +527,10 @@ mod tests {
     fn batch_check_test() {
         batch_check_test_template::<Bls12_381>().expect("test failed for bls12-381");
     }
+
+    #[test]
+    fn verifier_param_serialization_test() {
+        verifier_param_serialization_test_template::<Bls12_381>()
+            .expect("test failed for bls12-381");
+    }
 }
@@ -54,22 +54,134 @@ pub struct UnivariateProverParam<C: AffineCurve> {
 
 /// `UnivariateVerifierParam` is used to check evaluation proofs for a given
 /// commitment.
-#[derive(Derivative, CanonicalSerialize, CanonicalDeserialize)]
+///
+/// The G2 elements `h` and `beta_h` are also kept in prepared form so that
+/// pairing checks do not have to re-prepare them on every verification. The
+/// prepared forms are computed once in [`UnivariateVerifierParam::new`] (and
+/// hence at deserialization time), which is the only way to build the
+/// parameters, so the cached forms cannot go out of sync with the elements.
+#[derive(Derivative)]
 #[derivative(
-    Default(bound = ""),
     Clone(bound = ""),
-    Copy(bound = ""),
     Debug(bound = ""),
     PartialEq(bound = ""),
     Eq(bound = "")
 )]
 pub struct UnivariateVerifierParam<E: PairingEngine> {
     /// The generator of G1.
-    pub g: E::G1Affine,
+    g: E::G1Affine,
     /// The generator of G2.
-    pub h: E::G2Affine,
+    h: E::G2Affine,
     /// \beta times the above generator of G2.
-    pub beta_h: E::G2Affine,
+    beta_h: E::G2Affine,
+    /// `h` prepared for pairings.
+    #[derivative(Debug = "ignore", PartialEq = "ignore")]
+    pub(crate) prepared_h: E::G2Prepared,
+    /// `beta_h` prepared for pairings.
+    #[derivative(Debug = "ignore", PartialEq = "ignore")]
+    pub(crate) prepared_beta_h: E::G2Prepared,
+}
+
+impl<E: PairingEngine> UnivariateVerifierParam<E> {
+    /// Build the verifier parameters, preparing `h` and `beta_h` for pairings.
+    pub fn new(g: E::G1Affine, h: E::G2Affine, beta_h: E::G2Affine) -> Self {
+        Self {
+            g,
+            h,
+            beta_h,
+            prepared_h: h.into(),
+            prepared_beta_h: beta_h.into(),
+        }
+    }
+
+    /// The generator of G1.
+    pub fn g(&self) -> E::G1Affine {
+        self.g
+    }
+
+    /// The generator of G2.
+    pub fn h(&self) -> E::G2Affine {
+        self.h
+    }
+
+    /// \beta times the generator of G2.
+    pub fn beta_h(&self) -> E::G2Affine {
+        self.beta_h
+    }
+
+    /// Check `e(beta_h_term, beta_h) * e(h_term, h) == 1` using the cached
+    /// prepared G2 elements.
+    pub fn check_pairing_product(&self, beta_h_term: E::G1Affine, h_term: E::G1Affine) -> bool {
+        let pairing_inputs: Vec<(E::G1Prepared, E::G2Prepared)> = vec![
+            (beta_h_term.into(), self.prepared_beta_h.clone()),
+            (h_term.into(), self.prepared_h.clone()),
+        ];
+
+        E::product_of_pairings(pairing_inputs.iter()).is_one()
+    }
+}
+
+impl<E: PairingEngine> Default for UnivariateVerifierParam<E> {
+    fn default() -> Self {
+        Self::new(
+            E::G1Affine::default(),
+            E::G2Affine::default(),
+            E::G2Affine::default(),
+        )
+    }
+}
+
+// Only the affine elements are serialized, the prepared elements are
+// recomputed on deserialization.
+impl<E: PairingEngine> CanonicalSerialize for UnivariateVerifierParam<E> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.g.serialize(&mut writer)?;
+        self.h.serialize(&mut writer)?;
+        self.beta_h.serialize(&mut writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.g.serialized_size() + self.h.serialized_size() + self.beta_h.serialized_size()
+    }
+
+    fn serialize_uncompressed<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.g.serialize_uncompressed(&mut writer)?;
+        self.h.serialize_uncompressed(&mut writer)?;
+        self.beta_h.serialize_uncompressed(&mut writer)
+    }
+
+    fn serialize_unchecked<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.g.serialize_unchecked(&mut writer)?;
+        self.h.serialize_unchecked(&mut writer)?;
+        self.beta_h.serialize_unchecked(&mut writer)
+    }
+
+    fn uncompressed_size(&self) -> usize {
+        self.g.uncompressed_size() + self.h.uncompressed_size() + self.beta_h.uncompressed_size()
+    }
+}
+
+impl<E: PairingEngine> CanonicalDeserialize for UnivariateVerifierParam<E> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let g = E::G1Affine::deserialize(&mut reader)?;
+        let h = E::G2Affine::deserialize(&mut reader)?;
+        let beta_h = E::G2Affine::deserialize(&mut reader)?;
+        Ok(Self::new(g, h, beta_h))
+    }
+
+    fn deserialize_uncompressed<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let g = E::G1Affine::deserialize_uncompressed(&mut reader)?;
+        let h = E::G2Affine::deserialize_uncompressed(&mut reader)?;
+        let beta_h = E::G2Affine::deserialize_uncompressed(&mut reader)?;
+        Ok(Self::new(g, h, beta_h))
+    }
+
+    fn deserialize_unchecked<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let g = E::G1Affine::deserialize_unchecked(&mut reader)?;
+        let h = E::G2Affine::deserialize_unchecked(&mut reader)?;
+        let beta_h = E::G2Affine::deserialize_unchecked(&mut reader)?;
+        Ok(Self::new(g, h, beta_h))
+    }
 }
 
 impl<E: PairingEngine> StructuredReferenceString<E> for UnivariateUniversalParams<E> {
@@ -85,11 +197,7 @@ impl<E: PairingEngine> StructuredReferenceString<E> for UnivariateUniversalParam
 
     /// Extract the verifier parameters from the public parameters.
     fn extract_verifier_param(&self, _supported_size: usize) -> Self::VerifierParam {
-        Self::VerifierParam {
-            g: self.powers_of_g[0],
-            h: self.h,
-            beta_h: self.beta_h,
-        }
+        Self::VerifierParam::new(self.powers_of_g[0], self.h, self.beta_h)
     }
 
     /// Trim the universal parameters to specialize the public parameters
@@ -103,11 +211,7 @@ impl<E: PairingEngine> StructuredReferenceString<E> for UnivariateUniversalParam
         let powers_of_g = self.powers_of_g[..=supported_size].to_vec();
 
         let pk = Self::ProverParam { powers_of_g };
-        let vk = Self::VerifierParam {
-            g: self.powers_of_g[0],
-            h: self.h,
-            beta_h: self.beta_h,
-        };
+        let vk = Self::VerifierParam::new(self.powers_of_g[0], self.h, self.beta_h);
         Ok((pk, vk))
     }
 
@@ -1,10 +1,15 @@
 //! A vector representation of bases and corresponding scalars
 use crate::pcs::CommitmentGroup;
-use ark_ec::msm::VariableBaseMSM;
+use ark_ec::{msm::VariableBaseMSM, ProjectiveCurve};
 use ark_ff::vec::Vec;
 use ark_ff::PrimeField;
 use ark_std::Zero;
-use hashbrown::HashMap;
+use hashbrown::{HashMap, HashSet};
+
+/// Grouping bases by scalar is only worth its extra additions and
+/// normalization when at most `1 / GROUPING_RATIO` of the scalars are
+/// distinct.
+const GROUPING_RATIO: usize = 2;
 
 /// The vector representation of bases and corresponding scalars.
 #[derive(Debug, Clone)]
@@ -34,7 +39,60 @@ impl<E: CommitmentGroup> ScalarsAndBases<E> {
         }
     }
     /// Compute the multi-scalar multiplication.
+    ///
+    /// Folding and batched verification produce many identical scalars (e.g.
+    /// the same challenge power applied to several commitments). When few
+    /// scalars are distinct, the bases sharing a scalar are summed first so
+    /// that the MSM only runs over the distinct scalars.
     pub fn multi_scalar_mul(&self) -> E::G1Projective {
+        if self.grouping_pays_off() {
+            self.grouped_multi_scalar_mul()
+        } else {
+            self.ungrouped_multi_scalar_mul()
+        }
+    }
+
+    /// Whether at most `1 / GROUPING_RATIO` of the nonzero scalars are
+    /// distinct. Only the distinct scalars are counted, stopping as soon as
+    /// there are too many of them, so that the common case of mostly
+    /// distinct scalars does not pay for summing the bases.
+    fn grouping_pays_off(&self) -> bool {
+        let max_distinct = self.base_scalar_map.len() / GROUPING_RATIO;
+        let mut distinct = HashSet::new();
+        for scalar in self.base_scalar_map.values() {
+            if !scalar.is_zero() && distinct.insert(*scalar) && distinct.len() > max_distinct {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Compute the multi-scalar multiplication over the distinct scalars, with
+    /// the bases sharing a scalar summed first.
+    fn grouped_multi_scalar_mul(&self) -> E::G1Projective {
+        let mut grouped: HashMap<E::Fr, E::G1Projective> = HashMap::new();
+        for (base, scalar) in &self.base_scalar_map {
+            if scalar.is_zero() {
+                continue;
+            }
+            grouped
+                .entry(*scalar)
+                .or_insert_with(E::G1Projective::zero)
+                .add_assign_mixed(base);
+        }
+
+        let (sums, scalars): (Vec<_>, Vec<_>) = grouped
+            .into_iter()
+            .map(|(scalar, sum)| (sum, scalar.into_repr()))
+            .unzip();
+        let bases = E::G1Projective::batch_normalization_into_affine(&sums);
+        VariableBaseMSM::multi_scalar_mul(&bases, &scalars)
+    }
+
+    /// Compute the multi-scalar multiplication directly over all the stored
+    /// bases.
+    fn ungrouped_multi_scalar_mul(&self) -> E::G1Projective {
         let (bases, scalars): (Vec<_>, Vec<_>) = self
             .base_scalar_map
             .iter()
@@ -43,3 +101,85 @@ impl<E: CommitmentGroup> ScalarsAndBases<E> {
         VariableBaseMSM::multi_scalar_mul(&bases, &scalars)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::Bls12_381;
+    use ark_ec::{AffineCurve, PairingEngine};
+    use ark_std::{test_rng, UniformRand};
+
+    fn grouped_msm_test_template<E: PairingEngine>() {
+        let mut rng = test_rng();
+        let challenges: Vec<E::Fr> = (0..3).map(|_| E::Fr::rand(&mut rng)).collect();
+        let bases: Vec<E::G1Affine> = (0..32)
+            .map(|_| E::G1Projective::rand(&mut rng).into_affine())
+            .collect();
+
+        // many repeated scalars: the grouped path is taken
+        let mut repeated = ScalarsAndBases::<E>::new();
+        let mut expected = E::G1Projective::zero();
+        for (i, base) in bases.iter().enumerate() {
+            let scalar = challenges[i % challenges.len()];
+            repeated.push(scalar, *base);
+            expected += base.mul(scalar);
+        }
+        assert!(repeated.grouping_pays_off());
+        assert_eq!(repeated.multi_scalar_mul(), expected);
+        assert_eq!(repeated.ungrouped_multi_scalar_mul(), expected);
+
+        // distinct scalars: the plain MSM is used
+        let mut distinct = ScalarsAndBases::<E>::new();
+        let mut expected = E::G1Projective::zero();
+        for base in bases.iter() {
+            let scalar = E::Fr::rand(&mut rng);
+            distinct.push(scalar, *base);
+            expected += base.mul(scalar);
+        }
+        assert!(!distinct.grouping_pays_off());
+        assert_eq!(distinct.multi_scalar_mul(), expected);
+
+        // zero scalars are skipped
+        let mut zeros = ScalarsAndBases::<E>::new();
+        zeros.push(E::Fr::zero(), bases[0]);
+        assert_eq!(zeros.multi_scalar_mul(), E::G1Projective::zero());
+    }
+
+    fn grouped_matches_ungrouped_test_template<E: PairingEngine>() {
+        let mut rng = test_rng();
+        for distinct_scalars in [1, 2, 5, 16, 40] {
+            let scalars: Vec<E::Fr> = (0..distinct_scalars)
+                .map(|i| {
+                    // a few zero scalars among the others
+                    if i % 7 == 3 {
+                        E::Fr::zero()
+                    } else {
+                        E::Fr::rand(&mut rng)
+                    }
+                })
+                .collect();
+            let mut scalars_and_bases = ScalarsAndBases::<E>::new();
+            for i in 0..40 {
+                scalars_and_bases.push(
+                    scalars[i % distinct_scalars],
+                    E::G1Projective::rand(&mut rng).into_affine(),
+                );
+            }
+
+            assert_eq!(
+                scalars_and_bases.grouped_multi_scalar_mul(),
+                scalars_and_bases.ungrouped_multi_scalar_mul()
+            );
+        }
+    }
+
+    #[test]
+    fn test_grouped_msm() {
+        grouped_msm_test_template::<Bls12_381>();
+    }
+
+    #[test]
+    fn test_grouped_msm_matches_ungrouped() {
+        grouped_matches_ungrouped_test_template::<Bls12_381>();
+    }
+}
@@ -0,0 +1,816 @@
+use ark_crypto_primitives::sponge::{poseidon::PoseidonConfig, Absorb};
+use ark_ec::short_weierstrass::SWCurveConfig;
+use ark_ff::{BigInteger, PrimeField, Zero};
+
+use crate::{
+    folding_scheme::FoldingCommitmentConfig,
+    hints,
+    nonnative::{assert_zero, decompose, range_check},
+    poseidon::{linear_combination, multiplication_gate},
+    poseidon_hash, poseidon_hash_gadget,
+    relaxed_plonk::NUMBER_OF_WIRES,
+    trace::NUMBER_OF_SELECTORS,
+    EmbeddedPoint, HomomorphicCommitmentScheme, PLONKCircuit, PLONKStepCircuit,
+    RelaxedPLONKInstance, RowTag, SangriaConfig, SangriaError, TraceTable, LEFT_SELECTOR_INDEX,
+    MULTIPLICATION_SELECTOR_INDEX, OUTPUT_SELECTOR_INDEX, RIGHT_SELECTOR_INDEX,
+};
+
+/// A wire of a trace, given as (column, row).
+type Wire = (usize, usize);
+
+/// The commitments to the cross terms of one fold.
+type CrossTermCommitments<F, Comm> =
+    Vec<<<Comm as FoldingCommitmentConfig<F>>::CommitmentSlack as HomomorphicCommitmentScheme<F>>::Commitment>;
+
+/// A homomorphic commitment scheme whose commitments can be allocated and folded in a circuit over
+/// the field `F`, so that the augmented circuit can verify the folds of instances committed with it.
+/// A commitment is held by the wires of its sponge field elements, in the order the folding
+/// transcript absorbs them.
+///
+/// Commitments to vectors over `F` that are themselves elements of `F` fold with [`fold_scalars`].
+/// Curve commitments fold with [`fold_commitment`] in a circuit over the base field of the curve,
+/// which is not the field of the instances they commit to: they need the circuit of a second curve
+/// of a cycle, which this crate does not provide.
+pub trait CommitmentGadget<F: PrimeField>: HomomorphicCommitmentScheme<F> {
+    /// Allocates `commitment` in `trace` and returns the wires holding its sponge field elements,
+    /// constrained to encode a commitment.
+    fn allocate(
+        trace: &mut TraceTable<F>,
+        commitment: &Self::Commitment,
+    ) -> Result<Vec<Wire>, SangriaError>;
+
+    /// Folds the commitment held by the wires `fresh` into the one held by the wires `running` with
+    /// the challenge `r` held by the wire `challenge`, and returns the wires holding `running + r *
+    /// fresh`.
+    fn fold(
+        trace: &mut TraceTable<F>,
+        running: &[Wire],
+        fresh: &[Wire],
+        challenge: Wire,
+    ) -> Result<Vec<Wire>, SangriaError>;
+}
+
+/// The inputs of a step of a recursive proof besides the witness of the step circuit: the sponge
+/// field elements of the verifier key of the folding scheme, the number of steps proved so far, the
+/// encoded origin and current states, and the fold the step verifies, of the fresh instance of the
+/// previous step into the running instance of the proof. In the base case (no step proved yet) the
+/// instances and the cross term commitments are placeholders, which the circuit does not fold.
+pub struct RecursiveStep<F: PrimeField, Comm: FoldingCommitmentConfig<F>> {
+    /// The field elements the folding transcript absorbs for the verifier key of the folding scheme.
+    pub verifier_key: Vec<F>,
+    /// The number of steps proved so far.
+    pub number_of_steps: u64,
+    /// The encoded origin state.
+    pub origin_state: Vec<F>,
+    /// The encoded current state.
+    pub current_state: Vec<F>,
+    /// The running instance of the proof of the previous steps.
+    pub running_instance: RelaxedPLONKInstance<F, Comm>,
+    /// The fresh instance of the previous step.
+    pub step_instance: RelaxedPLONKInstance<F, Comm>,
+    /// The commitments to the cross terms folding the fresh instance into the running instance.
+    pub cross_term_commitments: CrossTermCommitments<F, Comm>,
+}
+
+impl<F: PrimeField, Comm: FoldingCommitmentConfig<F>> RecursiveStep<F, Comm> {
+    /// Returns the inputs of the first step from `origin_state`, under the verifier key of the
+    /// folding scheme whose sponge field elements are `verifier_key`. The instances are the default
+    /// running instance and the cross term commitments are zero.
+    pub fn base(config: &SangriaConfig, verifier_key: Vec<F>, origin_state: Vec<F>) -> Self {
+        Self {
+            verifier_key,
+            number_of_steps: 0,
+            current_state: origin_state.clone(),
+            origin_state,
+            running_instance: default_instance(),
+            step_instance: default_instance(),
+            cross_term_commitments: vec![Zero::zero(); config.gate_degree - 1],
+        }
+    }
+}
+
+/// Returns the default running instance of a recursive proof, the running instance of its first
+/// step: every scalar and every commitment is zero, so that the zero witness satisfies it.
+pub(crate) fn default_instance<F, Comm>() -> RelaxedPLONKInstance<F, Comm>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    RelaxedPLONKInstance::new(
+        vec![vec![F::zero(); RECURSIVE_PUBLIC_INPUTS + 1]; NUMBER_OF_WIRES],
+        vec![Zero::zero(); NUMBER_OF_WIRES],
+    ) * F::zero()
+}
+
+/// The number of public inputs of the recursive augmented circuit: the IO hash of the step.
+pub(crate) const RECURSIVE_PUBLIC_INPUTS: usize = 1;
+
+/// Returns the digest of the running instance of a recursive proof hashed into its IO hash, bound
+/// to the verifier key of the folding scheme: the [`poseidon_hash`] of the sponge field elements of
+/// both, as recomputed by the augmented circuit.
+pub(crate) fn accumulator_digest<F, Comm>(
+    poseidon_constants: &PoseidonConfig<F>,
+    verifier_key: &[F],
+    running_instance: &RelaxedPLONKInstance<F, Comm>,
+) -> F
+where
+    F: PrimeField + Absorb,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    let mut inputs = verifier_key.to_vec();
+    inputs.extend(running_instance.to_sponge_field_elements_as_vec::<F>());
+
+    poseidon_hash(poseidon_constants, &inputs)
+}
+
+/// The augmented circuit of a step circuit, which is the circuit actually folded by Sangria. Its
+/// trace is synthesized in one of two ways.
+///
+/// In a recursive proof ([`Self::synthesize`]), the circuit verifies the fold of the previous step
+/// into the running instance: its single public input is the [IO hash](crate::Sangria::io_hash)
+/// binding the number of steps, the origin state, the next state and the digest of the folded
+/// running instance, which the next step checks against the fresh instance it folds. The circuit
+/// hashes the running instance with [`poseidon_hash_gadget`], derives the folding challenge from
+/// the Poseidon transcript of the folding scheme and folds the instances with [`fold_scalars`] and
+/// the [`CommitmentGadget`] of the commitment schemes.
+///
+/// In a batched proof ([`Self::synthesize_batched`]), the public inputs are the encodings of the
+/// current and next states, copied into the wires of the step circuit holding them, followed by
+/// the gates of the step circuit and an unused output row. The circuit verifies no fold, so the
+/// verifier of a batched proof re-folds the step instances itself.
+#[derive(Clone, Debug)]
+pub struct AugmentedCircuit<SC> {
+    step_circuit: SC,
+}
+
+impl<SC> AugmentedCircuit<SC> {
+    /// Wraps a step circuit.
+    pub fn new(step_circuit: SC) -> Self {
+        Self { step_circuit }
+    }
+
+    /// Returns the wrapped step circuit.
+    pub fn step_circuit(&self) -> &SC {
+        &self.step_circuit
+    }
+
+    /// Synthesizes the trace of one step of a recursive proof, from `step.current_state` to
+    /// `next_state` (both encoded). Besides the gates of the step circuit, the trace:
+    ///
+    /// - tells the base case apart by `step.number_of_steps` being zero, in which case the current
+    ///   state must be the origin state;
+    /// - otherwise checks that the public input of the fresh instance of the previous step is the
+    ///   IO hash of the current state and of the running instance;
+    /// - derives the folding challenge from the transcript of the NIFS verifier, absorbing the
+    ///   folding verifier key, both instances and the cross term commitments, and folds the fresh
+    ///   instance into the running instance;
+    /// - exposes the IO hash of the next state and of the folded running instance, or of the
+    ///   default running instance in the base case, as its public input.
+    ///
+    /// Returns an error if a state does not have the length reported by the step circuit, if the
+    /// step circuit does not expose one wire per state element or if the instances or the cross
+    /// term commitments do not have the layout of the recursive augmented circuit.
+    pub fn synthesize<F, Comm>(
+        &self,
+        config: &SangriaConfig,
+        poseidon_constants: &PoseidonConfig<F>,
+        step: &RecursiveStep<F, Comm>,
+        next_state: &[F],
+        witness: &SC::Witness,
+    ) -> Result<TraceTable<F>, SangriaError>
+    where
+        F: PrimeField + Absorb,
+        Comm: FoldingCommitmentConfig<F>,
+        Comm::CommitmentWitness: CommitmentGadget<F>,
+        Comm::CommitmentSlack: CommitmentGadget<F>,
+        SC: PLONKStepCircuit<F>,
+    {
+        let state_len = self.step_circuit.state_len();
+        if [&step.origin_state[..], &step.current_state, next_state]
+            .iter()
+            .any(|state| state.len() != state_len)
+        {
+            return Err(SangriaError::PublicInputMismatch);
+        }
+        for instance in [&step.running_instance, &step.step_instance] {
+            instance.check_public_input_rows(RECURSIVE_PUBLIC_INPUTS)?;
+            instance.check_extra_commitments(0)?;
+            if instance.witness_commitments().len() != NUMBER_OF_WIRES {
+                return Err(SangriaError::CommitmentCount {
+                    expected: NUMBER_OF_WIRES,
+                    found: instance.witness_commitments().len(),
+                });
+            }
+        }
+        if step.cross_term_commitments.len() != config.gate_degree - 1 {
+            return Err(SangriaError::CrossTermCount {
+                expected: config.gate_degree - 1,
+                found: step.cross_term_commitments.len(),
+            });
+        }
+
+        let mut trace = TraceTable::new();
+        trace.push_row(RowTag::PublicInput, [F::zero(); NUMBER_OF_WIRES]);
+
+        let verifier_key = trace.hint(&[], |_| Ok(step.verifier_key.clone()))?;
+        let number_of_steps = trace.hint(&[], |_| Ok(vec![F::from(step.number_of_steps)]))?[0];
+        let origin = trace.hint(&[], |_| Ok(step.origin_state.clone()))?;
+        let current = trace.hint(&[], |_| Ok(step.current_state.clone()))?;
+        let running = InstanceWires::allocate(&mut trace, &step.running_instance)?;
+        let fresh = InstanceWires::allocate_fresh(&mut trace, &step.step_instance)?;
+        let cross_terms = step
+            .cross_term_commitments
+            .iter()
+            .map(|commitment| Comm::CommitmentSlack::allocate(&mut trace, commitment))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let wires =
+            self.step_circuit
+                .synthesize(&mut trace, &step.current_state, next_state, witness)?;
+        if wires.current_state.len() != state_len || wires.next_state.len() != state_len {
+            return Err(SangriaError::InvalidTrace(
+                "the step circuit must expose one wire per state element",
+            ));
+        }
+        for (&wire, &state_wire) in wires.current_state.iter().zip(&current) {
+            trace.copy(state_wire, wire);
+        }
+
+        // the base case is the zero number of steps, tested with its inverse (zero for zero)
+        let (zero, one) = (F::zero(), F::one());
+        let inverse = trace.hint(&[number_of_steps], hints::inverse())?[0];
+        let product = multiplication_gate(&mut trace, number_of_steps, inverse)?;
+        let is_base = linear_combination(&mut trace, &[(-one, product)], one)?;
+        let vanishing = multiplication_gate(&mut trace, number_of_steps, is_base)?;
+        assert_zero(&mut trace, vanishing);
+        let is_step = linear_combination(&mut trace, &[(-one, is_base)], one)?;
+        for (&origin, &current) in origin.iter().zip(&current) {
+            let difference =
+                linear_combination(&mut trace, &[(one, current), (-one, origin)], zero)?;
+            let product = multiplication_gate(&mut trace, is_base, difference)?;
+            assert_zero(&mut trace, product);
+        }
+
+        // past the base case, the fresh instance carries the IO hash of the current state
+        let running_elements = running.elements();
+        let digest = poseidon_hash_gadget(
+            &mut trace,
+            poseidon_constants,
+            &[&verifier_key[..], &running_elements].concat(),
+        )?;
+        let expected = io_hash_gadget(
+            &mut trace,
+            poseidon_constants,
+            &config.application_id,
+            number_of_steps,
+            &origin,
+            &current,
+            digest,
+        )?;
+        let difference = linear_combination(
+            &mut trace,
+            &[(one, fresh.matrix[0]), (-one, expected)],
+            zero,
+        )?;
+        let product = multiplication_gate(&mut trace, is_step, difference)?;
+        assert_zero(&mut trace, product);
+
+        // the transcript of the NIFS verifier
+        let transcript_hash = poseidon_hash_gadget(
+            &mut trace,
+            poseidon_constants,
+            &[
+                &verifier_key[..],
+                &running_elements,
+                &fresh.elements(),
+                &cross_terms.concat(),
+            ]
+            .concat(),
+        )?;
+        let challenge = challenge_gadget(&mut trace, transcript_hash)?;
+        let folded = running.fold::<F, Comm>(&mut trace, &fresh, &cross_terms, challenge)?;
+
+        let default_elements = default_instance::<F, Comm>().to_sponge_field_elements_as_vec::<F>();
+        let default_elements = default_elements
+            .iter()
+            .map(|&value| linear_combination(&mut trace, &[], value))
+            .collect::<Result<Vec<_>, _>>()?;
+        let next_running = select(&mut trace, is_base, &default_elements, &folded.elements())?;
+
+        let next_digest = poseidon_hash_gadget(
+            &mut trace,
+            poseidon_constants,
+            &[&verifier_key[..], &next_running].concat(),
+        )?;
+        let next_number_of_steps = linear_combination(&mut trace, &[(one, number_of_steps)], one)?;
+        let io_hash = io_hash_gadget(
+            &mut trace,
+            poseidon_constants,
+            &config.application_id,
+            next_number_of_steps,
+            &origin,
+            &wires.next_state,
+            next_digest,
+        )?;
+        trace.copy(io_hash, (0, 0));
+        trace.set_public_input(0, trace.wire(io_hash.0, io_hash.1)?)?;
+        trace.push_row(RowTag::Output, [F::zero(); NUMBER_OF_WIRES]);
+
+        Ok(trace)
+    }
+
+    /// Builds the recursive augmented PLONK circuit from the base step on zero states with the
+    /// default witness, whose values are ignored.
+    pub fn circuit<F, Comm>(
+        &self,
+        config: &SangriaConfig,
+        poseidon_constants: &PoseidonConfig<F>,
+    ) -> Result<PLONKCircuit<F>, SangriaError>
+    where
+        F: PrimeField + Absorb,
+        Comm: FoldingCommitmentConfig<F>,
+        Comm::CommitmentWitness: CommitmentGadget<F>,
+        Comm::CommitmentSlack: CommitmentGadget<F>,
+        SC: PLONKStepCircuit<F>,
+        SC::Witness: Default,
+    {
+        let placeholder = vec![F::zero(); self.step_circuit.state_len()];
+        let mut verifier_key =
+            <Comm::CommitmentSlack as HomomorphicCommitmentScheme<F>>::Commitment::zero()
+                .to_sponge_field_elements_as_vec::<F>();
+        verifier_key.push(F::zero());
+        let step = RecursiveStep::<F, Comm>::base(config, verifier_key, placeholder.clone());
+
+        self.synthesize(
+            config,
+            poseidon_constants,
+            &step,
+            &placeholder,
+            &SC::Witness::default(),
+        )?
+        .circuit(self.step_circuit.fixed_tables())
+    }
+
+    /// Synthesizes the trace of one step of a batched proof, from `current_state` to `next_state`
+    /// (both encoded), or returns an error if a state does not have the length reported by the step
+    /// circuit or if the step circuit does not expose one wire per state element.
+    pub fn synthesize_batched<F>(
+        &self,
+        current_state: &[F],
+        next_state: &[F],
+        witness: &SC::Witness,
+    ) -> Result<TraceTable<F>, SangriaError>
+    where
+        F: PrimeField,
+        SC: PLONKStepCircuit<F>,
+    {
+        let state_len = self.step_circuit.state_len();
+        if current_state.len() != state_len || next_state.len() != state_len {
+            return Err(SangriaError::PublicInputMismatch);
+        }
+
+        let mut trace = TraceTable::new();
+        for &value in current_state.iter().chain(next_state.iter()) {
+            trace.push_row(RowTag::PublicInput, [value, F::zero(), F::zero()]);
+        }
+
+        let wires = self
+            .step_circuit
+            .synthesize(&mut trace, current_state, next_state, witness)?;
+        if wires.current_state.len() != current_state.len()
+            || wires.next_state.len() != next_state.len()
+        {
+            return Err(SangriaError::InvalidTrace(
+                "the step circuit must expose one wire per state element",
+            ));
+        }
+
+        for (row, &wire) in wires
+            .current_state
+            .iter()
+            .chain(&wires.next_state)
+            .enumerate()
+        {
+            trace.copy((0, row), wire);
+        }
+        trace.push_row(RowTag::Output, [F::zero(); 3]);
+
+        Ok(trace)
+    }
+
+    /// Builds the batched augmented PLONK circuit from a step on zero states with the default
+    /// witness, whose values are ignored.
+    pub fn batched_circuit<F>(&self) -> Result<PLONKCircuit<F>, SangriaError>
+    where
+        F: PrimeField,
+        SC: PLONKStepCircuit<F>,
+        SC::Witness: Default,
+    {
+        let placeholder = vec![F::zero(); self.step_circuit.state_len()];
+
+        self.synthesize_batched(&placeholder, &placeholder, &SC::Witness::default())?
+            .circuit(self.step_circuit.fixed_tables())
+    }
+}
+
+/// The wires of a relaxed PLONK instance folded by the recursive augmented circuit, without extra
+/// commitments.
+struct InstanceWires {
+    /// The instance columns, one after the other.
+    matrix: Vec<Wire>,
+    scaling_factor: Wire,
+    slack_commitment: Vec<Wire>,
+    witness_commitments: Vec<Vec<Wire>>,
+}
+
+impl InstanceWires {
+    /// Allocates a running instance.
+    fn allocate<F, Comm>(
+        trace: &mut TraceTable<F>,
+        instance: &RelaxedPLONKInstance<F, Comm>,
+    ) -> Result<Self, SangriaError>
+    where
+        F: PrimeField,
+        Comm: FoldingCommitmentConfig<F>,
+        Comm::CommitmentWitness: CommitmentGadget<F>,
+        Comm::CommitmentSlack: CommitmentGadget<F>,
+    {
+        let mut fresh = Self::allocate_fresh(trace, instance)?;
+        fresh.scaling_factor = trace.hint(&[], |_| Ok(vec![instance.scaling_factor()]))?[0];
+        fresh.slack_commitment =
+            Comm::CommitmentSlack::allocate(trace, &instance.slack_commitment())?;
+
+        Ok(fresh)
+    }
+
+    /// Allocates a fresh instance, whose scaling factor is the constant one and whose slack
+    /// commitment is the constant zero.
+    fn allocate_fresh<F, Comm>(
+        trace: &mut TraceTable<F>,
+        instance: &RelaxedPLONKInstance<F, Comm>,
+    ) -> Result<Self, SangriaError>
+    where
+        F: PrimeField,
+        Comm: FoldingCommitmentConfig<F>,
+        Comm::CommitmentWitness: CommitmentGadget<F>,
+        Comm::CommitmentSlack: CommitmentGadget<F>,
+    {
+        let matrix = (0..NUMBER_OF_WIRES)
+            .map(|column| instance.instance_column(column))
+            .collect::<Result<Vec<_>, _>>()?
+            .concat();
+        let matrix = trace.hint(&[], |_| Ok(matrix))?;
+        let witness_commitments = instance
+            .witness_commitments()
+            .iter()
+            .map(|commitment| Comm::CommitmentWitness::allocate(trace, commitment))
+            .collect::<Result<Vec<_>, _>>()?;
+        let scaling_factor = linear_combination(trace, &[], F::one())?;
+        let slack_commitment =
+            <Comm::CommitmentSlack as HomomorphicCommitmentScheme<F>>::Commitment::zero()
+                .to_sponge_field_elements_as_vec::<F>()
+                .into_iter()
+                .map(|value| linear_combination(trace, &[], value))
+                .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            matrix,
+            scaling_factor,
+            slack_commitment,
+            witness_commitments,
+        })
+    }
+
+    /// Returns the wires of the sponge field elements of the instance, in the order the folding
+    /// transcript absorbs them.
+    fn elements(&self) -> Vec<Wire> {
+        let mut elements = self.matrix.clone();
+        elements.push(self.scaling_factor);
+        elements.extend(&self.slack_commitment);
+        elements.extend(self.witness_commitments.concat());
+
+        elements
+    }
+
+    /// Folds `fresh` into this instance with the cross term commitments and the challenge, like
+    /// [`RelaxedPLONKInstance::fold`]: the slack commitment is folded by Horner's rule as `E + r (T_1
+    /// + r (T_2 + ... + r E_fresh))`.
+    fn fold<F, Comm>(
+        &self,
+        trace: &mut TraceTable<F>,
+        fresh: &Self,
+        cross_terms: &[Vec<Wire>],
+        challenge: Wire,
+    ) -> Result<Self, SangriaError>
+    where
+        F: PrimeField,
+        Comm: FoldingCommitmentConfig<F>,
+        Comm::CommitmentWitness: CommitmentGadget<F>,
+        Comm::CommitmentSlack: CommitmentGadget<F>,
+    {
+        let matrix = fold_scalars(trace, &self.matrix, &fresh.matrix, challenge)?;
+        let scaling_factor = fold_scalars(
+            trace,
+            &[self.scaling_factor],
+            &[fresh.scaling_factor],
+            challenge,
+        )?[0];
+        let witness_commitments = self
+            .witness_commitments
+            .iter()
+            .zip(&fresh.witness_commitments)
+            .map(|(running, fresh)| Comm::CommitmentWitness::fold(trace, running, fresh, challenge))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut slack_commitment = fresh.slack_commitment.clone();
+        for cross_term in cross_terms.iter().rev() {
+            slack_commitment =
+                Comm::CommitmentSlack::fold(trace, cross_term, &slack_commitment, challenge)?;
+        }
+        let slack_commitment = Comm::CommitmentSlack::fold(
+            trace,
+            &self.slack_commitment,
+            &slack_commitment,
+            challenge,
+        )?;
+
+        Ok(Self {
+            matrix,
+            scaling_factor,
+            slack_commitment,
+            witness_commitments,
+        })
+    }
+}
+
+/// Returns the wires holding `if_one` where the bit held by `bit` is one and `if_zero` where it is
+/// zero, computed as `if_zero + b (if_one - if_zero)` element by element.
+fn select<F: PrimeField>(
+    trace: &mut TraceTable<F>,
+    bit: Wire,
+    if_one: &[Wire],
+    if_zero: &[Wire],
+) -> Result<Vec<Wire>, SangriaError> {
+    let one = F::one();
+    if_one
+        .iter()
+        .zip(if_zero)
+        .map(|(&if_one, &if_zero)| {
+            let difference =
+                linear_combination(trace, &[(one, if_one), (-one, if_zero)], F::zero())?;
+            let product = multiplication_gate(trace, bit, difference)?;
+            linear_combination(trace, &[(one, if_zero), (one, product)], F::zero())
+        })
+        .collect()
+}
+
+/// Derives the folding challenge from the element `h` squeezed from the transcript, held by the
+/// wire `hash`, as [`FoldChallenge`](crate::FoldChallenge) does natively with the Poseidon sponge:
+/// the challenge is made of the `k` least significant bits of `h`, for `k` one less than the size
+/// of the modulus `p`. The decomposition `h = 2^k high + low` is constrained to be that of the
+/// canonical representative of `h`: when `high` is one, `low` is below `delta = p - 2^k`, which
+/// holds if and only if `low + 2^m - delta` has `m` bits for `delta` of `m` bits.
+fn challenge_gadget<F: PrimeField>(
+    trace: &mut TraceTable<F>,
+    hash: Wire,
+) -> Result<Wire, SangriaError> {
+    let bits = F::MODULUS_BIT_SIZE as usize - 1;
+    let power = F::from(2u64).pow([bits as u64]);
+    let value = trace.wire(hash.0, hash.1)?;
+    let high_value = F::from(value.into_bigint().get_bit(bits));
+    let high = decompose(trace, high_value, 1).1;
+    let low = decompose(trace, value - power * high_value, bits).1;
+    let one = F::one();
+    let difference = linear_combination(
+        trace,
+        &[(one, hash), (-power, high), (-one, low)],
+        F::zero(),
+    )?;
+    assert_zero(trace, difference);
+
+    // p - 2^k, as a field element
+    let delta = -power;
+    let delta_bits = delta.into_bigint().num_bits() as usize;
+    let shifted = linear_combination(
+        trace,
+        &[(one, low)],
+        F::from(2u64).pow([delta_bits as u64]) - delta,
+    )?;
+    let bounded = multiplication_gate(trace, high, shifted)?;
+    range_check(trace, bounded, delta_bits)?;
+
+    Ok(low)
+}
+
+/// Computes the IO hash of [`Sangria::io_hash`](crate::Sangria::io_hash) in-circuit, from the
+/// application identifier (laid out as constants) and the wires of the number of steps, of the
+/// encoded origin and current states and of the accumulator digest.
+fn io_hash_gadget<F: PrimeField + Absorb>(
+    trace: &mut TraceTable<F>,
+    poseidon_constants: &PoseidonConfig<F>,
+    application_id: &[u8],
+    number_of_steps: Wire,
+    origin_state: &[Wire],
+    current_state: &[Wire],
+    accumulator_digest: Wire,
+) -> Result<Wire, SangriaError> {
+    let mut inputs = [F::from(application_id.len() as u64)]
+        .into_iter()
+        .chain(application_id.to_sponge_field_elements_as_vec::<F>())
+        .map(|value| linear_combination(trace, &[], value))
+        .collect::<Result<Vec<_>, _>>()?;
+    inputs.push(number_of_steps);
+    inputs.extend(origin_state);
+    inputs.extend(current_state);
+    inputs.push(accumulator_digest);
+
+    poseidon_hash_gadget(trace, poseidon_constants, &inputs)
+}
+
+/// Folds the scalars of a fresh instance into those of a running instance in-circuit, as the NIFS
+/// verifier does with the scaling factor and the public inputs: every output is `running + r *
+/// fresh`. The inputs and the challenge `r` are wires of `trace`, given as (column, row), and the
+/// wires holding the folded scalars are returned. The fresh scalars may also be those of a running
+/// instance, as when [merging](crate::Sangria::merge) two proofs. Each scalar costs a
+/// multiplication gate and an addition gate.
+pub fn fold_scalars<F: PrimeField>(
+    trace: &mut TraceTable<F>,
+    running: &[(usize, usize)],
+    fresh: &[(usize, usize)],
+    challenge: (usize, usize),
+) -> Result<Vec<(usize, usize)>, SangriaError> {
+    if running.len() != fresh.len() {
+        return Err(SangriaError::InvalidTrace(
+            "the running and fresh instances must have as many scalars",
+        ));
+    }
+
+    let r = trace.wire(challenge.0, challenge.1)?;
+    let mut multiplication = [F::zero(); NUMBER_OF_SELECTORS];
+    multiplication[MULTIPLICATION_SELECTOR_INDEX] = F::one();
+    multiplication[OUTPUT_SELECTOR_INDEX] = -F::one();
+    let mut addition = [F::zero(); NUMBER_OF_SELECTORS];
+    addition[LEFT_SELECTOR_INDEX] = F::one();
+    addition[RIGHT_SELECTOR_INDEX] = F::one();
+    addition[OUTPUT_SELECTOR_INDEX] = -F::one();
+
+    running
+        .iter()
+        .zip(fresh)
+        .map(|(&running, &fresh)| {
+            let (running_value, fresh_value) = (
+                trace.wire(running.0, running.1)?,
+                trace.wire(fresh.0, fresh.1)?,
+            );
+
+            let product_row = trace.number_of_rows();
+            trace.push_row(
+                RowTag::Gate(multiplication),
+                [r, fresh_value, r * fresh_value],
+            );
+            trace.copy(challenge, (0, product_row));
+            trace.copy(fresh, (1, product_row));
+
+            let sum_row = product_row + 1;
+            trace.push_row(
+                RowTag::Gate(addition),
+                [
+                    running_value,
+                    r * fresh_value,
+                    running_value + r * fresh_value,
+                ],
+            );
+            trace.copy(running, (0, sum_row));
+            trace.copy((2, product_row), (1, sum_row));
+
+            Ok((2, sum_row))
+        })
+        .collect()
+}
+
+/// Folds the commitment `fresh` into the commitment `running` in-circuit, as the NIFS verifier does
+/// with the commitments of the instances: the output is `running + r * fresh`, computed over the
+/// embedded curve `P` with the challenge `r` held in the wire `challenge`, given as (column, row),
+/// and constrained to have at most `challenge_bits` bits.
+pub fn fold_commitment<P: SWCurveConfig>(
+    trace: &mut TraceTable<P::BaseField>,
+    running: &EmbeddedPoint<P>,
+    fresh: &EmbeddedPoint<P>,
+    challenge: (usize, usize),
+    challenge_bits: usize,
+) -> Result<EmbeddedPoint<P>, SangriaError>
+where
+    P::BaseField: PrimeField,
+{
+    let scaled = fresh.scalar_mul(trace, challenge, challenge_bits)?;
+    running.add(trace, &scaled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fold_commitment, fold_scalars};
+    use crate::{
+        folding_scheme::{PLONKFoldingScheme, SetupInfo, SingleCommitment},
+        utils::{
+            inner_product::{InnerProduct, InnerProductConfig},
+            poseidon::poseidon_parameters_for_test,
+        },
+        CostModel, EmbeddedPoint, NonInteractiveFoldingScheme, RowTag, SangriaConfig, SangriaError,
+        TraceTable,
+    };
+    use ark_bn254::{g1::Config, Fq, Fr, G1Affine};
+    use ark_crypto_primitives::sponge::poseidon::PoseidonSponge;
+    use ark_ec::CurveGroup;
+    use ark_ff::{UniformRand, Zero};
+    use ark_std::test_rng;
+
+    type Scheme = PLONKFoldingScheme<Fr, InnerProductConfig, PoseidonSponge<Fr>>;
+
+    /// Lays out the challenge, then the running and fresh scalars `(u, x)` as public inputs, folds
+    /// them and copies the folded scalars into the output wires of the last two public inputs,
+    /// which are claimed to hold the result.
+    fn folding_trace(trace: &mut TraceTable<Fr>, folded: [Fr; 2]) -> Result<(), SangriaError> {
+        let (r, running, fresh) = (
+            Fr::from(3u64),
+            [Fr::from(5u64), Fr::from(7u64)],
+            [Fr::from(1u64), Fr::from(2u64)],
+        );
+        for value in [r].iter().chain(&running).chain(&fresh).chain(&folded) {
+            trace.push_row(RowTag::PublicInput, [*value, Fr::zero(), Fr::zero()]);
+        }
+
+        let wires = fold_scalars(trace, &[(0, 1), (0, 2)], &[(0, 3), (0, 4)], (0, 0))?;
+        trace.copy(wires[0], (0, 5));
+        trace.copy(wires[1], (0, 6));
+        trace.push_row(RowTag::Output, [Fr::zero(); 3]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn folded_scalars_satisfy_the_relation() {
+        let rng = &mut test_rng();
+        let info = SetupInfo {
+            number_of_public_inputs: 7,
+            number_of_gates: 4,
+            config: SangriaConfig::default(),
+            poseidon_constants: poseidon_parameters_for_test(),
+        };
+        let pp = Scheme::setup(&info, rng).unwrap();
+        let cost = CostModel::new()
+            .measure(|trace| folding_trace(trace, [Fr::zero(); 2]))
+            .unwrap();
+        assert_eq!((cost.gate_rows, cost.copy_constraints), (4, 10));
+
+        let mut check = |folded| {
+            let mut trace = TraceTable::new();
+            folding_trace(&mut trace, folded)?;
+            let instance_columns = trace.instance_columns();
+            let (circuit, witness) = trace.into_circuit_and_witness(Vec::new(), rng)?;
+            let instance = Scheme::commit_witness(&pp, instance_columns, &witness)?;
+            Scheme::check_relation(&pp, &circuit, &instance, &witness)
+        };
+
+        // 5 + 3 * 1 and 7 + 3 * 2
+        assert_eq!(check([Fr::from(8u64), Fr::from(13u64)]), Ok(()));
+        assert_eq!(
+            check([Fr::from(8u64), Fr::from(14u64)]),
+            Err(SangriaError::InvalidTrace(
+                "copied wires hold different values"
+            ))
+        );
+    }
+
+    #[test]
+    fn folded_commitments_satisfy_the_relation() {
+        type Scheme =
+            PLONKFoldingScheme<Fq, SingleCommitment<InnerProduct<Fq>>, PoseidonSponge<Fq>>;
+
+        let rng = &mut test_rng();
+        let (running, fresh, r) = (G1Affine::rand(rng), G1Affine::rand(rng), 1000u64);
+        let mut trace = TraceTable::new();
+        trace.push_row(RowTag::PublicInput, [Fq::from(r), Fq::zero(), Fq::zero()]);
+        let running_wires = EmbeddedPoint::allocate(&mut trace, running).unwrap();
+        let fresh_wires = EmbeddedPoint::<Config>::allocate(&mut trace, fresh).unwrap();
+        let folded = fold_commitment(&mut trace, &running_wires, &fresh_wires, (0, 0), 10).unwrap();
+        assert_eq!(
+            folded.value(&trace),
+            Ok((running + fresh * Fr::from(r)).into_affine())
+        );
+        trace.push_row(RowTag::Output, [Fq::zero(); 3]);
+
+        let info = SetupInfo {
+            number_of_public_inputs: 1,
+            number_of_gates: trace.number_of_rows() - 2,
+            config: SangriaConfig::default(),
+            poseidon_constants: poseidon_parameters_for_test(),
+        };
+        let pp = Scheme::setup(&info, rng).unwrap();
+        let instance_columns = trace.instance_columns();
+        let (circuit, witness) = trace.into_circuit_and_witness(Vec::new(), rng).unwrap();
+        let instance = Scheme::commit_witness(&pp, instance_columns, &witness).unwrap();
+        assert_eq!(
+            Scheme::check_relation(&pp, &circuit, &instance, &witness),
+            Ok(())
+        );
+    }
+}
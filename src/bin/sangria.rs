@@ -0,0 +1,869 @@
+//! The `sangria` command line tool, built with the `cli` feature. It proves the steps of a circuit
+//! over BN254 with batched Sangria proofs and KZG commitments, reading and writing the parameters,
+//! keys and proofs as files, so that a pipeline can be scripted without writing Rust:
+//!
+//! ```text
+//! sangria setup --circuit fibonacci --parameters parameters.bin
+//! sangria encode --circuit fibonacci --parameters parameters.bin \
+//!     --prover-key prover.key --verifier-key verifier.key
+//! sangria prove --prover-key prover.key --origin 0,1 --steps 10 --output proof.bin
+//! sangria prove --prover-key prover.key --input proof.bin --output proof.bin
+//! sangria verify --verifier-key verifier.key --proof proof.bin
+//! sangria compress --prover-key prover.key --proof proof.bin --output compressed.bin
+//! sangria verify --verifier-key verifier.key --proof compressed.bin --compressed
+//! ```
+//!
+//! The circuit is one of the built-in examples or a circuit artifact, a text file with one
+//! directive per line (see [`ImportedCircuit::parse`]).
+//!
+//! WARNING: the tool samples the KZG trapdoor itself, so its parameters and keys are only fit for
+//! experiments, and the batched proofs carry their witness (see [`BatchedIVCProof`]).
+
+use std::error::Error;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use ark_bn254::{Bn254, Fr};
+use ark_crypto_primitives::sponge::poseidon::PoseidonSponge;
+use ark_ff::{Field, One, Zero};
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, SerializationError, Valid, Validate, Write,
+};
+use ark_std::rand::{rngs::StdRng, SeedableRng};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use sangria_impl::{
+    decider::KZGConfig, spec_vectors, BatchedIVCProof, CompressedBatchedIVCProof,
+    NonInteractiveFoldingScheme, PLONKFoldingScheme, PLONKStepCircuit, RowTag, Sangria,
+    SangriaConfig, SangriaError, SetupInfo, StateWires, StepCircuit, TraceTable,
+    CONSTANT_SELECTOR_INDEX, DEFAULT_GATE_DEGREE, LEFT_SELECTOR_INDEX,
+    MULTIPLICATION_SELECTOR_INDEX, OUTPUT_SELECTOR_INDEX, POWER_SELECTOR_INDEX,
+    RIGHT_SELECTOR_INDEX,
+};
+
+type Comm = KZGConfig<Bn254>;
+type Scheme = Sangria<Comm>;
+type PublicParameters =
+    <PLONKFoldingScheme<Fr, Comm, PoseidonSponge<Fr>> as NonInteractiveFoldingScheme>::PublicParameters;
+type ProverKey = sangria_impl::ProverKey<Fr, Comm, Circuit>;
+type VerifierKey = sangria_impl::VerifierKey<Fr, Comm, Circuit>;
+
+/// The origin state, the current state and the proof of the steps between them.
+type ProofFile = (Vec<Fr>, Vec<Fr>, BatchedIVCProof<Fr, Comm>);
+
+/// The origin state, the current state and the compressed proof of the steps between them.
+type CompressedProofFile = (Vec<Fr>, Vec<Fr>, CompressedBatchedIVCProof<Fr, Comm>);
+
+#[derive(Parser)]
+#[command(
+    name = "sangria",
+    version,
+    about = "Proves the steps of a circuit with Sangria over BN254"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Samples the public parameters for the size of a circuit.
+    Setup {
+        #[command(flatten)]
+        circuit: CircuitArgs,
+        /// The file to write the public parameters to.
+        #[arg(long)]
+        parameters: PathBuf,
+        #[command(flatten)]
+        randomness: RandomnessArgs,
+    },
+    /// Encodes a circuit into a prover key and a verifier key.
+    Encode {
+        #[command(flatten)]
+        circuit: CircuitArgs,
+        /// The public parameters written by `setup`.
+        #[arg(long)]
+        parameters: PathBuf,
+        /// The file to write the prover key to.
+        #[arg(long)]
+        prover_key: PathBuf,
+        /// The file to write the verifier key to.
+        #[arg(long)]
+        verifier_key: PathBuf,
+        #[command(flatten)]
+        randomness: RandomnessArgs,
+    },
+    /// Proves steps from the origin state, or extends a proof with more steps.
+    Prove {
+        /// The prover key written by `encode`.
+        #[arg(long)]
+        prover_key: PathBuf,
+        /// A proof to extend. Without it, the steps start from the origin state.
+        #[arg(long, conflicts_with = "origin")]
+        input: Option<PathBuf>,
+        /// The comma-separated elements of the origin state, zero by default.
+        #[arg(long, value_parser = parse_elements)]
+        origin: Option<Elements>,
+        /// The comma-separated elements of the witness of every step.
+        #[arg(long, value_parser = parse_elements, default_value = "")]
+        witness: Elements,
+        /// The number of steps to prove.
+        #[arg(long, default_value_t = 1)]
+        steps: usize,
+        /// The file to write the proof to.
+        #[arg(long)]
+        output: PathBuf,
+        #[command(flatten)]
+        randomness: RandomnessArgs,
+    },
+    /// Verifies a proof, or a compressed proof with `--compressed`.
+    Verify {
+        /// The verifier key written by `encode`.
+        #[arg(long)]
+        verifier_key: PathBuf,
+        /// The proof written by `prove` or `compress`.
+        #[arg(long)]
+        proof: PathBuf,
+        /// Reads the proof as a compressed proof.
+        #[arg(long)]
+        compressed: bool,
+    },
+    /// Replaces the witness of a proof with a HyperPlonk proof of it.
+    Compress {
+        /// The prover key written by `encode`.
+        #[arg(long)]
+        prover_key: PathBuf,
+        /// The proof written by `prove`.
+        #[arg(long)]
+        proof: PathBuf,
+        /// The file to write the compressed proof to.
+        #[arg(long)]
+        output: PathBuf,
+    },
+}
+
+#[derive(Args)]
+#[group(required = true, multiple = false)]
+struct CircuitArgs {
+    /// A built-in example circuit.
+    #[arg(long, value_enum)]
+    circuit: Option<Example>,
+    /// A circuit artifact: one `gate`, `copy`, `current` or `next` directive per line.
+    #[arg(long)]
+    circuit_file: Option<PathBuf>,
+}
+
+impl CircuitArgs {
+    fn load(&self) -> Result<Circuit, Box<dyn Error>> {
+        match (&self.circuit, &self.circuit_file) {
+            (Some(example), _) => Ok(Circuit::Example(*example)),
+            (None, Some(path)) => Ok(Circuit::Imported(ImportedCircuit::parse(
+                &fs::read_to_string(path)?,
+            )?)),
+            (None, None) => Err("no circuit given".into()),
+        }
+    }
+}
+
+#[derive(Args)]
+struct RandomnessArgs {
+    /// Seeds the randomness instead of reading it from `/dev/urandom`, to reproduce a run. A known
+    /// seed reveals the KZG trapdoor and the blinding factors.
+    #[arg(long)]
+    seed: Option<u64>,
+}
+
+impl RandomnessArgs {
+    fn rng(&self) -> Result<StdRng, Box<dyn Error>> {
+        if let Some(seed) = self.seed {
+            return Ok(StdRng::seed_from_u64(seed));
+        }
+
+        let mut seed = [0u8; 32];
+        fs::File::open("/dev/urandom")
+            .and_then(|mut urandom| urandom.read_exact(&mut seed))
+            .map_err(|error| format!("cannot read /dev/urandom ({error}), pass --seed instead"))?;
+        Ok(StdRng::from_seed(seed))
+    }
+}
+
+/// The built-in example circuits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Example {
+    /// Adds the witness, which must not be zero, to the state `[x]`.
+    Counter,
+    /// Steps from the state `[a, b]` to `[b, a + b]`, without a witness.
+    Fibonacci,
+}
+
+/// The step circuit of the keys: a built-in example or an imported circuit artifact. States and
+/// witnesses are lists of field elements.
+#[derive(Clone, Debug, PartialEq)]
+enum Circuit {
+    Example(Example),
+    Imported(ImportedCircuit),
+}
+
+/// A circuit artifact: the selectors of its gate rows, its copy constraints and the wires holding
+/// the current and the next state, as `(column, row)` pairs counted from its first gate row. The
+/// witness of a step assigns every wire of every row, row by row.
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+struct ImportedCircuit {
+    gates: Vec<[Fr; 6]>,
+    copies: Vec<((usize, usize), (usize, usize))>,
+    current_state: Vec<(usize, usize)>,
+    next_state: Vec<(usize, usize)>,
+}
+
+impl ImportedCircuit {
+    /// Parses a circuit artifact. Each line holds one directive, and `#` starts a comment:
+    ///
+    /// - `gate l=1 r=1 o=-1` appends a gate row with the given left, right, output,
+    ///   multiplication (`m`), constant (`c`) and power (`p`) selectors, zero if omitted. Gates
+    ///   satisfy `l a + r b + o c + m a b + c + p a^2 = 0` on the wires `a`, `b` and `c`, as the
+    ///   tool uses the default gate degree.
+    /// - `copy 1,0 0,1` constrains the wire in column 1 of row 0 to equal the wire in column 0 of
+    ///   row 1.
+    /// - `current 0,0` and `next 2,0` append the wire holding the next element of the current and
+    ///   of the next state.
+    fn parse(artifact: &str) -> Result<Self, String> {
+        let mut circuit = Self {
+            gates: Vec::new(),
+            copies: Vec::new(),
+            current_state: Vec::new(),
+            next_state: Vec::new(),
+        };
+        for (index, line) in artifact.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default();
+            let mut words = line.split_whitespace();
+            let Some(directive) = words.next() else {
+                continue;
+            };
+            let arguments: Vec<&str> = words.collect();
+            let error = |message: &str| format!("line {}: {message}", index + 1);
+            match (directive, arguments.as_slice()) {
+                ("gate", selectors) => {
+                    let mut gate = [Fr::zero(); 6];
+                    for selector in selectors {
+                        let (name, value) = selector
+                            .split_once('=')
+                            .ok_or_else(|| error("expected a selector like `l=1`"))?;
+                        let index = match name {
+                            "l" => LEFT_SELECTOR_INDEX,
+                            "r" => RIGHT_SELECTOR_INDEX,
+                            "o" => OUTPUT_SELECTOR_INDEX,
+                            "m" => MULTIPLICATION_SELECTOR_INDEX,
+                            "c" => CONSTANT_SELECTOR_INDEX,
+                            "p" => POWER_SELECTOR_INDEX,
+                            _ => return Err(error("unknown selector")),
+                        };
+                        gate[index] = parse_element(value).map_err(|message| error(&message))?;
+                    }
+                    circuit.gates.push(gate);
+                }
+                ("copy", [from, to]) => circuit.copies.push((
+                    parse_wire(from).map_err(|message| error(&message))?,
+                    parse_wire(to).map_err(|message| error(&message))?,
+                )),
+                ("current", [wire]) => circuit
+                    .current_state
+                    .push(parse_wire(wire).map_err(|message| error(&message))?),
+                ("next", [wire]) => circuit
+                    .next_state
+                    .push(parse_wire(wire).map_err(|message| error(&message))?),
+                ("copy" | "current" | "next", _) => {
+                    return Err(error("wrong number of wires"));
+                }
+                _ => return Err(error("unknown directive")),
+            }
+        }
+        circuit.check_layout().map_err(str::to_string)?;
+
+        Ok(circuit)
+    }
+
+    /// Checks that every wire lies in the gate rows and that both states have the same length.
+    fn check_layout(&self) -> Result<(), &'static str> {
+        if self.current_state.is_empty() || self.current_state.len() != self.next_state.len() {
+            return Err("the current and the next state must hold the same number of elements");
+        }
+        let in_rows = |&(column, row): &(usize, usize)| column < 3 && row < self.gates.len();
+        let wires = self.copies.iter().flat_map(|(from, to)| [from, to]);
+        if !wires
+            .chain(&self.current_state)
+            .chain(&self.next_state)
+            .all(in_rows)
+        {
+            return Err("a wire lies outside the gate rows");
+        }
+
+        Ok(())
+    }
+
+    fn wire(witness: &[Fr], (column, row): (usize, usize)) -> Fr {
+        witness[3 * row + column]
+    }
+}
+
+impl CanonicalSerialize for Circuit {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        match self {
+            Self::Example(Example::Counter) => 0u8.serialize_with_mode(writer, compress),
+            Self::Example(Example::Fibonacci) => 1u8.serialize_with_mode(writer, compress),
+            Self::Imported(circuit) => {
+                2u8.serialize_with_mode(&mut writer, compress)?;
+                circuit.serialize_with_mode(writer, compress)
+            }
+        }
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        match self {
+            Self::Example(_) => 1,
+            Self::Imported(circuit) => 1 + circuit.serialized_size(compress),
+        }
+    }
+}
+
+impl Valid for Circuit {
+    fn check(&self) -> Result<(), SerializationError> {
+        match self {
+            Self::Example(_) => Ok(()),
+            Self::Imported(circuit) => {
+                circuit.check()?;
+                circuit
+                    .check_layout()
+                    .map_err(|_| SerializationError::InvalidData)
+            }
+        }
+    }
+}
+
+impl CanonicalDeserialize for Circuit {
+    fn deserialize_with_mode<R: ark_serialize::Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let circuit = match u8::deserialize_with_mode(&mut reader, compress, validate)? {
+            0 => Self::Example(Example::Counter),
+            1 => Self::Example(Example::Fibonacci),
+            2 => Self::Imported(ImportedCircuit::deserialize_with_mode(
+                reader, compress, validate,
+            )?),
+            _ => return Err(SerializationError::InvalidData),
+        };
+        if let Validate::Yes = validate {
+            circuit.check()?;
+        }
+
+        Ok(circuit)
+    }
+}
+
+impl StepCircuit<Fr> for Circuit {
+    type State = Vec<Fr>;
+    type Witness = Vec<Fr>;
+
+    fn execute(&self, current_state: &Vec<Fr>, witness: &Vec<Fr>) -> Result<Vec<Fr>, SangriaError> {
+        if current_state.len() != self.state_len() {
+            return Err(SangriaError::PublicInputMismatch);
+        }
+
+        match self {
+            Self::Example(Example::Counter) => match witness[..] {
+                [increment] if !increment.is_zero() => Ok(vec![current_state[0] + increment]),
+                _ => Err(SangriaError::InvalidWitness),
+            },
+            Self::Example(Example::Fibonacci) if witness.is_empty() => {
+                Ok(vec![current_state[1], current_state[0] + current_state[1]])
+            }
+            Self::Example(Example::Fibonacci) => Err(SangriaError::InvalidWitness),
+            Self::Imported(circuit) => {
+                let wire = |wire| ImportedCircuit::wire(witness, wire);
+                let satisfied = witness.len() == 3 * circuit.gates.len()
+                    && circuit.gates.iter().zip(witness.chunks(3)).all(|(q, w)| {
+                        q[LEFT_SELECTOR_INDEX] * w[0]
+                            + q[RIGHT_SELECTOR_INDEX] * w[1]
+                            + q[OUTPUT_SELECTOR_INDEX] * w[2]
+                            + q[MULTIPLICATION_SELECTOR_INDEX] * w[0] * w[1]
+                            + q[CONSTANT_SELECTOR_INDEX]
+                            + q[POWER_SELECTOR_INDEX] * w[0].pow([DEFAULT_GATE_DEGREE as u64])
+                            == Fr::zero()
+                    })
+                    && circuit
+                        .copies
+                        .iter()
+                        .all(|&(from, to)| wire(from) == wire(to))
+                    && circuit
+                        .current_state
+                        .iter()
+                        .map(|&position| wire(position))
+                        .eq(current_state.iter().copied());
+                if !satisfied {
+                    return Err(SangriaError::InvalidWitness);
+                }
+
+                Ok(circuit
+                    .next_state
+                    .iter()
+                    .map(|&position| wire(position))
+                    .collect())
+            }
+        }
+    }
+}
+
+impl PLONKStepCircuit<Fr> for Circuit {
+    fn state_len(&self) -> usize {
+        match self {
+            Self::Example(Example::Counter) => 1,
+            Self::Example(Example::Fibonacci) => 2,
+            Self::Imported(circuit) => circuit.current_state.len(),
+        }
+    }
+
+    fn encode_state(&self, state: &Vec<Fr>) -> Vec<Fr> {
+        state.clone()
+    }
+
+    /// Lays out the gates with zero wires when the witness is empty, as for the default witness
+    /// the keys are encoded with.
+    fn synthesize(
+        &self,
+        trace: &mut TraceTable<Fr>,
+        current_state: &[Fr],
+        next_state: &[Fr],
+        witness: &Vec<Fr>,
+    ) -> Result<StateWires, SangriaError> {
+        let row = trace.number_of_rows();
+        let mut addition = [Fr::zero(); 6];
+        addition[LEFT_SELECTOR_INDEX] = Fr::one();
+        addition[RIGHT_SELECTOR_INDEX] = Fr::one();
+        addition[OUTPUT_SELECTOR_INDEX] = -Fr::one();
+
+        match self {
+            Self::Example(Example::Counter) => {
+                let increment = witness.first().copied().unwrap_or_default();
+                trace.push_row(
+                    RowTag::Gate(addition),
+                    [current_state[0], increment, next_state[0]],
+                );
+
+                let mut non_zero = [Fr::zero(); 6];
+                non_zero[MULTIPLICATION_SELECTOR_INDEX] = Fr::one();
+                non_zero[CONSTANT_SELECTOR_INDEX] = -Fr::one();
+                let inverse = increment.inverse().unwrap_or_default();
+                trace.push_row(RowTag::Gate(non_zero), [increment, inverse, Fr::zero()]);
+                trace.copy((1, row), (0, row + 1));
+
+                Ok(StateWires {
+                    current_state: vec![(0, row)],
+                    next_state: vec![(2, row)],
+                })
+            }
+            Self::Example(Example::Fibonacci) => {
+                trace.push_row(
+                    RowTag::Gate(addition),
+                    [current_state[0], current_state[1], next_state[1]],
+                );
+
+                Ok(StateWires {
+                    current_state: vec![(0, row), (1, row)],
+                    next_state: vec![(1, row), (2, row)],
+                })
+            }
+            Self::Imported(circuit) => {
+                circuit.check_layout().map_err(SangriaError::InvalidTrace)?;
+                for (index, gate) in circuit.gates.iter().enumerate() {
+                    let value = |column| witness.get(3 * index + column).copied();
+                    let wires = [0, 1, 2].map(|column| value(column).unwrap_or_default());
+                    trace.push_row(RowTag::Gate(*gate), wires);
+                }
+                let shift = |(column, offset): (usize, usize)| (column, row + offset);
+                for &(from, to) in &circuit.copies {
+                    trace.copy(shift(from), shift(to));
+                }
+
+                Ok(StateWires {
+                    current_state: circuit.current_state.iter().map(|&w| shift(w)).collect(),
+                    next_state: circuit.next_state.iter().map(|&w| shift(w)).collect(),
+                })
+            }
+        }
+    }
+}
+
+/// Parses a field element written as a decimal integer, possibly negative.
+fn parse_element(value: &str) -> Result<Fr, String> {
+    let (negative, digits) = match value.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, value),
+    };
+    if digits.is_empty() || !digits.bytes().all(|digit| digit.is_ascii_digit()) {
+        return Err(format!("`{value}` is not a decimal integer"));
+    }
+    let element = digits.bytes().fold(Fr::zero(), |element, digit| {
+        element * Fr::from(10u64) + Fr::from(u64::from(digit - b'0'))
+    });
+
+    Ok(if negative { -element } else { element })
+}
+
+/// Field elements given on the command line. Clap would parse a `Vec` argument value by value.
+#[derive(Clone, Debug)]
+struct Elements(Vec<Fr>);
+
+/// Parses comma-separated field elements, none if `values` is empty.
+fn parse_elements(values: &str) -> Result<Elements, String> {
+    if values.trim().is_empty() {
+        return Ok(Elements(Vec::new()));
+    }
+
+    values
+        .split(',')
+        .map(|value| parse_element(value.trim()))
+        .collect::<Result<_, _>>()
+        .map(Elements)
+}
+
+/// Parses a wire written as `column,row`.
+fn parse_wire(wire: &str) -> Result<(usize, usize), String> {
+    let parse = |index: &str| index.parse().map_err(|_| format!("`{wire}` is not a wire"));
+    let (column, row) = wire
+        .split_once(',')
+        .ok_or_else(|| format!("`{wire}` is not a wire like `0,1`"))?;
+
+    Ok((parse(column)?, parse(row)?))
+}
+
+/// Writes field elements in decimal. The `Display` of a zero field element is empty.
+fn format_elements(elements: &[Fr]) -> String {
+    let elements: Vec<String> = elements
+        .iter()
+        .map(|element| match element.is_zero() {
+            true => "0".to_string(),
+            false => element.to_string(),
+        })
+        .collect();
+    format!("[{}]", elements.join(", "))
+}
+
+fn read<T: CanonicalDeserialize>(path: &Path) -> Result<T, Box<dyn Error>> {
+    let bytes =
+        fs::read(path).map_err(|error| format!("cannot read {}: {error}", path.display()))?;
+    T::deserialize_compressed(&bytes[..])
+        .map_err(|error| format!("cannot deserialize {}: {error}", path.display()).into())
+}
+
+fn write<T: CanonicalSerialize>(path: &Path, value: &T) -> Result<(), Box<dyn Error>> {
+    let mut bytes = Vec::with_capacity(value.compressed_size());
+    value.serialize_compressed(&mut bytes)?;
+    fs::write(path, bytes)
+        .map_err(|error| format!("cannot write {}: {error}", path.display()).into())
+}
+
+/// Runs a command, returning the line it prints.
+fn run(command: Command) -> Result<String, Box<dyn Error>> {
+    match command {
+        Command::Setup {
+            circuit,
+            parameters,
+            randomness,
+        } => {
+            let circuit = circuit.load()?;
+            let zeros = vec![Fr::zero(); circuit.state_len()];
+            let mut trace = TraceTable::new();
+            circuit.synthesize(&mut trace, &zeros, &zeros, &Vec::new())?;
+            let info = SetupInfo {
+                number_of_public_inputs: 2 * circuit.state_len(),
+                number_of_gates: trace.number_of_rows(),
+                config: SangriaConfig::default(),
+                poseidon_constants: spec_vectors::poseidon_constants(),
+            };
+            let public_parameters = Scheme::setup(&info, &mut randomness.rng()?)?;
+            write(&parameters, &public_parameters)?;
+
+            Ok(format!(
+                "wrote the parameters of {} gates to {}",
+                info.number_of_gates,
+                parameters.display()
+            ))
+        }
+        Command::Encode {
+            circuit,
+            parameters,
+            prover_key,
+            verifier_key,
+            randomness,
+        } => {
+            let circuit = circuit.load()?;
+            let public_parameters: PublicParameters = read(&parameters)?;
+            let (pk, vk) = Scheme::encode(&public_parameters, &circuit, &mut randomness.rng()?)?;
+            write(&prover_key, &pk)?;
+            write(&verifier_key, &vk)?;
+
+            Ok(format!(
+                "wrote the keys to {} and {}",
+                prover_key.display(),
+                verifier_key.display()
+            ))
+        }
+        Command::Prove {
+            prover_key,
+            input,
+            origin,
+            witness,
+            steps,
+            output,
+            randomness,
+        } => {
+            let pk: ProverKey = read(&prover_key)?;
+            let state_len = pk.step_circuit().state_len();
+            let (origin, mut state, mut proof) = match input {
+                Some(input) => {
+                    let (origin, state, proof): ProofFile = read(&input)?;
+                    (origin, state, Some(proof))
+                }
+                None => {
+                    let origin = origin.map_or_else(|| vec![Fr::zero(); state_len], |o| o.0);
+                    (origin.clone(), origin, None)
+                }
+            };
+            if origin.len() != state_len {
+                return Err(format!("the state must have {state_len} elements").into());
+            }
+
+            let mut rng = randomness.rng()?;
+            for _ in 0..steps {
+                let (next_state, next_proof) =
+                    Scheme::prove_step(&pk, &origin, state, proof, &witness.0, &mut rng)?;
+                (state, proof) = (next_state, Some(next_proof));
+            }
+            let proof = proof.ok_or("no steps to prove")?;
+            let line = format!(
+                "proved {} steps to {}",
+                proof.number_of_steps(),
+                format_elements(&state)
+            );
+            write(&output, &(origin, state, proof))?;
+
+            Ok(line)
+        }
+        Command::Verify {
+            verifier_key,
+            proof,
+            compressed,
+        } => {
+            let vk: VerifierKey = read(&verifier_key)?;
+            let (origin, state, steps) = if compressed {
+                let (origin, state, proof): CompressedProofFile = read(&proof)?;
+                let steps = proof.number_of_steps();
+                Scheme::verify_compressed(&vk, &origin, state.clone(), proof)?;
+                (origin, state, steps)
+            } else {
+                let (origin, state, proof): ProofFile = read(&proof)?;
+                let steps = proof.number_of_steps();
+                Scheme::verify(&vk, &origin, state.clone(), Some(proof))?;
+                (origin, state, steps)
+            };
+
+            Ok(format!(
+                "verified {steps} steps from {} to {}",
+                format_elements(&origin),
+                format_elements(&state)
+            ))
+        }
+        Command::Compress {
+            prover_key,
+            proof,
+            output,
+        } => {
+            let pk: ProverKey = read(&prover_key)?;
+            let (origin, state, proof): ProofFile = read(&proof)?;
+            let compressed = Scheme::compress(&pk, proof)?;
+            write(&output, &(origin, state, compressed))?;
+
+            Ok(format!(
+                "wrote the compressed proof to {}",
+                output.display()
+            ))
+        }
+    }
+}
+
+fn main() {
+    match run(Cli::parse().command) {
+        Ok(line) => println!("{line}"),
+        Err(error) => {
+            eprintln!("error: {error}");
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COUNTER: &str = "
+        # next = current + increment, with a non-zero increment
+        gate l=1 r=1 o=-1
+        gate m=1 c=-1 # increment * inverse = 1
+        copy 1,0 0,1
+        current 0,0
+        next 2,0
+    ";
+
+    fn run_line(directory: &Path, arguments: &str) -> Result<String, Box<dyn Error>> {
+        let arguments =
+            arguments
+                .split_whitespace()
+                .map(|argument| match argument.strip_prefix('@') {
+                    Some(file) => directory.join(file).display().to_string(),
+                    None => argument.to_string(),
+                });
+        run(
+            Cli::try_parse_from(["sangria"].into_iter().map(str::to_string).chain(arguments))?
+                .command,
+        )
+    }
+
+    #[test]
+    fn pipeline_proves_extends_and_compresses() {
+        let directory = std::env::temp_dir().join(format!("sangria-cli-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let circuit = "--circuit fibonacci";
+        run_line(
+            &directory,
+            &format!("setup {circuit} --parameters @parameters --seed 1"),
+        )
+        .unwrap();
+        run_line(
+            &directory,
+            &format!(
+                "encode {circuit} --parameters @parameters --prover-key @pk --verifier-key @vk \
+                 --seed 2"
+            ),
+        )
+        .unwrap();
+
+        assert_eq!(
+            run_line(
+                &directory,
+                "prove --prover-key @pk --origin 0,1 --steps 2 --output @proof"
+            )
+            .unwrap(),
+            "proved 2 steps to [1, 2]"
+        );
+        run_line(
+            &directory,
+            "prove --prover-key @pk --input @proof --output @proof",
+        )
+        .unwrap();
+        assert_eq!(
+            run_line(&directory, "verify --verifier-key @vk --proof @proof").unwrap(),
+            "verified 3 steps from [0, 1] to [2, 3]"
+        );
+        run_line(
+            &directory,
+            "compress --prover-key @pk --proof @proof --output @compressed",
+        )
+        .unwrap();
+        assert_eq!(
+            run_line(
+                &directory,
+                "verify --verifier-key @vk --proof @compressed --compressed"
+            )
+            .unwrap(),
+            "verified 3 steps from [0, 1] to [2, 3]"
+        );
+
+        // A witness the circuit rejects, and a proof whose final state was tampered with.
+        assert!(run_line(
+            &directory,
+            "prove --prover-key @pk --witness 1 --output @bad"
+        )
+        .is_err());
+        let (origin, _, proof): ProofFile = read(&directory.join("proof")).unwrap();
+        let forged: ProofFile = (origin, vec![Fr::from(3u64), Fr::from(5u64)], proof);
+        write(&directory.join("forged"), &forged).unwrap();
+        assert!(run_line(&directory, "verify --verifier-key @vk --proof @forged").is_err());
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn imported_circuit_executes_and_proves_like_the_counter() {
+        let imported = Circuit::Imported(ImportedCircuit::parse(COUNTER).unwrap());
+        let two = Fr::from(2u64);
+        let witness = vec![
+            Fr::from(5u64),
+            two,
+            Fr::from(7u64),
+            two,
+            two.inverse().unwrap(),
+            Fr::zero(),
+        ];
+        assert_eq!(
+            imported.execute(&vec![Fr::from(5u64)], &witness),
+            Ok(vec![Fr::from(7u64)])
+        );
+
+        // Wrong states, a gate that does not hold and a broken copy constraint.
+        let mut bad_witnesses = vec![witness.clone(); 3];
+        bad_witnesses[0][0] = Fr::from(6u64);
+        bad_witnesses[1][2] = Fr::from(8u64);
+        bad_witnesses[2][3] = Fr::one();
+        for bad_witness in &bad_witnesses {
+            assert_eq!(
+                imported.execute(&vec![Fr::from(5u64)], bad_witness),
+                Err(SangriaError::InvalidWitness)
+            );
+        }
+
+        let rng = &mut StdRng::seed_from_u64(1);
+        let info = SetupInfo {
+            number_of_public_inputs: 2,
+            number_of_gates: 2,
+            config: SangriaConfig::default(),
+            poseidon_constants: spec_vectors::poseidon_constants(),
+        };
+        let public_parameters = Scheme::setup(&info, rng).unwrap();
+        let (pk, vk) = Scheme::encode(&public_parameters, &imported, rng).unwrap();
+        let origin = vec![Fr::from(5u64)];
+        let (state, proof) =
+            Scheme::prove_step(&pk, &origin, origin.clone(), None, &witness, rng).unwrap();
+        assert_eq!(Scheme::verify(&vk, &origin, state, Some(proof)), Ok(()));
+
+        let mut bytes = Vec::new();
+        vk.serialize_compressed(&mut bytes).unwrap();
+        let deserialized = VerifierKey::deserialize_compressed(&bytes[..]).unwrap();
+        assert_eq!(deserialized.step_circuit(), &imported);
+    }
+
+    #[test]
+    fn artifacts_with_errors_are_rejected() {
+        for (artifact, error) in [
+            ("gate x=1", "line 1: unknown selector"),
+            ("gate l=one", "line 1: `one` is not a decimal integer"),
+            ("gate\ncopy 0,0", "line 2: wrong number of wires"),
+            ("gate\nrow", "line 2: unknown directive"),
+            (
+                "gate\ncurrent 0,1\nnext 2,0",
+                "a wire lies outside the gate rows",
+            ),
+            (
+                "gate\ncurrent 0,0",
+                "the current and the next state must hold the same number of elements",
+            ),
+        ] {
+            assert_eq!(ImportedCircuit::parse(artifact), Err(error.to_string()));
+        }
+    }
+}
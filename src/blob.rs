@@ -0,0 +1,253 @@
+use ark_crypto_primitives::sponge::{
+    poseidon::{PoseidonConfig, PoseidonSponge},
+    Absorb, CryptographicSponge, FieldBasedCryptographicSponge,
+};
+use ark_ff::PrimeField;
+
+use crate::{
+    poseidon_hash_gadget, BatchedIVCProof, FoldingCommitmentConfig, PLONKStepCircuit, SangriaError,
+    StateWires, StepCircuit, TraceTable,
+};
+
+/// Hashes an opaque byte blob (e.g. a transaction batch) into the field.
+pub fn blob_hash<F: PrimeField + Absorb>(poseidon_constants: &PoseidonConfig<F>, blob: &[u8]) -> F {
+    let mut sponge = PoseidonSponge::new(poseidon_constants);
+    sponge.absorb(&blob);
+    sponge.squeeze_field_elements(1)[0]
+}
+
+/// Extends the chain of bound blobs with the hash of the next blob.
+fn chain_blob_hash<F: PrimeField + Absorb>(
+    poseidon_constants: &PoseidonConfig<F>,
+    chain: F,
+    blob_hash: F,
+) -> F {
+    let mut sponge = PoseidonSponge::new(poseidon_constants);
+    sponge.absorb(&chain);
+    sponge.absorb(&blob_hash);
+    sponge.squeeze_native_field_elements(1)[0]
+}
+
+/// Recomputes the chain of bound blobs from its starting value and the sequence of blob hashes, one
+/// per step. A verifier holding the blobs compares the result to the chain in the final state of a
+/// [`BlobBoundStep`] computation.
+pub fn chain_blob_hashes<F: PrimeField + Absorb>(
+    poseidon_constants: &PoseidonConfig<F>,
+    origin_chain: F,
+    blob_hashes: &[F],
+) -> F {
+    blob_hashes.iter().fold(origin_chain, |chain, &blob_hash| {
+        chain_blob_hash(poseidon_constants, chain, blob_hash)
+    })
+}
+
+/// Wraps a step circuit so that every step binds an off-circuit blob. The state is the inner state
+/// together with a hash chain of the blobs bound so far and the hash of the last bound blob; the
+/// witness is the inner witness together with the hash of the step's blob, so the blob contents
+/// never enter the circuit.
+#[derive(Clone)]
+pub struct BlobBoundStep<F: PrimeField, SC> {
+    inner: SC,
+    poseidon_constants: PoseidonConfig<F>,
+}
+
+impl<F: PrimeField, SC> BlobBoundStep<F, SC> {
+    /// Wraps `inner`, chaining the blob hashes with Poseidon.
+    pub fn new(inner: SC, poseidon_constants: PoseidonConfig<F>) -> Self {
+        Self {
+            inner,
+            poseidon_constants,
+        }
+    }
+}
+
+impl<F, SC> StepCircuit<F> for BlobBoundStep<F, SC>
+where
+    F: PrimeField + Absorb,
+    SC: StepCircuit<F>,
+{
+    type State = (SC::State, F, F);
+    type Witness = (SC::Witness, F);
+
+    fn execute(
+        &self,
+        (current_state, chain, _): &Self::State,
+        (witness, blob_hash): &Self::Witness,
+    ) -> Result<Self::State, SangriaError> {
+        Ok((
+            self.inner.execute(current_state, witness)?,
+            chain_blob_hash(&self.poseidon_constants, *chain, *blob_hash),
+            *blob_hash,
+        ))
+    }
+
+    fn fixed_tables(&self) -> Vec<Vec<F>> {
+        self.inner.fixed_tables()
+    }
+}
+
+/// A state is encoded as the inner state followed by the chain and the last blob hash. The gates
+/// of the inner step are followed by those hashing the chain with the step's blob hash, which is
+/// the last element of the next state.
+impl<F, SC> PLONKStepCircuit<F> for BlobBoundStep<F, SC>
+where
+    F: PrimeField + Absorb,
+    SC: PLONKStepCircuit<F>,
+{
+    fn state_len(&self) -> usize {
+        self.inner.state_len() + 2
+    }
+
+    fn encode_state(&self, (state, chain, blob_hash): &Self::State) -> Vec<F> {
+        let mut encoding = self.inner.encode_state(state);
+        encoding.extend([*chain, *blob_hash]);
+        encoding
+    }
+
+    fn synthesize(
+        &self,
+        trace: &mut TraceTable<F>,
+        current_state: &[F],
+        next_state: &[F],
+        (witness, blob_hash): &Self::Witness,
+    ) -> Result<StateWires, SangriaError> {
+        let inner_len = self.inner.state_len();
+        if current_state.len() != inner_len + 2 || next_state.len() != inner_len + 2 {
+            return Err(SangriaError::InvalidWitness);
+        }
+
+        let mut wires = self.inner.synthesize(
+            trace,
+            &current_state[..inner_len],
+            &next_state[..inner_len],
+            witness,
+        )?;
+        let current = trace.hint(&[], |_| Ok(current_state[inner_len..].to_vec()))?;
+        let blob_hash = trace.hint(&[], |_| Ok(vec![*blob_hash]))?[0];
+        let chain =
+            poseidon_hash_gadget(trace, &self.poseidon_constants, &[current[0], blob_hash])?;
+
+        wires.current_state.extend(current);
+        wires.next_state.extend([chain, blob_hash]);
+        Ok(wires)
+    }
+}
+
+impl<F, Comm> BatchedIVCProof<F, Comm>
+where
+    F: PrimeField + Absorb,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    /// Returns the hashes of the blobs bound by the steps of a proof of a [`BlobBoundStep`]
+    /// computation, in the order of the steps, read from the public inputs of the step instances.
+    /// They are only bound to the computation once the proof has been verified.
+    pub fn bound_blob_hashes<SC: PLONKStepCircuit<F>>(
+        &self,
+        step: &BlobBoundStep<F, SC>,
+    ) -> Result<Vec<F>, SangriaError> {
+        self.next_states(step.state_len())?
+            .into_iter()
+            .map(|next_state| {
+                next_state
+                    .last()
+                    .copied()
+                    .ok_or(SangriaError::PublicInputMismatch)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{blob_hash, chain_blob_hashes, BlobBoundStep};
+    use crate::{
+        utils::{
+            circuits::Counter, inner_product::InnerProductConfig,
+            poseidon::poseidon_parameters_for_test,
+        },
+        AugmentedCircuit, NoopStep, Sangria, SangriaConfig, SangriaError, SetupInfo,
+    };
+    use ark_bn254::Fr;
+    use ark_ff::{One, Zero};
+    use ark_std::test_rng;
+
+    #[test]
+    fn final_chain_binds_the_blob_sequence() {
+        let params = poseidon_parameters_for_test::<Fr>();
+        let step = BlobBoundStep::new(NoopStep::<u64>::new(), params.clone());
+        let blobs: [&[u8]; 3] = [b"batch 0", b"batch 1", b""];
+        let blob_hashes: Vec<Fr> = blobs.iter().map(|blob| blob_hash(&params, blob)).collect();
+        let witnesses: Vec<((), Fr)> = blob_hashes.iter().map(|&hash| ((), hash)).collect();
+
+        let (state, chain, last_blob_hash) =
+            Sangria::replay(&step, (7, Fr::zero(), Fr::zero()), &witnesses).unwrap();
+
+        assert_eq!(state, 7);
+        assert_eq!(last_blob_hash, blob_hashes[2]);
+        assert_eq!(chain, chain_blob_hashes(&params, Fr::zero(), &blob_hashes));
+
+        let mut reordered = blob_hashes.clone();
+        reordered.swap(0, 1);
+        assert_ne!(chain, chain_blob_hashes(&params, Fr::zero(), &reordered));
+        assert_ne!(
+            chain,
+            chain_blob_hashes(&params, Fr::zero(), &blob_hashes[..2])
+        );
+    }
+
+    #[test]
+    fn chaining_is_proved_in_circuit() {
+        type Scheme = Sangria<InnerProductConfig>;
+
+        let rng = &mut test_rng();
+        let params = poseidon_parameters_for_test::<Fr>();
+        let step = BlobBoundStep::new(Counter, params.clone());
+        let circuit = AugmentedCircuit::new(step.clone())
+            .batched_circuit()
+            .unwrap();
+        let info = SetupInfo {
+            number_of_public_inputs: 6,
+            number_of_gates: circuit.number_of_rows() - 7,
+            config: SangriaConfig::default(),
+            poseidon_constants: params.clone(),
+        };
+        let public_parameters = Scheme::setup(&info, rng).unwrap();
+        let (pk, vk) = Scheme::encode(&public_parameters, &step, rng).unwrap();
+
+        let blobs: [&[u8]; 2] = [b"batch 0", b"batch 1"];
+        let blob_hashes: Vec<Fr> = blobs.iter().map(|blob| blob_hash(&params, blob)).collect();
+        let origin = (Fr::zero(), Fr::zero(), Fr::zero());
+        let (mut state, mut proof) = (origin, None);
+        for &hash in &blob_hashes {
+            (state, proof) =
+                Scheme::prove_step(&pk, &origin, state, proof, &(Fr::one(), hash), rng)
+                    .map(|(state, proof)| (state, Some(proof)))
+                    .unwrap();
+        }
+
+        assert_eq!(
+            state,
+            (
+                Fr::from(2u64),
+                chain_blob_hashes(&params, Fr::zero(), &blob_hashes),
+                blob_hashes[1]
+            )
+        );
+        assert_eq!(
+            proof.as_ref().unwrap().bound_blob_hashes(&step).unwrap(),
+            blob_hashes
+        );
+        assert_eq!(Scheme::verify(&vk, &origin, state, proof.clone()), Ok(()));
+
+        // the chain must be the hash of the bound blobs
+        let forged = (
+            state.0,
+            chain_blob_hashes(&params, Fr::zero(), &blob_hashes[1..]),
+            state.2,
+        );
+        assert_eq!(
+            Scheme::verify(&vk, &origin, forged, proof),
+            Err(SangriaError::InvalidProof)
+        );
+    }
+}
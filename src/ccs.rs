@@ -0,0 +1,642 @@
+use ark_crypto_primitives::sponge::Absorb;
+use ark_ff::PrimeField;
+use ark_std::{marker::PhantomData, rand::Rng};
+
+use crate::{
+    folding_scheme::FoldingCommitmentConfig,
+    relaxed_r1cs::{
+        prove_many, sparse_row, verify_many, PublicParameters, SlackCommitment, SparseMatrix,
+        VerifierKey as TranscriptKey, WireVariables,
+    },
+    transcript::Transcript,
+    NonInteractiveFoldingScheme, PLONKCircuit, R1CSFoldingScheme, R1CSShape, RelaxedR1CSInstance,
+    RelaxedR1CSWitness, SangriaError, SetupInfo, TraceTable, CONSTANT_SELECTOR_INDEX,
+    LEFT_SELECTOR_INDEX, MULTIPLICATION_SELECTOR_INDEX, OUTPUT_SELECTOR_INDEX,
+    POWER_SELECTOR_INDEX, RIGHT_SELECTOR_INDEX,
+};
+
+/// The structure of a customizable constraint system (CCS, see <https://eprint.iacr.org/2023/552>):
+/// matrices `M_j` over the vector `z = (W, u, x)` of the witness variables, the scaling factor and
+/// the public inputs, multisets `S_i` of matrices and constants `c_i`. With `d` the size of the
+/// largest multiset, a relaxed instance is satisfied if `Σ_i c_i u^(d - |S_i|) ∘_{j ∈ S_i} M_j z =
+/// E`, which generalizes both relaxed PLONK and relaxed R1CS.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CCSShape<F: PrimeField> {
+    number_of_public_inputs: usize,
+    number_of_variables: usize,
+    number_of_constraints: usize,
+    matrices: Vec<SparseMatrix<F>>,
+    multisets: Vec<Vec<usize>>,
+    constants: Vec<F>,
+    wires: WireVariables,
+}
+
+impl<F: PrimeField> CCSShape<F> {
+    /// Creates the structure of a CCS with `number_of_variables` witness variables and
+    /// `number_of_public_inputs` public inputs from its sparse matrices, given row by row as lists
+    /// of (column, value) over `z = (W, u, x)`, its multisets of matrix indices and their constants.
+    /// The rows are normalized like in [`R1CSShape::new`] and the multisets are sorted, so that equal
+    /// systems are absorbed into transcripts alike. Returns an error if the matrices do not have as
+    /// many rows, if an entry points outside `z` or a multiset outside the matrices, if there is not
+    /// one constant per multiset or if every multiset is empty.
+    pub fn new(
+        number_of_public_inputs: usize,
+        number_of_variables: usize,
+        matrices: Vec<Vec<Vec<(usize, F)>>>,
+        multisets: Vec<Vec<usize>>,
+        constants: Vec<F>,
+    ) -> Result<Self, SangriaError> {
+        let z_len = number_of_variables + 1 + number_of_public_inputs;
+        let number_of_constraints = matrices.first().map_or(0, Vec::len);
+        if matrices
+            .iter()
+            .any(|matrix| matrix.len() != number_of_constraints)
+        {
+            return Err(SangriaError::InvalidConfig(
+                "the CCS matrices must have as many rows",
+            ));
+        }
+        if multisets.len() != constants.len() {
+            return Err(SangriaError::InvalidConfig(
+                "a CCS has one constant per multiset",
+            ));
+        }
+        if multisets.iter().all(Vec::is_empty) {
+            return Err(SangriaError::InvalidConfig(
+                "a CCS needs a non-empty multiset",
+            ));
+        }
+        if matrices
+            .iter()
+            .flatten()
+            .flatten()
+            .any(|&(column, _)| column >= z_len)
+            || multisets
+                .iter()
+                .flatten()
+                .any(|&index| index >= matrices.len())
+        {
+            return Err(SangriaError::IndexOutOfBounds);
+        }
+
+        let matrices = matrices
+            .into_iter()
+            .map(|matrix| matrix.into_iter().map(sparse_row).collect())
+            .collect();
+        let mut multisets = multisets;
+        multisets
+            .iter_mut()
+            .for_each(|multiset| multiset.sort_unstable());
+        Ok(Self {
+            number_of_public_inputs,
+            number_of_variables,
+            number_of_constraints,
+            matrices,
+            multisets,
+            constants,
+            wires: WireVariables::default(),
+        })
+    }
+
+    /// Converts a PLONK circuit with `number_of_public_inputs` public input rows into a CCS of
+    /// degree `gate_degree`, with the variables of [`R1CSShape::from_plonk`]. Every gate row
+    /// enabling a selector becomes one constraint over the matrices `L = q_L a + q_R b + q_O c +
+    /// q_C u`, `Q = q_M a`, `B = b`, `P = q_P a` and `A = a`, with the multisets `{L}`, `{Q, B}` and
+    /// `{P, A, ..., A}` of constant 1, which is the relaxed PLONK gate `u^(d-1)(q_L a + q_R b + q_O
+    /// c) + u^(d-2) q_M ab + q_P a^d + u^d q_C = E`. Public inputs copied into one another are
+    /// constrained to be equal through `L`. Returns an error if the degree is smaller than two, if
+    /// the circuit has fewer rows than public inputs or if its copy constraint is not a permutation
+    /// of its wires.
+    pub fn from_plonk(
+        circuit: &PLONKCircuit<F>,
+        number_of_public_inputs: usize,
+        gate_degree: usize,
+    ) -> Result<Self, SangriaError> {
+        if gate_degree < 2 {
+            return Err(SangriaError::InvalidConfig(
+                "the gate degree must be at least two",
+            ));
+        }
+
+        let wires = WireVariables::new(circuit, number_of_public_inputs)?;
+        let u = wires.scaling_factor();
+        let [mut linear, mut multiplication, mut right, mut power, mut left] =
+            [(); 5].map(|_| Vec::new());
+        for &(first, other) in wires.public_equalities() {
+            linear.push(sparse_row([(first, F::one()), (other, -F::one())]));
+            for matrix in [&mut multiplication, &mut right, &mut power, &mut left] {
+                matrix.push(Vec::new());
+            }
+        }
+
+        let selectors = circuit.selectors();
+        for row in number_of_public_inputs..circuit.number_of_rows() {
+            let selector = |index: usize| selectors[index][row];
+            if (0..selectors.len()).all(|index| selector(index).is_zero()) {
+                continue;
+            }
+            let (a, b, c) = (
+                wires.variable(0, row),
+                wires.variable(1, row),
+                wires.variable(2, row),
+            );
+            linear.push(sparse_row([
+                (a, selector(LEFT_SELECTOR_INDEX)),
+                (b, selector(RIGHT_SELECTOR_INDEX)),
+                (c, selector(OUTPUT_SELECTOR_INDEX)),
+                (u, selector(CONSTANT_SELECTOR_INDEX)),
+            ]));
+            multiplication.push(sparse_row([(a, selector(MULTIPLICATION_SELECTOR_INDEX))]));
+            right.push(sparse_row([(b, F::one())]));
+            power.push(sparse_row([(a, selector(POWER_SELECTOR_INDEX))]));
+            left.push(sparse_row([(a, F::one())]));
+        }
+
+        let power_multiset = [3].into_iter().chain([4].repeat(gate_degree - 1)).collect();
+        let shape = Self::new(
+            number_of_public_inputs,
+            wires.number_of_variables(),
+            vec![linear, multiplication, right, power, left],
+            vec![vec![0], vec![1, 2], power_multiset],
+            vec![F::one(); 3],
+        )?;
+
+        Ok(Self { wires, ..shape })
+    }
+
+    /// Converts an R1CS into a CCS of degree 2 with the multisets `{A, B}` and `{C}` of constants 1
+    /// and -1, keeping its variables.
+    pub fn from_r1cs(shape: &R1CSShape<F>) -> Self {
+        let ccs = Self::new(
+            shape.number_of_public_inputs(),
+            shape.number_of_variables(),
+            shape.matrices().map(Clone::clone).into(),
+            vec![vec![0, 1], vec![2]],
+            vec![F::one(), -F::one()],
+        )
+        .expect("the matrices of an R1CS form a CCS");
+
+        Self {
+            wires: shape.wires().clone(),
+            ..ccs
+        }
+    }
+
+    /// Returns the number of public inputs.
+    pub fn number_of_public_inputs(&self) -> usize {
+        self.number_of_public_inputs
+    }
+
+    /// Returns the number of witness variables.
+    pub fn number_of_variables(&self) -> usize {
+        self.number_of_variables
+    }
+
+    /// Returns the number of constraints, the rows of the matrices.
+    pub fn number_of_constraints(&self) -> usize {
+        self.number_of_constraints
+    }
+
+    /// Returns the degree of the constraints, the size of the largest multiset.
+    pub fn degree(&self) -> usize {
+        self.multisets
+            .iter()
+            .map(Vec::len)
+            .max()
+            .expect("a CCS has a non-empty multiset")
+    }
+
+    /// Reads the witness variables and the public inputs of a trace of the PLONK circuit the
+    /// structure was converted from, every variable being read from a wire of its cycle. Returns an
+    /// error if the structure was not converted from a circuit with as many rows as the trace.
+    pub fn assignment(&self, trace: &TraceTable<F>) -> Result<(Vec<F>, Vec<F>), SangriaError> {
+        self.wires.assignment(trace)
+    }
+
+    /// Returns `Σ_i c_i u^(d - |S_i|) ∘_{j ∈ S_i} M_j z` for `z = (witness, u, public_inputs)`.
+    fn evaluate(&self, witness: &[F], u: F, public_inputs: &[F]) -> Vec<F> {
+        let z: Vec<F> = witness
+            .iter()
+            .copied()
+            .chain([u])
+            .chain(public_inputs.iter().copied())
+            .collect();
+        let products: Vec<Vec<F>> = self
+            .matrices
+            .iter()
+            .map(|matrix| {
+                matrix
+                    .iter()
+                    .map(|row| row.iter().map(|&(column, value)| value * z[column]).sum())
+                    .collect()
+            })
+            .collect();
+        let degree = self.degree();
+
+        (0..self.number_of_constraints)
+            .map(|row| {
+                self.multisets
+                    .iter()
+                    .zip(&self.constants)
+                    .map(|(multiset, &constant)| {
+                        multiset.iter().fold(
+                            constant * u.pow([(degree - multiset.len()) as u64]),
+                            |term, &index| term * products[index][row],
+                        )
+                    })
+                    .sum()
+            })
+            .collect()
+    }
+
+    /// Lists the sizes, the entries of the matrices, the multisets and the constants as field
+    /// elements, for transcripts.
+    fn transcript_elements(&self) -> Vec<F> {
+        let mut elements = vec![
+            F::from(self.number_of_public_inputs as u64),
+            F::from(self.number_of_variables as u64),
+            F::from(self.number_of_constraints as u64),
+            F::from(self.matrices.len() as u64),
+        ];
+        for row in self.matrices.iter().flatten() {
+            elements.push(F::from(row.len() as u64));
+            for &(column, value) in row {
+                elements.extend([F::from(column as u64), value]);
+            }
+        }
+        for (multiset, &constant) in self.multisets.iter().zip(&self.constants) {
+            elements.extend([constant, F::from(multiset.len() as u64)]);
+            elements.extend(multiset.iter().map(|&index| F::from(index as u64)));
+        }
+
+        elements
+    }
+}
+
+impl<F: PrimeField + Absorb> Absorb for CCSShape<F> {
+    fn to_sponge_bytes(&self, dest: &mut Vec<u8>) {
+        self.transcript_elements().to_sponge_bytes(dest);
+    }
+
+    fn to_sponge_field_elements<SpongeF: PrimeField>(&self, dest: &mut Vec<SpongeF>) {
+        self.transcript_elements().to_sponge_field_elements(dest);
+    }
+}
+
+/// A folding scheme for relaxed CCS, deriving its challenges from transcripts of type `RO`. It
+/// shares the instances, witnesses and public parameters of [`R1CSFoldingScheme`], and folds
+/// instances of a CCS of degree `d` in one round by committing to the `(d - 1)(k - 1)` quotient
+/// terms of `k` interpolated instances, so that multifolding schemes in the manner of HyperNova can
+/// reuse its structures.
+pub struct CCSFoldingScheme<F: PrimeField, Comm: FoldingCommitmentConfig<F>, RO: Transcript<F>>(
+    PhantomData<(F, Comm, RO)>,
+);
+
+/// The verifier key for the relaxed CCS folding scheme: the seed of the transcripts, bound to the
+/// CCS and the public parameters, and the degree of the CCS, which fixes the number of quotient
+/// terms.
+#[derive(Clone)]
+pub struct VerifierKey<F: PrimeField> {
+    /// The key binding the transcripts to the CCS and the public parameters.
+    pub transcript_key: TranscriptKey<F>,
+    /// The degree of the CCS.
+    pub degree: usize,
+}
+
+/// The prover key for the relaxed CCS folding scheme: the verifier key and the CCS, needed to
+/// compute quotient terms.
+pub struct ProverKey<F: PrimeField> {
+    /// The verifier key.
+    pub verifier_key: VerifierKey<F>,
+    /// The structure of the CCS.
+    pub shape: CCSShape<F>,
+}
+
+impl<F, Comm, RO> NonInteractiveFoldingScheme for CCSFoldingScheme<F, Comm, RO>
+where
+    F: PrimeField + Absorb,
+    Comm: FoldingCommitmentConfig<F>,
+    RO: Transcript<F>,
+{
+    type SetupInfo = SetupInfo<F>;
+    type PublicParameters = PublicParameters<F, Comm>;
+    type Structure = CCSShape<F>;
+    type Instance = RelaxedR1CSInstance<F, Comm>;
+    type Witness = RelaxedR1CSWitness<F>;
+    type ProverKey = ProverKey<F>;
+    type VerifierKey = VerifierKey<F>;
+    type ProverMessage = Vec<SlackCommitment<F, Comm>>;
+
+    /// Sizes the commit keys for the CCS conversion (see [`CCSShape::from_plonk`]) of PLONK
+    /// circuits with the public inputs and gates of `info`, as [`R1CSFoldingScheme`] does, for
+    /// constraints of any degree. Returns an error if the configuration is invalid.
+    fn setup<R: Rng>(
+        info: &SetupInfo<F>,
+        rng: &mut R,
+    ) -> Result<Self::PublicParameters, SangriaError> {
+        PublicParameters::new(info, rng)
+    }
+
+    fn encode<R: Rng>(
+        pp: &Self::PublicParameters,
+        shape: &Self::Structure,
+        _rng: &mut R,
+    ) -> Result<(Self::ProverKey, Self::VerifierKey), SangriaError> {
+        let verifier_key = VerifierKey {
+            transcript_key: pp.verifier_key::<RO>(
+                shape,
+                shape.number_of_public_inputs,
+                shape.number_of_variables,
+                shape.number_of_constraints,
+            )?,
+            degree: shape.degree(),
+        };
+
+        Ok((
+            ProverKey {
+                verifier_key: verifier_key.clone(),
+                shape: shape.clone(),
+            },
+            verifier_key,
+        ))
+    }
+
+    /// Folds two instance-witness pairs as [`Self::prover_many`] does, the prover message being
+    /// the commitments to the `d - 1` quotient terms.
+    fn prover<R: Rng>(
+        public_parameters: &Self::PublicParameters,
+        prover_key: &Self::ProverKey,
+        left_instance: &Self::Instance,
+        left_witness: &Self::Witness,
+        right_instance: &Self::Instance,
+        right_witness: &Self::Witness,
+        rng: &mut R,
+    ) -> Result<(Self::Instance, Self::Witness, Self::ProverMessage), SangriaError> {
+        Self::prover_many(
+            public_parameters,
+            prover_key,
+            &[left_instance.clone(), right_instance.clone()],
+            &[left_witness.clone(), right_witness.clone()],
+            rng,
+        )
+    }
+
+    fn verifier(
+        public_parameters: &Self::PublicParameters,
+        verifier_key: &Self::VerifierKey,
+        left_instance: &Self::Instance,
+        right_instance: &Self::Instance,
+        prover_message: &Self::ProverMessage,
+    ) -> Result<Self::Instance, SangriaError> {
+        Self::verifier_many(
+            public_parameters,
+            verifier_key,
+            &[left_instance.clone(), right_instance.clone()],
+            prover_message,
+        )
+    }
+
+    /// Folds `k` instances in one round: interpolating the instances at the points `0, ..., k - 1`
+    /// gives a left-hand side of degree `d (k - 1)`, and the prover commits to the `(d - 1)(k - 1)`
+    /// evaluations of its quotient by the vanishing polynomial, once the interpolated slack vectors
+    /// are subtracted, on the next points.
+    fn prover_many<R: Rng>(
+        public_parameters: &Self::PublicParameters,
+        prover_key: &Self::ProverKey,
+        instances: &[Self::Instance],
+        witnesses: &[Self::Witness],
+        rng: &mut R,
+    ) -> Result<(Self::Instance, Self::Witness, Self::ProverMessage), SangriaError> {
+        let shape = &prover_key.shape;
+        prove_many::<F, Comm, RO, R>(
+            public_parameters,
+            &prover_key.verifier_key.transcript_key,
+            instances,
+            witnesses,
+            prover_key.verifier_key.degree,
+            |witness, u, public_inputs| shape.evaluate(witness, u, public_inputs),
+            rng,
+        )
+    }
+
+    fn verifier_many(
+        public_parameters: &Self::PublicParameters,
+        verifier_key: &Self::VerifierKey,
+        instances: &[Self::Instance],
+        prover_message: &Self::ProverMessage,
+    ) -> Result<Self::Instance, SangriaError> {
+        verify_many::<F, Comm, RO>(
+            public_parameters,
+            &verifier_key.transcript_key,
+            instances,
+            prover_message,
+            verifier_key.degree,
+        )
+    }
+}
+
+impl<F, Comm, RO> CCSFoldingScheme<F, Comm, RO>
+where
+    F: PrimeField + Absorb,
+    Comm: FoldingCommitmentConfig<F>,
+    RO: Transcript<F>,
+{
+    /// Commits to the variables of a fresh witness and returns its instance, with the given public
+    /// inputs.
+    pub fn commit_witness(
+        public_parameters: &PublicParameters<F, Comm>,
+        public_inputs: Vec<F>,
+        witness: &RelaxedR1CSWitness<F>,
+    ) -> Result<RelaxedR1CSInstance<F, Comm>, SangriaError> {
+        R1CSFoldingScheme::<F, Comm, RO>::commit_witness(public_parameters, public_inputs, witness)
+    }
+
+    /// Checks that `witness` satisfies the relaxed CCS relation for `instance` and `shape`: the
+    /// commitments of the instance open to the witness and `Σ_i c_i u^(d - |S_i|) ∘_{j ∈ S_i} M_j z
+    /// = E`. Returns an error otherwise.
+    pub fn check_relation(
+        public_parameters: &PublicParameters<F, Comm>,
+        shape: &CCSShape<F>,
+        instance: &RelaxedR1CSInstance<F, Comm>,
+        witness: &RelaxedR1CSWitness<F>,
+    ) -> Result<(), SangriaError> {
+        public_parameters.check_commitments(
+            instance,
+            witness,
+            shape.number_of_variables,
+            shape.number_of_constraints,
+        )?;
+
+        let left_hand_side = shape.evaluate(
+            witness.witness(),
+            instance.scaling_factor(),
+            instance.public_inputs(),
+        );
+        if left_hand_side != witness.slack_vector() {
+            return Err(SangriaError::InvalidWitness);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CCSFoldingScheme, CCSShape};
+    use crate::{
+        utils::{
+            circuits::{fifth_power_plus_five, square_plus_five},
+            inner_product::InnerProductConfig,
+            poseidon::poseidon_parameters_for_test,
+        },
+        NonInteractiveFoldingScheme, R1CSShape, RelaxedR1CSWitness, SangriaConfig, SangriaError,
+        SetupInfo, TraceTable,
+    };
+    use ark_bn254::Fr;
+    use ark_crypto_primitives::sponge::poseidon::PoseidonSponge;
+    use ark_ff::{One, UniformRand, Zero};
+    use ark_std::test_rng;
+
+    type Scheme = CCSFoldingScheme<Fr, InnerProductConfig, PoseidonSponge<Fr>>;
+
+    /// Folds the traces of `trace` for 1 to 4 into a CCS of degree `gate_degree` converted from
+    /// its circuit, pairwise and then three at a time.
+    fn fold_converted_circuit(trace: fn(u64) -> TraceTable<Fr>, gate_degree: usize) {
+        let rng = &mut test_rng();
+        let info = SetupInfo {
+            number_of_public_inputs: 1,
+            number_of_gates: 2,
+            config: SangriaConfig {
+                gate_degree,
+                ..SangriaConfig::default()
+            },
+            poseidon_constants: poseidon_parameters_for_test(),
+        };
+        let pp = Scheme::setup(&info, rng).unwrap();
+        let circuit = trace(0).circuit(Vec::new()).unwrap();
+        let shape = CCSShape::from_plonk(&circuit, 1, gate_degree).unwrap();
+        assert_eq!(
+            (shape.degree(), shape.number_of_constraints()),
+            (gate_degree, 2)
+        );
+        let (pk, vk) = Scheme::encode(&pp, &shape, rng).unwrap();
+
+        let pairs: Vec<_> = (1..=4u64)
+            .map(|x| {
+                let (witness, public_inputs) = shape.assignment(&trace(x)).unwrap();
+                let witness =
+                    RelaxedR1CSWitness::new(witness, shape.number_of_constraints(), Fr::rand(rng));
+                let instance = Scheme::commit_witness(&pp, public_inputs, &witness).unwrap();
+                assert_eq!(
+                    Scheme::check_relation(&pp, &shape, &instance, &witness),
+                    Ok(())
+                );
+                (instance, witness)
+            })
+            .collect();
+
+        let (folded_instance, folded_witness, message) = Scheme::prover(
+            &pp,
+            &pk,
+            &pairs[0].0,
+            &pairs[0].1,
+            &pairs[1].0,
+            &pairs[1].1,
+            rng,
+        )
+        .unwrap();
+        assert_eq!(message.len(), gate_degree - 1);
+        assert!(
+            Scheme::verifier(&pp, &vk, &pairs[0].0, &pairs[1].0, &message).unwrap()
+                == folded_instance
+        );
+        assert_eq!(
+            Scheme::check_relation(&pp, &shape, &folded_instance, &folded_witness),
+            Ok(())
+        );
+
+        let (instances, witnesses): (Vec<_>, Vec<_>) = pairs
+            .into_iter()
+            .skip(2)
+            .chain([(folded_instance, folded_witness)])
+            .unzip();
+        let (folded_instance, folded_witness, message) =
+            Scheme::prover_many(&pp, &pk, &instances, &witnesses, rng).unwrap();
+        assert_eq!(message.len(), 2 * (gate_degree - 1));
+        assert!(Scheme::verifier_many(&pp, &vk, &instances, &message).unwrap() == folded_instance);
+        assert_eq!(
+            Scheme::check_relation(&pp, &shape, &folded_instance, &folded_witness),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn converted_plonk_circuits_fold() {
+        fold_converted_circuit(square_plus_five, 2);
+        fold_converted_circuit(fifth_power_plus_five, 5);
+    }
+
+    #[test]
+    fn reordered_entries_and_multisets_give_the_same_shape() {
+        let (one, two) = (Fr::one(), Fr::from(2u64));
+        // z = (w_0, w_1, u, x_0) and the constraint w_0 * w_1 = 2 * x_0
+        let shape = |entries: Vec<(usize, Fr)>, multisets: Vec<Vec<usize>>| {
+            CCSShape::new(
+                1,
+                2,
+                vec![vec![vec![(0, one)]], vec![vec![(1, one)]], vec![entries]],
+                multisets,
+                vec![one, -one],
+            )
+            .unwrap()
+        };
+
+        assert_eq!(
+            shape(vec![(3, two)], vec![vec![0, 1], vec![2]]),
+            shape(
+                vec![(3, one), (0, Fr::zero()), (3, one)],
+                vec![vec![1, 0], vec![2]]
+            )
+        );
+    }
+
+    #[test]
+    fn converted_r1cs_keeps_its_relation() {
+        let rng = &mut test_rng();
+        let info = SetupInfo {
+            number_of_public_inputs: 1,
+            number_of_gates: 2,
+            config: SangriaConfig::default(),
+            poseidon_constants: poseidon_parameters_for_test(),
+        };
+        let pp = Scheme::setup(&info, rng).unwrap();
+        let r1cs =
+            R1CSShape::from_plonk(&square_plus_five(0).circuit(Vec::new()).unwrap(), 1).unwrap();
+        let shape = CCSShape::from_r1cs(&r1cs);
+        assert_eq!(shape.degree(), 2);
+
+        let (witness, public_inputs) = shape.assignment(&square_plus_five(3)).unwrap();
+        let mut witness =
+            RelaxedR1CSWitness::new(witness, shape.number_of_constraints(), Fr::one());
+        let instance = Scheme::commit_witness(&pp, public_inputs.clone(), &witness).unwrap();
+        assert_eq!(
+            Scheme::check_relation(&pp, &shape, &instance, &witness),
+            Ok(())
+        );
+
+        witness = RelaxedR1CSWitness::new(
+            witness
+                .witness()
+                .iter()
+                .map(|&variable| variable + Fr::one())
+                .collect(),
+            shape.number_of_constraints(),
+            Fr::one(),
+        );
+        let instance = Scheme::commit_witness(&pp, public_inputs, &witness).unwrap();
+        assert_eq!(
+            Scheme::check_relation(&pp, &shape, &instance, &witness),
+            Err(SangriaError::InvalidWitness)
+        );
+    }
+}
@@ -0,0 +1,311 @@
+use ark_crypto_primitives::sponge::Absorb;
+use ark_ff::PrimeField;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+use ark_std::rand::{rngs::StdRng, Rng, SeedableRng};
+use chacha20poly1305::{
+    aead::{Aead, Payload},
+    KeyInit, XChaCha20Poly1305, XNonce,
+};
+use zeroize::Zeroizing;
+
+use crate::{
+    folding_scheme::FoldingCommitmentConfig, ivc::ProverKey, BatchedIVCProof, PLONKStepCircuit,
+    Sangria, SangriaError,
+};
+
+/// A snapshot of a long-running IVC computation, from which proving can resume after a crash: the
+/// current state, the proof of the steps leading to it (the step instances, the running instance
+/// and the running witness) and the seed of the randomness of the next steps. The witness and the
+/// seed are secret, so a checkpoint is only written to disk sealed under a key of the caller with
+/// [`Checkpoint::seal`] and has no plain serialization; writing it to a temporary file renamed
+/// over the previous checkpoint keeps a complete checkpoint on disk at all times.
+pub struct Checkpoint<F: PrimeField, Comm: FoldingCommitmentConfig<F>, S> {
+    state: S,
+    proof: Option<BatchedIVCProof<F, Comm>>,
+    rng_seed: Zeroizing<[u8; 32]>,
+}
+
+impl<F: PrimeField, Comm: FoldingCommitmentConfig<F>, S> Checkpoint<F, Comm, S> {
+    /// Returns the index of the next step, i.e. the number of steps proved, which is also the
+    /// index of the next witness to prove.
+    pub fn step_index(&self) -> usize {
+        self.proof
+            .as_ref()
+            .map_or(0, BatchedIVCProof::number_of_steps)
+    }
+
+    /// Returns the state of the computation.
+    pub fn state(&self) -> &S {
+        &self.state
+    }
+
+    /// Returns the generator of the randomness of the next steps. A prover that goes on proving
+    /// after writing the checkpoint should draw from it, so that the steps it proves are the ones
+    /// a prover resuming from the checkpoint would prove.
+    pub fn rng(&self) -> StdRng {
+        StdRng::from_seed(*self.rng_seed)
+    }
+}
+
+/// The length of the nonce drawn for every sealed checkpoint.
+const SEAL_NONCE_BYTES: usize = 24;
+
+/// The associated data authenticated with every sealed checkpoint, binding the ciphertext to this
+/// format.
+const SEAL_ASSOCIATED_DATA: &[u8] = b"sangria_impl checkpoint v2";
+
+impl<F, Comm, S> Checkpoint<F, Comm, S>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+    S: CanonicalSerialize,
+{
+    /// Encrypts and authenticates the checkpoint under the caller's `key` with XChaCha20-Poly1305,
+    /// so that the running witness and the seed it holds are never written in plaintext. The
+    /// sealed bytes are a fresh 24-byte nonce drawn from `rng` followed by the ciphertext of the
+    /// compressed serialization of the checkpoint and its tag; [`Checkpoint::open`] reads them
+    /// back. The plaintext is zeroized once encrypted.
+    pub fn seal<R: Rng>(&self, key: &[u8; 32], rng: &mut R) -> Result<Vec<u8>, SangriaError> {
+        let nonce: [u8; SEAL_NONCE_BYTES] = rng.gen();
+
+        let mut plaintext = Zeroizing::new(Vec::new());
+        self.state
+            .serialize_compressed(&mut *plaintext)
+            .and_then(|_| self.proof.serialize_compressed(&mut *plaintext))
+            .and_then(|_| self.rng_seed.serialize_compressed(&mut *plaintext))
+            .map_err(|_| SangriaError::SealedCheckpoint)?;
+        let ciphertext = XChaCha20Poly1305::new(key.into())
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: &plaintext,
+                    aad: SEAL_ASSOCIATED_DATA,
+                },
+            )
+            .map_err(|_| SangriaError::SealedCheckpoint)?;
+
+        Ok([&nonce[..], &ciphertext].concat())
+    }
+}
+
+impl<F, Comm, S> Checkpoint<F, Comm, S>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+    S: CanonicalDeserialize,
+{
+    /// Reads back a checkpoint sealed with [`Checkpoint::seal`] under `key`. Returns
+    /// [`SangriaError::SealedCheckpoint`] if the tag does not authenticate the bytes under the
+    /// key, in which case nothing is decrypted, or if the decrypted bytes do not decode. The
+    /// decrypted plaintext is zeroized once decoded.
+    pub fn open(key: &[u8; 32], sealed: &[u8]) -> Result<Self, SangriaError> {
+        if sealed.len() < SEAL_NONCE_BYTES {
+            return Err(SangriaError::SealedCheckpoint);
+        }
+        let (nonce, ciphertext) = sealed.split_at(SEAL_NONCE_BYTES);
+
+        let plaintext = Zeroizing::new(
+            XChaCha20Poly1305::new(key.into())
+                .decrypt(
+                    XNonce::from_slice(nonce),
+                    Payload {
+                        msg: ciphertext,
+                        aad: SEAL_ASSOCIATED_DATA,
+                    },
+                )
+                .map_err(|_| SangriaError::SealedCheckpoint)?,
+        );
+        let mut reader = &plaintext[..];
+        let decode = |reader: &mut &[u8]| {
+            Ok::<_, SerializationError>(Self {
+                state: S::deserialize_compressed(&mut *reader)?,
+                proof: Option::deserialize_compressed(&mut *reader)?,
+                rng_seed: Zeroizing::new(<[u8; 32]>::deserialize_compressed(&mut *reader)?),
+            })
+        };
+
+        decode(&mut reader).map_err(|_| SangriaError::SealedCheckpoint)
+    }
+}
+
+impl<Comm> Sangria<Comm> {
+    /// Captures the computation after the step that led to `state` with `proof`, seeding the
+    /// randomness of the next steps from `rng`. The prover should go on with
+    /// [`Checkpoint::rng`] rather than `rng`.
+    pub fn checkpoint<F, S, R>(
+        state: &S,
+        proof: Option<&BatchedIVCProof<F, Comm>>,
+        rng: &mut R,
+    ) -> Checkpoint<F, Comm, S>
+    where
+        F: PrimeField,
+        Comm: FoldingCommitmentConfig<F>,
+        S: Clone,
+        R: Rng,
+    {
+        Checkpoint {
+            state: state.clone(),
+            proof: proof.cloned(),
+            rng_seed: Zeroizing::new(rng.gen()),
+        }
+    }
+
+    /// Resumes the computation from `checkpoint`, returning its state, proof and the generator of
+    /// the randomness of the next steps, to be passed to [`Sangria::prove_step`](crate::Sangria::prove_step). Returns
+    /// an error if the state of the checkpoint is not the one its proof ends in (or the origin
+    /// state if no step was proved), e.g. if the checkpoint was taken from another computation.
+    #[allow(clippy::type_complexity)]
+    pub fn resume<F, SC>(
+        prover_key: &ProverKey<F, Comm, SC>,
+        origin_state: &SC::State,
+        checkpoint: Checkpoint<F, Comm, SC::State>,
+    ) -> Result<(SC::State, Option<BatchedIVCProof<F, Comm>>, StdRng), SangriaError>
+    where
+        F: PrimeField + Absorb,
+        Comm: FoldingCommitmentConfig<F>,
+        SC: PLONKStepCircuit<F>,
+    {
+        let step_circuit = prover_key.augmented_circuit.step_circuit();
+        let state = step_circuit.encode_state(&checkpoint.state);
+        let proved_state = match &checkpoint.proof {
+            None => step_circuit.encode_state(origin_state),
+            Some(proof) => proof.last_state(state.len())?,
+        };
+        if proved_state != state {
+            return Err(SangriaError::PublicInputMismatch);
+        }
+
+        let rng = checkpoint.rng();
+        Ok((checkpoint.state, checkpoint.proof, rng))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Checkpoint, SEAL_NONCE_BYTES};
+    use crate::{
+        ivc::{ProverKey, VerifierKey},
+        utils::{
+            circuits::Counter, inner_product::InnerProductConfig,
+            poseidon::poseidon_parameters_for_test,
+        },
+        BatchedIVCProof, Sangria, SangriaConfig, SangriaError, SetupInfo,
+    };
+    use ark_bn254::Fr;
+    use ark_ff::Zero;
+    use ark_serialize::CanonicalSerialize;
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+    type Scheme = Sangria<InnerProductConfig>;
+    type Proof = Option<BatchedIVCProof<Fr, InnerProductConfig>>;
+
+    fn keys(
+        rng: &mut StdRng,
+    ) -> (
+        ProverKey<Fr, InnerProductConfig, Counter>,
+        VerifierKey<Fr, InnerProductConfig, Counter>,
+    ) {
+        let info = SetupInfo {
+            number_of_public_inputs: 2,
+            number_of_gates: 2,
+            config: SangriaConfig::default(),
+            poseidon_constants: poseidon_parameters_for_test(),
+        };
+        let public_parameters = Scheme::setup(&info, rng).unwrap();
+        Scheme::encode(&public_parameters, &Counter, rng).unwrap()
+    }
+
+    /// Proves one step per increment of the counter from `state`, drawing from `rng`.
+    fn prove(
+        pk: &ProverKey<Fr, InnerProductConfig, Counter>,
+        state: Fr,
+        proof: Proof,
+        increments: &[u64],
+        rng: &mut StdRng,
+    ) -> (Fr, Proof) {
+        increments
+            .iter()
+            .fold((state, proof), |(state, proof), &increment| {
+                Scheme::prove_step(pk, &Fr::zero(), state, proof, &Fr::from(increment), rng)
+                    .map(|(state, proof)| (state, Some(proof)))
+                    .unwrap()
+            })
+    }
+
+    fn encode(proof: &Proof) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        proof.serialize_compressed(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn resumed_computations_prove_the_same_steps() {
+        let rng = &mut StdRng::seed_from_u64(0);
+        let (pk, vk) = keys(rng);
+        let origin = Fr::zero();
+        let key = [7; 32];
+
+        let (state, proof) = prove(&pk, origin, None, &[1, 2], rng);
+        let checkpoint = Scheme::checkpoint(&state, proof.as_ref(), rng);
+        assert_eq!(checkpoint.step_index(), 2);
+        let sealed = checkpoint.seal(&key, rng).unwrap();
+        let (_, uninterrupted) = prove(&pk, state, proof, &[3, 4], &mut checkpoint.rng());
+
+        let checkpoint = Checkpoint::<Fr, InnerProductConfig, Fr>::open(&key, &sealed).unwrap();
+        let (state, proof, mut resumed_rng) =
+            Scheme::resume::<Fr, Counter>(&pk, &origin, checkpoint).unwrap();
+        let (state, resumed) = prove(&pk, state, proof, &[3, 4], &mut resumed_rng);
+
+        assert_eq!(state, Fr::from(10u64));
+        assert_eq!(encode(&resumed), encode(&uninterrupted));
+        assert_eq!(Scheme::verify(&vk, &origin, state, resumed), Ok(()));
+
+        // a checkpoint whose state is not the one its proof ends in
+        let wrong_state = Scheme::checkpoint(&(state + state), uninterrupted.as_ref(), rng);
+        assert!(matches!(
+            Scheme::resume::<Fr, Counter>(&pk, &origin, wrong_state),
+            Err(SangriaError::PublicInputMismatch)
+        ));
+    }
+
+    #[test]
+    fn sealed_checkpoints_open_under_their_key_only() {
+        let rng = &mut StdRng::seed_from_u64(1);
+        let (pk, _) = keys(rng);
+        let (state, proof) = prove(&pk, Fr::zero(), None, &[1, 2], rng);
+        let checkpoint = Scheme::checkpoint(&state, proof.as_ref(), rng);
+        let key = [7; 32];
+
+        let sealed = checkpoint.seal(&key, rng).unwrap();
+        // neither the witness nor the seed is written in plaintext
+        let proof_bytes = encode(&proof);
+        assert!(!sealed
+            .windows(32)
+            .any(|window| window == &checkpoint.rng_seed[..]
+                || proof_bytes.windows(32).any(|part| part == window)));
+        // every seal draws a fresh nonce
+        assert_ne!(
+            &sealed[..SEAL_NONCE_BYTES],
+            &checkpoint.seal(&key, rng).unwrap()[..SEAL_NONCE_BYTES]
+        );
+
+        let opened = Checkpoint::<Fr, InnerProductConfig, Fr>::open(&key, &sealed).unwrap();
+        assert_eq!(opened.state(), checkpoint.state());
+        assert_eq!(encode(&opened.proof), encode(&proof));
+        assert_eq!(*opened.rng_seed, *checkpoint.rng_seed);
+
+        let mut tampered = sealed.clone();
+        tampered[SEAL_NONCE_BYTES] ^= 1;
+        for (key, sealed) in [
+            ([8; 32], &sealed[..]),
+            (key, &tampered[..]),
+            (key, &sealed[..40]),
+            (key, &sealed[..10]),
+        ] {
+            assert!(matches!(
+                Checkpoint::<Fr, InnerProductConfig, Fr>::open(&key, sealed),
+                Err(SangriaError::SealedCheckpoint)
+            ));
+        }
+    }
+}
@@ -0,0 +1,299 @@
+use ark_ff::PrimeField;
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, SerializationError, Valid, Validate,
+};
+use ark_std::io::Read;
+
+use crate::{serialization::deserialize_bounded_vec, SangriaError};
+
+/// The size in bits of the folding challenges recommended by the Sangria paper.
+pub const DEFAULT_CHALLENGE_BITS: usize = 128;
+
+/// The domain separator used by the default configuration.
+pub const DEFAULT_DOMAIN_SEPARATOR: &[u8] = b"sangria";
+
+/// The degree of the PLONK gate equation, whose power gate `q_P a^2` is a square.
+pub const DEFAULT_GATE_DEGREE: usize = 2;
+
+/// The maximum length in bytes of a domain separator accepted when deserializing a configuration.
+pub const MAX_DOMAIN_SEPARATOR_LEN: usize = 256;
+
+/// The maximum length in bytes of an application identifier accepted when deserializing a
+/// configuration.
+pub const MAX_APPLICATION_ID_LEN: usize = 256;
+
+/// Configuration knobs for a Sangria deployment. The configuration is serializable so that it can be
+/// pinned alongside the public parameters of a deployment.
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize)]
+pub struct SangriaConfig {
+    /// Bytes absorbed at the start of every transcript to separate deployments.
+    pub domain_separator: Vec<u8>,
+    /// The identifier of the application, absorbed with the rest of the configuration into every
+    /// transcript and, with [`crate::Sangria::io_hash`], into instance hashes, so that two
+    /// deployments of the same circuit (e.g. a testnet and a mainnet) never accept each other's
+    /// proofs. Empty unless set with [`SangriaConfig::with_application_id`].
+    pub application_id: Vec<u8>,
+    /// The size in bits of the folding challenges.
+    pub challenge_bits: usize,
+    /// Whether commitments are blinded and compressed proofs are zero-knowledge: the compression
+    /// folds in a random satisfying instance, masks its sumchecks and opens every commitment with
+    /// a hiding opening (see [`crate::hyperplonk`]). Uncompressed IVC proofs carry the folded
+    /// witness, so they hide nothing either way.
+    pub zero_knowledge: bool,
+    /// The degree `d` of the gate equation, which raises the left wire to the power `d` in the power
+    /// gate `q_P a^d`. Folding costs `d - 1` cross term commitments.
+    pub gate_degree: usize,
+}
+
+impl Default for SangriaConfig {
+    fn default() -> Self {
+        Self {
+            domain_separator: DEFAULT_DOMAIN_SEPARATOR.to_vec(),
+            application_id: Vec::new(),
+            challenge_bits: DEFAULT_CHALLENGE_BITS,
+            zero_knowledge: true,
+            gate_degree: DEFAULT_GATE_DEGREE,
+        }
+    }
+}
+
+impl Valid for SangriaConfig {
+    fn check(&self) -> Result<(), SerializationError> {
+        Ok(())
+    }
+}
+
+impl CanonicalDeserialize for SangriaConfig {
+    /// Deserializes a configuration, rejecting domain separators longer than
+    /// [`MAX_DOMAIN_SEPARATOR_LEN`] and application identifiers longer than
+    /// [`MAX_APPLICATION_ID_LEN`] before reading them.
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        Ok(Self {
+            domain_separator: deserialize_bounded_vec(
+                &mut reader,
+                MAX_DOMAIN_SEPARATOR_LEN,
+                compress,
+                validate,
+            )?,
+            application_id: deserialize_bounded_vec(
+                &mut reader,
+                MAX_APPLICATION_ID_LEN,
+                compress,
+                validate,
+            )?,
+            challenge_bits: usize::deserialize_with_mode(&mut reader, compress, validate)?,
+            zero_knowledge: bool::deserialize_with_mode(&mut reader, compress, validate)?,
+            gate_degree: usize::deserialize_with_mode(&mut reader, compress, validate)?,
+        })
+    }
+}
+
+/// A predefined configuration for a kind of deployment, so that a deployment can be set up soundly
+/// without tuning every knob. The curve, the transcript and the compression backend are types
+/// rather than knobs of the configuration, so each profile also documents the ones it is meant to
+/// be used with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Profile {
+    /// For tests and local development: 64-bit challenges and no blinding, which makes folding
+    /// cheaper but is neither sound enough nor zero-knowledge for production. Any commitment scheme
+    /// and transcript can be used.
+    DevFast,
+    /// For production: 128-bit challenges and blinded commitments, with Pedersen commitments over a
+    /// curve with a 128-bit security level (e.g. BN254 or BLS12-381) and the Poseidon transcript.
+    /// Pedersen commitments have no inner-product openings, so proofs are not compressed.
+    Prod128Bit,
+    /// For proofs verified by Ethereum smart contracts: as [`Profile::Prod128Bit`], with Pedersen
+    /// commitments over BN254 (which has precompiles), the [`crate::KeccakTranscript`] and field
+    /// elements hashed in their [canonical encoding](crate::fe_to_bytes_canonical).
+    Evm,
+}
+
+impl Profile {
+    /// Every profile.
+    pub const ALL: [Self; 3] = [Self::DevFast, Self::Prod128Bit, Self::Evm];
+
+    /// Returns the name of the profile: `dev-fast`, `prod-128bit` or `evm`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::DevFast => "dev-fast",
+            Self::Prod128Bit => "prod-128bit",
+            Self::Evm => "evm",
+        }
+    }
+
+    /// Returns the profile named `name`, or an error if there is none.
+    pub fn from_name(name: &str) -> Result<Self, SangriaError> {
+        Self::ALL
+            .into_iter()
+            .find(|profile| profile.name() == name)
+            .ok_or(SangriaError::InvalidConfig("unknown profile"))
+    }
+}
+
+impl SangriaConfig {
+    /// Returns the configuration of `profile`. Each profile has its own domain separator, so that
+    /// proofs of one profile are not accepted under another.
+    pub fn profile(profile: Profile) -> Self {
+        let (domain_separator, challenge_bits, zero_knowledge): (&[u8], _, _) = match profile {
+            Profile::DevFast => (b"sangria-dev-fast", 64, false),
+            Profile::Prod128Bit => (DEFAULT_DOMAIN_SEPARATOR, DEFAULT_CHALLENGE_BITS, true),
+            Profile::Evm => (b"sangria-evm", DEFAULT_CHALLENGE_BITS, true),
+        };
+
+        Self {
+            domain_separator: domain_separator.to_vec(),
+            application_id: Vec::new(),
+            challenge_bits,
+            zero_knowledge,
+            gate_degree: DEFAULT_GATE_DEGREE,
+        }
+    }
+
+    /// Returns the configuration with the identifier of the application registered, e.g.
+    /// `my-rollup/mainnet`.
+    pub fn with_application_id(self, application_id: &str) -> Self {
+        Self {
+            application_id: application_id.as_bytes().to_vec(),
+            ..self
+        }
+    }
+
+    /// Checks that the configuration can be used over the field `F` or returns an error.
+    pub fn validate<F: PrimeField>(&self) -> Result<(), SangriaError> {
+        if self.domain_separator.is_empty() {
+            return Err(SangriaError::Transcript(
+                "the domain separator must not be empty",
+            ));
+        }
+
+        if self.challenge_bits == 0 || self.challenge_bits >= F::MODULUS_BIT_SIZE as usize {
+            return Err(SangriaError::ChallengeSize {
+                bits: self.challenge_bits,
+                field_bits: F::MODULUS_BIT_SIZE as usize,
+            });
+        }
+
+        if self.gate_degree < 2 {
+            return Err(SangriaError::InvalidConfig(
+                "the gate degree must be at least two",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Profile, SangriaConfig, MAX_APPLICATION_ID_LEN, MAX_DOMAIN_SEPARATOR_LEN};
+    use crate::SangriaError;
+    use ark_bn254::Fr;
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+
+    #[test]
+    fn default_config_is_valid() {
+        assert_eq!(SangriaConfig::default().validate::<Fr>(), Ok(()));
+    }
+
+    #[test]
+    fn profiles_are_valid_and_distinct() {
+        for profile in Profile::ALL {
+            let config = SangriaConfig::profile(profile);
+            assert_eq!(config.validate::<Fr>(), Ok(()));
+            assert_eq!(Profile::from_name(profile.name()), Ok(profile));
+
+            for other in Profile::ALL.into_iter().filter(|&other| other != profile) {
+                assert_ne!(
+                    config.domain_separator,
+                    SangriaConfig::profile(other).domain_separator
+                );
+            }
+        }
+
+        assert_eq!(
+            SangriaConfig::profile(Profile::Prod128Bit),
+            SangriaConfig::default()
+        );
+        assert!(Profile::from_name("prod").is_err());
+    }
+
+    #[test]
+    fn invalid_configs_are_rejected() {
+        let empty_separator = SangriaConfig {
+            domain_separator: vec![],
+            ..Default::default()
+        };
+        let no_challenge = SangriaConfig {
+            challenge_bits: 0,
+            ..Default::default()
+        };
+        let oversized_challenge = SangriaConfig {
+            challenge_bits: 254,
+            ..Default::default()
+        };
+        let linear_gates = SangriaConfig {
+            gate_degree: 1,
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            empty_separator.validate::<Fr>(),
+            Err(SangriaError::Transcript(_))
+        ));
+        assert!(matches!(
+            linear_gates.validate::<Fr>(),
+            Err(SangriaError::InvalidConfig(_))
+        ));
+        for (config, bits) in [(no_challenge, 0), (oversized_challenge, 254)] {
+            assert_eq!(
+                config.validate::<Fr>(),
+                Err(SangriaError::ChallengeSize {
+                    bits,
+                    field_bits: 254
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn config_serialization_round_trip() {
+        let config = SangriaConfig {
+            domain_separator: b"testnet".to_vec(),
+            application_id: b"my-rollup".to_vec(),
+            challenge_bits: 64,
+            zero_knowledge: false,
+            gate_degree: 5,
+        };
+
+        let mut bytes = Vec::new();
+        config.serialize_compressed(&mut bytes).unwrap();
+
+        assert_eq!(
+            SangriaConfig::deserialize_compressed(&bytes[..]).unwrap(),
+            config
+        );
+    }
+
+    #[test]
+    fn oversized_domain_separator_is_rejected() {
+        let oversized_separator = SangriaConfig {
+            domain_separator: vec![0; MAX_DOMAIN_SEPARATOR_LEN + 1],
+            ..Default::default()
+        };
+        let oversized_application_id = SangriaConfig {
+            application_id: vec![0; MAX_APPLICATION_ID_LEN + 1],
+            ..Default::default()
+        };
+
+        for config in [oversized_separator, oversized_application_id] {
+            let mut bytes = Vec::new();
+            config.serialize_compressed(&mut bytes).unwrap();
+
+            assert!(SangriaConfig::deserialize_compressed(&bytes[..]).is_err());
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use ark_ff::PrimeField;
+
+use crate::{
+    relaxed_plonk::NUMBER_OF_WIRES, trace::NUMBER_OF_SELECTORS, RowTag, SangriaError, TraceTable,
+};
+
+/// The field multiplications needed to compute one row of the cross term of the quadratic gate
+/// equation: two linear parts of three products each, their two scalings, the three products of the
+/// multiplication term and the two of the power and constant terms.
+const CROSS_TERM_MULTIPLICATIONS_PER_ROW: usize = 15;
+
+/// The cost of a gadget, as measured by [`CostModel::measure`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GadgetCost {
+    /// The number of gate rows.
+    pub gate_rows: usize,
+    /// The number of public input and output rows.
+    pub instance_rows: usize,
+    /// The number of copy constraints.
+    pub copy_constraints: usize,
+    /// The number of gate rows enabling each selector, indexed by the `*_SELECTOR_INDEX` constants.
+    pub selector_usage: [usize; NUMBER_OF_SELECTORS],
+    /// The number of field elements the gadget adds to the commitments of every fold: its witness
+    /// columns and its entries of the cross term, for the quadratic gate equation.
+    pub committed_elements: usize,
+    /// The number of field multiplications the gadget adds to the cross term of every fold, for the
+    /// quadratic gate equation.
+    pub cross_term_multiplications: usize,
+}
+
+impl GadgetCost {
+    /// Returns the total number of rows of the gadget.
+    pub fn rows(&self) -> usize {
+        self.gate_rows + self.instance_rows
+    }
+}
+
+/// Measures the cost of gadgets, so that gadget authors can compare implementations (e.g. a range
+/// check by lookup or by bit decomposition) and check them against a row budget. A gadget is a
+/// closure laying out rows of a [`TraceTable`]; it is run on an empty table and only its rows and
+/// copy constraints are counted, so the wire values do not need to be consistent.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CostModel {
+    row_budget: Option<usize>,
+}
+
+impl CostModel {
+    /// Creates a cost model without row budget.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the number of rows gadgets may use, e.g. the length supported by the commit keys.
+    pub fn with_row_budget(self, rows: usize) -> Self {
+        Self {
+            row_budget: Some(rows),
+        }
+    }
+
+    /// Runs `gadget` on an empty trace table and reports its cost, or returns the error of the
+    /// gadget.
+    pub fn measure<F, G>(&self, gadget: G) -> Result<GadgetCost, SangriaError>
+    where
+        F: PrimeField,
+        G: FnOnce(&mut TraceTable<F>) -> Result<(), SangriaError>,
+    {
+        let mut trace = TraceTable::new();
+        gadget(&mut trace)?;
+
+        let mut cost = GadgetCost {
+            copy_constraints: trace.number_of_copies(),
+            ..Default::default()
+        };
+        for tag in trace.tags() {
+            match tag {
+                RowTag::Gate(selectors) => {
+                    cost.gate_rows += 1;
+                    for (usage, selector) in cost.selector_usage.iter_mut().zip(selectors) {
+                        if !selector.is_zero() {
+                            *usage += 1;
+                        }
+                    }
+                }
+                RowTag::PublicInput | RowTag::Output => cost.instance_rows += 1,
+            }
+        }
+        cost.committed_elements = NUMBER_OF_WIRES * cost.gate_rows + cost.rows();
+        cost.cross_term_multiplications = CROSS_TERM_MULTIPLICATIONS_PER_ROW * cost.rows();
+
+        Ok(cost)
+    }
+
+    /// Checks that `cost` fits in the row budget, or returns an error with the rows needed and
+    /// available. Any cost fits a model without budget.
+    pub fn check_budget(&self, cost: &GadgetCost) -> Result<(), SangriaError> {
+        match self.row_budget {
+            Some(available) if cost.rows() > available => Err(SangriaError::ParametersTooSmall {
+                needed: cost.rows(),
+                available,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CostModel, CROSS_TERM_MULTIPLICATIONS_PER_ROW};
+    use crate::{
+        RowTag, SangriaError, TraceTable, CONSTANT_SELECTOR_INDEX, LEFT_SELECTOR_INDEX,
+        MULTIPLICATION_SELECTOR_INDEX, OUTPUT_SELECTOR_INDEX, RIGHT_SELECTOR_INDEX,
+    };
+    use ark_bn254::Fr;
+    use ark_ff::{One, Zero};
+
+    /// Range checks a 4-bit value by decomposing it: one booleanity gate per bit and three
+    /// additions recomposing the value.
+    fn decomposition_range_check(trace: &mut TraceTable<Fr>) -> Result<(), SangriaError> {
+        let mut boolean = [Fr::zero(); 6];
+        boolean[MULTIPLICATION_SELECTOR_INDEX] = Fr::one();
+        boolean[OUTPUT_SELECTOR_INDEX] = -Fr::one();
+        let mut addition = [Fr::zero(); 6];
+        addition[LEFT_SELECTOR_INDEX] = Fr::one();
+        addition[RIGHT_SELECTOR_INDEX] = Fr::from(2u64);
+        addition[OUTPUT_SELECTOR_INDEX] = -Fr::one();
+
+        for bit in 0..4 {
+            trace.push_row(RowTag::Gate(boolean), [Fr::zero(); 3]);
+            trace.copy((0, bit), (1, bit));
+            trace.copy((0, bit), (2, bit));
+        }
+        for _ in 0..3 {
+            trace.push_row(RowTag::Gate(addition), [Fr::zero(); 3]);
+        }
+
+        Ok(())
+    }
+
+    /// Range checks a value with a single gate against a constant, standing in for a lookup.
+    fn lookup_range_check(trace: &mut TraceTable<Fr>) -> Result<(), SangriaError> {
+        let mut lookup = [Fr::zero(); 6];
+        lookup[LEFT_SELECTOR_INDEX] = Fr::one();
+        lookup[CONSTANT_SELECTOR_INDEX] = -Fr::one();
+        trace.push_row(RowTag::Gate(lookup), [Fr::one(), Fr::zero(), Fr::zero()]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn gadget_costs_are_measured() {
+        let model = CostModel::new();
+        let decomposition = model.measure(decomposition_range_check).unwrap();
+        let lookup = model.measure(lookup_range_check).unwrap();
+
+        assert_eq!(decomposition.rows(), 7);
+        assert_eq!(decomposition.copy_constraints, 8);
+        assert_eq!(decomposition.selector_usage, [3, 3, 7, 4, 0, 0]);
+        assert_eq!(decomposition.committed_elements, 28);
+        assert_eq!(
+            decomposition.cross_term_multiplications,
+            7 * CROSS_TERM_MULTIPLICATIONS_PER_ROW
+        );
+
+        assert_eq!(lookup.rows(), 1);
+        assert_eq!(lookup.selector_usage, [1, 0, 0, 0, 1, 0]);
+        assert!(lookup.committed_elements < decomposition.committed_elements);
+    }
+
+    #[test]
+    fn row_budget_is_enforced() {
+        let model = CostModel::new().with_row_budget(4);
+        let decomposition = model.measure(decomposition_range_check).unwrap();
+
+        assert_eq!(
+            model.check_budget(&decomposition),
+            Err(SangriaError::ParametersTooSmall {
+                needed: 7,
+                available: 4
+            })
+        );
+        assert_eq!(
+            model.check_budget(&model.measure(lookup_range_check).unwrap()),
+            Ok(())
+        );
+        assert_eq!(CostModel::new().check_budget(&decomposition), Ok(()));
+        assert_eq!(
+            model.measure(|_: &mut TraceTable<Fr>| Err(SangriaError::InvalidWitness)),
+            Err(SangriaError::InvalidWitness)
+        );
+    }
+}
@@ -0,0 +1,135 @@
+//! A decider configuration for Sangria over a pairing-friendly curve: the relaxed PLONK instances
+//! are committed to with [multilinear KZG commitments](crate::kzg::MultilinearKZG), so that the
+//! HyperPlonk proof produced by
+//! [`Sangria::compress`](crate::Sangria::compress) proves that the
+//! final running instance is satisfied with a logarithmic number of group elements: the sumchecks
+//! of the gate and permutation arguments, and one KZG opening (a sumcheck and a quotient commitment
+//! per variable) for each evaluation of a committed column.
+//!
+//! The verifier defers the pairing equations of the openings to a
+//! [`PairingAccumulator`](crate::kzg::PairingAccumulator) and checks them at the end, so it computes
+//! a constant number of pairings: `n + 1` for each of the commit keys of the witness, the slack and
+//! the permutation check with a secret point of `n` coordinates, whatever the number of openings.
+//!
+//! This still does not meet the goal of a decider verified in constant time. The SNARK is succinct,
+//! but the verifier checks the openings against public vectors it builds itself and evaluates the
+//! selectors and the copy constraint permutation itself, in time linear in the size of the
+//! circuit. Committing to the selectors and the permutation as preprocessed polynomials and
+//! opening them at the points of the sumchecks, as HyperPlonk does, is not implemented. The
+//! verifier also re-folds the steps of the [batched proof](crate::BatchedIVCProof) it compresses,
+//! and KZG commitments cannot be folded in the circuit of a recursive step, which only has one
+//! curve.
+//!
+//! For a verifier running on Ethereum, the openings take their challenges from a
+//! [`KeccakTranscript`](crate::KeccakTranscript) with `KZGConfig<E, KeccakTranscript>`, and the
+//! HyperPlonk proof from another one with [`Sangria::compress_with`](crate::Sangria::compress_with).
+
+use crate::{
+    folding_scheme::SingleCommitment, kzg::MultilinearKZG, Blake3Transcript,
+    CompressedBatchedIVCProof,
+};
+
+use ark_ec::pairing::Pairing;
+
+/// Commits to the witness columns and the slack vector of relaxed PLONK instances with multilinear
+/// KZG commitments over `E`, opened with transcripts of type `S`, so that `Sangria<KZGConfig<E>>`
+/// compresses proofs into decider proofs of logarithmic size.
+pub type KZGConfig<E, S = Blake3Transcript> = SingleCommitment<MultilinearKZG<E, S>>;
+
+/// A compressed batched proof of `Sangria<KZGConfig<E, S>>`, whose SNARK is the decider proof of
+/// the final running instance. Verifying it takes time linear in the size of the circuit and in the
+/// number of steps.
+pub type DeciderProof<E, S = Blake3Transcript> =
+    CompressedBatchedIVCProof<<E as Pairing>::ScalarField, KZGConfig<E, S>>;
+
+#[cfg(test)]
+mod tests {
+    use super::{DeciderProof, KZGConfig};
+    use crate::{
+        utils::{circuits::Counter, poseidon::poseidon_parameters_for_test},
+        KeccakTranscript, Sangria, SangriaConfig, SangriaError, SetupInfo,
+    };
+    use ark_bn254::{Bn254, Fr};
+    use ark_ff::One;
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+    use ark_std::test_rng;
+
+    type Scheme = Sangria<KZGConfig<Bn254>>;
+
+    #[test]
+    fn decider_proofs_verify() {
+        let rng = &mut test_rng();
+        let info = SetupInfo {
+            number_of_public_inputs: 2,
+            number_of_gates: 2,
+            config: SangriaConfig::default(),
+            poseidon_constants: poseidon_parameters_for_test(),
+        };
+        let public_parameters = Scheme::setup(&info, rng).unwrap();
+        let (pk, vk) = Scheme::encode(&public_parameters, &Counter, rng).unwrap();
+        let origin = Fr::from(5u64);
+
+        let (mut state, mut proof) = (origin, None);
+        for increment in 1..=3u64 {
+            (state, proof) =
+                Scheme::prove_step(&pk, &origin, state, proof, &Fr::from(increment), rng)
+                    .map(|(state, proof)| (state, Some(proof)))
+                    .unwrap();
+        }
+
+        let proof = proof.unwrap();
+        let decide = |state, decider_proof: DeciderProof<Bn254>| {
+            Scheme::verify_compressed(&vk, &origin, state, decider_proof)
+        };
+        let compress = |proof| Scheme::compress(&pk, proof).unwrap();
+
+        assert_eq!(decide(state, compress(proof.clone())), Ok(()));
+
+        let mut bytes = Vec::new();
+        compress(proof.clone())
+            .serialize_compressed(&mut bytes)
+            .unwrap();
+        let read = DeciderProof::<Bn254>::deserialize_compressed(&bytes[..]).unwrap();
+        assert_eq!(decide(state, read), Ok(()));
+        assert_eq!(
+            decide(state + Fr::one(), compress(proof)),
+            Err(SangriaError::InvalidProof)
+        );
+    }
+
+    #[test]
+    fn keccak_decider_proofs_verify() {
+        type KeccakScheme = Sangria<KZGConfig<Bn254, KeccakTranscript>>;
+
+        let rng = &mut test_rng();
+        let info = SetupInfo {
+            number_of_public_inputs: 2,
+            number_of_gates: 2,
+            config: SangriaConfig::default(),
+            poseidon_constants: poseidon_parameters_for_test(),
+        };
+        let public_parameters = KeccakScheme::setup(&info, rng).unwrap();
+        let (pk, vk) = KeccakScheme::encode(&public_parameters, &Counter, rng).unwrap();
+        let origin = Fr::from(5u64);
+        let (state, proof) =
+            KeccakScheme::prove_step(&pk, &origin, origin, None, &Fr::one(), rng).unwrap();
+
+        let compress = |proof| {
+            KeccakScheme::compress_with::<Fr, Counter, KeccakTranscript>(&pk, proof).unwrap()
+        };
+        assert_eq!(
+            KeccakScheme::verify_compressed_with::<Fr, Counter, KeccakTranscript>(
+                &vk,
+                &origin,
+                state,
+                compress(proof.clone()),
+            ),
+            Ok(())
+        );
+        // the Poseidon verifier derives other challenges
+        assert_eq!(
+            KeccakScheme::verify_compressed(&vk, &origin, state, compress(proof),),
+            Err(SangriaError::InvalidProof)
+        );
+    }
+}
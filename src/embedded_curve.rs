@@ -0,0 +1,379 @@
+use ark_ec::{
+    short_weierstrass::{Affine, SWCurveConfig},
+    AffineRepr,
+};
+use ark_ff::{Field, One, PrimeField, Zero};
+use ark_std::marker::PhantomData;
+
+use crate::{
+    nonnative::{assert_zero, decompose},
+    poseidon::{linear_combination, multiplication_gate},
+    RowTag, SangriaError, TraceTable,
+};
+
+/// A wire of a trace, given as (column, row).
+type Wire = (usize, usize);
+
+/// The number of bits of the scalar consumed by every addition of a windowed scalar
+/// multiplication.
+pub const WINDOW_BITS: usize = 2;
+
+/// A point of the short Weierstrass curve `P` with `a = 0`, whose base field is the native field of
+/// the trace, as the commitments of the folding verifier live on such an embedded curve. The point
+/// is held in homogeneous projective coordinates `(X : Y : Z)` standing for the affine point
+/// `(X / Z, Y / Z)`, with `(0 : 1 : 0)` for the identity, so that the complete addition formulas of
+/// Renes, Costello and Batina need no case distinction.
+pub struct EmbeddedPoint<P: SWCurveConfig> {
+    x: Wire,
+    y: Wire,
+    z: Wire,
+    _curve: PhantomData<P>,
+}
+
+impl<P: SWCurveConfig> Clone for EmbeddedPoint<P> {
+    fn clone(&self) -> Self {
+        Self {
+            x: self.x,
+            y: self.y,
+            z: self.z,
+            _curve: PhantomData,
+        }
+    }
+}
+
+impl<P: SWCurveConfig> std::fmt::Debug for EmbeddedPoint<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EmbeddedPoint")
+            .field("x", &self.x)
+            .field("y", &self.y)
+            .field("z", &self.z)
+            .finish()
+    }
+}
+
+impl<P: SWCurveConfig> EmbeddedPoint<P>
+where
+    P::BaseField: PrimeField,
+{
+    /// Returns the wires holding the coordinates `(X, Y, Z)`.
+    pub fn coordinates(&self) -> [Wire; 3] {
+        [self.x, self.y, self.z]
+    }
+
+    /// Allocates `point` in `trace` with `Z` in `{0, 1}`, constrained to be the identity or an
+    /// affine point on the curve.
+    pub fn allocate(
+        trace: &mut TraceTable<P::BaseField>,
+        point: Affine<P>,
+    ) -> Result<Self, SangriaError> {
+        check_curve::<P>()?;
+        let (x, y, z) = match point.xy() {
+            Some((&x, &y)) => (x, y, P::BaseField::one()),
+            None => (
+                P::BaseField::zero(),
+                P::BaseField::one(),
+                P::BaseField::zero(),
+            ),
+        };
+
+        let row = trace.number_of_rows();
+        trace.push_row(
+            RowTag::Gate(Default::default()),
+            [x, y, P::BaseField::zero()],
+        );
+        let (x, y) = ((0, row), (1, row));
+        let z = decompose(trace, z, 1).0[0];
+
+        // the identity is (0 : 1 : 0): x = z x and y - 1 = z (y - 1)
+        let (zero, one) = (P::BaseField::zero(), P::BaseField::one());
+        let zx = multiplication_gate(trace, z, x)?;
+        let difference = linear_combination(trace, &[(one, x), (-one, zx)], zero)?;
+        assert_zero(trace, difference);
+        let zy = multiplication_gate(trace, z, y)?;
+        let difference = linear_combination(trace, &[(one, y), (-one, zy), (one, z)], -one)?;
+        assert_zero(trace, difference);
+
+        // y^2 z = x^3 + b z, which holds for the identity once x = 0
+        let yy = multiplication_gate(trace, y, y)?;
+        let yyz = multiplication_gate(trace, yy, z)?;
+        let xx = multiplication_gate(trace, x, x)?;
+        let xxx = multiplication_gate(trace, xx, x)?;
+        let difference =
+            linear_combination(trace, &[(one, yyz), (-one, xxx), (-P::COEFF_B, z)], zero)?;
+        assert_zero(trace, difference);
+
+        Ok(Self::from_wires(x, y, z))
+    }
+
+    /// Returns the identity `(0 : 1 : 0)`, with constant wires.
+    pub fn identity(trace: &mut TraceTable<P::BaseField>) -> Result<Self, SangriaError> {
+        check_curve::<P>()?;
+        let (zero, one) = (P::BaseField::zero(), P::BaseField::one());
+
+        Ok(Self::from_wires(
+            linear_combination(trace, &[], zero)?,
+            linear_combination(trace, &[], one)?,
+            linear_combination(trace, &[], zero)?,
+        ))
+    }
+
+    /// Returns the affine point held by the coordinate wires of `trace`.
+    pub fn value(&self, trace: &TraceTable<P::BaseField>) -> Result<Affine<P>, SangriaError> {
+        let [x, y, z] = self
+            .coordinates()
+            .map(|(column, row)| trace.wire(column, row));
+        let Some(z_inverse) = z?.inverse() else {
+            return Ok(Affine::identity());
+        };
+
+        Ok(Affine::new_unchecked(x? * z_inverse, y? * z_inverse))
+    }
+
+    /// Adds `self` and `other` with the complete formulas for `a = 0` (algorithm 7 of Renes,
+    /// Costello and Batina), which also double and handle the identity. The sum costs 12
+    /// multiplication gates and 19 addition gates.
+    pub fn add(
+        &self,
+        trace: &mut TraceTable<P::BaseField>,
+        other: &Self,
+    ) -> Result<Self, SangriaError> {
+        let (zero, one) = (P::BaseField::zero(), P::BaseField::one());
+        let b3 = P::COEFF_B.double() + P::COEFF_B;
+        let three = P::BaseField::from(3u64);
+
+        let t0 = multiplication_gate(trace, self.x, other.x)?;
+        let t1 = multiplication_gate(trace, self.y, other.y)?;
+        let t2 = multiplication_gate(trace, self.z, other.z)?;
+
+        // the cross terms X1 Y2 + X2 Y1, Y1 Z2 + Y2 Z1 and X1 Z2 + X2 Z1
+        // as (A1 + B1)(A2 + B2) - A1 A2 - B1 B2, scaled by `scale`
+        let cross_term = |trace: &mut TraceTable<_>,
+                          first: [Wire; 2],
+                          second: [Wire; 2],
+                          squares: [Wire; 2],
+                          scale: P::BaseField| {
+            let left = linear_combination(trace, &[(one, first[0]), (one, first[1])], zero)?;
+            let right = linear_combination(trace, &[(one, second[0]), (one, second[1])], zero)?;
+            let product = multiplication_gate(trace, left, right)?;
+            linear_combination(
+                trace,
+                &[(scale, product), (-scale, squares[0]), (-scale, squares[1])],
+                zero,
+            )
+        };
+        let t3 = cross_term(trace, [self.x, self.y], [other.x, other.y], [t0, t1], one)?;
+        let t4 = cross_term(trace, [self.y, self.z], [other.y, other.z], [t1, t2], one)?;
+        let xz = cross_term(trace, [self.x, self.z], [other.x, other.z], [t0, t2], b3)?;
+
+        let z3 = linear_combination(trace, &[(one, t1), (b3, t2)], zero)?;
+        let t1 = linear_combination(trace, &[(one, t1), (-b3, t2)], zero)?;
+        let t0 = linear_combination(trace, &[(three, t0)], zero)?;
+
+        // X3 = t3 t1 - t4 xz, Y3 = t1 z3 + xz t0 and Z3 = z3 t4 + t0 t3
+        let combination = |trace: &mut TraceTable<_>, first: [Wire; 2], second: [Wire; 2], sign| {
+            let left = multiplication_gate(trace, first[0], first[1])?;
+            let right = multiplication_gate(trace, second[0], second[1])?;
+            linear_combination(trace, &[(one, left), (sign, right)], zero)
+        };
+        let x = combination(trace, [t3, t1], [t4, xz], -one)?;
+        let y = combination(trace, [t1, z3], [xz, t0], one)?;
+        let z = combination(trace, [z3, t4], [t0, t3], one)?;
+
+        Ok(Self::from_wires(x, y, z))
+    }
+
+    /// Multiplies `self` by the value of `scalar`, which is constrained to have at most `bits` bits,
+    /// with windows of [`WINDOW_BITS`] bits: the multiples `0, 1, .., 2^w - 1` of the point are
+    /// tabulated, and for every window after the most significant one, the accumulator is doubled
+    /// `w` times and the multiple selected by the bits of the window is added.
+    pub fn scalar_mul(
+        &self,
+        trace: &mut TraceTable<P::BaseField>,
+        scalar: Wire,
+        bits: usize,
+    ) -> Result<Self, SangriaError> {
+        if bits == 0 {
+            return Err(SangriaError::InvalidTrace(
+                "the scalar must have at least one bit",
+            ));
+        }
+
+        let mut table = vec![Self::identity(trace)?, self.clone()];
+        while table.len() < 1usize << WINDOW_BITS {
+            let multiple = table[table.len() - 1].add(trace, self)?;
+            table.push(multiple);
+        }
+
+        let value = trace.wire(scalar.0, scalar.1)?;
+        let (scalar_bits, recomposed) = decompose(trace, value, bits);
+        trace.copy(recomposed, scalar);
+
+        // the most significant window takes the bits left over by the others
+        let first_window = bits - (bits - 1) / WINDOW_BITS * WINDOW_BITS;
+        let mut accumulator = select(
+            trace,
+            &table[..1 << first_window],
+            &scalar_bits[..first_window],
+        )?;
+        for window in scalar_bits[first_window..].chunks(WINDOW_BITS) {
+            for _ in 0..WINDOW_BITS {
+                accumulator = accumulator.add(trace, &accumulator.clone())?;
+            }
+            let selected = select(trace, &table, window)?;
+            accumulator = accumulator.add(trace, &selected)?;
+        }
+
+        Ok(accumulator)
+    }
+
+    fn from_wires(x: Wire, y: Wire, z: Wire) -> Self {
+        Self {
+            x,
+            y,
+            z,
+            _curve: PhantomData,
+        }
+    }
+}
+
+/// Selects the entry of `table` indexed by `bits`, most significant first, with a multiplexer per
+/// bit and coordinate computing `low + b (high - low)`.
+fn select<P: SWCurveConfig>(
+    trace: &mut TraceTable<P::BaseField>,
+    table: &[EmbeddedPoint<P>],
+    bits: &[Wire],
+) -> Result<EmbeddedPoint<P>, SangriaError>
+where
+    P::BaseField: PrimeField,
+{
+    let (zero, one) = (P::BaseField::zero(), P::BaseField::one());
+    let mut entries: Vec<[Wire; 3]> = table.iter().map(EmbeddedPoint::coordinates).collect();
+    for &bit in bits.iter().rev() {
+        entries = entries
+            .chunks(2)
+            .map(|pair| {
+                let mut selected = pair[0];
+                for (coordinate, (&low, &high)) in
+                    selected.iter_mut().zip(pair[0].iter().zip(&pair[1]))
+                {
+                    let difference = linear_combination(trace, &[(one, high), (-one, low)], zero)?;
+                    let scaled = multiplication_gate(trace, bit, difference)?;
+                    *coordinate = linear_combination(trace, &[(one, low), (one, scaled)], zero)?;
+                }
+
+                Ok(selected)
+            })
+            .collect::<Result<_, SangriaError>>()?;
+    }
+    let [x, y, z] = entries[0];
+
+    Ok(EmbeddedPoint::from_wires(x, y, z))
+}
+
+/// Returns an error unless `a = 0`, as required by the addition formulas.
+fn check_curve<P: SWCurveConfig>() -> Result<(), SangriaError> {
+    if !P::COEFF_A.is_zero() {
+        return Err(SangriaError::InvalidTrace(
+            "embedded curves must have a = 0",
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EmbeddedPoint;
+    use crate::{
+        folding_scheme::{PLONKFoldingScheme, SetupInfo, SingleCommitment},
+        utils::{inner_product::InnerProduct, poseidon::poseidon_parameters_for_test},
+        NonInteractiveFoldingScheme, RowTag, SangriaConfig, SangriaError, TraceTable,
+    };
+    use ark_bn254::{g1::Config, Fq, Fr, G1Affine};
+    use ark_crypto_primitives::sponge::poseidon::PoseidonSponge;
+    use ark_ec::CurveGroup;
+    use ark_ff::{UniformRand, Zero};
+    use ark_std::test_rng;
+
+    type Scheme = PLONKFoldingScheme<Fq, SingleCommitment<InnerProduct<Fq>>, PoseidonSponge<Fq>>;
+    type Point = EmbeddedPoint<Config>;
+
+    /// Closes `trace` with an output row and checks that it satisfies the relation.
+    fn check_relation(
+        mut trace: TraceTable<Fq>,
+        number_of_public_inputs: usize,
+    ) -> Result<(), SangriaError> {
+        let rng = &mut test_rng();
+        trace.push_row(RowTag::Output, [Fq::zero(); 3]);
+        let info = SetupInfo {
+            number_of_public_inputs,
+            number_of_gates: trace.number_of_rows() - number_of_public_inputs - 1,
+            config: SangriaConfig::default(),
+            poseidon_constants: poseidon_parameters_for_test(),
+        };
+        let pp = Scheme::setup(&info, rng).unwrap();
+        let instance_columns = trace.instance_columns();
+        let (circuit, witness) = trace.into_circuit_and_witness(Vec::new(), rng)?;
+        let instance = Scheme::commit_witness(&pp, instance_columns, &witness)?;
+
+        Scheme::check_relation(&pp, &circuit, &instance, &witness)
+    }
+
+    #[test]
+    fn gadgets_compute_the_group_law() {
+        let rng = &mut test_rng();
+        let point = G1Affine::rand(rng);
+        let mut trace = TraceTable::new();
+        trace.push_row(
+            RowTag::PublicInput,
+            [Fq::from(45u64), Fq::zero(), Fq::zero()],
+        );
+        trace.push_row(RowTag::PublicInput, [Fq::zero(); 3]);
+
+        let allocated = Point::allocate(&mut trace, point).unwrap();
+        let negated = Point::allocate(&mut trace, -point).unwrap();
+        let identity = Point::allocate(&mut trace, G1Affine::identity()).unwrap();
+        let cases = [
+            (
+                allocated.add(&mut trace, &allocated).unwrap(),
+                point + point,
+            ),
+            (
+                allocated.add(&mut trace, &negated).unwrap(),
+                G1Affine::identity().into(),
+            ),
+            (identity.add(&mut trace, &allocated).unwrap(), point.into()),
+            (
+                allocated.scalar_mul(&mut trace, (0, 0), 6).unwrap(),
+                point * Fr::from(45u64),
+            ),
+            (
+                allocated.scalar_mul(&mut trace, (0, 1), 3).unwrap(),
+                G1Affine::identity().into(),
+            ),
+        ];
+        for (wires, expected) in cases {
+            assert_eq!(wires.value(&trace), Ok(expected.into_affine()));
+        }
+
+        assert_eq!(check_relation(trace, 2), Ok(()));
+    }
+
+    #[test]
+    fn invalid_points_and_scalars_violate_the_relation() {
+        let rng = &mut test_rng();
+        let off_curve = G1Affine::new_unchecked(Fq::rand(rng), Fq::rand(rng));
+        let mut trace = TraceTable::new();
+        Point::allocate(&mut trace, off_curve).unwrap();
+        assert!(check_relation(trace, 0).is_err());
+
+        let mut trace = TraceTable::new();
+        trace.push_row(
+            RowTag::PublicInput,
+            [Fq::from(8u64), Fq::zero(), Fq::zero()],
+        );
+        let point = Point::allocate(&mut trace, G1Affine::rand(rng)).unwrap();
+        point.scalar_mul(&mut trace, (0, 0), 3).unwrap();
+        assert!(check_relation(trace, 1).is_err());
+    }
+}
@@ -0,0 +1,344 @@
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, SerializationError, Valid, Validate,
+};
+use ark_std::io::{Read, Write};
+
+use crate::{folding_scheme::blake3_digest, SangriaError};
+
+/// The verifier key a compressed proof carries: the key itself, or its
+/// [digest](verifier_key_digest) and a URL the key can be fetched from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EmbeddedVerifierKey<VK> {
+    /// The verifier key.
+    Key(VK),
+    /// The BLAKE3 digest of the canonical serialization of the verifier key, and where to find it.
+    Digest {
+        /// The digest of the verifier key.
+        digest: [u8; 32],
+        /// A URL the verifier key can be fetched from.
+        url: String,
+    },
+}
+
+/// Which verifier key [`SelfContainedProof::verify`] verifies a proof under.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VerifierKeyPolicy {
+    /// The key given by the caller, which must match the key or digest embedded in the proof if
+    /// any.
+    Explicit,
+    /// The key embedded in the proof, which must match the key given by the caller if any. If the
+    /// proof only embeds a digest, the caller must give the key fetched from its URL.
+    Embedded,
+}
+
+/// Returns the BLAKE3 digest of the canonical serialization of `verifier_key`, which identifies it
+/// in a [`SelfContainedProof`].
+pub fn verifier_key_digest<VK: CanonicalSerialize>(verifier_key: &VK) -> [u8; 32] {
+    blake3_digest(verifier_key)
+}
+
+/// A compressed proof together with, optionally, the verifier key it verifies under or its digest,
+/// so that archived proofs are self-contained. The embedded key can be stripped again before the
+/// proof is shipped to verifiers that hold the key.
+///
+/// WARNING: a proof verified under the key it embeds only proves its statement under that key.
+/// Verifiers trusting the embedded key must check it by other means, e.g. against a published
+/// digest.
+pub struct SelfContainedProof<VK, P> {
+    compressed_proof: P,
+    verifier_key: Option<EmbeddedVerifierKey<VK>>,
+}
+
+impl<VK: CanonicalSerialize, P> SelfContainedProof<VK, P> {
+    /// Wraps a compressed proof, without a verifier key.
+    pub fn new(compressed_proof: P) -> Self {
+        Self {
+            compressed_proof,
+            verifier_key: None,
+        }
+    }
+
+    /// Embeds `verifier_key` in the proof, replacing any key or digest embedded before.
+    pub fn embed_key(self, verifier_key: VK) -> Self {
+        Self {
+            verifier_key: Some(EmbeddedVerifierKey::Key(verifier_key)),
+            ..self
+        }
+    }
+
+    /// Embeds the digest of `verifier_key` and the URL it can be fetched from in the proof,
+    /// replacing any key or digest embedded before.
+    pub fn embed_key_digest(self, verifier_key: &VK, url: String) -> Self {
+        Self {
+            verifier_key: Some(EmbeddedVerifierKey::Digest {
+                digest: verifier_key_digest(verifier_key),
+                url,
+            }),
+            ..self
+        }
+    }
+
+    /// Removes the key or digest embedded in the proof.
+    pub fn strip_key(self) -> Self {
+        Self {
+            verifier_key: None,
+            ..self
+        }
+    }
+
+    /// Returns the key or digest embedded in the proof, if any.
+    pub fn embedded_key(&self) -> Option<&EmbeddedVerifierKey<VK>> {
+        self.verifier_key.as_ref()
+    }
+
+    /// Returns the compressed proof.
+    pub fn compressed_proof(&self) -> &P {
+        &self.compressed_proof
+    }
+
+    /// Returns the compressed proof, dropping the embedded key.
+    pub fn into_compressed_proof(self) -> P {
+        self.compressed_proof
+    }
+
+    /// Picks the verifier key of the proof under `policy`, with `verifier_key` the key given by
+    /// the caller, and verifies the compressed proof under it with `verify_compressed`, e.g.
+    /// [`Sangria::verify_compressed`](crate::Sangria::verify_compressed).
+    /// Returns [`SangriaError::MissingVerifierKey`] if the policy needs a key that is neither given
+    /// nor embedded, and [`SangriaError::VerifierKeyMismatch`] if the given key does not match the
+    /// embedded key or digest.
+    pub fn verify<Verify>(
+        self,
+        verifier_key: Option<&VK>,
+        policy: VerifierKeyPolicy,
+        verify_compressed: Verify,
+    ) -> Result<(), SangriaError>
+    where
+        Verify: FnOnce(&VK, P) -> Result<(), SangriaError>,
+    {
+        let embedded_digest = match &self.verifier_key {
+            Some(EmbeddedVerifierKey::Key(key)) => Some(verifier_key_digest(key)),
+            Some(EmbeddedVerifierKey::Digest { digest, .. }) => Some(*digest),
+            None => None,
+        };
+        if let (Some(key), Some(digest)) = (verifier_key, embedded_digest) {
+            if verifier_key_digest(key) != digest {
+                return Err(SangriaError::VerifierKeyMismatch);
+            }
+        }
+
+        let key = match (policy, verifier_key, &self.verifier_key) {
+            (VerifierKeyPolicy::Explicit, Some(key), _) => key,
+            (VerifierKeyPolicy::Embedded, _, Some(EmbeddedVerifierKey::Key(key))) => key,
+            (VerifierKeyPolicy::Embedded, Some(key), Some(EmbeddedVerifierKey::Digest { .. })) => {
+                key
+            }
+            _ => return Err(SangriaError::MissingVerifierKey),
+        };
+
+        verify_compressed(key, self.compressed_proof)
+    }
+}
+
+impl<VK: CanonicalSerialize> CanonicalSerialize for EmbeddedVerifierKey<VK> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        match self {
+            Self::Key(key) => {
+                0u8.serialize_with_mode(&mut writer, compress)?;
+                key.serialize_with_mode(&mut writer, compress)
+            }
+            Self::Digest { digest, url } => {
+                1u8.serialize_with_mode(&mut writer, compress)?;
+                digest.serialize_with_mode(&mut writer, compress)?;
+                url.serialize_with_mode(&mut writer, compress)
+            }
+        }
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        1 + match self {
+            Self::Key(key) => key.serialized_size(compress),
+            Self::Digest { digest, url } => {
+                digest.serialized_size(compress) + url.serialized_size(compress)
+            }
+        }
+    }
+}
+
+impl<VK: Valid> Valid for EmbeddedVerifierKey<VK> {
+    fn check(&self) -> Result<(), SerializationError> {
+        match self {
+            Self::Key(key) => key.check(),
+            Self::Digest { .. } => Ok(()),
+        }
+    }
+}
+
+impl<VK: CanonicalDeserialize> CanonicalDeserialize for EmbeddedVerifierKey<VK> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        match u8::deserialize_with_mode(&mut reader, compress, validate)? {
+            0 => Ok(Self::Key(VK::deserialize_with_mode(
+                reader, compress, validate,
+            )?)),
+            1 => Ok(Self::Digest {
+                digest: <[u8; 32]>::deserialize_with_mode(&mut reader, compress, validate)?,
+                url: String::deserialize_with_mode(reader, compress, validate)?,
+            }),
+            _ => Err(SerializationError::InvalidData),
+        }
+    }
+}
+
+impl<VK: CanonicalSerialize, P: CanonicalSerialize> CanonicalSerialize
+    for SelfContainedProof<VK, P>
+{
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        self.compressed_proof
+            .serialize_with_mode(&mut writer, compress)?;
+        self.verifier_key.serialize_with_mode(&mut writer, compress)
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        self.compressed_proof.serialized_size(compress)
+            + self.verifier_key.serialized_size(compress)
+    }
+}
+
+impl<VK: Valid, P: Valid> Valid for SelfContainedProof<VK, P> {
+    fn check(&self) -> Result<(), SerializationError> {
+        self.compressed_proof.check()?;
+        self.verifier_key.check()
+    }
+}
+
+impl<VK: CanonicalDeserialize, P: CanonicalDeserialize> CanonicalDeserialize
+    for SelfContainedProof<VK, P>
+{
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        Ok(Self {
+            compressed_proof: P::deserialize_with_mode(&mut reader, compress, validate)?,
+            verifier_key: Option::deserialize_with_mode(reader, compress, validate)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{verifier_key_digest, EmbeddedVerifierKey, SelfContainedProof, VerifierKeyPolicy};
+    use crate::{
+        ivc::VerifierKey,
+        utils::{
+            circuits::Counter, inner_product::InnerProductConfig,
+            poseidon::poseidon_parameters_for_test,
+        },
+        CompressedBatchedIVCProof, Sangria, SangriaConfig, SangriaError, SetupInfo,
+    };
+    use ark_bn254::Fr;
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+    use ark_std::test_rng;
+
+    type Scheme = Sangria<InnerProductConfig>;
+    type Key = VerifierKey<Fr, InnerProductConfig, Counter>;
+    type Proof = SelfContainedProof<Key, CompressedBatchedIVCProof<Fr, InnerProductConfig>>;
+
+    fn round_trip<T: CanonicalSerialize + CanonicalDeserialize>(value: &T) -> T {
+        let mut bytes = Vec::new();
+        value.serialize_compressed(&mut bytes).unwrap();
+
+        T::deserialize_compressed(&bytes[..]).unwrap()
+    }
+
+    #[test]
+    fn proofs_verify_under_the_key_the_policy_picks() {
+        let rng = &mut test_rng();
+        let info = SetupInfo {
+            number_of_public_inputs: 2,
+            number_of_gates: 2,
+            config: SangriaConfig::default(),
+            poseidon_constants: poseidon_parameters_for_test(),
+        };
+        let public_parameters = Scheme::setup(&info, rng).unwrap();
+        let (pk, vk) = Scheme::encode(&public_parameters, &Counter, rng).unwrap();
+        let (_, other_vk) = Scheme::encode(&public_parameters, &Counter, rng).unwrap();
+        let origin = Fr::from(5u64);
+        let (state, proof) =
+            Scheme::prove_step(&pk, &origin, origin, None, &Fr::from(1u64), rng).unwrap();
+        let wrap = || Proof::new(Scheme::compress(&pk, proof.clone()).unwrap());
+        let verify = |verifier_key: Option<&Key>, proof: Proof, policy| {
+            proof.verify(verifier_key, policy, |verifier_key, compressed_proof| {
+                Scheme::verify_compressed(verifier_key, &origin, state, compressed_proof)
+            })
+        };
+        let url = "https://example.com/vk".to_string();
+        let (explicit, embedded) = (VerifierKeyPolicy::Explicit, VerifierKeyPolicy::Embedded);
+
+        // without an embedded key, only the explicit key verifies
+        assert_eq!(verify(Some(&vk), wrap(), explicit), Ok(()));
+        assert_eq!(
+            verify(Some(&vk), wrap(), embedded),
+            Err(SangriaError::MissingVerifierKey)
+        );
+        assert_eq!(
+            verify(None, wrap(), explicit),
+            Err(SangriaError::MissingVerifierKey)
+        );
+
+        // an embedded key verifies on its own and must match the explicit one
+        let read = round_trip(&wrap().embed_key(round_trip(&vk)));
+        assert!(matches!(
+            read.embedded_key(),
+            Some(EmbeddedVerifierKey::Key(_))
+        ));
+        assert_eq!(verify(None, read, embedded), Ok(()));
+        assert_eq!(
+            verify(Some(&vk), wrap().embed_key(round_trip(&vk)), explicit),
+            Ok(())
+        );
+        assert_eq!(
+            verify(Some(&vk), wrap().embed_key(round_trip(&other_vk)), explicit),
+            Err(SangriaError::VerifierKeyMismatch)
+        );
+        assert_eq!(
+            verify(None, wrap().embed_key(round_trip(&other_vk)), embedded),
+            Err(SangriaError::InvalidProof)
+        );
+
+        // a digest needs the key fetched from its URL
+        let with_digest = || wrap().embed_key_digest(&vk, url.clone());
+        assert!(matches!(
+            round_trip(&with_digest()).embedded_key(),
+            Some(EmbeddedVerifierKey::Digest { digest, url: read_url })
+                if *digest == verifier_key_digest(&vk) && *read_url == url
+        ));
+        assert_eq!(verify(Some(&vk), with_digest(), embedded), Ok(()));
+        assert_eq!(
+            verify(None, with_digest(), embedded),
+            Err(SangriaError::MissingVerifierKey)
+        );
+        assert_eq!(
+            verify(Some(&other_vk), with_digest(), embedded),
+            Err(SangriaError::VerifierKeyMismatch)
+        );
+
+        // stripping the key leaves the explicit key to the verifier
+        let stripped = wrap().embed_key(round_trip(&vk)).strip_key();
+        assert!(stripped.embedded_key().is_none());
+        assert_eq!(verify(Some(&vk), stripped, explicit), Ok(()));
+    }
+}
@@ -1,3 +1,5 @@
+use ark_serialize::SerializationError;
+use ark_std::io::ErrorKind;
 use thiserror::Error;
 
 /// Errors returned by Sangria
@@ -8,6 +10,206 @@ pub enum SangriaError {
     IndexOutOfBounds,
 
     /// returned if the commitment scheme returns an error
+    #[error("An error occurred with the commitment scheme: {0}")]
+    Commitment(#[from] PCSError),
+
+    /// returned by earlier releases if the commitment scheme returned an error, which is now
+    /// reported with its context as [`SangriaError::Commitment`]
+    #[deprecated(note = "commitment failures are returned as `SangriaError::Commitment`")]
     #[error("An error occurred with the commitment scheme")]
     CommitmentError,
+
+    /// returned if a transcript cannot be started or cannot derive the challenges asked of it
+    #[error("Invalid transcript: {0}")]
+    Transcript(&'static str),
+
+    /// returned if a value read from untrusted bytes does not decode or fails its validity checks,
+    /// e.g. a commitment off the curve
+    #[error("The value cannot be deserialized: {0}")]
+    Serialization(SerializationFailure),
+
+    /// returned if a configuration cannot be used
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(&'static str),
+
+    /// returned if a step circuit rejects the witness supplied for a step
+    #[error("The witness is not valid for the current state")]
+    InvalidWitness,
+
+    /// returned if a row of the trace does not satisfy the relaxed gate equation of the circuit
+    #[error("The gate on row {row} is not satisfied")]
+    UnsatisfiedGate {
+        /// the row of the trace
+        row: usize,
+    },
+
+    /// returned if two wires of the trace bound by a copy constraint hold different values
+    #[error("The copy constraint between wires {position} and {next} is not satisfied")]
+    UnsatisfiedCopyConstraint {
+        /// the position of the wire in the trace, column by column
+        position: usize,
+        /// the position of the next wire in its cycle of the copy constraint permutation
+        next: usize,
+    },
+
+    /// returned if a prover message does not hold one cross-term commitment per power of the
+    /// folding challenge
+    #[error("The prover message holds {found} cross-term commitments but {expected} are expected")]
+    CrossTermCount {
+        /// the number of cross-term commitments of the gate degree
+        expected: usize,
+        /// the number of cross-term commitments in the prover message
+        found: usize,
+    },
+
+    /// returned if two instances, or an instance and the layout of the instances of its circuit, do
+    /// not have as many commitments of a kind
+    #[error("The instance holds {found} commitments but {expected} are expected")]
+    CommitmentCount {
+        /// the number of commitments of the other instance or of the layout
+        expected: usize,
+        /// the number of commitments of the instance
+        found: usize,
+    },
+
+    /// returned if folding challenges cannot be squeezed with the configured size
+    #[error("Challenges of {bits} bits cannot be squeezed in a field of {field_bits} bits")]
+    ChallengeSize {
+        /// the configured size of the challenges
+        bits: usize,
+        /// the size of the field elements
+        field_bits: usize,
+    },
+
+    /// returned if the public parameters cannot commit to vectors as long as the circuit requires
+    #[error("The public parameters support vectors of length {available} but {needed} is needed")]
+    ParametersTooSmall {
+        /// the vector length required by the circuit
+        needed: usize,
+        /// the vector length supported by the public parameters
+        available: usize,
+    },
+
+    /// returned if an execution trace cannot be converted into a circuit
+    #[error("Invalid trace: {0}")]
+    InvalidTrace(&'static str),
+
+    /// returned if a proof is rejected by its verifier
+    #[error("The proof is invalid")]
+    InvalidProof,
+
+    /// returned if an instance's public inputs do not match the public-input rows of the circuit
+    #[error("The instance public inputs do not match the circuit public-input rows")]
+    PublicInputMismatch,
+
+    /// returned if a proving session cannot be written to or read from its recording
+    #[error("The proving session recording cannot be written or read")]
+    SessionRecording,
+
+    /// returned if bytes are not the canonical encoding of a field element
+    #[error("The bytes do not encode a field element canonically")]
+    InvalidEncoding,
+
+    /// returned if the chain proved by a self-test is rejected by the verifier key
+    #[error("The self-test chain was proved but rejected by the verifier key")]
+    SelfTestFailed,
+
+    /// returned if a sealed checkpoint is not authenticated under the key, or does not decode
+    #[error("The sealed checkpoint cannot be opened with the key")]
+    SealedCheckpoint,
+
+    /// returned if a verifier key is needed to verify a proof but is neither given nor embedded in
+    /// the proof
+    #[error("No verifier key is given or embedded in the proof")]
+    MissingVerifierKey,
+
+    /// returned if the verifier key given to verify a proof does not match the verifier key or
+    /// digest embedded in the proof
+    #[error("The verifier key does not match the one embedded in the proof")]
+    VerifierKeyMismatch,
+}
+
+impl From<SerializationError> for SangriaError {
+    fn from(error: SerializationError) -> Self {
+        Self::Serialization(match error {
+            SerializationError::NotEnoughSpace => SerializationFailure::NotEnoughSpace,
+            SerializationError::InvalidData => SerializationFailure::InvalidData,
+            SerializationError::UnexpectedFlags => SerializationFailure::UnexpectedFlags,
+            SerializationError::IoError(error) => SerializationFailure::Io(error.kind()),
+        })
+    }
+}
+
+impl SangriaError {
+    /// Returns a short identifier of the kind of error, stable across releases and free of the
+    /// details of the error, e.g. to label rejection counters.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::IndexOutOfBounds => "index_out_of_bounds",
+            #[allow(deprecated)]
+            Self::Commitment(_) | Self::CommitmentError => "commitment_error",
+            Self::Transcript(_) => "transcript",
+            Self::Serialization(_) => "serialization",
+            Self::InvalidConfig(_) => "invalid_config",
+            Self::InvalidWitness => "invalid_witness",
+            Self::UnsatisfiedGate { .. } => "unsatisfied_gate",
+            Self::UnsatisfiedCopyConstraint { .. } => "unsatisfied_copy_constraint",
+            Self::CrossTermCount { .. } => "cross_term_count",
+            Self::CommitmentCount { .. } => "commitment_count",
+            Self::ChallengeSize { .. } => "challenge_size",
+            Self::ParametersTooSmall { .. } => "parameters_too_small",
+            Self::InvalidTrace(_) => "invalid_trace",
+            Self::InvalidProof => "invalid_proof",
+            Self::PublicInputMismatch => "public_input_mismatch",
+            Self::SessionRecording => "session_recording",
+            Self::InvalidEncoding => "invalid_encoding",
+            Self::SelfTestFailed => "self_test_failed",
+            Self::SealedCheckpoint => "sealed_checkpoint",
+            Self::MissingVerifierKey => "missing_verifier_key",
+            Self::VerifierKeyMismatch => "verifier_key_mismatch",
+        }
+    }
+}
+
+/// Errors returned by the vector commitment schemes, carrying the lengths that were rejected.
+#[derive(Clone, Debug, Eq, PartialEq, Error)]
+pub enum PCSError {
+    /// returned if a vector does not fit in the commit key, ending past its degree bound
+    #[error("A vector ending at position {end} exceeds the degree bound {max_len}")]
+    DegreeBound {
+        /// the position following the last entry of the vector
+        end: usize,
+        /// the maximum length of the vectors the commit key commits to
+        max_len: usize,
+    },
+
+    /// returned if a batch of vectors does not come with one randomness per vector
+    #[error("{vectors} vectors are committed to with {randomness} randomness values")]
+    BatchLength {
+        /// the number of vectors
+        vectors: usize,
+        /// the number of randomness values
+        randomness: usize,
+    },
+}
+
+/// The reason a value could not be deserialized, mirroring [`SerializationError`] in a form that
+/// can be compared and cloned.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Error)]
+pub enum SerializationFailure {
+    /// returned if the serialization buffer is too small for the value
+    #[error("the buffer is too small")]
+    NotEnoughSpace,
+
+    /// returned if the bytes do not encode a valid value
+    #[error("the data is invalid")]
+    InvalidData,
+
+    /// returned if the flags of a serialized point or field element are not the expected ones
+    #[error("the flags are unexpected")]
+    UnexpectedFlags,
+
+    /// returned if reading or writing the bytes failed
+    #[error("the reader or writer failed: {0:?}")]
+    Io(ErrorKind),
 }
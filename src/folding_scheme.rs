@@ -1,33 +1,308 @@
-use crate::vector_commitment::HomomorphicCommitmentScheme;
+use crate::vector_commitment::{HomomorphicCommitmentScheme, InnerProductOpening};
+use ark_crypto_primitives::sponge::{
+    poseidon::PoseidonConfig, Absorb, CryptographicSponge, FieldBasedCryptographicSponge,
+    FieldElementSize,
+};
 use ark_ff::PrimeField;
-use ark_sponge::{
-    poseidon::{PoseidonParameters, PoseidonSponge},
-    Absorb, CryptographicSponge, FieldBasedCryptographicSponge,
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, SerializationError, Valid, Validate,
+};
+use ark_std::{
+    io::{Read, Write},
+    marker::PhantomData,
+    ops::Range,
+    rand::Rng,
 };
-use ark_std::{marker::PhantomData, rand::Rng};
 
 use crate::{
+    relaxed_plonk::{lagrange_basis, NUMBER_OF_WIRES},
+    serialization::{deserialize_bounded_vec, deserialize_bounded_vec_with},
+    transcript::Transcript,
     NonInteractiveFoldingScheme, PLONKCircuit, RelaxedPLONKInstance, RelaxedPLONKWitness,
-    SangriaError, CONSTANT_SELECTOR_INDEX,
+    SangriaConfig, SangriaError, SizeLimits, CONSTANT_SELECTOR_INDEX, LEFT_SELECTOR_INDEX,
+    MULTIPLICATION_SELECTOR_INDEX, OUTPUT_SELECTOR_INDEX, POWER_SELECTOR_INDEX,
+    RIGHT_SELECTOR_INDEX,
 };
 
-/// A folding scheme for relaxed PLONK
-pub struct PLONKFoldingScheme<
-    F: PrimeField,
-    Comm: FoldingCommitmentConfig<F>,
-    RO: FieldBasedCryptographicSponge<F>,
->(PhantomData<(F, Comm, RO)>);
+/// A folding scheme for relaxed PLONK, deriving its challenges from transcripts of type `RO`.
+pub struct PLONKFoldingScheme<F: PrimeField, Comm: FoldingCommitmentConfig<F>, RO: Transcript<F>>(
+    PhantomData<(F, Comm, RO)>,
+);
 
+/// Bundles the commitment schemes used to commit to the slack vector (and selectors) and to the
+/// witness columns of relaxed PLONK instances.
 pub trait FoldingCommitmentConfig<F: PrimeField> {
+    /// The scheme committing to vectors spanning every row of the circuit.
     type CommitmentSlack: HomomorphicCommitmentScheme<F>;
+    /// The scheme committing to the witness columns, which only span the gate rows.
     type CommitmentWitness: HomomorphicCommitmentScheme<F>;
 }
 
+/// Uses the scheme `C` for both the slack and the witness commitments, so that any homomorphic
+/// commitment scheme (e.g. a [`crate::pedersen::PedersenCommitment`]) can back the folding scheme
+/// and Sangria without a dedicated configuration.
+pub struct SingleCommitment<C>(PhantomData<C>);
+
+impl<F: PrimeField, C: HomomorphicCommitmentScheme<F>> FoldingCommitmentConfig<F>
+    for SingleCommitment<C>
+{
+    type CommitmentSlack = C;
+    type CommitmentWitness = C;
+}
+
+/// The arguments of the folding scheme setup.
 pub struct SetupInfo<F: PrimeField> {
+    /// The number of public input rows of the circuits.
     pub number_of_public_inputs: usize,
+    /// The number of gate rows of the circuits.
     pub number_of_gates: usize,
-    pub domain_separator: Vec<u8>,
-    pub poseidon_constants: PoseidonParameters<F>,
+    /// The deployment configuration.
+    pub config: SangriaConfig,
+    /// The constants of the Poseidon sponge, used by Poseidon transcripts.
+    pub poseidon_constants: PoseidonConfig<F>,
+}
+
+/// The maximum width of the Poseidon state accepted when deserializing setup arguments.
+const MAX_POSEIDON_WIDTH: usize = 64;
+
+/// The maximum number of Poseidon rounds accepted when deserializing setup arguments.
+const MAX_POSEIDON_ROUNDS: usize = 1024;
+
+impl<F: PrimeField> CanonicalSerialize for SetupInfo<F> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        self.number_of_public_inputs
+            .serialize_with_mode(&mut writer, compress)?;
+        self.number_of_gates
+            .serialize_with_mode(&mut writer, compress)?;
+        self.config.serialize_with_mode(&mut writer, compress)?;
+        serialize_poseidon_constants(&self.poseidon_constants, writer, compress)
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        self.number_of_public_inputs.serialized_size(compress)
+            + self.number_of_gates.serialized_size(compress)
+            + self.config.serialized_size(compress)
+            + poseidon_constants_size(&self.poseidon_constants, compress)
+    }
+}
+
+impl<F: PrimeField> Valid for SetupInfo<F> {
+    fn check(&self) -> Result<(), SerializationError> {
+        Ok(())
+    }
+}
+
+impl<F: PrimeField> CanonicalDeserialize for SetupInfo<F> {
+    /// Deserializes setup arguments, rejecting Poseidon constants wider than
+    /// `MAX_POSEIDON_WIDTH` or with more than `MAX_POSEIDON_ROUNDS` rounds before reading
+    /// them, and constants whose dimensions do not match their round numbers and width.
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        Ok(Self {
+            number_of_public_inputs: usize::deserialize_with_mode(&mut reader, compress, validate)?,
+            number_of_gates: usize::deserialize_with_mode(&mut reader, compress, validate)?,
+            config: SangriaConfig::deserialize_with_mode(&mut reader, compress, validate)?,
+            poseidon_constants: deserialize_poseidon_constants(reader, compress, validate)?,
+        })
+    }
+}
+
+fn serialize_poseidon_constants<F: PrimeField, W: Write>(
+    constants: &PoseidonConfig<F>,
+    mut writer: W,
+    compress: Compress,
+) -> Result<(), SerializationError> {
+    constants
+        .full_rounds
+        .serialize_with_mode(&mut writer, compress)?;
+    constants
+        .partial_rounds
+        .serialize_with_mode(&mut writer, compress)?;
+    constants.alpha.serialize_with_mode(&mut writer, compress)?;
+    constants.ark.serialize_with_mode(&mut writer, compress)?;
+    constants.mds.serialize_with_mode(&mut writer, compress)?;
+    constants.rate.serialize_with_mode(&mut writer, compress)?;
+    constants
+        .capacity
+        .serialize_with_mode(&mut writer, compress)
+}
+
+fn poseidon_constants_size<F: PrimeField>(
+    constants: &PoseidonConfig<F>,
+    compress: Compress,
+) -> usize {
+    constants.full_rounds.serialized_size(compress)
+        + constants.partial_rounds.serialized_size(compress)
+        + constants.alpha.serialized_size(compress)
+        + constants.ark.serialized_size(compress)
+        + constants.mds.serialized_size(compress)
+        + constants.rate.serialized_size(compress)
+        + constants.capacity.serialized_size(compress)
+}
+
+/// Deserializes Poseidon constants, rejecting constants wider than `MAX_POSEIDON_WIDTH` or with
+/// more than `MAX_POSEIDON_ROUNDS` rounds before reading them, and constants whose dimensions do
+/// not match their round numbers and width.
+fn deserialize_poseidon_constants<F: PrimeField, R: Read>(
+    mut reader: R,
+    compress: Compress,
+    validate: Validate,
+) -> Result<PoseidonConfig<F>, SerializationError> {
+    let full_rounds = usize::deserialize_with_mode(&mut reader, compress, validate)?;
+    let partial_rounds = usize::deserialize_with_mode(&mut reader, compress, validate)?;
+    let alpha = u64::deserialize_with_mode(&mut reader, compress, validate)?;
+    let ark: Vec<Vec<F>> = deserialize_bounded_vec_with(
+        &mut reader,
+        MAX_POSEIDON_ROUNDS,
+        compress,
+        validate,
+        |reader| deserialize_bounded_vec(reader, MAX_POSEIDON_WIDTH, compress, validate),
+    )?;
+    let mds: Vec<Vec<F>> = deserialize_bounded_vec_with(
+        &mut reader,
+        MAX_POSEIDON_WIDTH,
+        compress,
+        validate,
+        |reader| deserialize_bounded_vec(reader, MAX_POSEIDON_WIDTH, compress, validate),
+    )?;
+    let rate = usize::deserialize_with_mode(&mut reader, compress, validate)?;
+    let capacity = usize::deserialize_with_mode(&mut reader, compress, validate)?;
+
+    let width = rate.checked_add(capacity);
+    if Some(ark.len()) != full_rounds.checked_add(partial_rounds)
+        || mds.len() != ark.first().map_or(0, Vec::len)
+        || ark
+            .iter()
+            .chain(mds.iter())
+            .any(|row| Some(row.len()) != width)
+    {
+        return Err(SerializationError::InvalidData);
+    }
+
+    Ok(PoseidonConfig::new(
+        full_rounds,
+        partial_rounds,
+        alpha,
+        mds,
+        ark,
+        rate,
+        capacity,
+    ))
+}
+
+/// A folding challenge together with the context it was derived in: the round of folding it is
+/// used in and a digest of the transcript. Challenges can only be squeezed from a transcript and
+/// are consumed when used to fold, so a challenge cannot be forged, reused across folds or used
+/// with instances over a different field.
+#[derive(Debug, PartialEq, Eq)]
+pub struct FoldChallenge<F: PrimeField> {
+    value: F,
+    round: usize,
+    transcript_digest: F,
+}
+
+impl<F: PrimeField> FoldChallenge<F> {
+    /// Squeezes a challenge of `challenge_bits` bits from the transcript for the fold numbered
+    /// `round` by the caller. The digest is squeezed beforehand from a copy of the transcript that
+    /// also absorbs [`TRANSCRIPT_DIGEST_LABEL`], so it only depends on what the transcript absorbed
+    /// and not on the challenge.
+    pub(crate) fn squeeze<S: CryptographicSponge>(
+        sponge: &mut S,
+        challenge_bits: usize,
+        round: usize,
+    ) -> Self
+    where
+        F: Absorb,
+    {
+        let mut digest_sponge = sponge.clone();
+        digest_sponge.absorb(&F::from_le_bytes_mod_order(TRANSCRIPT_DIGEST_LABEL));
+        let transcript_digest = digest_sponge.squeeze_field_elements(1)[0];
+
+        let value = sponge
+            .squeeze_field_elements_with_sizes(&[FieldElementSize::Truncated(challenge_bits)])[0];
+
+        Self {
+            value,
+            round,
+            transcript_digest,
+        }
+    }
+
+    /// Returns the value of the challenge.
+    pub fn value(&self) -> F {
+        self.value
+    }
+
+    /// Returns the round of folding the challenge was derived for: the index of the fold in the
+    /// sequence of folds of the caller, zero for a single fold.
+    pub fn round(&self) -> usize {
+        self.round
+    }
+
+    /// Returns a digest of the transcript the challenge was derived from, as it was before the
+    /// challenge was squeezed.
+    pub fn transcript_digest(&self) -> F {
+        self.transcript_digest
+    }
+}
+
+/// The label absorbed, as the field element of its little-endian bytes, by the copy of the
+/// transcript a [`FoldChallenge`] squeezes its digest from.
+pub const TRANSCRIPT_DIGEST_LABEL: &[u8] = b"sangria transcript digest";
+
+/// A record of one run of the NIFS verifier, for auditors and differential testers checking the
+/// implementation against the Sangria paper: the field elements absorbed by the transcript, in
+/// order and labelled, the challenge squeezed from it and the folded instance.
+pub struct FoldAudit<F: PrimeField, Comm: FoldingCommitmentConfig<F>> {
+    /// The field elements of each absorbed value, in the order they were absorbed.
+    pub absorbed: Vec<(&'static str, Vec<F>)>,
+    /// The folding challenge.
+    pub challenge: F,
+    /// The round of folding the challenge was derived for.
+    pub round: usize,
+    /// The digest of the transcript the challenge was derived from.
+    pub transcript_digest: F,
+    /// The instance resulting from the fold.
+    pub folded_instance: RelaxedPLONKInstance<F, Comm>,
+}
+
+/// An opening of one wire of a committed witness, revealing its value without the rest of the
+/// witness. The verifier derives the opened vector from the position of the wire, so the opening
+/// cannot be replayed for another wire.
+pub struct WireOpening<F, Comm>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+    Comm::CommitmentWitness: InnerProductOpening<F>,
+{
+    column: usize,
+    row: usize,
+    value: F,
+    proof: <Comm::CommitmentWitness as InnerProductOpening<F>>::Proof,
+}
+
+impl<F, Comm> WireOpening<F, Comm>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+    Comm::CommitmentWitness: InnerProductOpening<F>,
+{
+    /// Returns the position of the opened wire in the trace, as (column, row).
+    pub fn position(&self) -> (usize, usize) {
+        (self.column, self.row)
+    }
+
+    /// Returns the revealed value of the wire.
+    pub fn value(&self) -> F {
+        self.value
+    }
 }
 
 /// Public parameters for the folding scheme. Contains size parameters for the PLONK circuits
@@ -38,9 +313,9 @@ pub struct PublicParameters<F: PrimeField, Comm: FoldingCommitmentConfig<F>> {
     pub commit_key_witness: <Comm::CommitmentWitness as HomomorphicCommitmentScheme<F>>::CommitKey,
     pub commit_key_selectors_and_slack:
         <Comm::CommitmentSlack as HomomorphicCommitmentScheme<F>>::CommitKey,
-    pub poseidon_constants: PoseidonParameters<F>,
+    pub poseidon_constants: PoseidonConfig<F>,
 
-    pub domain_separator: Vec<u8>,
+    pub config: SangriaConfig,
 }
 
 impl<F, Comm> Clone for PublicParameters<F, Comm>
@@ -55,9 +330,111 @@ where
             commit_key_witness: self.commit_key_witness.clone(),
             commit_key_selectors_and_slack: self.commit_key_selectors_and_slack.clone(),
             poseidon_constants: self.poseidon_constants.clone(),
-            domain_separator: self.domain_separator.clone(),
+            config: self.config.clone(),
+        }
+    }
+}
+
+impl<F, Comm> PublicParameters<F, Comm>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    /// Returns the size limits for parsing instances of circuits encoded with these parameters:
+    /// one column per wire and one row per public input plus the output row. No extra commitments
+    /// are allowed unless raised with [`SizeLimits::with_extra_commitments`].
+    pub fn size_limits(&self) -> SizeLimits {
+        SizeLimits {
+            max_columns: NUMBER_OF_WIRES,
+            max_rows: self.number_of_public_inputs + 1,
+            max_extra_commitments: 0,
         }
     }
+
+    /// Serializes every component of the parameters (sizes, commit keys, hash constants and
+    /// configuration) into the bytes absorbed by transcripts.
+    fn transcript_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.serialize_compressed(&mut bytes)
+            .expect("serializing into a vector cannot fail");
+
+        bytes
+    }
+}
+
+/// The parameters are serialized in the order they are absorbed by transcripts: the sizes, the
+/// commit keys, the configuration and the Poseidon constants.
+impl<F, Comm> CanonicalSerialize for PublicParameters<F, Comm>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        self.number_of_public_inputs
+            .serialize_with_mode(&mut writer, compress)?;
+        self.number_of_gates
+            .serialize_with_mode(&mut writer, compress)?;
+        self.commit_key_witness
+            .serialize_with_mode(&mut writer, compress)?;
+        self.commit_key_selectors_and_slack
+            .serialize_with_mode(&mut writer, compress)?;
+        self.config.serialize_with_mode(&mut writer, compress)?;
+        serialize_poseidon_constants(&self.poseidon_constants, writer, compress)
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        self.number_of_public_inputs.serialized_size(compress)
+            + self.number_of_gates.serialized_size(compress)
+            + self.commit_key_witness.serialized_size(compress)
+            + self
+                .commit_key_selectors_and_slack
+                .serialized_size(compress)
+            + self.config.serialized_size(compress)
+            + poseidon_constants_size(&self.poseidon_constants, compress)
+    }
+}
+
+impl<F, Comm> Valid for PublicParameters<F, Comm>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    fn check(&self) -> Result<(), SerializationError> {
+        Ok(())
+    }
+}
+
+impl<F, Comm> CanonicalDeserialize for PublicParameters<F, Comm>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        Ok(Self {
+            number_of_public_inputs: usize::deserialize_with_mode(&mut reader, compress, validate)?,
+            number_of_gates: usize::deserialize_with_mode(&mut reader, compress, validate)?,
+            commit_key_witness: CanonicalDeserialize::deserialize_with_mode(
+                &mut reader,
+                compress,
+                validate,
+            )?,
+            commit_key_selectors_and_slack: CanonicalDeserialize::deserialize_with_mode(
+                &mut reader,
+                compress,
+                validate,
+            )?,
+            config: SangriaConfig::deserialize_with_mode(&mut reader, compress, validate)?,
+            poseidon_constants: deserialize_poseidon_constants(reader, compress, validate)?,
+        })
+    }
 }
 
 impl<F, Comm> Absorb for PublicParameters<F, Comm>
@@ -65,20 +442,67 @@ where
     F: PrimeField,
     Comm: FoldingCommitmentConfig<F>,
 {
-    fn to_sponge_bytes(&self, _dest: &mut Vec<u8>) {
-        todo!()
+    fn to_sponge_bytes(&self, dest: &mut Vec<u8>) {
+        self.transcript_bytes().to_sponge_bytes(dest);
+    }
+
+    fn to_sponge_field_elements<SpongeF: PrimeField>(&self, dest: &mut Vec<SpongeF>) {
+        self.transcript_bytes().to_sponge_field_elements(dest);
     }
+}
 
-    fn to_sponge_field_elements<SpongeF: PrimeField>(&self, _dest: &mut Vec<SpongeF>) {
-        todo!()
+/// The layout of the relaxed PLONK instances of an encoded circuit, for generic tooling (explorers,
+/// relayers, code generators) parsing instances of any circuit without hard-coding their layout. A
+/// serialized instance holds, in order: `instance_columns` columns of `instance_rows` field
+/// elements, the scaling factor, the slack commitment, `witness_commitments` witness commitments
+/// and `extra_commitments` extra commitments, every list being prefixed with its length as a `u64`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct InstanceSchema {
+    /// The number of public inputs, held by the first wire of the first instance rows.
+    pub number_of_public_inputs: usize,
+    /// The number of instance columns, one per wire.
+    pub instance_columns: usize,
+    /// The number of rows of every instance column: the public inputs and the output row.
+    pub instance_rows: usize,
+    /// The number of witness commitments, one per wire.
+    pub witness_commitments: usize,
+    /// The number of application-defined extra commitments.
+    pub extra_commitments: usize,
+    /// The number of cross term commitments in the prover message folding two instances.
+    pub cross_term_commitments: usize,
+}
+
+impl InstanceSchema {
+    /// Returns the size limits accepting exactly the instances of this schema.
+    pub fn size_limits(&self) -> SizeLimits {
+        SizeLimits {
+            max_columns: self.instance_columns.max(self.witness_commitments),
+            max_rows: self.instance_rows,
+            max_extra_commitments: self.extra_commitments,
+        }
     }
 }
 
 /// The verifier key for the PLONK folding scheme. Contains a commitment to the q_C selector (constant)
+/// and the layout of the instances of the circuit, with the sizes and digests of the components
+/// bound by the transcript seed so that [`VerifierKey::diff`] can tell them apart.
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
 pub struct VerifierKey<F: PrimeField, Comm: FoldingCommitmentConfig<F>> {
     pub selector_c_commitment:
         <Comm::CommitmentSlack as HomomorphicCommitmentScheme<F>>::Commitment,
     pub transcript_seed: F,
+    pub instance_schema: InstanceSchema,
+    /// The number of rows of the circuit, i.e. its domain size: one per public input, one per gate
+    /// and the output row.
+    pub number_of_rows: usize,
+    /// The degree of the gate equation.
+    pub gate_degree: usize,
+    /// The BLAKE3 digest of the circuit (its selectors, fixed columns and copy constraints), which
+    /// identifies its gates.
+    pub circuit_digest: [u8; 32],
+    /// The BLAKE3 digest of the transcript configuration: the domain separator, the application
+    /// identifier, the challenge size and the Poseidon constants.
+    pub transcript_config_digest: [u8; 32],
 }
 
 impl<F, Comm> Clone for VerifierKey<F, Comm>
@@ -90,39 +514,128 @@ where
         Self {
             selector_c_commitment: self.selector_c_commitment,
             transcript_seed: self.transcript_seed,
+            instance_schema: self.instance_schema,
+            number_of_rows: self.number_of_rows,
+            gate_degree: self.gate_degree,
+            circuit_digest: self.circuit_digest,
+            transcript_config_digest: self.transcript_config_digest,
         }
     }
 }
 
-impl<F, Comm> Absorb for VerifierKey<F, Comm>
+impl<F, Comm> VerifierKey<F, Comm>
 where
     F: PrimeField,
     Comm: FoldingCommitmentConfig<F>,
 {
-    fn to_sponge_bytes(&self, _dest: &mut Vec<u8>) {
-        todo!()
+    /// Reports which components of the verifier key differ from `other`, e.g. to review what a
+    /// circuit upgrade changes.
+    pub fn diff(&self, other: &Self) -> KeyDiff {
+        KeyDiff {
+            number_of_rows: self.number_of_rows != other.number_of_rows,
+            gate_degree: self.gate_degree != other.gate_degree,
+            circuit: self.circuit_digest != other.circuit_digest,
+            selector_c_commitment: self.selector_c_commitment != other.selector_c_commitment,
+            transcript_config: self.transcript_config_digest != other.transcript_config_digest,
+            transcript_seed: self.transcript_seed != other.transcript_seed,
+            instance_schema: self.instance_schema != other.instance_schema,
+        }
+    }
+}
+
+/// The components that differ between two folding verifier keys, as computed by `VerifierKey::diff`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct KeyDiff {
+    /// The number of rows of the circuit (its domain size) changed.
+    pub number_of_rows: bool,
+    /// The degree of the gate equation changed.
+    pub gate_degree: bool,
+    /// The gates of the circuit changed: its selectors, fixed columns or copy constraints.
+    pub circuit: bool,
+    /// The commitment to the q_C selector changed.
+    pub selector_c_commitment: bool,
+    /// The transcript configuration changed: the domain separator, the application identifier, the
+    /// challenge size or the Poseidon constants.
+    pub transcript_config: bool,
+    /// The transcript seed changed. The seed binds the circuit, the public parameters (domain
+    /// separator, sizes, commit keys, hash constants) and the selector commitment randomness, so it
+    /// also changes with components that have no field of their own, e.g. the commit keys.
+    pub transcript_seed: bool,
+    /// The layout of the instances changed.
+    pub instance_schema: bool,
+}
+
+impl KeyDiff {
+    /// Returns true if the two verifier keys are identical.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl<F, Comm> Absorb for VerifierKey<F, Comm>
+where
+    F: PrimeField + Absorb,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    // the sizes and digests of the key are bound by the transcript seed
+    fn to_sponge_bytes(&self, dest: &mut Vec<u8>) {
+        self.selector_c_commitment.to_sponge_bytes(dest);
+        self.transcript_seed.to_sponge_bytes(dest);
     }
 
-    fn to_sponge_field_elements<SpongeF: PrimeField>(&self, _dest: &mut Vec<SpongeF>) {
-        todo!()
+    fn to_sponge_field_elements<SpongeF: PrimeField>(&self, dest: &mut Vec<SpongeF>) {
+        self.selector_c_commitment.to_sponge_field_elements(dest);
+        self.transcript_seed.to_sponge_field_elements(dest);
     }
 }
 
+/// Returns the BLAKE3 digest of the canonical serialization of `value`.
+pub(crate) fn blake3_digest<T: CanonicalSerialize>(value: &T) -> [u8; 32] {
+    let mut bytes = Vec::new();
+    value
+        .serialize_compressed(&mut bytes)
+        .expect("serializing into a vector cannot fail");
+
+    *blake3::hash(&bytes).as_bytes()
+}
+
+/// Returns the BLAKE3 digest of the parts of the public parameters configuring the transcripts.
+fn transcript_config_digest<F, Comm>(pp: &PublicParameters<F, Comm>) -> [u8; 32]
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    let mut bytes = Vec::new();
+    pp.config
+        .domain_separator
+        .serialize_compressed(&mut bytes)
+        .and_then(|_| pp.config.application_id.serialize_compressed(&mut bytes))
+        .and_then(|_| pp.config.challenge_bits.serialize_compressed(&mut bytes))
+        .and_then(|_| {
+            serialize_poseidon_constants(&pp.poseidon_constants, &mut bytes, Compress::Yes)
+        })
+        .expect("serializing into a vector cannot fail");
+
+    *blake3::hash(&bytes).as_bytes()
+}
+
 /// Prover key for the PLONK folding scheme. Contains:
 /// - a commitment to the q_C selector (as the verifier key)
 /// - a description of the circuit (needed to compute cross terms)
 /// - commitment parameters (as the public parameters)
 /// - the randomness that was used to commit to q_C
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
 pub struct ProverKey<F: PrimeField, Comm: FoldingCommitmentConfig<F>> {
     pub verifier_key: VerifierKey<F, Comm>,
     pub circuit: PLONKCircuit<F>,
     pub selector_c_commit_randomness: F,
 }
 
-impl<F, Comm> NonInteractiveFoldingScheme for PLONKFoldingScheme<F, Comm, PoseidonSponge<F>>
+impl<F, Comm, RO> NonInteractiveFoldingScheme for PLONKFoldingScheme<F, Comm, RO>
 where
     F: PrimeField + Absorb,
     Comm: FoldingCommitmentConfig<F>,
+    RO: Transcript<F>,
 {
     type SetupInfo = SetupInfo<F>;
     type PublicParameters = PublicParameters<F, Comm>;
@@ -131,9 +644,14 @@ where
     type Witness = RelaxedPLONKWitness<F>;
     type ProverKey = ProverKey<F, Comm>;
     type VerifierKey = VerifierKey<F, Comm>;
-    type ProverMessage = <Comm::CommitmentSlack as HomomorphicCommitmentScheme<F>>::Commitment;
+    type ProverMessage = Vec<<Comm::CommitmentSlack as HomomorphicCommitmentScheme<F>>::Commitment>;
+
+    fn setup<R: Rng>(
+        info: &SetupInfo<F>,
+        rng: &mut R,
+    ) -> Result<Self::PublicParameters, SangriaError> {
+        info.config.validate::<F>()?;
 
-    fn setup<R: Rng>(info: &SetupInfo<F>, rng: &mut R) -> Self::PublicParameters {
         let commit_key_witness = <Comm::CommitmentWitness as HomomorphicCommitmentScheme<F>>::setup(
             rng,
             info.number_of_gates,
@@ -144,14 +662,14 @@ where
                 info.number_of_gates + info.number_of_public_inputs + 1,
             );
 
-        PublicParameters {
+        Ok(PublicParameters {
             number_of_gates: info.number_of_gates,
             number_of_public_inputs: info.number_of_public_inputs,
             commit_key_witness,
             commit_key_selectors_and_slack,
-            domain_separator: info.domain_separator.clone(),
+            config: info.config.clone(),
             poseidon_constants: info.poseidon_constants.clone(),
-        }
+        })
     }
 
     fn encode<R: Rng>(
@@ -159,7 +677,383 @@ where
         circuit: &Self::Structure,
         rng: &mut R,
     ) -> Result<(Self::ProverKey, Self::VerifierKey), SangriaError> {
-        let randomness_c = F::rand(rng);
+        Self::encode_with_extra_commitments(pp, circuit, 0, rng)
+    }
+
+    fn prover<R: Rng>(
+        public_parameters: &Self::PublicParameters,
+        prover_key: &Self::ProverKey,
+        left_instance: &Self::Instance,
+        left_witness: &Self::Witness,
+        right_instance: &Self::Instance,
+        right_witness: &Self::Witness,
+        rng: &mut R,
+    ) -> Result<(Self::Instance, Self::Witness, Self::ProverMessage), SangriaError> {
+        Self::prover_in_round(
+            public_parameters,
+            prover_key,
+            left_instance,
+            left_witness,
+            right_instance,
+            right_witness,
+            0,
+            rng,
+        )
+    }
+
+    fn verifier(
+        public_parameters: &Self::PublicParameters,
+        verifier_key: &Self::VerifierKey,
+        left_instance: &Self::Instance,
+        right_instance: &Self::Instance,
+        prover_message: &Self::ProverMessage,
+    ) -> Result<Self::Instance, SangriaError> {
+        Self::verifier_in_round(
+            public_parameters,
+            verifier_key,
+            left_instance,
+            right_instance,
+            prover_message,
+            0,
+        )
+    }
+
+    /// Folds `k` instances in one round, in the manner of ProtoGalaxy: the instances are
+    /// interpolated at the points `0, ..., k - 1` and evaluating the gate equation on the
+    /// interpolated trace gives a polynomial of degree `d (k - 1)`. Subtracting the interpolated
+    /// slack vectors leaves a polynomial vanishing on the points of the instances, whose quotient
+    /// `K` by the vanishing polynomial has degree `d (k - 1) - k`. The prover commits to the
+    /// `(d - 1)(k - 1)` evaluations of `K` on the next points, as many commitments as `k - 1`
+    /// pairwise folds would take together.
+    fn prover_many<R: Rng>(
+        public_parameters: &Self::PublicParameters,
+        prover_key: &Self::ProverKey,
+        instances: &[Self::Instance],
+        witnesses: &[Self::Witness],
+        rng: &mut R,
+    ) -> Result<(Self::Instance, Self::Witness, Self::ProverMessage), SangriaError> {
+        if instances.is_empty() || instances.len() != witnesses.len() {
+            return Err(SangriaError::InvalidWitness);
+        }
+        for instance in instances {
+            instance.check_extra_commitments(
+                prover_key.verifier_key.instance_schema.extra_commitments,
+            )?;
+        }
+
+        let circuit = &prover_key.circuit;
+        let number_of_public_inputs = public_parameters.number_of_public_inputs;
+        let quotient_chunks = QuotientChunks {
+            circuit,
+            traces: instances
+                .iter()
+                .zip(witnesses)
+                .map(|(instance, witness)| {
+                    TraceRows::new(instance, witness, number_of_public_inputs, circuit)
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            degree: public_parameters.config.gate_degree,
+            chunk_rows: CROSS_TERM_CHUNK_ROWS,
+        };
+        let quotient_hidings: Vec<F> = (0..quotient_chunks.number_of_quotients())
+            .map(|_| {
+                if public_parameters.config.zero_knowledge {
+                    F::rand(rng)
+                } else {
+                    F::zero()
+                }
+            })
+            .collect();
+        let quotient_commitments = commit_in_chunks::<F, Comm::CommitmentSlack>(
+            &public_parameters.commit_key_selectors_and_slack,
+            &quotient_hidings,
+            quotient_chunks.chunks(),
+            |rows| quotient_chunks.evaluate(rows),
+        )?;
+
+        // the challenge is squeezed from the same transcript as in the verifier
+        let mut sponge = RO::start(&public_parameters.poseidon_constants);
+
+        sponge.absorb(&prover_key.verifier_key);
+        sponge.absorb(&F::from(instances.len() as u64));
+        sponge.absorb(&instances);
+        sponge.absorb(&quotient_commitments);
+        let challenge =
+            FoldChallenge::squeeze(&mut sponge, public_parameters.config.challenge_bits, 0);
+
+        let folded_witness = RelaxedPLONKWitness::fold_many(
+            witnesses,
+            quotient_chunks
+                .chunks()
+                .map(|rows| (rows.clone(), quotient_chunks.evaluate(rows))),
+            &quotient_hidings,
+            challenge.value(),
+        );
+        let folded_instance =
+            RelaxedPLONKInstance::fold_many(instances, &quotient_commitments, challenge)?;
+
+        Ok((folded_instance, folded_witness, quotient_commitments))
+    }
+
+    fn verifier_many(
+        public_parameters: &Self::PublicParameters,
+        verifier_key: &Self::VerifierKey,
+        instances: &[Self::Instance],
+        prover_message: &Self::ProverMessage,
+    ) -> Result<Self::Instance, SangriaError> {
+        for instance in instances {
+            instance.check_public_input_rows(public_parameters.number_of_public_inputs)?;
+            instance.check_extra_commitments(verifier_key.instance_schema.extra_commitments)?;
+        }
+        if instances.is_empty()
+            || prover_message.len()
+                != (public_parameters.config.gate_degree - 1) * (instances.len() - 1)
+        {
+            return Err(SangriaError::InvalidProof);
+        }
+
+        let mut sponge = RO::start(&public_parameters.poseidon_constants);
+
+        sponge.absorb(&verifier_key);
+        sponge.absorb(&F::from(instances.len() as u64));
+        sponge.absorb(&instances);
+        sponge.absorb(&prover_message);
+        let challenge =
+            FoldChallenge::squeeze(&mut sponge, public_parameters.config.challenge_bits, 0);
+
+        RelaxedPLONKInstance::fold_many(instances, prover_message, challenge)
+    }
+}
+
+impl<F, Comm, RO> PLONKFoldingScheme<F, Comm, RO>
+where
+    F: PrimeField + Absorb,
+    Comm: FoldingCommitmentConfig<F>,
+    RO: Transcript<F> + FieldBasedCryptographicSponge<F>,
+{
+    /// Runs the NIFS verifier like [`Self::verifier_in_round`], and also returns a record of the
+    /// transcript and of the fold. The record lists field elements, so it is only
+    /// available for transcripts over the field of the instances.
+    pub fn verifier_with_audit(
+        public_parameters: &PublicParameters<F, Comm>,
+        verifier_key: &VerifierKey<F, Comm>,
+        left_instance: &RelaxedPLONKInstance<F, Comm>,
+        right_instance: &RelaxedPLONKInstance<F, Comm>,
+        cross_term_commitments: &[<Comm::CommitmentSlack as HomomorphicCommitmentScheme<F>>::Commitment],
+        round: usize,
+    ) -> Result<FoldAudit<F, Comm>, SangriaError> {
+        left_instance.check_public_input_rows(public_parameters.number_of_public_inputs)?;
+        right_instance.check_public_input_rows(public_parameters.number_of_public_inputs)?;
+        left_instance.check_extra_commitments(verifier_key.instance_schema.extra_commitments)?;
+        right_instance.check_extra_commitments(verifier_key.instance_schema.extra_commitments)?;
+        check_cross_term_count(public_parameters, cross_term_commitments)?;
+
+        let absorbed = vec![
+            (
+                "verifier key",
+                verifier_key.to_sponge_field_elements_as_vec(),
+            ),
+            (
+                "left instance",
+                left_instance.to_sponge_field_elements_as_vec(),
+            ),
+            (
+                "right instance",
+                right_instance.to_sponge_field_elements_as_vec(),
+            ),
+            (
+                "cross term commitments",
+                cross_term_commitments
+                    .to_vec()
+                    .to_sponge_field_elements_as_vec(),
+            ),
+        ];
+        let mut sponge = RO::start(&public_parameters.poseidon_constants);
+        for (_, elements) in absorbed.iter() {
+            sponge.absorb(elements);
+        }
+        let challenge =
+            FoldChallenge::squeeze(&mut sponge, public_parameters.config.challenge_bits, round);
+
+        Ok(FoldAudit {
+            absorbed,
+            challenge: challenge.value(),
+            round: challenge.round(),
+            transcript_digest: challenge.transcript_digest(),
+            folded_instance: left_instance.fold(
+                right_instance,
+                cross_term_commitments,
+                challenge,
+            )?,
+        })
+    }
+}
+
+impl<F, Comm, RO> PLONKFoldingScheme<F, Comm, RO>
+where
+    F: PrimeField + Absorb,
+    Comm: FoldingCommitmentConfig<F>,
+    RO: Transcript<F>,
+{
+    /// Runs the NIFS prover like [`NonInteractiveFoldingScheme::prover`], for the fold numbered
+    /// `round` by the caller, e.g. the index of the step folded into an IVC proof. The round is
+    /// recorded in the [`FoldChallenge`] and does not change the challenge.
+    #[allow(clippy::type_complexity, clippy::too_many_arguments)]
+    pub fn prover_in_round<R: Rng>(
+        public_parameters: &PublicParameters<F, Comm>,
+        prover_key: &ProverKey<F, Comm>,
+        left_instance: &RelaxedPLONKInstance<F, Comm>,
+        left_witness: &RelaxedPLONKWitness<F>,
+        right_instance: &RelaxedPLONKInstance<F, Comm>,
+        right_witness: &RelaxedPLONKWitness<F>,
+        round: usize,
+        rng: &mut R,
+    ) -> Result<
+        (
+            RelaxedPLONKInstance<F, Comm>,
+            RelaxedPLONKWitness<F>,
+            Vec<<Comm::CommitmentSlack as HomomorphicCommitmentScheme<F>>::Commitment>,
+        ),
+        SangriaError,
+    > {
+        Self::fold_pairs(
+            public_parameters,
+            &prover_key.verifier_key,
+            &prover_key.circuit,
+            (left_instance, left_witness),
+            (right_instance, right_witness),
+            round,
+            rng,
+        )
+    }
+
+    /// Runs the NIFS prover like [`Self::prover_in_round`], with the verifier key and the circuit of
+    /// the prover key given apart.
+    #[allow(clippy::type_complexity)]
+    pub(crate) fn fold_pairs<R: Rng>(
+        public_parameters: &PublicParameters<F, Comm>,
+        verifier_key: &VerifierKey<F, Comm>,
+        circuit: &PLONKCircuit<F>,
+        (left_instance, left_witness): (&RelaxedPLONKInstance<F, Comm>, &RelaxedPLONKWitness<F>),
+        (right_instance, right_witness): (&RelaxedPLONKInstance<F, Comm>, &RelaxedPLONKWitness<F>),
+        round: usize,
+        rng: &mut R,
+    ) -> Result<
+        (
+            RelaxedPLONKInstance<F, Comm>,
+            RelaxedPLONKWitness<F>,
+            Vec<<Comm::CommitmentSlack as HomomorphicCommitmentScheme<F>>::Commitment>,
+        ),
+        SangriaError,
+    > {
+        let extra_commitments = verifier_key.instance_schema.extra_commitments;
+        left_instance.check_extra_commitments(extra_commitments)?;
+        right_instance.check_extra_commitments(extra_commitments)?;
+
+        let number_of_public_inputs = public_parameters.number_of_public_inputs;
+        let cross_term_chunks = CrossTermChunks {
+            circuit,
+            left: TraceRows::new(
+                left_instance,
+                left_witness,
+                number_of_public_inputs,
+                circuit,
+            )?,
+            right: TraceRows::new(
+                right_instance,
+                right_witness,
+                number_of_public_inputs,
+                circuit,
+            )?,
+            degree: public_parameters.config.gate_degree,
+            chunk_rows: CROSS_TERM_CHUNK_ROWS,
+        };
+        let cross_term_hidings: Vec<F> = (1..public_parameters.config.gate_degree)
+            .map(|_| {
+                if public_parameters.config.zero_knowledge {
+                    F::rand(rng)
+                } else {
+                    F::zero()
+                }
+            })
+            .collect();
+        let cross_term_commitments = cross_term_chunks.commit::<Comm::CommitmentSlack>(
+            &public_parameters.commit_key_selectors_and_slack,
+            &cross_term_hidings,
+        )?;
+
+        // the challenge is squeezed from the same transcript as in the verifier
+        let mut sponge = RO::start(&public_parameters.poseidon_constants);
+
+        sponge.absorb(verifier_key);
+        sponge.absorb(&left_instance);
+        sponge.absorb(&right_instance);
+        sponge.absorb(&cross_term_commitments);
+        let challenge =
+            FoldChallenge::squeeze(&mut sponge, public_parameters.config.challenge_bits, round);
+
+        // the cross terms are evaluated again rather than kept, to fold the slack vector block by
+        // block
+        let folded_witness = left_witness.fold(
+            right_witness,
+            cross_term_chunks
+                .chunks()
+                .map(|rows| cross_term_chunks.evaluate(rows)),
+            &cross_term_hidings,
+            challenge.value(),
+        );
+        let folded_instance =
+            left_instance.fold(right_instance, &cross_term_commitments, challenge)?;
+
+        Ok((folded_instance, folded_witness, cross_term_commitments))
+    }
+
+    /// Runs the NIFS verifier like [`NonInteractiveFoldingScheme::verifier`], for the fold numbered
+    /// `round` by the caller (see [`Self::prover_in_round`]).
+    pub fn verifier_in_round(
+        public_parameters: &PublicParameters<F, Comm>,
+        verifier_key: &VerifierKey<F, Comm>,
+        left_instance: &RelaxedPLONKInstance<F, Comm>,
+        right_instance: &RelaxedPLONKInstance<F, Comm>,
+        prover_message: &Vec<<Comm::CommitmentSlack as HomomorphicCommitmentScheme<F>>::Commitment>,
+        round: usize,
+    ) -> Result<RelaxedPLONKInstance<F, Comm>, SangriaError> {
+        left_instance.check_public_input_rows(public_parameters.number_of_public_inputs)?;
+        right_instance.check_public_input_rows(public_parameters.number_of_public_inputs)?;
+        left_instance.check_extra_commitments(verifier_key.instance_schema.extra_commitments)?;
+        right_instance.check_extra_commitments(verifier_key.instance_schema.extra_commitments)?;
+        check_cross_term_count(public_parameters, prover_message)?;
+
+        let mut sponge = RO::start(&public_parameters.poseidon_constants);
+
+        sponge.absorb(&verifier_key);
+        sponge.absorb(&left_instance);
+        sponge.absorb(&right_instance);
+        sponge.absorb(&prover_message);
+        let challenge =
+            FoldChallenge::squeeze(&mut sponge, public_parameters.config.challenge_bits, round);
+
+        left_instance.fold(right_instance, prover_message, challenge)
+    }
+
+    /// Encodes the circuit like [`NonInteractiveFoldingScheme::encode`], for instances carrying
+    /// `extra_commitments` application-defined extra commitments (see
+    /// [`RelaxedPLONKInstance::with_extra_commitments`]). The NIFS prover and verifier then reject
+    /// instances with another number of extra commitments.
+    #[allow(clippy::type_complexity)]
+    pub fn encode_with_extra_commitments<R: Rng>(
+        pp: &PublicParameters<F, Comm>,
+        circuit: &PLONKCircuit<F>,
+        extra_commitments: usize,
+        rng: &mut R,
+    ) -> Result<(ProverKey<F, Comm>, VerifierKey<F, Comm>), SangriaError> {
+        check_commit_key_sizes(pp, circuit)?;
+
+        let randomness_c = if pp.config.zero_knowledge {
+            F::rand(rng)
+        } else {
+            F::zero()
+        };
 
         let c_selector = circuit.single_selector(CONSTANT_SELECTOR_INDEX)?;
         let commitment_q_c = <Comm::CommitmentSlack as HomomorphicCommitmentScheme<F>>::commit(
@@ -168,16 +1062,29 @@ where
             randomness_c,
         )?;
 
-        let mut sponge = PoseidonSponge::new(&pp.poseidon_constants);
+        let mut sponge = RO::start(&pp.poseidon_constants);
 
         sponge.absorb(circuit);
         sponge.absorb(pp);
         sponge.absorb(&randomness_c);
-        let transcript_seed = sponge.squeeze_native_field_elements(1);
+        sponge.absorb(&F::from(extra_commitments as u64));
+        let transcript_seed: Vec<F> = sponge.squeeze_field_elements(1);
 
         let vk: VerifierKey<F, Comm> = VerifierKey {
             selector_c_commitment: commitment_q_c,
             transcript_seed: transcript_seed[0],
+            instance_schema: InstanceSchema {
+                number_of_public_inputs: pp.number_of_public_inputs,
+                instance_columns: NUMBER_OF_WIRES,
+                instance_rows: pp.number_of_public_inputs + 1,
+                witness_commitments: NUMBER_OF_WIRES,
+                extra_commitments,
+                cross_term_commitments: pp.config.gate_degree - 1,
+            },
+            number_of_rows: circuit.number_of_rows(),
+            gate_degree: pp.config.gate_degree,
+            circuit_digest: blake3_digest(circuit),
+            transcript_config_digest: transcript_config_digest(pp),
         };
 
         let pk = ProverKey {
@@ -189,37 +1096,1872 @@ where
         Ok((pk, vk))
     }
 
-    fn prover(
-        _public_parameters: &Self::PublicParameters,
-        _prover_key: &Self::ProverKey,
-        _left_instance: &Self::Instance,
-        _left_witness: &Self::Witness,
-        _right_instance: &Self::Instance,
-        _right_witness: &Self::Witness,
-    ) -> Result<(Self::Instance, Self::Witness, Self::ProverMessage), SangriaError> {
-        todo!()
+    /// Commits to the columns of a fresh witness and returns its instance, built from the public
+    /// input and output rows given column by column.
+    pub fn commit_witness(
+        public_parameters: &PublicParameters<F, Comm>,
+        instance_columns: Vec<Vec<F>>,
+        witness: &RelaxedPLONKWitness<F>,
+    ) -> Result<RelaxedPLONKInstance<F, Comm>, SangriaError> {
+        let witness_commitments = Self::commit_witness_columns(public_parameters, witness)?;
+
+        Self::instance_with_commitments(public_parameters, instance_columns, witness_commitments)
     }
 
-    fn verifier(
-        public_parameters: &Self::PublicParameters,
-        verifier_key: &Self::VerifierKey,
-        left_instance: &Self::Instance,
-        right_instance: &Self::Instance,
-        prover_message: &Self::ProverMessage,
-    ) -> Result<Self::Instance, SangriaError> {
-        let mut sponge = PoseidonSponge::new(&public_parameters.poseidon_constants);
+    /// Commits to the witness columns of a fresh witness. A column a step leaves unused commits to
+    /// its hiding alone, without a multi-scalar multiplication over its zeros.
+    fn commit_witness_columns(
+        public_parameters: &PublicParameters<F, Comm>,
+        witness: &RelaxedPLONKWitness<F>,
+    ) -> Result<
+        Vec<<Comm::CommitmentWitness as HomomorphicCommitmentScheme<F>>::Commitment>,
+        SangriaError,
+    > {
+        let zero_columns = witness.zero_columns();
+        let hidings = witness.hiding_randomnesses();
+        let (witness_columns, column_hidings): (Vec<_>, Vec<_>) = (0..NUMBER_OF_WIRES)
+            .filter(|&column_index| !zero_columns[column_index])
+            .map(|column_index| witness.witness_column_with_rand(column_index))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .unzip();
+        let mut commitments =
+            <Comm::CommitmentWitness as HomomorphicCommitmentScheme<F>>::batch_commit(
+                &public_parameters.commit_key_witness,
+                &witness_columns,
+                &column_hidings,
+            )?
+            .into_iter();
+        (0..NUMBER_OF_WIRES)
+            .map(|column_index| {
+                if zero_columns[column_index] {
+                    <Comm::CommitmentWitness as HomomorphicCommitmentScheme<F>>::commit(
+                        &public_parameters.commit_key_witness,
+                        &[],
+                        hidings[column_index],
+                    )
+                } else {
+                    Ok(commitments
+                        .next()
+                        .expect("a commitment is returned for every nonzero column"))
+                }
+            })
+            .collect()
+    }
 
-        sponge.absorb(&verifier_key);
-        sponge.absorb(&left_instance);
-        sponge.absorb(&right_instance);
-        sponge.absorb(&prover_message);
-        let challenge: F = sponge.squeeze_field_elements(1)[0];
+    /// Commits to the witness column `column_index` of a fresh witness, so that the columns of
+    /// several witnesses can be committed to as independent tasks.
+    pub(crate) fn commit_witness_column(
+        public_parameters: &PublicParameters<F, Comm>,
+        witness: &RelaxedPLONKWitness<F>,
+        column_index: usize,
+    ) -> Result<<Comm::CommitmentWitness as HomomorphicCommitmentScheme<F>>::Commitment, SangriaError>
+    {
+        let (mut column, hiding) = witness.witness_column_with_rand(column_index)?;
+        // like in `commit_witness_columns`, an unused column commits to its hiding alone
+        if column.iter().all(F::is_zero) {
+            column.clear();
+        }
+        <Comm::CommitmentWitness as HomomorphicCommitmentScheme<F>>::commit(
+            &public_parameters.commit_key_witness,
+            &column,
+            hiding,
+        )
+    }
 
-        let folded_instance = right_instance.clone() * challenge + left_instance;
+    /// Returns the instance of a fresh witness from its public input and output rows, given column
+    /// by column, and the commitments to its witness columns.
+    pub(crate) fn instance_with_commitments(
+        public_parameters: &PublicParameters<F, Comm>,
+        instance_columns: Vec<Vec<F>>,
+        witness_commitments: Vec<
+            <Comm::CommitmentWitness as HomomorphicCommitmentScheme<F>>::Commitment,
+        >,
+    ) -> Result<RelaxedPLONKInstance<F, Comm>, SangriaError> {
+        let instance = RelaxedPLONKInstance::new(instance_columns, witness_commitments);
+        instance.check_public_input_rows(public_parameters.number_of_public_inputs)?;
 
-        Ok(folded_instance)
+        Ok(instance)
     }
-}
 
-#[cfg(test)]
-mod tests {}
+    /// Checks that `witness` satisfies the relaxed PLONK relation for `instance` and `circuit`: the
+    /// commitments of the instance open to the witness, every row satisfies the relaxed gate
+    /// equation `u^(d-1) (q_L a + q_R b + q_O c) + u^(d-2) q_M a b + q_P a^d + u^d q_C = E` of degree
+    /// `d` and the copy constraints hold. Returns an error otherwise.
+    pub fn check_relation(
+        public_parameters: &PublicParameters<F, Comm>,
+        circuit: &PLONKCircuit<F>,
+        instance: &RelaxedPLONKInstance<F, Comm>,
+        witness: &RelaxedPLONKWitness<F>,
+    ) -> Result<(), SangriaError> {
+        instance.check_public_input_rows(public_parameters.number_of_public_inputs)?;
+        let columns = trace_columns(instance, witness)?;
+        let slack_vector = witness.slack_vector();
+        if columns
+            .iter()
+            .any(|column| column.len() != circuit.number_of_rows())
+            || slack_vector.len() != circuit.number_of_rows()
+        {
+            return Err(SangriaError::InvalidWitness);
+        }
+
+        let witness_commitments = Self::commit_witness_columns(public_parameters, witness)?;
+        let slack_commitment = <Comm::CommitmentSlack as HomomorphicCommitmentScheme<F>>::commit(
+            &public_parameters.commit_key_selectors_and_slack,
+            &slack_vector,
+            witness.slack_hiding(),
+        )?;
+        if witness_commitments != instance.witness_commitments()
+            || slack_commitment != instance.slack_commitment()
+        {
+            return Err(SangriaError::InvalidWitness);
+        }
+
+        let gates = gate_evaluations(
+            &circuit.selectors(),
+            &columns,
+            instance.scaling_factor(),
+            public_parameters.config.gate_degree,
+        );
+        if let Some(row) = (0..gates.len()).find(|&row| gates[row] != slack_vector[row]) {
+            return Err(SangriaError::UnsatisfiedGate { row });
+        }
+
+        let wires: Vec<F> = columns.concat();
+        let copy_constraint = circuit.copy_constraint();
+        for (position, &next) in copy_constraint.iter().enumerate() {
+            let next = permutation_index(next, wires.len())?;
+            if wires[position] != wires[next] {
+                return Err(SangriaError::UnsatisfiedCopyConstraint { position, next });
+            }
+        }
+
+        check_public_io(
+            &copy_constraint,
+            &columns,
+            public_parameters.number_of_public_inputs,
+        )
+    }
+    /// Samples a uniformly random instance-witness pair satisfying the relaxed PLONK relation for
+    /// `circuit`: every cycle of copies holds a random value, except those of the public input and
+    /// output rows that must be zero, the scaling factor is random and the slack vector is the
+    /// evaluation of the gate equation. Folding a pair with it makes the witness of the folded pair
+    /// uniformly random among those respecting the copy constraints.
+    pub(crate) fn sample_satisfying_pair<R: Rng>(
+        public_parameters: &PublicParameters<F, Comm>,
+        verifier_key: &VerifierKey<F, Comm>,
+        circuit: &PLONKCircuit<F>,
+        rng: &mut R,
+    ) -> Result<(RelaxedPLONKInstance<F, Comm>, RelaxedPLONKWitness<F>), SangriaError> {
+        let number_of_rows = circuit.number_of_rows();
+        let number_of_public_inputs = public_parameters.number_of_public_inputs;
+        let copy_constraint = circuit.copy_constraint();
+        let size = NUMBER_OF_WIRES * number_of_rows;
+        if copy_constraint.len() != size || number_of_rows <= number_of_public_inputs {
+            return Err(SangriaError::InvalidConfig(
+                "the circuit does not match the public parameters",
+            ));
+        }
+        let is_instance_row =
+            |row: usize| row < number_of_public_inputs || row == number_of_rows - 1;
+
+        let mut wires: Vec<Option<F>> = vec![None; size];
+        for start in 0..size {
+            if wires[start].is_some() {
+                continue;
+            }
+            let mut cycle = vec![start];
+            let mut position = permutation_index(copy_constraint[start], size)?;
+            while position != start {
+                if cycle.len() == size {
+                    return Err(SangriaError::InvalidWitness);
+                }
+                cycle.push(position);
+                position = permutation_index(copy_constraint[position], size)?;
+            }
+
+            // like in `check_public_io`, only the first wire of an instance row carries IO, and
+            // only if it is copied into a gate row
+            let carries_io = cycle.iter().all(|&position| {
+                !is_instance_row(position % number_of_rows) || position < number_of_rows
+            }) && cycle
+                .iter()
+                .any(|&position| !is_instance_row(position % number_of_rows));
+            let value = if carries_io { F::rand(rng) } else { F::zero() };
+            for position in cycle {
+                wires[position] = Some(value);
+            }
+        }
+        let columns: Vec<Vec<F>> = wires
+            .chunks(number_of_rows)
+            .map(|column| column.iter().map(|wire| wire.unwrap_or_default()).collect())
+            .collect();
+
+        let scaling_factor = F::rand(rng);
+        let slack_vector = gate_evaluations(
+            &circuit.selectors(),
+            &columns,
+            scaling_factor,
+            public_parameters.config.gate_degree,
+        );
+        let slack_hiding = F::rand(rng);
+        let witness = RelaxedPLONKWitness::new(
+            columns
+                .iter()
+                .map(|column| column[number_of_public_inputs..number_of_rows - 1].to_vec())
+                .collect(),
+            slack_vector.clone(),
+            (0..NUMBER_OF_WIRES).map(|_| F::rand(rng)).collect(),
+        )
+        .with_slack_hiding(slack_hiding);
+        let instance_columns = columns
+            .iter()
+            .map(|column| {
+                let mut rows = column[..number_of_public_inputs].to_vec();
+                rows.push(column[number_of_rows - 1]);
+                rows
+            })
+            .collect();
+        let slack_commitment = <Comm::CommitmentSlack as HomomorphicCommitmentScheme<F>>::commit(
+            &public_parameters.commit_key_selectors_and_slack,
+            &slack_vector,
+            slack_hiding,
+        )?;
+        let extra_commitments = (0..verifier_key.instance_schema.extra_commitments)
+            .map(|_| {
+                <Comm::CommitmentWitness as HomomorphicCommitmentScheme<F>>::commit(
+                    &public_parameters.commit_key_witness,
+                    &[],
+                    F::rand(rng),
+                )
+            })
+            .collect::<Result<_, _>>()?;
+        let instance = Self::commit_witness(public_parameters, instance_columns, &witness)?
+            .with_slack_commitment(scaling_factor, slack_commitment)
+            .with_extra_commitments(extra_commitments);
+
+        Ok((instance, witness))
+    }
+}
+
+impl<F, Comm, RO> PLONKFoldingScheme<F, Comm, RO>
+where
+    F: PrimeField + Absorb,
+    Comm: FoldingCommitmentConfig<F>,
+    RO: Transcript<F>,
+    Comm::CommitmentWitness: InnerProductOpening<F>,
+{
+    /// Opens the wire at (`column`, `row`) of the trace of `witness`, whose commitments are those of
+    /// `instance`. Only the gate rows are committed; the public input and output rows are part of
+    /// the instance. The wires of a folded witness hold random combinations of the values of the
+    /// witnesses folded into it, so outputs of a computation are revealed from its fresh witness.
+    pub fn open_wire(
+        public_parameters: &PublicParameters<F, Comm>,
+        instance: &RelaxedPLONKInstance<F, Comm>,
+        witness: &RelaxedPLONKWitness<F>,
+        column: usize,
+        row: usize,
+    ) -> Result<WireOpening<F, Comm>, SangriaError> {
+        instance.check_public_input_rows(public_parameters.number_of_public_inputs)?;
+        let unit = wire_selector(public_parameters, column, row)?;
+        let (values, hiding) = witness.witness_column_with_rand(column)?;
+        let (value, proof) = <Comm::CommitmentWitness as InnerProductOpening<F>>::open(
+            &public_parameters.commit_key_witness,
+            &values,
+            hiding,
+            &unit,
+        )?;
+
+        Ok(WireOpening {
+            column,
+            row,
+            value,
+            proof,
+        })
+    }
+
+    /// Checks that the witness committed in `instance` holds the revealed value at the position of
+    /// `opening`, or returns an error.
+    pub fn verify_wire_opening(
+        public_parameters: &PublicParameters<F, Comm>,
+        instance: &RelaxedPLONKInstance<F, Comm>,
+        opening: &WireOpening<F, Comm>,
+    ) -> Result<(), SangriaError> {
+        instance.check_public_input_rows(public_parameters.number_of_public_inputs)?;
+        let unit = wire_selector(public_parameters, opening.column, opening.row)?;
+
+        <Comm::CommitmentWitness as InnerProductOpening<F>>::verify_opening(
+            &public_parameters.commit_key_witness,
+            &instance.single_witness_commitment(opening.column)?,
+            &unit,
+            opening.value,
+            &opening.proof,
+        )
+    }
+}
+
+/// Returns the unit vector selecting the wire at (`column`, `row`) of the trace from its witness
+/// column, or an error if the wire is not in a gate row.
+fn wire_selector<F, Comm>(
+    pp: &PublicParameters<F, Comm>,
+    column: usize,
+    row: usize,
+) -> Result<Vec<F>, SangriaError>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    let gate = row
+        .checked_sub(pp.number_of_public_inputs)
+        .filter(|&gate| gate < pp.number_of_gates && column < NUMBER_OF_WIRES)
+        .ok_or(SangriaError::IndexOutOfBounds)?;
+    let mut unit = vec![F::zero(); pp.number_of_gates];
+    unit[gate] = F::one();
+
+    Ok(unit)
+}
+
+/// Assembles the full trace columns of an instance-witness pair: the public input rows of the
+/// instance, the gate rows of the witness and the output row of the instance.
+pub(crate) fn trace_columns<F, Comm>(
+    instance: &RelaxedPLONKInstance<F, Comm>,
+    witness: &RelaxedPLONKWitness<F>,
+) -> Result<Vec<Vec<F>>, SangriaError>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    (0..NUMBER_OF_WIRES)
+        .map(|column_index| {
+            let mut column = instance.instance_column(column_index)?;
+            let output = column.pop().ok_or(SangriaError::PublicInputMismatch)?;
+            column.extend(witness.witness_column(column_index)?);
+            column.push(output);
+            Ok(column)
+        })
+        .collect()
+}
+
+/// Checks that the public inputs and the output carried by the instance rows of the trace `columns`
+/// agree with the witness: the first wire of every instance row must be copied into a wire of a gate
+/// row holding the same value, or be zero if it is copied nowhere, and its other wires must be zero,
+/// so that an instance cannot advertise other IO than the one its witness computes on. Returns
+/// [`SangriaError::PublicInputMismatch`] otherwise.
+fn check_public_io<F: PrimeField>(
+    copy_constraint: &[F],
+    columns: &[Vec<F>],
+    number_of_public_inputs: usize,
+) -> Result<(), SangriaError> {
+    let number_of_rows = columns[0].len();
+    let wires: Vec<F> = columns.concat();
+    let gate_rows = number_of_public_inputs..number_of_rows - 1;
+
+    for row in (0..number_of_public_inputs).chain([number_of_rows - 1]) {
+        if columns[1..].iter().any(|column| !column[row].is_zero()) {
+            return Err(SangriaError::PublicInputMismatch);
+        }
+
+        // walk the copy cycle of the wire until it reaches a gate row or comes back to the wire
+        let mut position = row;
+        let copied = (0..wires.len()).find_map(|_| {
+            permutation_index(copy_constraint[position], wires.len())
+                .map(|next| {
+                    position = next;
+                    (position == row || gate_rows.contains(&(position % number_of_rows)))
+                        .then_some(position)
+                })
+                .transpose()
+        });
+        match copied {
+            Some(Ok(position)) if position != row && wires[position] == wires[row] => {}
+            // a wire copied nowhere, like the unused output row of a built circuit, carries no IO
+            Some(Ok(position)) if position == row && wires[row].is_zero() => {}
+            Some(Err(error)) => return Err(error),
+            _ => return Err(SangriaError::PublicInputMismatch),
+        }
+    }
+
+    Ok(())
+}
+
+/// The number of trace rows whose cross terms the prover evaluates and commits to at once.
+const CROSS_TERM_CHUNK_ROWS: usize = 1 << 14;
+
+/// The trace of an instance-witness pair, read a block of rows at a time without copying it whole.
+struct TraceRows<'a, F: PrimeField> {
+    instance_columns: Vec<Vec<F>>,
+    witness: &'a RelaxedPLONKWitness<F>,
+    scaling_factor: F,
+    /// Whether each column is zero on every row, public input and output rows included.
+    zero_columns: [bool; NUMBER_OF_WIRES],
+}
+
+impl<'a, F: PrimeField> TraceRows<'a, F> {
+    /// Reads the trace of `instance` and `witness`, or returns an error if they do not span the
+    /// rows of `circuit`.
+    fn new<Comm: FoldingCommitmentConfig<F>>(
+        instance: &RelaxedPLONKInstance<F, Comm>,
+        witness: &'a RelaxedPLONKWitness<F>,
+        number_of_public_inputs: usize,
+        circuit: &PLONKCircuit<F>,
+    ) -> Result<Self, SangriaError> {
+        instance.check_public_input_rows(number_of_public_inputs)?;
+        let (number_of_gates, slack_len) = witness.dimensions();
+        if number_of_public_inputs + number_of_gates + 1 != circuit.number_of_rows()
+            || slack_len != circuit.number_of_rows()
+        {
+            return Err(SangriaError::InvalidWitness);
+        }
+
+        let instance_columns = (0..NUMBER_OF_WIRES)
+            .map(|column_index| instance.instance_column(column_index))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut zero_columns = witness.zero_columns();
+        for (zero, instance_column) in zero_columns.iter_mut().zip(&instance_columns) {
+            *zero &= instance_column.iter().all(F::is_zero);
+        }
+
+        Ok(Self {
+            instance_columns,
+            witness,
+            scaling_factor: instance.scaling_factor(),
+            zero_columns,
+        })
+    }
+
+    /// Returns the trace columns on `rows`, like [`trace_columns`] restricted to `rows`.
+    fn columns(&self, rows: Range<usize>) -> Vec<Vec<F>> {
+        let public_inputs = self.instance_columns[0].len() - 1;
+        let output = public_inputs + self.witness.dimensions().0;
+        let gates = rows.start.clamp(public_inputs, output) - public_inputs
+            ..rows.end.clamp(public_inputs, output) - public_inputs;
+
+        self.instance_columns
+            .iter()
+            .enumerate()
+            .map(|(column_index, instance_column)| {
+                let mut column = instance_column
+                    [rows.start.min(public_inputs)..rows.end.min(public_inputs)]
+                    .to_vec();
+                column.extend(
+                    self.witness
+                        .witness_column_rows(column_index, gates.clone()),
+                );
+                if rows.contains(&output) {
+                    column.push(instance_column[public_inputs]);
+                }
+                column
+            })
+            .collect()
+    }
+}
+
+/// The cross terms of two instance-witness pairs, evaluated a block of rows at a time so that the
+/// prover holds at most one block of them, on top of the witnesses being folded.
+struct CrossTermChunks<'a, F: PrimeField> {
+    circuit: &'a PLONKCircuit<F>,
+    left: TraceRows<'a, F>,
+    right: TraceRows<'a, F>,
+    degree: usize,
+    chunk_rows: usize,
+}
+
+impl<'a, F: PrimeField> CrossTermChunks<'a, F> {
+    /// Returns the consecutive blocks of rows the cross terms are evaluated on.
+    fn chunks(&self) -> impl Iterator<Item = Range<usize>> {
+        row_chunks(self.circuit.number_of_rows(), self.chunk_rows)
+    }
+
+    /// Evaluates the cross terms `T_1, ..., T_(d-1)` on `rows`.
+    fn evaluate(&self, rows: Range<usize>) -> Vec<Vec<F>> {
+        cross_terms(
+            &self.circuit.selector_rows(rows.clone()),
+            &self.left.columns(rows.clone()),
+            self.left.scaling_factor,
+            &self.right.columns(rows),
+            self.right.scaling_factor,
+            self.right.zero_columns,
+            self.degree,
+        )
+    }
+
+    /// Commits to the cross terms block by block, blinding the i-th one with `hidings[i]`.
+    fn commit<C: HomomorphicCommitmentScheme<F>>(
+        &self,
+        commit_key: &C::CommitKey,
+        hidings: &[F],
+    ) -> Result<Vec<C::Commitment>, SangriaError> {
+        commit_in_chunks::<F, C>(commit_key, hidings, self.chunks(), |rows| {
+            self.evaluate(rows)
+        })
+    }
+}
+
+/// The quotient terms of several instance-witness pairs folded at once (see
+/// [`NonInteractiveFoldingScheme::prover_many`]), evaluated a block of rows at a time like
+/// [`CrossTermChunks`].
+struct QuotientChunks<'a, F: PrimeField> {
+    circuit: &'a PLONKCircuit<F>,
+    traces: Vec<TraceRows<'a, F>>,
+    degree: usize,
+    chunk_rows: usize,
+}
+
+impl<'a, F: PrimeField> QuotientChunks<'a, F> {
+    /// Returns the consecutive blocks of rows the quotient terms are evaluated on.
+    fn chunks(&self) -> impl Iterator<Item = Range<usize>> {
+        row_chunks(self.circuit.number_of_rows(), self.chunk_rows)
+    }
+
+    /// Returns the number of quotient terms, `(d - 1)(k - 1)` for `k` traces.
+    fn number_of_quotients(&self) -> usize {
+        (self.degree - 1) * (self.traces.len() - 1)
+    }
+
+    /// Evaluates the quotient terms `K_t` on `rows`, at the points following those of the traces.
+    fn evaluate(&self, rows: Range<usize>) -> Vec<Vec<F>> {
+        let selectors = self.circuit.selector_rows(rows.clone());
+        let blocks: Vec<TraceBlock<F>> = self
+            .traces
+            .iter()
+            .map(|trace| TraceBlock {
+                columns: trace.columns(rows.clone()),
+                scaling_factor: trace.scaling_factor,
+                slack: trace.witness.slack_rows(rows.clone()),
+            })
+            .collect();
+
+        let number_of_traces = self.traces.len();
+        (number_of_traces..number_of_traces + self.number_of_quotients())
+            .map(|point| {
+                let point = F::from(point as u64);
+                let basis = lagrange_basis(0..number_of_traces, point);
+                let vanishing_inverse = (0..number_of_traces)
+                    .map(|other| point - F::from(other as u64))
+                    .product::<F>()
+                    .inverse()
+                    .expect("the points of the quotients are not points of the traces");
+                let interpolate = |value: &dyn Fn(&TraceBlock<F>) -> F| -> F {
+                    blocks
+                        .iter()
+                        .zip(&basis)
+                        .map(|(block, &basis)| basis * value(block))
+                        .sum()
+                };
+
+                let columns: Vec<Vec<F>> = (0..NUMBER_OF_WIRES)
+                    .map(|column| {
+                        (0..rows.len())
+                            .map(|row| interpolate(&|block| block.columns[column][row]))
+                            .collect()
+                    })
+                    .collect();
+                let scaling_factor = interpolate(&|block| block.scaling_factor);
+                gate_evaluations(&selectors, &columns, scaling_factor, self.degree)
+                    .into_iter()
+                    .enumerate()
+                    .map(|(row, gate)| {
+                        (gate - interpolate(&|block| block.slack[row])) * vanishing_inverse
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// A block of rows of the trace and slack vector of an instance-witness pair.
+struct TraceBlock<F> {
+    columns: Vec<Vec<F>>,
+    scaling_factor: F,
+    slack: Vec<F>,
+}
+
+/// Returns the consecutive blocks of at most `chunk_rows` rows covering `number_of_rows` rows.
+fn row_chunks(number_of_rows: usize, chunk_rows: usize) -> impl Iterator<Item = Range<usize>> {
+    (0..number_of_rows)
+        .step_by(chunk_rows)
+        .map(move |start| start..number_of_rows.min(start + chunk_rows))
+}
+
+/// Commits to vectors block by block, blinding the i-th one with `hidings[i]`: `evaluate` returns
+/// the entries of every vector on a block of rows.
+fn commit_in_chunks<F: PrimeField, C: HomomorphicCommitmentScheme<F>>(
+    commit_key: &C::CommitKey,
+    hidings: &[F],
+    chunks: impl Iterator<Item = Range<usize>>,
+    evaluate: impl Fn(Range<usize>) -> Vec<Vec<F>>,
+) -> Result<Vec<C::Commitment>, SangriaError> {
+    let mut commitments = hidings
+        .iter()
+        .map(|&hiding| C::commit(commit_key, &[], hiding))
+        .collect::<Result<Vec<_>, _>>()?;
+    for rows in chunks {
+        let offset = rows.start;
+        for (commitment, vector) in commitments.iter_mut().zip(evaluate(rows)) {
+            *commitment = *commitment + C::commit_chunk(commit_key, offset, &vector)?;
+        }
+    }
+
+    Ok(commitments)
+}
+
+/// Returns the linear part `q_L a + q_R b + q_O c` of the gate equation on `row`.
+fn linear_gate<F: PrimeField>(selectors: &[Vec<F>], columns: &[Vec<F>], row: usize) -> F {
+    selectors[LEFT_SELECTOR_INDEX][row] * columns[0][row]
+        + selectors[RIGHT_SELECTOR_INDEX][row] * columns[1][row]
+        + selectors[OUTPUT_SELECTOR_INDEX][row] * columns[2][row]
+}
+
+/// Evaluates the relaxed gate equation of degree `d`, `u^(d-1) (q_L a + q_R b + q_O c) + u^(d-2) q_M
+/// a b + q_P a^d + u^d q_C`, on every row of the trace.
+pub(crate) fn gate_evaluations<F: PrimeField>(
+    selectors: &[Vec<F>],
+    columns: &[Vec<F>],
+    u: F,
+    degree: usize,
+) -> Vec<F> {
+    let u_powers: Vec<F> = ark_std::iter::successors(Some(F::one()), |&power| Some(power * u))
+        .take(degree + 1)
+        .collect();
+
+    (0..columns[0].len())
+        .map(|row| {
+            let a = columns[0][row];
+            u_powers[degree - 1] * linear_gate(selectors, columns, row)
+                + u_powers[degree - 2]
+                    * selectors[MULTIPLICATION_SELECTOR_INDEX][row]
+                    * a
+                    * columns[1][row]
+                + selectors[POWER_SELECTOR_INDEX][row] * a.pow([degree as u64])
+                + u_powers[degree] * selectors[CONSTANT_SELECTOR_INDEX][row]
+        })
+        .collect()
+}
+
+/// Multiplies two polynomials in the folding challenge, given by their coefficients.
+fn multiply<F: PrimeField>(left: &[F], right: &[F]) -> Vec<F> {
+    let mut product = vec![F::zero(); left.len() + right.len() - 1];
+    for (i, &left) in left.iter().enumerate() {
+        for (j, &right) in right.iter().enumerate() {
+            product[i + j] += left * right;
+        }
+    }
+
+    product
+}
+
+/// Returns the powers `0..=degree` of a polynomial in the folding challenge.
+fn powers<F: PrimeField>(polynomial: &[F], degree: usize) -> Vec<Vec<F>> {
+    ark_std::iter::successors(Some(vec![F::one()]), |power| {
+        Some(multiply(power, polynomial))
+    })
+    .take(degree + 1)
+    .collect()
+}
+
+/// Computes the cross terms `T_1, ..., T_(d-1)` of two traces, such that the gate equation of
+/// degree `d` on the trace folded with challenge `r` evaluates to `G(left) + r T_1 + ... +
+/// r^(d-1) T_(d-1) + r^d G(right)`.
+///
+/// The columns flagged in `right_zero_columns` are zero on every row of the right trace, as when a
+/// step leaves a wire unused: their wires of the folded trace are constant in `r`, so the products
+/// involving them are computed with fewer coefficients, and skipped from the linear part.
+fn cross_terms<F: PrimeField>(
+    selectors: &[Vec<F>],
+    left_columns: &[Vec<F>],
+    left_u: F,
+    right_columns: &[Vec<F>],
+    right_u: F,
+    right_zero_columns: [bool; NUMBER_OF_WIRES],
+    degree: usize,
+) -> Vec<Vec<F>> {
+    // every wire and the scaling factor of the folded trace are linear in `r`
+    let u_powers = powers(&[left_u, right_u], degree);
+    let mut cross_terms = vec![Vec::with_capacity(left_columns[0].len()); degree - 1];
+    let wire_coefficients = |column: usize| 2 - usize::from(right_zero_columns[column]);
+    let right_linear_selectors: Vec<(usize, usize)> = [
+        LEFT_SELECTOR_INDEX,
+        RIGHT_SELECTOR_INDEX,
+        OUTPUT_SELECTOR_INDEX,
+    ]
+    .into_iter()
+    .enumerate()
+    .filter(|&(column, _)| !right_zero_columns[column])
+    .collect();
+
+    for row in 0..left_columns[0].len() {
+        let [a, b] = [0, 1].map(|column| [left_columns[column][row], right_columns[column][row]]);
+        let [a, b] = [(&a, 0), (&b, 1)].map(|(wire, column)| &wire[..wire_coefficients(column)]);
+        let linear = [
+            linear_gate(selectors, left_columns, row),
+            right_linear_selectors
+                .iter()
+                .map(|&(column, selector)| selectors[selector][row] * right_columns[column][row])
+                .sum(),
+        ];
+        let terms = [
+            (F::one(), multiply(&u_powers[degree - 1], &linear)),
+            (
+                selectors[MULTIPLICATION_SELECTOR_INDEX][row],
+                multiply(&u_powers[degree - 2], &multiply(a, b)),
+            ),
+            (
+                selectors[POWER_SELECTOR_INDEX][row],
+                powers(a, degree).swap_remove(degree),
+            ),
+            (
+                selectors[CONSTANT_SELECTOR_INDEX][row],
+                u_powers[degree].clone(),
+            ),
+        ];
+
+        for (k, cross_term) in cross_terms.iter_mut().enumerate() {
+            cross_term.push(
+                terms
+                    .iter()
+                    .filter_map(|(selector, polynomial)| {
+                        polynomial
+                            .get(k + 1)
+                            .map(|&coefficient| *selector * coefficient)
+                    })
+                    .sum(),
+            );
+        }
+    }
+
+    cross_terms
+}
+
+/// Checks that a prover message holds the `d - 1` cross term commitments of a gate equation of
+/// degree `d`, or returns an error.
+fn check_cross_term_count<F, Comm>(
+    pp: &PublicParameters<F, Comm>,
+    cross_term_commitments: &[<Comm::CommitmentSlack as HomomorphicCommitmentScheme<F>>::Commitment],
+) -> Result<(), SangriaError>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    if cross_term_commitments.len() + 1 != pp.config.gate_degree {
+        return Err(SangriaError::CrossTermCount {
+            expected: pp.config.gate_degree - 1,
+            found: cross_term_commitments.len(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Converts an entry of the copy constraint permutation back into a wire position, or returns an
+/// error if it does not encode a position smaller than `size`.
+pub(crate) fn permutation_index<F: PrimeField>(
+    entry: F,
+    size: usize,
+) -> Result<usize, SangriaError> {
+    let bigint = entry.into_bigint();
+    let limbs = bigint.as_ref();
+    if limbs[1..].iter().any(|&limb| limb != 0) || limbs[0] >= size as u64 {
+        return Err(SangriaError::InvalidWitness);
+    }
+
+    Ok(limbs[0] as usize)
+}
+
+/// Checks that the commit keys in the public parameters are long enough for the circuit: the
+/// selectors and slack vector span every row of the circuit while the witness columns only span the
+/// gate rows. Longer commit keys are accepted as they are, since shorter vectors are committed to as
+/// if padded with zeros.
+fn check_commit_key_sizes<F, Comm>(
+    pp: &PublicParameters<F, Comm>,
+    circuit: &PLONKCircuit<F>,
+) -> Result<(), SangriaError>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    let needed = circuit.number_of_rows();
+    let available = <Comm::CommitmentSlack as HomomorphicCommitmentScheme<F>>::max_len(
+        &pp.commit_key_selectors_and_slack,
+    );
+    if needed > available {
+        return Err(SangriaError::ParametersTooSmall { needed, available });
+    }
+
+    let needed = needed.saturating_sub(pp.number_of_public_inputs + 1);
+    let available = <Comm::CommitmentWitness as HomomorphicCommitmentScheme<F>>::max_len(
+        &pp.commit_key_witness,
+    );
+    if needed > available {
+        return Err(SangriaError::ParametersTooSmall { needed, available });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        cross_terms, gate_evaluations, trace_columns, CrossTermChunks, FoldChallenge,
+        InstanceSchema, KeyDiff, PLONKFoldingScheme, SetupInfo, TraceRows, VerifierKey,
+    };
+    use crate::{
+        trace::NUMBER_OF_SELECTORS,
+        utils::{
+            circuits::{fifth_power_plus_five, square_plus_five},
+            inner_product::{InnerProduct, InnerProductConfig},
+            poseidon::poseidon_parameters_for_test,
+            rand::sample_vector,
+        },
+        Blake3Transcript, HomomorphicCommitmentScheme, KeccakTranscript,
+        NonInteractiveFoldingScheme, RelaxedPLONKInstance, RelaxedPLONKWitness, RowTag,
+        SangriaConfig, SangriaError, TraceTable, Transcript, CONSTANT_SELECTOR_INDEX,
+        LEFT_SELECTOR_INDEX, MULTIPLICATION_SELECTOR_INDEX, OUTPUT_SELECTOR_INDEX,
+    };
+    use ark_bn254::Fr;
+    use ark_crypto_primitives::sponge::{poseidon::PoseidonSponge, CryptographicSponge};
+    use ark_ff::{Field, One, UniformRand, Zero};
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Compress, Validate};
+    use ark_std::test_rng;
+
+    type Scheme = PLONKFoldingScheme<Fr, InnerProductConfig, PoseidonSponge<Fr>>;
+    type SchemeWith<RO> = PLONKFoldingScheme<Fr, InnerProductConfig, RO>;
+
+    /// A function returning the trace of a circuit for a public input.
+    type TraceOf = fn(u64) -> TraceTable<Fr>;
+
+    #[test]
+    fn folded_pair_satisfies_the_relation() {
+        let rng = &mut test_rng();
+        let info = SetupInfo {
+            number_of_public_inputs: 1,
+            number_of_gates: 2,
+            config: SangriaConfig::default(),
+            poseidon_constants: poseidon_parameters_for_test(),
+        };
+        let pp = Scheme::setup(&info, rng).unwrap();
+
+        let pairs = [3u64, 7].map(|x| {
+            let trace = square_plus_five(x);
+            let instance_columns = trace.instance_columns();
+            let (circuit, witness) = trace.into_circuit_and_witness(Vec::new(), rng).unwrap();
+            let instance = Scheme::commit_witness(&pp, instance_columns, &witness).unwrap();
+            (circuit, instance, witness)
+        });
+        let (circuit, left_instance, left_witness) = pairs[0].clone();
+        let (_, right_instance, right_witness) = pairs[1].clone();
+        let (pk, vk) = Scheme::encode(&pp, &circuit, rng).unwrap();
+
+        let (instance, witness, cross_term_commitment) = Scheme::prover(
+            &pp,
+            &pk,
+            &left_instance,
+            &left_witness,
+            &right_instance,
+            &right_witness,
+            rng,
+        )
+        .unwrap();
+        let verifier_instance = Scheme::verifier(
+            &pp,
+            &vk,
+            &left_instance,
+            &right_instance,
+            &cross_term_commitment,
+        )
+        .unwrap();
+
+        assert!(verifier_instance == instance);
+        assert_eq!(
+            Scheme::check_relation(&pp, &circuit, &instance, &witness),
+            Ok(())
+        );
+
+        // the scaling factors are one, so the folded one is 1 + r
+        let r = instance.scaling_factor() - Fr::one();
+        assert_eq!(
+            instance.public_inputs(),
+            Ok(vec![Fr::from(3u64) + r * Fr::from(7u64)])
+        );
+
+        // a witness of the other trace does not open the first instance
+        assert_eq!(
+            Scheme::check_relation(&pp, &circuit, &left_instance, &right_witness),
+            Err(SangriaError::InvalidWitness)
+        );
+    }
+
+    #[test]
+    fn sampled_pairs_satisfy_the_relation() {
+        let rng = &mut test_rng();
+        for (trace_of, gate_degree) in
+            [(square_plus_five as TraceOf, 2), (fifth_power_plus_five, 5)]
+        {
+            let info = SetupInfo {
+                number_of_public_inputs: 1,
+                number_of_gates: 2,
+                config: SangriaConfig {
+                    gate_degree,
+                    ..Default::default()
+                },
+                poseidon_constants: poseidon_parameters_for_test(),
+            };
+            let pp = Scheme::setup(&info, rng).unwrap();
+            let (circuit, _) = trace_of(3)
+                .into_circuit_and_witness(Vec::new(), rng)
+                .unwrap();
+            let (_, vk) = Scheme::encode(&pp, &circuit, rng).unwrap();
+
+            let (instance, witness) =
+                Scheme::sample_satisfying_pair(&pp, &vk, &circuit, rng).unwrap();
+            assert_eq!(
+                Scheme::check_relation(&pp, &circuit, &instance, &witness),
+                Ok(())
+            );
+
+            // the pair is random
+            let (other_instance, _) =
+                Scheme::sample_satisfying_pair(&pp, &vk, &circuit, rng).unwrap();
+            assert!(other_instance != instance);
+        }
+    }
+
+    #[test]
+    fn commit_keys_longer_than_the_circuit_are_accepted() {
+        let rng = &mut test_rng();
+        let setup = |number_of_gates| {
+            let info = SetupInfo {
+                number_of_public_inputs: 1,
+                number_of_gates,
+                config: SangriaConfig::default(),
+                poseidon_constants: poseidon_parameters_for_test(),
+            };
+            Scheme::setup(&info, &mut test_rng()).unwrap()
+        };
+        let trace = square_plus_five(3);
+        let instance_columns = trace.instance_columns();
+        let (circuit, witness) = trace.into_circuit_and_witness(Vec::new(), rng).unwrap();
+
+        let pp = setup(1);
+        assert!(matches!(
+            Scheme::encode(&pp, &circuit, rng),
+            Err(SangriaError::ParametersTooSmall { .. })
+        ));
+
+        // keys for eight times as many gates commit to the shorter vectors of the circuit
+        let pp = setup(16);
+        let (pk, vk) = Scheme::encode(&pp, &circuit, rng).unwrap();
+        let instance = Scheme::commit_witness(&pp, instance_columns, &witness).unwrap();
+        let (folded_instance, folded_witness, cross_term_commitment) =
+            Scheme::prover(&pp, &pk, &instance, &witness, &instance, &witness, rng).unwrap();
+        let verifier_instance =
+            Scheme::verifier(&pp, &vk, &instance, &instance, &cross_term_commitment).unwrap();
+
+        assert!(verifier_instance == folded_instance);
+        assert_eq!(
+            Scheme::check_relation(&pp, &circuit, &folded_instance, &folded_witness),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn extra_commitments_fold_linearly() {
+        let rng = &mut test_rng();
+        let info = SetupInfo {
+            number_of_public_inputs: 1,
+            number_of_gates: 2,
+            config: SangriaConfig::default(),
+            poseidon_constants: poseidon_parameters_for_test(),
+        };
+        let pp = Scheme::setup(&info, rng).unwrap();
+
+        let pairs = [3u64, 7].map(|x| {
+            let trace = square_plus_five(x);
+            let instance_columns = trace.instance_columns();
+            let (circuit, witness) = trace.into_circuit_and_witness(Vec::new(), rng).unwrap();
+            let instance = Scheme::commit_witness(&pp, instance_columns, &witness)
+                .unwrap()
+                .with_extra_commitments(vec![Fr::rand(rng)]);
+            (circuit, instance, witness)
+        });
+        let (circuit, left_instance, left_witness) = pairs[0].clone();
+        let (_, right_instance, right_witness) = pairs[1].clone();
+        let (pk, vk) = Scheme::encode_with_extra_commitments(&pp, &circuit, 1, rng).unwrap();
+
+        let (instance, witness, cross_term_commitments) = Scheme::prover_in_round(
+            &pp,
+            &pk,
+            &left_instance,
+            &left_witness,
+            &right_instance,
+            &right_witness,
+            5,
+            rng,
+        )
+        .unwrap();
+        let audit = Scheme::verifier_with_audit(
+            &pp,
+            &vk,
+            &left_instance,
+            &right_instance,
+            &cross_term_commitments,
+            5,
+        )
+        .unwrap();
+
+        assert!(audit.folded_instance == instance);
+        assert_eq!(audit.round, 5);
+        assert_eq!(
+            Scheme::check_relation(&pp, &circuit, &instance, &witness),
+            Ok(())
+        );
+        assert_eq!(
+            instance.extra_commitments(),
+            vec![
+                left_instance.extra_commitments()[0]
+                    + audit.challenge * right_instance.extra_commitments()[0]
+            ]
+        );
+
+        // both instances carry exactly as many extra commitments as the key declares
+        let bare_instance = right_instance.clone().with_extra_commitments(Vec::new());
+        let (_, bare_vk) = Scheme::encode(&pp, &circuit, rng).unwrap();
+        assert!(matches!(
+            Scheme::verifier(
+                &pp,
+                &vk,
+                &left_instance,
+                &bare_instance,
+                &cross_term_commitments
+            ),
+            Err(SangriaError::CommitmentCount {
+                expected: 1,
+                found: 0
+            })
+        ));
+        assert!(matches!(
+            Scheme::verifier(
+                &pp,
+                &bare_vk,
+                &left_instance,
+                &right_instance,
+                &cross_term_commitments
+            ),
+            Err(SangriaError::CommitmentCount {
+                expected: 0,
+                found: 1
+            })
+        ));
+        assert!(matches!(
+            Scheme::prover(
+                &pp,
+                &pk,
+                &left_instance,
+                &left_witness,
+                &bare_instance,
+                &right_witness,
+                rng,
+            ),
+            Err(SangriaError::CommitmentCount { .. })
+        ));
+        let mut sponge = PoseidonSponge::<Fr>::new(&poseidon_parameters_for_test());
+        let challenge = FoldChallenge::squeeze(&mut sponge, 128, 0);
+        assert!(matches!(
+            left_instance.fold(&bare_instance, &cross_term_commitments, challenge),
+            Err(SangriaError::CommitmentCount { .. })
+        ));
+    }
+
+    /// Folds the traces of `square_plus_five` for 3 and 7 with challenges derived from `RO`, checks
+    /// that the folded pair satisfies the relation and returns the folded scaling factor.
+    fn fold_with_transcript<RO: Transcript<Fr>>() -> Fr {
+        let rng = &mut test_rng();
+        let info = SetupInfo {
+            number_of_public_inputs: 1,
+            number_of_gates: 2,
+            config: SangriaConfig::default(),
+            poseidon_constants: poseidon_parameters_for_test(),
+        };
+        let pp = SchemeWith::<RO>::setup(&info, rng).unwrap();
+
+        let pairs = [3u64, 7].map(|x| {
+            let trace = square_plus_five(x);
+            let instance_columns = trace.instance_columns();
+            let (circuit, witness) = trace.into_circuit_and_witness(Vec::new(), rng).unwrap();
+            let instance =
+                SchemeWith::<RO>::commit_witness(&pp, instance_columns, &witness).unwrap();
+            (circuit, instance, witness)
+        });
+        let (circuit, left_instance, left_witness) = pairs[0].clone();
+        let (_, right_instance, right_witness) = pairs[1].clone();
+        let (pk, vk) = SchemeWith::<RO>::encode(&pp, &circuit, rng).unwrap();
+
+        let (instance, witness, cross_term_commitments) = SchemeWith::<RO>::prover(
+            &pp,
+            &pk,
+            &left_instance,
+            &left_witness,
+            &right_instance,
+            &right_witness,
+            rng,
+        )
+        .unwrap();
+        let verifier_instance = SchemeWith::<RO>::verifier(
+            &pp,
+            &vk,
+            &left_instance,
+            &right_instance,
+            &cross_term_commitments,
+        )
+        .unwrap();
+
+        assert!(verifier_instance == instance);
+        assert_eq!(
+            SchemeWith::<RO>::check_relation(&pp, &circuit, &instance, &witness),
+            Ok(())
+        );
+
+        instance.scaling_factor()
+    }
+
+    #[test]
+    fn hash_transcripts_derive_folding_challenges() {
+        let poseidon = fold_with_transcript::<PoseidonSponge<Fr>>();
+        let blake3 = fold_with_transcript::<Blake3Transcript>();
+        let keccak = fold_with_transcript::<KeccakTranscript>();
+
+        assert_ne!(poseidon, blake3);
+        assert_ne!(blake3, keccak);
+    }
+
+    /// Encodes the circuit of `trace` for parameters with three gates, without blinding so that the
+    /// key only depends on the circuit and the configuration.
+    fn unblinded_verifier_key(
+        config: SangriaConfig,
+        trace: TraceTable<Fr>,
+    ) -> VerifierKey<Fr, InnerProductConfig> {
+        let info = SetupInfo {
+            number_of_public_inputs: 1,
+            number_of_gates: 3,
+            config: SangriaConfig {
+                zero_knowledge: false,
+                ..config
+            },
+            poseidon_constants: poseidon_parameters_for_test(),
+        };
+        let pp = Scheme::setup(&info, &mut test_rng()).unwrap();
+        let circuit = trace.circuit(Vec::new()).unwrap();
+
+        Scheme::encode(&pp, &circuit, &mut test_rng()).unwrap().1
+    }
+
+    /// The trace of `y = x^2 + c`, whose output is copied from its gate unless `copy_output` is
+    /// false, padded with `padding` gates without selectors.
+    fn square_plus(c: u64, copy_output: bool, padding: usize) -> TraceTable<Fr> {
+        let (x, zero, one) = (Fr::from(3u64), Fr::zero(), Fr::one());
+        let mut square = [zero; NUMBER_OF_SELECTORS];
+        square[MULTIPLICATION_SELECTOR_INDEX] = one;
+        square[OUTPUT_SELECTOR_INDEX] = -one;
+        let mut add = [zero; NUMBER_OF_SELECTORS];
+        add[LEFT_SELECTOR_INDEX] = one;
+        add[OUTPUT_SELECTOR_INDEX] = -one;
+        add[CONSTANT_SELECTOR_INDEX] = Fr::from(c);
+        let y = x * x + Fr::from(c);
+
+        let mut trace = TraceTable::new();
+        trace.push_row(RowTag::PublicInput, [x, zero, zero]);
+        trace.push_row(RowTag::Gate(square), [x, x, x * x]);
+        trace.push_row(RowTag::Gate(add), [x * x, zero, y]);
+        for _ in 0..padding {
+            trace.push_row(RowTag::Gate([zero; NUMBER_OF_SELECTORS]), [zero; 3]);
+        }
+        trace.push_row(RowTag::Output, [y, zero, zero]);
+        trace.copy((0, 0), (0, 1));
+        trace.copy((0, 1), (1, 1));
+        trace.copy((2, 1), (0, 2));
+        if copy_output {
+            trace.copy((2, 2), (0, 3 + padding));
+        }
+        trace
+    }
+
+    #[test]
+    fn key_diffs_report_the_domain_size() {
+        let key = unblinded_verifier_key(SangriaConfig::default(), square_plus(5, true, 0));
+        assert!(key
+            .diff(&unblinded_verifier_key(
+                SangriaConfig::default(),
+                square_plus(5, true, 0)
+            ))
+            .is_empty());
+
+        let padded = unblinded_verifier_key(SangriaConfig::default(), square_plus(5, true, 1));
+        assert_eq!(
+            key.diff(&padded),
+            KeyDiff {
+                number_of_rows: true,
+                circuit: true,
+                transcript_seed: true,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn key_diffs_report_the_gate_degree() {
+        let key = unblinded_verifier_key(SangriaConfig::default(), square_plus(5, true, 0));
+        let config = SangriaConfig {
+            gate_degree: 3,
+            ..Default::default()
+        };
+        assert_eq!(
+            key.diff(&unblinded_verifier_key(config, square_plus(5, true, 0))),
+            KeyDiff {
+                gate_degree: true,
+                transcript_seed: true,
+                instance_schema: true,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn key_diffs_report_the_gates() {
+        let key = unblinded_verifier_key(SangriaConfig::default(), square_plus(5, true, 0));
+        assert_eq!(
+            key.diff(&unblinded_verifier_key(
+                SangriaConfig::default(),
+                square_plus(5, false, 0)
+            )),
+            KeyDiff {
+                circuit: true,
+                transcript_seed: true,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn key_diffs_report_the_selector_commitment() {
+        let key = unblinded_verifier_key(SangriaConfig::default(), square_plus(5, true, 0));
+        assert_eq!(
+            key.diff(&unblinded_verifier_key(
+                SangriaConfig::default(),
+                square_plus(6, true, 0)
+            )),
+            KeyDiff {
+                circuit: true,
+                selector_c_commitment: true,
+                transcript_seed: true,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn key_diffs_report_the_transcript_configuration() {
+        let key = unblinded_verifier_key(SangriaConfig::default(), square_plus(5, true, 0));
+        let config = SangriaConfig::default().with_application_id("testnet");
+        assert_eq!(
+            key.diff(&unblinded_verifier_key(config, square_plus(5, true, 0))),
+            KeyDiff {
+                transcript_config: true,
+                transcript_seed: true,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn instance_schema_describes_the_instances() {
+        let rng = &mut test_rng();
+        let info = SetupInfo {
+            number_of_public_inputs: 1,
+            number_of_gates: 2,
+            config: SangriaConfig {
+                gate_degree: 5,
+                ..Default::default()
+            },
+            poseidon_constants: poseidon_parameters_for_test(),
+        };
+        let pp = Scheme::setup(&info, rng).unwrap();
+        let trace = fifth_power_plus_five(2);
+        let instance_columns = trace.instance_columns();
+        let (circuit, witness) = trace.into_circuit_and_witness(Vec::new(), rng).unwrap();
+        let instance = Scheme::commit_witness(&pp, instance_columns, &witness).unwrap();
+        let (_, vk) = Scheme::encode(&pp, &circuit, rng).unwrap();
+
+        let schema = vk.instance_schema;
+        assert_eq!(
+            schema,
+            InstanceSchema {
+                number_of_public_inputs: 1,
+                instance_columns: 3,
+                instance_rows: 2,
+                witness_commitments: 3,
+                extra_commitments: 0,
+                cross_term_commitments: 4,
+            }
+        );
+
+        let mut bytes = Vec::new();
+        schema.serialize_compressed(&mut bytes).unwrap();
+        assert_eq!(
+            InstanceSchema::deserialize_compressed(&bytes[..]).unwrap(),
+            schema
+        );
+
+        let mut bytes = Vec::new();
+        instance.serialize_compressed(&mut bytes).unwrap();
+        let parsed = RelaxedPLONKInstance::<Fr, InnerProductConfig>::deserialize_bounded(
+            &bytes[..],
+            &schema.size_limits(),
+            Compress::Yes,
+            Validate::Yes,
+        )
+        .unwrap();
+        assert!(parsed == instance);
+    }
+
+    #[test]
+    fn miswired_witness_is_rejected() {
+        let rng = &mut test_rng();
+        let info = SetupInfo {
+            number_of_public_inputs: 1,
+            number_of_gates: 2,
+            config: SangriaConfig::default(),
+            poseidon_constants: poseidon_parameters_for_test(),
+        };
+        let pp = Scheme::setup(&info, rng).unwrap();
+
+        // the gates of `4^2 + 5` are satisfied, but not wired to the public input 3
+        let instance_columns = square_plus_five(3).instance_columns();
+        let (circuit, witness) = square_plus_five(4)
+            .into_circuit_and_witness(Vec::new(), rng)
+            .unwrap();
+        let instance = Scheme::commit_witness(&pp, instance_columns, &witness).unwrap();
+
+        // the public input is the first wire, copied into the left wire of the first gate
+        assert_eq!(
+            Scheme::check_relation(&pp, &circuit, &instance, &witness),
+            Err(SangriaError::UnsatisfiedCopyConstraint {
+                position: 0,
+                next: 1
+            })
+        );
+    }
+
+    #[test]
+    fn instance_io_must_agree_with_the_witness() {
+        let rng = &mut test_rng();
+        let info = SetupInfo {
+            number_of_public_inputs: 1,
+            number_of_gates: 2,
+            config: SangriaConfig::default(),
+            poseidon_constants: poseidon_parameters_for_test(),
+        };
+        let pp = Scheme::setup(&info, rng).unwrap();
+        let check = |trace: TraceTable<Fr>, instance_columns| {
+            let (circuit, witness) = trace
+                .into_circuit_and_witness(Vec::new(), &mut test_rng())
+                .unwrap();
+            let instance = Scheme::commit_witness(&pp, instance_columns, &witness).unwrap();
+            Scheme::check_relation(&pp, &circuit, &instance, &witness)
+        };
+
+        let instance_columns = square_plus_five(3).instance_columns();
+        assert_eq!(check(square_plus_five(3), instance_columns), Ok(()));
+
+        // a value on an unused wire of the public input row
+        let mut instance_columns = square_plus_five(3).instance_columns();
+        instance_columns[1][0] = Fr::one();
+        assert_eq!(
+            check(square_plus_five(3), instance_columns),
+            Err(SangriaError::PublicInputMismatch)
+        );
+
+        // an output that is not copied from the gate computing it
+        let trace = square_plus_five(3);
+        let mut unbound = TraceTable::new();
+        for row in 0..4 {
+            unbound.push_row(
+                trace.tags()[row],
+                [0, 1, 2].map(|column| trace.wire(column, row).unwrap()),
+            );
+        }
+        unbound.copy((0, 0), (0, 1));
+        unbound.copy((0, 1), (1, 1));
+        unbound.copy((2, 1), (0, 2));
+        let instance_columns = unbound.instance_columns();
+        assert_eq!(
+            check(unbound, instance_columns),
+            Err(SangriaError::PublicInputMismatch)
+        );
+    }
+
+    #[test]
+    fn power_gates_fold_with_higher_degree() {
+        let rng = &mut test_rng();
+        let info = SetupInfo {
+            number_of_public_inputs: 1,
+            number_of_gates: 2,
+            config: SangriaConfig {
+                gate_degree: 5,
+                ..Default::default()
+            },
+            poseidon_constants: poseidon_parameters_for_test(),
+        };
+        let pp = Scheme::setup(&info, rng).unwrap();
+
+        let pairs = [2u64, 3].map(|x| {
+            let trace = fifth_power_plus_five(x);
+            let instance_columns = trace.instance_columns();
+            let (circuit, witness) = trace.into_circuit_and_witness(Vec::new(), rng).unwrap();
+            let instance = Scheme::commit_witness(&pp, instance_columns, &witness).unwrap();
+            (circuit, instance, witness)
+        });
+        let (circuit, left_instance, left_witness) = pairs[0].clone();
+        let (_, right_instance, right_witness) = pairs[1].clone();
+        let (pk, vk) = Scheme::encode(&pp, &circuit, rng).unwrap();
+        assert_eq!(
+            Scheme::check_relation(&pp, &circuit, &left_instance, &left_witness),
+            Ok(())
+        );
+
+        let (instance, witness, cross_term_commitments) = Scheme::prover(
+            &pp,
+            &pk,
+            &left_instance,
+            &left_witness,
+            &right_instance,
+            &right_witness,
+            rng,
+        )
+        .unwrap();
+        assert_eq!(cross_term_commitments.len(), 4);
+        let verifier_instance = Scheme::verifier(
+            &pp,
+            &vk,
+            &left_instance,
+            &right_instance,
+            &cross_term_commitments,
+        )
+        .unwrap();
+        assert!(verifier_instance == instance);
+        assert_eq!(
+            Scheme::check_relation(&pp, &circuit, &instance, &witness),
+            Ok(())
+        );
+
+        // a quadratic fold does not carry enough cross terms
+        assert!(matches!(
+            Scheme::verifier(
+                &pp,
+                &vk,
+                &left_instance,
+                &right_instance,
+                &cross_term_commitments[..1].to_vec(),
+            ),
+            Err(SangriaError::CrossTermCount {
+                expected: 4,
+                found: 1
+            })
+        ));
+    }
+
+    #[test]
+    fn cross_terms_expand_the_folded_gate_equation() {
+        let rng = &mut test_rng();
+        let selectors: Vec<Vec<Fr>> = (0..NUMBER_OF_SELECTORS)
+            .map(|_| sample_vector(rng, 4))
+            .collect();
+        let mut random_trace = || -> (Vec<Vec<Fr>>, Fr) {
+            (
+                (0..3).map(|_| sample_vector(rng, 4)).collect(),
+                Fr::rand(rng),
+            )
+        };
+        let ((left, left_u), (right, right_u)) = (random_trace(), random_trace());
+        let r = Fr::rand(rng);
+
+        // the cross terms skip the columns flagged as zero in the right trace
+        let zero_columns = [
+            [false; 3],
+            [false, true, false],
+            [true, false, true],
+            [true; 3],
+        ];
+        for degree in [2, 3, 5] {
+            for right_zero_columns in zero_columns {
+                let right: Vec<Vec<Fr>> = right
+                    .iter()
+                    .zip(right_zero_columns)
+                    .map(|(column, zero)| {
+                        if zero {
+                            vec![Fr::zero(); 4]
+                        } else {
+                            column.clone()
+                        }
+                    })
+                    .collect();
+                let folded: Vec<Vec<Fr>> = left
+                    .iter()
+                    .zip(&right)
+                    .map(|(left, right)| {
+                        left.iter()
+                            .zip(right)
+                            .map(|(&left, &right)| left + r * right)
+                            .collect()
+                    })
+                    .collect();
+                let cross_terms = cross_terms(
+                    &selectors,
+                    &left,
+                    left_u,
+                    &right,
+                    right_u,
+                    right_zero_columns,
+                    degree,
+                );
+                assert_eq!(cross_terms.len(), degree - 1);
+
+                let left_gate = gate_evaluations(&selectors, &left, left_u, degree);
+                let right_gate = gate_evaluations(&selectors, &right, right_u, degree);
+                for (row, folded_gate) in
+                    gate_evaluations(&selectors, &folded, left_u + r * right_u, degree)
+                        .into_iter()
+                        .enumerate()
+                {
+                    let expansion = left_gate[row]
+                        + (1..degree)
+                            .map(|k| r.pow([k as u64]) * cross_terms[k - 1][row])
+                            .sum::<Fr>()
+                        + r.pow([degree as u64]) * right_gate[row];
+                    assert_eq!(folded_gate, expansion);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn cross_terms_are_committed_in_chunks() {
+        let rng = &mut test_rng();
+        let info = SetupInfo {
+            number_of_public_inputs: 1,
+            number_of_gates: 2,
+            config: SangriaConfig {
+                gate_degree: 5,
+                ..Default::default()
+            },
+            poseidon_constants: poseidon_parameters_for_test(),
+        };
+        let pp = Scheme::setup(&info, rng).unwrap();
+        let pairs = [2u64, 3].map(|x| {
+            let trace = fifth_power_plus_five(x);
+            let instance_columns = trace.instance_columns();
+            let (circuit, witness) = trace.into_circuit_and_witness(Vec::new(), rng).unwrap();
+            let instance = Scheme::commit_witness(&pp, instance_columns, &witness).unwrap();
+            (circuit, instance, witness)
+        });
+        let [(circuit, left_instance, left_witness), (_, right_instance, right_witness)] = &pairs;
+        let hidings = sample_vector(rng, 4);
+
+        let expected = cross_terms(
+            &circuit.selectors(),
+            &trace_columns(left_instance, left_witness).unwrap(),
+            left_instance.scaling_factor(),
+            &trace_columns(right_instance, right_witness).unwrap(),
+            right_instance.scaling_factor(),
+            [false; 3],
+            5,
+        );
+        let expected_commitments =
+            InnerProduct::batch_commit(&pp.commit_key_selectors_and_slack, &expected, &hidings);
+
+        for chunk_rows in [1, 3, circuit.number_of_rows(), 16] {
+            let chunks = CrossTermChunks {
+                circuit,
+                left: TraceRows::new(left_instance, left_witness, 1, circuit).unwrap(),
+                right: TraceRows::new(right_instance, right_witness, 1, circuit).unwrap(),
+                degree: 5,
+                chunk_rows,
+            };
+            let mut evaluated = vec![Vec::new(); 4];
+            for chunk in chunks.chunks().map(|rows| chunks.evaluate(rows)) {
+                for (cross_term, rows) in evaluated.iter_mut().zip(chunk) {
+                    cross_term.extend(rows);
+                }
+            }
+
+            assert_eq!(evaluated, expected);
+            assert_eq!(
+                chunks.commit::<InnerProduct>(&pp.commit_key_selectors_and_slack, &hidings),
+                expected_commitments
+            );
+        }
+
+        // a witness with an extra gate row does not span the rows of the circuit
+        let longer_witness = RelaxedPLONKWitness::new(
+            vec![sample_vector(rng, 3); 3],
+            sample_vector(rng, circuit.number_of_rows()),
+            sample_vector(rng, 3),
+        );
+        assert!(matches!(
+            TraceRows::new(left_instance, &longer_witness, 1, circuit),
+            Err(SangriaError::InvalidWitness)
+        ));
+    }
+
+    #[test]
+    fn unused_witness_columns_are_skipped() {
+        let rng = &mut test_rng();
+        let info = SetupInfo {
+            number_of_public_inputs: 1,
+            number_of_gates: 2,
+            config: SangriaConfig {
+                gate_degree: 5,
+                ..Default::default()
+            },
+            poseidon_constants: poseidon_parameters_for_test(),
+        };
+        let pp = Scheme::setup(&info, rng).unwrap();
+        // the second wire is never used, and the first one is zero on the gate rows of 0^5 + 5
+        let pairs = [3u64, 0].map(|x| {
+            let trace = fifth_power_plus_five(x);
+            let instance_columns = trace.instance_columns();
+            let (circuit, witness) = trace.into_circuit_and_witness(Vec::new(), rng).unwrap();
+            let instance = Scheme::commit_witness(&pp, instance_columns, &witness).unwrap();
+            (circuit, instance, witness)
+        });
+        let [(circuit, left_instance, left_witness), (_, right_instance, right_witness)] = &pairs;
+        assert_eq!(right_witness.zero_columns(), [true, true, false]);
+        assert_eq!(
+            TraceRows::new(right_instance, right_witness, 1, circuit)
+                .unwrap()
+                .zero_columns,
+            [false, true, false]
+        );
+
+        // the commitments to the skipped columns are those of zero columns
+        let commitments = right_instance.witness_commitments();
+        for (commitment, hiding) in commitments
+            .iter()
+            .zip(right_witness.hiding_randomnesses())
+            .take(2)
+        {
+            assert_eq!(
+                *commitment,
+                InnerProduct::commit(&pp.commit_key_witness, &[Fr::zero(); 2], hiding).unwrap()
+            );
+        }
+
+        let (pk, vk) = Scheme::encode(&pp, circuit, rng).unwrap();
+        let (instance, witness, cross_term_commitments) = Scheme::prover(
+            &pp,
+            &pk,
+            left_instance,
+            left_witness,
+            right_instance,
+            right_witness,
+            rng,
+        )
+        .unwrap();
+        let verifier_instance = Scheme::verifier(
+            &pp,
+            &vk,
+            left_instance,
+            right_instance,
+            &cross_term_commitments,
+        )
+        .unwrap();
+        assert!(verifier_instance == instance);
+        assert_eq!(
+            Scheme::check_relation(&pp, circuit, &instance, &witness),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn relaxed_instances_fold() {
+        let rng = &mut test_rng();
+        let traces: [(TraceOf, usize); 2] = [(square_plus_five, 2), (fifth_power_plus_five, 5)];
+
+        for (trace_of, gate_degree) in traces {
+            let info = SetupInfo {
+                number_of_public_inputs: 1,
+                number_of_gates: 2,
+                config: SangriaConfig {
+                    gate_degree,
+                    ..Default::default()
+                },
+                poseidon_constants: poseidon_parameters_for_test(),
+            };
+            let pp = Scheme::setup(&info, rng).unwrap();
+            let pairs = [3u64, 7, 4, 5].map(|x| {
+                let trace = trace_of(x);
+                let instance_columns = trace.instance_columns();
+                let (circuit, witness) = trace.into_circuit_and_witness(Vec::new(), rng).unwrap();
+                let instance = Scheme::commit_witness(&pp, instance_columns, &witness).unwrap();
+                (circuit, instance, witness)
+            });
+            let circuit = pairs[0].0.clone();
+            let (pk, vk) = Scheme::encode(&pp, &circuit, rng).unwrap();
+
+            let mut fold = |(_, left_instance, left_witness),
+                            (_, right_instance, right_witness)| {
+                let (instance, witness, cross_term_commitments) = Scheme::prover(
+                    &pp,
+                    &pk,
+                    &left_instance,
+                    &left_witness,
+                    &right_instance,
+                    &right_witness,
+                    rng,
+                )
+                .unwrap();
+                let verifier_instance = Scheme::verifier(
+                    &pp,
+                    &vk,
+                    &left_instance,
+                    &right_instance,
+                    &cross_term_commitments,
+                )
+                .unwrap();
+                assert!(verifier_instance == instance);
+                assert_eq!(
+                    Scheme::check_relation(&pp, &circuit, &instance, &witness),
+                    Ok(())
+                );
+
+                (circuit.clone(), instance, witness)
+            };
+
+            // both accumulators have nontrivial scaling factors and slack vectors
+            let [first, second, third, fourth] = pairs;
+            let (left, right) = (fold(first, second), fold(third, fourth));
+            for (_, instance, _) in [&left, &right] {
+                assert!(instance.scaling_factor() != Fr::one());
+                assert!(!instance.slack_commitment().is_zero());
+            }
+            fold(left, right);
+        }
+    }
+
+    #[test]
+    fn many_instances_fold_in_one_round() {
+        let rng = &mut test_rng();
+        let traces: [(TraceOf, usize); 2] = [(square_plus_five, 2), (fifth_power_plus_five, 5)];
+
+        for (trace_of, gate_degree) in traces {
+            let info = SetupInfo {
+                number_of_public_inputs: 1,
+                number_of_gates: 2,
+                config: SangriaConfig {
+                    gate_degree,
+                    ..Default::default()
+                },
+                poseidon_constants: poseidon_parameters_for_test(),
+            };
+            let pp = Scheme::setup(&info, rng).unwrap();
+            let (mut instances, mut witnesses): (Vec<_>, Vec<_>) = [3u64, 7, 4, 5, 6, 2]
+                .map(|x| {
+                    let trace = trace_of(x);
+                    let instance_columns = trace.instance_columns();
+                    let (_, witness) = trace.into_circuit_and_witness(Vec::new(), rng).unwrap();
+                    let instance = Scheme::commit_witness(&pp, instance_columns, &witness).unwrap();
+                    (instance, witness)
+                })
+                .into_iter()
+                .unzip();
+            let circuit = trace_of(0).circuit(Vec::new()).unwrap();
+            let (pk, vk) = Scheme::encode(&pp, &circuit, rng).unwrap();
+
+            // the last instance is relaxed
+            let (right_instance, right_witness) = (instances.pop(), witnesses.pop());
+            let (relaxed_instance, relaxed_witness, _) = Scheme::prover(
+                &pp,
+                &pk,
+                &instances.pop().unwrap(),
+                &witnesses.pop().unwrap(),
+                &right_instance.unwrap(),
+                &right_witness.unwrap(),
+                rng,
+            )
+            .unwrap();
+            instances.push(relaxed_instance);
+            witnesses.push(relaxed_witness);
+
+            for k in 1..=instances.len() {
+                let (instance, witness, quotient_commitments) =
+                    Scheme::prover_many(&pp, &pk, &instances[..k], &witnesses[..k], rng).unwrap();
+                assert_eq!(quotient_commitments.len(), (gate_degree - 1) * (k - 1));
+                assert!(
+                    Scheme::verifier_many(&pp, &vk, &instances[..k], &quotient_commitments)
+                        .unwrap()
+                        == instance
+                );
+                assert_eq!(
+                    Scheme::check_relation(&pp, &circuit, &instance, &witness),
+                    Ok(())
+                );
+            }
+
+            let (_, witness, mut quotient_commitments) =
+                Scheme::prover_many(&pp, &pk, &instances, &witnesses, rng).unwrap();
+            quotient_commitments[1] += Fr::one();
+            let instance =
+                Scheme::verifier_many(&pp, &vk, &instances, &quotient_commitments).unwrap();
+            assert!(Scheme::check_relation(&pp, &circuit, &instance, &witness).is_err());
+            assert_eq!(
+                Scheme::verifier_many(&pp, &vk, &instances, &quotient_commitments[1..].to_vec())
+                    .err(),
+                Some(SangriaError::InvalidProof)
+            );
+        }
+    }
+
+    #[test]
+    fn opened_wires_are_bound_to_their_position() {
+        let rng = &mut test_rng();
+        let info = SetupInfo {
+            number_of_public_inputs: 1,
+            number_of_gates: 2,
+            config: SangriaConfig::default(),
+            poseidon_constants: poseidon_parameters_for_test(),
+        };
+        let pp = Scheme::setup(&info, rng).unwrap();
+        let trace = square_plus_five(3);
+        let instance_columns = trace.instance_columns();
+        let (_, witness) = trace.into_circuit_and_witness(Vec::new(), rng).unwrap();
+        let instance = Scheme::commit_witness(&pp, instance_columns, &witness).unwrap();
+
+        // the output wire of the squaring gate holds 3^2
+        let opening = Scheme::open_wire(&pp, &instance, &witness, 2, 1).unwrap();
+        assert_eq!(
+            (opening.position(), opening.value()),
+            ((2, 1), Fr::from(9u64))
+        );
+        assert_eq!(
+            Scheme::verify_wire_opening(&pp, &instance, &opening),
+            Ok(())
+        );
+
+        let mut wrong_value = Scheme::open_wire(&pp, &instance, &witness, 2, 1).unwrap();
+        wrong_value.value += Fr::one();
+        let mut wrong_position = Scheme::open_wire(&pp, &instance, &witness, 2, 1).unwrap();
+        wrong_position.row = 2;
+        for opening in [wrong_value, wrong_position] {
+            assert_eq!(
+                Scheme::verify_wire_opening(&pp, &instance, &opening),
+                Err(SangriaError::InvalidProof)
+            );
+        }
+
+        // public input and output rows are not committed
+        for row in [0, 3] {
+            assert!(matches!(
+                Scheme::open_wire(&pp, &instance, &witness, 0, row),
+                Err(SangriaError::IndexOutOfBounds)
+            ));
+        }
+    }
+}
@@ -0,0 +1,143 @@
+//! Standard hints computing non-deterministic advice for gadgets, to be run with
+//! [`TraceTable::hint`](crate::TraceTable::hint). A hint only computes values: the gadget requesting
+//! the advice must constrain it in-circuit.
+
+use ark_ff::{BigInteger, PrimeField};
+
+use crate::{
+    fe_to_bytes_canonical,
+    nonnative::{div_rem as div_rem_limbs, from_limbs, to_limbs},
+    SangriaError,
+};
+
+/// Returns the hint inverting every input, zero being mapped to zero so that gadgets can also use
+/// the advice to test for zero.
+pub fn inverse<F: PrimeField>() -> impl FnOnce(&[F]) -> Result<Vec<F>, SangriaError> {
+    |inputs| {
+        Ok(inputs
+            .iter()
+            .map(|input| input.inverse().unwrap_or_else(F::zero))
+            .collect())
+    }
+}
+
+/// Returns the hint decomposing its single input into its `bits` least significant bits, most
+/// significant first.
+pub fn bits<F: PrimeField>(bits: usize) -> impl FnOnce(&[F]) -> Result<Vec<F>, SangriaError> {
+    move |inputs| {
+        let [input] = inputs else {
+            return Err(SangriaError::InvalidTrace(
+                "the bits hint takes a single input",
+            ));
+        };
+        let input = input.into_bigint();
+
+        Ok((0..bits)
+            .rev()
+            .map(|index| F::from(input.get_bit(index)))
+            .collect())
+    }
+}
+
+/// Returns the hint dividing the integer of its first input by that of its second, both taken as
+/// canonical representatives, and returning the quotient and the remainder.
+pub fn div_rem<F: PrimeField>() -> impl FnOnce(&[F]) -> Result<Vec<F>, SangriaError> {
+    |inputs| {
+        let [numerator, divisor] = inputs else {
+            return Err(SangriaError::InvalidTrace(
+                "the division hint takes a numerator and a divisor",
+            ));
+        };
+        if divisor.is_zero() {
+            return Err(SangriaError::InvalidTrace("the divisor must not be zero"));
+        }
+
+        let limbs = |value: &F| to_limbs(&fe_to_bytes_canonical(value));
+        let (quotient, remainder) = div_rem_limbs(&limbs(numerator), &limbs(divisor));
+        Ok(vec![from_limbs(&quotient), from_limbs(&remainder)])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bits, div_rem, inverse};
+    use crate::{
+        folding_scheme::{PLONKFoldingScheme, SetupInfo},
+        trace::NUMBER_OF_SELECTORS,
+        utils::{inner_product::InnerProductConfig, poseidon::poseidon_parameters_for_test},
+        NonInteractiveFoldingScheme, RowTag, SangriaConfig, SangriaError, TraceTable,
+        CONSTANT_SELECTOR_INDEX, MULTIPLICATION_SELECTOR_INDEX,
+    };
+    use ark_bn254::Fr;
+    use ark_crypto_primitives::sponge::poseidon::PoseidonSponge;
+    use ark_ff::{One, Zero};
+    use ark_std::test_rng;
+
+    type Scheme = PLONKFoldingScheme<Fr, InnerProductConfig, PoseidonSponge<Fr>>;
+
+    /// Proves that the public input is not zero with its inverse as advice, constrained by the gate
+    /// `x x^-1 - 1 = 0`.
+    fn check_non_zero(x: Fr) -> Result<(), SangriaError> {
+        let rng = &mut test_rng();
+        let mut trace = TraceTable::new();
+        trace.push_row(RowTag::PublicInput, [x, Fr::zero(), Fr::zero()]);
+        let inverse_wire = trace.hint(&[(0, 0)], inverse())?[0];
+
+        let mut selectors = [Fr::zero(); NUMBER_OF_SELECTORS];
+        selectors[MULTIPLICATION_SELECTOR_INDEX] = Fr::one();
+        selectors[CONSTANT_SELECTOR_INDEX] = -Fr::one();
+        let row = trace.number_of_rows();
+        let x_inverse = trace.wire(inverse_wire.0, inverse_wire.1)?;
+        trace.push_row(RowTag::Gate(selectors), [x, x_inverse, Fr::zero()]);
+        trace.copy((0, 0), (0, row));
+        trace.copy(inverse_wire, (1, row));
+        trace.push_row(RowTag::Output, [Fr::zero(); 3]);
+
+        let info = SetupInfo {
+            number_of_public_inputs: 1,
+            number_of_gates: 2,
+            config: SangriaConfig::default(),
+            poseidon_constants: poseidon_parameters_for_test(),
+        };
+        let pp = Scheme::setup(&info, rng)?;
+        let instance_columns = trace.instance_columns();
+        let (circuit, witness) = trace.into_circuit_and_witness(Vec::new(), rng)?;
+        let instance = Scheme::commit_witness(&pp, instance_columns, &witness)?;
+        Scheme::check_relation(&pp, &circuit, &instance, &witness)
+    }
+
+    #[test]
+    fn constrained_advice_satisfies_the_relation() {
+        assert_eq!(check_non_zero(Fr::from(7u64)), Ok(()));
+        assert!(check_non_zero(Fr::zero()).is_err());
+    }
+
+    #[test]
+    fn standard_hints_compute_their_advice() {
+        let mut trace = TraceTable::new();
+        trace.push_row(
+            RowTag::PublicInput,
+            [Fr::from(47u64), Fr::from(5u64), Fr::zero()],
+        );
+
+        let wires = trace.hint(&[(0, 0), (1, 0)], div_rem()).unwrap();
+        let values: Vec<Fr> = wires
+            .iter()
+            .map(|&(column, row)| trace.wire(column, row).unwrap())
+            .collect();
+        assert_eq!(values, [Fr::from(9u64), Fr::from(2u64)]);
+
+        let wires = trace.hint(&[(0, 0)], bits(7)).unwrap();
+        assert_eq!((wires.len(), wires[6]), (7, (0, 4)));
+        let values: Vec<Fr> = wires
+            .iter()
+            .map(|&(column, row)| trace.wire(column, row).unwrap())
+            .collect();
+        assert_eq!(values, [0u64, 1, 0, 1, 1, 1, 1].map(Fr::from));
+
+        assert_eq!(
+            trace.hint(&[(0, 0), (2, 0)], div_rem()),
+            Err(SangriaError::InvalidTrace("the divisor must not be zero"))
+        );
+    }
+}
@@ -0,0 +1,1053 @@
+//! A HyperPlonk SNARK for relaxed PLONK, used to compress IVC proofs. It replaces the witness of a
+//! relaxed instance with a zero check of the relaxed gate equation, a permutation check of the copy
+//! constraints and inner-product openings of the commitments of the instance.
+//!
+//! The verifier evaluates the selectors and the copy constraint permutation itself, and the proof
+//! is only as succinct as the openings of the commitment scheme.
+//!
+//! When the configuration asks for zero knowledge, the prover first folds the instance with a random
+//! satisfying instance, so the witness it proves is uniformly random. Both sumchecks are masked, the
+//! inverses of the permutation check are committed to with hidings and every commitment is opened
+//! with a [`HidingOpening`], so the proof reveals nothing about the witness.
+
+use ark_crypto_primitives::sponge::{poseidon::PoseidonSponge, Absorb};
+use ark_ff::PrimeField;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+use crate::{
+    folding_scheme::{
+        blake3_digest, trace_columns, FoldingCommitmentConfig, PLONKFoldingScheme,
+        PublicParameters, VerifierKey,
+    },
+    piop::{
+        eq_extension, prove_masked_zero_check, prove_permutation_check, prove_zero_check,
+        verify_masked_zero_check, verify_permutation_check, verify_zero_check, MaskedSumcheckProof,
+        PermutationCheckProof, SumcheckMask, SumcheckProof, VirtualPolynomial, ZeroCheckSubClaim,
+        PRODUCT_CHECK_DEGREE,
+    },
+    relaxed_plonk::NUMBER_OF_WIRES,
+    transcript::Transcript,
+    vector_commitment::{open_hiding, verify_hiding_opening, HidingOpening, InnerProductOpening},
+    HomomorphicCommitmentScheme, MultilinearExtension, PLONKCircuit, RelaxedPLONKInstance,
+    RelaxedPLONKWitness, SangriaError, CONSTANT_SELECTOR_INDEX, LEFT_SELECTOR_INDEX,
+    MULTIPLICATION_SELECTOR_INDEX, OUTPUT_SELECTOR_INDEX, POWER_SELECTOR_INDEX,
+    RIGHT_SELECTOR_INDEX,
+};
+
+type SlackScheme<F, Comm> = <Comm as FoldingCommitmentConfig<F>>::CommitmentSlack;
+type WitnessScheme<F, Comm> = <Comm as FoldingCommitmentConfig<F>>::CommitmentWitness;
+type SlackProof<F, Comm> = HidingOpening<F, SlackScheme<F, Comm>>;
+type WitnessProof<F, Comm> = HidingOpening<F, WitnessScheme<F, Comm>>;
+type SlackCommitment<F, Comm> =
+    <SlackScheme<F, Comm> as HomomorphicCommitmentScheme<F>>::Commitment;
+
+/// A zero-knowledge zero check of the gate equation: the commitment to the mask, the masked
+/// sumcheck and the opening of the mask at the point of the check.
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+struct MaskedGateProof<F, Comm>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+    Comm::CommitmentSlack: InnerProductOpening<F>,
+{
+    mask_commitment: SlackCommitment<F, Comm>,
+    sumcheck_proof: MaskedSumcheckProof<F>,
+    mask_opening: SlackProof<F, Comm>,
+}
+
+/// The fold of the proven instance with a random satisfying instance, which makes the witness of
+/// the rest of a zero-knowledge proof uniformly random: the random instance and the cross term
+/// commitments of the fold.
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+struct BlindingFold<F, Comm>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    instance: RelaxedPLONKInstance<F, Comm>,
+    cross_term_commitments: Vec<SlackCommitment<F, Comm>>,
+}
+
+/// The mask of the zero-knowledge sumcheck of the permutation check: its commitment and its opening
+/// at the point of the check.
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+struct PermutationMaskProof<F, Comm>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+    Comm::CommitmentSlack: InnerProductOpening<F>,
+{
+    mask_commitment: SlackCommitment<F, Comm>,
+    mask_opening: SlackProof<F, Comm>,
+}
+
+/// A proof that a relaxed PLONK instance is satisfied.
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct HyperPlonkProof<F, Comm>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+    Comm::CommitmentSlack: InnerProductOpening<F>,
+    Comm::CommitmentWitness: InnerProductOpening<F>,
+{
+    blinding_fold: Option<BlindingFold<F, Comm>>,
+    gate_proof: Option<SumcheckProof<F>>,
+    masked_gate_proof: Option<MaskedGateProof<F, Comm>>,
+    wire_evaluations: Vec<F>,
+    wire_openings: Vec<WitnessProof<F, Comm>>,
+    slack_evaluation: F,
+    slack_opening: SlackProof<F, Comm>,
+    permutation_mask_proof: Option<PermutationMaskProof<F, Comm>>,
+    permutation_proof: PermutationCheckProof<F, SlackScheme<F, Comm>>,
+    copy_evaluations: Vec<F>,
+    copy_openings: Vec<WitnessProof<F, Comm>>,
+    inverse_openings: Vec<SlackProof<F, Comm>>,
+}
+
+/// Returns the length of the vectors committed in the permutation check of a circuit with
+/// `number_of_rows` rows: every wire of the trace, padded to a power of two. The commit key passed
+/// to [`prove`] and [`verify`] must support it.
+pub fn permutation_vector_len(number_of_rows: usize) -> usize {
+    (NUMBER_OF_WIRES * number_of_rows).next_power_of_two()
+}
+
+/// Returns the length of the vectors the permutation commit key passed to [`prove`] and [`verify`]
+/// must support for a circuit with `number_of_rows` rows and gate equations of degree
+/// `gate_degree`: the vectors of the permutation check and the masks of the zero-knowledge gate
+/// zero check and permutation check.
+pub fn permutation_commit_key_len(number_of_rows: usize, gate_degree: usize) -> usize {
+    permutation_vector_len(number_of_rows)
+        .max(gate_mask_len(number_of_rows, gate_degree))
+        .max(permutation_mask_len(number_of_rows))
+}
+
+/// Starts the transcript of a proof, bound to the circuit (through the folding verifier key) and
+/// to the instance.
+fn transcript<F, Comm, RO>(
+    public_parameters: &PublicParameters<F, Comm>,
+    verifier_key: &VerifierKey<F, Comm>,
+    instance: &RelaxedPLONKInstance<F, Comm>,
+) -> RO
+where
+    F: PrimeField + Absorb,
+    Comm: FoldingCommitmentConfig<F>,
+    RO: Transcript<F>,
+{
+    let mut sponge = RO::start(&public_parameters.poseidon_constants);
+    sponge.absorb(verifier_key);
+    sponge.absorb(instance);
+
+    sponge
+}
+
+/// Splits the evaluation of a trace column, placed at `offset` of a vector whose extension is
+/// evaluated with the evaluations `eq`, into the contribution of the public input and output rows,
+/// and the public vector whose inner product with the witness column completes it.
+fn split_column_evaluation<F: PrimeField>(
+    eq: &[F],
+    offset: usize,
+    instance_column: &[F],
+    number_of_gates: usize,
+) -> Result<(F, Vec<F>), SangriaError> {
+    let (&output, public_inputs) = instance_column
+        .split_last()
+        .ok_or(SangriaError::PublicInputMismatch)?;
+    let gates = offset + public_inputs.len()..offset + public_inputs.len() + number_of_gates;
+    if gates.end >= eq.len() {
+        return Err(SangriaError::IndexOutOfBounds);
+    }
+
+    let public = public_inputs
+        .iter()
+        .zip(eq[offset..].iter())
+        .map(|(&value, &eq)| value * eq)
+        .sum::<F>()
+        + output * eq[gates.end];
+
+    Ok((public, eq[gates].to_vec()))
+}
+
+/// Returns the length of the mask of the zero-knowledge gate zero check of a circuit with
+/// `number_of_rows` rows and gate equations of degree `gate_degree`.
+fn gate_mask_len(number_of_rows: usize, gate_degree: usize) -> usize {
+    let number_of_variables = number_of_rows.next_power_of_two().trailing_zeros() as usize;
+    1 + number_of_variables * (zero_check_degree(gate_degree) + 1)
+}
+
+/// Returns the length of the mask of the zero-knowledge permutation check of a circuit with
+/// `number_of_rows` rows.
+fn permutation_mask_len(number_of_rows: usize) -> usize {
+    let number_of_variables = permutation_vector_len(number_of_rows).trailing_zeros() as usize;
+    1 + number_of_variables * PRODUCT_CHECK_DEGREE
+}
+
+/// Seeds the randomness of the masks from the instance and the witness, including the hidings of
+/// its commitments, so that it is as secret as the witness without a source of randomness.
+fn mask_rng<F, Comm>(
+    instance: &RelaxedPLONKInstance<F, Comm>,
+    witness: &RelaxedPLONKWitness<F>,
+) -> Result<StdRng, SangriaError>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    let columns = (0..NUMBER_OF_WIRES)
+        .map(|column| witness.witness_column_with_rand(column))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(StdRng::from_seed(blake3_digest(&(
+        blake3_digest(instance),
+        columns,
+        witness.slack_vector(),
+        witness.slack_hiding(),
+    ))))
+}
+
+/// Returns the degree of the relaxed gate equation of degree `gate_degree` as a polynomial in the
+/// extensions: `q_P a^d` is a product of `d + 1` extensions.
+fn zero_check_degree(gate_degree: usize) -> usize {
+    gate_degree + 1
+}
+
+/// Builds the relaxed gate equation `u^(d-1) (q_L a + q_R b + q_O c) + u^(d-2) q_M a b + q_P a^d +
+/// u^d q_C - E` of degree `d`, which is zero on the hypercube for a satisfied instance.
+fn gate_polynomial<F: PrimeField>(
+    selectors: &[Vec<F>],
+    columns: &[Vec<F>],
+    slack_vector: &[F],
+    u: F,
+    degree: usize,
+) -> Result<VirtualPolynomial<F>, SangriaError> {
+    let selector = |index: usize| MultilinearExtension::from_column(&selectors[index]);
+    let [a, b, c] = [0, 1, 2].map(|column| MultilinearExtension::from_column(&columns[column]));
+    let mut polynomial = VirtualPolynomial::new(a.number_of_variables());
+    let u_power = |exponent: usize| u.pow([exponent as u64]);
+
+    let mut power = vec![selector(POWER_SELECTOR_INDEX)];
+    power.extend(ark_std::iter::repeat_n(a.clone(), degree));
+    polynomial.add_product(F::one(), power)?;
+    polynomial.add_product(
+        u_power(degree - 1),
+        vec![selector(LEFT_SELECTOR_INDEX), a.clone()],
+    )?;
+    polynomial.add_product(
+        u_power(degree - 1),
+        vec![selector(RIGHT_SELECTOR_INDEX), b.clone()],
+    )?;
+    polynomial.add_product(
+        u_power(degree - 1),
+        vec![selector(OUTPUT_SELECTOR_INDEX), c],
+    )?;
+    polynomial.add_product(
+        u_power(degree - 2),
+        vec![selector(MULTIPLICATION_SELECTOR_INDEX), a, b],
+    )?;
+    polynomial.add_product(u_power(degree), vec![selector(CONSTANT_SELECTOR_INDEX)])?;
+    polynomial.add_product(
+        -F::one(),
+        vec![MultilinearExtension::from_column(slack_vector)],
+    )?;
+
+    Ok(polynomial)
+}
+
+/// Returns the extension of the copy constraint permutation over every wire of the trace, padded
+/// with the identity.
+fn permutation_extension<F: PrimeField>(circuit: &PLONKCircuit<F>) -> MultilinearExtension<F> {
+    let mut permutation = circuit.copy_constraint();
+    let padded_len = permutation_vector_len(circuit.number_of_rows());
+    permutation.extend((permutation.len()..padded_len).map(|position| F::from(position as u64)));
+
+    MultilinearExtension::from_column(&permutation)
+}
+
+/// Opens every witness column of the trace at `offset(column)` of a vector evaluated with `eq`,
+/// with hiding openings if there is an `rng` to draw their masks from, returning the values and
+/// proofs of the openings.
+#[allow(clippy::type_complexity)]
+fn open_witness_columns<F, Comm, RO>(
+    public_parameters: &PublicParameters<F, Comm>,
+    instance: &RelaxedPLONKInstance<F, Comm>,
+    witness: &RelaxedPLONKWitness<F>,
+    eq: &[F],
+    offset: impl Fn(usize) -> usize,
+    sponge: &mut RO,
+    mut rng: Option<&mut StdRng>,
+) -> Result<(Vec<F>, Vec<WitnessProof<F, Comm>>), SangriaError>
+where
+    F: PrimeField + Absorb,
+    Comm: FoldingCommitmentConfig<F>,
+    Comm::CommitmentWitness: InnerProductOpening<F>,
+    RO: Transcript<F>,
+{
+    (0..NUMBER_OF_WIRES)
+        .map(|column_index| {
+            let (column, hiding) = witness.witness_column_with_rand(column_index)?;
+            let (_, y) = split_column_evaluation(
+                eq,
+                offset(column_index),
+                &instance.instance_column(column_index)?,
+                column.len(),
+            )?;
+
+            open_hiding::<F, WitnessScheme<F, Comm>, _, _>(
+                &public_parameters.commit_key_witness,
+                &column,
+                hiding,
+                &y,
+                sponge,
+                rng.as_deref_mut(),
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map(|openings| openings.into_iter().unzip())
+}
+
+/// Checks the openings of every witness column of the trace at `offset(column)` of a vector
+/// evaluated with `eq`, deferring to `accumulator` what the commitment scheme defers, and returns
+/// the evaluations of the full trace columns.
+#[allow(clippy::too_many_arguments)]
+fn verify_witness_columns<F, Comm, RO>(
+    public_parameters: &PublicParameters<F, Comm>,
+    instance: &RelaxedPLONKInstance<F, Comm>,
+    number_of_gates: usize,
+    eq: &[F],
+    offset: impl Fn(usize) -> usize,
+    evaluations: &[F],
+    openings: &[WitnessProof<F, Comm>],
+    sponge: &mut RO,
+    accumulator: &mut <WitnessScheme<F, Comm> as InnerProductOpening<F>>::Accumulator,
+) -> Result<Vec<F>, SangriaError>
+where
+    F: PrimeField + Absorb,
+    Comm: FoldingCommitmentConfig<F>,
+    Comm::CommitmentWitness: InnerProductOpening<F>,
+    RO: Transcript<F>,
+{
+    if evaluations.len() != NUMBER_OF_WIRES || openings.len() != NUMBER_OF_WIRES {
+        return Err(SangriaError::InvalidProof);
+    }
+
+    let commitments = instance.witness_commitments();
+    (0..NUMBER_OF_WIRES)
+        .map(|column_index| {
+            let (public, y) = split_column_evaluation(
+                eq,
+                offset(column_index),
+                &instance.instance_column(column_index)?,
+                number_of_gates,
+            )?;
+            verify_hiding_opening::<F, WitnessScheme<F, Comm>, _>(
+                &public_parameters.commit_key_witness,
+                commitments
+                    .get(column_index)
+                    .ok_or(SangriaError::InvalidProof)?,
+                &y,
+                evaluations[column_index],
+                &openings[column_index],
+                sponge,
+                accumulator,
+            )?;
+
+            Ok(public + evaluations[column_index])
+        })
+        .collect()
+}
+
+/// Proves that `witness` satisfies the relaxed PLONK relation for `instance` and `circuit`, which
+/// was encoded into `verifier_key`. The inverses of the permutation check, and the masks of the
+/// sumchecks if the configuration asks for zero knowledge, are committed to with
+/// `permutation_commit_key`, which must support vectors of length [`permutation_commit_key_len`].
+/// The challenges are derived from a Poseidon transcript, see [`prove_with`] for other transcripts.
+pub fn prove<F, Comm>(
+    public_parameters: &PublicParameters<F, Comm>,
+    verifier_key: &VerifierKey<F, Comm>,
+    circuit: &PLONKCircuit<F>,
+    permutation_commit_key: &<Comm::CommitmentSlack as HomomorphicCommitmentScheme<F>>::CommitKey,
+    instance: &RelaxedPLONKInstance<F, Comm>,
+    witness: &RelaxedPLONKWitness<F>,
+) -> Result<HyperPlonkProof<F, Comm>, SangriaError>
+where
+    F: PrimeField + Absorb,
+    Comm: FoldingCommitmentConfig<F>,
+    Comm::CommitmentSlack: InnerProductOpening<F>,
+    Comm::CommitmentWitness: InnerProductOpening<F>,
+{
+    prove_with::<F, Comm, PoseidonSponge<F>>(
+        public_parameters,
+        verifier_key,
+        circuit,
+        permutation_commit_key,
+        instance,
+        witness,
+    )
+}
+
+/// Proves like [`prove`], deriving the challenges from a transcript of type `RO`, e.g. a
+/// [`crate::KeccakTranscript`] for a verifier running on Ethereum.
+pub fn prove_with<F, Comm, RO>(
+    public_parameters: &PublicParameters<F, Comm>,
+    verifier_key: &VerifierKey<F, Comm>,
+    circuit: &PLONKCircuit<F>,
+    permutation_commit_key: &<Comm::CommitmentSlack as HomomorphicCommitmentScheme<F>>::CommitKey,
+    instance: &RelaxedPLONKInstance<F, Comm>,
+    witness: &RelaxedPLONKWitness<F>,
+) -> Result<HyperPlonkProof<F, Comm>, SangriaError>
+where
+    F: PrimeField + Absorb,
+    Comm: FoldingCommitmentConfig<F>,
+    Comm::CommitmentSlack: InnerProductOpening<F>,
+    Comm::CommitmentWitness: InnerProductOpening<F>,
+    RO: Transcript<F>,
+{
+    instance.check_public_input_rows(public_parameters.number_of_public_inputs)?;
+    let number_of_rows = circuit.number_of_rows();
+    let zero_knowledge = public_parameters.config.zero_knowledge;
+    let mut rng = zero_knowledge
+        .then(|| mask_rng(instance, witness))
+        .transpose()?;
+
+    // under zero knowledge, the rest of the proof is about the fold with a random satisfying pair,
+    // whose witness is uniformly random
+    let (blinding_fold, folded) = match rng.as_mut() {
+        Some(rng) => {
+            let (blinding_instance, blinding_witness) =
+                PLONKFoldingScheme::<F, Comm, RO>::sample_satisfying_pair(
+                    public_parameters,
+                    verifier_key,
+                    circuit,
+                    rng,
+                )?;
+            let (folded_instance, folded_witness, cross_term_commitments) =
+                PLONKFoldingScheme::<F, Comm, RO>::fold_pairs(
+                    public_parameters,
+                    verifier_key,
+                    circuit,
+                    (instance, witness),
+                    (&blinding_instance, &blinding_witness),
+                    0,
+                    rng,
+                )?;
+            let blinding_fold = BlindingFold {
+                instance: blinding_instance,
+                cross_term_commitments,
+            };
+            (Some(blinding_fold), Some((folded_instance, folded_witness)))
+        }
+        None => (None, None),
+    };
+    let (instance, witness) = match &folded {
+        Some((instance, witness)) => (instance, witness),
+        None => (instance, witness),
+    };
+
+    let columns = trace_columns(instance, witness)?;
+    let slack_vector = witness.slack_vector();
+    if columns.iter().any(|column| column.len() != number_of_rows)
+        || slack_vector.len() != number_of_rows
+    {
+        return Err(SangriaError::InvalidWitness);
+    }
+    let mut sponge: RO = transcript(public_parameters, verifier_key, instance);
+
+    // the gate equation holds on every row
+    let polynomial = gate_polynomial(
+        &circuit.selectors(),
+        &columns,
+        &slack_vector,
+        instance.scaling_factor(),
+        public_parameters.config.gate_degree,
+    )?;
+    let (gate_proof, masked_gate_proof, gate_point) = match rng.as_mut() {
+        Some(rng) => {
+            let mask_degree = polynomial.degree() + 1;
+            let mask = SumcheckMask::rand(rng, polynomial.number_of_variables(), mask_degree);
+            let mask_hiding = F::rand(rng);
+            let mask_commitment = SlackScheme::<F, Comm>::commit(
+                permutation_commit_key,
+                mask.coefficients(),
+                mask_hiding,
+            )?;
+            sponge.absorb(&mask_commitment);
+            let (sumcheck_proof, point) = prove_masked_zero_check(&polynomial, &mask, &mut sponge)?;
+            let (_, mask_opening) = open_hiding::<F, SlackScheme<F, Comm>, _, _>(
+                permutation_commit_key,
+                mask.coefficients(),
+                mask_hiding,
+                &SumcheckMask::evaluation_vector(&point, mask_degree),
+                &mut sponge,
+                Some(rng),
+            )?;
+            let masked_gate_proof = MaskedGateProof {
+                mask_commitment,
+                sumcheck_proof,
+                mask_opening,
+            };
+            (None, Some(masked_gate_proof), point)
+        }
+        None => {
+            let (gate_proof, point) = prove_zero_check(&polynomial, &mut sponge)?;
+            (Some(gate_proof), None, point)
+        }
+    };
+    let gate_eq = eq_extension(&gate_point).evaluations().to_vec();
+    let (wire_evaluations, wire_openings) = open_witness_columns(
+        public_parameters,
+        instance,
+        witness,
+        &gate_eq,
+        |_| 0,
+        &mut sponge,
+        rng.as_mut(),
+    )?;
+    let (slack_evaluation, slack_opening) = open_hiding::<F, SlackScheme<F, Comm>, _, _>(
+        &public_parameters.commit_key_selectors_and_slack,
+        &slack_vector,
+        witness.slack_hiding(),
+        &gate_eq[..number_of_rows],
+        &mut sponge,
+        rng.as_mut(),
+    )?;
+    sponge.absorb(&wire_evaluations);
+    sponge.absorb(&slack_evaluation);
+
+    // every wire equals the next wire in its cycle of copies
+    let permutation_mask = match rng.as_mut() {
+        Some(rng) => {
+            let number_of_variables =
+                permutation_vector_len(number_of_rows).trailing_zeros() as usize;
+            let mask = SumcheckMask::rand(rng, number_of_variables, PRODUCT_CHECK_DEGREE);
+            let mask_hiding = F::rand(rng);
+            let mask_commitment = SlackScheme::<F, Comm>::commit(
+                permutation_commit_key,
+                mask.coefficients(),
+                mask_hiding,
+            )?;
+            sponge.absorb(&mask_commitment);
+            Some((mask, mask_hiding, mask_commitment))
+        }
+        None => None,
+    };
+    let inverse_hidings = match rng.as_mut() {
+        Some(rng) => [F::rand(rng), F::rand(rng)],
+        None => [F::zero(); 2],
+    };
+    let wires = MultilinearExtension::from_column(&columns.concat());
+    let (permutation_proof, inverses) = prove_permutation_check(
+        permutation_commit_key,
+        &wires,
+        &wires,
+        &permutation_extension(circuit),
+        inverse_hidings,
+        permutation_mask.as_ref().map(|(mask, _, _)| mask),
+        &mut sponge,
+    )?;
+    let copy_point = &permutation_proof.product_check_proof().evaluations().point;
+    let copy_eq = eq_extension(copy_point).evaluations().to_vec();
+    let (copy_evaluations, copy_openings) = open_witness_columns(
+        public_parameters,
+        instance,
+        witness,
+        &copy_eq,
+        |column| column * number_of_rows,
+        &mut sponge,
+        rng.as_mut(),
+    )?;
+    let inverse_openings = inverses
+        .iter()
+        .zip(inverse_hidings)
+        .map(|(inverse, hiding)| {
+            open_hiding::<F, SlackScheme<F, Comm>, _, _>(
+                permutation_commit_key,
+                inverse,
+                hiding,
+                &copy_eq,
+                &mut sponge,
+                rng.as_mut(),
+            )
+            .map(|(_, proof)| proof)
+        })
+        .collect::<Result<_, _>>()?;
+    let permutation_mask_proof = match permutation_mask {
+        Some((mask, mask_hiding, mask_commitment)) => {
+            let (_, mask_opening) = open_hiding::<F, SlackScheme<F, Comm>, _, _>(
+                permutation_commit_key,
+                mask.coefficients(),
+                mask_hiding,
+                &SumcheckMask::evaluation_vector(copy_point, PRODUCT_CHECK_DEGREE),
+                &mut sponge,
+                rng.as_mut(),
+            )?;
+            Some(PermutationMaskProof {
+                mask_commitment,
+                mask_opening,
+            })
+        }
+        None => None,
+    };
+
+    Ok(HyperPlonkProof {
+        blinding_fold,
+        gate_proof,
+        masked_gate_proof,
+        wire_evaluations,
+        wire_openings,
+        slack_evaluation,
+        slack_opening,
+        permutation_mask_proof,
+        permutation_proof,
+        copy_evaluations,
+        copy_openings,
+        inverse_openings,
+    })
+}
+
+/// Verifies that the instance is satisfied for `circuit`, which was encoded into `verifier_key`, or
+/// returns an error. The permutation commit key must be the one given to the prover.
+pub fn verify<F, Comm>(
+    public_parameters: &PublicParameters<F, Comm>,
+    verifier_key: &VerifierKey<F, Comm>,
+    circuit: &PLONKCircuit<F>,
+    permutation_commit_key: &<Comm::CommitmentSlack as HomomorphicCommitmentScheme<F>>::CommitKey,
+    instance: &RelaxedPLONKInstance<F, Comm>,
+    proof: &HyperPlonkProof<F, Comm>,
+) -> Result<(), SangriaError>
+where
+    F: PrimeField + Absorb,
+    Comm: FoldingCommitmentConfig<F>,
+    Comm::CommitmentSlack: InnerProductOpening<F>,
+    Comm::CommitmentWitness: InnerProductOpening<F>,
+{
+    verify_with::<F, Comm, PoseidonSponge<F>>(
+        public_parameters,
+        verifier_key,
+        circuit,
+        permutation_commit_key,
+        instance,
+        proof,
+    )
+}
+
+/// Verifies a proof produced by [`prove_with`] with a transcript of type `RO`.
+pub fn verify_with<F, Comm, RO>(
+    public_parameters: &PublicParameters<F, Comm>,
+    verifier_key: &VerifierKey<F, Comm>,
+    circuit: &PLONKCircuit<F>,
+    permutation_commit_key: &<Comm::CommitmentSlack as HomomorphicCommitmentScheme<F>>::CommitKey,
+    instance: &RelaxedPLONKInstance<F, Comm>,
+    proof: &HyperPlonkProof<F, Comm>,
+) -> Result<(), SangriaError>
+where
+    F: PrimeField + Absorb,
+    Comm: FoldingCommitmentConfig<F>,
+    Comm::CommitmentSlack: InnerProductOpening<F>,
+    Comm::CommitmentWitness: InnerProductOpening<F>,
+    RO: Transcript<F>,
+{
+    instance.check_public_input_rows(public_parameters.number_of_public_inputs)?;
+    let number_of_rows = circuit.number_of_rows();
+    let number_of_gates = public_parameters.number_of_gates;
+    if number_of_rows != public_parameters.number_of_public_inputs + number_of_gates + 1 {
+        return Err(SangriaError::InvalidConfig(
+            "the circuit does not match the public parameters",
+        ));
+    }
+    let number_of_variables = number_of_rows.next_power_of_two().trailing_zeros() as usize;
+    let zero_knowledge = public_parameters.config.zero_knowledge;
+    if zero_knowledge
+        != (proof.blinding_fold.is_some()
+            && proof.permutation_mask_proof.is_some()
+            && proof.permutation_proof.product_check_proof().is_masked())
+    {
+        return Err(SangriaError::InvalidProof);
+    }
+    let folded_instance = match &proof.blinding_fold {
+        Some(blinding_fold) => Some(PLONKFoldingScheme::<F, Comm, RO>::verifier_in_round(
+            public_parameters,
+            verifier_key,
+            instance,
+            &blinding_fold.instance,
+            &blinding_fold.cross_term_commitments,
+            0,
+        )?),
+        None => None,
+    };
+    let instance = folded_instance.as_ref().unwrap_or(instance);
+    let mut sponge: RO = transcript(public_parameters, verifier_key, instance);
+    // the checks the commitment schemes defer, e.g. the pairings of KZG openings, are made together
+    // once every opening was read
+    let mut witness_accumulator = Default::default();
+    let mut slack_accumulator = Default::default();
+
+    let degree = public_parameters.config.gate_degree;
+    let subclaim = match (zero_knowledge, &proof.gate_proof, &proof.masked_gate_proof) {
+        (false, Some(gate_proof), None) => verify_zero_check(
+            zero_check_degree(degree),
+            number_of_variables,
+            gate_proof,
+            &mut sponge,
+        )?,
+        (true, None, Some(masked_gate_proof)) => {
+            sponge.absorb(&masked_gate_proof.mask_commitment);
+            let subclaim = verify_masked_zero_check(
+                zero_check_degree(degree),
+                number_of_variables,
+                &masked_gate_proof.sumcheck_proof,
+                &mut sponge,
+            )?;
+            verify_hiding_opening::<F, SlackScheme<F, Comm>, _>(
+                permutation_commit_key,
+                &masked_gate_proof.mask_commitment,
+                &SumcheckMask::evaluation_vector(&subclaim.point, zero_check_degree(degree) + 1),
+                subclaim.mask_evaluation,
+                &masked_gate_proof.mask_opening,
+                &mut sponge,
+                &mut slack_accumulator,
+            )?;
+            ZeroCheckSubClaim {
+                point: subclaim.point,
+                expected_evaluation: subclaim.expected_evaluation,
+            }
+        }
+        _ => return Err(SangriaError::InvalidProof),
+    };
+    let gate_eq = eq_extension(&subclaim.point).evaluations().to_vec();
+    let [a, b, c]: [F; NUMBER_OF_WIRES] = verify_witness_columns(
+        public_parameters,
+        instance,
+        number_of_gates,
+        &gate_eq,
+        |_| 0,
+        &proof.wire_evaluations,
+        &proof.wire_openings,
+        &mut sponge,
+        &mut witness_accumulator,
+    )?
+    .try_into()
+    .map_err(|_| SangriaError::InvalidProof)?;
+    verify_hiding_opening::<F, SlackScheme<F, Comm>, _>(
+        &public_parameters.commit_key_selectors_and_slack,
+        &instance.slack_commitment(),
+        &gate_eq[..number_of_rows],
+        proof.slack_evaluation,
+        &proof.slack_opening,
+        &mut sponge,
+        &mut slack_accumulator,
+    )?;
+
+    let selectors = circuit
+        .selectors()
+        .iter()
+        .map(|selector| MultilinearExtension::from_column(selector).evaluate(&subclaim.point))
+        .collect::<Result<Vec<_>, _>>()?;
+    let u_power = |exponent: usize| instance.scaling_factor().pow([exponent as u64]);
+    let gate = u_power(degree - 1)
+        * (selectors[LEFT_SELECTOR_INDEX] * a
+            + selectors[RIGHT_SELECTOR_INDEX] * b
+            + selectors[OUTPUT_SELECTOR_INDEX] * c)
+        + u_power(degree - 2) * selectors[MULTIPLICATION_SELECTOR_INDEX] * a * b
+        + selectors[POWER_SELECTOR_INDEX] * a.pow([degree as u64])
+        + u_power(degree) * selectors[CONSTANT_SELECTOR_INDEX]
+        - proof.slack_evaluation;
+    if gate != subclaim.expected_evaluation {
+        return Err(SangriaError::InvalidProof);
+    }
+    sponge.absorb(&proof.wire_evaluations);
+    sponge.absorb(&proof.slack_evaluation);
+
+    if let Some(permutation_mask_proof) = &proof.permutation_mask_proof {
+        sponge.absorb(&permutation_mask_proof.mask_commitment);
+    }
+    let permutation_len = permutation_vector_len(number_of_rows);
+    let subclaim = verify_permutation_check(
+        permutation_len.trailing_zeros() as usize,
+        &proof.permutation_proof,
+        &mut sponge,
+    )?;
+    let copy_eq = eq_extension(&subclaim.point).evaluations().to_vec();
+    let wires: F = verify_witness_columns(
+        public_parameters,
+        instance,
+        number_of_gates,
+        &copy_eq,
+        |column| column * number_of_rows,
+        &proof.copy_evaluations,
+        &proof.copy_openings,
+        &mut sponge,
+        &mut witness_accumulator,
+    )?
+    .into_iter()
+    .sum();
+    if subclaim.left_evaluation != wires
+        || subclaim.right_evaluation != wires
+        || permutation_extension(circuit).evaluate(&subclaim.point)?
+            != subclaim.permutation_evaluation
+    {
+        return Err(SangriaError::InvalidProof);
+    }
+
+    let product_check_proof = proof.permutation_proof.product_check_proof();
+    let inverse_commitments = [
+        product_check_proof.left_inverse_commitment(),
+        product_check_proof.right_inverse_commitment(),
+    ];
+    if proof.inverse_openings.len() != inverse_commitments.len() {
+        return Err(SangriaError::InvalidProof);
+    }
+    for ((commitment, value), opening) in inverse_commitments
+        .iter()
+        .zip(subclaim.inverse_evaluations)
+        .zip(proof.inverse_openings.iter())
+    {
+        verify_hiding_opening::<F, SlackScheme<F, Comm>, _>(
+            permutation_commit_key,
+            commitment,
+            &copy_eq,
+            value,
+            opening,
+            &mut sponge,
+            &mut slack_accumulator,
+        )?;
+    }
+    match (&proof.permutation_mask_proof, subclaim.mask_evaluation) {
+        (Some(permutation_mask_proof), Some(mask_evaluation)) => {
+            verify_hiding_opening::<F, SlackScheme<F, Comm>, _>(
+                permutation_commit_key,
+                &permutation_mask_proof.mask_commitment,
+                &SumcheckMask::evaluation_vector(&subclaim.point, PRODUCT_CHECK_DEGREE),
+                mask_evaluation,
+                &permutation_mask_proof.mask_opening,
+                &mut sponge,
+                &mut slack_accumulator,
+            )?;
+        }
+        (None, None) => {}
+        _ => return Err(SangriaError::InvalidProof),
+    }
+
+    <WitnessScheme<F, Comm> as InnerProductOpening<F>>::decide(witness_accumulator)?;
+    <SlackScheme<F, Comm> as InnerProductOpening<F>>::decide(slack_accumulator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{permutation_commit_key_len, prove, verify};
+    use crate::relaxed_plonk::NUMBER_OF_WIRES;
+    use crate::{
+        folding_scheme::{PLONKFoldingScheme, SetupInfo},
+        utils::{
+            circuits::{fifth_power_plus_five, square_plus_five},
+            inner_product::{InnerProduct, InnerProductConfig},
+            poseidon::poseidon_parameters_for_test,
+        },
+        HomomorphicCommitmentScheme, NonInteractiveFoldingScheme, SangriaConfig, SangriaError,
+        TraceTable,
+    };
+    use ark_bn254::Fr;
+    use ark_crypto_primitives::sponge::poseidon::PoseidonSponge;
+    use ark_std::test_rng;
+
+    type Scheme = PLONKFoldingScheme<Fr, InnerProductConfig, PoseidonSponge<Fr>>;
+
+    /// Folds the traces of `trace_of(3)` and `trace_of(7)`, for gate equations of degree
+    /// `gate_degree`, and proves the folded instance, with a masked gate zero check if
+    /// `zero_knowledge` is set.
+    fn check_folded_instance(
+        trace_of: fn(u64) -> TraceTable<Fr>,
+        gate_degree: usize,
+        zero_knowledge: bool,
+    ) {
+        let rng = &mut test_rng();
+        let info = SetupInfo {
+            number_of_public_inputs: 1,
+            number_of_gates: 2,
+            config: SangriaConfig {
+                gate_degree,
+                zero_knowledge,
+                ..Default::default()
+            },
+            poseidon_constants: poseidon_parameters_for_test(),
+        };
+        let pp = Scheme::setup(&info, rng).unwrap();
+
+        let pairs = [3u64, 7].map(|x| {
+            let trace = trace_of(x);
+            let instance_columns = trace.instance_columns();
+            let (circuit, witness) = trace.into_circuit_and_witness(Vec::new(), rng).unwrap();
+            let instance = Scheme::commit_witness(&pp, instance_columns, &witness).unwrap();
+            (circuit, instance, witness)
+        });
+        let (circuit, left_instance, left_witness) = pairs[0].clone();
+        let (_, right_instance, right_witness) = pairs[1].clone();
+        let (pk, vk) = Scheme::encode(&pp, &circuit, rng).unwrap();
+        let (instance, witness, _) = Scheme::prover(
+            &pp,
+            &pk,
+            &left_instance,
+            &left_witness,
+            &right_instance,
+            &right_witness,
+            rng,
+        )
+        .unwrap();
+        let permutation_commit_key = InnerProduct::setup(
+            rng,
+            permutation_commit_key_len(circuit.number_of_rows(), gate_degree),
+        );
+
+        let proof = prove(
+            &pp,
+            &vk,
+            &circuit,
+            &permutation_commit_key,
+            &instance,
+            &witness,
+        )
+        .unwrap();
+        assert_eq!(
+            verify(
+                &pp,
+                &vk,
+                &circuit,
+                &permutation_commit_key,
+                &instance,
+                &proof
+            ),
+            Ok(())
+        );
+
+        // the proof is bound to the instance
+        assert_eq!(
+            verify(
+                &pp,
+                &vk,
+                &circuit,
+                &permutation_commit_key,
+                &left_instance,
+                &proof
+            ),
+            Err(SangriaError::InvalidProof)
+        );
+
+        // a witness of the other trace does not open the first instance
+        let proof = prove(
+            &pp,
+            &vk,
+            &circuit,
+            &permutation_commit_key,
+            &left_instance,
+            &right_witness,
+        )
+        .unwrap();
+        assert_eq!(
+            verify(
+                &pp,
+                &vk,
+                &circuit,
+                &permutation_commit_key,
+                &left_instance,
+                &proof
+            ),
+            Err(SangriaError::InvalidProof)
+        );
+    }
+
+    /// Proves a folded instance of `square_plus_five` and returns the vectors revealed by the
+    /// openings of its witness columns, with whether every opening of the proof is hiding, and the
+    /// witness columns.
+    fn revealed_witness_vectors(zero_knowledge: bool) -> (Vec<Vec<Fr>>, bool, Vec<Vec<Fr>>) {
+        let rng = &mut test_rng();
+        let info = SetupInfo {
+            number_of_public_inputs: 1,
+            number_of_gates: 2,
+            config: SangriaConfig {
+                zero_knowledge,
+                ..Default::default()
+            },
+            poseidon_constants: poseidon_parameters_for_test(),
+        };
+        let pp = Scheme::setup(&info, rng).unwrap();
+        let trace = square_plus_five(3);
+        let instance_columns = trace.instance_columns();
+        let (circuit, witness) = trace.into_circuit_and_witness(Vec::new(), rng).unwrap();
+        let instance = Scheme::commit_witness(&pp, instance_columns, &witness).unwrap();
+        let (_, vk) = Scheme::encode(&pp, &circuit, rng).unwrap();
+        let permutation_commit_key =
+            InnerProduct::setup(rng, permutation_commit_key_len(circuit.number_of_rows(), 2));
+
+        let proof = prove(
+            &pp,
+            &vk,
+            &circuit,
+            &permutation_commit_key,
+            &instance,
+            &witness,
+        )
+        .unwrap();
+        assert_eq!(
+            verify(
+                &pp,
+                &vk,
+                &circuit,
+                &permutation_commit_key,
+                &instance,
+                &proof
+            ),
+            Ok(())
+        );
+        assert_eq!(proof.blinding_fold.is_some(), zero_knowledge);
+        assert_eq!(proof.permutation_mask_proof.is_some(), zero_knowledge);
+        assert_eq!(
+            proof.permutation_proof.product_check_proof().is_masked(),
+            zero_knowledge
+        );
+
+        let witness_openings = proof.wire_openings.iter().chain(&proof.copy_openings);
+        let all_hiding = witness_openings.clone().all(|opening| opening.is_hiding())
+            && proof.slack_opening.is_hiding()
+            && proof
+                .inverse_openings
+                .iter()
+                .all(|opening| opening.is_hiding())
+            && proof
+                .masked_gate_proof
+                .iter()
+                .all(|masked_gate_proof| masked_gate_proof.mask_opening.is_hiding());
+        let revealed = witness_openings
+            .map(|opening| opening.proof().0.clone())
+            .collect();
+        let columns = (0..NUMBER_OF_WIRES)
+            .map(|column| witness.witness_column_with_rand(column).unwrap().0)
+            .collect();
+
+        (revealed, all_hiding, columns)
+    }
+
+    #[test]
+    fn zero_knowledge_openings_hide_the_witness() {
+        let (revealed, all_hiding, columns) = revealed_witness_vectors(true);
+        assert!(all_hiding);
+        assert!(revealed.iter().all(|vector| !columns.contains(vector)));
+
+        // without zero knowledge, the openings reveal the witness columns
+        let (revealed, all_hiding, columns) = revealed_witness_vectors(false);
+        assert!(!all_hiding);
+        assert!(revealed.iter().all(|vector| columns.contains(vector)));
+    }
+
+    #[test]
+    fn folded_instance_is_proven() {
+        check_folded_instance(square_plus_five, 2, true);
+    }
+
+    #[test]
+    fn folded_instance_is_proven_without_zero_knowledge() {
+        check_folded_instance(square_plus_five, 2, false);
+    }
+
+    #[test]
+    fn folded_power_gates_are_proven() {
+        check_folded_instance(fifth_power_plus_five, 5, true);
+    }
+}
@@ -1,132 +1,2161 @@
-use ark_ff::PrimeField;
-use ark_std::{marker::PhantomData, rand::Rng};
+use ark_crypto_primitives::sponge::{poseidon::PoseidonSponge, Absorb, CryptographicSponge};
+use ark_ff::{PrimeField, Zero};
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, SerializationError, Valid, Validate,
+};
+use ark_std::io::{Read, Write};
+use ark_std::rand::{rngs::StdRng, Rng, SeedableRng};
+use std::time::{Duration, Instant};
 
 use crate::{
-    folding_scheme::{self, FoldingCommitmentConfig},
-    RelaxedPLONKInstance, RelaxedPLONKWitness, StepCircuit, IVC,
+    folding_scheme::{self, FoldAudit, FoldingCommitmentConfig, SetupInfo},
+    hyperplonk::{self, HyperPlonkProof},
+    parallel::{available_threads, parallel_map, parallel_map_on},
+    relaxed_plonk::NUMBER_OF_WIRES,
+    transcript::Transcript,
+    vector_commitment::{HomomorphicCommitmentScheme, InnerProductOpening},
+    AugmentedCircuit, NonInteractiveFoldingScheme, PLONKCircuit, PLONKFoldingScheme,
+    PLONKStepCircuit, RelaxedPLONKInstance, RelaxedPLONKWitness, Sangria, SangriaError,
+    VerifierMetrics,
 };
 
-/// A "pre-sangria" scheme. Implements IVC from a NIFS as described in Construction3 of Nova.
-/// WARNING: this scheme is neither succinct nor zero-knowledge.
+type FoldingScheme<F, Comm> = PLONKFoldingScheme<F, Comm, PoseidonSponge<F>>;
+
+/// The commitments to the cross terms of one fold.
+type CrossTermCommitments<F, Comm> =
+    Vec<<<Comm as FoldingCommitmentConfig<F>>::CommitmentSlack as HomomorphicCommitmentScheme<F>>::Commitment>;
+
+type PermutationCommitKey<F, Comm> =
+    <<Comm as FoldingCommitmentConfig<F>>::CommitmentSlack as HomomorphicCommitmentScheme<F>>::CommitKey;
+
+/// The wires of a step's gates holding the encoded current and next states, each given as
+/// (column, row) in the order of the encoding.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StateWires {
+    /// The wires holding the elements of the current state.
+    pub current_state: Vec<(usize, usize)>,
+    /// The wires holding the elements of the next state.
+    pub next_state: Vec<(usize, usize)>,
+}
+
+/// The outcome of a successful [self-test](Sangria::self_test): the number of steps of the chain,
+/// the time spent proving them and the time spent verifying the proof of the chain.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SelfTestReport {
+    /// The number of steps proved.
+    pub number_of_steps: usize,
+    /// The total time spent proving the steps.
+    pub proving_time: Duration,
+    /// The time spent verifying the proof of all steps.
+    pub verification_time: Duration,
+}
+
+/// The Sangria prover key contains the public parameters, the prover key of the folding scheme
+/// for the augmented circuit, the commit key of the permutation check (to compress proofs) and the
+/// augmented circuit itself (to synthesize steps).
+pub struct ProverKey<F: PrimeField, Comm: FoldingCommitmentConfig<F>, SC> {
+    pub(crate) public_parameters: folding_scheme::PublicParameters<F, Comm>,
+    pub(crate) folding_key: folding_scheme::ProverKey<F, Comm>,
+    pub(crate) permutation_commit_key: PermutationCommitKey<F, Comm>,
+    pub(crate) augmented_circuit: AugmentedCircuit<SC>,
+    #[cfg(feature = "usage-tracking")]
+    pub(crate) usage: crate::usage::UsageLog,
+}
+
+impl<F: PrimeField, Comm: FoldingCommitmentConfig<F>, SC> ProverKey<F, Comm, SC> {
+    /// Returns the step circuit the key was encoded from.
+    pub fn step_circuit(&self) -> &SC {
+        self.augmented_circuit.step_circuit()
+    }
+}
+
+#[cfg(feature = "usage-tracking")]
+impl<F: PrimeField, Comm: FoldingCommitmentConfig<F>, SC> ProverKey<F, Comm, SC> {
+    /// Returns the statistics of the proofs produced under this key so far.
+    pub fn usage(&self) -> crate::UsageStatistics {
+        self.usage.statistics()
+    }
+}
+
+/// Prover keys are serialized without their usage statistics, and the step circuit is serialized
+/// with the key so that it can be synthesized again.
+impl<F, Comm, SC> CanonicalSerialize for ProverKey<F, Comm, SC>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+    SC: CanonicalSerialize,
+{
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        self.public_parameters
+            .serialize_with_mode(&mut writer, compress)?;
+        self.folding_key
+            .serialize_with_mode(&mut writer, compress)?;
+        self.permutation_commit_key
+            .serialize_with_mode(&mut writer, compress)?;
+        self.augmented_circuit
+            .step_circuit()
+            .serialize_with_mode(&mut writer, compress)
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        self.public_parameters.serialized_size(compress)
+            + self.folding_key.serialized_size(compress)
+            + self.permutation_commit_key.serialized_size(compress)
+            + self
+                .augmented_circuit
+                .step_circuit()
+                .serialized_size(compress)
+    }
+}
+
+impl<F, Comm, SC> Valid for ProverKey<F, Comm, SC>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+    SC: CanonicalDeserialize,
+{
+    fn check(&self) -> Result<(), SerializationError> {
+        Ok(())
+    }
+}
+
+/// A deserialized prover key starts with empty usage statistics.
+impl<F, Comm, SC> CanonicalDeserialize for ProverKey<F, Comm, SC>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+    SC: CanonicalDeserialize,
+{
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        Ok(Self {
+            public_parameters: CanonicalDeserialize::deserialize_with_mode(
+                &mut reader,
+                compress,
+                validate,
+            )?,
+            folding_key: CanonicalDeserialize::deserialize_with_mode(
+                &mut reader,
+                compress,
+                validate,
+            )?,
+            permutation_commit_key: CanonicalDeserialize::deserialize_with_mode(
+                &mut reader,
+                compress,
+                validate,
+            )?,
+            augmented_circuit: AugmentedCircuit::new(SC::deserialize_with_mode(
+                &mut reader,
+                compress,
+                validate,
+            )?),
+            #[cfg(feature = "usage-tracking")]
+            usage: Default::default(),
+        })
+    }
+}
+
+/// The Sangria verifier key contains the public parameters, the verifier key of the folding scheme,
+/// the augmented PLONK circuit (to decide the running instance), the commit key of the permutation
+/// check (to verify compressed proofs) and the augmented circuit (to encode states).
+pub struct VerifierKey<F: PrimeField, Comm: FoldingCommitmentConfig<F>, SC> {
+    pub(crate) public_parameters: folding_scheme::PublicParameters<F, Comm>,
+    pub(crate) folding_key: folding_scheme::VerifierKey<F, Comm>,
+    pub(crate) circuit: PLONKCircuit<F>,
+    pub(crate) permutation_commit_key: PermutationCommitKey<F, Comm>,
+    pub(crate) augmented_circuit: AugmentedCircuit<SC>,
+}
+
+impl<F, Comm, SC> CanonicalSerialize for VerifierKey<F, Comm, SC>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+    SC: CanonicalSerialize,
+{
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        self.public_parameters
+            .serialize_with_mode(&mut writer, compress)?;
+        self.folding_key
+            .serialize_with_mode(&mut writer, compress)?;
+        self.circuit.serialize_with_mode(&mut writer, compress)?;
+        self.permutation_commit_key
+            .serialize_with_mode(&mut writer, compress)?;
+        self.augmented_circuit
+            .step_circuit()
+            .serialize_with_mode(&mut writer, compress)
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        self.public_parameters.serialized_size(compress)
+            + self.folding_key.serialized_size(compress)
+            + self.circuit.serialized_size(compress)
+            + self.permutation_commit_key.serialized_size(compress)
+            + self
+                .augmented_circuit
+                .step_circuit()
+                .serialized_size(compress)
+    }
+}
+
+impl<F, Comm, SC> Valid for VerifierKey<F, Comm, SC>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+    SC: CanonicalDeserialize,
+{
+    fn check(&self) -> Result<(), SerializationError> {
+        Ok(())
+    }
+}
+
+impl<F, Comm, SC> CanonicalDeserialize for VerifierKey<F, Comm, SC>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+    SC: CanonicalDeserialize,
+{
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        Ok(Self {
+            public_parameters: CanonicalDeserialize::deserialize_with_mode(
+                &mut reader,
+                compress,
+                validate,
+            )?,
+            folding_key: CanonicalDeserialize::deserialize_with_mode(
+                &mut reader,
+                compress,
+                validate,
+            )?,
+            circuit: PLONKCircuit::deserialize_with_mode(&mut reader, compress, validate)?,
+            permutation_commit_key: CanonicalDeserialize::deserialize_with_mode(
+                &mut reader,
+                compress,
+                validate,
+            )?,
+            augmented_circuit: AugmentedCircuit::new(SC::deserialize_with_mode(
+                &mut reader,
+                compress,
+                validate,
+            )?),
+        })
+    }
+}
+
+impl<F: PrimeField, Comm: FoldingCommitmentConfig<F>, SC> VerifierKey<F, Comm, SC> {
+    /// Returns the step circuit the key was encoded from.
+    pub fn step_circuit(&self) -> &SC {
+        self.augmented_circuit.step_circuit()
+    }
+}
+
+impl<F, Comm, SC> VerifierKey<F, Comm, SC>
+where
+    F: PrimeField + Absorb,
+    Comm: FoldingCommitmentConfig<F>,
+    SC: PLONKStepCircuit<F>,
+{
+    /// Returns a digest of the statement that `number_of_steps` steps lead from `origin_state` to
+    /// `current_state`, bound to this verifier key (and through it to the circuit and the public
+    /// parameters). A service that verified a proof of the statement can sign the digest with its
+    /// own key, so that systems trusting the service accept the statement without running the
+    /// verifier.
+    pub fn statement_digest(
+        &self,
+        origin_state: &SC::State,
+        current_state: &SC::State,
+        number_of_steps: usize,
+    ) -> F {
+        let step_circuit = self.augmented_circuit.step_circuit();
+        let mut sponge = PoseidonSponge::new(&self.public_parameters.poseidon_constants);
+        sponge.absorb(&self.folding_key);
+        sponge.absorb(&F::from(number_of_steps as u64));
+        sponge.absorb(&step_circuit.encode_state(origin_state));
+        sponge.absorb(&step_circuit.encode_state(current_state));
+
+        sponge.squeeze_field_elements(1)[0]
+    }
+}
+
+/// An experimental batched IVC proof: the fresh instance of every step, the commitments to the
+/// cross terms folding them and the resulting running instance-witness pair. A proof built step by
+/// step folds the steps one after the other, while [merging](Sangria::merge) proofs folds their
+/// running instances together, so the fold shape records how many folds follow each step in a
+/// proof's tree of folds.
+///
+/// WARNING: this is not an IVC proof in the sense of Nova. The batched augmented circuit does not
+/// verify the folding of the previous steps, so [`Sangria::verify`] re-folds every step instance
+/// itself: the proof and the work of the verifier grow with the number of steps (by one instance
+/// and one commitment per cross term per step), and the proof is not zero-knowledge, as it contains
+/// the running witness.
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct BatchedIVCProof<F: PrimeField, Comm: FoldingCommitmentConfig<F>> {
+    pub(crate) step_instances: Vec<RelaxedPLONKInstance<F, Comm>>,
+    pub(crate) cross_term_commitments: Vec<CrossTermCommitments<F, Comm>>,
+    pub(crate) fold_shape: Vec<usize>,
+    running_instance: RelaxedPLONKInstance<F, Comm>,
+    running_witness: RelaxedPLONKWitness<F>,
+}
+
+impl<F: PrimeField, Comm: FoldingCommitmentConfig<F>> BatchedIVCProof<F, Comm> {
+    /// Returns the number of steps proved.
+    pub fn number_of_steps(&self) -> usize {
+        self.step_instances.len()
+    }
+
+    /// Checks that `ancestor` is a proof of a prefix of the chain proved by this proof, i.e. that
+    /// this proof was obtained by proving further steps on top of `ancestor`, or returns an error.
+    /// Both proofs should have been verified: a verified proof's running instance is determined by
+    /// its step instances and cross term commitments, so comparing those is enough.
+    pub fn check_ancestor(&self, ancestor: &Self) -> Result<(), SangriaError> {
+        let steps = ancestor.number_of_steps();
+        if steps > self.number_of_steps()
+            || self.step_instances[..steps] != ancestor.step_instances[..]
+            || self.cross_term_commitments.get(..steps.saturating_sub(1))
+                != Some(&ancestor.cross_term_commitments[..])
+            || self.fold_shape.get(..steps) != Some(&ancestor.fold_shape[..])
+        {
+            return Err(SangriaError::InvalidProof);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the running instance the step instances fold into.
+    pub fn running_instance(&self) -> &RelaxedPLONKInstance<F, Comm> {
+        &self.running_instance
+    }
+
+    /// Returns the instance of the first step and the messages folding every later step into the
+    /// running instance, to be archived for [`Sangria::reconstruct_accumulator`], or an error if
+    /// the proof does not fold its steps one after the other (e.g. it [merged](Sangria::merge)
+    /// proofs).
+    #[allow(clippy::type_complexity)]
+    pub fn prover_messages(
+        &self,
+    ) -> Result<(RelaxedPLONKInstance<F, Comm>, Vec<StepMessage<F, Comm>>), SangriaError> {
+        let (origin_instance, step_instances) = self
+            .step_instances
+            .split_first()
+            .ok_or(SangriaError::InvalidProof)?;
+        // the shape of a chain is `[0, 1, 1, ...]`
+        if self.fold_shape.len() != self.step_instances.len()
+            || (self.fold_shape.iter().enumerate())
+                .any(|(step, &folds)| folds != usize::from(step > 0))
+            || self.cross_term_commitments.len() != step_instances.len()
+        {
+            return Err(SangriaError::InvalidProof);
+        }
+
+        let messages = step_instances
+            .iter()
+            .zip(&self.cross_term_commitments)
+            .map(|(step_instance, cross_term_commitments)| StepMessage {
+                step_instance: step_instance.clone(),
+                cross_term_commitments: cross_term_commitments.clone(),
+            })
+            .collect();
+        Ok((origin_instance.clone(), messages))
+    }
+
+    /// Returns the state the last step ends in, read from the public inputs of its instance, given
+    /// the number of elements encoding a state.
+    pub(crate) fn last_state(&self, state_len: usize) -> Result<Vec<F>, SangriaError> {
+        let last_instance = self
+            .step_instances
+            .last()
+            .ok_or(SangriaError::InvalidProof)?;
+
+        Ok(step_states(last_instance, state_len)?.1)
+    }
+
+    /// Returns the state each step ends in, in the order of the steps, read from the public inputs
+    /// of the step instances, given the number of elements encoding a state.
+    pub(crate) fn next_states(&self, state_len: usize) -> Result<Vec<Vec<F>>, SangriaError> {
+        self.step_instances
+            .iter()
+            .map(|instance| Ok(step_states(instance, state_len)?.1))
+            .collect()
+    }
+
+    /// Checks a proof received from an untrusted party, e.g. a deserialized one, before it is
+    /// verified: every instance must have the layout of the instances of `verifier_key`, every fold
+    /// must have one commitment per cross term and every commitment must be valid, e.g. a point on
+    /// the curve and in the prime-order subgroup. [`Sangria::verify`] validates the proofs it
+    /// verifies.
+    pub fn validate<SC>(
+        &self,
+        verifier_key: &VerifierKey<F, Comm, SC>,
+    ) -> Result<(), SangriaError> {
+        validate_instances(
+            verifier_key,
+            &self.step_instances,
+            &self.cross_term_commitments,
+            &self.running_instance,
+        )
+    }
+}
+
+impl<F, Comm> Clone for BatchedIVCProof<F, Comm>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    fn clone(&self) -> Self {
+        Self {
+            step_instances: self.step_instances.clone(),
+            cross_term_commitments: self.cross_term_commitments.clone(),
+            fold_shape: self.fold_shape.clone(),
+            running_instance: self.running_instance.clone(),
+            running_witness: self.running_witness.clone(),
+        }
+    }
+}
+
+/// What the prover sends to fold a step into the running instance of a chain: the fresh instance of
+/// the step and the commitments to the cross terms of the fold. Unlike a proof, a sequence of
+/// messages holds no witness, so it can be archived and handed to auditors.
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct StepMessage<F: PrimeField, Comm: FoldingCommitmentConfig<F>> {
+    step_instance: RelaxedPLONKInstance<F, Comm>,
+    cross_term_commitments: CrossTermCommitments<F, Comm>,
+}
+
+impl<F, Comm> Clone for StepMessage<F, Comm>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    fn clone(&self) -> Self {
+        Self {
+            step_instance: self.step_instance.clone(),
+            cross_term_commitments: self.cross_term_commitments.clone(),
+        }
+    }
+}
+
+/// A compressed batched proof: the step instances and cross term commitments of a
+/// [`BatchedIVCProof`], and a HyperPlonk proof that the running instance is satisfied in place of
+/// its witness. It only replaces the running witness; the verifier still re-folds every step.
 ///
-/// This scheme makes use of a main field and a help field. The trace resulting of running one step of the computation
-/// is no longer in the MainField F_p, we have moved to some helper field F_q. Using cycles of curves, we can define a helper circuit
-/// in F_q that allows us to cycle back to F_p in order to compute our next step in F_p again.
-pub(crate) struct SangriaNoCompression<
-    MainField: PrimeField,
-    HelperField: PrimeField,
-    Config: SangriaIVCConfig<MainField, HelperField>,
-    SC: StepCircuit<MainField>,
->(PhantomData<(MainField, HelperField, Config, SC)>);
-
-/// A `SangriaIVCConfig` is a trait that allows to bundle types related to an IVC instantiation.
-/// By combining all the types here we avoid passing them as generics in structs such as `VerifierKey`, `ProverKey`, etc
-pub trait SangriaIVCConfig<MainField: PrimeField, HelperField: PrimeField> {
-    type MainCommitmentSchemes: FoldingCommitmentConfig<MainField>;
-
-    type HelperCommitmentSchemes: FoldingCommitmentConfig<HelperField>;
-}
-
-/// Public parameters for the SangriaIVC scheme (no compression) contains commit parameters for the step circuit
-/// in the main field, and commit parameters for the helper circuit in the helper field.
-pub(crate) struct PublicParameters<
-    MainField: PrimeField,
-    HelperField: PrimeField,
-    Config: SangriaIVCConfig<MainField, HelperField>,
-> {
-    pub _main_nifs_pp: folding_scheme::PublicParameters<MainField, Config::MainCommitmentSchemes>,
-    pub _helper_nifs_pp:
-        folding_scheme::PublicParameters<HelperField, Config::HelperCommitmentSchemes>,
-}
-
-/// The SangriaIVC VerifierKey contains verifier keys for the foldings of the main and helper
-/// circuits. It also contains a description of the step circuit.
-pub(crate) struct VerifierKey<
-    MainField: PrimeField,
-    HelperField: PrimeField,
-    Config: SangriaIVCConfig<MainField, HelperField>,
-    SC: StepCircuit<MainField>,
-> {
-    pub _main_nifs_vk: folding_scheme::VerifierKey<MainField, Config::MainCommitmentSchemes>,
-    pub _helper_nifs_vk: folding_scheme::VerifierKey<HelperField, Config::HelperCommitmentSchemes>,
-    pub _step_circuit: SC,
-}
-
-/// The SangriaIVC ProverKey contains prover keys for the foldings of the main and helper
-/// circuits. It also contains a description of the step circuit.
-pub(crate) struct ProverKey<
-    MainField: PrimeField,
-    HelperField: PrimeField,
-    Config: SangriaIVCConfig<MainField, HelperField>,
-    SC: StepCircuit<MainField>,
-> {
-    pub _main_nifs_pk: folding_scheme::ProverKey<MainField, Config::MainCommitmentSchemes>,
-    pub _helper_nifs_pk: folding_scheme::ProverKey<HelperField, Config::HelperCommitmentSchemes>,
-    pub _step_circuit: SC,
-}
-
-/// A half cycle proof is composed of two instance-witness pairs: one running instance-witness
-/// that captures steps 0 to i-1 (via folding) and one instance-witness for the i-th step (the latest).
-pub(crate) struct HalfCycleProof<F: PrimeField, Comm: FoldingCommitmentConfig<F>> {
-    pub _latest_step_instance: RelaxedPLONKInstance<F, Comm>,
-    pub _latest_step_witness: RelaxedPLONKWitness<F>,
-    pub _running_instance: RelaxedPLONKInstance<F, Comm>,
-    pub _running_witness: RelaxedPLONKWitness<F>,
-}
-
-/// An IVC proof is composed of two half-cycle proofs. Each half cycle proof is composed
-/// of two instance-witness pairs: one running instance-witness that captures steps 0 to i-1 (via folding)
-/// and one instance-witness for the i-th step (the latest).
-pub(crate) struct IVCProof<
-    MainField: PrimeField,
-    HelperField: PrimeField,
-    Config: SangriaIVCConfig<MainField, HelperField>,
-> {
-    pub _main_half_proof: HalfCycleProof<MainField, Config::MainCommitmentSchemes>,
-    pub _helper_half_proof: HalfCycleProof<HelperField, Config::HelperCommitmentSchemes>,
-}
-
-impl<MainField, HelperField, Config, SC> IVC<MainField, SC>
-    for SangriaNoCompression<MainField, HelperField, Config, SC>
-where
-    MainField: PrimeField,
-    HelperField: PrimeField,
-    Config: SangriaIVCConfig<MainField, HelperField>,
-    SC: StepCircuit<MainField>,
-{
-    type PublicParameters = PublicParameters<MainField, HelperField, Config>;
-    type ProverKey = ProverKey<MainField, HelperField, Config, SC>;
-    type VerifierKey = VerifierKey<MainField, HelperField, Config, SC>;
-    type Proof = IVCProof<MainField, HelperField, Config>;
-
-    fn setup<R: Rng>(_rng: &mut R) -> Self::PublicParameters {
-        todo!()
-    }
-
-    fn encode<R: Rng>(
-        _public_parameters: &Self::PublicParameters,
-        _step_circuit: &SC,
-        _rng: &mut R,
-    ) -> Result<(Self::ProverKey, Self::VerifierKey), crate::SangriaError> {
-        todo!()
-    }
-
-    fn prove_step(
-        _prover_key: &Self::ProverKey,
-        _origin_state: &SC::State,
-        _current_state: SC::State,
-        _current_proof: Option<Self::Proof>,
-        _current_witness: &SC::Witness,
-    ) -> Result<(SC::State, Self::Proof), crate::SangriaError> {
-        todo!()
-    }
-
-    fn verify(
-        _verifier_key: &Self::VerifierKey,
-        _origin_state: &SC::State,
-        _current_state: SC::State,
-        _current_proof: Option<Self::Proof>,
-    ) -> Result<(), crate::SangriaError> {
-        todo!()
+/// WARNING: like [`BatchedIVCProof`], it grows with the number of steps and is experimental.
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct CompressedBatchedIVCProof<F, Comm>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+    Comm::CommitmentSlack: InnerProductOpening<F>,
+    Comm::CommitmentWitness: InnerProductOpening<F>,
+{
+    step_instances: Vec<RelaxedPLONKInstance<F, Comm>>,
+    cross_term_commitments: Vec<CrossTermCommitments<F, Comm>>,
+    fold_shape: Vec<usize>,
+    running_instance: RelaxedPLONKInstance<F, Comm>,
+    snark: HyperPlonkProof<F, Comm>,
+}
+
+impl<F, Comm> CompressedBatchedIVCProof<F, Comm>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+    Comm::CommitmentSlack: InnerProductOpening<F>,
+    Comm::CommitmentWitness: InnerProductOpening<F>,
+{
+    /// Returns the number of steps proved.
+    pub fn number_of_steps(&self) -> usize {
+        self.step_instances.len()
+    }
+
+    /// Checks the step instances, the cross term commitments and the running instance of a proof
+    /// received from an untrusted party like [`BatchedIVCProof::validate`]. The group elements of the
+    /// HyperPlonk proof are checked when it is deserialized with validation.
+    pub fn validate<SC>(
+        &self,
+        verifier_key: &VerifierKey<F, Comm, SC>,
+    ) -> Result<(), SangriaError> {
+        validate_instances(
+            verifier_key,
+            &self.step_instances,
+            &self.cross_term_commitments,
+            &self.running_instance,
+        )
+    }
+}
+
+/// Checks the instances and the cross term commitments of a proof against `verifier_key`, checking
+/// all the commitments of the proof in one batch per commitment scheme.
+fn validate_instances<F, Comm, SC>(
+    verifier_key: &VerifierKey<F, Comm, SC>,
+    step_instances: &[RelaxedPLONKInstance<F, Comm>],
+    cross_term_commitments: &[CrossTermCommitments<F, Comm>],
+    running_instance: &RelaxedPLONKInstance<F, Comm>,
+) -> Result<(), SangriaError>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    let schema = &verifier_key.folding_key.instance_schema;
+    if cross_term_commitments
+        .iter()
+        .any(|commitments| commitments.len() != schema.cross_term_commitments)
+    {
+        return Err(SangriaError::InvalidProof);
+    }
+
+    let instances: Vec<_> = step_instances.iter().chain([running_instance]).collect();
+    RelaxedPLONKInstance::validate_batch(
+        schema,
+        &instances,
+        cross_term_commitments.iter().flatten(),
+    )
+}
+
+/// Reads the current and next states of a step from the public inputs of its instance.
+fn step_states<F, Comm>(
+    instance: &RelaxedPLONKInstance<F, Comm>,
+    state_len: usize,
+) -> Result<(Vec<F>, Vec<F>), SangriaError>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    let mut public_inputs = instance.public_inputs()?;
+    if public_inputs.len() != 2 * state_len {
+        return Err(SangriaError::PublicInputMismatch);
+    }
+    let next_state = public_inputs.split_off(state_len);
+
+    Ok((public_inputs, next_state))
+}
+
+/// Checks that the step instances are fresh and chain from the origin state to the current state,
+/// and re-folds them along the fold shape like [`fold_steps`]. Returns the running instance they
+/// fold into.
+fn refold_steps<F, Comm, Fold>(
+    origin: Vec<F>,
+    current: &[F],
+    step_instances: &[RelaxedPLONKInstance<F, Comm>],
+    cross_term_commitments: &[CrossTermCommitments<F, Comm>],
+    fold_shape: &[usize],
+    fold: Fold,
+) -> Result<RelaxedPLONKInstance<F, Comm>, SangriaError>
+where
+    F: PrimeField + Absorb,
+    Comm: FoldingCommitmentConfig<F>,
+    Fold: FnMut(
+        &RelaxedPLONKInstance<F, Comm>,
+        &RelaxedPLONKInstance<F, Comm>,
+        &CrossTermCommitments<F, Comm>,
+    ) -> Result<RelaxedPLONKInstance<F, Comm>, SangriaError>,
+{
+    check_step_chain(origin, current, step_instances)?;
+    fold_steps(step_instances, cross_term_commitments, fold_shape, fold)
+}
+
+/// Checks that the step instances, in the order of the steps, are fresh and chain from the origin
+/// state to the current state.
+pub(crate) fn check_step_chain<'a, F, Comm>(
+    origin: Vec<F>,
+    current: &[F],
+    step_instances: impl IntoIterator<Item = &'a RelaxedPLONKInstance<F, Comm>>,
+) -> Result<(), SangriaError>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F> + 'a,
+{
+    let mut state = origin;
+    for instance in step_instances {
+        if instance.scaling_factor() != F::one()
+            || !instance.slack_commitment().is_zero()
+            || !instance.extra_commitments().is_empty()
+        {
+            return Err(SangriaError::InvalidProof);
+        }
+
+        let (step_current, step_next) = step_states(instance, state.len())?;
+        if step_current != state {
+            return Err(SangriaError::InvalidProof);
+        }
+        state = step_next;
+    }
+    if state != current {
+        return Err(SangriaError::InvalidProof);
+    }
+
+    Ok(())
+}
+
+/// Re-folds the step instances with the cross term commitments along the fold shape, and returns
+/// the running instance they fold into. The steps are pushed on a stack in order and each step is
+/// followed by as many folds as its entry in `fold_shape`, each calling `fold` with the two
+/// instances on top of the stack (the earlier first) and the next cross term commitments. The
+/// shape of a chain of steps is `[0, 1, 1, ...]`. The instances may be of either relation.
+pub(crate) fn fold_steps<Instance, Message, Fold>(
+    step_instances: &[Instance],
+    cross_term_commitments: &[Message],
+    fold_shape: &[usize],
+    mut fold: Fold,
+) -> Result<Instance, SangriaError>
+where
+    Instance: Clone,
+    Fold: FnMut(&Instance, &Instance, &Message) -> Result<Instance, SangriaError>,
+{
+    if fold_shape.len() != step_instances.len() {
+        return Err(SangriaError::InvalidProof);
+    }
+
+    let mut cross_term_commitments = cross_term_commitments.iter();
+    let mut stack = Vec::new();
+    for (instance, &folds) in step_instances.iter().zip(fold_shape) {
+        stack.push(instance.clone());
+        for _ in 0..folds {
+            let (Some(right), Some(left), Some(cross_term_commitments)) =
+                (stack.pop(), stack.pop(), cross_term_commitments.next())
+            else {
+                return Err(SangriaError::InvalidProof);
+            };
+            stack.push(fold(&left, &right, cross_term_commitments)?);
+        }
+    }
+
+    match (stack.pop(), stack.is_empty(), cross_term_commitments.next()) {
+        (Some(running_instance), true, None) => Ok(running_instance),
+        _ => Err(SangriaError::InvalidProof),
+    }
+}
+
+/// Returns a closure folding an instance into the running instance with the NIFS verifier, as
+/// expected by [`refold_steps`]. The folds are numbered in the order the closure runs them, which is
+/// the order of their cross-term commitments in the proof.
+#[allow(clippy::type_complexity)]
+pub(crate) fn nifs_verifier<'a, F, Comm, SC>(
+    verifier_key: &'a VerifierKey<F, Comm, SC>,
+) -> impl FnMut(
+    &RelaxedPLONKInstance<F, Comm>,
+    &RelaxedPLONKInstance<F, Comm>,
+    &CrossTermCommitments<F, Comm>,
+) -> Result<RelaxedPLONKInstance<F, Comm>, SangriaError>
+       + 'a
+where
+    F: PrimeField + Absorb,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    let mut round = 0;
+    move |running_instance, instance, cross_term_commitments| {
+        round += 1;
+        FoldingScheme::<F, Comm>::verifier_in_round(
+            &verifier_key.public_parameters,
+            &verifier_key.folding_key,
+            running_instance,
+            instance,
+            cross_term_commitments,
+            round - 1,
+        )
+    }
+}
+
+/// Checks that the re-folded running instance is the one of the proof and that the running witness
+/// satisfies it.
+pub(crate) fn decide<F, Comm, SC>(
+    verifier_key: &VerifierKey<F, Comm, SC>,
+    running_instance: &RelaxedPLONKInstance<F, Comm>,
+    proof: &BatchedIVCProof<F, Comm>,
+) -> Result<(), SangriaError>
+where
+    F: PrimeField + Absorb,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    if *running_instance != proof.running_instance {
+        return Err(SangriaError::InvalidProof);
+    }
+
+    FoldingScheme::<F, Comm>::check_relation(
+        &verifier_key.public_parameters,
+        &verifier_key.circuit,
+        running_instance,
+        &proof.running_witness,
+    )
+    .map_err(|_| SangriaError::InvalidProof)
+}
+
+/// Executes a step of the circuit of `prover_key` from `current_state` and commits to the trace
+/// of the augmented circuit. Returns the next state and the fresh instance-witness pair of the
+/// step.
+#[allow(clippy::type_complexity)]
+pub(crate) fn commit_step<F, Comm, SC, R>(
+    prover_key: &ProverKey<F, Comm, SC>,
+    current_state: &SC::State,
+    current_witness: &SC::Witness,
+    rng: &mut R,
+) -> Result<
+    (
+        SC::State,
+        RelaxedPLONKInstance<F, Comm>,
+        RelaxedPLONKWitness<F>,
+    ),
+    SangriaError,
+>
+where
+    F: PrimeField + Absorb,
+    Comm: FoldingCommitmentConfig<F>,
+    SC: PLONKStepCircuit<F>,
+    R: Rng,
+{
+    let (next_state, instance_columns, step_witness) =
+        synthesize_step(prover_key, current_state, current_witness, rng)?;
+    let step_instance = FoldingScheme::<F, Comm>::commit_witness(
+        &prover_key.public_parameters,
+        instance_columns,
+        &step_witness,
+    )?;
+
+    Ok((next_state, step_instance, step_witness))
+}
+
+/// Executes a step of the circuit of `prover_key` from `current_state` and synthesizes the trace of
+/// the augmented circuit. Returns the next state, the public input and output rows of the step
+/// given column by column, and its fresh witness, still to be committed to.
+#[allow(clippy::type_complexity)]
+fn synthesize_step<F, Comm, SC, R>(
+    prover_key: &ProverKey<F, Comm, SC>,
+    current_state: &SC::State,
+    current_witness: &SC::Witness,
+    rng: &mut R,
+) -> Result<(SC::State, Vec<Vec<F>>, RelaxedPLONKWitness<F>), SangriaError>
+where
+    F: PrimeField + Absorb,
+    Comm: FoldingCommitmentConfig<F>,
+    SC: PLONKStepCircuit<F>,
+    R: Rng,
+{
+    let step_circuit = prover_key.augmented_circuit.step_circuit();
+    let next_state = step_circuit.execute(current_state, current_witness)?;
+    let current = step_circuit.encode_state(current_state);
+    let next = step_circuit.encode_state(&next_state);
+
+    let trace =
+        prover_key
+            .augmented_circuit
+            .synthesize_batched(&current, &next, current_witness)?;
+    let instance_columns = trace.instance_columns();
+    let (circuit, step_witness) =
+        trace.into_circuit_and_witness(step_circuit.fixed_tables(), rng)?;
+    if circuit != prover_key.folding_key.circuit {
+        return Err(SangriaError::InvalidTrace(
+            "the layout of the step circuit depends on its values",
+        ));
+    }
+
+    Ok((next_state, instance_columns, step_witness))
+}
+
+/// Folds the fresh instance-witness pair of a step into the running instance of `proof` and records
+/// the step in it. Without a proof, the step's instance becomes the running instance of a new proof.
+pub(crate) fn fold_step<F, Comm, SC, R>(
+    prover_key: &ProverKey<F, Comm, SC>,
+    proof: Option<BatchedIVCProof<F, Comm>>,
+    step_instance: RelaxedPLONKInstance<F, Comm>,
+    step_witness: RelaxedPLONKWitness<F>,
+    rng: &mut R,
+) -> Result<BatchedIVCProof<F, Comm>, SangriaError>
+where
+    F: PrimeField + Absorb,
+    Comm: FoldingCommitmentConfig<F>,
+    R: Rng,
+{
+    let Some(mut proof) = proof else {
+        return Ok(BatchedIVCProof {
+            step_instances: vec![step_instance.clone()],
+            cross_term_commitments: Vec::new(),
+            fold_shape: vec![0],
+            running_instance: step_instance,
+            running_witness: step_witness,
+        });
+    };
+
+    let (running_instance, running_witness, cross_term_commitments) =
+        FoldingScheme::<F, Comm>::prover_in_round(
+            &prover_key.public_parameters,
+            &prover_key.folding_key,
+            &proof.running_instance,
+            &proof.running_witness,
+            &step_instance,
+            &step_witness,
+            proof.cross_term_commitments.len(),
+            rng,
+        )?;
+    proof.step_instances.push(step_instance);
+    proof.cross_term_commitments.push(cross_term_commitments);
+    proof.fold_shape.push(1);
+    proof.running_instance = running_instance;
+    proof.running_witness = running_witness;
+
+    Ok(proof)
+}
+
+/// The batched proofs of Sangria are not IVC proofs in the sense of Nova, as their verifier re-folds
+/// every step, so Sangria does not implement [`IVC`](crate::IVC) and proves them with the functions
+/// below instead.
+impl<Comm> Sangria<Comm> {
+    /// Runs the setup of the folding scheme. The augmented circuit has two public inputs per
+    /// element of the state encoding and as many gates as the step circuit.
+    pub fn setup<F, R>(
+        info: &SetupInfo<F>,
+        rng: &mut R,
+    ) -> Result<folding_scheme::PublicParameters<F, Comm>, SangriaError>
+    where
+        F: PrimeField + Absorb,
+        Comm: FoldingCommitmentConfig<F>,
+        R: Rng,
+    {
+        FoldingScheme::<F, Comm>::setup(info, rng)
+    }
+
+    /// Builds the augmented circuit from a step on zero states with the default witness, whose
+    /// values are ignored, and encodes it with the folding scheme.
+    #[allow(clippy::type_complexity)]
+    pub fn encode<F, SC, R>(
+        public_parameters: &folding_scheme::PublicParameters<F, Comm>,
+        step_circuit: &SC,
+        rng: &mut R,
+    ) -> Result<(ProverKey<F, Comm, SC>, VerifierKey<F, Comm, SC>), SangriaError>
+    where
+        F: PrimeField + Absorb,
+        Comm: FoldingCommitmentConfig<F>,
+        SC: PLONKStepCircuit<F> + Clone,
+        SC::Witness: Default,
+        R: Rng,
+    {
+        if public_parameters.number_of_public_inputs != 2 * step_circuit.state_len() {
+            return Err(SangriaError::InvalidConfig(
+                "the public parameters must have two public inputs per state element",
+            ));
+        }
+
+        let augmented_circuit = AugmentedCircuit::new(step_circuit.clone());
+        let circuit = augmented_circuit.batched_circuit()?;
+        let (folding_pk, folding_vk) =
+            FoldingScheme::<F, Comm>::encode(public_parameters, &circuit, rng)?;
+        let permutation_commit_key = Comm::CommitmentSlack::setup(
+            rng,
+            hyperplonk::permutation_commit_key_len(
+                circuit.number_of_rows(),
+                public_parameters.config.gate_degree,
+            ),
+        );
+
+        Ok((
+            ProverKey {
+                public_parameters: public_parameters.clone(),
+                folding_key: folding_pk,
+                permutation_commit_key: permutation_commit_key.clone(),
+                augmented_circuit: augmented_circuit.clone(),
+                #[cfg(feature = "usage-tracking")]
+                usage: Default::default(),
+            },
+            VerifierKey {
+                public_parameters: public_parameters.clone(),
+                folding_key: folding_vk,
+                circuit,
+                permutation_commit_key,
+                augmented_circuit,
+            },
+        ))
+    }
+
+    /// Executes the step, commits to the trace of the augmented circuit and folds it into the
+    /// running instance. In the base case (no proof yet) the current state must be the origin state
+    /// and the step's instance becomes the running instance.
+    pub fn prove_step<F, SC, R>(
+        prover_key: &ProverKey<F, Comm, SC>,
+        origin_state: &SC::State,
+        current_state: SC::State,
+        current_proof: Option<BatchedIVCProof<F, Comm>>,
+        current_witness: &SC::Witness,
+        rng: &mut R,
+    ) -> Result<(SC::State, BatchedIVCProof<F, Comm>), SangriaError>
+    where
+        F: PrimeField + Absorb,
+        Comm: FoldingCommitmentConfig<F>,
+        SC: PLONKStepCircuit<F> + Clone,
+        SC::Witness: Default,
+        R: Rng,
+    {
+        let step_circuit = prover_key.augmented_circuit.step_circuit();
+        let current = step_circuit.encode_state(&current_state);
+        match &current_proof {
+            None if step_circuit.encode_state(origin_state) != current => {
+                return Err(SangriaError::PublicInputMismatch);
+            }
+            Some(proof) if proof.last_state(current.len())? != current => {
+                return Err(SangriaError::PublicInputMismatch);
+            }
+            _ => {}
+        }
+
+        let (next_state, step_instance, step_witness) =
+            commit_step(prover_key, &current_state, current_witness, rng)?;
+        let proof = fold_step(prover_key, current_proof, step_instance, step_witness, rng)?;
+        #[cfg(feature = "usage-tracking")]
+        prover_key.usage.record(proof.compressed_size());
+
+        Ok((next_state, proof))
+    }
+
+    /// Checks that the step instances are fresh and chain from the origin state to the current
+    /// state, re-folds them into the running instance and checks that the running witness
+    /// satisfies it. Without a proof, the current state must be the origin state.
+    pub fn verify<F, SC>(
+        verifier_key: &VerifierKey<F, Comm, SC>,
+        origin_state: &SC::State,
+        current_state: SC::State,
+        current_proof: Option<BatchedIVCProof<F, Comm>>,
+    ) -> Result<(), SangriaError>
+    where
+        F: PrimeField + Absorb,
+        Comm: FoldingCommitmentConfig<F>,
+        SC: PLONKStepCircuit<F> + Clone,
+        SC::Witness: Default,
+    {
+        let step_circuit = verifier_key.augmented_circuit.step_circuit();
+        let origin = step_circuit.encode_state(origin_state);
+        let current = step_circuit.encode_state(&current_state);
+
+        let proof = match current_proof {
+            None if origin == current => return Ok(()),
+            None => return Err(SangriaError::InvalidProof),
+            Some(proof) => proof,
+        };
+        proof.validate(verifier_key)?;
+        let running_instance = refold_steps(
+            origin,
+            &current,
+            &proof.step_instances,
+            &proof.cross_term_commitments,
+            &proof.fold_shape,
+            nifs_verifier(verifier_key),
+        )?;
+        decide(verifier_key, &running_instance, &proof)
+    }
+
+    /// Merges two proofs produced independently, e.g. by different provers, into a proof of their
+    /// concatenated steps, by folding the running instance of `right` into that of `left`. The
+    /// first step of `right` must start in the state the last step of `left` ends in, and the
+    /// merged proof is verified from the origin state of `left` with [`Self::verify`], which
+    /// re-folds the steps of both proofs along its tree of folds.
+    ///
+    /// WARNING: like the augmented circuit of a step, which does not verify the incoming proof, no
+    /// circuit verifies the two incoming proofs of a merge: the verifier re-folds them itself.
+    pub fn merge<F, SC, R>(
+        prover_key: &ProverKey<F, Comm, SC>,
+        left: BatchedIVCProof<F, Comm>,
+        right: BatchedIVCProof<F, Comm>,
+        rng: &mut R,
+    ) -> Result<BatchedIVCProof<F, Comm>, SangriaError>
+    where
+        F: PrimeField + Absorb,
+        Comm: FoldingCommitmentConfig<F>,
+        SC: PLONKStepCircuit<F>,
+        R: Rng,
+    {
+        let state_len = prover_key.augmented_circuit.step_circuit().state_len();
+        let first_right_instance = right
+            .step_instances
+            .first()
+            .ok_or(SangriaError::InvalidProof)?;
+        if left.last_state(state_len)? != step_states(first_right_instance, state_len)?.0 {
+            return Err(SangriaError::PublicInputMismatch);
+        }
+
+        let (running_instance, running_witness, cross_term_commitments) =
+            FoldingScheme::<F, Comm>::prover_in_round(
+                &prover_key.public_parameters,
+                &prover_key.folding_key,
+                &left.running_instance,
+                &left.running_witness,
+                &right.running_instance,
+                &right.running_witness,
+                left.cross_term_commitments.len() + right.cross_term_commitments.len(),
+                rng,
+            )?;
+        let mut fold_shape = [left.fold_shape, right.fold_shape].concat();
+        if let Some(folds) = fold_shape.last_mut() {
+            *folds += 1;
+        }
+        let proof = BatchedIVCProof {
+            step_instances: [left.step_instances, right.step_instances].concat(),
+            cross_term_commitments: [
+                left.cross_term_commitments,
+                right.cross_term_commitments,
+                vec![cross_term_commitments],
+            ]
+            .concat(),
+            fold_shape,
+            running_instance,
+            running_witness,
+        };
+        #[cfg(feature = "usage-tracking")]
+        prover_key.usage.record(proof.compressed_size());
+
+        Ok(proof)
+    }
+
+    /// Proves one step per witness from `origin_state`, like calling [`Self::prove_step`] on every
+    /// witness in turn, but on all available cores: the steps are synthesized independently, the
+    /// witness columns of all steps are committed to from a shared queue of tasks, then the proofs
+    /// of the steps are [merged](Self::merge) pairwise in a binary tree, each level in parallel.
+    /// Returns the final state and a proof of all steps, accepted by [`Self::verify`] like the proof
+    /// of the sequential chain although its folds follow the tree, or an error if there is no
+    /// witness or a witness is not valid.
+    ///
+    /// Each step and merge draws its randomness from an RNG seeded from `rng`, so a prover seeded
+    /// with a deterministic RNG remains reproducible.
+    pub fn prove_steps_parallel<F, SC, R>(
+        prover_key: &ProverKey<F, Comm, SC>,
+        origin_state: &SC::State,
+        witnesses: &[SC::Witness],
+        rng: &mut R,
+    ) -> Result<(SC::State, BatchedIVCProof<F, Comm>), SangriaError>
+    where
+        F: PrimeField + Absorb,
+        Comm: FoldingCommitmentConfig<F>,
+        SC: PLONKStepCircuit<F>,
+        SC::State: Sync,
+        SC::Witness: Sync,
+        ProverKey<F, Comm, SC>: Sync,
+        <Comm::CommitmentWitness as HomomorphicCommitmentScheme<F>>::Commitment: Send,
+        BatchedIVCProof<F, Comm>: Send,
+        R: Rng,
+    {
+        if witnesses.is_empty() {
+            return Err(SangriaError::InvalidWitness);
+        }
+
+        // the states are cheap to compute natively, which lets every step be proved independently
+        let step_circuit = prover_key.augmented_circuit.step_circuit();
+        let mut current_states = Vec::with_capacity(witnesses.len());
+        let mut state = step_circuit.execute(origin_state, &witnesses[0])?;
+        for witness in &witnesses[1..] {
+            let next_state = step_circuit.execute(&state, witness)?;
+            current_states.push(state);
+            state = next_state;
+        }
+        let steps: Vec<_> = [origin_state]
+            .into_iter()
+            .chain(&current_states)
+            .zip(witnesses)
+            .map(|step| (step, rng.gen::<[u8; 32]>()))
+            .collect();
+        let synthesized_steps = parallel_map(steps, |((current_state, witness), seed)| {
+            let rng = &mut StdRng::from_seed(seed);
+            synthesize_step(prover_key, current_state, witness, rng)
+                .map(|(_, instance_columns, step_witness)| (instance_columns, step_witness))
+        })
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()?;
+
+        // the columns of all steps are committed to as independent tasks, which balances the
+        // threads better than committing to the columns of one step at a time
+        let columns: Vec<_> = (0..synthesized_steps.len())
+            .flat_map(|step| (0..NUMBER_OF_WIRES).map(move |column| (step, column)))
+            .collect();
+        let mut witness_commitments = parallel_map(columns, |(step, column)| {
+            FoldingScheme::<F, Comm>::commit_witness_column(
+                &prover_key.public_parameters,
+                &synthesized_steps[step].1,
+                column,
+            )
+        })
+        .into_iter();
+
+        let mut proofs = Vec::with_capacity(synthesized_steps.len());
+        for (instance_columns, step_witness) in synthesized_steps {
+            let step_instance = FoldingScheme::<F, Comm>::instance_with_commitments(
+                &prover_key.public_parameters,
+                instance_columns,
+                witness_commitments
+                    .by_ref()
+                    .take(NUMBER_OF_WIRES)
+                    .collect::<Result<_, _>>()?,
+            )?;
+            proofs.push(fold_step(
+                prover_key,
+                None,
+                step_instance,
+                step_witness,
+                rng,
+            )?);
+        }
+
+        let proof = Self::merge_tree(prover_key, proofs, available_threads(), rng)?;
+        Ok((state, proof))
+    }
+
+    /// Proves one step per witness from `origin_state` in `parallelism` segments of consecutive
+    /// steps: every segment is proved as a chain of [`Self::prove_step`] on its own thread, from the
+    /// state its first step starts in, then the proofs of the segments are [merged](Self::merge)
+    /// pairwise in a binary tree, each level on up to `parallelism` threads. Unlike
+    /// [`Self::prove_steps_parallel`], which spreads every step over all cores, a segment only
+    /// holds the running witness of its own chain, so that long chains are proved with
+    /// `parallelism` witnesses in memory. Returns the final state and a proof of all steps,
+    /// accepted by [`Self::verify`], or an error if there is no witness, if a witness is not valid
+    /// or if `parallelism` is zero.
+    ///
+    /// Each segment and merge draws its randomness from an RNG seeded from `rng`.
+    pub fn prove_parallel<F, SC, R>(
+        prover_key: &ProverKey<F, Comm, SC>,
+        origin_state: &SC::State,
+        witnesses: &[SC::Witness],
+        parallelism: usize,
+        rng: &mut R,
+    ) -> Result<(SC::State, BatchedIVCProof<F, Comm>), SangriaError>
+    where
+        F: PrimeField + Absorb,
+        Comm: FoldingCommitmentConfig<F>,
+        SC: PLONKStepCircuit<F>,
+        SC::State: Sync,
+        SC::Witness: Sync,
+        ProverKey<F, Comm, SC>: Sync,
+        BatchedIVCProof<F, Comm>: Send,
+        R: Rng,
+    {
+        if parallelism == 0 {
+            return Err(SangriaError::InvalidConfig(
+                "the parallelism must be at least one",
+            ));
+        }
+        if witnesses.is_empty() {
+            return Err(SangriaError::InvalidWitness);
+        }
+
+        // the segments start in the states reached natively after the previous segments
+        let step_circuit = prover_key.augmented_circuit.step_circuit();
+        let segment_len = witnesses.len().div_ceil(parallelism);
+        let mut segment_states = Vec::with_capacity(parallelism - 1);
+        let mut state = None;
+        for (step, witness) in witnesses.iter().enumerate() {
+            let next_state =
+                step_circuit.execute(state.as_ref().unwrap_or(origin_state), witness)?;
+            if let Some(current_state) = state.take().filter(|_| step % segment_len == 0) {
+                segment_states.push(current_state);
+            }
+            state = Some(next_state);
+        }
+        let state = state.ok_or(SangriaError::InvalidWitness)?;
+        let segments: Vec<_> = [origin_state]
+            .into_iter()
+            .chain(&segment_states)
+            .zip(witnesses.chunks(segment_len))
+            .map(|segment| (segment, rng.gen::<[u8; 32]>()))
+            .collect();
+
+        let proofs = parallel_map_on(parallelism, segments, |((start_state, segment), seed)| {
+            let rng = &mut StdRng::from_seed(seed);
+            let (mut current_state, step_instance, step_witness) =
+                commit_step(prover_key, start_state, &segment[0], rng)?;
+            let mut proof = fold_step(prover_key, None, step_instance, step_witness, rng)?;
+            for witness in &segment[1..] {
+                let (next_state, step_instance, step_witness) =
+                    commit_step(prover_key, &current_state, witness, rng)?;
+                proof = fold_step(prover_key, Some(proof), step_instance, step_witness, rng)?;
+                current_state = next_state;
+            }
+            Ok(proof)
+        })
+        .into_iter()
+        .collect::<Result<Vec<_>, SangriaError>>()?;
+
+        let proof = Self::merge_tree(prover_key, proofs, parallelism, rng)?;
+        #[cfg(feature = "usage-tracking")]
+        prover_key.usage.record(proof.compressed_size());
+
+        Ok((state, proof))
+    }
+
+    /// Merges consecutive proofs pairwise in a binary tree, each level on up to `threads` threads,
+    /// into one proof of all their steps. Returns an error if there is no proof.
+    fn merge_tree<F, SC, R>(
+        prover_key: &ProverKey<F, Comm, SC>,
+        mut proofs: Vec<BatchedIVCProof<F, Comm>>,
+        threads: usize,
+        rng: &mut R,
+    ) -> Result<BatchedIVCProof<F, Comm>, SangriaError>
+    where
+        F: PrimeField + Absorb,
+        Comm: FoldingCommitmentConfig<F>,
+        SC: PLONKStepCircuit<F>,
+        ProverKey<F, Comm, SC>: Sync,
+        BatchedIVCProof<F, Comm>: Send,
+        R: Rng,
+    {
+        while proofs.len() > 1 {
+            let mut proofs_left = proofs.into_iter();
+            let mut pairs = Vec::new();
+            while let Some(left) = proofs_left.next() {
+                pairs.push(((left, proofs_left.next()), rng.gen::<[u8; 32]>()));
+            }
+            proofs = parallel_map_on(threads, pairs, |((left, right), seed)| match right {
+                Some(right) => Self::merge(prover_key, left, right, &mut StdRng::from_seed(seed)),
+                None => Ok(left),
+            })
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()?;
+        }
+
+        proofs.pop().ok_or(SangriaError::InvalidWitness)
+    }
+
+    /// Recomputes the running instance of a chain from the instance of its first step and the
+    /// archived messages of the later steps (see [`BatchedIVCProof::prover_messages`]), with the NIFS
+    /// verifier and without any witness, so that auditors can compare it with the running instance
+    /// of a proof. Returns an error if an instance or a message does not have the layout of
+    /// `verifier_key`. The messages are not checked to chain states, which [`Self::verify`] does.
+    pub fn reconstruct_accumulator<F, SC>(
+        verifier_key: &VerifierKey<F, Comm, SC>,
+        origin_instance: &RelaxedPLONKInstance<F, Comm>,
+        messages: &[StepMessage<F, Comm>],
+    ) -> Result<RelaxedPLONKInstance<F, Comm>, SangriaError>
+    where
+        F: PrimeField + Absorb,
+        Comm: FoldingCommitmentConfig<F>,
+    {
+        let schema = &verifier_key.folding_key.instance_schema;
+        if messages
+            .iter()
+            .any(|message| message.cross_term_commitments.len() != schema.cross_term_commitments)
+        {
+            return Err(SangriaError::InvalidProof);
+        }
+        let instances: Vec<_> = [origin_instance]
+            .into_iter()
+            .chain(messages.iter().map(|message| &message.step_instance))
+            .collect();
+        RelaxedPLONKInstance::validate_batch(
+            schema,
+            &instances,
+            messages
+                .iter()
+                .flat_map(|message| &message.cross_term_commitments),
+        )?;
+
+        let mut fold = nifs_verifier(verifier_key);
+        messages
+            .iter()
+            .try_fold(origin_instance.clone(), |running_instance, message| {
+                fold(
+                    &running_instance,
+                    &message.step_instance,
+                    &message.cross_term_commitments,
+                )
+            })
+    }
+
+    /// Verifies a proof like [`Self::verify`], and also returns the record of every fold of the step
+    /// instances, in order, up to the first fold that fails. Auditors can check each transcript and
+    /// fold against the Sangria paper independently of this implementation.
+    #[allow(clippy::type_complexity)]
+    pub fn verify_with_audit<F, SC>(
+        verifier_key: &VerifierKey<F, Comm, SC>,
+        origin_state: &SC::State,
+        current_state: SC::State,
+        proof: BatchedIVCProof<F, Comm>,
+    ) -> (Result<(), SangriaError>, Vec<FoldAudit<F, Comm>>)
+    where
+        F: PrimeField + Absorb,
+        Comm: FoldingCommitmentConfig<F>,
+        SC: PLONKStepCircuit<F>,
+    {
+        if let Err(error) = proof.validate(verifier_key) {
+            return (Err(error), Vec::new());
+        }
+
+        let step_circuit = verifier_key.augmented_circuit.step_circuit();
+        let mut audits = Vec::new();
+        let running_instance = refold_steps(
+            step_circuit.encode_state(origin_state),
+            &step_circuit.encode_state(&current_state),
+            &proof.step_instances,
+            &proof.cross_term_commitments,
+            &proof.fold_shape,
+            |running_instance, instance, cross_term_commitments| {
+                let audit = FoldingScheme::<F, Comm>::verifier_with_audit(
+                    &verifier_key.public_parameters,
+                    &verifier_key.folding_key,
+                    running_instance,
+                    instance,
+                    cross_term_commitments,
+                    audits.len(),
+                )?;
+                let folded_instance = audit.folded_instance.clone();
+                audits.push(audit);
+                Ok(folded_instance)
+            },
+        );
+
+        let result = running_instance
+            .and_then(|running_instance| decide(verifier_key, &running_instance, &proof));
+        (result, audits)
+    }
+
+    /// Verifies a proof like [`Self::verify`], recording every fold it checks, with its challenge,
+    /// and the outcome of the verification into `metrics`.
+    pub fn verify_with_metrics<F, SC, M>(
+        verifier_key: &VerifierKey<F, Comm, SC>,
+        origin_state: &SC::State,
+        current_state: SC::State,
+        proof: Option<BatchedIVCProof<F, Comm>>,
+        metrics: &M,
+    ) -> Result<(), SangriaError>
+    where
+        F: PrimeField + Absorb,
+        Comm: FoldingCommitmentConfig<F>,
+        SC: PLONKStepCircuit<F> + Clone,
+        SC::Witness: Default,
+        M: VerifierMetrics<F> + ?Sized,
+    {
+        let result = match proof {
+            None => Self::verify(verifier_key, origin_state, current_state, None),
+            Some(proof) => {
+                let (result, audits) =
+                    Self::verify_with_audit(verifier_key, origin_state, current_state, proof);
+                for audit in audits {
+                    metrics.fold_verified(audit.challenge);
+                }
+                result
+            }
+        };
+
+        metrics.proof_verified(result.as_ref().map(|_| ()));
+        result
+    }
+
+    /// Proves a short canned chain of one step per witness from `origin_state` with `prover_key`,
+    /// then verifies it with `verifier_key`, as a health check of a proving service at startup: it
+    /// exercises the commit keys and the commitment backends the prover runs on, and catches a
+    /// verifier key that does not belong with the prover key. Returns the timings of the chain, the
+    /// error of the step that failed, or [`SangriaError::SelfTestFailed`] if the chain was proved but
+    /// its proof is rejected.
+    pub fn self_test<F, SC, R>(
+        prover_key: &ProverKey<F, Comm, SC>,
+        verifier_key: &VerifierKey<F, Comm, SC>,
+        origin_state: &SC::State,
+        witnesses: &[SC::Witness],
+        rng: &mut R,
+    ) -> Result<SelfTestReport, SangriaError>
+    where
+        F: PrimeField + Absorb,
+        Comm: FoldingCommitmentConfig<F>,
+        SC: PLONKStepCircuit<F> + Clone,
+        SC::State: Clone,
+        SC::Witness: Default,
+        R: Rng,
+    {
+        let start = Instant::now();
+        let (mut state, mut proof) = (origin_state.clone(), None);
+        for witness in witnesses {
+            let (next_state, next_proof) =
+                Self::prove_step(prover_key, origin_state, state, proof, witness, rng)?;
+            (state, proof) = (next_state, Some(next_proof));
+        }
+        let proving_time = start.elapsed();
+
+        let start = Instant::now();
+        Self::verify(verifier_key, origin_state, state, proof)
+            .map_err(|_| SangriaError::SelfTestFailed)?;
+
+        Ok(SelfTestReport {
+            number_of_steps: witnesses.len(),
+            proving_time,
+            verification_time: start.elapsed(),
+        })
+    }
+
+    /// Proves one step per witness from `current_state` and `proof`, like calling
+    /// [`Self::prove_step`] on every witness in turn, until `deadline`. A step is only started if it
+    /// is expected to end before the deadline, taking the previous step of the call as an estimate
+    /// of its duration, so the proof returned is always the proof of a whole number of steps.
+    /// Returns the state and the proof after the last step proved, and the number of witnesses
+    /// consumed: the remaining witnesses can be proved by the next call, from the returned state and
+    /// proof. The proof is `None` if no step was ever proved. Returns the error of the step that
+    /// failed, in which case the steps proved by this call are lost.
+    #[allow(clippy::type_complexity)]
+    pub fn prove_steps_until<F, SC, R>(
+        prover_key: &ProverKey<F, Comm, SC>,
+        origin_state: &SC::State,
+        current_state: SC::State,
+        proof: Option<BatchedIVCProof<F, Comm>>,
+        witnesses: &[SC::Witness],
+        deadline: Instant,
+        rng: &mut R,
+    ) -> Result<(SC::State, Option<BatchedIVCProof<F, Comm>>, usize), SangriaError>
+    where
+        F: PrimeField + Absorb,
+        Comm: FoldingCommitmentConfig<F>,
+        SC: PLONKStepCircuit<F> + Clone,
+        SC::Witness: Default,
+        R: Rng,
+    {
+        let (mut state, mut proof) = (current_state, proof);
+        let mut step_time = Duration::ZERO;
+        let mut consumed = 0;
+        for witness in witnesses {
+            let start = Instant::now();
+            if start + step_time > deadline {
+                break;
+            }
+
+            let (next_state, next_proof) =
+                Self::prove_step(prover_key, origin_state, state, proof, witness, rng)?;
+            (state, proof) = (next_state, Some(next_proof));
+            step_time = start.elapsed();
+            consumed += 1;
+        }
+
+        Ok((state, proof, consumed))
+    }
+}
+
+impl<Comm> Sangria<Comm> {
+    /// Replaces the running witness with a HyperPlonk proof that it satisfies the running instance,
+    /// with challenges derived from a Poseidon transcript.
+    pub fn compress<F, SC>(
+        prover_key: &ProverKey<F, Comm, SC>,
+        proof: BatchedIVCProof<F, Comm>,
+    ) -> Result<CompressedBatchedIVCProof<F, Comm>, SangriaError>
+    where
+        F: PrimeField + Absorb,
+        Comm: FoldingCommitmentConfig<F>,
+        Comm::CommitmentSlack: InnerProductOpening<F>,
+        Comm::CommitmentWitness: InnerProductOpening<F>,
+        SC: PLONKStepCircuit<F>,
+    {
+        Self::compress_with::<F, SC, PoseidonSponge<F>>(prover_key, proof)
+    }
+
+    /// Checks the step instances and re-folds them like [`Self::verify`], then verifies the
+    /// HyperPlonk proof of the running instance.
+    pub fn verify_compressed<F, SC>(
+        verifier_key: &VerifierKey<F, Comm, SC>,
+        origin_state: &SC::State,
+        current_state: SC::State,
+        compressed_proof: CompressedBatchedIVCProof<F, Comm>,
+    ) -> Result<(), SangriaError>
+    where
+        F: PrimeField + Absorb,
+        Comm: FoldingCommitmentConfig<F>,
+        Comm::CommitmentSlack: InnerProductOpening<F>,
+        Comm::CommitmentWitness: InnerProductOpening<F>,
+        SC: PLONKStepCircuit<F>,
+    {
+        Self::verify_compressed_with::<F, SC, PoseidonSponge<F>>(
+            verifier_key,
+            origin_state,
+            current_state,
+            compressed_proof,
+        )
+    }
+
+    /// Compresses a proof like [`Self::compress`], deriving the challenges of
+    /// the HyperPlonk proof from a transcript of type `RO`, e.g. a [`crate::KeccakTranscript`] for
+    /// a verifier running on Ethereum. The folds of the steps keep their Poseidon transcripts.
+    pub fn compress_with<F, SC, RO>(
+        prover_key: &ProverKey<F, Comm, SC>,
+        proof: BatchedIVCProof<F, Comm>,
+    ) -> Result<CompressedBatchedIVCProof<F, Comm>, SangriaError>
+    where
+        F: PrimeField + Absorb,
+        Comm: FoldingCommitmentConfig<F>,
+        Comm::CommitmentSlack: InnerProductOpening<F>,
+        Comm::CommitmentWitness: InnerProductOpening<F>,
+        SC: PLONKStepCircuit<F>,
+        RO: Transcript<F>,
+    {
+        let snark = hyperplonk::prove_with::<F, Comm, RO>(
+            &prover_key.public_parameters,
+            &prover_key.folding_key.verifier_key,
+            &prover_key.folding_key.circuit,
+            &prover_key.permutation_commit_key,
+            &proof.running_instance,
+            &proof.running_witness,
+        )?;
+
+        Ok(CompressedBatchedIVCProof {
+            step_instances: proof.step_instances,
+            cross_term_commitments: proof.cross_term_commitments,
+            fold_shape: proof.fold_shape,
+            running_instance: proof.running_instance,
+            snark,
+        })
+    }
+
+    /// Verifies a proof compressed by [`Self::compress_with`] with a transcript of type `RO`.
+    pub fn verify_compressed_with<F, SC, RO>(
+        verifier_key: &VerifierKey<F, Comm, SC>,
+        origin_state: &SC::State,
+        current_state: SC::State,
+        compressed_proof: CompressedBatchedIVCProof<F, Comm>,
+    ) -> Result<(), SangriaError>
+    where
+        F: PrimeField + Absorb,
+        Comm: FoldingCommitmentConfig<F>,
+        Comm::CommitmentSlack: InnerProductOpening<F>,
+        Comm::CommitmentWitness: InnerProductOpening<F>,
+        SC: PLONKStepCircuit<F>,
+        RO: Transcript<F>,
+    {
+        compressed_proof.validate(verifier_key)?;
+
+        let step_circuit = verifier_key.augmented_circuit.step_circuit();
+        let running_instance = refold_steps(
+            step_circuit.encode_state(origin_state),
+            &step_circuit.encode_state(&current_state),
+            &compressed_proof.step_instances,
+            &compressed_proof.cross_term_commitments,
+            &compressed_proof.fold_shape,
+            nifs_verifier(verifier_key),
+        )?;
+        if running_instance != compressed_proof.running_instance {
+            return Err(SangriaError::InvalidProof);
+        }
+
+        hyperplonk::verify_with::<F, Comm, RO>(
+            &verifier_key.public_parameters,
+            &verifier_key.folding_key,
+            &verifier_key.circuit,
+            &verifier_key.permutation_commit_key,
+            &running_instance,
+            &compressed_proof.snark,
+        )
+        .map_err(|_| SangriaError::InvalidProof)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BatchedIVCProof, ProverKey, StepMessage, VerifierKey};
+    use crate::{
+        pedersen::PedersenCommitment,
+        utils::{
+            circuits::Counter, inner_product::InnerProductConfig,
+            poseidon::poseidon_parameters_for_test,
+        },
+        AugmentedCircuit, Sangria, SangriaConfig, SangriaError, SetupInfo, SingleCommitment,
+    };
+    use ark_bn254::{Fr, G1Affine};
+    use ark_crypto_primitives::sponge::Absorb;
+    use ark_ff::{One, Zero};
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Compress, Validate};
+    use ark_std::test_rng;
+    use std::time::{Duration, Instant};
+
+    type Scheme = Sangria<InnerProductConfig>;
+
+    fn keys() -> (
+        ProverKey<Fr, InnerProductConfig, Counter>,
+        VerifierKey<Fr, InnerProductConfig, Counter>,
+    ) {
+        keys_with(SangriaConfig::default())
+    }
+
+    fn keys_with(
+        config: SangriaConfig,
+    ) -> (
+        ProverKey<Fr, InnerProductConfig, Counter>,
+        VerifierKey<Fr, InnerProductConfig, Counter>,
+    ) {
+        let rng = &mut test_rng();
+        let info = SetupInfo {
+            number_of_public_inputs: 2,
+            number_of_gates: 2,
+            config,
+            poseidon_constants: poseidon_parameters_for_test(),
+        };
+        let public_parameters = Scheme::setup(&info, rng).unwrap();
+
+        Scheme::encode(&public_parameters, &Counter, rng).unwrap()
+    }
+
+    #[test]
+    fn counter_chain_verifies() {
+        let rng = &mut test_rng();
+        let (pk, vk) = keys();
+        let origin = Fr::from(5u64);
+
+        let (mut state, mut proof) = (origin, None);
+        for increment in 1..=12u64 {
+            let (next_state, next_proof) =
+                Scheme::prove_step(&pk, &origin, state, proof, &Fr::from(increment), rng).unwrap();
+            assert_eq!(
+                Scheme::verify(&vk, &origin, next_state, Some(next_proof.clone())),
+                Ok(())
+            );
+
+            (state, proof) = (next_state, Some(next_proof));
+        }
+
+        assert_eq!(state, Fr::from(83u64));
+        assert_eq!(
+            proof.as_ref().map(|proof| proof.number_of_steps()),
+            Some(12)
+        );
+        assert_eq!(Scheme::verify(&vk, &origin, origin, None), Ok(()));
+    }
+
+    #[cfg(feature = "usage-tracking")]
+    #[test]
+    fn usage_records_every_proof() {
+        let rng = &mut test_rng();
+        let (pk, _) = keys();
+        let origin = Fr::from(5u64);
+
+        let (mut state, mut proof) = (origin, None);
+        let mut sizes = Vec::new();
+        for increment in [1u64, 2, 0, 3] {
+            if let Ok((next_state, next_proof)) = Scheme::prove_step(
+                &pk,
+                &origin,
+                state,
+                proof.clone(),
+                &Fr::from(increment),
+                rng,
+            ) {
+                sizes.push(next_proof.compressed_size());
+                (state, proof) = (next_state, Some(next_proof));
+            }
+        }
+
+        let usage = pk.usage();
+        assert_eq!(usage.number_of_proofs, 3);
+        assert_eq!(usage.total_size, sizes.iter().sum::<usize>() as u64);
+        assert_eq!(usage.smallest_size, sizes[0]);
+        assert_eq!(usage.largest_size, sizes[2]);
+    }
+
+    #[test]
+    fn counter_chain_verifies_with_pedersen_commitments() {
+        type PedersenScheme = Sangria<SingleCommitment<PedersenCommitment<G1Affine>>>;
+
+        let rng = &mut test_rng();
+        let info = SetupInfo {
+            number_of_public_inputs: 2,
+            number_of_gates: 2,
+            config: SangriaConfig::default(),
+            poseidon_constants: poseidon_parameters_for_test(),
+        };
+        let public_parameters = PedersenScheme::setup(&info, rng).unwrap();
+        let (pk, vk) = PedersenScheme::encode(&public_parameters, &Counter, rng).unwrap();
+        let origin = Fr::from(5u64);
+
+        let (mut state, mut proof) = (origin, None);
+        for increment in 1..=3u64 {
+            (state, proof) =
+                PedersenScheme::prove_step(&pk, &origin, state, proof, &Fr::from(increment), rng)
+                    .map(|(state, proof)| (state, Some(proof)))
+                    .unwrap();
+        }
+
+        assert_eq!(state, Fr::from(11u64));
+        assert_eq!(
+            PedersenScheme::verify(&vk, &origin, state, proof.clone()),
+            Ok(())
+        );
+        assert_eq!(
+            PedersenScheme::verify(&vk, &origin, origin, proof),
+            Err(SangriaError::InvalidProof)
+        );
+    }
+
+    #[test]
+    fn self_test_checks_the_keys() {
+        let rng = &mut test_rng();
+        let (pk, vk) = keys();
+        let witnesses = [Fr::one(), Fr::from(2u64), Fr::from(3u64)];
+
+        let report = Scheme::self_test(&pk, &vk, &Fr::zero(), &witnesses, rng).unwrap();
+        assert_eq!(report.number_of_steps, 3);
+
+        // the verifier key of another setup
+        let (_, other_vk) = keys_with(SangriaConfig::default().with_application_id("other"));
+        assert_eq!(
+            Scheme::self_test(&pk, &other_vk, &Fr::zero(), &witnesses, rng),
+            Err(SangriaError::SelfTestFailed)
+        );
+    }
+
+    #[test]
+    fn wrong_states_are_rejected() {
+        let rng = &mut test_rng();
+        let (pk, vk) = keys();
+        let origin = Fr::from(5u64);
+
+        let (state, proof) =
+            Scheme::prove_step(&pk, &origin, origin, None, &Fr::one(), rng).unwrap();
+        let (state, proof) =
+            Scheme::prove_step(&pk, &origin, state, Some(proof), &Fr::one(), rng).unwrap();
+
+        for (origin, state) in [(origin, state + Fr::one()), (Fr::zero(), state)] {
+            assert_eq!(
+                Scheme::verify(&vk, &origin, state, Some(proof.clone())),
+                Err(SangriaError::InvalidProof)
+            );
+        }
+        assert_eq!(
+            Scheme::verify(&vk, &origin, state, None),
+            Err(SangriaError::InvalidProof)
+        );
+
+        // the base case starts from the origin and later steps from the proved state
+        assert_eq!(
+            Scheme::prove_step(&pk, &origin, state, None, &Fr::one(), rng).err(),
+            Some(SangriaError::PublicInputMismatch)
+        );
+        assert_eq!(
+            Scheme::prove_step(&pk, &origin, origin, Some(proof), &Fr::one(), rng).err(),
+            Some(SangriaError::PublicInputMismatch)
+        );
+
+        // states must have the length reported by the step circuit
+        assert_eq!(
+            AugmentedCircuit::new(Counter)
+                .synthesize_batched(&[origin; 2], &[origin; 2], &Fr::one())
+                .err(),
+            Some(SangriaError::PublicInputMismatch)
+        );
+    }
+
+    /// Proves `steps` increments by `increment` from zero, returning the final state and proof.
+    fn prove_chain(
+        pk: &ProverKey<Fr, InnerProductConfig, Counter>,
+        steps: usize,
+        increment: u64,
+    ) -> (Fr, BatchedIVCProof<Fr, InnerProductConfig>) {
+        prove_chain_from(pk, Fr::zero(), steps, increment)
+    }
+
+    /// Proves `steps` increments by `increment` from `origin`, returning the final state and proof.
+    fn prove_chain_from(
+        pk: &ProverKey<Fr, InnerProductConfig, Counter>,
+        origin: Fr,
+        steps: usize,
+        increment: u64,
+    ) -> (Fr, BatchedIVCProof<Fr, InnerProductConfig>) {
+        let rng = &mut test_rng();
+        let (mut state, mut proof) = (origin, None);
+        for _ in 0..steps {
+            let (next_state, next_proof) =
+                Scheme::prove_step(pk, &origin, state, proof, &Fr::from(increment), rng).unwrap();
+            (state, proof) = (next_state, Some(next_proof));
+        }
+
+        (state, proof.unwrap())
+    }
+
+    #[test]
+    fn tampered_proofs_are_rejected() {
+        let (pk, vk) = keys();
+        let (state, proof) = prove_chain(&pk, 3, 2);
+
+        let mut dropped_step = proof.clone();
+        dropped_step.step_instances.pop();
+        let mut wrong_cross_term = proof.clone();
+        wrong_cross_term.cross_term_commitments[1][0] += Fr::one();
+        let mut wrong_witness = proof.clone();
+        wrong_witness.running_witness = prove_chain(&pk, 3, 3).1.running_witness;
+
+        for tampered in [dropped_step, wrong_cross_term, wrong_witness] {
+            assert_eq!(
+                Scheme::verify(&vk, &Fr::zero(), state, Some(tampered)),
+                Err(SangriaError::InvalidProof)
+            );
+        }
+    }
+
+    #[test]
+    fn malformed_proofs_fail_validation() {
+        let (pk, vk) = keys();
+        let (state, proof) = prove_chain(&pk, 3, 2);
+        assert_eq!(proof.validate(&vk), Ok(()));
+
+        let mut extra_cross_term = proof.clone();
+        extra_cross_term.cross_term_commitments[0].push(Fr::zero());
+        // a step instance with two witness commitments
+        let mut wrong_layout = proof;
+        wrong_layout.step_instances[1] = {
+            let mut bytes = Vec::new();
+            (vec![vec![Fr::zero(); 3]; 3], [Fr::one(), Fr::zero()])
+                .serialize_compressed(&mut bytes)
+                .unwrap();
+            (vec![Fr::zero(); 2], Vec::<Fr>::new())
+                .serialize_compressed(&mut bytes)
+                .unwrap();
+            CanonicalDeserialize::deserialize_compressed(&bytes[..]).unwrap()
+        };
+
+        for malformed in [extra_cross_term, wrong_layout] {
+            assert_eq!(malformed.validate(&vk), Err(SangriaError::InvalidProof));
+            assert_eq!(
+                Scheme::verify(&vk, &Fr::zero(), state, Some(malformed)),
+                Err(SangriaError::InvalidProof)
+            );
+        }
+    }
+
+    #[test]
+    fn compressed_proofs_verify() {
+        let (pk, vk) = keys();
+        let (state, proof) = prove_chain(&pk, 3, 2);
+        let compress = |proof| Scheme::compress(&pk, proof);
+        let verify_compressed = |state, compressed_proof| {
+            Scheme::verify_compressed(&vk, &Fr::zero(), state, compressed_proof)
+        };
+
+        let compressed_proof = compress(proof.clone()).unwrap();
+        assert_eq!(compressed_proof.number_of_steps(), 3);
+        assert_eq!(verify_compressed(state, compressed_proof), Ok(()));
+        assert_eq!(
+            verify_compressed(state + Fr::one(), compress(proof.clone()).unwrap()),
+            Err(SangriaError::InvalidProof)
+        );
+
+        let mut wrong_witness = proof;
+        wrong_witness.running_witness = prove_chain(&pk, 3, 3).1.running_witness;
+        assert_eq!(
+            verify_compressed(state, compress(wrong_witness).unwrap()),
+            Err(SangriaError::InvalidProof)
+        );
+    }
+
+    fn round_trip<T: CanonicalSerialize + CanonicalDeserialize>(
+        value: &T,
+        compress: Compress,
+    ) -> T {
+        let mut bytes = Vec::new();
+        value.serialize_with_mode(&mut bytes, compress).unwrap();
+        assert_eq!(bytes.len(), value.serialized_size(compress));
+
+        let read = T::deserialize_with_mode(&bytes[..], compress, Validate::Yes).unwrap();
+        let mut read_bytes = Vec::new();
+        read.serialize_with_mode(&mut read_bytes, compress).unwrap();
+        assert_eq!(read_bytes, bytes);
+
+        read
+    }
+
+    #[test]
+    fn keys_and_proofs_round_trip() {
+        let rng = &mut test_rng();
+        let (pk, vk) = keys();
+        let (state, proof) = prove_chain(&pk, 2, 3);
+
+        for compress in [Compress::Yes, Compress::No] {
+            let (pk, vk) = (round_trip(&pk, compress), round_trip(&vk, compress));
+            let proof = round_trip(&proof, compress);
+            assert_eq!(
+                Scheme::verify(&vk, &Fr::zero(), state, Some(proof.clone())),
+                Ok(())
+            );
+
+            // the chain goes on from the read proof under the read prover key
+            let (next_state, next_proof) = Scheme::prove_step(
+                &pk,
+                &Fr::zero(),
+                state,
+                Some(proof.clone()),
+                &Fr::from(3u64),
+                rng,
+            )
+            .unwrap();
+            assert_eq!(
+                Scheme::verify(&vk, &Fr::zero(), next_state, Some(next_proof)),
+                Ok(())
+            );
+
+            let compressed_proof = Scheme::compress(&pk, proof).unwrap();
+            assert_eq!(
+                Scheme::verify_compressed(
+                    &vk,
+                    &Fr::zero(),
+                    state,
+                    round_trip(&compressed_proof, compress),
+                ),
+                Ok(())
+            );
+        }
+    }
+
+    #[test]
+    fn proving_stops_at_the_deadline() {
+        let rng = &mut test_rng();
+        let (pk, vk) = keys();
+        let witnesses = vec![Fr::from(2u64); 4];
+
+        // nothing fits before a deadline that passed
+        let (state, proof, consumed) = Scheme::prove_steps_until(
+            &pk,
+            &Fr::zero(),
+            Fr::zero(),
+            None,
+            &witnesses,
+            Instant::now(),
+            rng,
+        )
+        .unwrap();
+        assert_eq!((state, consumed), (Fr::zero(), 0));
+        assert!(proof.is_none());
+
+        // a call resumes from the state and proof left by the previous one
+        let deadline = Instant::now() + Duration::from_secs(600);
+        let (state, proof, consumed) = Scheme::prove_steps_until(
+            &pk,
+            &Fr::zero(),
+            state,
+            proof,
+            &witnesses[..1],
+            deadline,
+            rng,
+        )
+        .unwrap();
+        assert_eq!(consumed, 1);
+        let (state, proof, consumed) = Scheme::prove_steps_until(
+            &pk,
+            &Fr::zero(),
+            state,
+            proof,
+            &witnesses[consumed..],
+            deadline,
+            rng,
+        )
+        .unwrap();
+        assert_eq!((state, consumed), (Fr::from(8u64), 3));
+        assert_eq!(Scheme::verify(&vk, &Fr::zero(), state, proof), Ok(()));
+    }
+
+    #[test]
+    fn accumulators_are_reconstructed_from_archived_messages() {
+        let (pk, vk) = keys();
+        let (_, proof) = prove_chain(&pk, 4, 2);
+        let (origin_instance, messages) = proof.prover_messages().unwrap();
+        assert_eq!(messages.len(), 3);
+
+        let mut archive = Vec::new();
+        messages.serialize_compressed(&mut archive).unwrap();
+        let mut messages =
+            Vec::<StepMessage<Fr, InnerProductConfig>>::deserialize_compressed(&archive[..])
+                .unwrap();
+        let reconstruct =
+            |messages: &[_]| Scheme::reconstruct_accumulator(&vk, &origin_instance, messages);
+        assert!(reconstruct(&messages).unwrap() == *proof.running_instance());
+
+        messages[1].cross_term_commitments[0] += Fr::one();
+        assert!(reconstruct(&messages).unwrap() != *proof.running_instance());
+        messages[1].cross_term_commitments.pop();
+        assert_eq!(
+            reconstruct(&messages).err(),
+            Some(SangriaError::InvalidProof)
+        );
+
+        // the steps of merged proofs are not folded one after the other
+        let rng = &mut test_rng();
+        let merged = Scheme::merge(
+            &pk,
+            prove_chain(&pk, 2, 2).1,
+            prove_chain_from(&pk, Fr::from(4u64), 2, 2).1,
+            rng,
+        )
+        .unwrap();
+        assert_eq!(
+            merged.prover_messages().err(),
+            Some(SangriaError::InvalidProof)
+        );
+    }
+
+    #[test]
+    fn merged_proofs_verify() {
+        let rng = &mut test_rng();
+        let (pk, vk) = keys();
+        let verify = |state, proof| Scheme::verify(&vk, &Fr::zero(), state, Some(proof));
+
+        // four provers each prove two steps from the state the previous one ends in
+        let chains: Vec<_> = (0..4u64)
+            .map(|index| prove_chain_from(&pk, Fr::from(4 * index), 2, 2).1)
+            .collect();
+        let [first, second, third, fourth] = <[_; 4]>::try_from(chains).ok().unwrap();
+        let left = Scheme::merge(&pk, first.clone(), second.clone(), rng).unwrap();
+        let right = Scheme::merge(&pk, third, fourth, rng).unwrap();
+        assert_eq!(verify(Fr::from(8u64), left.clone()), Ok(()));
+
+        let merged = Scheme::merge(&pk, left.clone(), right, rng).unwrap();
+        assert_eq!(merged.fold_shape, [0, 1, 0, 2, 0, 1, 0, 3]);
+        assert_eq!(verify(Fr::from(16u64), merged.clone()), Ok(()));
+        assert_eq!(merged.check_ancestor(&left), Ok(()));
+
+        // merged proofs can be extended step by step
+        let (state, extended) = Scheme::prove_step(
+            &pk,
+            &Fr::zero(),
+            Fr::from(16u64),
+            Some(merged.clone()),
+            &Fr::one(),
+            rng,
+        )
+        .unwrap();
+        assert_eq!(verify(state, extended), Ok(()));
+
+        assert_eq!(
+            Scheme::merge(&pk, second, first, rng).err(),
+            Some(SangriaError::PublicInputMismatch)
+        );
+        let mut wrong_shape = merged;
+        wrong_shape.fold_shape = vec![0, 1, 1, 1, 1, 1, 1, 1];
+        assert_eq!(
+            verify(Fr::from(16u64), wrong_shape),
+            Err(SangriaError::InvalidProof)
+        );
+    }
+
+    #[test]
+    fn parallel_proofs_verify() {
+        let rng = &mut test_rng();
+        let (pk, vk) = keys();
+        let origin = Fr::from(5u64);
+
+        for steps in [1, 2, 5] {
+            let witnesses: Vec<Fr> = (1..=steps).map(Fr::from).collect();
+            let (state, proof) =
+                Scheme::prove_steps_parallel(&pk, &origin, &witnesses, rng).unwrap();
+
+            assert_eq!(Ok(state), Sangria::replay(&Counter, origin, &witnesses));
+            assert_eq!(proof.number_of_steps(), steps as usize);
+            assert_eq!(Scheme::verify(&vk, &origin, state, Some(proof)), Ok(()));
+        }
+
+        for witnesses in [&[][..], &[Fr::one(), Fr::zero()]] {
+            assert_eq!(
+                Scheme::prove_steps_parallel(&pk, &origin, witnesses, rng).err(),
+                Some(SangriaError::InvalidWitness)
+            );
+        }
+    }
+
+    #[test]
+    fn segmented_proofs_verify() {
+        let rng = &mut test_rng();
+        let (pk, vk) = keys();
+        let origin = Fr::from(5u64);
+        let witnesses: Vec<Fr> = (1..=5u64).map(Fr::from).collect();
+
+        for parallelism in [1, 2, 3, 8] {
+            let (state, proof) =
+                Scheme::prove_parallel(&pk, &origin, &witnesses, parallelism, rng).unwrap();
+
+            assert_eq!(Ok(state), Sangria::replay(&Counter, origin, &witnesses));
+            assert_eq!(proof.number_of_steps(), witnesses.len());
+            assert_eq!(Scheme::verify(&vk, &origin, state, Some(proof)), Ok(()));
+        }
+
+        assert_eq!(
+            Scheme::prove_parallel(&pk, &origin, &witnesses, 0, rng).err(),
+            Some(SangriaError::InvalidConfig(
+                "the parallelism must be at least one"
+            ))
+        );
+        for witnesses in [&[][..], &[Fr::one(), Fr::one(), Fr::zero()]] {
+            assert_eq!(
+                Scheme::prove_parallel(&pk, &origin, witnesses, 2, rng).err(),
+                Some(SangriaError::InvalidWitness)
+            );
+        }
+    }
+
+    #[test]
+    fn ancestors_are_recognized() {
+        let (pk, _) = keys();
+        let (_, proof) = prove_chain(&pk, 5, 2);
+
+        for steps in 1..=5 {
+            assert_eq!(proof.check_ancestor(&prove_chain(&pk, steps, 2).1), Ok(()));
+        }
+        assert_eq!(
+            prove_chain(&pk, 3, 2).1.check_ancestor(&proof),
+            Err(SangriaError::InvalidProof)
+        );
+        assert_eq!(
+            proof.check_ancestor(&prove_chain(&pk, 3, 3).1),
+            Err(SangriaError::InvalidProof)
+        );
+    }
+
+    #[test]
+    fn audits_record_every_fold() {
+        let (pk, vk) = keys();
+        let (state, proof) = prove_chain(&pk, 4, 2);
+
+        let (result, audits) = Scheme::verify_with_audit(&vk, &Fr::zero(), state, proof.clone());
+        assert_eq!(result, Ok(()));
+        assert_eq!(audits.len(), 3);
+        for (round, (audit, step_instance)) in
+            audits.iter().zip(&proof.step_instances[1..]).enumerate()
+        {
+            assert_eq!(audit.round, round);
+            let labels: Vec<_> = audit.absorbed.iter().map(|(label, _)| *label).collect();
+            assert_eq!(
+                labels,
+                [
+                    "verifier key",
+                    "left instance",
+                    "right instance",
+                    "cross term commitments"
+                ]
+            );
+            assert_eq!(
+                audit.absorbed[2].1,
+                step_instance.to_sponge_field_elements_as_vec::<Fr>()
+            );
+        }
+        assert!(audits[2].folded_instance == proof.running_instance);
+
+        let mut wrong_cross_term = proof;
+        wrong_cross_term.cross_term_commitments[1][0] += Fr::one();
+        let (result, audits) = Scheme::verify_with_audit(&vk, &Fr::zero(), state, wrong_cross_term);
+        assert_eq!(result, Err(SangriaError::InvalidProof));
+        assert_eq!(audits.len(), 3);
+    }
+
+    #[test]
+    fn statement_digests_bind_every_component() {
+        let (_, vk) = keys();
+        let (_, other_vk) = keys_with(SangriaConfig {
+            domain_separator: b"testnet".to_vec(),
+            ..Default::default()
+        });
+        let (_, application_vk) =
+            keys_with(SangriaConfig::default().with_application_id("mainnet"));
+        let (origin, current) = (Fr::from(5u64), Fr::from(8u64));
+        let digest = vk.statement_digest(&origin, &current, 3);
+
+        assert_eq!(vk.statement_digest(&origin, &current, 3), digest);
+        for other in [
+            vk.statement_digest(&origin, &current, 4),
+            vk.statement_digest(&current, &current, 3),
+            vk.statement_digest(&origin, &origin, 3),
+            other_vk.statement_digest(&origin, &current, 3),
+            application_vk.statement_digest(&origin, &current, 3),
+        ] {
+            assert_ne!(other, digest);
+        }
     }
 }
@@ -1,13 +1,30 @@
+#![forbid(unsafe_code)]
 #![warn(missing_docs)]
 //! Sangria is a Nova-like scheme for recursive zero-knowledge proofs. It implements incrementally
-//! verifiable computation by using a folding for PLONK. We use a modified version of HyperPlonk to
-//! compress the IVC proofs.
+//! verifiable computation by using a folding for PLONK. We use a modified version of HyperPlonk (see
+//! [`hyperplonk`]) to compress the IVC proofs.
+//!
+//! The library never samples randomness on its own: every randomised algorithm (setup, encoding,
+//! folding, proving a step) takes its randomness from a caller-supplied [`Rng`]. The crate does not
+//! depend on `getrandom`, so it builds for targets without OS randomness and a prover seeded with a
+//! deterministic RNG is fully reproducible.
+//!
+//! The crate forbids `unsafe` code, in the prover as well as in the verifier, so integrators get an
+//! `unsafe`-free verifier without selecting features.
 
 use ark_ff::PrimeField;
+use ark_serialize::CanonicalSerialize;
 use ark_std::rand::Rng;
 
-/// Interface for an IVC scheme.
+/// Interface for an IVC scheme, whose verifier checks a constant-size proof of the last step.
+///
+/// It is meant for the recursive form of Sangria, which verifies the previous fold in the circuit
+/// of every step; the batched proofs of [`Sangria`] re-fold every step when verified, so they are
+/// produced by inherent functions such as [`Sangria::prove_step`] instead.
 pub trait IVC<F: PrimeField, SC: StepCircuit<F>> {
+    /// A type to contain the arguments necessary to run `setup`
+    type SetupInfo;
+
     /// Public parameters for the IVC scheme.
     type PublicParameters;
 
@@ -21,7 +38,10 @@ pub trait IVC<F: PrimeField, SC: StepCircuit<F>> {
     type Proof;
 
     /// Run the IVC setup to produce public parameters.
-    fn setup<R: Rng>(rng: &mut R) -> Self::PublicParameters;
+    fn setup<R: Rng>(
+        info: &Self::SetupInfo,
+        rng: &mut R,
+    ) -> Result<Self::PublicParameters, SangriaError>;
 
     /// Run the IVC encoder to produce a proving key and a verifying key.
     fn encode<R: Rng>(
@@ -31,12 +51,14 @@ pub trait IVC<F: PrimeField, SC: StepCircuit<F>> {
     ) -> Result<(Self::ProverKey, Self::VerifierKey), SangriaError>;
 
     /// Prove a step of the IVC computation. Consume the current state and proof and produce the *next* state and proof.
-    fn prove_step(
+    /// The commitment blinders for the step are sampled from `rng`.
+    fn prove_step<R: Rng>(
         prover_key: &Self::ProverKey,
         origin_state: &SC::State,
         current_state: SC::State,
         current_proof: Option<Self::Proof>,
         current_witness: &SC::Witness,
+        rng: &mut R,
     ) -> Result<(SC::State, Self::Proof), SangriaError>;
 
     /// Verify a step of the IVC computation.
@@ -48,8 +70,101 @@ pub trait IVC<F: PrimeField, SC: StepCircuit<F>> {
     ) -> Result<(), SangriaError>;
 }
 
-/// A marker trait for an IVC scheme which implements proof compression.
-pub trait IVCWithProofCompression<F: PrimeField, SC: StepCircuit<F>>: IVC<F, SC> {}
+/// Interface for a non-uniform IVC scheme, in the style of SuperNova: several step circuits over the
+/// same states are encoded together, and every step runs the circuit selected for it, so that a
+/// program with many instruction types does not pay for a universal circuit at every step.
+pub trait NonUniformIVC<F: PrimeField, SC: StepCircuit<F>> {
+    /// A type to contain the arguments necessary to run `setup`
+    type SetupInfo;
+
+    /// Public parameters for the scheme, shared by every step circuit.
+    type PublicParameters;
+
+    /// A collection of data needed for proving.
+    type ProverKey;
+
+    /// A collection of data needed for verifying.
+    type VerifierKey;
+
+    /// A non-uniform IVC proof.
+    type Proof;
+
+    /// Run the setup to produce public parameters large enough for every step circuit.
+    fn setup<R: Rng>(
+        info: &Self::SetupInfo,
+        rng: &mut R,
+    ) -> Result<Self::PublicParameters, SangriaError>;
+
+    /// Run the encoder on every step circuit to produce a proving key and a verifying key. The
+    /// circuit at index `i` of `step_circuits` is the one selected by `circuit_index == i` when
+    /// proving a step.
+    fn encode<R: Rng>(
+        public_parameters: &Self::PublicParameters,
+        step_circuits: &[SC],
+        rng: &mut R,
+    ) -> Result<(Self::ProverKey, Self::VerifierKey), SangriaError>;
+
+    /// Prove a step of the computation with the step circuit at index `circuit_index`. Consume the
+    /// current state and proof and produce the *next* state and proof. The commitment blinders for
+    /// the step are sampled from `rng`.
+    #[allow(clippy::too_many_arguments)]
+    fn prove_step<R: Rng>(
+        prover_key: &Self::ProverKey,
+        origin_state: &SC::State,
+        current_state: SC::State,
+        current_proof: Option<Self::Proof>,
+        circuit_index: usize,
+        current_witness: &SC::Witness,
+        rng: &mut R,
+    ) -> Result<(SC::State, Self::Proof), SangriaError>;
+
+    /// Verify a step of the computation.
+    fn verify(
+        verifier_key: &Self::VerifierKey,
+        origin_state: &SC::State,
+        current_state: SC::State,
+        current_proof: Option<Self::Proof>,
+    ) -> Result<(), SangriaError>;
+}
+
+/// An IVC scheme whose proofs can be compressed, replacing the witness of the final running
+/// instance with a SNARK.
+pub trait IVCWithProofCompression<F: PrimeField, SC: StepCircuit<F>>: IVC<F, SC> {
+    /// A compressed IVC proof.
+    type CompressedProof;
+
+    /// Compress an IVC proof produced by `prove_step`.
+    fn compress(
+        prover_key: &Self::ProverKey,
+        proof: Self::Proof,
+    ) -> Result<Self::CompressedProof, SangriaError>;
+
+    /// Verify a compressed IVC proof that the computation went from the origin state to the
+    /// current state.
+    fn verify_compressed(
+        verifier_key: &Self::VerifierKey,
+        origin_state: &SC::State,
+        current_state: SC::State,
+        compressed_proof: Self::CompressedProof,
+    ) -> Result<(), SangriaError>;
+
+    /// Verify a compressed IVC proof that may embed its verifier key, under the key given by the
+    /// caller or the embedded one as `policy` selects (see [`SelfContainedProof::verify`]).
+    fn verify_self_contained(
+        verifier_key: Option<&Self::VerifierKey>,
+        origin_state: &SC::State,
+        current_state: SC::State,
+        proof: SelfContainedProof<Self::VerifierKey, Self::CompressedProof>,
+        policy: VerifierKeyPolicy,
+    ) -> Result<(), SangriaError>
+    where
+        Self::VerifierKey: CanonicalSerialize,
+    {
+        proof.verify(verifier_key, policy, |verifier_key, compressed_proof| {
+            Self::verify_compressed(verifier_key, origin_state, current_state, compressed_proof)
+        })
+    }
+}
 
 /// Interface for a single step of the incremental computation.
 pub trait StepCircuit<F: PrimeField> {
@@ -58,6 +173,44 @@ pub trait StepCircuit<F: PrimeField> {
 
     /// The non-deterministic input for a step of the computation
     type Witness;
+
+    /// Natively execute a single step of the computation, producing the next state from the current
+    /// state and the step's witness, or an error if the witness is not valid for the current state.
+    fn execute(
+        &self,
+        current_state: &Self::State,
+        witness: &Self::Witness,
+    ) -> Result<Self::State, SangriaError>;
+
+    /// Constant tables (e.g. round constants) used by the step circuit. Each table is baked into a
+    /// fixed column of the circuit when the circuit is encoded, rather than being re-derived with
+    /// witness wires and gates in every step, and its entries are read with
+    /// [`CircuitBuilder::fixed`]. By default a step circuit has no constant tables.
+    fn fixed_tables(&self) -> Vec<Vec<F>> {
+        Vec::new()
+    }
+}
+
+/// A step circuit arithmetized as PLONK gates, which can be proved with Sangria.
+pub trait PLONKStepCircuit<F: PrimeField>: StepCircuit<F> {
+    /// The number of field elements encoding a state. The augmented circuit has two public inputs
+    /// per element, for the current and the next state.
+    fn state_len(&self) -> usize;
+
+    /// Encode a state as field elements. Every state must be encoded with [`Self::state_len`]
+    /// elements.
+    fn encode_state(&self, state: &Self::State) -> Vec<F>;
+
+    /// Append the gate rows of a step from the encoded `current_state` to the encoded `next_state`
+    /// with `witness` to `trace`, and return the wires holding each element of both states. The
+    /// layout must not depend on the values, so that every step shares the same circuit.
+    fn synthesize(
+        &self,
+        trace: &mut TraceTable<F>,
+        current_state: &[F],
+        next_state: &[F],
+        witness: &Self::Witness,
+    ) -> Result<StateWires, SangriaError>;
 }
 
 /// Interface for a non-interactive folding scheme (NIFS).
@@ -87,7 +240,10 @@ pub trait NonInteractiveFoldingScheme {
     type ProverMessage;
 
     /// Run the randomised setup for the folding scheme to produce public parameters.
-    fn setup<R: Rng>(info: &Self::SetupInfo, rng: &mut R) -> Self::PublicParameters;
+    fn setup<R: Rng>(
+        info: &Self::SetupInfo,
+        rng: &mut R,
+    ) -> Result<Self::PublicParameters, SangriaError>;
 
     /// Using the public parameters, run the randomised encoder that produces a prover key and verifier key.
     fn encode<R: Rng>(
@@ -97,14 +253,16 @@ pub trait NonInteractiveFoldingScheme {
     ) -> Result<(Self::ProverKey, Self::VerifierKey), SangriaError>;
 
     /// The folding scheme prover. Outputs a folded instance-witness pair and the prover's message.
+    /// The randomness used to commit to the prover's message is sampled from `rng`.
     #[allow(clippy::type_complexity)]
-    fn prover(
+    fn prover<R: Rng>(
         public_parameters: &Self::PublicParameters,
         prover_key: &Self::ProverKey,
         left_instance: &Self::Instance,
         left_witness: &Self::Witness,
         right_instance: &Self::Instance,
         right_witness: &Self::Witness,
+        rng: &mut R,
     ) -> Result<(Self::Instance, Self::Witness, Self::ProverMessage), SangriaError>;
 
     /// The folding scheme verifier. Outputs a folded instance.
@@ -115,24 +273,171 @@ pub trait NonInteractiveFoldingScheme {
         right_instance: &Self::Instance,
         prover_message: &Self::ProverMessage,
     ) -> Result<Self::Instance, SangriaError>;
+
+    /// The folding scheme prover for several instances at once, folding them in a single round
+    /// with a single prover message rather than one fold per instance. Outputs a folded
+    /// instance-witness pair and the prover's message.
+    #[allow(clippy::type_complexity)]
+    fn prover_many<R: Rng>(
+        public_parameters: &Self::PublicParameters,
+        prover_key: &Self::ProverKey,
+        instances: &[Self::Instance],
+        witnesses: &[Self::Witness],
+        rng: &mut R,
+    ) -> Result<(Self::Instance, Self::Witness, Self::ProverMessage), SangriaError>;
+
+    /// The folding scheme verifier for several instances at once. Outputs a folded instance.
+    fn verifier_many(
+        public_parameters: &Self::PublicParameters,
+        verifier_key: &Self::VerifierKey,
+        instances: &[Self::Instance],
+        prover_message: &Self::ProverMessage,
+    ) -> Result<Self::Instance, SangriaError>;
 }
 
+mod augmented_circuit;
+pub use augmented_circuit::{
+    fold_commitment, fold_scalars, AugmentedCircuit, CommitmentGadget, RecursiveStep,
+};
+
+mod blob;
+pub use blob::{blob_hash, chain_blob_hashes, BlobBoundStep};
+
+mod ccs;
+pub use ccs::{CCSFoldingScheme, CCSShape};
+
+mod checkpoint;
+pub use checkpoint::Checkpoint;
+
+mod config;
+pub use config::{
+    Profile, SangriaConfig, DEFAULT_CHALLENGE_BITS, DEFAULT_DOMAIN_SEPARATOR, DEFAULT_GATE_DEGREE,
+    MAX_APPLICATION_ID_LEN, MAX_DOMAIN_SEPARATOR_LEN,
+};
+
+mod cost;
+pub use cost::{CostModel, GadgetCost};
+
+pub mod decider;
+
+mod embedded_curve;
+pub use embedded_curve::{EmbeddedPoint, WINDOW_BITS};
+
+mod embedded_key;
+pub use embedded_key::{
+    verifier_key_digest, EmbeddedVerifierKey, SelfContainedProof, VerifierKeyPolicy,
+};
+
 mod folding_scheme;
-pub use folding_scheme::PLONKFoldingScheme;
+pub use folding_scheme::{
+    FoldAudit, FoldChallenge, FoldingCommitmentConfig, InstanceSchema, KeyDiff, PLONKFoldingScheme,
+    SetupInfo, SingleCommitment, WireOpening, TRANSCRIPT_DIGEST_LABEL,
+};
+
+pub mod hints;
 
-// mod ivc;
+pub mod hyperplonk;
+
+mod ivc;
+pub use ivc::{
+    BatchedIVCProof, CompressedBatchedIVCProof, ProverKey, SelfTestReport, StateWires, StepMessage,
+    VerifierKey,
+};
+
+mod merkle;
+pub use merkle::{MerkleTree, MerkleUpdate, MerkleUpdateStep};
+
+mod metrics;
+pub use metrics::{VerificationLog, VerificationStatistics, VerifierMetrics, CHALLENGE_BUCKETS};
+
+mod mle;
+pub use mle::MultilinearExtension;
+
+mod multi_opening;
+pub use multi_opening::{
+    commitment_digest, multi_opening_gadget, verify_deferred_openings, DeferredOpening,
+    OpeningBoundStep, OpeningClaim,
+};
+
+mod nonnative;
+pub use nonnative::{NonNativeElement, LIMB_BITS};
+
+mod non_uniform;
+pub use non_uniform::NonUniformIVCProof;
+
+mod noop;
+pub use noop::{NoopStep, PaddedStep};
+
+pub mod piop;
+
+mod parallel;
+
+mod poseidon;
+pub use poseidon::{poseidon_hash, poseidon_hash_gadget};
+
+mod r1cs_ivc;
+pub use r1cs_ivc::R1CSIVCProof;
+
+// The recursive IVC needs commitment schemes folded in-circuit over the field of the instances,
+// which only the inner product commitment of the tests is: Pedersen and KZG commitments live on a
+// curve whose base field is not that field. It is not exported until a curve commitment has a
+// `CommitmentGadget`.
+#[allow(dead_code)]
+mod recursive_ivc;
 
 mod relaxed_plonk;
 pub use relaxed_plonk::{
-    PLONKCircuit, RelaxedPLONKInstance, RelaxedPLONKWitness, CONSTANT_SELECTOR_INDEX,
-    LEFT_SELECTOR_INDEX, MULTIPLICATION_SELECTOR_INDEX, OUTPUT_SELECTOR_INDEX,
+    CircuitBuilder, GateFailure, MockProver, PLONKCircuit, RelaxedPLONKInstance,
+    RelaxedPLONKWitness, Variable, WitnessLayout, CONSTANT_SELECTOR_INDEX, LEFT_SELECTOR_INDEX,
+    MULTIPLICATION_SELECTOR_INDEX, OUTPUT_SELECTOR_INDEX, POWER_SELECTOR_INDEX,
     RIGHT_SELECTOR_INDEX,
 };
 
+mod relaxed_r1cs;
+pub use relaxed_r1cs::{R1CSFoldingScheme, R1CSShape, RelaxedR1CSInstance, RelaxedR1CSWitness};
+
 mod sangria;
-pub use sangria::Sangria;
+pub use sangria::{PLONKRelation, R1CSRelation, Sangria};
+
+#[cfg(feature = "serde")]
+mod serde_encoding;
+
+mod serialization;
+pub use serialization::{
+    fe_canonical_length, fe_from_bytes_canonical, fe_to_bytes_canonical, SizeLimits,
+};
+
+mod session;
+pub use session::{replay, ReplayedSession, SessionRecorder};
+
+pub mod spec_vectors;
+
+mod trace;
+pub use trace::{OptimizationReport, RowTag, TraceTable};
 
 mod errors;
-pub use errors::SangriaError;
+pub use errors::{PCSError, SangriaError, SerializationFailure};
+
+mod transcript;
+pub use transcript::{
+    Blake3, Blake3Transcript, HashTranscript, Keccak256, KeccakTranscript, Transcript,
+    TranscriptHash,
+};
+
+pub mod vc_testsuite;
 
 mod vector_commitment;
+pub use vector_commitment::{
+    kzg, pedersen, HidingOpening, HomomorphicCommitmentScheme, InnerProductOpening,
+};
+
+mod witness_codec;
+pub use witness_codec::{decode_field_elements, encode_field_elements, encoded_len};
+
+#[cfg(feature = "usage-tracking")]
+mod usage;
+#[cfg(feature = "usage-tracking")]
+pub use usage::UsageStatistics;
+
+#[cfg(test)]
+mod utils;
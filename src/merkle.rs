@@ -0,0 +1,408 @@
+use ark_crypto_primitives::sponge::{
+    poseidon::{PoseidonConfig, PoseidonSponge},
+    Absorb, CryptographicSponge, FieldBasedCryptographicSponge,
+};
+use ark_ff::PrimeField;
+use std::collections::BTreeMap;
+
+use crate::{
+    nonnative::{assert_zero, decompose},
+    poseidon::{linear_combination, multiplication_gate},
+    poseidon_hash_gadget, PLONKStepCircuit, SangriaError, StateWires, StepCircuit, TraceTable,
+};
+
+/// A wire of a trace, given as (column, row).
+type Wire = (usize, usize);
+
+/// Hashes two sibling nodes into their parent.
+fn hash_children<F: PrimeField + Absorb>(
+    poseidon_constants: &PoseidonConfig<F>,
+    left: F,
+    right: F,
+) -> F {
+    let mut sponge = PoseidonSponge::new(poseidon_constants);
+    sponge.absorb(&left);
+    sponge.absorb(&right);
+    sponge.squeeze_native_field_elements(1)[0]
+}
+
+/// Recomputes the root of a tree from a leaf, its index and its authentication path (the siblings
+/// from the leaf level up to the level below the root).
+fn root_from_path<F: PrimeField + Absorb>(
+    poseidon_constants: &PoseidonConfig<F>,
+    index: u64,
+    leaf: F,
+    path: &[F],
+) -> F {
+    path.iter()
+        .enumerate()
+        .fold(leaf, |node, (level, &sibling)| {
+            if (index >> level) & 1 == 0 {
+                hash_children(poseidon_constants, node, sibling)
+            } else {
+                hash_children(poseidon_constants, sibling, node)
+            }
+        })
+}
+
+/// The update of a single leaf of a Merkle tree: the leaf at `index` goes from `old_leaf` to
+/// `new_leaf`, `path` being the authentication path of the leaf.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MerkleUpdate<F: PrimeField> {
+    /// The index of the updated leaf.
+    pub index: u64,
+    /// The value of the leaf before the update.
+    pub old_leaf: F,
+    /// The value of the leaf after the update.
+    pub new_leaf: F,
+    /// The siblings of the leaf, from the leaf level up to the level below the root.
+    pub path: Vec<F>,
+}
+
+/// A step circuit applying a batch of leaf updates to a Poseidon Merkle tree of fixed depth. The
+/// state is the root of the tree and the witness is the sequence of updates of the step.
+///
+/// As a PLONK circuit, a step has room for a fixed number of updates (one unless set with
+/// [`Self::with_updates_per_step`]), so that every step has the same layout: the updates of a step
+/// fill the first slots and the remaining slots are disabled.
+#[derive(Clone)]
+pub struct MerkleUpdateStep<F: PrimeField> {
+    poseidon_constants: PoseidonConfig<F>,
+    depth: usize,
+    updates_per_step: usize,
+}
+
+impl<F: PrimeField + Absorb> MerkleUpdateStep<F> {
+    /// Creates the step circuit for trees of the given depth.
+    pub fn new(poseidon_constants: PoseidonConfig<F>, depth: usize) -> Self {
+        Self {
+            poseidon_constants,
+            depth,
+            updates_per_step: 1,
+        }
+    }
+
+    /// Sets the number of update slots of the PLONK circuit of a step.
+    pub fn with_updates_per_step(self, updates_per_step: usize) -> Self {
+        Self {
+            updates_per_step,
+            ..self
+        }
+    }
+
+    /// Appends the gates recomputing the root from the wires of a leaf, of the bits of its index
+    /// (most significant first) and of its authentication path, and returns the wire holding it.
+    fn root_gadget(
+        &self,
+        trace: &mut TraceTable<F>,
+        leaf: Wire,
+        index_bits: &[Wire],
+        path: &[Wire],
+    ) -> Result<Wire, SangriaError> {
+        let one = F::one();
+        path.iter()
+            .zip(index_bits.iter().rev())
+            .try_fold(leaf, |node, (&sibling, &bit)| {
+                // the node is the right child where the bit is one
+                let difference =
+                    linear_combination(trace, &[(one, sibling), (-one, node)], F::zero())?;
+                let swap = multiplication_gate(trace, bit, difference)?;
+                let left = linear_combination(trace, &[(one, node), (one, swap)], F::zero())?;
+                let right = linear_combination(trace, &[(one, sibling), (-one, swap)], F::zero())?;
+
+                poseidon_hash_gadget(trace, &self.poseidon_constants, &[left, right])
+            })
+    }
+}
+
+impl<F: PrimeField + Absorb> StepCircuit<F> for MerkleUpdateStep<F> {
+    type State = F;
+    type Witness = Vec<MerkleUpdate<F>>;
+
+    fn execute(&self, current_root: &F, updates: &Self::Witness) -> Result<F, SangriaError> {
+        updates.iter().try_fold(*current_root, |root, update| {
+            if update.path.len() != self.depth || update.index >> self.depth != 0 {
+                return Err(SangriaError::InvalidWitness);
+            }
+
+            let old_root = root_from_path(
+                &self.poseidon_constants,
+                update.index,
+                update.old_leaf,
+                &update.path,
+            );
+            if old_root != root {
+                return Err(SangriaError::InvalidWitness);
+            }
+
+            Ok(root_from_path(
+                &self.poseidon_constants,
+                update.index,
+                update.new_leaf,
+                &update.path,
+            ))
+        })
+    }
+}
+
+/// Each update slot decomposes the index of the leaf into `depth` bits, recomputes the root from
+/// the old leaf and from the new leaf with the same path, and checks that the old root is the
+/// current root where the slot is enabled. The next root is the new root of every enabled slot in
+/// turn.
+impl<F: PrimeField + Absorb> PLONKStepCircuit<F> for MerkleUpdateStep<F> {
+    fn state_len(&self) -> usize {
+        1
+    }
+
+    fn encode_state(&self, root: &F) -> Vec<F> {
+        vec![*root]
+    }
+
+    fn synthesize(
+        &self,
+        trace: &mut TraceTable<F>,
+        current_state: &[F],
+        _next_state: &[F],
+        updates: &Self::Witness,
+    ) -> Result<StateWires, SangriaError> {
+        if updates.len() > self.updates_per_step
+            || updates.iter().any(|update| update.path.len() != self.depth)
+        {
+            return Err(SangriaError::InvalidWitness);
+        }
+
+        let one = F::one();
+        let current_root = trace.hint(&[], |_| Ok(current_state.to_vec()))?[0];
+        let mut root = current_root;
+        for slot in 0..self.updates_per_step {
+            let (enabled, update) = match updates.get(slot) {
+                Some(update) => (one, update.clone()),
+                None => (
+                    F::zero(),
+                    MerkleUpdate {
+                        index: 0,
+                        old_leaf: F::zero(),
+                        new_leaf: F::zero(),
+                        path: vec![F::zero(); self.depth],
+                    },
+                ),
+            };
+            let enabled = decompose(trace, enabled, 1).1;
+            let index_bits = decompose(trace, F::from(update.index), self.depth).0;
+            let leaves = trace.hint(&[], |_| Ok(vec![update.old_leaf, update.new_leaf]))?;
+            let path = trace.hint(&[], |_| Ok(update.path))?;
+
+            let old_root = self.root_gadget(trace, leaves[0], &index_bits, &path)?;
+            let new_root = self.root_gadget(trace, leaves[1], &index_bits, &path)?;
+            let difference =
+                linear_combination(trace, &[(one, old_root), (-one, root)], F::zero())?;
+            let product = multiplication_gate(trace, enabled, difference)?;
+            assert_zero(trace, product);
+
+            let difference =
+                linear_combination(trace, &[(one, new_root), (-one, root)], F::zero())?;
+            let product = multiplication_gate(trace, enabled, difference)?;
+            root = linear_combination(trace, &[(one, root), (one, product)], F::zero())?;
+        }
+
+        Ok(StateWires {
+            current_state: vec![current_root],
+            next_state: vec![root],
+        })
+    }
+}
+
+/// A sparse Poseidon Merkle tree whose leaves are all zero initially. It generates the witnesses for
+/// [`MerkleUpdateStep`].
+pub struct MerkleTree<F: PrimeField> {
+    poseidon_constants: PoseidonConfig<F>,
+    depth: usize,
+    /// The root of an empty subtree at each level, from the leaves up to the root.
+    empty_nodes: Vec<F>,
+    /// The non-empty nodes, indexed by level (0 for the leaves) and index in the level.
+    nodes: BTreeMap<(usize, u64), F>,
+}
+
+impl<F: PrimeField + Absorb> MerkleTree<F> {
+    /// Creates a tree of the given depth with all leaves set to zero.
+    pub fn new(poseidon_constants: PoseidonConfig<F>, depth: usize) -> Self {
+        let mut empty_nodes = vec![F::zero()];
+        for level in 0..depth {
+            let empty_node = empty_nodes[level];
+            empty_nodes.push(hash_children(&poseidon_constants, empty_node, empty_node));
+        }
+
+        Self {
+            poseidon_constants,
+            depth,
+            empty_nodes,
+            nodes: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the root of the tree.
+    pub fn root(&self) -> F {
+        self.node(self.depth, 0)
+    }
+
+    /// Sets the leaf at `index` to `new_leaf` and returns the corresponding update, or an error if the
+    /// index is out of bounds.
+    pub fn update(&mut self, index: u64, new_leaf: F) -> Result<MerkleUpdate<F>, SangriaError> {
+        if index >> self.depth != 0 {
+            return Err(SangriaError::IndexOutOfBounds);
+        }
+
+        let old_leaf = self.node(0, index);
+        let path: Vec<F> = (0..self.depth)
+            .map(|level| self.node(level, (index >> level) ^ 1))
+            .collect();
+
+        let mut node = new_leaf;
+        self.nodes.insert((0, index), node);
+        for (level, &sibling) in path.iter().enumerate() {
+            node = if (index >> level) & 1 == 0 {
+                hash_children(&self.poseidon_constants, node, sibling)
+            } else {
+                hash_children(&self.poseidon_constants, sibling, node)
+            };
+            self.nodes.insert((level + 1, index >> (level + 1)), node);
+        }
+
+        Ok(MerkleUpdate {
+            index,
+            old_leaf,
+            new_leaf,
+            path,
+        })
+    }
+
+    fn node(&self, level: usize, index: u64) -> F {
+        self.nodes
+            .get(&(level, index))
+            .copied()
+            .unwrap_or(self.empty_nodes[level])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MerkleTree, MerkleUpdateStep};
+    use crate::{
+        utils::{inner_product::InnerProductConfig, poseidon::poseidon_parameters_for_test},
+        AugmentedCircuit, Sangria, SangriaConfig, SangriaError, SetupInfo, StepCircuit,
+    };
+    use ark_bn254::Fr;
+    use ark_ff::One;
+    use ark_std::test_rng;
+
+    const DEPTH: usize = 20;
+
+    #[test]
+    fn replayed_updates_match_tree_root() {
+        let params = poseidon_parameters_for_test::<Fr>();
+        let mut tree = MerkleTree::new(params.clone(), DEPTH);
+        let step = MerkleUpdateStep::new(params, DEPTH);
+        let origin = tree.root();
+
+        // three steps of four updates each, some of them hitting the same leaf
+        let witnesses: Vec<_> = (0..3u64)
+            .map(|step_index| {
+                (0..4u64)
+                    .map(|i| {
+                        let index = (step_index * 7 + i * 1000) % 5000;
+                        tree.update(index, Fr::from(step_index * 4 + i + 1))
+                            .unwrap()
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        let final_root = Sangria::replay(&step, origin, &witnesses).unwrap();
+
+        assert_eq!(final_root, tree.root());
+    }
+
+    #[test]
+    fn stale_update_is_rejected() {
+        let params = poseidon_parameters_for_test::<Fr>();
+        let mut tree = MerkleTree::new(params.clone(), DEPTH);
+        let step = MerkleUpdateStep::new(params, DEPTH);
+        let origin = tree.root();
+
+        let first = tree.update(3, Fr::one()).unwrap();
+        let second = tree.update(4, Fr::one()).unwrap();
+
+        // applying the second update on the original root uses a stale path
+        assert_eq!(
+            step.execute(&origin, &vec![second.clone()]),
+            Err(SangriaError::InvalidWitness)
+        );
+        assert_eq!(step.execute(&origin, &vec![first, second]), Ok(tree.root()));
+    }
+
+    #[test]
+    fn out_of_range_leaf_is_rejected() {
+        let mut tree = MerkleTree::new(poseidon_parameters_for_test::<Fr>(), DEPTH);
+
+        assert_eq!(
+            tree.update(1 << DEPTH, Fr::one()),
+            Err(SangriaError::IndexOutOfBounds)
+        );
+    }
+
+    #[test]
+    fn updates_are_proved_in_circuit() {
+        type Scheme = Sangria<InnerProductConfig>;
+        const SMALL_DEPTH: usize = 4;
+
+        let rng = &mut test_rng();
+        let params = poseidon_parameters_for_test::<Fr>();
+        let mut tree = MerkleTree::new(params.clone(), SMALL_DEPTH);
+        let step = MerkleUpdateStep::new(params.clone(), SMALL_DEPTH).with_updates_per_step(2);
+        let circuit = AugmentedCircuit::new(step.clone())
+            .batched_circuit()
+            .unwrap();
+        let info = SetupInfo {
+            number_of_public_inputs: 2,
+            number_of_gates: circuit.number_of_rows() - 3,
+            config: SangriaConfig::default(),
+            poseidon_constants: params,
+        };
+        let public_parameters = Scheme::setup(&info, rng).unwrap();
+        let (pk, vk) = Scheme::encode(&public_parameters, &step, rng).unwrap();
+        let origin = tree.root();
+
+        // a step with both slots, then one leaving a slot disabled
+        let witnesses = [
+            vec![
+                tree.update(3, Fr::from(7u64)).unwrap(),
+                tree.update(12, Fr::from(8u64)).unwrap(),
+            ],
+            vec![tree.update(3, Fr::from(9u64)).unwrap()],
+        ];
+        let (mut root, mut proof) = (origin, None);
+        for witness in &witnesses {
+            (root, proof) = Scheme::prove_step(&pk, &origin, root, proof, witness, rng)
+                .map(|(root, proof)| (root, Some(proof)))
+                .unwrap();
+        }
+
+        assert_eq!(root, tree.root());
+        assert_eq!(Scheme::verify(&vk, &origin, root, proof.clone()), Ok(()));
+        assert_eq!(
+            Scheme::verify(&vk, &origin, origin, proof.clone()),
+            Err(SangriaError::InvalidProof)
+        );
+
+        // a step cannot hold more updates than the circuit has slots
+        let too_many = vec![
+            tree.update(1, Fr::one()).unwrap(),
+            tree.update(2, Fr::one()).unwrap(),
+            tree.update(4, Fr::one()).unwrap(),
+        ];
+        assert!(matches!(
+            Scheme::prove_step(&pk, &origin, root, proof, &too_many, rng,),
+            Err(SangriaError::InvalidWitness)
+        ));
+    }
+}
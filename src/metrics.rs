@@ -0,0 +1,175 @@
+use ark_ff::{BigInteger, PrimeField};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::Mutex,
+};
+
+use crate::SangriaError;
+
+/// The number of buckets of the distribution of the challenges.
+pub const CHALLENGE_BUCKETS: usize = 16;
+
+/// The number of most recent challenges a new challenge is compared with.
+const RECENT_CHALLENGES: usize = 1024;
+
+/// A sink for the metrics of the IVC verifier, e.g. counters and histograms exported to Prometheus,
+/// fed by [`Sangria::verify_with_metrics`](crate::Sangria::verify_with_metrics). Operators
+/// monitoring a fleet of verifiers can spot anomalies such as repeated challenges, which indicate
+/// a transcript that does not absorb everything it should.
+pub trait VerifierMetrics<F> {
+    /// Records a fold checked by the verifier and the challenge it was folded with.
+    fn fold_verified(&self, challenge: F);
+
+    /// Records the outcome of the verification of a proof.
+    fn proof_verified(&self, outcome: Result<(), &SangriaError>);
+}
+
+/// A snapshot of the metrics recorded by a [`VerificationLog`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VerificationStatistics {
+    /// The number of folds checked.
+    pub folds_verified: u64,
+    /// The number of proofs accepted.
+    pub proofs_accepted: u64,
+    /// The number of proofs rejected, by [error code](SangriaError::code).
+    pub rejections: BTreeMap<&'static str, u64>,
+    /// The number of challenges by their four least significant bits, which are uniformly
+    /// distributed for a sound transcript.
+    pub challenge_buckets: [u64; CHALLENGE_BUCKETS],
+    /// The number of challenges equal to one of the challenges recorded shortly before them.
+    pub repeated_challenges: u64,
+}
+
+/// Records the metrics of the verifier in memory, to be scraped with [`Self::statistics`]. It can be
+/// shared by verifiers running on several threads.
+#[derive(Debug)]
+pub struct VerificationLog<F> {
+    statistics: Mutex<VerificationStatistics>,
+    recent_challenges: Mutex<VecDeque<F>>,
+}
+
+impl<F> Default for VerificationLog<F> {
+    fn default() -> Self {
+        Self {
+            statistics: Default::default(),
+            recent_challenges: Mutex::new(VecDeque::with_capacity(RECENT_CHALLENGES)),
+        }
+    }
+}
+
+impl<F> VerificationLog<F> {
+    /// Returns a snapshot of the metrics recorded so far.
+    pub fn statistics(&self) -> VerificationStatistics {
+        self.statistics
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+}
+
+impl<F: PrimeField> VerifierMetrics<F> for VerificationLog<F> {
+    fn fold_verified(&self, challenge: F) {
+        let repeated = {
+            let mut recent = self
+                .recent_challenges
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            let repeated = recent.contains(&challenge);
+            if recent.len() == RECENT_CHALLENGES {
+                recent.pop_front();
+            }
+            recent.push_back(challenge);
+            repeated
+        };
+        let bucket = challenge.into_bigint().to_bytes_le()[0] as usize % CHALLENGE_BUCKETS;
+
+        let mut statistics = self
+            .statistics
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        statistics.folds_verified += 1;
+        statistics.challenge_buckets[bucket] += 1;
+        statistics.repeated_challenges += u64::from(repeated);
+    }
+
+    fn proof_verified(&self, outcome: Result<(), &SangriaError>) {
+        let mut statistics = self
+            .statistics
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        match outcome {
+            Ok(()) => statistics.proofs_accepted += 1,
+            Err(error) => *statistics.rejections.entry(error.code()).or_default() += 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{VerificationLog, VerifierMetrics};
+    use crate::{
+        utils::{
+            circuits::Counter, inner_product::InnerProductConfig,
+            poseidon::poseidon_parameters_for_test,
+        },
+        Sangria, SangriaConfig, SangriaError, SetupInfo,
+    };
+    use ark_bn254::Fr;
+    use ark_ff::{One, Zero};
+    use ark_std::test_rng;
+
+    type Scheme = Sangria<InnerProductConfig>;
+
+    #[test]
+    fn verifications_are_counted() {
+        let rng = &mut test_rng();
+        let info = SetupInfo {
+            number_of_public_inputs: 2,
+            number_of_gates: 2,
+            config: SangriaConfig::default(),
+            poseidon_constants: poseidon_parameters_for_test(),
+        };
+        let public_parameters = Scheme::setup(&info, rng).unwrap();
+        let (pk, vk) = Scheme::encode(&public_parameters, &Counter, rng).unwrap();
+        let (mut state, mut proof) = (Fr::zero(), None);
+        for _ in 0..4 {
+            (state, proof) = Scheme::prove_step(&pk, &Fr::zero(), state, proof, &Fr::one(), rng)
+                .map(|(state, proof)| (state, Some(proof)))
+                .unwrap();
+        }
+
+        let log = VerificationLog::default();
+        let verify =
+            |state, proof| Scheme::verify_with_metrics(&vk, &Fr::zero(), state, proof, &log);
+        assert_eq!(verify(state, proof.clone()), Ok(()));
+        assert_eq!(verify(Fr::zero(), None), Ok(()));
+        assert_eq!(
+            verify(state + Fr::one(), proof),
+            Err(SangriaError::InvalidProof)
+        );
+        assert_eq!(verify(state, None), Err(SangriaError::InvalidProof));
+
+        let statistics = log.statistics();
+        assert_eq!(statistics.proofs_accepted, 2);
+        assert_eq!(statistics.rejections.get("invalid_proof"), Some(&2));
+        // the three folds of the accepted proof, the wrong state being rejected before any fold
+        assert_eq!(statistics.folds_verified, 3);
+        assert_eq!(
+            statistics.challenge_buckets.iter().sum::<u64>(),
+            statistics.folds_verified
+        );
+    }
+
+    #[test]
+    fn repeated_challenges_are_detected() {
+        let log = VerificationLog::default();
+        for challenge in [1u64, 2, 1, 3, 3] {
+            log.fold_verified(Fr::from(challenge));
+        }
+
+        let statistics = log.statistics();
+        assert_eq!(statistics.repeated_challenges, 2);
+        assert_eq!(statistics.challenge_buckets[1], 2);
+        assert_eq!(statistics.challenge_buckets[3], 2);
+    }
+}
@@ -0,0 +1,183 @@
+use ark_ff::Field;
+use std::ops::{Add, Mul};
+
+use crate::SangriaError;
+
+/// The multilinear extension of a column, given by its evaluations over the boolean hypercube.
+/// The i-th evaluation is the value at the point whose j-th coordinate is the j-th bit of i, so the
+/// first variable selects between consecutive entries.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MultilinearExtension<F: Field> {
+    evaluations: Vec<F>,
+    number_of_variables: usize,
+}
+
+impl<F: Field> MultilinearExtension<F> {
+    /// Builds the multilinear extension of a witness or selector column, padding the column with
+    /// zeros up to the next power of two.
+    pub fn from_column(column: &[F]) -> Self {
+        let size = column.len().max(1).next_power_of_two();
+        let mut evaluations = column.to_vec();
+        evaluations.resize(size, F::zero());
+
+        Self {
+            evaluations,
+            number_of_variables: size.trailing_zeros() as usize,
+        }
+    }
+
+    /// Returns the number of variables of the extension.
+    pub fn number_of_variables(&self) -> usize {
+        self.number_of_variables
+    }
+
+    /// Returns the evaluations over the boolean hypercube.
+    pub fn evaluations(&self) -> &[F] {
+        &self.evaluations
+    }
+
+    /// Fixes the first variable to `r`, returning an extension with one variable less, or an error
+    /// if the extension has no variable left.
+    pub fn fix_variable(&self, r: F) -> Result<Self, SangriaError> {
+        if self.number_of_variables == 0 {
+            return Err(SangriaError::IndexOutOfBounds);
+        }
+
+        let evaluations = self
+            .evaluations
+            .chunks(2)
+            .map(|pair| pair[0] + r * (pair[1] - pair[0]))
+            .collect();
+
+        Ok(Self {
+            evaluations,
+            number_of_variables: self.number_of_variables - 1,
+        })
+    }
+
+    /// Evaluates the extension at `point`, which needs one coordinate per variable, or returns an
+    /// error.
+    pub fn evaluate(&self, point: &[F]) -> Result<F, SangriaError> {
+        if point.len() != self.number_of_variables {
+            return Err(SangriaError::IndexOutOfBounds);
+        }
+
+        let fixed = point
+            .iter()
+            .try_fold(self.clone(), |extension, &coordinate| {
+                extension.fix_variable(coordinate)
+            })?;
+
+        Ok(fixed.evaluations[0])
+    }
+
+    /// Folds `other` into this extension as `self + r * other`, the way witness columns are folded,
+    /// or returns an error if the extensions have different numbers of variables.
+    pub fn fold(&self, other: &Self, r: F) -> Result<Self, SangriaError> {
+        if self.number_of_variables != other.number_of_variables {
+            return Err(SangriaError::IndexOutOfBounds);
+        }
+
+        Ok(self.clone() + &(other.clone() * r))
+    }
+}
+
+impl<F: Field> Add<&Self> for MultilinearExtension<F> {
+    type Output = Self;
+
+    fn add(self, rhs: &Self) -> Self::Output {
+        let evaluations = self
+            .evaluations
+            .iter()
+            .zip(rhs.evaluations.iter())
+            .map(|(&left, &right)| left + right)
+            .collect();
+
+        Self {
+            evaluations,
+            number_of_variables: self.number_of_variables,
+        }
+    }
+}
+
+impl<F: Field> Mul<F> for MultilinearExtension<F> {
+    type Output = Self;
+
+    fn mul(self, rhs: F) -> Self::Output {
+        Self {
+            evaluations: self
+                .evaluations
+                .into_iter()
+                .map(|value| value * rhs)
+                .collect(),
+            number_of_variables: self.number_of_variables,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MultilinearExtension;
+    use crate::SangriaError;
+    use ark_bn254::Fr;
+    use ark_ff::{One, UniformRand, Zero};
+    use ark_std::test_rng;
+
+    #[test]
+    fn extension_matches_column_on_hypercube() {
+        let column: Vec<Fr> = (1..=5u64).map(Fr::from).collect();
+        let extension = MultilinearExtension::from_column(&column);
+
+        assert_eq!(extension.number_of_variables(), 3);
+        for index in 0..8usize {
+            let point: Vec<Fr> = (0..3)
+                .map(|bit| {
+                    if (index >> bit) & 1 == 1 {
+                        Fr::one()
+                    } else {
+                        Fr::zero()
+                    }
+                })
+                .collect();
+            let expected = column.get(index).copied().unwrap_or_else(Fr::zero);
+
+            assert_eq!(extension.evaluate(&point), Ok(expected));
+        }
+    }
+
+    #[test]
+    fn folding_commutes_with_evaluation() {
+        let rng = &mut test_rng();
+        let left: Vec<Fr> = (0..8).map(|_| Fr::rand(rng)).collect();
+        let right: Vec<Fr> = (0..8).map(|_| Fr::rand(rng)).collect();
+        let (left, right) = (
+            MultilinearExtension::from_column(&left),
+            MultilinearExtension::from_column(&right),
+        );
+        let (r, point) = (Fr::rand(rng), [Fr::rand(rng), Fr::rand(rng), Fr::rand(rng)]);
+
+        let folded = left.fold(&right, r).unwrap();
+
+        assert_eq!(
+            folded.evaluate(&point).unwrap(),
+            left.evaluate(&point).unwrap() + r * right.evaluate(&point).unwrap()
+        );
+    }
+
+    #[test]
+    fn mismatched_dimensions_are_rejected() {
+        let extension = MultilinearExtension::from_column(&[Fr::one(), Fr::zero()]);
+        let constant = extension.fix_variable(Fr::one()).unwrap();
+
+        assert_eq!(constant.evaluations(), &[Fr::zero()]);
+        assert_eq!(
+            constant.fix_variable(Fr::one()),
+            Err(SangriaError::IndexOutOfBounds)
+        );
+        assert_eq!(extension.evaluate(&[]), Err(SangriaError::IndexOutOfBounds));
+        assert_eq!(
+            extension.fold(&constant, Fr::one()),
+            Err(SangriaError::IndexOutOfBounds)
+        );
+    }
+}
@@ -0,0 +1,482 @@
+//! Openings of [multilinear KZG commitments](crate::kzg::MultilinearKZG) to data held outside the
+//! circuit (e.g. the blobs of EIP-4844 transactions), checked by a step circuit without any group
+//! operation or pairing in the circuit.
+//!
+//! A step opens several commitments at the same point. The [`multi_opening_gadget`] draws the
+//! challenge batching the openings from a Poseidon hash of the digests of the commitments, the point
+//! and the values, and combines the values with its powers; the [`OpeningBoundStep`] chains the
+//! batched claim into its state. Outside the circuit, the prover opens the same combination of the
+//! committed vectors once per step, and the verifier recomputes the chain from the
+//! [`DeferredOpening`]s of the steps, accumulating their pairing equations, which it checks
+//! together with a single multi-pairing.
+
+use ark_crypto_primitives::sponge::{poseidon::PoseidonConfig, Absorb};
+use ark_ec::{pairing::Pairing, AffineRepr};
+use ark_ff::{One, PrimeField, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+
+use crate::{
+    blob_hash,
+    folding_scheme::blake3_digest,
+    kzg::{CommitKey, EvaluationProof, MultilinearKZG, PairingAccumulator},
+    pedersen::Commitment,
+    piop::eq_extension,
+    poseidon::{linear_combination, multiplication_gate},
+    poseidon_hash, poseidon_hash_gadget,
+    vector_commitment::check_batch_lengths,
+    HomomorphicCommitmentScheme, InnerProductOpening, PLONKStepCircuit, SangriaError, StateWires,
+    StepCircuit, TraceTable,
+};
+
+/// A wire of a trace, given as (column, row).
+type Wire = (usize, usize);
+
+/// Returns the element standing for `commitment` in the circuit: the BLAKE3 digest of its
+/// serialization, hashed into the field.
+pub fn commitment_digest<F, G>(
+    poseidon_constants: &PoseidonConfig<F>,
+    commitment: &Commitment<G>,
+) -> F
+where
+    F: PrimeField + Absorb,
+    G: AffineRepr,
+{
+    blob_hash(poseidon_constants, &blake3_digest(commitment))
+}
+
+/// The openings claimed by a step: the extensions of the vectors committed in the commitments with
+/// the given digests evaluate to `values` at `point`. The default claim, with which the circuit is
+/// laid out, stands for zeros.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OpeningClaim<F: PrimeField> {
+    /// The digests of the opened commitments, see [`commitment_digest`].
+    pub digests: Vec<F>,
+    /// The point the commitments are opened at.
+    pub point: Vec<F>,
+    /// The evaluations of the committed extensions at the point, one per commitment.
+    pub values: Vec<F>,
+}
+
+impl<F: PrimeField + Absorb> OpeningClaim<F> {
+    /// Returns the challenge batching the openings, the Poseidon hash of the digests, the point and
+    /// the values.
+    pub fn challenge(&self, poseidon_constants: &PoseidonConfig<F>) -> F {
+        let inputs: Vec<F> = self
+            .digests
+            .iter()
+            .chain(&self.point)
+            .chain(&self.values)
+            .copied()
+            .collect();
+
+        poseidon_hash(poseidon_constants, &inputs)
+    }
+
+    /// Returns the combination `Σ_j c^j v_j` of the values with the powers of `challenge`.
+    pub fn combined_value(&self, challenge: F) -> F {
+        self.values
+            .iter()
+            .rev()
+            .fold(F::zero(), |combined, &value| combined * challenge + value)
+    }
+
+    /// Extends `chain` with the batched claim: the challenge, the point and the combined value.
+    fn chain(&self, poseidon_constants: &PoseidonConfig<F>, chain: F) -> F {
+        let challenge = self.challenge(poseidon_constants);
+        let mut inputs = vec![chain, challenge];
+        inputs.extend(&self.point);
+        inputs.push(self.combined_value(challenge));
+
+        poseidon_hash(poseidon_constants, &inputs)
+    }
+
+    /// Checks that the claim opens `number_of_commitments` commitments at a point of
+    /// `number_of_variables` coordinates.
+    fn check_shape(
+        &self,
+        number_of_commitments: usize,
+        number_of_variables: usize,
+    ) -> Result<(), SangriaError> {
+        if self.digests.len() != number_of_commitments
+            || self.values.len() != number_of_commitments
+            || self.point.len() != number_of_variables
+        {
+            return Err(SangriaError::InvalidWitness);
+        }
+
+        Ok(())
+    }
+}
+
+/// Batches in-circuit the openings claimed by the `digests`, `point` and `values` wires of `trace`,
+/// as [`OpeningClaim::challenge`] and [`OpeningClaim::combined_value`] do natively, and returns the
+/// wires holding the challenge and the combined value. The circuit trusts the values: they only
+/// hold once the combination of the committed vectors is opened outside the circuit, see
+/// [`DeferredOpening`].
+pub fn multi_opening_gadget<F: PrimeField>(
+    trace: &mut TraceTable<F>,
+    poseidon_constants: &PoseidonConfig<F>,
+    digests: &[Wire],
+    point: &[Wire],
+    values: &[Wire],
+) -> Result<(Wire, Wire), SangriaError> {
+    let inputs: Vec<Wire> = digests.iter().chain(point).chain(values).copied().collect();
+    let challenge = poseidon_hash_gadget(trace, poseidon_constants, &inputs)?;
+
+    // Σ_j c^j v_j by Horner's rule from the last value
+    let Some((&last, rest)) = values.split_last() else {
+        return Ok((challenge, linear_combination(trace, &[], F::zero())?));
+    };
+    let mut combined = last;
+    for &value in rest.iter().rev() {
+        let scaled = multiplication_gate(trace, combined, challenge)?;
+        combined = linear_combination(trace, &[(F::one(), scaled), (F::one(), value)], F::zero())?;
+    }
+
+    Ok((challenge, combined))
+}
+
+/// Wraps a step circuit so that every step opens `number_of_commitments` commitments at a point of
+/// `number_of_variables` coordinates with the [`multi_opening_gadget`]. The state is the inner
+/// state together with a hash chain of the batched claims of the steps so far; the witness is the
+/// inner witness together with the [`OpeningClaim`] of the step.
+#[derive(Clone)]
+pub struct OpeningBoundStep<F: PrimeField, SC> {
+    inner: SC,
+    poseidon_constants: PoseidonConfig<F>,
+    number_of_commitments: usize,
+    number_of_variables: usize,
+}
+
+impl<F: PrimeField, SC> OpeningBoundStep<F, SC> {
+    /// Wraps `inner`, hashing the claims with Poseidon.
+    pub fn new(
+        inner: SC,
+        poseidon_constants: PoseidonConfig<F>,
+        number_of_commitments: usize,
+        number_of_variables: usize,
+    ) -> Self {
+        Self {
+            inner,
+            poseidon_constants,
+            number_of_commitments,
+            number_of_variables,
+        }
+    }
+}
+
+impl<F, SC> StepCircuit<F> for OpeningBoundStep<F, SC>
+where
+    F: PrimeField + Absorb,
+    SC: StepCircuit<F>,
+{
+    type State = (SC::State, F);
+    type Witness = (SC::Witness, OpeningClaim<F>);
+
+    fn execute(
+        &self,
+        (current_state, chain): &Self::State,
+        (witness, claim): &Self::Witness,
+    ) -> Result<Self::State, SangriaError> {
+        claim.check_shape(self.number_of_commitments, self.number_of_variables)?;
+
+        Ok((
+            self.inner.execute(current_state, witness)?,
+            claim.chain(&self.poseidon_constants, *chain),
+        ))
+    }
+
+    fn fixed_tables(&self) -> Vec<Vec<F>> {
+        self.inner.fixed_tables()
+    }
+}
+
+/// A state is encoded as the inner state followed by the chain. The gates of the inner step are
+/// followed by those of the [`multi_opening_gadget`] and those hashing the chain with the batched
+/// claim.
+impl<F, SC> PLONKStepCircuit<F> for OpeningBoundStep<F, SC>
+where
+    F: PrimeField + Absorb,
+    SC: PLONKStepCircuit<F>,
+{
+    fn state_len(&self) -> usize {
+        self.inner.state_len() + 1
+    }
+
+    fn encode_state(&self, (state, chain): &Self::State) -> Vec<F> {
+        let mut encoding = self.inner.encode_state(state);
+        encoding.push(*chain);
+        encoding
+    }
+
+    fn synthesize(
+        &self,
+        trace: &mut TraceTable<F>,
+        current_state: &[F],
+        next_state: &[F],
+        (witness, claim): &Self::Witness,
+    ) -> Result<StateWires, SangriaError> {
+        let inner_len = self.inner.state_len();
+        if current_state.len() != inner_len + 1 || next_state.len() != inner_len + 1 {
+            return Err(SangriaError::InvalidWitness);
+        }
+        let claim = if *claim == OpeningClaim::default() {
+            OpeningClaim {
+                digests: vec![F::zero(); self.number_of_commitments],
+                point: vec![F::zero(); self.number_of_variables],
+                values: vec![F::zero(); self.number_of_commitments],
+            }
+        } else {
+            claim.check_shape(self.number_of_commitments, self.number_of_variables)?;
+            claim.clone()
+        };
+
+        let mut wires = self.inner.synthesize(
+            trace,
+            &current_state[..inner_len],
+            &next_state[..inner_len],
+            witness,
+        )?;
+        let current = trace.hint(&[], |_| Ok(vec![current_state[inner_len]]))?[0];
+        let digests = trace.hint(&[], |_| Ok(claim.digests.clone()))?;
+        let point = trace.hint(&[], |_| Ok(claim.point.clone()))?;
+        let values = trace.hint(&[], |_| Ok(claim.values.clone()))?;
+        let (challenge, combined) =
+            multi_opening_gadget(trace, &self.poseidon_constants, &digests, &point, &values)?;
+        let mut inputs = vec![current, challenge];
+        inputs.extend(&point);
+        inputs.push(combined);
+        let chain = poseidon_hash_gadget(trace, &self.poseidon_constants, &inputs)?;
+
+        wires.current_state.push(current);
+        wires.next_state.push(chain);
+        Ok(wires)
+    }
+}
+
+/// The openings of a step of an [`OpeningBoundStep`] computation, checked outside the circuit: the
+/// commitments, the point, the values and a proof of the evaluation of the combination of the
+/// committed vectors with the powers of the challenge.
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct DeferredOpening<E: Pairing> {
+    commitments: Vec<Commitment<E::G1Affine>>,
+    point: Vec<E::ScalarField>,
+    values: Vec<E::ScalarField>,
+    proof: EvaluationProof<E>,
+}
+
+impl<E: Pairing> DeferredOpening<E>
+where
+    E::ScalarField: Absorb,
+{
+    /// Commits to `vectors` with randomness `hidings` and opens the commitments at `point`,
+    /// returning the opening for the verifier and the claim the step takes as witness. The opening
+    /// reveals the randomness of the combined commitment, so the commitments should not be relied
+    /// on to hide the vectors.
+    pub fn open(
+        poseidon_constants: &PoseidonConfig<E::ScalarField>,
+        commit_key: &CommitKey<E>,
+        vectors: &[Vec<E::ScalarField>],
+        hidings: &[E::ScalarField],
+        point: &[E::ScalarField],
+    ) -> Result<(Self, OpeningClaim<E::ScalarField>), SangriaError> {
+        check_batch_lengths(vectors, hidings, MultilinearKZG::<E>::max_len(commit_key))?;
+        let commitments = MultilinearKZG::<E>::batch_commit(commit_key, vectors, hidings)?;
+        let eq = eq_extension(point);
+        let claim = OpeningClaim {
+            digests: commitments
+                .iter()
+                .map(|commitment| commitment_digest(poseidon_constants, commitment))
+                .collect(),
+            point: point.to_vec(),
+            values: vectors
+                .iter()
+                .map(|x| x.iter().zip(eq.evaluations()).map(|(&x, &eq)| x * eq).sum())
+                .collect(),
+        };
+
+        let challenge = claim.challenge(poseidon_constants);
+        let mut combined =
+            vec![E::ScalarField::zero(); vectors.iter().map(Vec::len).max().unwrap_or(0)];
+        let (mut hiding, mut power) = (E::ScalarField::zero(), E::ScalarField::one());
+        for (x, &r) in vectors.iter().zip(hidings) {
+            for (combined, &x) in combined.iter_mut().zip(x) {
+                *combined += power * x;
+            }
+            hiding += power * r;
+            power *= challenge;
+        }
+        let (_, proof) =
+            MultilinearKZG::<E>::open_evaluation(commit_key, &combined, hiding, point)?;
+
+        Ok((
+            Self {
+                commitments,
+                point: claim.point.clone(),
+                values: claim.values.clone(),
+                proof,
+            },
+            claim,
+        ))
+    }
+
+    /// Returns the claim of the opening, as the step took it.
+    fn claim(
+        &self,
+        poseidon_constants: &PoseidonConfig<E::ScalarField>,
+    ) -> OpeningClaim<E::ScalarField> {
+        OpeningClaim {
+            digests: self
+                .commitments
+                .iter()
+                .map(|commitment| commitment_digest(poseidon_constants, commitment))
+                .collect(),
+            point: self.point.clone(),
+            values: self.values.clone(),
+        }
+    }
+}
+
+/// Checks the openings of the steps of an [`OpeningBoundStep`] computation whose chain went from
+/// `origin_chain` to `chain`, given in the order of the steps: recomputes the chain from their
+/// claims, defers the pairing equations of their combined openings and checks all of them with a
+/// single multi-pairing. Returns [`SangriaError::InvalidProof`] if the chain or an opening does not
+/// hold. The chain is only bound to the computation once its IVC proof has been verified.
+pub fn verify_deferred_openings<E>(
+    poseidon_constants: &PoseidonConfig<E::ScalarField>,
+    commit_key: &CommitKey<E>,
+    origin_chain: E::ScalarField,
+    chain: E::ScalarField,
+    openings: &[DeferredOpening<E>],
+) -> Result<(), SangriaError>
+where
+    E: Pairing,
+    E::ScalarField: Absorb,
+{
+    let mut accumulator = PairingAccumulator::default();
+    let mut current = origin_chain;
+    for opening in openings {
+        if opening.values.len() != opening.commitments.len() {
+            return Err(SangriaError::InvalidProof);
+        }
+        let claim = opening.claim(poseidon_constants);
+        let challenge = claim.challenge(poseidon_constants);
+        let combined_commitment = opening
+            .commitments
+            .iter()
+            .rev()
+            .fold(Commitment::zero(), |combined, &commitment| {
+                combined * challenge + commitment
+            });
+        MultilinearKZG::<E>::verify_evaluation_deferred(
+            commit_key,
+            &combined_commitment,
+            &opening.point,
+            claim.combined_value(challenge),
+            &opening.proof,
+            &mut accumulator,
+        )?;
+        current = claim.chain(poseidon_constants, current);
+    }
+    if current != chain {
+        return Err(SangriaError::InvalidProof);
+    }
+
+    MultilinearKZG::<E>::decide(accumulator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{verify_deferred_openings, DeferredOpening, OpeningBoundStep};
+    use crate::{
+        kzg::MultilinearKZG,
+        utils::{
+            circuits::Counter, inner_product::InnerProductConfig,
+            poseidon::poseidon_parameters_for_test, rand::sample_vector,
+        },
+        AugmentedCircuit, HomomorphicCommitmentScheme, Sangria, SangriaConfig, SangriaError,
+        SetupInfo,
+    };
+    use ark_bn254::{Bn254, Fr};
+    use ark_ff::{One, Zero};
+    use ark_std::{test_rng, UniformRand};
+
+    #[test]
+    fn openings_are_bound_in_circuit_and_checked_together() {
+        type Scheme = Sangria<InnerProductConfig>;
+
+        let rng = &mut test_rng();
+        let params = poseidon_parameters_for_test::<Fr>();
+        let commit_key = MultilinearKZG::<Bn254>::setup(rng, 8);
+        let step = OpeningBoundStep::new(Counter, params.clone(), 2, 3);
+        let circuit = AugmentedCircuit::new(step.clone())
+            .batched_circuit()
+            .unwrap();
+        let info = SetupInfo {
+            number_of_public_inputs: 4,
+            number_of_gates: circuit.number_of_rows() - 5,
+            config: SangriaConfig::default(),
+            poseidon_constants: params.clone(),
+        };
+        let public_parameters = Scheme::setup(&info, rng).unwrap();
+        let (pk, vk) = Scheme::encode(&public_parameters, &step, rng).unwrap();
+
+        let origin = (Fr::zero(), Fr::zero());
+        let (mut state, mut proof) = (origin, None);
+        let mut openings = Vec::new();
+        for _ in 0..2 {
+            let vectors = vec![sample_vector(rng, 8), sample_vector(rng, 5)];
+            let hidings = sample_vector(rng, 2);
+            let point = sample_vector(rng, 3);
+            let (opening, claim) =
+                DeferredOpening::open(&params, &commit_key, &vectors, &hidings, &point).unwrap();
+            openings.push(opening);
+            (state, proof) =
+                Scheme::prove_step(&pk, &origin, state, proof, &(Fr::one(), claim), rng)
+                    .map(|(state, proof)| (state, Some(proof)))
+                    .unwrap();
+        }
+        assert_eq!(Scheme::verify(&vk, &origin, state, proof), Ok(()));
+
+        let verify = |openings: &[DeferredOpening<Bn254>]| {
+            verify_deferred_openings(&params, &commit_key, origin.1, state.1, openings)
+        };
+        assert_eq!(verify(&openings), Ok(()));
+
+        // the chain binds the values and the order of the openings
+        let mut forged = openings.clone();
+        forged[0].values[1] += Fr::one();
+        assert_eq!(verify(&forged), Err(SangriaError::InvalidProof));
+        let mut reordered = openings.clone();
+        reordered.swap(0, 1);
+        assert_eq!(verify(&reordered), Err(SangriaError::InvalidProof));
+        assert_eq!(verify(&openings[..1]), Err(SangriaError::InvalidProof));
+
+        // the pairings bind the values to the commitments
+        let mut forged = openings.clone();
+        forged[1].proof = openings[0].proof.clone();
+        assert_eq!(verify(&forged), Err(SangriaError::InvalidProof));
+    }
+
+    #[test]
+    fn claims_must_have_the_shape_of_the_step() {
+        let rng = &mut test_rng();
+        let params = poseidon_parameters_for_test::<Fr>();
+        let commit_key = MultilinearKZG::<Bn254>::setup(rng, 8);
+        let step = OpeningBoundStep::new(Counter, params.clone(), 2, 3);
+
+        let point: Vec<Fr> = (0..3).map(|_| Fr::rand(rng)).collect();
+        let (_, claim) = DeferredOpening::open(
+            &params,
+            &commit_key,
+            &[sample_vector(rng, 8)],
+            &[Fr::one()],
+            &point,
+        )
+        .unwrap();
+        assert_eq!(
+            Sangria::replay(&step, (Fr::zero(), Fr::zero()), &[(Fr::one(), claim)]),
+            Err(SangriaError::InvalidWitness)
+        );
+    }
+}
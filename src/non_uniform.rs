@@ -0,0 +1,407 @@
+use ark_crypto_primitives::sponge::Absorb;
+use ark_ff::PrimeField;
+use ark_serialize::CanonicalSerialize;
+use ark_std::rand::Rng;
+
+use crate::{
+    folding_scheme::{self, FoldingCommitmentConfig, SetupInfo},
+    ivc::{
+        check_step_chain, commit_step, decide, fold_step, fold_steps, nifs_verifier, ProverKey,
+        VerifierKey,
+    },
+    BatchedIVCProof, NonUniformIVC, PLONKStepCircuit, Sangria, SangriaError,
+};
+
+/// The prover key of a non-uniform Sangria chain: the Sangria prover key of every step circuit,
+/// all sharing the same public parameters.
+pub struct NonUniformProverKey<F: PrimeField, Comm: FoldingCommitmentConfig<F>, SC> {
+    step_keys: Vec<ProverKey<F, Comm, SC>>,
+}
+
+/// The verifier key of a non-uniform Sangria chain: the Sangria verifier key of every step
+/// circuit, all sharing the same public parameters.
+pub struct NonUniformVerifierKey<F: PrimeField, Comm: FoldingCommitmentConfig<F>, SC> {
+    step_keys: Vec<VerifierKey<F, Comm, SC>>,
+}
+
+/// A non-uniform IVC proof: the index of the step circuit run by every step and, for every step
+/// circuit, the [`BatchedIVCProof`] folding the steps it ran into its own running instance, or `None` if it
+/// never ran.
+///
+/// WARNING: like [`BatchedIVCProof`], it grows with the number of steps and is not zero-knowledge.
+pub struct NonUniformIVCProof<F: PrimeField, Comm: FoldingCommitmentConfig<F>> {
+    circuit_indices: Vec<usize>,
+    circuit_proofs: Vec<Option<BatchedIVCProof<F, Comm>>>,
+}
+
+impl<F: PrimeField, Comm: FoldingCommitmentConfig<F>> NonUniformIVCProof<F, Comm> {
+    /// Returns the number of steps proved.
+    pub fn number_of_steps(&self) -> usize {
+        self.circuit_indices.len()
+    }
+
+    /// Returns the size of the proof in bytes, the sum of the sizes of the proofs of the step
+    /// circuits.
+    pub fn compressed_size(&self) -> usize {
+        self.circuit_proofs
+            .iter()
+            .flatten()
+            .map(CanonicalSerialize::compressed_size)
+            .sum()
+    }
+
+    /// Returns the index of the step circuit run by every step, in order.
+    pub fn circuit_indices(&self) -> &[usize] {
+        &self.circuit_indices
+    }
+
+    /// Returns the state the last step ends in, given the number of elements encoding a state.
+    fn last_state(&self, state_len: usize) -> Result<Vec<F>, SangriaError> {
+        self.circuit_indices
+            .last()
+            .and_then(|&index| self.circuit_proofs.get(index)?.as_ref())
+            .ok_or(SangriaError::InvalidProof)?
+            .last_state(state_len)
+    }
+}
+
+impl<F, Comm> Clone for NonUniformIVCProof<F, Comm>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    fn clone(&self) -> Self {
+        Self {
+            circuit_indices: self.circuit_indices.clone(),
+            circuit_proofs: self.circuit_proofs.clone(),
+        }
+    }
+}
+
+impl<F, Comm, SC> NonUniformIVC<F, SC> for Sangria<Comm>
+where
+    F: PrimeField + Absorb,
+    Comm: FoldingCommitmentConfig<F>,
+    SC: PLONKStepCircuit<F> + Clone,
+    SC::Witness: Default,
+{
+    type SetupInfo = SetupInfo<F>;
+    type PublicParameters = folding_scheme::PublicParameters<F, Comm>;
+    type ProverKey = NonUniformProverKey<F, Comm, SC>;
+    type VerifierKey = NonUniformVerifierKey<F, Comm, SC>;
+    type Proof = NonUniformIVCProof<F, Comm>;
+
+    /// Runs the setup of the folding scheme, whose gates must fit the largest step circuit.
+    fn setup<R: Rng>(
+        info: &SetupInfo<F>,
+        rng: &mut R,
+    ) -> Result<Self::PublicParameters, SangriaError> {
+        Self::setup(info, rng)
+    }
+
+    /// Encodes the augmented circuit of every step circuit like [`Sangria::encode`]. The step circuits
+    /// must encode states alike.
+    fn encode<R: Rng>(
+        public_parameters: &Self::PublicParameters,
+        step_circuits: &[SC],
+        rng: &mut R,
+    ) -> Result<(Self::ProverKey, Self::VerifierKey), SangriaError> {
+        if step_circuits.is_empty() {
+            return Err(SangriaError::InvalidConfig(
+                "a non-uniform chain needs at least one step circuit",
+            ));
+        }
+
+        let (prover_keys, verifier_keys) = step_circuits
+            .iter()
+            .map(|step_circuit| Self::encode(public_parameters, step_circuit, rng))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .unzip();
+
+        Ok((
+            NonUniformProverKey {
+                step_keys: prover_keys,
+            },
+            NonUniformVerifierKey {
+                step_keys: verifier_keys,
+            },
+        ))
+    }
+
+    /// Executes the step with the selected circuit, commits to the trace of its augmented circuit
+    /// and folds it into the running instance of that circuit, which the step starts if it is the
+    /// first one of the circuit. In the base case (no proof yet) the current state must be the
+    /// origin state.
+    fn prove_step<R: Rng>(
+        prover_key: &Self::ProverKey,
+        origin_state: &SC::State,
+        current_state: SC::State,
+        current_proof: Option<Self::Proof>,
+        circuit_index: usize,
+        current_witness: &SC::Witness,
+        rng: &mut R,
+    ) -> Result<(SC::State, Self::Proof), SangriaError> {
+        let step_key = prover_key
+            .step_keys
+            .get(circuit_index)
+            .ok_or(SangriaError::IndexOutOfBounds)?;
+        let step_circuit = step_key.augmented_circuit.step_circuit();
+        let current = step_circuit.encode_state(&current_state);
+
+        let mut proof = match current_proof {
+            None if step_circuit.encode_state(origin_state) != current => {
+                return Err(SangriaError::PublicInputMismatch);
+            }
+            None => NonUniformIVCProof {
+                circuit_indices: Vec::new(),
+                circuit_proofs: vec![None; prover_key.step_keys.len()],
+            },
+            Some(proof) if proof.last_state(current.len())? != current => {
+                return Err(SangriaError::PublicInputMismatch);
+            }
+            Some(proof) => proof,
+        };
+        let circuit_proof = proof
+            .circuit_proofs
+            .get_mut(circuit_index)
+            .ok_or(SangriaError::InvalidProof)?;
+
+        let (next_state, step_instance, step_witness) =
+            commit_step(step_key, &current_state, current_witness, rng)?;
+        *circuit_proof = Some(fold_step(
+            step_key,
+            circuit_proof.take(),
+            step_instance,
+            step_witness,
+            rng,
+        )?);
+        proof.circuit_indices.push(circuit_index);
+        #[cfg(feature = "usage-tracking")]
+        step_key.usage.record(proof.compressed_size());
+
+        Ok((next_state, proof))
+    }
+
+    /// Checks that the step instances of all circuits, taken in the order of the steps, are fresh
+    /// and chain from the origin state to the current state. Then re-folds the step instances of
+    /// every circuit into its running instance and checks that its running witness satisfies it.
+    /// Without a proof, the current state must be the origin state.
+    fn verify(
+        verifier_key: &Self::VerifierKey,
+        origin_state: &SC::State,
+        current_state: SC::State,
+        current_proof: Option<Self::Proof>,
+    ) -> Result<(), SangriaError> {
+        let step_circuit = verifier_key.step_keys[0].augmented_circuit.step_circuit();
+        let origin = step_circuit.encode_state(origin_state);
+        let current = step_circuit.encode_state(&current_state);
+
+        let proof = match current_proof {
+            None if origin == current => return Ok(()),
+            None => return Err(SangriaError::InvalidProof),
+            Some(proof) => proof,
+        };
+        if proof.circuit_proofs.len() != verifier_key.step_keys.len() {
+            return Err(SangriaError::InvalidProof);
+        }
+
+        // interleave the step instances of the circuits in the order of the steps
+        let mut steps_taken = vec![0; proof.circuit_proofs.len()];
+        let step_instances = proof
+            .circuit_indices
+            .iter()
+            .map(|&index| {
+                let circuit_proof = proof.circuit_proofs.get(index)?.as_ref()?;
+                let instance = circuit_proof.step_instances.get(steps_taken[index])?;
+                steps_taken[index] += 1;
+                Some(instance)
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or(SangriaError::InvalidProof)?;
+        if proof
+            .circuit_proofs
+            .iter()
+            .zip(&steps_taken)
+            .any(|(circuit_proof, &steps)| {
+                circuit_proof
+                    .as_ref()
+                    .map_or(0, BatchedIVCProof::number_of_steps)
+                    != steps
+            })
+        {
+            return Err(SangriaError::InvalidProof);
+        }
+        check_step_chain(origin, &current, step_instances)?;
+
+        for (step_key, circuit_proof) in verifier_key.step_keys.iter().zip(&proof.circuit_proofs) {
+            let Some(circuit_proof) = circuit_proof else {
+                continue;
+            };
+            circuit_proof.validate(step_key)?;
+            let running_instance = fold_steps(
+                &circuit_proof.step_instances,
+                &circuit_proof.cross_term_commitments,
+                &circuit_proof.fold_shape,
+                nifs_verifier(step_key),
+            )?;
+            decide(step_key, &running_instance, circuit_proof)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        utils::{
+            circuits::Counter, inner_product::InnerProductConfig,
+            poseidon::poseidon_parameters_for_test,
+        },
+        NonUniformIVC, PLONKStepCircuit, RowTag, Sangria, SangriaConfig, SangriaError, SetupInfo,
+        StateWires, StepCircuit, TraceTable, LEFT_SELECTOR_INDEX, OUTPUT_SELECTOR_INDEX,
+    };
+    use ark_bn254::Fr;
+    use ark_ff::{Field, One, Zero};
+    use ark_std::test_rng;
+
+    type Scheme = Sangria<InnerProductConfig>;
+
+    /// The instructions of a toy program: adding the witness to the state, or doubling the state.
+    #[derive(Clone)]
+    enum Instruction {
+        Add,
+        Double,
+    }
+
+    impl StepCircuit<Fr> for Instruction {
+        type State = Fr;
+        type Witness = Fr;
+
+        fn execute(&self, current_state: &Fr, witness: &Fr) -> Result<Fr, SangriaError> {
+            match self {
+                Self::Add => Counter.execute(current_state, witness),
+                Self::Double => Ok(current_state.double()),
+            }
+        }
+    }
+
+    impl PLONKStepCircuit<Fr> for Instruction {
+        fn state_len(&self) -> usize {
+            1
+        }
+
+        fn encode_state(&self, state: &Fr) -> Vec<Fr> {
+            vec![*state]
+        }
+
+        fn synthesize(
+            &self,
+            trace: &mut TraceTable<Fr>,
+            current_state: &[Fr],
+            next_state: &[Fr],
+            witness: &Fr,
+        ) -> Result<StateWires, SangriaError> {
+            if let Self::Add = self {
+                return Counter.synthesize(trace, current_state, next_state, witness);
+            }
+
+            let row = trace.number_of_rows();
+            let mut doubling = [Fr::zero(); 6];
+            doubling[LEFT_SELECTOR_INDEX] = Fr::from(2u64);
+            doubling[OUTPUT_SELECTOR_INDEX] = -Fr::one();
+            trace.push_row(
+                RowTag::Gate(doubling),
+                [current_state[0], Fr::zero(), next_state[0]],
+            );
+
+            Ok(StateWires {
+                current_state: vec![(0, row)],
+                next_state: vec![(2, row)],
+            })
+        }
+    }
+
+    #[test]
+    fn non_uniform_chains_verify() {
+        let rng = &mut test_rng();
+        let info = SetupInfo {
+            number_of_public_inputs: 2,
+            number_of_gates: 2,
+            config: SangriaConfig::default(),
+            poseidon_constants: poseidon_parameters_for_test(),
+        };
+        let pp = <Scheme as NonUniformIVC<Fr, Instruction>>::setup(&info, rng).unwrap();
+        let (pk, vk) = <Scheme as NonUniformIVC<Fr, Instruction>>::encode(
+            &pp,
+            &[Instruction::Add, Instruction::Double],
+            rng,
+        )
+        .unwrap();
+
+        // ((1 + 2) * 2 + 3) * 2 * 2 = 36
+        let origin = Fr::one();
+        let (mut state, mut proof) = (origin, None);
+        for (index, witness) in [(0, 2u64), (1, 0), (0, 3), (1, 0), (1, 0)] {
+            let step = <Scheme as NonUniformIVC<Fr, Instruction>>::prove_step(
+                &pk,
+                &origin,
+                state,
+                proof,
+                index,
+                &Fr::from(witness),
+                rng,
+            )
+            .unwrap();
+            (state, proof) = (step.0, Some(step.1));
+        }
+        assert_eq!(state, Fr::from(36u64));
+        let proof = proof.unwrap();
+        assert_eq!(proof.circuit_indices(), [0, 1, 0, 1, 1]);
+        assert_eq!(
+            <Scheme as NonUniformIVC<Fr, Instruction>>::verify(
+                &vk,
+                &origin,
+                state,
+                Some(proof.clone())
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            <Scheme as NonUniformIVC<Fr, Instruction>>::verify(
+                &vk,
+                &origin,
+                Fr::from(35u64),
+                Some(proof.clone())
+            ),
+            Err(SangriaError::InvalidProof)
+        );
+
+        // the steps must be replayed in the recorded order
+        let mut reordered = proof.clone();
+        reordered.circuit_indices.swap(0, 1);
+        assert_eq!(
+            <Scheme as NonUniformIVC<Fr, Instruction>>::verify(
+                &vk,
+                &origin,
+                state,
+                Some(reordered)
+            ),
+            Err(SangriaError::InvalidProof)
+        );
+        assert_eq!(
+            <Scheme as NonUniformIVC<Fr, Instruction>>::prove_step(
+                &pk,
+                &origin,
+                state,
+                Some(proof),
+                2,
+                &Fr::one(),
+                rng
+            )
+            .err(),
+            Some(SangriaError::IndexOutOfBounds)
+        );
+    }
+}
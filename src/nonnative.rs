@@ -0,0 +1,525 @@
+use ark_ff::{BigInteger, PrimeField};
+use ark_std::marker::PhantomData;
+
+use crate::{
+    fe_to_bytes_canonical, hints,
+    poseidon::{linear_combination, multiplication_gate},
+    trace::NUMBER_OF_SELECTORS,
+    RowTag, SangriaError, TraceTable, LEFT_SELECTOR_INDEX, MULTIPLICATION_SELECTOR_INDEX,
+    OUTPUT_SELECTOR_INDEX, RIGHT_SELECTOR_INDEX,
+};
+
+/// A wire of a trace, given as (column, row).
+type Wire = (usize, usize);
+
+/// The number of bits of a limb of an emulated element.
+pub const LIMB_BITS: usize = 32;
+
+/// The maximum number of bits of the integers reduced modulo the emulated modulus, position by
+/// position: a position adds up the products of two limbs, so the limbs of the operands of a
+/// multiplication may have up to about 38 bits, i.e. the sums of up to 64 reduced elements.
+const POSITION_BITS: usize = 80;
+
+/// The number of bits of the range checked carries, which are shifted by `2^(CARRY_BITS - 1)` to
+/// be non-negative.
+const CARRY_BITS: usize = 57;
+
+/// The minimum size of the native field, in which no relation of the gadgets may wrap around.
+const MIN_NATIVE_BITS: u32 = 128;
+
+/// An element of the field `T` emulated in the trace of a circuit over another field, as the
+/// folding verifier needs to compute over the base field of the commitment curve. The element is
+/// the integer `sum_i limb_i 2^(32 i)` of its limb wires, taken modulo the modulus of `T`. Every limb
+/// is known to have at most `limb_bits` bits: allocated and reduced elements have range checked
+/// limbs of [`LIMB_BITS`] bits, and sums grow them by one bit. Reduced elements are only known to be
+/// below `2^(32 n)` for `n` limbs, not to be canonical.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NonNativeElement<T: PrimeField> {
+    limbs: Vec<Wire>,
+    limb_bits: usize,
+    _target: PhantomData<T>,
+}
+
+impl<T: PrimeField> NonNativeElement<T> {
+    /// Returns the number of limbs of the elements of `T`.
+    pub fn number_of_limbs() -> usize {
+        (T::MODULUS_BIT_SIZE as usize).div_ceil(LIMB_BITS)
+    }
+
+    /// Returns the wires holding the limbs, least significant first.
+    pub fn limbs(&self) -> &[Wire] {
+        &self.limbs
+    }
+
+    /// Allocates `value` in `trace` as range checked limbs.
+    pub fn allocate<F: PrimeField>(
+        trace: &mut TraceTable<F>,
+        value: T,
+    ) -> Result<Self, SangriaError> {
+        check_native_field::<F>()?;
+        let limbs = to_limbs(&fe_to_bytes_canonical(&value));
+
+        Ok(Self {
+            limbs: (0..Self::number_of_limbs())
+                .map(|index| {
+                    range_checked(
+                        trace,
+                        limbs.get(index).copied().unwrap_or(0).into(),
+                        LIMB_BITS,
+                    )
+                })
+                .collect(),
+            limb_bits: LIMB_BITS,
+            _target: PhantomData,
+        })
+    }
+
+    /// Returns the element of `T` held by the limb wires of `trace`.
+    pub fn value<F: PrimeField>(&self, trace: &TraceTable<F>) -> Result<T, SangriaError> {
+        let shift = T::from(1u64 << LIMB_BITS);
+        self.limbs.iter().rev().try_fold(T::zero(), |value, &limb| {
+            Ok(value * shift + T::from(small_value(trace, limb)?))
+        })
+    }
+
+    /// Adds the limbs of `self` and `other` with one addition gate per limb, without reducing.
+    pub fn add<F: PrimeField>(
+        &self,
+        trace: &mut TraceTable<F>,
+        other: &Self,
+    ) -> Result<Self, SangriaError> {
+        let limb_bits = self.limb_bits.max(other.limb_bits) + 1;
+        if limb_bits > POSITION_BITS {
+            return Err(SangriaError::InvalidTrace(
+                "the sum is too large to be reduced, reduce the operands first",
+            ));
+        }
+
+        Ok(Self {
+            limbs: self
+                .limbs
+                .iter()
+                .zip(&other.limbs)
+                .map(|(&left, &right)| {
+                    linear_combination(trace, &[(F::one(), left), (F::one(), right)], F::zero())
+                })
+                .collect::<Result<_, _>>()?,
+            limb_bits,
+            _target: PhantomData,
+        })
+    }
+
+    /// Multiplies `self` by `other` and reduces the product modulo the modulus of `T`. The limbs of
+    /// the product are computed with a multiplication gate per pair of limbs, then reduced with
+    /// [`Self::reduce`].
+    pub fn mul<F: PrimeField>(
+        &self,
+        trace: &mut TraceTable<F>,
+        other: &Self,
+    ) -> Result<Self, SangriaError> {
+        let terms = self.limbs.len().min(other.limbs.len());
+        let position_bits =
+            self.limb_bits + other.limb_bits + terms.next_power_of_two().ilog2() as usize;
+        if position_bits > POSITION_BITS {
+            return Err(SangriaError::InvalidTrace(
+                "the operands are too large to be multiplied, reduce them first",
+            ));
+        }
+
+        let mut products = vec![Vec::new(); self.limbs.len() + other.limbs.len() - 1];
+        for (i, &left) in self.limbs.iter().enumerate() {
+            for (j, &right) in other.limbs.iter().enumerate() {
+                products[i + j].push((F::one(), multiplication_gate(trace, left, right)?));
+            }
+        }
+        let positions = products
+            .iter()
+            .map(|terms| linear_combination(trace, terms, F::zero()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        reduce_positions(trace, &positions, position_bits)
+    }
+
+    /// Reduces `self` modulo the modulus of `T` into range checked limbs. The quotient `q` and the
+    /// remainder `r` are allocated as range checked limbs, and `self = q p + r` is checked over the
+    /// integers, position by position, by propagating range checked carries.
+    pub fn reduce<F: PrimeField>(&self, trace: &mut TraceTable<F>) -> Result<Self, SangriaError> {
+        reduce_positions(trace, &self.limbs, self.limb_bits)
+    }
+}
+
+/// Reduces the integer `sum_i positions_i 2^(32 i)` modulo the modulus `p` of `T`, given that every
+/// position has at most `position_bits` bits. The positions of `N - q p - r` are computed with
+/// linear combinations and each is checked to add up with the previous carry to `2^32` times the
+/// next one. The carries are small enough for no check to wrap around in the native field, so they
+/// prove `N = q p + r` over the integers.
+fn reduce_positions<F: PrimeField, T: PrimeField>(
+    trace: &mut TraceTable<F>,
+    positions: &[Wire],
+    position_bits: usize,
+) -> Result<NonNativeElement<T>, SangriaError> {
+    check_native_field::<F>()?;
+    let modulus = to_limbs(&T::MODULUS.to_bytes_le());
+    let number_of_limbs = NonNativeElement::<T>::number_of_limbs();
+
+    let quotient_bits = (LIMB_BITS * (positions.len() - 1) + position_bits + 2)
+        .saturating_sub(T::MODULUS_BIT_SIZE as usize);
+    let quotient_limbs = quotient_bits.div_ceil(LIMB_BITS).max(1);
+    let advice = trace.hint(positions, |values| {
+        let values = values
+            .iter()
+            .map(|&value| to_u128(value))
+            .collect::<Result<Vec<_>, _>>()?;
+        let (quotient, remainder) = div_rem(&from_positions(&values), &modulus);
+        if quotient[quotient_limbs.min(quotient.len())..]
+            .iter()
+            .any(|&limb| limb != 0)
+        {
+            return Err(SangriaError::InvalidTrace("a position exceeds its bound"));
+        }
+
+        let limb = |limbs: &[u32], index| F::from(limbs.get(index).copied().unwrap_or(0));
+        Ok((0..quotient_limbs)
+            .map(|index| limb(&quotient, index))
+            .chain((0..number_of_limbs).map(|index| limb(&remainder, index)))
+            .collect())
+    })?;
+    let (quotient_wires, remainder_wires) = advice.split_at(quotient_limbs);
+    for &wire in &advice {
+        range_check(trace, wire, LIMB_BITS)?;
+    }
+
+    let position_values = positions
+        .iter()
+        .map(|&position| small_value(trace, position))
+        .collect::<Result<Vec<_>, _>>()?;
+    let limb = |wire: Wire| small_value(trace, wire).map(|value| value as i128);
+    let quotient = quotient_wires
+        .iter()
+        .map(|&wire| limb(wire))
+        .collect::<Result<Vec<_>, _>>()?;
+    let remainder = remainder_wires
+        .iter()
+        .map(|&wire| limb(wire))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let carry_offset = 1i128 << (CARRY_BITS - 1);
+    let shift = F::from(1u64 << LIMB_BITS);
+    let number_of_positions = positions
+        .len()
+        .max(quotient_limbs + modulus.len() - 1)
+        .max(number_of_limbs);
+    let (mut carry, mut carry_wire) = (0i128, None);
+    for index in 0..number_of_positions {
+        // the position of N - q p - r and the incoming carry
+        let mut terms = Vec::new();
+        let mut difference = carry;
+        if let Some((&position, &value)) = positions.get(index).zip(position_values.get(index)) {
+            terms.push((F::one(), position));
+            difference += value as i128;
+        }
+        for (j, &quotient_wire) in quotient_wires.iter().enumerate() {
+            if let Some(k) = index.checked_sub(j).filter(|&k| k < modulus.len()) {
+                terms.push((-F::from(modulus[k]), quotient_wire));
+                difference -= quotient[j] * modulus[k] as i128;
+            }
+        }
+        if let Some((&remainder_wire, &value)) =
+            remainder_wires.get(index).zip(remainder.get(index))
+        {
+            terms.push((-F::one(), remainder_wire));
+            difference -= value;
+        }
+        let mut constant = F::zero();
+        if let Some(wire) = carry_wire {
+            terms.push((F::one(), wire));
+            constant -= F::from(carry_offset as u128);
+        }
+
+        if index + 1 < number_of_positions {
+            if difference % (1 << LIMB_BITS) != 0 {
+                return Err(SangriaError::InvalidTrace(
+                    "the quotient and remainder do not reduce the integer",
+                ));
+            }
+            carry = difference >> LIMB_BITS;
+            let wire = range_checked(trace, (carry + carry_offset) as u128, CARRY_BITS);
+            terms.push((-shift, wire));
+            constant += shift * F::from(carry_offset as u128);
+            carry_wire = Some(wire);
+        }
+        let sum = linear_combination(trace, &terms, constant)?;
+        assert_zero(trace, sum);
+    }
+
+    Ok(NonNativeElement {
+        limbs: remainder_wires.to_vec(),
+        limb_bits: LIMB_BITS,
+        _target: PhantomData,
+    })
+}
+
+/// Returns an error unless the native field is large enough for the relations of the gadgets not to
+/// wrap around.
+fn check_native_field<F: PrimeField>() -> Result<(), SangriaError> {
+    if F::MODULUS_BIT_SIZE < MIN_NATIVE_BITS {
+        return Err(SangriaError::InvalidTrace(
+            "the native field is too small to emulate another field",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Appends the gates decomposing `value` into `bits` bits and returns the wire recomposing them,
+/// which holds `value`.
+fn range_checked<F: PrimeField>(trace: &mut TraceTable<F>, value: u128, bits: usize) -> Wire {
+    decompose(trace, F::from(value), bits).1
+}
+
+/// Appends the gates decomposing the value of `wire` into `bits` bits, which constrains it to have at
+/// most `bits` bits.
+pub(crate) fn range_check<F: PrimeField>(
+    trace: &mut TraceTable<F>,
+    wire: Wire,
+    bits: usize,
+) -> Result<(), SangriaError> {
+    let value = trace.wire(wire.0, wire.1)?;
+    let recomposed = decompose(trace, value, bits).1;
+    trace.copy(recomposed, wire);
+
+    Ok(())
+}
+
+/// Appends the gates decomposing `value` into `bits` bits, most significant first, and returns the
+/// wires of the bits, most significant first, and the wire recomposing them, which holds `value`
+/// if it has at most `bits` bits. Every bit takes a booleanity gate `b^2 - b = 0` and a gate
+/// doubling the running sum and adding the bit.
+pub(crate) fn decompose<F: PrimeField>(
+    trace: &mut TraceTable<F>,
+    value: F,
+    bits: usize,
+) -> (Vec<Wire>, Wire) {
+    let mut booleanity = [F::zero(); NUMBER_OF_SELECTORS];
+    booleanity[MULTIPLICATION_SELECTOR_INDEX] = F::one();
+    booleanity[LEFT_SELECTOR_INDEX] = -F::one();
+    let mut doubling = [F::zero(); NUMBER_OF_SELECTORS];
+    doubling[LEFT_SELECTOR_INDEX] = F::from(2u64);
+    doubling[RIGHT_SELECTOR_INDEX] = F::one();
+    doubling[OUTPUT_SELECTOR_INDEX] = -F::one();
+
+    let bit_values = hints::bits(bits)(&[value]).expect("the value is the single input");
+    let mut bit_wires = Vec::with_capacity(bits);
+    let mut sum: Option<(Wire, F)> = None;
+    for bit in bit_values {
+        let bit_row = trace.number_of_rows();
+        trace.push_row(RowTag::Gate(booleanity), [bit, bit, F::zero()]);
+        trace.copy((0, bit_row), (1, bit_row));
+        bit_wires.push((0, bit_row));
+
+        sum = Some(match sum {
+            None => ((0, bit_row), bit),
+            Some((wire, partial)) => {
+                let row = trace.number_of_rows();
+                let doubled = partial.double() + bit;
+                trace.push_row(RowTag::Gate(doubling), [partial, bit, doubled]);
+                trace.copy(wire, (0, row));
+                trace.copy((0, bit_row), (1, row));
+                ((2, row), doubled)
+            }
+        });
+    }
+
+    (
+        bit_wires,
+        sum.expect("decompositions have at least one bit").0,
+    )
+}
+
+/// Appends a gate constraining the value of `wire` to be zero.
+pub(crate) fn assert_zero<F: PrimeField>(trace: &mut TraceTable<F>, wire: Wire) {
+    let mut selectors = [F::zero(); NUMBER_OF_SELECTORS];
+    selectors[LEFT_SELECTOR_INDEX] = F::one();
+    let row = trace.number_of_rows();
+    trace.push_row(RowTag::Gate(selectors), [F::zero(); 3]);
+    trace.copy(wire, (0, row));
+}
+
+/// Returns the value of `wire` as an integer, or an error if it does not fit 128 bits.
+fn small_value<F: PrimeField>(trace: &TraceTable<F>, wire: Wire) -> Result<u128, SangriaError> {
+    to_u128(trace.wire(wire.0, wire.1)?)
+}
+
+/// Returns `value` as an integer, or an error if it does not fit 128 bits.
+fn to_u128<F: PrimeField>(value: F) -> Result<u128, SangriaError> {
+    let value = value.into_bigint();
+    let limbs = value.as_ref();
+    if limbs[2..].iter().any(|&limb| limb != 0) {
+        return Err(SangriaError::InvalidTrace("a limb exceeds its bound"));
+    }
+
+    Ok(u128::from(limbs[0]) | u128::from(limbs[1]) << 64)
+}
+
+/// Splits little-endian bytes into 32-bit limbs, least significant first.
+pub(crate) fn to_limbs(bytes: &[u8]) -> Vec<u32> {
+    bytes
+        .chunks(4)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .rev()
+                .fold(0, |limb, &byte| (limb << 8) | u32::from(byte))
+        })
+        .collect()
+}
+
+/// Returns the field element of the integer of 32-bit limbs, least significant first, reduced modulo
+/// the modulus of `F`.
+pub(crate) fn from_limbs<F: PrimeField>(limbs: &[u32]) -> F {
+    let bytes: Vec<u8> = limbs.iter().flat_map(|limb| limb.to_le_bytes()).collect();
+    F::from_le_bytes_mod_order(&bytes)
+}
+
+/// Returns the limbs of `sum_i positions_i 2^(32 i)`.
+fn from_positions(positions: &[u128]) -> Vec<u32> {
+    let mut limbs = vec![0u32; positions.len() + 4];
+    for (index, &position) in positions.iter().enumerate() {
+        let mut carry = position;
+        for limb in limbs[index..].iter_mut() {
+            if carry == 0 {
+                break;
+            }
+            let sum = u128::from(*limb) + (carry & u128::from(u32::MAX));
+            *limb = sum as u32;
+            carry = (carry >> LIMB_BITS) + (sum >> LIMB_BITS);
+        }
+    }
+
+    limbs
+}
+
+/// Divides `numerator` by `divisor` bit by bit and returns the quotient and remainder limbs.
+pub(crate) fn div_rem(numerator: &[u32], divisor: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    let mut quotient = vec![0u32; numerator.len()];
+    let mut remainder = vec![0u32; divisor.len() + 1];
+    for bit in (0..LIMB_BITS * numerator.len()).rev() {
+        // remainder = 2 remainder + the next bit of the numerator
+        let mut carry = (numerator[bit / LIMB_BITS] >> (bit % LIMB_BITS)) & 1;
+        for limb in remainder.iter_mut() {
+            let shifted = (*limb >> (LIMB_BITS - 1)) & 1;
+            *limb = (*limb << 1) | carry;
+            carry = shifted;
+        }
+
+        if !less_than(&remainder, divisor) {
+            let mut borrow = 0i64;
+            for (index, limb) in remainder.iter_mut().enumerate() {
+                let difference =
+                    i64::from(*limb) - i64::from(divisor.get(index).copied().unwrap_or(0)) - borrow;
+                borrow = i64::from(difference < 0);
+                *limb = (difference + (borrow << LIMB_BITS)) as u32;
+            }
+            quotient[bit / LIMB_BITS] |= 1 << (bit % LIMB_BITS);
+        }
+    }
+
+    (quotient, remainder)
+}
+
+/// Returns true if the integer of the limbs `left` is smaller than that of `right`.
+fn less_than(left: &[u32], right: &[u32]) -> bool {
+    let len = left.len().max(right.len());
+    for index in (0..len).rev() {
+        let (l, r) = (
+            left.get(index).copied().unwrap_or(0),
+            right.get(index).copied().unwrap_or(0),
+        );
+        if l != r {
+            return l < r;
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NonNativeElement;
+    use crate::{
+        folding_scheme::{PLONKFoldingScheme, SetupInfo},
+        utils::{inner_product::InnerProductConfig, poseidon::poseidon_parameters_for_test},
+        NonInteractiveFoldingScheme, RowTag, SangriaConfig, SangriaError, TraceTable,
+    };
+    use ark_bn254::{Fq, Fr};
+    use ark_crypto_primitives::sponge::poseidon::PoseidonSponge;
+    use ark_ff::{UniformRand, Zero};
+    use ark_std::test_rng;
+
+    type Scheme = PLONKFoldingScheme<Fr, InnerProductConfig, PoseidonSponge<Fr>>;
+    type Element = NonNativeElement<Fq>;
+
+    /// Computes `(a + b) a` and reduces `a + b` in-circuit, and copies the first limb of the product
+    /// into the output row.
+    fn emulated_trace(a: Fq, b: Fq) -> Result<(TraceTable<Fr>, Fq, Fq), SangriaError> {
+        let mut trace = TraceTable::new();
+        let (a, b) = (
+            Element::allocate(&mut trace, a)?,
+            Element::allocate(&mut trace, b)?,
+        );
+        let sum = a.add(&mut trace, &b)?;
+        let product = sum.mul(&mut trace, &a)?;
+        let reduced = sum.reduce(&mut trace)?;
+
+        let output = product.limbs()[0];
+        let output_row = trace.number_of_rows();
+        let output_value = trace.wire(output.0, output.1)?;
+        trace.push_row(RowTag::Output, [output_value, Fr::zero(), Fr::zero()]);
+        trace.copy(output, (0, output_row));
+        let (product, reduced) = (product.value(&trace)?, reduced.value(&trace)?);
+
+        Ok((trace, product, reduced))
+    }
+
+    #[test]
+    fn emulated_arithmetic_satisfies_the_relation() {
+        let rng = &mut test_rng();
+        let (a, b) = (Fq::rand(rng), Fq::rand(rng));
+        let (trace, product, reduced) = emulated_trace(a, b).unwrap();
+        assert_eq!(product, (a + b) * a);
+        assert_eq!(reduced, a + b);
+
+        let info = SetupInfo {
+            number_of_public_inputs: 0,
+            number_of_gates: trace.number_of_rows() - 1,
+            config: SangriaConfig::default(),
+            poseidon_constants: poseidon_parameters_for_test(),
+        };
+        let pp = Scheme::setup(&info, rng).unwrap();
+        let instance_columns = trace.instance_columns();
+        let (circuit, witness) = trace.into_circuit_and_witness(Vec::new(), rng).unwrap();
+        let instance = Scheme::commit_witness(&pp, instance_columns, &witness).unwrap();
+        assert_eq!(
+            Scheme::check_relation(&pp, &circuit, &instance, &witness),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn oversized_operands_are_rejected() {
+        let mut trace = TraceTable::<Fr>::new();
+        let mut sum = Element::allocate(&mut trace, Fq::from(3u64)).unwrap();
+        for _ in 0..8 {
+            sum = sum.add(&mut trace, &sum.clone()).unwrap();
+        }
+        assert_eq!(sum.value(&trace), Ok(Fq::from(3u64 << 8)));
+
+        assert!(matches!(
+            sum.mul(&mut trace, &sum),
+            Err(SangriaError::InvalidTrace(_))
+        ));
+        let reduced = sum.reduce(&mut trace).unwrap();
+        assert_eq!(reduced.value(&trace), Ok(Fq::from(3u64 << 8)));
+        assert!(reduced.mul(&mut trace, &reduced).is_ok());
+    }
+}
@@ -0,0 +1,106 @@
+use ark_ff::PrimeField;
+use ark_std::marker::PhantomData;
+
+use crate::{SangriaError, StepCircuit};
+
+/// A step circuit that leaves the state unchanged. It is the canonical padding step, e.g. to reach a
+/// power-of-two number of steps before merging proofs.
+pub struct NoopStep<S>(PhantomData<S>);
+
+impl<S> NoopStep<S> {
+    /// Creates a no-op step for states of type `S`.
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<S> Default for NoopStep<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: PrimeField, S: Clone> StepCircuit<F> for NoopStep<S> {
+    type State = S;
+    type Witness = ();
+
+    fn execute(&self, current_state: &S, _witness: &()) -> Result<S, SangriaError> {
+        Ok(current_state.clone())
+    }
+}
+
+/// Wraps a step circuit so that any step of the chain can be a no-op: a step with witness `None`
+/// leaves the state unchanged, a step with witness `Some(w)` runs the inner circuit on `w`.
+pub struct PaddedStep<SC>(pub SC);
+
+impl<F, SC> StepCircuit<F> for PaddedStep<SC>
+where
+    F: PrimeField,
+    SC: StepCircuit<F>,
+    SC::State: Clone,
+{
+    type State = SC::State;
+    type Witness = Option<SC::Witness>;
+
+    fn execute(
+        &self,
+        current_state: &Self::State,
+        witness: &Self::Witness,
+    ) -> Result<Self::State, SangriaError> {
+        match witness {
+            Some(witness) => self.0.execute(current_state, witness),
+            None => Ok(current_state.clone()),
+        }
+    }
+
+    fn fixed_tables(&self) -> Vec<Vec<F>> {
+        self.0.fixed_tables()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NoopStep, PaddedStep};
+    use crate::{Sangria, SangriaError, StepCircuit};
+    use ark_bn254::Fr;
+    use ark_ff::Field;
+
+    /// Doubles the state and adds the witness.
+    struct DoubleAndAdd;
+
+    impl StepCircuit<Fr> for DoubleAndAdd {
+        type State = Fr;
+        type Witness = Fr;
+
+        fn execute(&self, current_state: &Fr, witness: &Fr) -> Result<Fr, SangriaError> {
+            Ok(current_state.double() + witness)
+        }
+    }
+
+    #[test]
+    fn noop_step_keeps_state() {
+        let state = Fr::from(42u64);
+
+        let final_state = Sangria::replay::<Fr, _>(&NoopStep::new(), state, &[(), (), ()]).unwrap();
+
+        assert_eq!(final_state, state);
+    }
+
+    #[test]
+    fn padded_and_unpadded_chains_agree() {
+        let origin = Fr::from(3u64);
+        let witnesses: Vec<Fr> = (1..=5u64).map(Fr::from).collect();
+
+        let unpadded = Sangria::replay(&DoubleAndAdd, origin, &witnesses).unwrap();
+
+        // interleave padding steps and pad up to 16 steps
+        let mut padded_witnesses: Vec<Option<Fr>> = witnesses
+            .iter()
+            .flat_map(|&witness| [Some(witness), None])
+            .collect();
+        padded_witnesses.resize(16, None);
+        let padded = Sangria::replay(&PaddedStep(DoubleAndAdd), origin, &padded_witnesses).unwrap();
+
+        assert_eq!(padded, unpadded);
+    }
+}
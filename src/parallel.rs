@@ -0,0 +1,97 @@
+use std::{
+    num::NonZeroUsize,
+    panic,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex, PoisonError,
+    },
+    thread,
+};
+
+/// Applies `f` to every item on scoped threads, one per available core. The items form a shared
+/// queue from which every thread takes the next item once it is done with the previous one, so
+/// that items of uneven cost (e.g. commitments to the columns of different steps) keep all threads
+/// busy. Returns the results in the order of the items, and resumes the panic of a thread that
+/// panicked.
+pub(crate) fn parallel_map<T, U, Map>(items: Vec<T>, f: Map) -> Vec<U>
+where
+    T: Send,
+    U: Send,
+    Map: Fn(T) -> U + Sync,
+{
+    parallel_map_on(available_threads(), items, f)
+}
+
+/// Returns the number of available cores, or one if it is unknown.
+pub(crate) fn available_threads() -> usize {
+    thread::available_parallelism().map_or(1, NonZeroUsize::get)
+}
+
+/// Applies `f` to every item like [`parallel_map`], on at most `threads` scoped threads.
+pub(crate) fn parallel_map_on<T, U, Map>(threads: usize, items: Vec<T>, f: Map) -> Vec<U>
+where
+    T: Send,
+    U: Send,
+    Map: Fn(T) -> U + Sync,
+{
+    let threads = threads.max(1).min(items.len());
+    let queue: Vec<Mutex<Option<T>>> = items
+        .into_iter()
+        .map(|item| Mutex::new(Some(item)))
+        .collect();
+    let next = AtomicUsize::new(0);
+
+    let mut results: Vec<(usize, U)> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut results = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(slot) = queue.get(index) else {
+                            break;
+                        };
+                        let item = slot.lock().unwrap_or_else(PoisonError::into_inner).take();
+                        if let Some(item) = item {
+                            results.push((index, f(item)));
+                        }
+                    }
+                    results
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|error| panic::resume_unwind(error))
+            })
+            .collect()
+    });
+    results.sort_unstable_by_key(|&(index, _)| index);
+
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parallel_map, parallel_map_on};
+
+    #[test]
+    fn results_keep_the_order_of_the_items() {
+        for len in [0, 1, 7, 100] {
+            let items: Vec<u64> = (0..len).collect();
+
+            let squares: Vec<u64> = items.iter().map(|item| item * item).collect();
+            assert_eq!(parallel_map(items.clone(), |item| item * item), squares);
+            for threads in [0, 1, 3] {
+                assert_eq!(
+                    parallel_map_on(threads, items.clone(), |item| item * item),
+                    squares
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,30 @@
+//! Polynomial IOPs over multilinear extensions, as used by HyperPlonk: sumcheck (also batched over
+//! several claims, or masked for zero knowledge), zero check, product check and permutation check. Challenges are drawn from a
+//! caller-supplied sponge, and each verifier returns the evaluations it could not check itself. The
+//! caller checks those against the commitments to the polynomials, e.g. with a batched opening of a
+//! multilinear PCS.
+
+mod permutation_check;
+mod product_check;
+mod sumcheck;
+mod zero_check;
+
+pub use permutation_check::{
+    prove_permutation_check, verify_permutation_check, PermutationCheckProof,
+    PermutationCheckSubClaim,
+};
+pub use product_check::{
+    prove_product_check, verify_product_check, ProductCheckProof, ProductCheckSubClaim,
+    PRODUCT_CHECK_DEGREE,
+};
+pub use sumcheck::{
+    prove_batched_sumcheck, prove_masked_sumcheck, prove_sumcheck, verify_batched_sumcheck,
+    verify_masked_sumcheck, verify_sumcheck, BatchedSumcheckSubClaim, MaskedSumcheckProof,
+    MaskedSumcheckSubClaim, SumcheckMask, SumcheckProof, SumcheckSubClaim, VirtualPolynomial,
+};
+pub use zero_check::{
+    prove_masked_zero_check, prove_zero_check, verify_masked_zero_check, verify_zero_check,
+    ZeroCheckSubClaim,
+};
+
+pub(crate) use zero_check::{eq_evaluation, eq_extension};
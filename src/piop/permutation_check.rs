@@ -0,0 +1,221 @@
+use ark_crypto_primitives::sponge::{Absorb, CryptographicSponge};
+use ark_ff::PrimeField;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+
+use crate::{
+    piop::{prove_product_check, verify_product_check, ProductCheckProof, SumcheckMask},
+    HomomorphicCommitmentScheme, MultilinearExtension, SangriaError,
+};
+
+/// A proof that `g(x) = f(permutation(x))` on the hypercube, where the permutation is given by the
+/// multilinear extension of the permuted indices. It reduces to a product check of `f + alpha * id`
+/// against `g + alpha * permutation`, `id` being the extension of the identity.
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct PermutationCheckProof<F: PrimeField, C: HomomorphicCommitmentScheme<F>> {
+    product_check_proof: ProductCheckProof<F, C>,
+    evaluations: PermutationCheckSubClaim<F>,
+}
+
+impl<F: PrimeField, C: HomomorphicCommitmentScheme<F>> PermutationCheckProof<F, C> {
+    /// Returns the underlying product check proof, which holds the commitments to the inverses.
+    pub fn product_check_proof(&self) -> &ProductCheckProof<F, C> {
+        &self.product_check_proof
+    }
+}
+
+/// What remains to be checked after a permutation check: the evaluations of `f`, `g` and the
+/// permutation at `point`, as well as those of the committed inverses.
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PermutationCheckSubClaim<F: PrimeField> {
+    /// The random point the check was reduced to.
+    pub point: Vec<F>,
+    /// The claimed evaluation of `f` at `point`.
+    pub left_evaluation: F,
+    /// The claimed evaluation of `g` at `point`.
+    pub right_evaluation: F,
+    /// The claimed evaluation of the permutation at `point`.
+    pub permutation_evaluation: F,
+    /// The claimed evaluations of the inverses committed in the product check at `point`.
+    pub inverse_evaluations: [F; 2],
+    /// The claimed evaluation of the mask of a zero-knowledge product check at `point`.
+    pub mask_evaluation: Option<F>,
+}
+
+/// Returns the extension of the identity over the hypercube of `number_of_variables` variables.
+fn identity_extension<F: PrimeField>(number_of_variables: usize) -> MultilinearExtension<F> {
+    let identity: Vec<F> = (0..1u64 << number_of_variables).map(F::from).collect();
+    MultilinearExtension::from_column(&identity)
+}
+
+/// Evaluates the extension of the identity at `point`.
+fn identity_evaluation<F: PrimeField>(point: &[F]) -> F {
+    point
+        .iter()
+        .rev()
+        .fold(F::zero(), |value, &coordinate| value.double() + coordinate)
+}
+
+/// Proves that `right(x) = left(permutation(x))` on the hypercube, committing to the inverse
+/// polynomials of the product check with `commit_key` and `inverse_hidings`, and masking its
+/// sumcheck with `mask` if there is one (see [`prove_product_check`]). Returns the proof and the two
+/// committed inverse vectors, which the prover needs to open their commitments.
+#[allow(clippy::type_complexity)]
+pub fn prove_permutation_check<F, C, S>(
+    commit_key: &C::CommitKey,
+    left: &MultilinearExtension<F>,
+    right: &MultilinearExtension<F>,
+    permutation: &MultilinearExtension<F>,
+    inverse_hidings: [F; 2],
+    mask: Option<&SumcheckMask<F>>,
+    sponge: &mut S,
+) -> Result<(PermutationCheckProof<F, C>, [Vec<F>; 2]), SangriaError>
+where
+    F: PrimeField + Absorb,
+    C: HomomorphicCommitmentScheme<F>,
+    S: CryptographicSponge,
+{
+    let number_of_variables = left.number_of_variables();
+    if right.number_of_variables() != number_of_variables
+        || permutation.number_of_variables() != number_of_variables
+    {
+        return Err(SangriaError::IndexOutOfBounds);
+    }
+
+    let alpha: F = sponge.squeeze_field_elements(1)[0];
+    let (product_check_proof, inverses) = prove_product_check(
+        commit_key,
+        &left.fold(&identity_extension(number_of_variables), alpha)?,
+        &right.fold(permutation, alpha)?,
+        inverse_hidings,
+        mask,
+        sponge,
+    )?;
+
+    let point = product_check_proof.evaluations().point.clone();
+    let evaluations = PermutationCheckSubClaim {
+        left_evaluation: left.evaluate(&point)?,
+        right_evaluation: right.evaluate(&point)?,
+        permutation_evaluation: permutation.evaluate(&point)?,
+        inverse_evaluations: [
+            product_check_proof.evaluations().left_inverse_evaluation,
+            product_check_proof.evaluations().right_inverse_evaluation,
+        ],
+        mask_evaluation: product_check_proof.evaluations().mask_evaluation,
+        point,
+    };
+
+    Ok((
+        PermutationCheckProof {
+            product_check_proof,
+            evaluations,
+        },
+        inverses,
+    ))
+}
+
+/// Verifies a permutation check over polynomials in `number_of_variables` variables. Returns the
+/// evaluation claims left to check against the commitments, or an error if the proof is rejected.
+pub fn verify_permutation_check<F, C, S>(
+    number_of_variables: usize,
+    proof: &PermutationCheckProof<F, C>,
+    sponge: &mut S,
+) -> Result<PermutationCheckSubClaim<F>, SangriaError>
+where
+    F: PrimeField + Absorb,
+    C: HomomorphicCommitmentScheme<F>,
+    S: CryptographicSponge,
+{
+    let alpha: F = sponge.squeeze_field_elements(1)[0];
+    let product_subclaim =
+        verify_product_check(number_of_variables, &proof.product_check_proof, sponge)?;
+
+    let evaluations = &proof.evaluations;
+    if product_subclaim.point != evaluations.point
+        || product_subclaim.left_evaluation
+            != evaluations.left_evaluation + alpha * identity_evaluation(&evaluations.point)
+        || product_subclaim.right_evaluation
+            != evaluations.right_evaluation + alpha * evaluations.permutation_evaluation
+        || [
+            product_subclaim.left_inverse_evaluation,
+            product_subclaim.right_inverse_evaluation,
+        ] != evaluations.inverse_evaluations
+        || product_subclaim.mask_evaluation != evaluations.mask_evaluation
+    {
+        return Err(SangriaError::InvalidProof);
+    }
+
+    Ok(evaluations.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        identity_evaluation, identity_extension, prove_permutation_check, verify_permutation_check,
+    };
+    use crate::{
+        utils::{
+            inner_product::InnerProduct, poseidon::poseidon_parameters_for_test,
+            rand::sample_vector,
+        },
+        HomomorphicCommitmentScheme, MultilinearExtension, SangriaError,
+    };
+    use ark_bn254::Fr;
+    use ark_crypto_primitives::sponge::{poseidon::PoseidonSponge, CryptographicSponge};
+    use ark_ff::Zero;
+    use ark_std::test_rng;
+
+    #[test]
+    fn identity_extension_matches_its_evaluation() {
+        let point: Vec<Fr> = sample_vector(&mut test_rng(), 3);
+
+        assert_eq!(
+            identity_extension(3).evaluate(&point),
+            Ok(identity_evaluation(&point))
+        );
+    }
+
+    /// Checks `right(x) = left(permutation(x))` for the rotation `x -> x + 1 mod 8`, with `right`
+    /// either consistent with the rotation or not.
+    fn check_rotation(consistent: bool) -> Result<(), SangriaError> {
+        let rng = &mut test_rng();
+        let params = poseidon_parameters_for_test::<Fr>();
+        let commit_key = InnerProduct::setup(rng, 8);
+        let left: Vec<Fr> = sample_vector(rng, 8);
+        let permutation: Vec<usize> = (0..8).map(|index| (index + 1) % 8).collect();
+        let mut right: Vec<Fr> = permutation.iter().map(|&index| left[index]).collect();
+        if !consistent {
+            right.swap(0, 1);
+        }
+        let permutation: Vec<Fr> = permutation
+            .into_iter()
+            .map(|index| Fr::from(index as u64))
+            .collect();
+
+        let (proof, _) = prove_permutation_check::<_, InnerProduct, _>(
+            &commit_key,
+            &MultilinearExtension::from_column(&left),
+            &MultilinearExtension::from_column(&right),
+            &MultilinearExtension::from_column(&permutation),
+            [Fr::zero(); 2],
+            None,
+            &mut PoseidonSponge::new(&params),
+        )?;
+        let subclaim = verify_permutation_check(3, &proof, &mut PoseidonSponge::new(&params))?;
+
+        assert_eq!(
+            MultilinearExtension::from_column(&permutation).evaluate(&subclaim.point),
+            Ok(subclaim.permutation_evaluation)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn permuted_column_passes() {
+        assert_eq!(check_rotation(true), Ok(()));
+    }
+
+    #[test]
+    fn unpermuted_column_fails() {
+        assert_eq!(check_rotation(false), Err(SangriaError::InvalidProof));
+    }
+}
@@ -0,0 +1,391 @@
+use ark_crypto_primitives::sponge::{Absorb, CryptographicSponge};
+use ark_ff::{batch_inversion, PrimeField};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+
+use crate::{
+    piop::{eq_evaluation, eq_extension, prove_sumcheck, verify_sumcheck, SumcheckProof},
+    piop::{
+        prove_masked_sumcheck, verify_masked_sumcheck, MaskedSumcheckProof, SumcheckMask,
+        SumcheckSubClaim, VirtualPolynomial,
+    },
+    HomomorphicCommitmentScheme, MultilinearExtension, SangriaError,
+};
+
+/// A proof that `prod_x (beta + f(x)) = prod_x (beta + g(x))` for a random `beta`, i.e. that `f` and
+/// `g` take the same values on the hypercube up to reordering. The products are compared through
+/// the rational sumcheck `sum_x 1 / (beta + f(x)) = sum_x 1 / (beta + g(x))`: the prover commits to
+/// the inverses and proves their sums equal together with a zero check that they are the inverses.
+/// For zero knowledge, the inverses are committed to with hidings and the sumcheck is masked.
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct ProductCheckProof<F: PrimeField, C: HomomorphicCommitmentScheme<F>> {
+    left_inverse_commitment: C::Commitment,
+    right_inverse_commitment: C::Commitment,
+    sumcheck_proof: Option<SumcheckProof<F>>,
+    masked_sumcheck_proof: Option<MaskedSumcheckProof<F>>,
+    evaluations: ProductCheckSubClaim<F>,
+}
+
+impl<F: PrimeField, C: HomomorphicCommitmentScheme<F>> ProductCheckProof<F, C> {
+    /// Returns the commitment to `1 / (beta + f)`.
+    pub fn left_inverse_commitment(&self) -> C::Commitment {
+        self.left_inverse_commitment
+    }
+
+    /// Returns the commitment to `1 / (beta + g)`.
+    pub fn right_inverse_commitment(&self) -> C::Commitment {
+        self.right_inverse_commitment
+    }
+
+    /// Returns the evaluations claimed by the prover at the point the check was reduced to.
+    pub fn evaluations(&self) -> &ProductCheckSubClaim<F> {
+        &self.evaluations
+    }
+
+    /// Returns whether the sumcheck is masked.
+    pub fn is_masked(&self) -> bool {
+        self.masked_sumcheck_proof.is_some()
+    }
+}
+
+/// What remains to be checked after a product check: the evaluations of `f`, `g` and of the two
+/// committed inverses at `point`.
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct ProductCheckSubClaim<F: PrimeField> {
+    /// The random point the check was reduced to.
+    pub point: Vec<F>,
+    /// The claimed evaluation of `f` at `point`.
+    pub left_evaluation: F,
+    /// The claimed evaluation of `g` at `point`.
+    pub right_evaluation: F,
+    /// The claimed evaluation of `1 / (beta + f)` at `point`.
+    pub left_inverse_evaluation: F,
+    /// The claimed evaluation of `1 / (beta + g)` at `point`.
+    pub right_inverse_evaluation: F,
+    /// The claimed evaluation at `point` of the mask of a zero-knowledge sumcheck, which the caller
+    /// checks against its commitment.
+    pub mask_evaluation: Option<F>,
+}
+
+/// Builds the polynomial whose sum over the hypercube is zero for an honest prover:
+/// `a - b + eq(x, r) * (mu * (a * (beta + f) - 1) + mu^2 * (b * (beta + g) - 1))`.
+fn batched_polynomial<F: PrimeField>(
+    extensions: [&MultilinearExtension<F>; 4],
+    beta: F,
+    r: &[F],
+    mu: F,
+) -> Result<VirtualPolynomial<F>, SangriaError> {
+    let [left, right, left_inverse, right_inverse] = extensions;
+    let eq = eq_extension(r);
+    let mut polynomial = VirtualPolynomial::new(left.number_of_variables());
+
+    polynomial.add_product(F::one(), vec![left_inverse.clone()])?;
+    polynomial.add_product(-F::one(), vec![right_inverse.clone()])?;
+    for (scale, values, inverse) in [(mu, left, left_inverse), (mu * mu, right, right_inverse)] {
+        polynomial.add_product(scale * beta, vec![eq.clone(), inverse.clone()])?;
+        polynomial.add_product(scale, vec![eq.clone(), inverse.clone(), values.clone()])?;
+        polynomial.add_product(-scale, vec![eq.clone()])?;
+    }
+
+    Ok(polynomial)
+}
+
+/// Evaluates [`batched_polynomial`] from the claimed evaluations at `point`.
+fn batched_evaluation<F: PrimeField>(
+    evaluations: &ProductCheckSubClaim<F>,
+    beta: F,
+    r: &[F],
+    mu: F,
+) -> F {
+    let left =
+        evaluations.left_inverse_evaluation * (beta + evaluations.left_evaluation) - F::one();
+    let right =
+        evaluations.right_inverse_evaluation * (beta + evaluations.right_evaluation) - F::one();
+
+    evaluations.left_inverse_evaluation - evaluations.right_inverse_evaluation
+        + eq_evaluation(&evaluations.point, r) * (mu * left + mu * mu * right)
+}
+
+fn absorb_evaluations<F: PrimeField + Absorb, S: CryptographicSponge>(
+    sponge: &mut S,
+    evaluations: &ProductCheckSubClaim<F>,
+) {
+    sponge.absorb(&evaluations.left_evaluation);
+    sponge.absorb(&evaluations.right_evaluation);
+    sponge.absorb(&evaluations.left_inverse_evaluation);
+    sponge.absorb(&evaluations.right_inverse_evaluation);
+}
+
+/// The degree in each variable of the polynomial summed by the product check, which is the degree of
+/// the mask of a zero-knowledge product check.
+pub const PRODUCT_CHECK_DEGREE: usize = 3;
+
+/// Proves that `left` and `right` take the same values on the hypercube up to reordering,
+/// committing to the inverse polynomials with `commit_key` and `inverse_hidings`. With a `mask` of
+/// degree [`PRODUCT_CHECK_DEGREE`], the sumcheck is zero-knowledge: the caller commits to the mask,
+/// absorbs the commitment into `sponge` beforehand and opens it at the point of the check. Returns
+/// the proof and the two committed inverse vectors, which the prover needs to open their
+/// commitments.
+#[allow(clippy::type_complexity)]
+pub fn prove_product_check<F, C, S>(
+    commit_key: &C::CommitKey,
+    left: &MultilinearExtension<F>,
+    right: &MultilinearExtension<F>,
+    inverse_hidings: [F; 2],
+    mask: Option<&SumcheckMask<F>>,
+    sponge: &mut S,
+) -> Result<(ProductCheckProof<F, C>, [Vec<F>; 2]), SangriaError>
+where
+    F: PrimeField + Absorb,
+    C: HomomorphicCommitmentScheme<F>,
+    S: CryptographicSponge,
+{
+    if left.number_of_variables() != right.number_of_variables() {
+        return Err(SangriaError::IndexOutOfBounds);
+    }
+
+    let beta: F = sponge.squeeze_field_elements(1)[0];
+    let inverses: Vec<Vec<F>> = [left, right]
+        .iter()
+        .map(|extension| {
+            let mut shifted: Vec<F> = extension
+                .evaluations()
+                .iter()
+                .map(|&value| beta + value)
+                .collect();
+            if shifted.iter().any(|value| value.is_zero()) {
+                return Err(SangriaError::InvalidWitness);
+            }
+            batch_inversion(&mut shifted);
+            Ok(shifted)
+        })
+        .collect::<Result<_, _>>()?;
+
+    let commitments = C::batch_commit(commit_key, &inverses, &inverse_hidings)?;
+    sponge.absorb(&commitments[0]);
+    sponge.absorb(&commitments[1]);
+
+    let r: Vec<F> = sponge.squeeze_field_elements(left.number_of_variables());
+    let mu: F = sponge.squeeze_field_elements(1)[0];
+    let (left_inverse, right_inverse) = (
+        MultilinearExtension::from_column(&inverses[0]),
+        MultilinearExtension::from_column(&inverses[1]),
+    );
+    let polynomial =
+        batched_polynomial([left, right, &left_inverse, &right_inverse], beta, &r, mu)?;
+    let [left_inverses, right_inverses]: [Vec<F>; 2] = inverses
+        .try_into()
+        .expect("there is one inverse vector per side");
+    let (sumcheck_proof, masked_sumcheck_proof, point) = match mask {
+        Some(mask) => {
+            let (proof, point) = prove_masked_sumcheck(&polynomial, mask, sponge)?;
+            (None, Some(proof), point)
+        }
+        None => {
+            let (proof, point) = prove_sumcheck(&polynomial, sponge);
+            (Some(proof), None, point)
+        }
+    };
+
+    let evaluations = ProductCheckSubClaim {
+        left_evaluation: left.evaluate(&point)?,
+        right_evaluation: right.evaluate(&point)?,
+        left_inverse_evaluation: left_inverse.evaluate(&point)?,
+        right_inverse_evaluation: right_inverse.evaluate(&point)?,
+        mask_evaluation: mask.map(|mask| mask.evaluate(&point)).transpose()?,
+        point,
+    };
+    absorb_evaluations(sponge, &evaluations);
+
+    Ok((
+        ProductCheckProof {
+            left_inverse_commitment: commitments[0],
+            right_inverse_commitment: commitments[1],
+            sumcheck_proof,
+            masked_sumcheck_proof,
+            evaluations,
+        },
+        [left_inverses, right_inverses],
+    ))
+}
+
+/// Verifies a product check over polynomials in `number_of_variables` variables, masked or not.
+/// Returns the evaluation claims left to check against the commitments to `f`, `g`, the inverses
+/// and the mask if there is one, or an error if the proof is rejected.
+pub fn verify_product_check<F, C, S>(
+    number_of_variables: usize,
+    proof: &ProductCheckProof<F, C>,
+    sponge: &mut S,
+) -> Result<ProductCheckSubClaim<F>, SangriaError>
+where
+    F: PrimeField + Absorb,
+    C: HomomorphicCommitmentScheme<F>,
+    S: CryptographicSponge,
+{
+    let beta: F = sponge.squeeze_field_elements(1)[0];
+    sponge.absorb(&proof.left_inverse_commitment);
+    sponge.absorb(&proof.right_inverse_commitment);
+
+    let r: Vec<F> = sponge.squeeze_field_elements(number_of_variables);
+    let mu: F = sponge.squeeze_field_elements(1)[0];
+    let (point, expected_evaluation, mask_evaluation) =
+        match (&proof.sumcheck_proof, &proof.masked_sumcheck_proof) {
+            (Some(sumcheck_proof), None) => {
+                let SumcheckSubClaim {
+                    point,
+                    expected_evaluation,
+                } = verify_sumcheck(
+                    F::zero(),
+                    PRODUCT_CHECK_DEGREE,
+                    number_of_variables,
+                    sumcheck_proof,
+                    sponge,
+                )?;
+                (point, expected_evaluation, None)
+            }
+            (None, Some(masked_sumcheck_proof)) => {
+                let subclaim = verify_masked_sumcheck(
+                    F::zero(),
+                    PRODUCT_CHECK_DEGREE,
+                    number_of_variables,
+                    masked_sumcheck_proof,
+                    sponge,
+                )?;
+                (
+                    subclaim.point,
+                    subclaim.expected_evaluation,
+                    Some(subclaim.mask_evaluation),
+                )
+            }
+            _ => return Err(SangriaError::InvalidProof),
+        };
+
+    if point != proof.evaluations.point
+        || mask_evaluation != proof.evaluations.mask_evaluation
+        || batched_evaluation(&proof.evaluations, beta, &r, mu) != expected_evaluation
+    {
+        return Err(SangriaError::InvalidProof);
+    }
+    absorb_evaluations(sponge, &proof.evaluations);
+
+    Ok(proof.evaluations.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{prove_product_check, verify_product_check, PRODUCT_CHECK_DEGREE};
+    use crate::{
+        piop::SumcheckMask,
+        utils::{
+            inner_product::InnerProduct, poseidon::poseidon_parameters_for_test,
+            rand::sample_vector,
+        },
+        HomomorphicCommitmentScheme, MultilinearExtension, SangriaError,
+    };
+    use ark_bn254::Fr;
+    use ark_crypto_primitives::sponge::{poseidon::PoseidonSponge, CryptographicSponge};
+    use ark_ff::{One, Zero};
+    use ark_std::test_rng;
+
+    #[test]
+    fn reordered_values_pass() {
+        let rng = &mut test_rng();
+        let params = poseidon_parameters_for_test::<Fr>();
+        let commit_key = InnerProduct::setup(rng, 8);
+        let left: Vec<Fr> = sample_vector(rng, 8);
+        let mut right = left.clone();
+        right.reverse();
+        let (left, right) = (
+            MultilinearExtension::from_column(&left),
+            MultilinearExtension::from_column(&right),
+        );
+
+        let (proof, inverses) = prove_product_check::<_, InnerProduct, _>(
+            &commit_key,
+            &left,
+            &right,
+            [Fr::zero(); 2],
+            None,
+            &mut PoseidonSponge::new(&params),
+        )
+        .unwrap();
+        let subclaim = verify_product_check(3, &proof, &mut PoseidonSponge::new(&params)).unwrap();
+
+        assert_eq!(left.evaluate(&subclaim.point), Ok(subclaim.left_evaluation));
+        assert_eq!(
+            right.evaluate(&subclaim.point),
+            Ok(subclaim.right_evaluation)
+        );
+        assert_eq!(
+            MultilinearExtension::from_column(&inverses[0]).evaluate(&subclaim.point),
+            Ok(subclaim.left_inverse_evaluation)
+        );
+        assert_eq!(
+            InnerProduct::commit(&commit_key, &inverses[1], Fr::zero()),
+            Ok(proof.right_inverse_commitment())
+        );
+    }
+
+    #[test]
+    fn different_values_fail() {
+        let rng = &mut test_rng();
+        let params = poseidon_parameters_for_test::<Fr>();
+        let commit_key = InnerProduct::setup(rng, 8);
+        let left: Vec<Fr> = sample_vector(rng, 8);
+        let mut right = left.clone();
+        right[3] += Fr::one();
+
+        let (proof, _) = prove_product_check::<_, InnerProduct, _>(
+            &commit_key,
+            &MultilinearExtension::from_column(&left),
+            &MultilinearExtension::from_column(&right),
+            [Fr::zero(); 2],
+            None,
+            &mut PoseidonSponge::new(&params),
+        )
+        .unwrap();
+
+        assert!(matches!(
+            verify_product_check(3, &proof, &mut PoseidonSponge::new(&params)),
+            Err(SangriaError::InvalidProof)
+        ));
+    }
+
+    #[test]
+    fn masked_product_check_passes() {
+        let rng = &mut test_rng();
+        let params = poseidon_parameters_for_test::<Fr>();
+        let commit_key = InnerProduct::setup(rng, 16);
+        let left: Vec<Fr> = sample_vector(rng, 8);
+        let mut right = left.clone();
+        right.rotate_left(3);
+        let (left, right) = (
+            MultilinearExtension::from_column(&left),
+            MultilinearExtension::from_column(&right),
+        );
+        let mask = SumcheckMask::rand(rng, 3, PRODUCT_CHECK_DEGREE);
+        let hidings = [Fr::from(5u64), Fr::from(7u64)];
+
+        let (proof, inverses) = prove_product_check::<_, InnerProduct, _>(
+            &commit_key,
+            &left,
+            &right,
+            hidings,
+            Some(&mask),
+            &mut PoseidonSponge::new(&params),
+        )
+        .unwrap();
+        assert!(proof.is_masked());
+        let subclaim = verify_product_check(3, &proof, &mut PoseidonSponge::new(&params)).unwrap();
+
+        assert_eq!(
+            subclaim.mask_evaluation,
+            Some(mask.evaluate(&subclaim.point).unwrap())
+        );
+        assert_eq!(left.evaluate(&subclaim.point), Ok(subclaim.left_evaluation));
+        // the inverses are committed to with their hidings
+        assert_eq!(
+            InnerProduct::commit(&commit_key, &inverses[0], hidings[0]),
+            Ok(proof.left_inverse_commitment())
+        );
+    }
+}
@@ -0,0 +1,782 @@
+use ark_crypto_primitives::sponge::{Absorb, CryptographicSponge};
+use ark_ff::PrimeField;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::rand::Rng;
+
+use crate::{MultilinearExtension, SangriaError};
+
+/// A sum of products of multilinear extensions, each product being scaled by a coefficient. A
+/// product of no extension is a constant.
+#[derive(Clone, Debug)]
+pub struct VirtualPolynomial<F: PrimeField> {
+    number_of_variables: usize,
+    products: Vec<(F, Vec<MultilinearExtension<F>>)>,
+}
+
+impl<F: PrimeField> VirtualPolynomial<F> {
+    /// Creates the zero polynomial in `number_of_variables` variables.
+    pub fn new(number_of_variables: usize) -> Self {
+        Self {
+            number_of_variables,
+            products: Vec::new(),
+        }
+    }
+
+    /// Adds `coefficient` times the product of `extensions`, or returns an error if an extension
+    /// does not have the polynomial's number of variables.
+    pub fn add_product(
+        &mut self,
+        coefficient: F,
+        extensions: Vec<MultilinearExtension<F>>,
+    ) -> Result<(), SangriaError> {
+        if extensions
+            .iter()
+            .any(|extension| extension.number_of_variables() != self.number_of_variables)
+        {
+            return Err(SangriaError::IndexOutOfBounds);
+        }
+
+        self.products.push((coefficient, extensions));
+        Ok(())
+    }
+
+    /// Returns the product of this polynomial with `extension`, or an error if the numbers of
+    /// variables differ.
+    pub fn mul_extension(&self, extension: &MultilinearExtension<F>) -> Result<Self, SangriaError> {
+        if extension.number_of_variables() != self.number_of_variables {
+            return Err(SangriaError::IndexOutOfBounds);
+        }
+
+        let products = self
+            .products
+            .iter()
+            .map(|(coefficient, extensions)| {
+                let mut extensions = extensions.clone();
+                extensions.push(extension.clone());
+                (*coefficient, extensions)
+            })
+            .collect();
+
+        Ok(Self {
+            number_of_variables: self.number_of_variables,
+            products,
+        })
+    }
+
+    /// Returns the number of variables of the polynomial.
+    pub fn number_of_variables(&self) -> usize {
+        self.number_of_variables
+    }
+
+    /// Returns the degree of the polynomial in each variable: the size of its largest product.
+    pub fn degree(&self) -> usize {
+        self.products
+            .iter()
+            .map(|(_, extensions)| extensions.len())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Evaluates the polynomial at `point` or returns an error if the point has the wrong size.
+    pub fn evaluate(&self, point: &[F]) -> Result<F, SangriaError> {
+        self.products
+            .iter()
+            .try_fold(F::zero(), |sum, (coefficient, extensions)| {
+                let product = extensions
+                    .iter()
+                    .try_fold(*coefficient, |product, extension| {
+                        Ok::<_, SangriaError>(product * extension.evaluate(point)?)
+                    })?;
+                Ok(sum + product)
+            })
+    }
+
+    /// Returns the sum of the polynomial over the boolean hypercube.
+    pub fn sum_over_hypercube(&self) -> F {
+        (0..1usize << self.number_of_variables)
+            .map(|index| self.evaluate_on_hypercube(index))
+            .sum()
+    }
+
+    fn evaluate_on_hypercube(&self, index: usize) -> F {
+        self.products
+            .iter()
+            .map(|(coefficient, extensions)| {
+                extensions.iter().fold(*coefficient, |product, extension| {
+                    product * extension.evaluations()[index]
+                })
+            })
+            .sum()
+    }
+
+    /// Fixes the first variable of every extension to `r`.
+    fn fix_variable(&self, r: F) -> Self {
+        let products = self
+            .products
+            .iter()
+            .map(|(coefficient, extensions)| {
+                let extensions = extensions
+                    .iter()
+                    .map(|extension| {
+                        extension
+                            .fix_variable(r)
+                            .expect("the polynomial has a variable left")
+                    })
+                    .collect();
+                (*coefficient, extensions)
+            })
+            .collect();
+
+        Self {
+            number_of_variables: self.number_of_variables - 1,
+            products,
+        }
+    }
+
+    /// Returns the evaluations at `0, 1, ..., degree` of the univariate polynomial obtained by
+    /// summing over every variable but the first.
+    fn round_polynomial(&self, degree: usize) -> Vec<F> {
+        let half = 1usize << (self.number_of_variables - 1);
+        (0..=degree)
+            .map(|t| {
+                let t = F::from(t as u64);
+                (0..half)
+                    .map(|index| {
+                        self.products
+                            .iter()
+                            .map(|(coefficient, extensions)| {
+                                extensions.iter().fold(*coefficient, |product, extension| {
+                                    let (low, high) = (
+                                        extension.evaluations()[2 * index],
+                                        extension.evaluations()[2 * index + 1],
+                                    );
+                                    product * (low + t * (high - low))
+                                })
+                            })
+                            .sum::<F>()
+                    })
+                    .sum()
+            })
+            .collect()
+    }
+}
+
+/// A sumcheck proof: for each variable, the evaluations at `0, 1, ..., degree` of the round
+/// polynomial.
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct SumcheckProof<F: PrimeField> {
+    round_polynomials: Vec<Vec<F>>,
+}
+
+/// What remains to be checked after a sumcheck: the polynomial must evaluate to
+/// `expected_evaluation` at `point`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SumcheckSubClaim<F: PrimeField> {
+    /// The random point the sum was reduced to.
+    pub point: Vec<F>,
+    /// The evaluation of the polynomial at `point` implied by the proof.
+    pub expected_evaluation: F,
+}
+
+/// Proves the sum of `polynomial` over the boolean hypercube, drawing the challenges from `sponge`.
+/// Returns the proof and the point the sum was reduced to.
+pub fn prove_sumcheck<F, S>(
+    polynomial: &VirtualPolynomial<F>,
+    sponge: &mut S,
+) -> (SumcheckProof<F>, Vec<F>)
+where
+    F: PrimeField + Absorb,
+    S: CryptographicSponge,
+{
+    run_sumcheck(polynomial, |_, _| None, sponge)
+}
+
+/// Runs the rounds of a sumcheck of `polynomial`, adding to each round polynomial the evaluations
+/// returned by `mask` given the round and the challenges drawn so far.
+fn run_sumcheck<F, S>(
+    polynomial: &VirtualPolynomial<F>,
+    mask: impl Fn(usize, &[F]) -> Option<Vec<F>>,
+    sponge: &mut S,
+) -> (SumcheckProof<F>, Vec<F>)
+where
+    F: PrimeField + Absorb,
+    S: CryptographicSponge,
+{
+    let degree = polynomial.degree();
+    let mut polynomial = polynomial.clone();
+    let mut round_polynomials = Vec::with_capacity(polynomial.number_of_variables);
+    let mut point = Vec::with_capacity(polynomial.number_of_variables);
+
+    while polynomial.number_of_variables > 0 {
+        let mut round_polynomial = polynomial.round_polynomial(degree);
+        if let Some(mask) = mask(point.len(), &point) {
+            for (value, mask) in round_polynomial.iter_mut().zip(mask) {
+                *value += mask;
+            }
+        }
+        sponge.absorb(&round_polynomial);
+        let challenge = sponge.squeeze_field_elements(1)[0];
+
+        polynomial = polynomial.fix_variable(challenge);
+        round_polynomials.push(round_polynomial);
+        point.push(challenge);
+    }
+
+    (SumcheckProof { round_polynomials }, point)
+}
+
+/// Verifies that a polynomial of the given degree and number of variables sums to `claimed_sum`
+/// over the boolean hypercube, drawing the challenges from `sponge`. Returns the evaluation claim
+/// left to check, or an error if the proof is rejected.
+pub fn verify_sumcheck<F, S>(
+    claimed_sum: F,
+    degree: usize,
+    number_of_variables: usize,
+    proof: &SumcheckProof<F>,
+    sponge: &mut S,
+) -> Result<SumcheckSubClaim<F>, SangriaError>
+where
+    F: PrimeField + Absorb,
+    S: CryptographicSponge,
+{
+    if proof.round_polynomials.len() != number_of_variables {
+        return Err(SangriaError::InvalidProof);
+    }
+
+    let mut expected_evaluation = claimed_sum;
+    let mut point = Vec::with_capacity(number_of_variables);
+    for round_polynomial in proof.round_polynomials.iter() {
+        if round_polynomial.len() != degree + 1
+            || round_polynomial[0] + round_polynomial[1] != expected_evaluation
+        {
+            return Err(SangriaError::InvalidProof);
+        }
+
+        sponge.absorb(round_polynomial);
+        let challenge = sponge.squeeze_field_elements(1)[0];
+
+        expected_evaluation = interpolate(round_polynomial, challenge);
+        point.push(challenge);
+    }
+
+    Ok(SumcheckSubClaim {
+        point,
+        expected_evaluation,
+    })
+}
+
+/// A masking polynomial for a zero-knowledge sumcheck: a constant plus one univariate polynomial
+/// without constant term per variable, `c + sum_i g_i(x_i)`. Adding a random multiple of a random
+/// mask to the summed polynomial makes the round polynomials independent of it, except for its
+/// evaluation at the final point. The mask is a vector of coefficients, so a homomorphic commitment
+/// to it is opened at a point as an inner product with [`SumcheckMask::evaluation_vector`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SumcheckMask<F: PrimeField> {
+    number_of_variables: usize,
+    degree: usize,
+    /// The constant, then the coefficients of `x_i, x_i^2, ..., x_i^degree` for each variable.
+    coefficients: Vec<F>,
+}
+
+impl<F: PrimeField> SumcheckMask<F> {
+    /// Samples a mask in `number_of_variables` variables of the given degree in each variable.
+    pub fn rand<R: Rng>(rng: &mut R, number_of_variables: usize, degree: usize) -> Self {
+        Self {
+            number_of_variables,
+            degree,
+            coefficients: (0..1 + number_of_variables * degree)
+                .map(|_| F::rand(rng))
+                .collect(),
+        }
+    }
+
+    /// Returns the coefficients of the mask, the vector to commit to.
+    pub fn coefficients(&self) -> &[F] {
+        &self.coefficients
+    }
+
+    /// Returns the public vector whose inner product with the coefficients of a mask of the given
+    /// degree is its evaluation at `point`.
+    pub fn evaluation_vector(point: &[F], degree: usize) -> Vec<F> {
+        let mut vector = vec![F::one()];
+        for &coordinate in point {
+            vector.extend((1..=degree).scan(F::one(), |power, _| {
+                *power *= coordinate;
+                Some(*power)
+            }));
+        }
+
+        vector
+    }
+
+    /// Evaluates the mask at `point` or returns an error if the point has the wrong size.
+    pub fn evaluate(&self, point: &[F]) -> Result<F, SangriaError> {
+        if point.len() != self.number_of_variables {
+            return Err(SangriaError::IndexOutOfBounds);
+        }
+
+        Ok(Self::evaluation_vector(point, self.degree)
+            .iter()
+            .zip(&self.coefficients)
+            .map(|(&power, &coefficient)| power * coefficient)
+            .sum())
+    }
+
+    /// Returns the sum of the mask over the boolean hypercube.
+    pub fn sum_over_hypercube(&self) -> F {
+        if self.number_of_variables == 0 {
+            return self.coefficients[0];
+        }
+
+        let half = F::from(1u64 << (self.number_of_variables - 1));
+        half * (self.coefficients[0].double()
+            + (0..self.number_of_variables)
+                .map(|variable| self.at_one(variable))
+                .sum::<F>())
+    }
+
+    /// Evaluates `g_variable` at `x`.
+    fn univariate(&self, variable: usize, x: F) -> F {
+        self.coefficients[1 + variable * self.degree..1 + (variable + 1) * self.degree]
+            .iter()
+            .rev()
+            .fold(F::zero(), |value, &coefficient| (value + coefficient) * x)
+    }
+
+    /// Evaluates `g_variable` at one.
+    fn at_one(&self, variable: usize) -> F {
+        self.univariate(variable, F::one())
+    }
+
+    /// Returns the evaluations at `0, 1, ..., degree` of the round polynomial of the mask in round
+    /// `round`, once the earlier variables are fixed to `challenges`.
+    fn round_polynomial(&self, round: usize, challenges: &[F]) -> Vec<F> {
+        let remaining = 1u64 << (self.number_of_variables - round - 1);
+        let fixed = self.coefficients[0]
+            + challenges
+                .iter()
+                .enumerate()
+                .map(|(variable, &challenge)| self.univariate(variable, challenge))
+                .sum::<F>();
+        let later = F::from(remaining / 2)
+            * (round + 1..self.number_of_variables)
+                .map(|variable| self.at_one(variable))
+                .sum::<F>();
+
+        (0..=self.degree)
+            .map(|t| {
+                F::from(remaining) * (fixed + self.univariate(round, F::from(t as u64))) + later
+            })
+            .collect()
+    }
+}
+
+/// A zero-knowledge sumcheck proof: the sum of the committed mask, the sumcheck proof of the
+/// polynomial plus a random multiple of the mask, and the evaluation of the mask at the point the
+/// sum was reduced to.
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct MaskedSumcheckProof<F: PrimeField> {
+    mask_sum: F,
+    sumcheck_proof: SumcheckProof<F>,
+    mask_evaluation: F,
+}
+
+/// What remains to be checked after a zero-knowledge sumcheck: the polynomial must evaluate to
+/// `expected_evaluation` at `point`, and the committed mask to `mask_evaluation`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MaskedSumcheckSubClaim<F: PrimeField> {
+    /// The random point the sum was reduced to.
+    pub point: Vec<F>,
+    /// The evaluation of the polynomial at `point` implied by the proof.
+    pub expected_evaluation: F,
+    /// The evaluation of the mask at `point` claimed by the prover.
+    pub mask_evaluation: F,
+}
+
+/// Proves the sum of `polynomial` over the boolean hypercube without revealing anything else about
+/// it than its evaluation at the final point, drawing the challenges from `sponge`. The caller
+/// commits to the coefficients of `mask`, absorbs the commitment into `sponge` beforehand and opens
+/// it at the returned point. Returns the proof and the point, or an error if the mask does not
+/// have the number of variables and the degree of the polynomial.
+pub fn prove_masked_sumcheck<F, S>(
+    polynomial: &VirtualPolynomial<F>,
+    mask: &SumcheckMask<F>,
+    sponge: &mut S,
+) -> Result<(MaskedSumcheckProof<F>, Vec<F>), SangriaError>
+where
+    F: PrimeField + Absorb,
+    S: CryptographicSponge,
+{
+    if mask.number_of_variables != polynomial.number_of_variables
+        || mask.degree != polynomial.degree()
+    {
+        return Err(SangriaError::IndexOutOfBounds);
+    }
+
+    let mask_sum = mask.sum_over_hypercube();
+    sponge.absorb(&mask_sum);
+    let rho: F = sponge.squeeze_field_elements(1)[0];
+    let (sumcheck_proof, point) = run_sumcheck(
+        polynomial,
+        |round, challenges| {
+            Some(
+                mask.round_polynomial(round, challenges)
+                    .into_iter()
+                    .map(|value| rho * value)
+                    .collect(),
+            )
+        },
+        sponge,
+    );
+    let mask_evaluation = mask.evaluate(&point)?;
+    sponge.absorb(&mask_evaluation);
+
+    Ok((
+        MaskedSumcheckProof {
+            mask_sum,
+            sumcheck_proof,
+            mask_evaluation,
+        },
+        point,
+    ))
+}
+
+/// Verifies that a polynomial of the given degree and number of variables sums to `claimed_sum`
+/// over the boolean hypercube, with a proof of [`prove_masked_sumcheck`] drawing the challenges
+/// from `sponge`, into which the commitment to the mask must have been absorbed. Returns the
+/// evaluation claims left to check on the polynomial and the mask, or an error if the proof is
+/// rejected.
+pub fn verify_masked_sumcheck<F, S>(
+    claimed_sum: F,
+    degree: usize,
+    number_of_variables: usize,
+    proof: &MaskedSumcheckProof<F>,
+    sponge: &mut S,
+) -> Result<MaskedSumcheckSubClaim<F>, SangriaError>
+where
+    F: PrimeField + Absorb,
+    S: CryptographicSponge,
+{
+    sponge.absorb(&proof.mask_sum);
+    let rho: F = sponge.squeeze_field_elements(1)[0];
+    let subclaim = verify_sumcheck(
+        claimed_sum + rho * proof.mask_sum,
+        degree,
+        number_of_variables,
+        &proof.sumcheck_proof,
+        sponge,
+    )?;
+    sponge.absorb(&proof.mask_evaluation);
+
+    Ok(MaskedSumcheckSubClaim {
+        point: subclaim.point,
+        expected_evaluation: subclaim.expected_evaluation - rho * proof.mask_evaluation,
+        mask_evaluation: proof.mask_evaluation,
+    })
+}
+
+/// What remains to be checked after a batched sumcheck: the combination of the polynomials with
+/// `coefficients` must evaluate to `expected_evaluation` at `point`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BatchedSumcheckSubClaim<F: PrimeField> {
+    /// The coefficients the polynomials were combined with, in the order of the claims.
+    pub coefficients: Vec<F>,
+    /// The random point the sums were reduced to.
+    pub point: Vec<F>,
+    /// The evaluation of the combination at `point` implied by the proof.
+    pub expected_evaluation: F,
+}
+
+/// The sums proved by a batched sumcheck, its proof and the point the sums were reduced to.
+type BatchedSumcheckOutput<F> = (Vec<F>, SumcheckProof<F>, Vec<F>);
+
+/// Proves the sums of several polynomials in the same number of variables over the boolean
+/// hypercube with a single sumcheck on their random combination, drawing the challenges from
+/// `sponge`. The sums are absorbed before the coefficients of the combination are squeezed.
+/// Returns the sums, the proof and the point the sums were reduced to, or an error if there is no
+/// polynomial or if their numbers of variables differ.
+pub fn prove_batched_sumcheck<F, S>(
+    polynomials: &[VirtualPolynomial<F>],
+    sponge: &mut S,
+) -> Result<BatchedSumcheckOutput<F>, SangriaError>
+where
+    F: PrimeField + Absorb,
+    S: CryptographicSponge,
+{
+    let number_of_variables = batch_variables(polynomials.iter().map(|p| p.number_of_variables))?;
+    let sums: Vec<F> = polynomials
+        .iter()
+        .map(VirtualPolynomial::sum_over_hypercube)
+        .collect();
+    sponge.absorb(&sums);
+    let coefficients: Vec<F> = sponge.squeeze_field_elements(polynomials.len());
+
+    let combination = VirtualPolynomial {
+        number_of_variables,
+        products: polynomials
+            .iter()
+            .zip(&coefficients)
+            .flat_map(|(polynomial, &coefficient)| {
+                polynomial
+                    .products
+                    .iter()
+                    .map(move |(product_coefficient, extensions)| {
+                        (coefficient * product_coefficient, extensions.clone())
+                    })
+            })
+            .collect(),
+    };
+    let (proof, point) = prove_sumcheck(&combination, sponge);
+
+    Ok((sums, proof, point))
+}
+
+/// Verifies that polynomials of degree at most `degree` in `number_of_variables` variables sum to
+/// `claimed_sums` over the boolean hypercube, with a proof of [`prove_batched_sumcheck`] drawing the
+/// challenges from `sponge`. Returns the evaluation claim on their combination left to check, or an
+/// error if there is no claim or if the proof is rejected.
+pub fn verify_batched_sumcheck<F, S>(
+    claimed_sums: &[F],
+    degree: usize,
+    number_of_variables: usize,
+    proof: &SumcheckProof<F>,
+    sponge: &mut S,
+) -> Result<BatchedSumcheckSubClaim<F>, SangriaError>
+where
+    F: PrimeField + Absorb,
+    S: CryptographicSponge,
+{
+    if claimed_sums.is_empty() {
+        return Err(SangriaError::InvalidProof);
+    }
+
+    sponge.absorb(&claimed_sums);
+    let coefficients: Vec<F> = sponge.squeeze_field_elements(claimed_sums.len());
+    let claimed_sum = claimed_sums
+        .iter()
+        .zip(&coefficients)
+        .map(|(&sum, &coefficient)| coefficient * sum)
+        .sum();
+    let subclaim = verify_sumcheck(claimed_sum, degree, number_of_variables, proof, sponge)?;
+
+    Ok(BatchedSumcheckSubClaim {
+        coefficients,
+        point: subclaim.point,
+        expected_evaluation: subclaim.expected_evaluation,
+    })
+}
+
+/// Returns the number of variables shared by the polynomials of a batch, or an error if there is
+/// no polynomial or if their numbers of variables differ.
+fn batch_variables(
+    mut numbers_of_variables: impl Iterator<Item = usize>,
+) -> Result<usize, SangriaError> {
+    let first = numbers_of_variables
+        .next()
+        .ok_or(SangriaError::IndexOutOfBounds)?;
+    if numbers_of_variables.any(|number| number != first) {
+        return Err(SangriaError::IndexOutOfBounds);
+    }
+
+    Ok(first)
+}
+
+/// Evaluates at `x` the polynomial taking the values `evaluations` at `0, 1, ..., n - 1`.
+fn interpolate<F: PrimeField>(evaluations: &[F], x: F) -> F {
+    let nodes: Vec<F> = (0..evaluations.len())
+        .map(|node| F::from(node as u64))
+        .collect();
+
+    evaluations
+        .iter()
+        .enumerate()
+        .map(|(i, &evaluation)| {
+            let (numerator, denominator) = nodes.iter().enumerate().filter(|(j, _)| *j != i).fold(
+                (F::one(), F::one()),
+                |(numerator, denominator), (_, &node)| {
+                    (numerator * (x - node), denominator * (nodes[i] - node))
+                },
+            );
+            evaluation * numerator * denominator.inverse().expect("the nodes are distinct")
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        prove_batched_sumcheck, prove_masked_sumcheck, prove_sumcheck, verify_batched_sumcheck,
+        verify_masked_sumcheck, verify_sumcheck, SumcheckMask, VirtualPolynomial,
+    };
+    use crate::{
+        utils::{poseidon::poseidon_parameters_for_test, rand::sample_vector},
+        MultilinearExtension, SangriaError,
+    };
+    use ark_bn254::Fr;
+    use ark_crypto_primitives::sponge::{poseidon::PoseidonSponge, CryptographicSponge};
+    use ark_ff::{One, UniformRand};
+    use ark_std::{rand::Rng, test_rng};
+
+    fn random_extension<R: Rng>(
+        rng: &mut R,
+        number_of_variables: usize,
+    ) -> MultilinearExtension<Fr> {
+        MultilinearExtension::from_column(&sample_vector(rng, 1 << number_of_variables))
+    }
+
+    fn random_polynomial<R: Rng>(rng: &mut R) -> VirtualPolynomial<Fr> {
+        let mut polynomial = VirtualPolynomial::new(4);
+        let (a, b, c) = (
+            random_extension(rng, 4),
+            random_extension(rng, 4),
+            random_extension(rng, 4),
+        );
+        polynomial
+            .add_product(Fr::rand(rng), vec![a.clone(), b, c])
+            .unwrap();
+        polynomial.add_product(Fr::rand(rng), vec![a]).unwrap();
+        polynomial.add_product(Fr::one(), vec![]).unwrap();
+        polynomial
+    }
+
+    #[test]
+    fn honest_sumcheck_verifies() {
+        let rng = &mut test_rng();
+        let params = poseidon_parameters_for_test::<Fr>();
+        let polynomial = random_polynomial(rng);
+        let sum = polynomial.sum_over_hypercube();
+
+        let (proof, point) = prove_sumcheck(&polynomial, &mut PoseidonSponge::new(&params));
+        let subclaim = verify_sumcheck(
+            sum,
+            polynomial.degree(),
+            4,
+            &proof,
+            &mut PoseidonSponge::new(&params),
+        )
+        .unwrap();
+
+        assert_eq!(subclaim.point, point);
+        assert_eq!(
+            polynomial.evaluate(&subclaim.point),
+            Ok(subclaim.expected_evaluation)
+        );
+    }
+
+    #[test]
+    fn wrong_sum_is_rejected() {
+        let rng = &mut test_rng();
+        let params = poseidon_parameters_for_test::<Fr>();
+        let polynomial = random_polynomial(rng);
+        let sum = polynomial.sum_over_hypercube();
+
+        let (proof, _) = prove_sumcheck(&polynomial, &mut PoseidonSponge::new(&params));
+
+        assert_eq!(
+            verify_sumcheck(
+                sum + Fr::one(),
+                polynomial.degree(),
+                4,
+                &proof,
+                &mut PoseidonSponge::new(&params)
+            ),
+            Err(SangriaError::InvalidProof)
+        );
+    }
+
+    #[test]
+    fn batched_sumcheck_verifies_every_claim() {
+        let rng = &mut test_rng();
+        let params = poseidon_parameters_for_test::<Fr>();
+        let polynomials = [random_polynomial(rng), random_polynomial(rng)];
+        let degree = polynomials[0].degree();
+
+        let (sums, proof, point) =
+            prove_batched_sumcheck(&polynomials, &mut PoseidonSponge::new(&params)).unwrap();
+        let subclaim =
+            verify_batched_sumcheck(&sums, degree, 4, &proof, &mut PoseidonSponge::new(&params))
+                .unwrap();
+        assert_eq!(subclaim.point, point);
+        let combination: Fr = polynomials
+            .iter()
+            .zip(&subclaim.coefficients)
+            .map(|(polynomial, &coefficient)| coefficient * polynomial.evaluate(&point).unwrap())
+            .sum();
+        assert_eq!(combination, subclaim.expected_evaluation);
+
+        let wrong_sums = [sums[0], sums[1] + Fr::one()];
+        assert_eq!(
+            verify_batched_sumcheck(
+                &wrong_sums,
+                degree,
+                4,
+                &proof,
+                &mut PoseidonSponge::new(&params)
+            ),
+            Err(SangriaError::InvalidProof)
+        );
+        assert!(prove_batched_sumcheck(
+            &[polynomials[0].clone(), VirtualPolynomial::new(3)],
+            &mut PoseidonSponge::new(&params)
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn masked_sumcheck_verifies() {
+        let rng = &mut test_rng();
+        let params = poseidon_parameters_for_test::<Fr>();
+        let polynomial = random_polynomial(rng);
+        let sum = polynomial.sum_over_hypercube();
+        let mask = SumcheckMask::rand(rng, 4, polynomial.degree());
+        let hypercube_sum: Fr = (0..16u64)
+            .map(|index| {
+                let point: Vec<Fr> = (0..4).map(|bit| Fr::from((index >> bit) & 1)).collect();
+                mask.evaluate(&point).unwrap()
+            })
+            .sum();
+        assert_eq!(mask.sum_over_hypercube(), hypercube_sum);
+
+        let (proof, point) =
+            prove_masked_sumcheck(&polynomial, &mask, &mut PoseidonSponge::new(&params)).unwrap();
+        let subclaim = verify_masked_sumcheck(
+            sum,
+            polynomial.degree(),
+            4,
+            &proof,
+            &mut PoseidonSponge::new(&params),
+        )
+        .unwrap();
+
+        assert_eq!(subclaim.point, point);
+        assert_eq!(
+            polynomial.evaluate(&subclaim.point),
+            Ok(subclaim.expected_evaluation)
+        );
+        assert_eq!(mask.evaluate(&point), Ok(subclaim.mask_evaluation));
+        let evaluation_vector = SumcheckMask::evaluation_vector(&point, polynomial.degree());
+        let inner_product: Fr = evaluation_vector
+            .iter()
+            .zip(mask.coefficients())
+            .map(|(&power, &coefficient)| power * coefficient)
+            .sum();
+        assert_eq!(inner_product, subclaim.mask_evaluation);
+
+        assert_eq!(
+            verify_masked_sumcheck(
+                sum + Fr::one(),
+                polynomial.degree(),
+                4,
+                &proof,
+                &mut PoseidonSponge::new(&params)
+            ),
+            Err(SangriaError::InvalidProof)
+        );
+        assert!(prove_masked_sumcheck(
+            &polynomial,
+            &SumcheckMask::rand(rng, 4, polynomial.degree() + 1),
+            &mut PoseidonSponge::new(&params)
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,237 @@
+use ark_crypto_primitives::sponge::{Absorb, CryptographicSponge};
+use ark_ff::PrimeField;
+
+use crate::{
+    piop::{
+        prove_masked_sumcheck, prove_sumcheck, verify_masked_sumcheck, verify_sumcheck,
+        MaskedSumcheckProof, MaskedSumcheckSubClaim, SumcheckMask, SumcheckProof,
+        VirtualPolynomial,
+    },
+    MultilinearExtension, SangriaError,
+};
+
+/// What remains to be checked after a zero check: the polynomial must evaluate to
+/// `expected_evaluation` at `point`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ZeroCheckSubClaim<F: PrimeField> {
+    /// The random point the check was reduced to.
+    pub point: Vec<F>,
+    /// The evaluation of the polynomial at `point` implied by the proof.
+    pub expected_evaluation: F,
+}
+
+/// Returns the multilinear extension of the equality function `eq(x, r)`, which is one at `x = r`
+/// and zero elsewhere on the hypercube.
+pub(crate) fn eq_extension<F: PrimeField>(r: &[F]) -> MultilinearExtension<F> {
+    // each coordinate of `r` adds the next bit of the hypercube index
+    let mut evaluations = vec![F::one()];
+    for &coordinate in r.iter() {
+        evaluations = evaluations
+            .iter()
+            .map(|&value| value * (F::one() - coordinate))
+            .chain(evaluations.iter().map(|&value| value * coordinate))
+            .collect();
+    }
+
+    MultilinearExtension::from_column(&evaluations)
+}
+
+/// Evaluates `eq(x, r)` at `x = point`.
+pub(crate) fn eq_evaluation<F: PrimeField>(point: &[F], r: &[F]) -> F {
+    point
+        .iter()
+        .zip(r.iter())
+        .map(|(&x, &r)| x * r + (F::one() - x) * (F::one() - r))
+        .product()
+}
+
+/// Proves that `polynomial` is zero on the whole boolean hypercube, by a sumcheck of
+/// `polynomial(x) * eq(x, r)` for a random `r` drawn from `sponge`. Returns the proof and the point
+/// the check was reduced to.
+pub fn prove_zero_check<F, S>(
+    polynomial: &VirtualPolynomial<F>,
+    sponge: &mut S,
+) -> Result<(SumcheckProof<F>, Vec<F>), SangriaError>
+where
+    F: PrimeField + Absorb,
+    S: CryptographicSponge,
+{
+    let r: Vec<F> = sponge.squeeze_field_elements(polynomial.number_of_variables());
+    let masked = polynomial.mul_extension(&eq_extension(&r))?;
+
+    Ok(prove_sumcheck(&masked, sponge))
+}
+
+/// Verifies that a polynomial of the given degree and number of variables is zero on the boolean
+/// hypercube. Returns the evaluation claim left to check, or an error if the proof is rejected.
+pub fn verify_zero_check<F, S>(
+    degree: usize,
+    number_of_variables: usize,
+    proof: &SumcheckProof<F>,
+    sponge: &mut S,
+) -> Result<ZeroCheckSubClaim<F>, SangriaError>
+where
+    F: PrimeField + Absorb,
+    S: CryptographicSponge,
+{
+    let r: Vec<F> = sponge.squeeze_field_elements(number_of_variables);
+    let subclaim = verify_sumcheck(F::zero(), degree + 1, number_of_variables, proof, sponge)?;
+
+    let eq = eq_evaluation(&subclaim.point, &r)
+        .inverse()
+        .ok_or(SangriaError::InvalidProof)?;
+
+    Ok(ZeroCheckSubClaim {
+        expected_evaluation: subclaim.expected_evaluation * eq,
+        point: subclaim.point,
+    })
+}
+
+/// Proves that `polynomial` is zero on the whole boolean hypercube like [`prove_zero_check`], with a
+/// zero-knowledge sumcheck masked by `mask`, whose degree must be one more than the polynomial's.
+/// The caller commits to the mask and absorbs the commitment into `sponge` beforehand. Returns the
+/// proof and the point the check was reduced to.
+pub fn prove_masked_zero_check<F, S>(
+    polynomial: &VirtualPolynomial<F>,
+    mask: &SumcheckMask<F>,
+    sponge: &mut S,
+) -> Result<(MaskedSumcheckProof<F>, Vec<F>), SangriaError>
+where
+    F: PrimeField + Absorb,
+    S: CryptographicSponge,
+{
+    let r: Vec<F> = sponge.squeeze_field_elements(polynomial.number_of_variables());
+    let masked = polynomial.mul_extension(&eq_extension(&r))?;
+
+    prove_masked_sumcheck(&masked, mask, sponge)
+}
+
+/// Verifies a zero check proved by [`prove_masked_zero_check`]. Returns the evaluation claims left
+/// to check on the polynomial and the mask, or an error if the proof is rejected.
+pub fn verify_masked_zero_check<F, S>(
+    degree: usize,
+    number_of_variables: usize,
+    proof: &MaskedSumcheckProof<F>,
+    sponge: &mut S,
+) -> Result<MaskedSumcheckSubClaim<F>, SangriaError>
+where
+    F: PrimeField + Absorb,
+    S: CryptographicSponge,
+{
+    let r: Vec<F> = sponge.squeeze_field_elements(number_of_variables);
+    let subclaim =
+        verify_masked_sumcheck(F::zero(), degree + 1, number_of_variables, proof, sponge)?;
+
+    let eq = eq_evaluation(&subclaim.point, &r)
+        .inverse()
+        .ok_or(SangriaError::InvalidProof)?;
+
+    Ok(MaskedSumcheckSubClaim {
+        expected_evaluation: subclaim.expected_evaluation * eq,
+        ..subclaim
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        eq_evaluation, eq_extension, prove_masked_zero_check, prove_zero_check,
+        verify_masked_zero_check, verify_zero_check,
+    };
+    use crate::{
+        piop::{SumcheckMask, VirtualPolynomial},
+        utils::{poseidon::poseidon_parameters_for_test, rand::sample_vector},
+        MultilinearExtension, SangriaError,
+    };
+    use ark_bn254::Fr;
+    use ark_crypto_primitives::sponge::{poseidon::PoseidonSponge, CryptographicSponge};
+    use ark_ff::One;
+    use ark_std::test_rng;
+
+    #[test]
+    fn eq_extension_matches_its_evaluation() {
+        let rng = &mut test_rng();
+        let (r, point): (Vec<Fr>, Vec<Fr>) = (sample_vector(rng, 3), sample_vector(rng, 3));
+
+        assert_eq!(
+            eq_extension(&r).evaluate(&point),
+            Ok(eq_evaluation(&point, &r))
+        );
+    }
+
+    /// Builds `a * b - c` where `c = a * b` entrywise, plus `offset` on the first entry of `c`.
+    fn product_constraint(offset: Fr) -> VirtualPolynomial<Fr> {
+        let rng = &mut test_rng();
+        let (a, b): (Vec<Fr>, Vec<Fr>) = (sample_vector(rng, 8), sample_vector(rng, 8));
+        let mut c: Vec<Fr> = a.iter().zip(b.iter()).map(|(&a, &b)| a * b).collect();
+        c[0] += offset;
+
+        let mut polynomial = VirtualPolynomial::new(3);
+        polynomial
+            .add_product(
+                Fr::one(),
+                vec![
+                    MultilinearExtension::from_column(&a),
+                    MultilinearExtension::from_column(&b),
+                ],
+            )
+            .unwrap();
+        polynomial
+            .add_product(-Fr::one(), vec![MultilinearExtension::from_column(&c)])
+            .unwrap();
+        polynomial
+    }
+
+    #[test]
+    fn zero_polynomial_passes() {
+        let params = poseidon_parameters_for_test::<Fr>();
+        let polynomial = product_constraint(Fr::from(0u64));
+
+        let (proof, _) = prove_zero_check(&polynomial, &mut PoseidonSponge::new(&params)).unwrap();
+        let subclaim = verify_zero_check(2, 3, &proof, &mut PoseidonSponge::new(&params)).unwrap();
+
+        assert_eq!(
+            polynomial.evaluate(&subclaim.point),
+            Ok(subclaim.expected_evaluation)
+        );
+    }
+
+    #[test]
+    fn nonzero_polynomial_fails() {
+        let params = poseidon_parameters_for_test::<Fr>();
+        let polynomial = product_constraint(Fr::one());
+
+        let (proof, _) = prove_zero_check(&polynomial, &mut PoseidonSponge::new(&params)).unwrap();
+
+        assert_eq!(
+            verify_zero_check(2, 3, &proof, &mut PoseidonSponge::new(&params)),
+            Err(SangriaError::InvalidProof)
+        );
+    }
+
+    #[test]
+    fn masked_zero_check_passes_only_for_zero_polynomial() {
+        let rng = &mut test_rng();
+        let params = poseidon_parameters_for_test::<Fr>();
+        let mask = SumcheckMask::rand(rng, 3, 3);
+
+        let polynomial = product_constraint(Fr::from(0u64));
+        let (proof, _) =
+            prove_masked_zero_check(&polynomial, &mask, &mut PoseidonSponge::new(&params)).unwrap();
+        let subclaim =
+            verify_masked_zero_check(2, 3, &proof, &mut PoseidonSponge::new(&params)).unwrap();
+        assert_eq!(
+            polynomial.evaluate(&subclaim.point),
+            Ok(subclaim.expected_evaluation)
+        );
+        assert_eq!(mask.evaluate(&subclaim.point), Ok(subclaim.mask_evaluation));
+
+        let polynomial = product_constraint(Fr::one());
+        let (proof, _) =
+            prove_masked_zero_check(&polynomial, &mask, &mut PoseidonSponge::new(&params)).unwrap();
+        assert_eq!(
+            verify_masked_zero_check(2, 3, &proof, &mut PoseidonSponge::new(&params)),
+            Err(SangriaError::InvalidProof)
+        );
+    }
+}
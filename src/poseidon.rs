@@ -0,0 +1,319 @@
+use ark_crypto_primitives::sponge::poseidon::PoseidonConfig;
+use ark_ff::PrimeField;
+
+use crate::{
+    trace::NUMBER_OF_SELECTORS, RowTag, SangriaError, TraceTable, CONSTANT_SELECTOR_INDEX,
+    LEFT_SELECTOR_INDEX, MULTIPLICATION_SELECTOR_INDEX, OUTPUT_SELECTOR_INDEX,
+    RIGHT_SELECTOR_INDEX,
+};
+
+/// A wire of a trace, given as (column, row).
+type Wire = (usize, usize);
+
+/// Hashes `inputs` with the Poseidon sponge of `constants`: the inputs are absorbed `rate` at a time
+/// into a zero state, permuting after each block, and the first rate element of the state is
+/// squeezed. This is the value squeezed by the Poseidon sponge of `ark-crypto-primitives` after
+/// absorbing the same field elements, and the value of the wire returned by
+/// [`poseidon_hash_gadget`].
+pub fn poseidon_hash<F: PrimeField>(constants: &PoseidonConfig<F>, inputs: &[F]) -> F {
+    let mut state = vec![F::zero(); constants.rate + constants.capacity];
+    for block in inputs.chunks(constants.rate) {
+        for (element, &input) in state[constants.capacity..].iter_mut().zip(block) {
+            *element += input;
+        }
+        permute(constants, &mut state);
+    }
+    if inputs.is_empty() {
+        permute(constants, &mut state);
+    }
+
+    state[constants.capacity]
+}
+
+/// Applies the Poseidon permutation to `state`: full rounds, then partial rounds applying the S-box
+/// to the first element only, then full rounds again, each adding the round constants, applying
+/// the S-box and multiplying by the MDS matrix.
+fn permute<F: PrimeField>(constants: &PoseidonConfig<F>, state: &mut [F]) {
+    for (round, round_constants) in constants.ark.iter().enumerate() {
+        for (element, &constant) in state.iter_mut().zip(round_constants) {
+            *element += constant;
+        }
+        let sbox_width = if is_full_round(constants, round) {
+            state.len()
+        } else {
+            1
+        };
+        for element in state[..sbox_width].iter_mut() {
+            *element = element.pow([constants.alpha]);
+        }
+        let mixed: Vec<F> = constants
+            .mds
+            .iter()
+            .map(|row| row.iter().zip(state.iter()).map(|(&m, &s)| m * s).sum())
+            .collect();
+        state.copy_from_slice(&mixed);
+    }
+}
+
+/// Returns true if `round` is one of the full rounds, which come in two halves around the partial
+/// rounds.
+fn is_full_round<F: PrimeField>(constants: &PoseidonConfig<F>, round: usize) -> bool {
+    let half = constants.full_rounds / 2;
+    round < half || round >= half + constants.partial_rounds
+}
+
+/// Hashes the values of the `inputs` wires of `trace` in-circuit with the Poseidon sponge of
+/// `constants`, as [`poseidon_hash`] does natively, and returns the wire holding the hash. The zero
+/// initial state is laid out with constant gates, each round adds its constants and multiplies by
+/// the MDS matrix with addition gates, and each S-box raises its input to the power `alpha` by
+/// square-and-multiply with multiplication gates.
+pub fn poseidon_hash_gadget<F: PrimeField>(
+    trace: &mut TraceTable<F>,
+    constants: &PoseidonConfig<F>,
+    inputs: &[Wire],
+) -> Result<Wire, SangriaError> {
+    let mut state = (0..constants.rate + constants.capacity)
+        .map(|_| linear_combination(trace, &[], F::zero()))
+        .collect::<Result<Vec<_>, _>>()?;
+    for block in inputs.chunks(constants.rate) {
+        for (element, &input) in state[constants.capacity..].iter_mut().zip(block) {
+            *element =
+                linear_combination(trace, &[(F::one(), *element), (F::one(), input)], F::zero())?;
+        }
+        permute_gadget(trace, constants, &mut state)?;
+    }
+    if inputs.is_empty() {
+        permute_gadget(trace, constants, &mut state)?;
+    }
+
+    Ok(state[constants.capacity])
+}
+
+/// Applies the Poseidon permutation in-circuit to the wires of `state`, like [`permute`].
+fn permute_gadget<F: PrimeField>(
+    trace: &mut TraceTable<F>,
+    constants: &PoseidonConfig<F>,
+    state: &mut [Wire],
+) -> Result<(), SangriaError> {
+    for (round, round_constants) in constants.ark.iter().enumerate() {
+        for (element, &constant) in state.iter_mut().zip(round_constants) {
+            *element = linear_combination(trace, &[(F::one(), *element)], constant)?;
+        }
+        let sbox_width = if is_full_round(constants, round) {
+            state.len()
+        } else {
+            1
+        };
+        for element in state[..sbox_width].iter_mut() {
+            *element = power(trace, *element, constants.alpha)?;
+        }
+        let mixed = constants
+            .mds
+            .iter()
+            .map(|row| {
+                let terms: Vec<(F, Wire)> =
+                    row.iter().copied().zip(state.iter().copied()).collect();
+                linear_combination(trace, &terms, F::zero())
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        state.copy_from_slice(&mixed);
+    }
+
+    Ok(())
+}
+
+/// Appends gates constraining a new wire to `constant + c_1 w_1 + ... + c_k w_k` for the
+/// `(c_i, w_i)` in `terms` and returns it. The first gate adds up to two terms and the constant,
+/// and every further term costs one gate.
+pub(crate) fn linear_combination<F: PrimeField>(
+    trace: &mut TraceTable<F>,
+    terms: &[(F, Wire)],
+    constant: F,
+) -> Result<Wire, SangriaError> {
+    let Some((&first, rest)) = terms.split_first() else {
+        // a left wire holding the constant
+        let mut selectors = [F::zero(); NUMBER_OF_SELECTORS];
+        selectors[LEFT_SELECTOR_INDEX] = F::one();
+        selectors[CONSTANT_SELECTOR_INDEX] = -constant;
+        let row = trace.number_of_rows();
+        trace.push_row(RowTag::Gate(selectors), [constant, F::zero(), F::zero()]);
+
+        return Ok((0, row));
+    };
+
+    let (second, rest) = match rest.split_first() {
+        Some((&second, rest)) => (Some(second), rest),
+        None => (None, rest),
+    };
+    let mut sum = addition_gate(trace, first, second, constant)?;
+    for &term in rest {
+        sum = addition_gate(trace, (F::one(), sum), Some(term), F::zero())?;
+    }
+
+    Ok(sum)
+}
+
+/// Appends a gate `c_L a + c_R b + constant - c = 0` adding the terms `(c_L, a)` and `(c_R, b)`
+/// (the latter being optional) and returns its output wire.
+fn addition_gate<F: PrimeField>(
+    trace: &mut TraceTable<F>,
+    left: (F, Wire),
+    right: Option<(F, Wire)>,
+    constant: F,
+) -> Result<Wire, SangriaError> {
+    let left_value = trace.wire(left.1 .0, left.1 .1)?;
+    let (right_coefficient, right_value) = match right {
+        Some((coefficient, wire)) => (coefficient, trace.wire(wire.0, wire.1)?),
+        None => (F::zero(), F::zero()),
+    };
+    let mut selectors = [F::zero(); NUMBER_OF_SELECTORS];
+    selectors[LEFT_SELECTOR_INDEX] = left.0;
+    selectors[RIGHT_SELECTOR_INDEX] = right_coefficient;
+    selectors[OUTPUT_SELECTOR_INDEX] = -F::one();
+    selectors[CONSTANT_SELECTOR_INDEX] = constant;
+
+    let row = trace.number_of_rows();
+    trace.push_row(
+        RowTag::Gate(selectors),
+        [
+            left_value,
+            right_value,
+            left.0 * left_value + right_coefficient * right_value + constant,
+        ],
+    );
+    trace.copy(left.1, (0, row));
+    if let Some((_, wire)) = right {
+        trace.copy(wire, (1, row));
+    }
+
+    Ok((2, row))
+}
+
+/// Appends the multiplication gates raising the value of `base` to the power `exponent`, which must
+/// be positive, by square-and-multiply from the most significant bit, and returns the output wire.
+fn power<F: PrimeField>(
+    trace: &mut TraceTable<F>,
+    base: Wire,
+    exponent: u64,
+) -> Result<Wire, SangriaError> {
+    if exponent == 0 {
+        return Err(SangriaError::InvalidTrace(
+            "the exponent of the S-box must be positive",
+        ));
+    }
+
+    let mut result = base;
+    for bit in (0..exponent.ilog2()).rev() {
+        result = multiplication_gate(trace, result, result)?;
+        if (exponent >> bit) & 1 == 1 {
+            result = multiplication_gate(trace, result, base)?;
+        }
+    }
+
+    Ok(result)
+}
+
+/// Appends a gate `a b - c = 0` multiplying the values of two wires and returns its output wire.
+pub(crate) fn multiplication_gate<F: PrimeField>(
+    trace: &mut TraceTable<F>,
+    left: Wire,
+    right: Wire,
+) -> Result<Wire, SangriaError> {
+    let (left_value, right_value) = (trace.wire(left.0, left.1)?, trace.wire(right.0, right.1)?);
+    let mut selectors = [F::zero(); NUMBER_OF_SELECTORS];
+    selectors[MULTIPLICATION_SELECTOR_INDEX] = F::one();
+    selectors[OUTPUT_SELECTOR_INDEX] = -F::one();
+
+    let row = trace.number_of_rows();
+    trace.push_row(
+        RowTag::Gate(selectors),
+        [left_value, right_value, left_value * right_value],
+    );
+    trace.copy(left, (0, row));
+    trace.copy(right, (1, row));
+
+    Ok((2, row))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{poseidon_hash, poseidon_hash_gadget};
+    use crate::{
+        folding_scheme::{PLONKFoldingScheme, SetupInfo},
+        utils::{inner_product::InnerProductConfig, poseidon::poseidon_parameters_for_test},
+        NonInteractiveFoldingScheme, RowTag, SangriaConfig, SangriaError, TraceTable,
+    };
+    use ark_bn254::Fr;
+    use ark_crypto_primitives::sponge::{poseidon::PoseidonSponge, CryptographicSponge};
+    use ark_ff::{UniformRand, Zero};
+    use ark_std::test_rng;
+
+    type Scheme = PLONKFoldingScheme<Fr, InnerProductConfig, PoseidonSponge<Fr>>;
+
+    #[test]
+    fn native_hash_matches_the_sponge() {
+        let rng = &mut test_rng();
+        let constants = poseidon_parameters_for_test();
+
+        for len in 0..6 {
+            let inputs: Vec<Fr> = (0..len).map(|_| Fr::rand(rng)).collect();
+            let mut sponge = PoseidonSponge::new(&constants);
+            sponge.absorb(&inputs);
+
+            assert_eq!(
+                poseidon_hash(&constants, &inputs),
+                sponge.squeeze_field_elements::<Fr>(1)[0]
+            );
+        }
+    }
+
+    /// Lays out `inputs` as public inputs, hashes them in-circuit and copies the hash into the
+    /// output row, which is claimed to hold `hash`.
+    fn hashing_trace(inputs: &[Fr], hash: Fr) -> Result<TraceTable<Fr>, SangriaError> {
+        let mut trace = TraceTable::new();
+        for &input in inputs {
+            trace.push_row(RowTag::PublicInput, [input, Fr::zero(), Fr::zero()]);
+        }
+        let wires: Vec<_> = (0..inputs.len()).map(|row| (0, row)).collect();
+        let output = poseidon_hash_gadget(&mut trace, &poseidon_parameters_for_test(), &wires)?;
+        let output_row = trace.number_of_rows();
+        trace.push_row(RowTag::Output, [hash, Fr::zero(), Fr::zero()]);
+        trace.copy(output, (0, output_row));
+
+        Ok(trace)
+    }
+
+    #[test]
+    fn gadget_hash_satisfies_the_relation() {
+        let rng = &mut test_rng();
+        let inputs: Vec<Fr> = (0..3).map(|_| Fr::rand(rng)).collect();
+        let hash = poseidon_hash(&poseidon_parameters_for_test(), &inputs);
+        let number_of_gates = hashing_trace(&inputs, hash).unwrap().number_of_rows() - 4;
+        let info = SetupInfo {
+            number_of_public_inputs: 3,
+            number_of_gates,
+            config: SangriaConfig::default(),
+            poseidon_constants: poseidon_parameters_for_test(),
+        };
+        let pp = Scheme::setup(&info, rng).unwrap();
+
+        let trace = hashing_trace(&inputs, hash).unwrap();
+        let instance_columns = trace.instance_columns();
+        let (circuit, witness) = trace.into_circuit_and_witness(Vec::new(), rng).unwrap();
+        let instance = Scheme::commit_witness(&pp, instance_columns, &witness).unwrap();
+        assert_eq!(
+            Scheme::check_relation(&pp, &circuit, &instance, &witness),
+            Ok(())
+        );
+
+        assert_eq!(
+            hashing_trace(&inputs, hash + Fr::from(1u64))
+                .unwrap()
+                .into_circuit_and_witness(Vec::new(), rng)
+                .err(),
+            Some(SangriaError::InvalidTrace(
+                "copied wires hold different values"
+            ))
+        );
+    }
+}
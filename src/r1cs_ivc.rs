@@ -0,0 +1,355 @@
+use ark_crypto_primitives::sponge::{poseidon::PoseidonSponge, Absorb};
+use ark_ff::{PrimeField, Zero};
+use ark_std::rand::Rng;
+
+use crate::{
+    ivc::fold_steps,
+    relaxed_r1cs::{self, R1CSFoldingScheme},
+    vector_commitment::HomomorphicCommitmentScheme,
+    AugmentedCircuit, FoldingCommitmentConfig, NonInteractiveFoldingScheme, PLONKCircuit,
+    PLONKStepCircuit, R1CSRelation, R1CSShape, RelaxedR1CSInstance, RelaxedR1CSWitness, Sangria,
+    SangriaError, SetupInfo,
+};
+
+type FoldingScheme<F, Comm> = R1CSFoldingScheme<F, Comm, PoseidonSponge<F>>;
+
+/// The commitments to the cross term of one fold.
+type CrossTermCommitments<F, Comm> =
+    Vec<<<Comm as FoldingCommitmentConfig<F>>::CommitmentSlack as HomomorphicCommitmentScheme<F>>::Commitment>;
+
+/// The prover key of Sangria over relaxed R1CS: the public parameters and prover key of the R1CS
+/// folding scheme, and the augmented circuit with its PLONK arithmetization, to synthesize steps.
+pub struct ProverKey<F: PrimeField, Comm: FoldingCommitmentConfig<F>, SC> {
+    public_parameters: relaxed_r1cs::PublicParameters<F, Comm>,
+    folding_key: relaxed_r1cs::ProverKey<F>,
+    circuit: PLONKCircuit<F>,
+    augmented_circuit: AugmentedCircuit<SC>,
+}
+
+/// The verifier key of Sangria over relaxed R1CS: the public parameters and verifier key of the
+/// R1CS folding scheme, the R1CS of the augmented circuit (to decide the running instance) and the
+/// augmented circuit (to encode states).
+pub struct VerifierKey<F: PrimeField, Comm: FoldingCommitmentConfig<F>, SC> {
+    public_parameters: relaxed_r1cs::PublicParameters<F, Comm>,
+    folding_key: relaxed_r1cs::VerifierKey<F>,
+    shape: R1CSShape<F>,
+    augmented_circuit: AugmentedCircuit<SC>,
+}
+
+/// An IVC proof of Sangria over relaxed R1CS: the fresh instance of every step, the commitments to
+/// the cross terms folding them and the resulting running instance-witness pair.
+///
+/// WARNING: as with [`crate::BatchedIVCProof`], the verifier re-folds the step instances itself, so the
+/// proof grows with the number of steps and is not zero-knowledge. Proofs over relaxed R1CS cannot
+/// be compressed, merged or proved in parallel yet.
+pub struct R1CSIVCProof<F: PrimeField, Comm: FoldingCommitmentConfig<F>> {
+    step_instances: Vec<RelaxedR1CSInstance<F, Comm>>,
+    cross_term_commitments: Vec<CrossTermCommitments<F, Comm>>,
+    fold_shape: Vec<usize>,
+    running_instance: RelaxedR1CSInstance<F, Comm>,
+    running_witness: RelaxedR1CSWitness<F>,
+}
+
+impl<F: PrimeField, Comm: FoldingCommitmentConfig<F>> R1CSIVCProof<F, Comm> {
+    /// Returns the number of steps proved.
+    pub fn number_of_steps(&self) -> usize {
+        self.step_instances.len()
+    }
+}
+
+impl<F, Comm> Clone for R1CSIVCProof<F, Comm>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    fn clone(&self) -> Self {
+        Self {
+            step_instances: self.step_instances.clone(),
+            cross_term_commitments: self.cross_term_commitments.clone(),
+            fold_shape: self.fold_shape.clone(),
+            running_instance: self.running_instance.clone(),
+            running_witness: self.running_witness.clone(),
+        }
+    }
+}
+
+/// Reads the current and next states of a step from the public inputs of its instance.
+fn step_states<F, Comm>(
+    instance: &RelaxedR1CSInstance<F, Comm>,
+    state_len: usize,
+) -> Result<(&[F], &[F]), SangriaError>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    let public_inputs = instance.public_inputs();
+    if public_inputs.len() != 2 * state_len {
+        return Err(SangriaError::PublicInputMismatch);
+    }
+
+    Ok(public_inputs.split_at(state_len))
+}
+
+/// Like over relaxed PLONK, the proofs re-fold every step, so Sangria over relaxed R1CS does not
+/// implement [`IVC`](crate::IVC) and proves them with the functions below instead.
+impl<Comm> Sangria<Comm, R1CSRelation> {
+    /// Runs the setup of the R1CS folding scheme, sized as for the PLONK augmented circuit: two
+    /// public inputs per element of the state encoding and as many gates as the step circuit.
+    pub fn setup<F, R>(
+        info: &SetupInfo<F>,
+        rng: &mut R,
+    ) -> Result<relaxed_r1cs::PublicParameters<F, Comm>, SangriaError>
+    where
+        F: PrimeField + Absorb,
+        Comm: FoldingCommitmentConfig<F>,
+        R: Rng,
+    {
+        FoldingScheme::<F, Comm>::setup(info, rng)
+    }
+
+    /// Builds the augmented circuit like [`Sangria`] over relaxed PLONK, converts it to R1CS and
+    /// encodes the R1CS with the folding scheme.
+    #[allow(clippy::type_complexity)]
+    pub fn encode<F, SC, R>(
+        public_parameters: &relaxed_r1cs::PublicParameters<F, Comm>,
+        step_circuit: &SC,
+        rng: &mut R,
+    ) -> Result<(ProverKey<F, Comm, SC>, VerifierKey<F, Comm, SC>), SangriaError>
+    where
+        F: PrimeField + Absorb,
+        Comm: FoldingCommitmentConfig<F>,
+        SC: PLONKStepCircuit<F> + Clone,
+        SC::Witness: Default,
+        R: Rng,
+    {
+        if public_parameters.number_of_public_inputs != 2 * step_circuit.state_len() {
+            return Err(SangriaError::InvalidConfig(
+                "the public parameters must have two public inputs per state element",
+            ));
+        }
+
+        let augmented_circuit = AugmentedCircuit::new(step_circuit.clone());
+        let circuit = augmented_circuit.batched_circuit()?;
+        let shape = R1CSShape::from_plonk(&circuit, public_parameters.number_of_public_inputs)?;
+        let (folding_pk, folding_vk) =
+            FoldingScheme::<F, Comm>::encode(public_parameters, &shape, rng)?;
+
+        Ok((
+            ProverKey {
+                public_parameters: public_parameters.clone(),
+                folding_key: folding_pk,
+                circuit,
+                augmented_circuit: augmented_circuit.clone(),
+            },
+            VerifierKey {
+                public_parameters: public_parameters.clone(),
+                folding_key: folding_vk,
+                shape,
+                augmented_circuit,
+            },
+        ))
+    }
+
+    /// Executes the step, reads the variables of the R1CS from the trace of the augmented circuit,
+    /// commits to them and folds the step's instance into the running instance. In the base case
+    /// (no proof yet) the current state must be the origin state and the step's instance becomes
+    /// the running instance.
+    pub fn prove_step<F, SC, R>(
+        prover_key: &ProverKey<F, Comm, SC>,
+        origin_state: &SC::State,
+        current_state: SC::State,
+        current_proof: Option<R1CSIVCProof<F, Comm>>,
+        current_witness: &SC::Witness,
+        rng: &mut R,
+    ) -> Result<(SC::State, R1CSIVCProof<F, Comm>), SangriaError>
+    where
+        F: PrimeField + Absorb,
+        Comm: FoldingCommitmentConfig<F>,
+        SC: PLONKStepCircuit<F> + Clone,
+        SC::Witness: Default,
+        R: Rng,
+    {
+        let step_circuit = prover_key.augmented_circuit.step_circuit();
+        let current = step_circuit.encode_state(&current_state);
+        let last_state = match &current_proof {
+            None => step_circuit.encode_state(origin_state),
+            Some(proof) => {
+                let last_instance = proof
+                    .step_instances
+                    .last()
+                    .ok_or(SangriaError::InvalidProof)?;
+                step_states(last_instance, current.len())?.1.to_vec()
+            }
+        };
+        if last_state != current {
+            return Err(SangriaError::PublicInputMismatch);
+        }
+
+        let next_state = step_circuit.execute(&current_state, current_witness)?;
+        let next = step_circuit.encode_state(&next_state);
+        let trace =
+            prover_key
+                .augmented_circuit
+                .synthesize_batched(&current, &next, current_witness)?;
+        if trace.circuit(step_circuit.fixed_tables())? != prover_key.circuit {
+            return Err(SangriaError::InvalidTrace(
+                "the layout of the step circuit depends on its values",
+            ));
+        }
+        trace.check_copies()?;
+
+        let shape = &prover_key.folding_key.shape;
+        let (variables, public_inputs) = shape.assignment(&trace)?;
+        let step_witness =
+            RelaxedR1CSWitness::new(variables, shape.number_of_constraints(), F::rand(rng));
+        let step_instance = FoldingScheme::<F, Comm>::commit_witness(
+            &prover_key.public_parameters,
+            public_inputs,
+            &step_witness,
+        )?;
+
+        let Some(mut proof) = current_proof else {
+            return Ok((
+                next_state,
+                R1CSIVCProof {
+                    step_instances: vec![step_instance.clone()],
+                    cross_term_commitments: Vec::new(),
+                    fold_shape: vec![0],
+                    running_instance: step_instance,
+                    running_witness: step_witness,
+                },
+            ));
+        };
+        let (running_instance, running_witness, cross_term_commitments) =
+            FoldingScheme::<F, Comm>::prover(
+                &prover_key.public_parameters,
+                &prover_key.folding_key,
+                &proof.running_instance,
+                &proof.running_witness,
+                &step_instance,
+                &step_witness,
+                rng,
+            )?;
+        proof.step_instances.push(step_instance);
+        proof.cross_term_commitments.push(cross_term_commitments);
+        proof.fold_shape.push(1);
+        proof.running_instance = running_instance;
+        proof.running_witness = running_witness;
+
+        Ok((next_state, proof))
+    }
+
+    /// Checks that the step instances are fresh and chain from the origin state to the current
+    /// state, re-folds them into the running instance and checks that the running witness
+    /// satisfies it. Without a proof, the current state must be the origin state.
+    pub fn verify<F, SC>(
+        verifier_key: &VerifierKey<F, Comm, SC>,
+        origin_state: &SC::State,
+        current_state: SC::State,
+        current_proof: Option<R1CSIVCProof<F, Comm>>,
+    ) -> Result<(), SangriaError>
+    where
+        F: PrimeField + Absorb,
+        Comm: FoldingCommitmentConfig<F>,
+        SC: PLONKStepCircuit<F> + Clone,
+        SC::Witness: Default,
+    {
+        let step_circuit = verifier_key.augmented_circuit.step_circuit();
+        let origin = step_circuit.encode_state(origin_state);
+        let current = step_circuit.encode_state(&current_state);
+
+        let proof = match current_proof {
+            None if origin == current => return Ok(()),
+            None => return Err(SangriaError::InvalidProof),
+            Some(proof) => proof,
+        };
+
+        let mut state = &origin[..];
+        for instance in proof.step_instances.iter() {
+            if instance.scaling_factor() != F::one() || !instance.slack_commitment().is_zero() {
+                return Err(SangriaError::InvalidProof);
+            }
+            let (step_current, step_next) = step_states(instance, origin.len())?;
+            if step_current != state {
+                return Err(SangriaError::InvalidProof);
+            }
+            state = step_next;
+        }
+        if state != current {
+            return Err(SangriaError::InvalidProof);
+        }
+
+        let running_instance = fold_steps(
+            &proof.step_instances,
+            &proof.cross_term_commitments,
+            &proof.fold_shape,
+            |running_instance, instance, cross_term_commitments| {
+                FoldingScheme::<F, Comm>::verifier(
+                    &verifier_key.public_parameters,
+                    &verifier_key.folding_key,
+                    running_instance,
+                    instance,
+                    cross_term_commitments,
+                )
+            },
+        )?;
+        if running_instance != proof.running_instance {
+            return Err(SangriaError::InvalidProof);
+        }
+
+        FoldingScheme::<F, Comm>::check_relation(
+            &verifier_key.public_parameters,
+            &verifier_key.shape,
+            &running_instance,
+            &proof.running_witness,
+        )
+        .map_err(|_| SangriaError::InvalidProof)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        utils::{
+            circuits::Counter, inner_product::InnerProductConfig,
+            poseidon::poseidon_parameters_for_test,
+        },
+        R1CSRelation, Sangria, SangriaConfig, SangriaError, SetupInfo,
+    };
+    use ark_bn254::Fr;
+    use ark_std::test_rng;
+
+    type Scheme = Sangria<InnerProductConfig, R1CSRelation>;
+
+    #[test]
+    fn counter_chain_verifies_over_r1cs() {
+        let rng = &mut test_rng();
+        let info = SetupInfo {
+            number_of_public_inputs: 2,
+            number_of_gates: 2,
+            config: SangriaConfig::default(),
+            poseidon_constants: poseidon_parameters_for_test(),
+        };
+        let public_parameters = Scheme::setup(&info, rng).unwrap();
+        let (pk, vk) = Scheme::encode(&public_parameters, &Counter, rng).unwrap();
+        let origin = Fr::from(5u64);
+
+        let (mut state, mut proof) = (origin, None);
+        for increment in 1..=5u64 {
+            let (next_state, next_proof) =
+                Scheme::prove_step(&pk, &origin, state, proof, &Fr::from(increment), rng).unwrap();
+            assert_eq!(
+                Scheme::verify(&vk, &origin, next_state, Some(next_proof.clone())),
+                Ok(())
+            );
+
+            (state, proof) = (next_state, Some(next_proof));
+        }
+
+        assert_eq!(state, Fr::from(20u64));
+        assert_eq!(
+            Scheme::verify(&vk, &origin, Fr::from(21u64), proof),
+            Err(SangriaError::InvalidProof)
+        );
+    }
+}
@@ -0,0 +1,670 @@
+use ark_crypto_primitives::sponge::{
+    poseidon::{PoseidonConfig, PoseidonSponge},
+    Absorb,
+};
+use ark_ff::{PrimeField, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{
+    marker::PhantomData,
+    rand::{rngs::StdRng, Rng, SeedableRng},
+};
+
+use crate::{
+    augmented_circuit::{accumulator_digest, default_instance, RECURSIVE_PUBLIC_INPUTS},
+    folding_scheme::{self, blake3_digest, FoldingCommitmentConfig, SetupInfo},
+    hyperplonk::{self, HyperPlonkProof},
+    ivc::{ProverKey, VerifierKey},
+    relaxed_plonk::NUMBER_OF_WIRES,
+    AugmentedCircuit, CommitmentGadget, HomomorphicCommitmentScheme, IVCWithProofCompression,
+    InnerProductOpening, NonInteractiveFoldingScheme, PLONKFoldingScheme, PLONKStepCircuit,
+    RecursiveStep, RelaxedPLONKInstance, RelaxedPLONKWitness, Sangria, SangriaConfig, SangriaError,
+    IVC,
+};
+
+type FoldingScheme<F, Comm> = PLONKFoldingScheme<F, Comm, PoseidonSponge<F>>;
+
+/// The commitments to the cross terms of one fold.
+type CrossTermCommitments<F, Comm> =
+    Vec<<<Comm as FoldingCommitmentConfig<F>>::CommitmentSlack as HomomorphicCommitmentScheme<F>>::Commitment>;
+
+/// The Sangria IVC scheme with the folding verified in-circuit, as in Nova: the augmented circuit of
+/// every step folds the fresh instance of the previous step into the running instance (see
+/// [`AugmentedCircuit::synthesize`]), so a proof only holds the running instance and the instance
+/// of the last step with their witnesses, whatever the number of steps. The commitment schemes of
+/// `Comm` must be folded in-circuit over the field of the instances (see [`CommitmentGadget`]).
+/// [Compressing](IVCWithProofCompression::compress) a proof replaces its witnesses with a HyperPlonk
+/// proof of the fold of its two instances.
+pub struct RecursiveSangria<Comm>(PhantomData<Comm>);
+
+/// A recursive IVC proof of `n` steps: the running instance folding the first `n - 1` steps and the
+/// fresh instance of the last step, which exposes the IO hash of the `n` steps and of the running
+/// instance, with their witnesses. Its size does not depend on the number of steps.
+///
+/// WARNING: the proof is not zero-knowledge, as it contains the witnesses.
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct RecursiveIVCProof<F: PrimeField, Comm: FoldingCommitmentConfig<F>> {
+    number_of_steps: u64,
+    running_instance: RelaxedPLONKInstance<F, Comm>,
+    running_witness: RelaxedPLONKWitness<F>,
+    step_instance: RelaxedPLONKInstance<F, Comm>,
+    step_witness: RelaxedPLONKWitness<F>,
+}
+
+impl<F: PrimeField, Comm: FoldingCommitmentConfig<F>> RecursiveIVCProof<F, Comm> {
+    /// Returns the number of steps proved.
+    pub fn number_of_steps(&self) -> u64 {
+        self.number_of_steps
+    }
+
+    /// Checks a proof received from an untrusted party, e.g. a deserialized one, before it is
+    /// verified: both instances must have the layout of the instances of `verifier_key` and every
+    /// commitment must be valid. [`IVC::verify`] validates the proofs it verifies.
+    pub fn validate<SC>(
+        &self,
+        verifier_key: &VerifierKey<F, Comm, SC>,
+    ) -> Result<(), SangriaError> {
+        RelaxedPLONKInstance::validate_batch(
+            &verifier_key.folding_key.instance_schema,
+            &[&self.running_instance, &self.step_instance],
+            [].iter(),
+        )
+    }
+}
+
+impl<F, Comm> Clone for RecursiveIVCProof<F, Comm>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    fn clone(&self) -> Self {
+        Self {
+            number_of_steps: self.number_of_steps,
+            running_instance: self.running_instance.clone(),
+            running_witness: self.running_witness.clone(),
+            step_instance: self.step_instance.clone(),
+            step_witness: self.step_witness.clone(),
+        }
+    }
+}
+
+/// A compressed recursive IVC proof of `n` steps: the running instance and the fresh instance of the
+/// last step of a [`RecursiveIVCProof`], the commitments to the cross terms folding them into a
+/// final instance and a HyperPlonk proof that the final instance is satisfied, in place of the
+/// witnesses. Neither its size nor the work of its verifier depends on the number of steps.
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct CompressedRecursiveIVCProof<F, Comm>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+    Comm::CommitmentSlack: InnerProductOpening<F>,
+    Comm::CommitmentWitness: InnerProductOpening<F>,
+{
+    number_of_steps: u64,
+    running_instance: RelaxedPLONKInstance<F, Comm>,
+    step_instance: RelaxedPLONKInstance<F, Comm>,
+    cross_term_commitments: CrossTermCommitments<F, Comm>,
+    snark: HyperPlonkProof<F, Comm>,
+}
+
+impl<F, Comm> CompressedRecursiveIVCProof<F, Comm>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+    Comm::CommitmentSlack: InnerProductOpening<F>,
+    Comm::CommitmentWitness: InnerProductOpening<F>,
+{
+    /// Returns the number of steps proved.
+    pub fn number_of_steps(&self) -> u64 {
+        self.number_of_steps
+    }
+
+    /// Checks the instances and the cross term commitments of a proof received from an untrusted
+    /// party like [`RecursiveIVCProof::validate`]. The group elements of the HyperPlonk proof are
+    /// checked when it is deserialized with validation.
+    pub fn validate<SC>(
+        &self,
+        verifier_key: &VerifierKey<F, Comm, SC>,
+    ) -> Result<(), SangriaError> {
+        let schema = &verifier_key.folding_key.instance_schema;
+        if self.cross_term_commitments.len() != schema.cross_term_commitments {
+            return Err(SangriaError::InvalidProof);
+        }
+
+        RelaxedPLONKInstance::validate_batch(
+            schema,
+            &[&self.running_instance, &self.step_instance],
+            self.cross_term_commitments.iter(),
+        )
+    }
+}
+
+impl<Comm> RecursiveSangria<Comm> {
+    /// Returns the setup information of the recursive augmented circuit of `step_circuit` under
+    /// `config`: a single public input and as many gates as the circuit has.
+    pub fn setup_info<F, SC>(
+        step_circuit: &SC,
+        config: SangriaConfig,
+        poseidon_constants: PoseidonConfig<F>,
+    ) -> Result<SetupInfo<F>, SangriaError>
+    where
+        F: PrimeField + Absorb,
+        Comm: FoldingCommitmentConfig<F>,
+        Comm::CommitmentWitness: CommitmentGadget<F>,
+        Comm::CommitmentSlack: CommitmentGadget<F>,
+        SC: PLONKStepCircuit<F> + Clone,
+        SC::Witness: Default,
+    {
+        let circuit = AugmentedCircuit::new(step_circuit.clone())
+            .circuit::<F, Comm>(&config, &poseidon_constants)?;
+
+        Ok(SetupInfo {
+            number_of_public_inputs: RECURSIVE_PUBLIC_INPUTS,
+            number_of_gates: circuit.number_of_rows() - RECURSIVE_PUBLIC_INPUTS - 1,
+            config,
+            poseidon_constants,
+        })
+    }
+}
+
+/// Returns the expected IO hash of a proof of `number_of_steps` steps from `origin` to `current`
+/// with `running_instance`, as exposed by the fresh instance of its last step.
+fn expected_io_hash<F, Comm>(
+    public_parameters: &folding_scheme::PublicParameters<F, Comm>,
+    folding_key: &folding_scheme::VerifierKey<F, Comm>,
+    number_of_steps: u64,
+    origin: &Vec<F>,
+    current: &Vec<F>,
+    running_instance: &RelaxedPLONKInstance<F, Comm>,
+) -> F
+where
+    F: PrimeField + Absorb,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    let constants = &public_parameters.poseidon_constants;
+    let digest = accumulator_digest(
+        constants,
+        &folding_key.to_sponge_field_elements_as_vec::<F>(),
+        running_instance,
+    );
+
+    Sangria::io_hash(
+        constants,
+        &public_parameters.config.application_id,
+        number_of_steps,
+        origin,
+        current,
+        digest,
+    )
+}
+
+/// Checks that `step_instance` is a fresh instance exposing the IO hash of `number_of_steps` steps
+/// from `origin` to `current` with `running_instance`, as the last step of a proof must.
+fn check_last_step<F, Comm, SC>(
+    verifier_key: &VerifierKey<F, Comm, SC>,
+    number_of_steps: u64,
+    origin: &Vec<F>,
+    current: &Vec<F>,
+    running_instance: &RelaxedPLONKInstance<F, Comm>,
+    step_instance: &RelaxedPLONKInstance<F, Comm>,
+) -> Result<(), SangriaError>
+where
+    F: PrimeField + Absorb,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    if number_of_steps == 0
+        || step_instance.scaling_factor() != F::one()
+        || !step_instance.slack_commitment().is_zero()
+    {
+        return Err(SangriaError::InvalidProof);
+    }
+
+    let io_hash = expected_io_hash::<F, Comm>(
+        &verifier_key.public_parameters,
+        &verifier_key.folding_key,
+        number_of_steps,
+        origin,
+        current,
+        running_instance,
+    );
+    if step_instance.public_inputs()? != [io_hash] {
+        return Err(SangriaError::InvalidProof);
+    }
+
+    Ok(())
+}
+
+/// Returns the zero witness of the circuit of `number_of_rows` rows, which satisfies the default
+/// running instance.
+fn default_witness<F: PrimeField>(number_of_rows: usize) -> RelaxedPLONKWitness<F> {
+    RelaxedPLONKWitness::new(
+        vec![vec![F::zero(); number_of_rows - RECURSIVE_PUBLIC_INPUTS - 1]; NUMBER_OF_WIRES],
+        vec![F::zero(); number_of_rows],
+        vec![F::zero(); NUMBER_OF_WIRES],
+    )
+}
+
+/// Returns the round of the fold of the last step of a proof of `number_of_steps` steps into its
+/// running instance: the first fold is round zero.
+fn fold_round(number_of_steps: u64) -> usize {
+    number_of_steps.saturating_sub(1) as usize
+}
+
+impl<F, Comm, SC> IVC<F, SC> for RecursiveSangria<Comm>
+where
+    F: PrimeField + Absorb,
+    Comm: FoldingCommitmentConfig<F>,
+    Comm::CommitmentWitness: CommitmentGadget<F>,
+    Comm::CommitmentSlack: CommitmentGadget<F>,
+    SC: PLONKStepCircuit<F> + Clone,
+    SC::Witness: Default,
+{
+    type SetupInfo = SetupInfo<F>;
+    type PublicParameters = folding_scheme::PublicParameters<F, Comm>;
+    type ProverKey = ProverKey<F, Comm, SC>;
+    type VerifierKey = VerifierKey<F, Comm, SC>;
+    type Proof = RecursiveIVCProof<F, Comm>;
+
+    /// Runs the setup of the folding scheme, e.g. with the information returned by
+    /// [`RecursiveSangria::setup_info`].
+    fn setup<R: Rng>(
+        info: &SetupInfo<F>,
+        rng: &mut R,
+    ) -> Result<Self::PublicParameters, SangriaError> {
+        FoldingScheme::<F, Comm>::setup(info, rng)
+    }
+
+    /// Builds the recursive augmented circuit from the base step on zero states with the default
+    /// witness, whose values are ignored, and encodes it with the folding scheme.
+    fn encode<R: Rng>(
+        public_parameters: &Self::PublicParameters,
+        step_circuit: &SC,
+        rng: &mut R,
+    ) -> Result<(Self::ProverKey, Self::VerifierKey), SangriaError> {
+        if public_parameters.number_of_public_inputs != RECURSIVE_PUBLIC_INPUTS {
+            return Err(SangriaError::InvalidConfig(
+                "the public parameters of a recursive proof must have a single public input",
+            ));
+        }
+
+        let augmented_circuit = AugmentedCircuit::new(step_circuit.clone());
+        let circuit = augmented_circuit.circuit::<F, Comm>(
+            &public_parameters.config,
+            &public_parameters.poseidon_constants,
+        )?;
+        let (folding_pk, folding_vk) =
+            FoldingScheme::<F, Comm>::encode(public_parameters, &circuit, rng)?;
+        let permutation_commit_key = Comm::CommitmentSlack::setup(
+            rng,
+            hyperplonk::permutation_commit_key_len(
+                circuit.number_of_rows(),
+                public_parameters.config.gate_degree,
+            ),
+        );
+
+        Ok((
+            ProverKey {
+                public_parameters: public_parameters.clone(),
+                folding_key: folding_pk,
+                permutation_commit_key: permutation_commit_key.clone(),
+                augmented_circuit: augmented_circuit.clone(),
+                #[cfg(feature = "usage-tracking")]
+                usage: Default::default(),
+            },
+            VerifierKey {
+                public_parameters: public_parameters.clone(),
+                folding_key: folding_vk,
+                circuit,
+                permutation_commit_key,
+                augmented_circuit,
+            },
+        ))
+    }
+
+    /// Folds the last step of the proof into its running instance natively, then executes the step
+    /// and synthesizes the augmented circuit verifying that fold in-circuit. In the base case (no
+    /// proof yet) the current state must be the origin state and the running instance is the
+    /// default one.
+    fn prove_step<R: Rng>(
+        prover_key: &Self::ProverKey,
+        origin_state: &SC::State,
+        current_state: SC::State,
+        current_proof: Option<Self::Proof>,
+        current_witness: &SC::Witness,
+        rng: &mut R,
+    ) -> Result<(SC::State, Self::Proof), SangriaError> {
+        let public_parameters = &prover_key.public_parameters;
+        let folding_key = &prover_key.folding_key;
+        let step_circuit = prover_key.augmented_circuit.step_circuit();
+        let origin = step_circuit.encode_state(origin_state);
+        let current = step_circuit.encode_state(&current_state);
+        let verifier_key = folding_key
+            .verifier_key
+            .to_sponge_field_elements_as_vec::<F>();
+
+        let (step, running_instance, running_witness) = match current_proof {
+            None if origin != current => return Err(SangriaError::PublicInputMismatch),
+            None => (
+                RecursiveStep::base(&public_parameters.config, verifier_key, origin),
+                default_instance(),
+                default_witness(folding_key.circuit.number_of_rows()),
+            ),
+            Some(proof) => {
+                let io_hash = expected_io_hash::<F, Comm>(
+                    public_parameters,
+                    &folding_key.verifier_key,
+                    proof.number_of_steps,
+                    &origin,
+                    &current,
+                    &proof.running_instance,
+                );
+                if proof.step_instance.public_inputs()? != [io_hash] {
+                    return Err(SangriaError::PublicInputMismatch);
+                }
+
+                let (running_instance, running_witness, cross_term_commitments) =
+                    FoldingScheme::<F, Comm>::prover_in_round(
+                        public_parameters,
+                        folding_key,
+                        &proof.running_instance,
+                        &proof.running_witness,
+                        &proof.step_instance,
+                        &proof.step_witness,
+                        fold_round(proof.number_of_steps),
+                        rng,
+                    )?;
+                let step = RecursiveStep {
+                    verifier_key,
+                    number_of_steps: proof.number_of_steps,
+                    origin_state: origin,
+                    current_state: current,
+                    running_instance: proof.running_instance,
+                    step_instance: proof.step_instance,
+                    cross_term_commitments,
+                };
+                (step, running_instance, running_witness)
+            }
+        };
+
+        let next_state = step_circuit.execute(&current_state, current_witness)?;
+        let trace = prover_key.augmented_circuit.synthesize(
+            &public_parameters.config,
+            &public_parameters.poseidon_constants,
+            &step,
+            &step_circuit.encode_state(&next_state),
+            current_witness,
+        )?;
+        let instance_columns = trace.instance_columns();
+        let (circuit, step_witness) =
+            trace.into_circuit_and_witness(step_circuit.fixed_tables(), rng)?;
+        if circuit != folding_key.circuit {
+            return Err(SangriaError::InvalidTrace(
+                "the layout of the step circuit depends on its values",
+            ));
+        }
+        let step_instance = FoldingScheme::<F, Comm>::commit_witness(
+            public_parameters,
+            instance_columns,
+            &step_witness,
+        )?;
+
+        Ok((
+            next_state,
+            RecursiveIVCProof {
+                number_of_steps: step.number_of_steps + 1,
+                running_instance,
+                running_witness,
+                step_instance,
+                step_witness,
+            },
+        ))
+    }
+
+    /// Checks that the instance of the last step is fresh and exposes the IO hash of the number of
+    /// steps, the origin state, the current state and the running instance, and that both
+    /// witnesses satisfy their instances. Without a proof, the current state must be the origin
+    /// state.
+    fn verify(
+        verifier_key: &Self::VerifierKey,
+        origin_state: &SC::State,
+        current_state: SC::State,
+        current_proof: Option<Self::Proof>,
+    ) -> Result<(), SangriaError> {
+        let step_circuit = verifier_key.augmented_circuit.step_circuit();
+        let origin = step_circuit.encode_state(origin_state);
+        let current = step_circuit.encode_state(&current_state);
+
+        let proof = match current_proof {
+            None if origin == current => return Ok(()),
+            None => return Err(SangriaError::InvalidProof),
+            Some(proof) => proof,
+        };
+        proof.validate(verifier_key)?;
+        check_last_step(
+            verifier_key,
+            proof.number_of_steps,
+            &origin,
+            &current,
+            &proof.running_instance,
+            &proof.step_instance,
+        )?;
+
+        for (instance, witness) in [
+            (&proof.running_instance, &proof.running_witness),
+            (&proof.step_instance, &proof.step_witness),
+        ] {
+            FoldingScheme::<F, Comm>::check_relation(
+                &verifier_key.public_parameters,
+                &verifier_key.circuit,
+                instance,
+                witness,
+            )
+            .map_err(|_| SangriaError::InvalidProof)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<F, Comm, SC> IVCWithProofCompression<F, SC> for RecursiveSangria<Comm>
+where
+    F: PrimeField + Absorb,
+    Comm: FoldingCommitmentConfig<F>,
+    Comm::CommitmentWitness: CommitmentGadget<F> + InnerProductOpening<F>,
+    Comm::CommitmentSlack: CommitmentGadget<F> + InnerProductOpening<F>,
+    SC: PLONKStepCircuit<F> + Clone,
+    SC::Witness: Default,
+{
+    type CompressedProof = CompressedRecursiveIVCProof<F, Comm>;
+
+    /// Folds the last step into the running instance once more, natively, and replaces both
+    /// witnesses with a HyperPlonk proof that the folded witness satisfies the folded instance.
+    fn compress(
+        prover_key: &Self::ProverKey,
+        proof: Self::Proof,
+    ) -> Result<Self::CompressedProof, SangriaError> {
+        // the hidings of the cross terms of the last fold are drawn from a seed as secret as the
+        // witnesses, which include the hidings of their commitments
+        let rng = &mut StdRng::from_seed(blake3_digest(&[
+            blake3_digest(&proof.running_witness),
+            blake3_digest(&proof.step_witness),
+        ]));
+        let (instance, witness, cross_term_commitments) =
+            FoldingScheme::<F, Comm>::prover_in_round(
+                &prover_key.public_parameters,
+                &prover_key.folding_key,
+                &proof.running_instance,
+                &proof.running_witness,
+                &proof.step_instance,
+                &proof.step_witness,
+                fold_round(proof.number_of_steps),
+                rng,
+            )?;
+        let snark = hyperplonk::prove::<F, Comm>(
+            &prover_key.public_parameters,
+            &prover_key.folding_key.verifier_key,
+            &prover_key.folding_key.circuit,
+            &prover_key.permutation_commit_key,
+            &instance,
+            &witness,
+        )?;
+
+        Ok(CompressedRecursiveIVCProof {
+            number_of_steps: proof.number_of_steps,
+            running_instance: proof.running_instance,
+            step_instance: proof.step_instance,
+            cross_term_commitments,
+            snark,
+        })
+    }
+
+    /// Checks the last step like [`IVC::verify`], folds it into the running instance with the NIFS
+    /// verifier and verifies the HyperPlonk proof of the folded instance.
+    fn verify_compressed(
+        verifier_key: &Self::VerifierKey,
+        origin_state: &SC::State,
+        current_state: SC::State,
+        compressed_proof: Self::CompressedProof,
+    ) -> Result<(), SangriaError> {
+        compressed_proof.validate(verifier_key)?;
+
+        let step_circuit = verifier_key.augmented_circuit.step_circuit();
+        check_last_step(
+            verifier_key,
+            compressed_proof.number_of_steps,
+            &step_circuit.encode_state(origin_state),
+            &step_circuit.encode_state(&current_state),
+            &compressed_proof.running_instance,
+            &compressed_proof.step_instance,
+        )?;
+        let instance = FoldingScheme::<F, Comm>::verifier_in_round(
+            &verifier_key.public_parameters,
+            &verifier_key.folding_key,
+            &compressed_proof.running_instance,
+            &compressed_proof.step_instance,
+            &compressed_proof.cross_term_commitments,
+            fold_round(compressed_proof.number_of_steps),
+        )
+        .map_err(|_| SangriaError::InvalidProof)?;
+
+        hyperplonk::verify::<F, Comm>(
+            &verifier_key.public_parameters,
+            &verifier_key.folding_key,
+            &verifier_key.circuit,
+            &verifier_key.permutation_commit_key,
+            &instance,
+            &compressed_proof.snark,
+        )
+        .map_err(|_| SangriaError::InvalidProof)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RecursiveSangria;
+    use crate::{
+        utils::{
+            circuits::Counter, inner_product::InnerProductConfig,
+            poseidon::poseidon_parameters_for_test,
+        },
+        IVCWithProofCompression, SangriaConfig, SangriaError, IVC,
+    };
+    use ark_bn254::Fr;
+    use ark_std::test_rng;
+
+    type Scheme = RecursiveSangria<InnerProductConfig>;
+
+    #[test]
+    fn counter_chain_verifies_with_constant_size_proofs() {
+        let rng = &mut test_rng();
+        let info = Scheme::setup_info(
+            &Counter,
+            SangriaConfig::default(),
+            poseidon_parameters_for_test(),
+        )
+        .unwrap();
+        let public_parameters = <Scheme as IVC<Fr, Counter>>::setup(&info, rng).unwrap();
+        let (pk, vk) =
+            <Scheme as IVC<Fr, Counter>>::encode(&public_parameters, &Counter, rng).unwrap();
+        let origin = Fr::from(5u64);
+
+        let (mut state, mut proof) = (origin, None);
+        for increment in 1..=3u64 {
+            let (next_state, next_proof) = <Scheme as IVC<Fr, Counter>>::prove_step(
+                &pk,
+                &origin,
+                state,
+                proof,
+                &Fr::from(increment),
+                rng,
+            )
+            .unwrap();
+            assert_eq!(
+                <Scheme as IVC<Fr, Counter>>::verify(
+                    &vk,
+                    &origin,
+                    next_state,
+                    Some(next_proof.clone())
+                ),
+                Ok(())
+            );
+
+            (state, proof) = (next_state, Some(next_proof));
+        }
+        let proof = proof.unwrap();
+        assert_eq!(state, Fr::from(11u64));
+        assert_eq!(proof.number_of_steps(), 3);
+
+        // the proof binds the origin and current states and the running instance
+        assert_eq!(
+            <Scheme as IVC<Fr, Counter>>::verify(
+                &vk,
+                &origin,
+                Fr::from(12u64),
+                Some(proof.clone())
+            ),
+            Err(SangriaError::InvalidProof)
+        );
+        assert_eq!(
+            <Scheme as IVC<Fr, Counter>>::verify(&vk, &Fr::from(6u64), state, Some(proof.clone())),
+            Err(SangriaError::InvalidProof)
+        );
+        let mut tampered = proof.clone();
+        tampered.running_instance = tampered.step_instance.clone();
+        tampered.running_witness = tampered.step_witness.clone();
+        assert_eq!(
+            <Scheme as IVC<Fr, Counter>>::verify(&vk, &origin, state, Some(tampered)),
+            Err(SangriaError::InvalidProof)
+        );
+        let mut tampered = proof.clone();
+        tampered.number_of_steps = 2;
+        assert_eq!(
+            <Scheme as IVC<Fr, Counter>>::verify(&vk, &origin, state, Some(tampered)),
+            Err(SangriaError::InvalidProof)
+        );
+
+        // the compressed proof holds the last instances and a SNARK of their fold
+        let compress =
+            |proof| <Scheme as IVCWithProofCompression<Fr, Counter>>::compress(&pk, proof).unwrap();
+        let verify_compressed = |state, compressed_proof| {
+            <Scheme as IVCWithProofCompression<Fr, Counter>>::verify_compressed(
+                &vk,
+                &origin,
+                state,
+                compressed_proof,
+            )
+        };
+        let compressed_proof = compress(proof.clone());
+        assert_eq!(compressed_proof.number_of_steps(), 3);
+        assert_eq!(verify_compressed(state, compressed_proof), Ok(()));
+        assert_eq!(
+            verify_compressed(Fr::from(12u64), compress(proof.clone())),
+            Err(SangriaError::InvalidProof)
+        );
+        let mut tampered = compress(proof);
+        tampered.cross_term_commitments[0] += Fr::from(1u64);
+        assert_eq!(
+            verify_compressed(state, tampered),
+            Err(SangriaError::InvalidProof)
+        );
+    }
+}
@@ -1,13 +1,29 @@
-use ark_ff::{Field, PrimeField};
-use ark_sponge::Absorb;
-use std::ops::{Add, Mul};
+use ark_crypto_primitives::sponge::{Absorb, AbsorbWithLength};
+use ark_ff::{BigInteger, Field, PrimeField, Zero};
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, SerializationError, Valid, Validate,
+};
+use ark_std::io::{Read, Write};
+use std::{
+    array, fmt,
+    ops::{Mul, Range},
+};
 
+use crate::serialization::{deserialize_bounded_vec, deserialize_bounded_vec_with};
 use crate::vector_commitment::HomomorphicCommitmentScheme;
-use crate::{folding_scheme::FoldingCommitmentConfig, SangriaError};
+use crate::{
+    decode_field_elements, encode_field_elements, encoded_len,
+    folding_scheme::{gate_evaluations, FoldingCommitmentConfig},
+    trace::NUMBER_OF_SELECTORS,
+    FoldChallenge, InstanceSchema, RowTag, SangriaError, SizeLimits, TraceTable,
+};
 
 type ColumnVector<F> = Vec<F>;
 type Permutation<F> = Vec<F>;
 
+/// The number of wire columns of the trace: left, right and output.
+pub(crate) const NUMBER_OF_WIRES: usize = 3;
+
 /// A constant variable for the q_L selector's index
 pub const LEFT_SELECTOR_INDEX: usize = 0;
 
@@ -23,6 +39,10 @@ pub const MULTIPLICATION_SELECTOR_INDEX: usize = 3;
 /// A constant variable for the q_C selector's index
 pub const CONSTANT_SELECTOR_INDEX: usize = 4;
 
+/// A constant variable for the q_P selector's index, which multiplies the left wire raised to the
+/// gate degree
+pub const POWER_SELECTOR_INDEX: usize = 5;
+
 /// A committed relaxed PLONK instance
 pub struct RelaxedPLONKInstance<F: PrimeField, Comm: FoldingCommitmentConfig<F>> {
     plonk_instance: PLONKInstance<F>,
@@ -30,9 +50,61 @@ pub struct RelaxedPLONKInstance<F: PrimeField, Comm: FoldingCommitmentConfig<F>>
     slack_commitment: <Comm::CommitmentSlack as HomomorphicCommitmentScheme<F>>::Commitment,
     witness_commitments:
         Vec<<Comm::CommitmentWitness as HomomorphicCommitmentScheme<F>>::Commitment>,
+    extra_commitments: Vec<<Comm::CommitmentWitness as HomomorphicCommitmentScheme<F>>::Commitment>,
 }
 
 impl<F: PrimeField, Comm: FoldingCommitmentConfig<F>> RelaxedPLONKInstance<F, Comm> {
+    /// Creates the instance of a fresh (non-relaxed) witness from its public input and output rows,
+    /// given column by column, and the commitments to its witness columns. The scaling factor is one,
+    /// the slack commitment is zero and there are no extra commitments.
+    pub(crate) fn new(
+        instance_columns: Vec<ColumnVector<F>>,
+        witness_commitments: Vec<
+            <Comm::CommitmentWitness as HomomorphicCommitmentScheme<F>>::Commitment,
+        >,
+    ) -> Self {
+        Self {
+            plonk_instance: PLONKInstance {
+                matrix: instance_columns,
+            },
+            scaling_factor: F::one(),
+            slack_commitment: Zero::zero(),
+            witness_commitments,
+            extra_commitments: Vec::new(),
+        }
+    }
+
+    /// Replaces the scaling factor and the slack commitment of the instance, for a relaxed instance
+    /// built from its parts.
+    pub(crate) fn with_slack_commitment(
+        self,
+        scaling_factor: F,
+        slack_commitment: <Comm::CommitmentSlack as HomomorphicCommitmentScheme<F>>::Commitment,
+    ) -> Self {
+        Self {
+            scaling_factor,
+            slack_commitment,
+            ..self
+        }
+    }
+
+    /// Attaches application-defined extra commitments (e.g. to a lookup accumulator or a memory
+    /// log) to the instance, replacing its current ones. The folding scheme folds them linearly, so
+    /// the application folds the committed vectors with the same challenges. Instances are only
+    /// folded with exactly as many extra commitments as the layout of their circuit declares, see
+    /// [`PLONKFoldingScheme::encode_with_extra_commitments`](crate::PLONKFoldingScheme::encode_with_extra_commitments).
+    pub fn with_extra_commitments(
+        self,
+        extra_commitments: Vec<
+            <Comm::CommitmentWitness as HomomorphicCommitmentScheme<F>>::Commitment,
+        >,
+    ) -> Self {
+        Self {
+            extra_commitments,
+            ..self
+        }
+    }
+
     /// Returns the i-th column of the PLONK instance or an error if index is out of bounds.
     pub fn instance_column(&self, column_index: usize) -> Result<ColumnVector<F>, SangriaError> {
         self.plonk_instance.column(column_index)
@@ -43,6 +115,99 @@ impl<F: PrimeField, Comm: FoldingCommitmentConfig<F>> RelaxedPLONKInstance<F, Co
         self.plonk_instance.row(row_index)
     }
 
+    /// Returns the public inputs of the instance, held by the first wire of its public input rows.
+    /// They are folded with the rest of the instance, so those of a folded instance are the linear
+    /// combination of the folded public inputs.
+    pub fn public_inputs(&self) -> Result<Vec<F>, SangriaError> {
+        let mut public_inputs = self.plonk_instance.column(0)?;
+        public_inputs
+            .pop()
+            .ok_or(SangriaError::PublicInputMismatch)?;
+
+        Ok(public_inputs)
+    }
+
+    /// Checks that the PLONK instance has one column per wire, each with exactly one row per public
+    /// input plus the output row, as laid out in the circuit, or returns an error.
+    pub fn check_public_input_rows(
+        &self,
+        number_of_public_inputs: usize,
+    ) -> Result<(), SangriaError> {
+        if !self
+            .plonk_instance
+            .has_shape(NUMBER_OF_WIRES, number_of_public_inputs + 1)
+        {
+            return Err(SangriaError::PublicInputMismatch);
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the instance holds `number_of_extra_commitments` extra commitments, or returns
+    /// [`SangriaError::CommitmentCount`].
+    pub(crate) fn check_extra_commitments(
+        &self,
+        number_of_extra_commitments: usize,
+    ) -> Result<(), SangriaError> {
+        if self.extra_commitments.len() != number_of_extra_commitments {
+            return Err(SangriaError::CommitmentCount {
+                expected: number_of_extra_commitments,
+                found: self.extra_commitments.len(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Checks an instance received from an untrusted party, e.g. a deserialized one, against the
+    /// layout of `schema` (the number and length of the instance columns, the number of witness
+    /// commitments and the number of extra commitments), then checks that its
+    /// commitments are valid, e.g. that points are on the curve and in the prime-order subgroup.
+    /// Returns [`SangriaError::InvalidProof`] if the layout does not match and
+    /// [`SangriaError::Serialization`] if a commitment is not valid.
+    pub fn validate(&self, schema: &InstanceSchema) -> Result<(), SangriaError> {
+        Self::validate_batch(schema, &[self], [].iter())
+    }
+
+    /// Validates `instances` like [`Self::validate`], checking their commitments together with
+    /// `slack_commitments` (e.g. the cross term commitments of a proof) in one batch per commitment
+    /// scheme, which curve implementations of [`Valid::batch_check`] can check faster than one
+    /// commitment at a time.
+    pub(crate) fn validate_batch<'a>(
+        schema: &InstanceSchema,
+        instances: &[&'a Self],
+        slack_commitments: impl Iterator<
+                Item = &'a <Comm::CommitmentSlack as HomomorphicCommitmentScheme<F>>::Commitment,
+            > + Send,
+    ) -> Result<(), SangriaError> {
+        for instance in instances {
+            if !instance
+                .plonk_instance
+                .has_shape(schema.instance_columns, schema.instance_rows)
+                || instance.witness_commitments.len() != schema.witness_commitments
+                || instance.extra_commitments.len() != schema.extra_commitments
+            {
+                return Err(SangriaError::InvalidProof);
+            }
+        }
+
+        Valid::batch_check(
+            instances
+                .iter()
+                .map(|instance| &instance.slack_commitment)
+                .chain(slack_commitments),
+        )
+        .and_then(|_| {
+            Valid::batch_check(instances.iter().flat_map(|instance| {
+                instance
+                    .witness_commitments
+                    .iter()
+                    .chain(&instance.extra_commitments)
+            }))
+        })
+        .map_err(SangriaError::from)
+    }
+
     /// Returns the scaling factor of the relaxed PLONK instance.
     pub fn scaling_factor(&self) -> F {
         self.scaling_factor
@@ -62,13 +227,75 @@ impl<F: PrimeField, Comm: FoldingCommitmentConfig<F>> RelaxedPLONKInstance<F, Co
         self.witness_commitments.clone()
     }
 
+    /// Returns the application-defined extra commitments (e.g. lookup accumulators or memory logs).
+    /// Their number is fixed by the application and they are folded linearly, like the witness
+    /// commitments.
+    pub fn extra_commitments(
+        &self,
+    ) -> Vec<<Comm::CommitmentWitness as HomomorphicCommitmentScheme<F>>::Commitment> {
+        self.extra_commitments.clone()
+    }
+
+    /// Folds `other` into this instance with the commitments to the `d - 1` cross terms of a gate
+    /// equation of degree `d` and the folding challenge `r`. Every component is folded linearly as
+    /// `self + r * other`, except the slack commitment which becomes `E_self + r T_1 + ... +
+    /// r^(d-1) T_(d-1) + r^d E_other`. Returns [`SangriaError::CommitmentCount`] if the instances
+    /// do not have as many witness or extra commitments.
+    pub fn fold(
+        &self,
+        other: &Self,
+        cross_term_commitments: &[<Comm::CommitmentSlack as HomomorphicCommitmentScheme<F>>::Commitment],
+        challenge: FoldChallenge<F>,
+    ) -> Result<Self, SangriaError> {
+        let r = challenge.value();
+        let mut folded = (other.clone() * r).try_add(self)?;
+        folded.slack_commitment = self.slack_commitment
+            + cross_term_commitments
+                .iter()
+                .rev()
+                .fold(other.slack_commitment * r, |folded, &cross_term| {
+                    (cross_term + folded) * r
+                });
+
+        Ok(folded)
+    }
+
+    /// Folds several instances at once with the commitments to the quotient terms `K_t` and the
+    /// folding challenge `r`, mirroring [`RelaxedPLONKWitness::fold_many`]: the instances are
+    /// combined as `sum_j L_j(r) instance_j`, with the Lagrange basis over the positions of the
+    /// instances, and `Z(r) sum_t L'_t(r) K_t` is added to the slack commitment (see
+    /// [`fold_many_coefficients`]). Returns an error if there is no instance or if the instances do
+    /// not have as many witness or extra commitments.
+    pub(crate) fn fold_many(
+        instances: &[Self],
+        quotient_commitments: &[<Comm::CommitmentSlack as HomomorphicCommitmentScheme<F>>::Commitment],
+        challenge: FoldChallenge<F>,
+    ) -> Result<Self, SangriaError> {
+        let (coefficients, quotient_coefficients) = fold_many_coefficients(
+            instances.len(),
+            quotient_commitments.len(),
+            challenge.value(),
+        );
+        let (first, others) = instances.split_first().ok_or(SangriaError::InvalidProof)?;
+        let mut folded = others.iter().zip(&coefficients[1..]).try_fold(
+            first.clone() * coefficients[0],
+            |folded, (instance, &coefficient)| folded.try_add(&(instance.clone() * coefficient)),
+        )?;
+        folded.slack_commitment = quotient_commitments.iter().zip(quotient_coefficients).fold(
+            folded.slack_commitment,
+            |folded, (&quotient, coefficient)| folded + quotient * coefficient,
+        );
+
+        Ok(folded)
+    }
+
     /// Returns a commitment to the i-th row of the witness or an error if index is out of bounds.
     pub fn single_witness_commitment(
         &self,
         column_index: usize,
     ) -> Result<<Comm::CommitmentWitness as HomomorphicCommitmentScheme<F>>::Commitment, SangriaError>
     {
-        if column_index > self.witness_commitments.len() {
+        if column_index >= self.witness_commitments.len() {
             return Err(SangriaError::IndexOutOfBounds);
         }
 
@@ -77,28 +304,160 @@ impl<F: PrimeField, Comm: FoldingCommitmentConfig<F>> RelaxedPLONKInstance<F, Co
 }
 
 impl<F, Comm> Absorb for RelaxedPLONKInstance<F, Comm>
+where
+    F: PrimeField + Absorb,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    fn to_sponge_bytes(&self, dest: &mut Vec<u8>) {
+        self.plonk_instance.matrix.to_sponge_bytes(dest);
+        self.scaling_factor.to_sponge_bytes(dest);
+        self.slack_commitment.to_sponge_bytes(dest);
+        self.witness_commitments.to_sponge_bytes(dest);
+        self.extra_commitments.to_sponge_bytes(dest);
+    }
+
+    fn to_sponge_field_elements<SpongeF: PrimeField>(&self, dest: &mut Vec<SpongeF>) {
+        self.plonk_instance.matrix.to_sponge_field_elements(dest);
+        self.scaling_factor.to_sponge_field_elements(dest);
+        self.slack_commitment.to_sponge_field_elements(dest);
+        self.witness_commitments.to_sponge_field_elements(dest);
+        self.extra_commitments.to_sponge_field_elements(dest);
+    }
+}
+
+impl<F, Comm> CanonicalSerialize for RelaxedPLONKInstance<F, Comm>
 where
     F: PrimeField,
     Comm: FoldingCommitmentConfig<F>,
 {
-    fn to_sponge_bytes(&self, _dest: &mut Vec<u8>) {
-        todo!()
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        self.plonk_instance
+            .matrix
+            .serialize_with_mode(&mut writer, compress)?;
+        self.scaling_factor
+            .serialize_with_mode(&mut writer, compress)?;
+        self.slack_commitment
+            .serialize_with_mode(&mut writer, compress)?;
+        self.witness_commitments
+            .serialize_with_mode(&mut writer, compress)?;
+        self.extra_commitments
+            .serialize_with_mode(&mut writer, compress)
     }
 
-    fn to_sponge_field_elements<SpongeF: PrimeField>(&self, _dest: &mut Vec<SpongeF>) {
-        todo!()
+    fn serialized_size(&self, compress: Compress) -> usize {
+        self.plonk_instance.matrix.serialized_size(compress)
+            + self.scaling_factor.serialized_size(compress)
+            + self.slack_commitment.serialized_size(compress)
+            + self.witness_commitments.serialized_size(compress)
+            + self.extra_commitments.serialized_size(compress)
     }
 }
 
-impl<F, Comm> Add<&Self> for RelaxedPLONKInstance<F, Comm>
+impl<F, Comm> RelaxedPLONKInstance<F, Comm>
 where
     F: PrimeField,
     Comm: FoldingCommitmentConfig<F>,
 {
-    type Output = Self;
+    /// Deserializes an instance from untrusted input. Every length prefix is checked against
+    /// `limits` before the entries are read, so oversized input is rejected without allocating.
+    pub fn deserialize_bounded<R: Read>(
+        mut reader: R,
+        limits: &SizeLimits,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let matrix = deserialize_bounded_vec_with(
+            &mut reader,
+            limits.max_columns,
+            compress,
+            validate,
+            |reader| deserialize_bounded_vec(reader, limits.max_rows, compress, validate),
+        )?;
+        let scaling_factor = F::deserialize_with_mode(&mut reader, compress, validate)?;
+        let slack_commitment =
+            CanonicalDeserialize::deserialize_with_mode(&mut reader, compress, validate)?;
+        let witness_commitments =
+            deserialize_bounded_vec(&mut reader, limits.max_columns, compress, validate)?;
+        let extra_commitments = deserialize_bounded_vec(
+            &mut reader,
+            limits.max_extra_commitments,
+            compress,
+            validate,
+        )?;
+
+        Ok(Self {
+            plonk_instance: PLONKInstance { matrix },
+            scaling_factor,
+            slack_commitment,
+            witness_commitments,
+            extra_commitments,
+        })
+    }
+}
 
-    fn add(self, _rhs: &Self) -> Self::Output {
-        todo!()
+impl<F, Comm> Valid for RelaxedPLONKInstance<F, Comm>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    fn check(&self) -> Result<(), SerializationError> {
+        Ok(())
+    }
+}
+
+/// Instances received from untrusted parties should be read with
+/// [`RelaxedPLONKInstance::deserialize_bounded`], which bounds every length prefix.
+impl<F, Comm> CanonicalDeserialize for RelaxedPLONKInstance<F, Comm>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        Ok(Self {
+            plonk_instance: PLONKInstance {
+                matrix: Vec::deserialize_with_mode(&mut reader, compress, validate)?,
+            },
+            scaling_factor: F::deserialize_with_mode(&mut reader, compress, validate)?,
+            slack_commitment: CanonicalDeserialize::deserialize_with_mode(
+                &mut reader,
+                compress,
+                validate,
+            )?,
+            witness_commitments: Vec::deserialize_with_mode(&mut reader, compress, validate)?,
+            extra_commitments: Vec::deserialize_with_mode(&mut reader, compress, validate)?,
+        })
+    }
+}
+
+impl<F, Comm> RelaxedPLONKInstance<F, Comm>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    /// Adds two instances component by component, or returns an error if they do not have as many
+    /// witness or extra commitments.
+    fn try_add(self, rhs: &Self) -> Result<Self, SangriaError> {
+        Ok(Self {
+            plonk_instance: self.plonk_instance.add(&rhs.plonk_instance),
+            scaling_factor: self.scaling_factor + rhs.scaling_factor,
+            slack_commitment: self.slack_commitment + rhs.slack_commitment,
+            witness_commitments: add_commitments::<F, Comm::CommitmentWitness>(
+                &self.witness_commitments,
+                &rhs.witness_commitments,
+            )?,
+            extra_commitments: add_commitments::<F, Comm::CommitmentWitness>(
+                &self.extra_commitments,
+                &rhs.extra_commitments,
+            )?,
+        })
     }
 }
 
@@ -109,8 +468,22 @@ where
 {
     type Output = Self;
 
-    fn mul(self, _rhs: F) -> Self::Output {
-        todo!()
+    fn mul(self, rhs: F) -> Self::Output {
+        Self {
+            plonk_instance: self.plonk_instance.scale(rhs),
+            scaling_factor: self.scaling_factor * rhs,
+            slack_commitment: self.slack_commitment * rhs,
+            witness_commitments: self
+                .witness_commitments
+                .into_iter()
+                .map(|commitment| commitment * rhs)
+                .collect(),
+            extra_commitments: self
+                .extra_commitments
+                .into_iter()
+                .map(|commitment| commitment * rhs)
+                .collect(),
+        }
     }
 }
 
@@ -120,18 +493,256 @@ where
     Comm: FoldingCommitmentConfig<F>,
 {
     fn clone(&self) -> Self {
-        todo!()
+        Self {
+            plonk_instance: self.plonk_instance.clone(),
+            scaling_factor: self.scaling_factor,
+            slack_commitment: self.slack_commitment,
+            witness_commitments: self.witness_commitments.clone(),
+            extra_commitments: self.extra_commitments.clone(),
+        }
     }
 }
 
+impl<F, Comm> PartialEq for RelaxedPLONKInstance<F, Comm>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.plonk_instance.matrix == other.plonk_instance.matrix
+            && self.scaling_factor == other.scaling_factor
+            && self.slack_commitment == other.slack_commitment
+            && self.witness_commitments == other.witness_commitments
+            && self.extra_commitments == other.extra_commitments
+    }
+}
+
+/// Returns the coefficients folding `number_of_instances` instances with `number_of_quotients`
+/// quotient terms at the challenge `r`. The instances sit at the points `0, ..., k - 1` and the
+/// quotient terms at the following points `k, k + 1, ...`: the instances are weighted by their
+/// Lagrange basis `L_j(r)` and the quotient terms by `Z(r) L'_t(r)`, where `Z` vanishes on the
+/// points of the instances and `L'_t` is the Lagrange basis over the points of the quotient terms.
+pub(crate) fn fold_many_coefficients<F: Field>(
+    number_of_instances: usize,
+    number_of_quotients: usize,
+    r: F,
+) -> (Vec<F>, Vec<F>) {
+    let instances = 0..number_of_instances;
+    let quotients = number_of_instances..number_of_instances + number_of_quotients;
+    let vanishing: F = instances
+        .clone()
+        .map(|point| r - F::from(point as u64))
+        .product();
+
+    (
+        lagrange_basis(instances, r),
+        lagrange_basis(quotients, r)
+            .into_iter()
+            .map(|basis| vanishing * basis)
+            .collect(),
+    )
+}
+
+/// Evaluates at `x` the Lagrange basis polynomials over the integer points of `points`.
+pub(crate) fn lagrange_basis<F: Field>(points: Range<usize>, x: F) -> Vec<F> {
+    points
+        .clone()
+        .map(|point| {
+            let (numerator, denominator) = points
+                .clone()
+                .filter(|&other| other != point)
+                .map(|other| {
+                    let other = F::from(other as u64);
+                    (x - other, F::from(point as u64) - other)
+                })
+                .fold((F::one(), F::one()), |(numerator, denominator), (x, y)| {
+                    (numerator * x, denominator * y)
+                });
+            numerator
+                * denominator
+                    .inverse()
+                    .expect("distinct integer points are distinct field elements")
+        })
+        .collect()
+}
+
+/// Adds two vectors of commitments entry by entry, or returns an error if they do not have the same
+/// length.
+fn add_commitments<F: PrimeField, C: HomomorphicCommitmentScheme<F>>(
+    left: &[C::Commitment],
+    right: &[C::Commitment],
+) -> Result<Vec<C::Commitment>, SangriaError> {
+    if left.len() != right.len() {
+        return Err(SangriaError::CommitmentCount {
+            expected: left.len(),
+            found: right.len(),
+        });
+    }
+
+    Ok(left
+        .iter()
+        .zip(right.iter())
+        .map(|(&left, &right)| left + right)
+        .collect())
+}
+
 /// A committed relaxed PLONK witness.
+#[derive(Clone)]
 pub struct RelaxedPLONKWitness<F: PrimeField> {
     plonk_witness: PLONKWitness<F>,
     slack_vector: ColumnVector<F>,
+    slack_hiding: F,
     commitment_hidings: Vec<F>,
 }
 
 impl<F: PrimeField> RelaxedPLONKWitness<F> {
+    /// Creates a relaxed witness from the witness columns, the slack vector and the randomness used
+    /// to commit to each witness column. The slack vector of a fresh witness is zero, so it is
+    /// committed to without randomness.
+    pub(crate) fn new(
+        witness_columns: Vec<ColumnVector<F>>,
+        slack_vector: ColumnVector<F>,
+        commitment_hidings: Vec<F>,
+    ) -> Self {
+        Self {
+            plonk_witness: PLONKWitness::from_columns(&witness_columns, WitnessLayout::default()),
+            slack_vector,
+            slack_hiding: F::zero(),
+            commitment_hidings,
+        }
+    }
+
+    /// Replaces the randomness of the commitment to the slack vector, for a relaxed witness whose
+    /// slack vector is not zero.
+    pub(crate) fn with_slack_hiding(self, slack_hiding: F) -> Self {
+        Self {
+            slack_hiding,
+            ..self
+        }
+    }
+
+    /// Folds `other` into this witness with the cross terms `T_1, ..., T_(d-1)`, the randomness
+    /// used to commit to them and the folding challenge `r`, mirroring
+    /// [`RelaxedPLONKInstance::fold`]: the witness columns and their randomness are folded as
+    /// `self + r * other` and the slack vector and its randomness become `E_self + r T_1 + ... +
+    /// r^(d-1) T_(d-1) + r^d E_other`. The cross terms are given as consecutive blocks of rows, so
+    /// that they can be evaluated one block at a time while the slack vector is folded.
+    pub(crate) fn fold(
+        &self,
+        other: &Self,
+        cross_term_chunks: impl IntoIterator<Item = Vec<Vec<F>>>,
+        cross_term_hidings: &[F],
+        r: F,
+    ) -> Self {
+        let fold_linearly = |left: &[F], right: &[F]| -> Vec<F> {
+            left.iter()
+                .zip(right.iter())
+                .map(|(&left, &right)| left + r * right)
+                .collect()
+        };
+        let mut slack_vector = Vec::with_capacity(self.slack_vector.len());
+        for cross_terms in cross_term_chunks {
+            let start = slack_vector.len();
+            let rows = start..start + cross_terms.first().map_or(0, Vec::len);
+            // Horner's rule, from the highest power of `r`
+            let fold_slack = |row: usize, left: F, right: F| -> F {
+                left + cross_terms
+                    .iter()
+                    .rev()
+                    .fold(r * right, |folded, cross_term| {
+                        r * (cross_term[row - start] + folded)
+                    })
+            };
+            slack_vector.extend(
+                self.slack_vector[rows.clone()]
+                    .iter()
+                    .zip(other.slack_vector[rows.clone()].iter())
+                    .zip(rows)
+                    .map(|((&left, &right), row)| fold_slack(row, left, right)),
+            );
+        }
+
+        Self {
+            plonk_witness: self.plonk_witness.fold(&other.plonk_witness, r),
+            slack_vector,
+            slack_hiding: self.slack_hiding
+                + cross_term_hidings
+                    .iter()
+                    .rev()
+                    .fold(r * other.slack_hiding, |folded, &hiding| {
+                        r * (hiding + folded)
+                    }),
+            commitment_hidings: fold_linearly(&self.commitment_hidings, &other.commitment_hidings),
+        }
+    }
+
+    /// Folds several witnesses at once with the quotient terms `K_t`, the randomness used to commit
+    /// to them and the folding challenge `r`, mirroring [`RelaxedPLONKInstance::fold_many`]: the
+    /// witness columns, the slack vectors and their randomness are combined as `sum_j L_j(r)
+    /// witness_j`, and `Z(r) sum_t L'_t(r) K_t` is added to the slack vector and its randomness.
+    /// The quotient terms are given on consecutive blocks of rows, like the cross terms of
+    /// [`Self::fold`], along with the rows of each block since there may be no quotient term.
+    pub(crate) fn fold_many(
+        witnesses: &[Self],
+        quotient_chunks: impl IntoIterator<Item = (Range<usize>, Vec<Vec<F>>)>,
+        quotient_hidings: &[F],
+        r: F,
+    ) -> Self {
+        let (coefficients, quotient_coefficients) =
+            fold_many_coefficients(witnesses.len(), quotient_hidings.len(), r);
+        let combine = |values: &dyn Fn(&Self) -> F| -> F {
+            witnesses
+                .iter()
+                .zip(&coefficients)
+                .map(|(witness, &coefficient)| coefficient * values(witness))
+                .sum()
+        };
+
+        let mut slack_vector = Vec::with_capacity(witnesses[0].slack_vector.len());
+        for (rows, quotients) in quotient_chunks {
+            let start = rows.start;
+            slack_vector.extend(rows.map(|row| {
+                combine(&|witness| witness.slack_vector[row])
+                    + quotients
+                        .iter()
+                        .zip(&quotient_coefficients)
+                        .map(|(quotient, &coefficient)| coefficient * quotient[row - start])
+                        .sum::<F>()
+            }));
+        }
+
+        Self {
+            plonk_witness: PLONKWitness::combine(
+                witnesses.iter().map(|witness| &witness.plonk_witness),
+                &coefficients,
+            ),
+            slack_vector,
+            slack_hiding: combine(&|witness| witness.slack_hiding)
+                + quotient_hidings
+                    .iter()
+                    .zip(&quotient_coefficients)
+                    .map(|(&hiding, &coefficient)| coefficient * hiding)
+                    .sum::<F>(),
+            commitment_hidings: (0..witnesses[0].commitment_hidings.len())
+                .map(|index| combine(&|witness| witness.commitment_hidings[index]))
+                .collect(),
+        }
+    }
+
+    /// Returns the memory layout of the witness columns.
+    pub fn layout(&self) -> WitnessLayout {
+        self.plonk_witness.layout
+    }
+
+    /// Returns this witness with its columns laid out with `layout`. Folding keeps the layout of
+    /// the running witness.
+    pub fn with_layout(self, layout: WitnessLayout) -> Self {
+        Self {
+            plonk_witness: self.plonk_witness.with_layout(layout),
+            ..self
+        }
+    }
+
     /// Returns the i-th column of the PLONK witness or an error if index is out of bounds.
     pub fn witness_column(&self, column_index: usize) -> Result<ColumnVector<F>, SangriaError> {
         self.plonk_witness.column(column_index)
@@ -147,6 +758,36 @@ impl<F: PrimeField> RelaxedPLONKWitness<F> {
         self.slack_vector.clone()
     }
 
+    /// Returns the number of gate rows of the witness and the length of its slack vector.
+    pub(crate) fn dimensions(&self) -> (usize, usize) {
+        (self.plonk_witness.number_of_rows, self.slack_vector.len())
+    }
+
+    /// Returns the entries of the i-th column of the PLONK witness on `rows`, which must be gate
+    /// rows of the witness.
+    pub(crate) fn witness_column_rows(&self, column_index: usize, rows: Range<usize>) -> Vec<F> {
+        rows.map(|row_index| {
+            self.plonk_witness.entries[self.plonk_witness.position(column_index, row_index)]
+        })
+        .collect()
+    }
+
+    /// Returns which columns of the PLONK witness are zero on every gate row, e.g. the wires a step
+    /// leaves unused.
+    pub(crate) fn zero_columns(&self) -> [bool; NUMBER_OF_WIRES] {
+        array::from_fn(|column_index| self.plonk_witness.is_zero_column(column_index))
+    }
+
+    /// Returns the entries of the slack vector on `rows`.
+    pub(crate) fn slack_rows(&self, rows: Range<usize>) -> Vec<F> {
+        self.slack_vector[rows].to_vec()
+    }
+
+    /// Returns the random value used when committing to the slack vector.
+    pub fn slack_hiding(&self) -> F {
+        self.slack_hiding
+    }
+
     /// Returns the random values used when committing to the witness columns.
     pub fn hiding_randomnesses(&self) -> Vec<F> {
         self.commitment_hidings.clone()
@@ -162,31 +803,245 @@ impl<F: PrimeField> RelaxedPLONKWitness<F> {
     }
 }
 
+/// Witnesses are serialized with [`encode_field_elements`], which writes the runs of zeros and
+/// small values of their columns and slack vector compactly, so that checkpoints and archives of
+/// running witnesses stay far smaller than their canonical encoding. The compression mode only
+/// applies to the randomness of the commitments.
+impl<F: PrimeField> CanonicalSerialize for RelaxedPLONKWitness<F> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        let layout = match self.plonk_witness.layout {
+            WitnessLayout::ColumnMajor => 0u8,
+            WitnessLayout::RowMajor => 1u8,
+        };
+        layout.serialize_with_mode(&mut writer, compress)?;
+        (self.plonk_witness.number_of_rows as u64).serialize_with_mode(&mut writer, compress)?;
+        encode_field_elements(&self.plonk_witness.entries, &mut writer)?;
+        encode_field_elements(&self.slack_vector, &mut writer)?;
+        self.slack_hiding
+            .serialize_with_mode(&mut writer, compress)?;
+        self.commitment_hidings
+            .serialize_with_mode(&mut writer, compress)
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        1 + 8
+            + encoded_len(&self.plonk_witness.entries)
+            + encoded_len(&self.slack_vector)
+            + self.slack_hiding.serialized_size(compress)
+            + self.commitment_hidings.serialized_size(compress)
+    }
+}
+
+impl<F: PrimeField> RelaxedPLONKWitness<F> {
+    /// Deserializes a witness from untrusted input. The number of rows and the lengths of the
+    /// columns, the slack vector and the randomness are checked against `limits` before the entries
+    /// are read, so oversized input is rejected without allocating.
+    pub fn deserialize_bounded<R: Read>(
+        mut reader: R,
+        limits: &SizeLimits,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let layout = match u8::deserialize_with_mode(&mut reader, compress, validate)? {
+            0 => WitnessLayout::ColumnMajor,
+            1 => WitnessLayout::RowMajor,
+            _ => return Err(SerializationError::InvalidData),
+        };
+        let number_of_rows = u64::deserialize_with_mode(&mut reader, compress, validate)?;
+        if number_of_rows > limits.max_rows as u64 {
+            return Err(SerializationError::InvalidData);
+        }
+        let number_of_rows = number_of_rows as usize;
+        let entries = decode_field_elements(&mut reader, number_of_rows * NUMBER_OF_WIRES)?;
+        if entries.len() != number_of_rows * NUMBER_OF_WIRES {
+            return Err(SerializationError::InvalidData);
+        }
+        let slack_vector = decode_field_elements(&mut reader, limits.max_rows)?;
+        let slack_hiding = F::deserialize_with_mode(&mut reader, compress, validate)?;
+        let commitment_hidings =
+            deserialize_bounded_vec(&mut reader, limits.max_columns, compress, validate)?;
+
+        Ok(Self {
+            plonk_witness: PLONKWitness {
+                entries,
+                number_of_rows,
+                layout,
+            },
+            slack_vector,
+            slack_hiding,
+            commitment_hidings,
+        })
+    }
+}
+
+impl<F: PrimeField> Valid for RelaxedPLONKWitness<F> {
+    fn check(&self) -> Result<(), SerializationError> {
+        Ok(())
+    }
+}
+
+/// Witnesses read from untrusted storage should be read with
+/// [`RelaxedPLONKWitness::deserialize_bounded`], which bounds every length prefix.
+impl<F: PrimeField> CanonicalDeserialize for RelaxedPLONKWitness<F> {
+    fn deserialize_with_mode<R: Read>(
+        reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let limits = SizeLimits {
+            max_columns: NUMBER_OF_WIRES,
+            max_rows: usize::MAX / NUMBER_OF_WIRES,
+            max_extra_commitments: usize::MAX,
+        };
+
+        Self::deserialize_bounded(reader, &limits, compress, validate)
+    }
+}
+
+/// The memory layout of the witness matrix. Commitments read the witness column by column while the
+/// cross term reads it row by row, so the faster layout depends on which pass dominates the prover
+/// (see the `witness_layout` benchmark).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WitnessLayout {
+    /// The entries of a column are contiguous.
+    #[default]
+    ColumnMajor,
+    /// The entries of a row are contiguous.
+    RowMajor,
+}
+
 /// A PLONK witness, this is a sub-table of the Trace with one row per circuit gate.
+#[derive(Clone)]
 pub struct PLONKWitness<F: PrimeField> {
-    matrix: Vec<ColumnVector<F>>,
+    entries: Vec<F>,
+    number_of_rows: usize,
+    layout: WitnessLayout,
 }
 
 impl<F: PrimeField> PLONKWitness<F> {
+    /// Lays out the witness columns with `layout`.
+    fn from_columns(columns: &[ColumnVector<F>], layout: WitnessLayout) -> Self {
+        let number_of_rows = columns.first().map_or(0, Vec::len);
+        let entries = match layout {
+            WitnessLayout::ColumnMajor => columns.concat(),
+            WitnessLayout::RowMajor => (0..number_of_rows)
+                .flat_map(|row| columns.iter().map(move |column| column[row]))
+                .collect(),
+        };
+
+        Self {
+            entries,
+            number_of_rows,
+            layout,
+        }
+    }
+
+    /// Returns whether the i-th column is zero on every row.
+    fn is_zero_column(&self, column_index: usize) -> bool {
+        (0..self.number_of_rows)
+            .all(|row_index| self.entries[self.position(column_index, row_index)].is_zero())
+    }
+
+    /// Returns the position of the entry at `(column, row)` in `entries`.
+    fn position(&self, column_index: usize, row_index: usize) -> usize {
+        match self.layout {
+            WitnessLayout::ColumnMajor => column_index * self.number_of_rows + row_index,
+            WitnessLayout::RowMajor => row_index * NUMBER_OF_WIRES + column_index,
+        }
+    }
+
     pub fn column(&self, column_index: usize) -> Result<ColumnVector<F>, SangriaError> {
-        if column_index > self.matrix.len() {
+        if column_index >= NUMBER_OF_WIRES {
             return Err(SangriaError::IndexOutOfBounds);
         }
 
-        Ok(self.matrix[column_index].clone())
+        Ok(match self.layout {
+            WitnessLayout::ColumnMajor => {
+                let start = self.position(column_index, 0);
+                self.entries[start..start + self.number_of_rows].to_vec()
+            }
+            WitnessLayout::RowMajor => self.entries[column_index..]
+                .iter()
+                .step_by(NUMBER_OF_WIRES)
+                .copied()
+                .collect(),
+        })
     }
 
     pub fn row(&self, row_index: usize) -> Result<Vec<F>, SangriaError> {
-        self.matrix
-            .iter()
-            .map(|column| -> Result<F, SangriaError> {
-                if row_index > column.len() {
-                    return Err(SangriaError::IndexOutOfBounds);
-                }
+        if row_index >= self.number_of_rows {
+            return Err(SangriaError::IndexOutOfBounds);
+        }
 
-                Ok(column[row_index])
+        Ok(match self.layout {
+            WitnessLayout::ColumnMajor => (0..NUMBER_OF_WIRES)
+                .map(|column_index| self.entries[self.position(column_index, row_index)])
+                .collect(),
+            WitnessLayout::RowMajor => {
+                let start = self.position(0, row_index);
+                self.entries[start..start + NUMBER_OF_WIRES].to_vec()
+            }
+        })
+    }
+
+    /// Returns the witness laid out with `layout`.
+    fn with_layout(self, layout: WitnessLayout) -> Self {
+        if layout == self.layout {
+            return self;
+        }
+
+        let columns: Vec<ColumnVector<F>> = (0..NUMBER_OF_WIRES)
+            .map(|column_index| {
+                (0..self.number_of_rows)
+                    .map(|row_index| self.entries[self.position(column_index, row_index)])
+                    .collect()
             })
-            .collect::<Result<Vec<_>, SangriaError>>()
+            .collect();
+        Self::from_columns(&columns, layout)
+    }
+
+    /// Combines witnesses as `sum_j coefficients_j witness_j`, keeping the layout of the first one.
+    fn combine<'a>(witnesses: impl IntoIterator<Item = &'a Self>, coefficients: &[F]) -> Self {
+        let mut witnesses = witnesses.into_iter().zip(coefficients);
+        let (first, &first_coefficient) =
+            witnesses.next().expect("at least one witness is combined");
+        let mut entries: Vec<F> = first
+            .entries
+            .iter()
+            .map(|&entry| first_coefficient * entry)
+            .collect();
+        for (witness, &coefficient) in witnesses {
+            let witness = witness.clone().with_layout(first.layout);
+            for (entry, &other) in entries.iter_mut().zip(&witness.entries) {
+                *entry += coefficient * other;
+            }
+        }
+
+        Self {
+            entries,
+            number_of_rows: first.number_of_rows,
+            layout: first.layout,
+        }
+    }
+
+    /// Folds `other` into this witness as `self + r * other`, keeping the layout of this witness.
+    fn fold(&self, other: &Self, r: F) -> Self {
+        let other = other.clone().with_layout(self.layout);
+
+        Self {
+            entries: self
+                .entries
+                .iter()
+                .zip(other.entries.iter())
+                .map(|(&left, &right)| left + r * right)
+                .collect(),
+            number_of_rows: self.number_of_rows,
+            layout: self.layout,
+        }
     }
 }
 
@@ -198,8 +1053,46 @@ pub struct PLONKInstance<F: PrimeField> {
 }
 
 impl<F: PrimeField> PLONKInstance<F> {
+    /// Adds two instances entry by entry.
+    pub fn add(&self, other: &Self) -> Self {
+        let matrix = self
+            .matrix
+            .iter()
+            .zip(other.matrix.iter())
+            .map(|(left, right)| {
+                left.iter()
+                    .zip(right.iter())
+                    .map(|(&l, &r)| l + r)
+                    .collect()
+            })
+            .collect();
+
+        Self { matrix }
+    }
+
+    /// Multiplies every entry of the instance by `scalar`.
+    pub fn scale(&self, scalar: F) -> Self {
+        let matrix = self
+            .matrix
+            .iter()
+            .map(|column| column.iter().map(|&value| value * scalar).collect())
+            .collect();
+
+        Self { matrix }
+    }
+
+    /// Returns true if the instance has `number_of_columns` columns of exactly `number_of_rows`
+    /// entries each.
+    pub fn has_shape(&self, number_of_columns: usize, number_of_rows: usize) -> bool {
+        self.matrix.len() == number_of_columns
+            && self
+                .matrix
+                .iter()
+                .all(|column| column.len() == number_of_rows)
+    }
+
     pub fn column(&self, column_index: usize) -> Result<ColumnVector<F>, SangriaError> {
-        if column_index > self.matrix.len() {
+        if column_index >= self.matrix.len() {
             return Err(SangriaError::IndexOutOfBounds);
         }
 
@@ -210,7 +1103,7 @@ impl<F: PrimeField> PLONKInstance<F> {
         self.matrix
             .iter()
             .map(|column| -> Result<F, SangriaError> {
-                if row_index > column.len() {
+                if row_index >= column.len() {
                     return Err(SangriaError::IndexOutOfBounds);
                 }
 
@@ -221,39 +1114,873 @@ impl<F: PrimeField> PLONKInstance<F> {
 }
 
 /// A structure that hold the defining elements of a PLONK circuit
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
 pub struct PLONKCircuit<F: Field> {
     selectors: Vec<ColumnVector<F>>,
+    fixed_columns: Vec<ColumnVector<F>>,
     copy_constraint: Permutation<F>,
 }
 
 impl<F: Field> PLONKCircuit<F> {
+    /// Creates a circuit from its selector columns, fixed columns and copy constraints.
+    pub(crate) fn new(
+        selectors: Vec<ColumnVector<F>>,
+        fixed_columns: Vec<ColumnVector<F>>,
+        copy_constraint: Permutation<F>,
+    ) -> Self {
+        Self {
+            selectors,
+            fixed_columns,
+            copy_constraint,
+        }
+    }
+
     /// Returns the selectors matrix.
     pub fn selectors(&self) -> Vec<ColumnVector<F>> {
         self.selectors.clone()
     }
 
+    /// Returns the selectors on `rows`.
+    pub(crate) fn selector_rows(&self, rows: Range<usize>) -> Vec<ColumnVector<F>> {
+        self.selectors
+            .iter()
+            .map(|selector| selector[rows.clone()].to_vec())
+            .collect()
+    }
+
+    /// Returns the number of rows of the circuit: one per public input, one per gate and the output row.
+    pub fn number_of_rows(&self) -> usize {
+        self.selectors.first().map_or(0, |selector| selector.len())
+    }
+
     /// Returns a single selector or an error if index is out of bounds.
     pub fn single_selector(&self, selector_index: usize) -> Result<ColumnVector<F>, SangriaError> {
-        if selector_index > self.selectors.len() {
+        if selector_index >= self.selectors.len() {
             return Err(SangriaError::IndexOutOfBounds);
         }
 
         Ok(self.selectors[selector_index].clone())
     }
 
+    /// Returns the fixed columns holding the step circuit's constant tables. The gate equation does
+    /// not read them directly: a gate reads a table entry through its constant selector, as laid out
+    /// by [`CircuitBuilder::fixed`], and the fixed columns are absorbed with the circuit so that its
+    /// keys are bound to the tables.
+    pub fn fixed_columns(&self) -> Vec<ColumnVector<F>> {
+        self.fixed_columns.clone()
+    }
+
+    /// Returns the entry at `row` of the constant table `table_id` or an error if either index is out of bounds.
+    pub fn fixed(&self, table_id: usize, row: usize) -> Result<F, SangriaError> {
+        self.fixed_columns
+            .get(table_id)
+            .and_then(|column| column.get(row))
+            .copied()
+            .ok_or(SangriaError::IndexOutOfBounds)
+    }
+
     /// Returns the copy constraints.
     pub fn copy_constraint(&self) -> Permutation<F> {
         self.copy_constraint.clone()
     }
 }
 
-impl<CircuitField: PrimeField> Absorb for PLONKCircuit<CircuitField> {
-    fn to_sponge_bytes(&self, _dest: &mut Vec<u8>) {
-        todo!()
+impl<CircuitField: PrimeField + Absorb> Absorb for PLONKCircuit<CircuitField> {
+    fn to_sponge_bytes(&self, dest: &mut Vec<u8>) {
+        self.number_of_rows().to_sponge_bytes(dest);
+        self.selectors.to_sponge_bytes(dest);
+        // the constant tables may have any length
+        self.fixed_columns.len().to_sponge_bytes(dest);
+        for column in self.fixed_columns.iter() {
+            column.to_sponge_bytes_with_length(dest);
+        }
+        self.copy_constraint.to_sponge_bytes(dest);
+    }
+
+    fn to_sponge_field_elements<F: PrimeField>(&self, dest: &mut Vec<F>) {
+        self.number_of_rows().to_sponge_field_elements(dest);
+        self.selectors.to_sponge_field_elements(dest);
+        // the constant tables may have any length
+        self.fixed_columns.len().to_sponge_field_elements(dest);
+        for column in self.fixed_columns.iter() {
+            column.to_sponge_field_elements_with_length(dest);
+        }
+        self.copy_constraint.to_sponge_field_elements(dest);
+    }
+}
+
+/// A row of a trace that does not satisfy its gate, as reported by [`MockProver`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GateFailure<F> {
+    /// the row of the trace
+    pub row: usize,
+    /// the selectors of the row, indexed by [`LEFT_SELECTOR_INDEX`] and the other selector indices
+    pub selectors: [F; NUMBER_OF_SELECTORS],
+    /// the left, right and output wires of the row
+    pub wires: [F; NUMBER_OF_WIRES],
+    /// the value of the gate equation `q_L a + q_R b + q_O c + q_M a b + q_P a^d + q_C` on the row,
+    /// which is zero on the rows satisfying their gate
+    pub residual: F,
+}
+
+/// Writes the values as signed integers, so that e.g. the `-1` selectors of output wires read as
+/// such.
+impl<F: PrimeField> fmt::Display for GateFailure<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [a, b, c] = self.wires.map(signed);
+        let selector = |index: usize| signed(self.selectors[index]);
+        write!(
+            f,
+            "row {}: q_L = {}, q_R = {}, q_O = {}, q_M = {}, q_C = {}, q_P = {}, a = {a}, b = {b}, \
+             c = {c}, residual = {}",
+            self.row,
+            selector(LEFT_SELECTOR_INDEX),
+            selector(RIGHT_SELECTOR_INDEX),
+            selector(OUTPUT_SELECTOR_INDEX),
+            selector(MULTIPLICATION_SELECTOR_INDEX),
+            selector(CONSTANT_SELECTOR_INDEX),
+            selector(POWER_SELECTOR_INDEX),
+            signed(self.residual),
+        )
+    }
+}
+
+/// Returns `value` in decimal, as a negative integer when its negation is shorter (e.g. `-1` rather
+/// than `p - 1`).
+fn signed<F: PrimeField>(value: F) -> String {
+    let negated = -value;
+    if value.is_zero() {
+        "0".to_string()
+    } else if negated.into_bigint().num_bits() < value.into_bigint().num_bits() {
+        format!("-{negated}")
+    } else {
+        value.to_string()
+    }
+}
+
+/// Evaluates the gates of a circuit on a trace without committing to anything, to debug a step
+/// circuit: unlike [`PLONKFoldingScheme::check_relation`](crate::PLONKFoldingScheme::check_relation),
+/// which stops at the first error, it reports every row that does not satisfy its gate with the
+/// selectors, the wires and the residual of the gate equation on that row. Copy constraints are
+/// checked when the witness is extracted from the trace.
+#[derive(Clone, Debug)]
+pub struct MockProver<F: PrimeField> {
+    selectors: Vec<ColumnVector<F>>,
+    columns: Vec<ColumnVector<F>>,
+    gate_degree: usize,
+}
+
+impl<F: PrimeField> MockProver<F> {
+    /// Reads the wires of `trace` to check them against the gates of `circuit`, whose power gates
+    /// raise the left wire to `gate_degree`. Returns an error if the trace does not have a row per
+    /// row of the circuit.
+    pub fn new(
+        circuit: &PLONKCircuit<F>,
+        trace: &TraceTable<F>,
+        gate_degree: usize,
+    ) -> Result<Self, SangriaError> {
+        if trace.number_of_rows() != circuit.number_of_rows() {
+            return Err(SangriaError::InvalidTrace(
+                "the trace does not have a row per row of the circuit",
+            ));
+        }
+        if gate_degree < 2 {
+            return Err(SangriaError::InvalidConfig(
+                "the gate degree must be at least two",
+            ));
+        }
+
+        let columns = (0..NUMBER_OF_WIRES)
+            .map(|column| {
+                (0..trace.number_of_rows())
+                    .map(|row| trace.wire(column, row))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            selectors: circuit.selectors(),
+            columns,
+            gate_degree,
+        })
     }
 
-    fn to_sponge_field_elements<F: PrimeField>(&self, _dest: &mut Vec<F>) {
-        todo!()
+    /// Returns the rows that do not satisfy their gate, in order.
+    pub fn failures(&self) -> Vec<GateFailure<F>> {
+        gate_evaluations(&self.selectors, &self.columns, F::one(), self.gate_degree)
+            .into_iter()
+            .enumerate()
+            .filter(|(_, residual)| !residual.is_zero())
+            .map(|(row, residual)| GateFailure {
+                row,
+                selectors: array::from_fn(|index| self.selectors[index][row]),
+                wires: array::from_fn(|column| self.columns[column][row]),
+                residual,
+            })
+            .collect()
+    }
+
+    /// Checks that every row satisfies its gate, or returns the rows that do not.
+    pub fn verify(&self) -> Result<(), Vec<GateFailure<F>>> {
+        let failures = self.failures();
+        if !failures.is_empty() {
+            return Err(failures);
+        }
+
+        Ok(())
+    }
+}
+
+/// A variable of a [`CircuitBuilder`]: a public input or the output of a gate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Variable(usize);
+
+/// A wire of a [`CircuitBuilder`], before the rows are laid out: the public input row or the gate
+/// row, and the column.
+#[derive(Clone, Copy, Debug)]
+enum BuilderWire {
+    PublicInput(usize),
+    Gate(usize, usize),
+}
+
+/// Builds a circuit gate by gate, tracking which wires hold each variable so that the copy
+/// constraints follow from the variables passed to the gates. Public inputs can be declared at any
+/// point: they are laid out before the gates when the circuit is built.
+#[derive(Clone, Debug)]
+pub struct CircuitBuilder<F: PrimeField> {
+    values: Vec<F>,
+    definitions: Vec<BuilderWire>,
+    public_inputs: Vec<Variable>,
+    gates: Vec<(
+        [F; NUMBER_OF_SELECTORS],
+        [Option<Variable>; NUMBER_OF_WIRES],
+    )>,
+    equalities: Vec<(Variable, Variable)>,
+    fixed_tables: Vec<ColumnVector<F>>,
+}
+
+impl<F: PrimeField> CircuitBuilder<F> {
+    /// Creates an empty builder.
+    pub fn new() -> Self {
+        Self::with_fixed_tables(Vec::new())
+    }
+
+    /// Creates an empty builder of a circuit with the given constant tables, e.g. those of
+    /// [`StepCircuit::fixed_tables`](crate::StepCircuit::fixed_tables), which become the fixed
+    /// columns of the built circuit and are read with [`Self::fixed`].
+    pub fn with_fixed_tables(fixed_tables: Vec<Vec<F>>) -> Self {
+        Self {
+            values: Vec::new(),
+            definitions: Vec::new(),
+            public_inputs: Vec::new(),
+            gates: Vec::new(),
+            equalities: Vec::new(),
+            fixed_tables,
+        }
+    }
+
+    /// Returns the value assigned to `variable`, or an error if it was not created by this
+    /// builder.
+    pub fn value(&self, variable: Variable) -> Result<F, SangriaError> {
+        self.values
+            .get(variable.0)
+            .copied()
+            .ok_or(SangriaError::IndexOutOfBounds)
+    }
+
+    /// Declares a public input with the given value.
+    pub fn public_input(&mut self, value: F) -> Variable {
+        let variable = self.new_variable(value, BuilderWire::PublicInput(self.public_inputs.len()));
+        self.public_inputs.push(variable);
+        variable
+    }
+
+    /// Returns a variable constrained to equal `value`.
+    pub fn constant(&mut self, value: F) -> Variable {
+        let mut selectors = [F::zero(); NUMBER_OF_SELECTORS];
+        selectors[LEFT_SELECTOR_INDEX] = F::one();
+        selectors[CONSTANT_SELECTOR_INDEX] = -value;
+
+        let variable = self.new_variable(value, BuilderWire::Gate(self.gates.len(), 0));
+        self.gates.push((selectors, [None; NUMBER_OF_WIRES]));
+        variable
+    }
+
+    /// Returns a variable constrained to equal the entry at `row` of the constant table `table_id`,
+    /// or an error if either index is out of bounds. The entry is read by the constant selector of a
+    /// gate, so that the gate only holds for the tables the circuit was built with.
+    pub fn fixed(&mut self, table_id: usize, row: usize) -> Result<Variable, SangriaError> {
+        let value = self
+            .fixed_tables
+            .get(table_id)
+            .and_then(|table| table.get(row))
+            .copied()
+            .ok_or(SangriaError::IndexOutOfBounds)?;
+
+        Ok(self.constant(value))
+    }
+
+    /// Returns a variable constrained to equal `left + right`.
+    pub fn add(&mut self, left: Variable, right: Variable) -> Result<Variable, SangriaError> {
+        let value = self.value(left)? + self.value(right)?;
+        let mut selectors = [F::zero(); NUMBER_OF_SELECTORS];
+        selectors[LEFT_SELECTOR_INDEX] = F::one();
+        selectors[RIGHT_SELECTOR_INDEX] = F::one();
+        selectors[OUTPUT_SELECTOR_INDEX] = -F::one();
+
+        Ok(self.push_gate(selectors, left, right, value))
+    }
+
+    /// Returns a variable constrained to equal `left * right`.
+    pub fn mul(&mut self, left: Variable, right: Variable) -> Result<Variable, SangriaError> {
+        let value = self.value(left)? * self.value(right)?;
+        let mut selectors = [F::zero(); NUMBER_OF_SELECTORS];
+        selectors[MULTIPLICATION_SELECTOR_INDEX] = F::one();
+        selectors[OUTPUT_SELECTOR_INDEX] = -F::one();
+
+        Ok(self.push_gate(selectors, left, right, value))
+    }
+
+    /// Constrains two variables to be equal. Their values are only compared when the witness is
+    /// extracted from the trace, so a circuit can be built from placeholder values.
+    pub fn assert_equal(&mut self, left: Variable, right: Variable) -> Result<(), SangriaError> {
+        self.value(left)?;
+        self.value(right)?;
+
+        self.equalities.push((left, right));
+        Ok(())
+    }
+
+    /// Returns the number of gates added so far.
+    pub fn number_of_gates(&self) -> usize {
+        self.gates.len()
+    }
+
+    /// Lays out the public input rows, the gate rows and an unused output row, and returns the
+    /// circuit, whose fixed columns hold the constant tables of the builder, and the trace holding
+    /// the assigned values, from which the instance and the witness are extracted.
+    pub fn build(self) -> Result<(PLONKCircuit<F>, TraceTable<F>), SangriaError> {
+        let number_of_public_inputs = self.public_inputs.len();
+        let position = |wire: BuilderWire| match wire {
+            BuilderWire::PublicInput(row) => (0, row),
+            BuilderWire::Gate(gate, column) => (column, number_of_public_inputs + gate),
+        };
+
+        let mut trace = TraceTable::new();
+        for &variable in self.public_inputs.iter() {
+            trace.push_row(
+                RowTag::PublicInput,
+                [self.values[variable.0], F::zero(), F::zero()],
+            );
+        }
+        let mut gate_wires = vec![[F::zero(); NUMBER_OF_WIRES]; self.gates.len()];
+        for (&value, definition) in self.values.iter().zip(self.definitions.iter()) {
+            if let BuilderWire::Gate(gate, column) = *definition {
+                gate_wires[gate][column] = value;
+            }
+        }
+        for (gate, ((selectors, inputs), mut wires)) in
+            self.gates.iter().zip(gate_wires).enumerate()
+        {
+            for (column, input) in inputs.iter().enumerate() {
+                if let Some(variable) = input {
+                    wires[column] = self.values[variable.0];
+                    trace.copy(
+                        position(self.definitions[variable.0]),
+                        position(BuilderWire::Gate(gate, column)),
+                    );
+                }
+            }
+            trace.push_row(RowTag::Gate(*selectors), wires);
+        }
+        for &(left, right) in self.equalities.iter() {
+            trace.copy(
+                position(self.definitions[left.0]),
+                position(self.definitions[right.0]),
+            );
+        }
+        trace.push_row(RowTag::Output, [F::zero(); NUMBER_OF_WIRES]);
+
+        Ok((trace.circuit(self.fixed_tables)?, trace))
+    }
+
+    /// Assigns `value` to a new variable defined by the wire `definition`.
+    fn new_variable(&mut self, value: F, definition: BuilderWire) -> Variable {
+        self.values.push(value);
+        self.definitions.push(definition);
+        Variable(self.values.len() - 1)
+    }
+
+    /// Adds a gate on `left` and `right` whose output wire defines a new variable.
+    fn push_gate(
+        &mut self,
+        selectors: [F; NUMBER_OF_SELECTORS],
+        left: Variable,
+        right: Variable,
+        value: F,
+    ) -> Variable {
+        let variable = self.new_variable(value, BuilderWire::Gate(self.gates.len(), 2));
+        self.gates
+            .push((selectors, [Some(left), Some(right), None]));
+        variable
+    }
+}
+
+impl<F: PrimeField> Default for CircuitBuilder<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        CircuitBuilder, GateFailure, MockProver, PLONKInstance, RelaxedPLONKInstance,
+        RelaxedPLONKWitness, WitnessLayout, NUMBER_OF_WIRES,
+    };
+    use crate::{
+        folding_scheme::{PLONKFoldingScheme, SetupInfo},
+        pedersen::{Commitment, PedersenCommitment},
+        trace::NUMBER_OF_SELECTORS,
+        utils::{
+            inner_product::InnerProductConfig, poseidon::poseidon_parameters_for_test,
+            rand::sample_vector,
+        },
+        InstanceSchema, NonInteractiveFoldingScheme, PLONKCircuit, RowTag, SangriaConfig,
+        SangriaError, SerializationFailure, SingleCommitment, SizeLimits, TraceTable,
+        MULTIPLICATION_SELECTOR_INDEX, OUTPUT_SELECTOR_INDEX,
+    };
+    use ark_bn254::{Fq, Fr, G1Affine};
+    use ark_crypto_primitives::sponge::poseidon::PoseidonSponge;
+    use ark_ec::AffineRepr;
+    use ark_ff::{One, UniformRand, Zero};
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Compress, Validate};
+    use ark_std::{rand::Rng, test_rng};
+
+    const LIMITS: SizeLimits = SizeLimits {
+        max_columns: NUMBER_OF_WIRES,
+        max_rows: 4,
+        max_extra_commitments: 1,
+    };
+
+    fn random_instance<R: Rng>(rng: &mut R) -> RelaxedPLONKInstance<Fr, InnerProductConfig> {
+        RelaxedPLONKInstance {
+            plonk_instance: PLONKInstance {
+                matrix: (0..NUMBER_OF_WIRES)
+                    .map(|_| (0..4).map(|_| Fr::rand(rng)).collect())
+                    .collect(),
+            },
+            scaling_factor: Fr::rand(rng),
+            slack_commitment: Fr::rand(rng),
+            witness_commitments: (0..NUMBER_OF_WIRES).map(|_| Fr::rand(rng)).collect(),
+            extra_commitments: vec![Fr::rand(rng)],
+        }
+    }
+
+    fn parse(bytes: &[u8], limits: &SizeLimits) -> bool {
+        RelaxedPLONKInstance::<Fr, InnerProductConfig>::deserialize_bounded(
+            bytes,
+            limits,
+            Compress::Yes,
+            Validate::Yes,
+        )
+        .is_ok()
+    }
+
+    #[test]
+    fn instance_round_trips_within_limits() {
+        let instance = random_instance(&mut test_rng());
+        let mut bytes = Vec::new();
+        instance.serialize_compressed(&mut bytes).unwrap();
+
+        let parsed = RelaxedPLONKInstance::<Fr, InnerProductConfig>::deserialize_bounded(
+            &bytes[..],
+            &LIMITS,
+            Compress::Yes,
+            Validate::Yes,
+        )
+        .unwrap();
+        let mut reserialized = Vec::new();
+        parsed.serialize_compressed(&mut reserialized).unwrap();
+
+        assert_eq!(reserialized, bytes);
+        assert_eq!(bytes.len(), instance.compressed_size());
+        let read =
+            RelaxedPLONKInstance::<Fr, InnerProductConfig>::deserialize_compressed(&bytes[..])
+                .unwrap();
+        assert!(read == instance);
+        assert!(!parse(&bytes, &LIMITS.with_extra_commitments(0)));
+        assert!(!parse(
+            &bytes,
+            &SizeLimits {
+                max_rows: 3,
+                ..LIMITS
+            }
+        ));
+    }
+
+    #[test]
+    fn malformed_instances_are_rejected_without_panicking() {
+        let rng = &mut test_rng();
+        let mut bytes = Vec::new();
+        random_instance(rng)
+            .serialize_compressed(&mut bytes)
+            .unwrap();
+
+        for _ in 0..1000 {
+            // truncate, corrupt a byte or feed random bytes
+            let mut input = bytes.clone();
+            match rng.gen_range(0..3) {
+                0 => input.truncate(rng.gen_range(0..bytes.len())),
+                1 => input[rng.gen_range(0..bytes.len())] = rng.gen(),
+                _ => input = (0..rng.gen_range(0..256)).map(|_| rng.gen()).collect(),
+            }
+            parse(&input, &LIMITS);
+        }
+
+        // a column count prefix of 2^64 - 1
+        let mut oversized = bytes;
+        oversized[..8].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(!parse(&oversized, &LIMITS));
+    }
+
+    #[test]
+    fn instances_are_validated_against_the_schema() {
+        let rng = &mut test_rng();
+        let schema = InstanceSchema {
+            number_of_public_inputs: 3,
+            instance_columns: NUMBER_OF_WIRES,
+            instance_rows: 4,
+            witness_commitments: NUMBER_OF_WIRES,
+            extra_commitments: 1,
+            cross_term_commitments: 1,
+        };
+        let instance = random_instance(rng);
+        assert_eq!(instance.validate(&schema), Ok(()));
+
+        let mut missing_commitment = instance.clone();
+        missing_commitment.witness_commitments.pop();
+        let mut short_column = instance.clone();
+        short_column.plonk_instance.matrix[2].pop();
+        for invalid in [missing_commitment, short_column] {
+            assert_eq!(invalid.validate(&schema), Err(SangriaError::InvalidProof));
+        }
+        let no_extra_commitments = InstanceSchema {
+            extra_commitments: 0,
+            ..schema
+        };
+        let mut missing_extra_commitment = instance.clone();
+        missing_extra_commitment.extra_commitments.clear();
+        assert_eq!(
+            instance.validate(&no_extra_commitments),
+            Err(SangriaError::InvalidProof)
+        );
+        assert_eq!(
+            missing_extra_commitment.validate(&schema),
+            Err(SangriaError::InvalidProof)
+        );
+
+        // a point off the curve, as read from unchecked input
+        let generator = Commitment(G1Affine::generator());
+        let mut instance = RelaxedPLONKInstance::<Fr, SingleCommitment<PedersenCommitment<_>>> {
+            plonk_instance: PLONKInstance {
+                matrix: vec![vec![Fr::zero(); 4]; NUMBER_OF_WIRES],
+            },
+            scaling_factor: Fr::one(),
+            slack_commitment: generator,
+            witness_commitments: vec![generator; NUMBER_OF_WIRES],
+            extra_commitments: vec![generator],
+        };
+        assert_eq!(instance.validate(&schema), Ok(()));
+        instance.witness_commitments[1] = Commitment(G1Affine::new_unchecked(Fq::one(), Fq::one()));
+        assert_eq!(
+            instance.validate(&schema),
+            Err(SangriaError::Serialization(
+                SerializationFailure::InvalidData
+            ))
+        );
+    }
+
+    #[test]
+    fn missing_instance_columns_and_rows_are_errors() {
+        let rng = &mut test_rng();
+        let mut instance = random_instance(rng);
+        assert_eq!(
+            instance.instance_row(4),
+            Err(SangriaError::IndexOutOfBounds)
+        );
+        assert_eq!(
+            instance.instance_column(NUMBER_OF_WIRES),
+            Err(SangriaError::IndexOutOfBounds)
+        );
+
+        instance.plonk_instance.matrix.clear();
+        assert_eq!(
+            instance.public_inputs(),
+            Err(SangriaError::IndexOutOfBounds)
+        );
+        assert_eq!(
+            instance.check_public_input_rows(3),
+            Err(SangriaError::PublicInputMismatch)
+        );
+    }
+
+    #[test]
+    fn witness_layouts_agree() {
+        let rng = &mut test_rng();
+        let random_witness = |rng: &mut _| {
+            RelaxedPLONKWitness::<Fr>::new(
+                (0..NUMBER_OF_WIRES)
+                    .map(|_| sample_vector(rng, 5))
+                    .collect(),
+                sample_vector(rng, 7),
+                sample_vector(rng, NUMBER_OF_WIRES),
+            )
+        };
+        let (left, right) = (random_witness(rng), random_witness(rng));
+        let (cross_terms, r): (Vec<Vec<Fr>>, Fr) = (vec![sample_vector(rng, 7)], Fr::rand(rng));
+        let hidings = [Fr::rand(rng)];
+
+        let row_major = left.clone().with_layout(WitnessLayout::RowMajor);
+        assert_eq!(row_major.layout(), WitnessLayout::RowMajor);
+        let folded = left.fold(&right, [cross_terms.clone()], &hidings, r);
+        let folded_row_major = row_major.fold(&right, [cross_terms.clone()], &hidings, r);
+        assert_eq!(folded_row_major.layout(), WitnessLayout::RowMajor);
+        let folded_in_chunks = left.fold(
+            &right,
+            [
+                vec![cross_terms[0][..3].to_vec()],
+                vec![cross_terms[0][3..].to_vec()],
+            ],
+            &hidings,
+            r,
+        );
+        assert_eq!(folded_in_chunks.slack_vector(), folded.slack_vector());
+
+        for (witness, other) in [(&left, &row_major), (&folded, &folded_row_major)] {
+            for index in 0..NUMBER_OF_WIRES {
+                assert_eq!(witness.witness_column(index), other.witness_column(index));
+            }
+            for index in 0..5 {
+                assert_eq!(witness.witness_row(index), other.witness_row(index));
+            }
+        }
+        assert!(row_major.witness_row(5).is_err());
+        assert!(row_major.witness_column(NUMBER_OF_WIRES).is_err());
+    }
+
+    #[test]
+    fn witnesses_round_trip_compactly() {
+        let rng = &mut test_rng();
+        // small wire values and a zero slack vector, like a fresh witness
+        let columns: Vec<Vec<Fr>> = (0..NUMBER_OF_WIRES)
+            .map(|_| {
+                (0..64)
+                    .map(|_| Fr::from(rng.gen_range(0..1000u64)))
+                    .collect()
+            })
+            .collect();
+        let limits = SizeLimits {
+            max_rows: 64,
+            ..LIMITS
+        };
+
+        for layout in [WitnessLayout::ColumnMajor, WitnessLayout::RowMajor] {
+            let witness = RelaxedPLONKWitness::new(
+                columns.clone(),
+                vec![Fr::zero(); 64],
+                sample_vector(rng, NUMBER_OF_WIRES),
+            )
+            .with_layout(layout);
+            let mut bytes = Vec::new();
+            witness.serialize_compressed(&mut bytes).unwrap();
+            assert_eq!(bytes.len(), witness.compressed_size());
+            // the columns, the slack vector and the randomness
+            let elements = NUMBER_OF_WIRES * 64 + 64 + 1 + NUMBER_OF_WIRES;
+            assert!(bytes.len() * 5 < elements * 32);
+            let read = RelaxedPLONKWitness::<Fr>::deserialize_compressed(&bytes[..]).unwrap();
+            assert_eq!(read.witness_row(63), witness.witness_row(63));
+
+            let parsed = RelaxedPLONKWitness::<Fr>::deserialize_bounded(
+                &bytes[..],
+                &limits,
+                Compress::Yes,
+                Validate::Yes,
+            )
+            .unwrap();
+            assert_eq!(parsed.layout(), layout);
+            for index in 0..NUMBER_OF_WIRES {
+                assert_eq!(parsed.witness_column(index), witness.witness_column(index));
+            }
+            assert_eq!(parsed.slack_vector(), witness.slack_vector());
+            assert_eq!(parsed.hiding_randomnesses(), witness.hiding_randomnesses());
+
+            let too_small = SizeLimits {
+                max_rows: 63,
+                ..limits
+            };
+            assert!(RelaxedPLONKWitness::<Fr>::deserialize_bounded(
+                &bytes[..],
+                &too_small,
+                Compress::Yes,
+                Validate::Yes,
+            )
+            .is_err());
+        }
+    }
+
+    /// Builds the circuit checking the public inputs `y = x^2 + 5`.
+    fn square_plus_five(x: u64, y: u64) -> (PLONKCircuit<Fr>, TraceTable<Fr>) {
+        let mut builder = CircuitBuilder::new();
+        let x = builder.public_input(Fr::from(x));
+        let square = builder.mul(x, x).unwrap();
+        let five = builder.constant(Fr::from(5u64));
+        let sum = builder.add(square, five).unwrap();
+        let y = builder.public_input(Fr::from(y));
+        builder.assert_equal(sum, y).unwrap();
+        assert_eq!(builder.number_of_gates(), 3);
+
+        builder.build().unwrap()
+    }
+
+    #[test]
+    fn built_circuit_satisfies_the_relation() {
+        type Scheme = PLONKFoldingScheme<Fr, InnerProductConfig, PoseidonSponge<Fr>>;
+        let rng = &mut test_rng();
+        let info = SetupInfo {
+            number_of_public_inputs: 2,
+            number_of_gates: 3,
+            config: SangriaConfig::default(),
+            poseidon_constants: poseidon_parameters_for_test(),
+        };
+        let pp = Scheme::setup(&info, rng).unwrap();
+
+        let (circuit, trace) = square_plus_five(3, 14);
+        assert_eq!(trace.public_inputs(), [Fr::from(3u64), Fr::from(14u64)]);
+        // the circuit does not depend on the values
+        assert_eq!(square_plus_five(0, 0).0, circuit);
+
+        let instance_columns = trace.instance_columns();
+        let (_, witness) = trace.into_circuit_and_witness(Vec::new(), rng).unwrap();
+        let instance = Scheme::commit_witness(&pp, instance_columns, &witness).unwrap();
+        assert_eq!(
+            Scheme::check_relation(&pp, &circuit, &instance, &witness),
+            Ok(())
+        );
+
+        let (_, trace) = square_plus_five(3, 15);
+        assert!(matches!(
+            trace.into_circuit_and_witness(Vec::new(), rng),
+            Err(SangriaError::InvalidTrace(_))
+        ));
+    }
+
+    #[test]
+    fn fixed_table_entries_constrain_the_circuit() {
+        type Scheme = PLONKFoldingScheme<Fr, InnerProductConfig, PoseidonSponge<Fr>>;
+        let rng = &mut test_rng();
+        let info = SetupInfo {
+            number_of_public_inputs: 2,
+            number_of_gates: 2,
+            config: SangriaConfig::default(),
+            poseidon_constants: poseidon_parameters_for_test(),
+        };
+        let pp = Scheme::setup(&info, rng).unwrap();
+
+        // x * table[1] = y
+        let build = |table: [u64; 2], x: u64, y: u64| {
+            let mut builder = CircuitBuilder::with_fixed_tables(vec![table.map(Fr::from).to_vec()]);
+            let (x, y) = (
+                builder.public_input(Fr::from(x)),
+                builder.public_input(Fr::from(y)),
+            );
+            let entry = builder.fixed(0, 1).unwrap();
+            let product = builder.mul(x, entry).unwrap();
+            builder.assert_equal(product, y).unwrap();
+            assert_eq!(builder.fixed(0, 2), Err(SangriaError::IndexOutOfBounds));
+            builder.build().unwrap()
+        };
+
+        let (circuit, trace) = build([3, 7], 2, 14);
+        assert_eq!(circuit.fixed(0, 1), Ok(Fr::from(7u64)));
+        let instance_columns = trace.instance_columns();
+        let (_, witness) = trace.into_circuit_and_witness(Vec::new(), rng).unwrap();
+        let instance = Scheme::commit_witness(&pp, instance_columns, &witness).unwrap();
+        assert_eq!(
+            Scheme::check_relation(&pp, &circuit, &instance, &witness),
+            Ok(())
+        );
+
+        // the same witness does not satisfy the circuit of another table
+        let (other_circuit, _) = build([3, 8], 2, 16);
+        assert_eq!(
+            Scheme::check_relation(&pp, &other_circuit, &instance, &witness),
+            Err(SangriaError::UnsatisfiedGate { row: 2 })
+        );
+    }
+
+    #[test]
+    fn mock_prover_reports_unsatisfied_gates() {
+        let (circuit, trace) = square_plus_five(3, 14);
+        assert_eq!(
+            MockProver::new(&circuit, &trace, 2).unwrap().verify(),
+            Ok(())
+        );
+
+        // the output of the multiplication gate is off by one
+        let mut tampered = TraceTable::new();
+        for (row, &tag) in trace.tags().iter().enumerate() {
+            let mut wires = [0, 1, 2].map(|column| trace.wire(column, row).unwrap());
+            if row == 2 {
+                wires[2] += Fr::one();
+            }
+            tampered.push_row(tag, wires);
+        }
+
+        let failures = MockProver::new(&circuit, &tampered, 2)
+            .unwrap()
+            .verify()
+            .unwrap_err();
+        let mut selectors = [Fr::zero(); NUMBER_OF_SELECTORS];
+        selectors[MULTIPLICATION_SELECTOR_INDEX] = Fr::one();
+        selectors[OUTPUT_SELECTOR_INDEX] = -Fr::one();
+        assert_eq!(
+            failures,
+            [GateFailure {
+                row: 2,
+                selectors,
+                wires: [3u64, 3, 10].map(Fr::from),
+                residual: -Fr::one(),
+            }]
+        );
+        assert_eq!(
+            failures[0].to_string(),
+            "row 2: q_L = 0, q_R = 0, q_O = -1, q_M = 1, q_C = 0, q_P = 0, a = 3, b = 3, c = 10, \
+             residual = -1"
+        );
+
+        // the trace of another circuit does not span its rows
+        let mut shorter = TraceTable::new();
+        shorter.push_row(RowTag::PublicInput, [Fr::one(); NUMBER_OF_WIRES]);
+        assert!(matches!(
+            MockProver::new(&circuit, &shorter, 2),
+            Err(SangriaError::InvalidTrace(_))
+        ));
+    }
+
+    #[test]
+    fn unknown_variables_are_rejected() {
+        let mut other = CircuitBuilder::<Fr>::new();
+        let x = other.public_input(Fr::from(1u64));
+        let y = other.public_input(Fr::from(2u64));
+
+        let mut builder = CircuitBuilder::<Fr>::new();
+        let z = builder.public_input(Fr::from(3u64));
+        assert_eq!(builder.add(z, y), Err(SangriaError::IndexOutOfBounds));
+        assert_eq!(builder.mul(y, x), Err(SangriaError::IndexOutOfBounds));
+        assert_eq!(
+            builder.assert_equal(z, y),
+            Err(SangriaError::IndexOutOfBounds)
+        );
     }
 }
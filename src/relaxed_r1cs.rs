@@ -0,0 +1,1638 @@
+use ark_crypto_primitives::sponge::{poseidon::PoseidonConfig, Absorb};
+use ark_ff::{PrimeField, Zero};
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, SerializationError, Validate,
+};
+use ark_std::{
+    collections::BTreeMap,
+    io::{Read, Write},
+    marker::PhantomData,
+    rand::Rng,
+};
+use std::ops::{Add, Mul};
+
+use crate::{
+    folding_scheme::{permutation_index, trace_columns, FoldingCommitmentConfig},
+    relaxed_plonk::{fold_many_coefficients, lagrange_basis, NUMBER_OF_WIRES},
+    serialization::deserialize_bounded_vec,
+    transcript::Transcript,
+    vector_commitment::HomomorphicCommitmentScheme,
+    FoldChallenge, NonInteractiveFoldingScheme, PLONKCircuit, RelaxedPLONKInstance,
+    RelaxedPLONKWitness, SangriaConfig, SangriaError, SetupInfo, SizeLimits, TraceTable,
+    CONSTANT_SELECTOR_INDEX, LEFT_SELECTOR_INDEX, MULTIPLICATION_SELECTOR_INDEX,
+    OUTPUT_SELECTOR_INDEX, POWER_SELECTOR_INDEX, RIGHT_SELECTOR_INDEX,
+};
+
+/// A sparse matrix, row by row, every row listing its non-zero entries as (column, value).
+pub(crate) type SparseMatrix<F> = Vec<Vec<(usize, F)>>;
+
+type WitnessCommitment<F, Comm> =
+    <<Comm as FoldingCommitmentConfig<F>>::CommitmentWitness as HomomorphicCommitmentScheme<F>>::Commitment;
+
+pub(crate) type SlackCommitment<F, Comm> =
+    <<Comm as FoldingCommitmentConfig<F>>::CommitmentSlack as HomomorphicCommitmentScheme<F>>::Commitment;
+
+/// The structure of an R1CS: matrices `A`, `B` and `C` over the vector `z = (W, u, x)` of the
+/// witness variables, the scaling factor and the public inputs, as in Nova. A relaxed instance is
+/// satisfied if `Az ∘ Bz = u Cz + E`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct R1CSShape<F: PrimeField> {
+    number_of_public_inputs: usize,
+    number_of_variables: usize,
+    a: SparseMatrix<F>,
+    b: SparseMatrix<F>,
+    c: SparseMatrix<F>,
+    wires: WireVariables,
+}
+
+impl<F: PrimeField> R1CSShape<F> {
+    /// Creates the structure of an R1CS with `number_of_variables` witness variables and
+    /// `number_of_public_inputs` public inputs from its sparse matrices, given row by row as lists
+    /// of (column, value) over `z = (W, u, x)`. The rows are sorted by column, with the values of
+    /// repeated columns summed, so that equal matrices are absorbed into transcripts alike. Returns
+    /// an error if the matrices do not have as many rows or if an entry points outside `z`.
+    pub fn new(
+        number_of_public_inputs: usize,
+        number_of_variables: usize,
+        a: Vec<Vec<(usize, F)>>,
+        b: Vec<Vec<(usize, F)>>,
+        c: Vec<Vec<(usize, F)>>,
+    ) -> Result<Self, SangriaError> {
+        let z_len = number_of_variables + 1 + number_of_public_inputs;
+        if a.len() != b.len() || a.len() != c.len() {
+            return Err(SangriaError::InvalidConfig(
+                "the R1CS matrices must have as many rows",
+            ));
+        }
+        if [&a, &b, &c]
+            .into_iter()
+            .flatten()
+            .flatten()
+            .any(|&(column, _)| column >= z_len)
+        {
+            return Err(SangriaError::IndexOutOfBounds);
+        }
+
+        let [a, b, c] = [a, b, c].map(|matrix| matrix.into_iter().map(sparse_row).collect());
+        Ok(Self {
+            number_of_public_inputs,
+            number_of_variables,
+            a,
+            b,
+            c,
+            wires: WireVariables::default(),
+        })
+    }
+
+    /// Converts a PLONK circuit of gate degree 2 with `number_of_public_inputs` public input rows
+    /// into an R1CS. Every cycle of copied wires becomes one variable, which is the public input of
+    /// the cycle if it holds the left wire of a public input row, and every gate row enabling a
+    /// selector becomes the constraint `a (q_M b + q_P a) = -(q_L a + q_R b + q_O c + q_C u)`. The
+    /// other wires of the public input rows and the output row become witness variables, and
+    /// public inputs copied into one another are constrained to be equal. Returns an error if the
+    /// circuit has fewer rows than public inputs or if its copy constraint is not a permutation of
+    /// its wires.
+    pub fn from_plonk(
+        circuit: &PLONKCircuit<F>,
+        number_of_public_inputs: usize,
+    ) -> Result<Self, SangriaError> {
+        let wires = WireVariables::new(circuit, number_of_public_inputs)?;
+        let u = wires.scaling_factor();
+        let (mut a, mut b, mut c) = (Vec::new(), Vec::new(), Vec::new());
+        for &(first, other) in wires.public_equalities() {
+            a.push(sparse_row([(first, F::one()), (other, -F::one())]));
+            b.push(vec![(u, F::one())]);
+            c.push(Vec::new());
+        }
+
+        let selectors = circuit.selectors();
+        for row in constraint_rows(circuit, number_of_public_inputs) {
+            let selector = |index: usize| selectors[index][row];
+            let (left, right, output) = (
+                wires.variable(0, row),
+                wires.variable(1, row),
+                wires.variable(2, row),
+            );
+            a.push(sparse_row([(left, F::one())]));
+            b.push(sparse_row([
+                (right, selector(MULTIPLICATION_SELECTOR_INDEX)),
+                (left, selector(POWER_SELECTOR_INDEX)),
+            ]));
+            c.push(sparse_row([
+                (left, -selector(LEFT_SELECTOR_INDEX)),
+                (right, -selector(RIGHT_SELECTOR_INDEX)),
+                (output, -selector(OUTPUT_SELECTOR_INDEX)),
+                (u, -selector(CONSTANT_SELECTOR_INDEX)),
+            ]));
+        }
+
+        let shape = Self::new(number_of_public_inputs, wires.number_of_variables, a, b, c)?;
+
+        Ok(Self { wires, ..shape })
+    }
+
+    /// Returns the number of public inputs.
+    pub fn number_of_public_inputs(&self) -> usize {
+        self.number_of_public_inputs
+    }
+
+    /// Returns the number of witness variables.
+    pub fn number_of_variables(&self) -> usize {
+        self.number_of_variables
+    }
+
+    /// Returns the number of constraints, i.e. of rows of the matrices.
+    pub fn number_of_constraints(&self) -> usize {
+        self.a.len()
+    }
+
+    /// Returns the variables of the PLONK circuit the structure was converted from.
+    pub(crate) fn wires(&self) -> &WireVariables {
+        &self.wires
+    }
+
+    /// Returns the matrices `A`, `B` and `C`.
+    pub(crate) fn matrices(&self) -> [&SparseMatrix<F>; 3] {
+        [&self.a, &self.b, &self.c]
+    }
+
+    /// Reads the witness variables and the public inputs of a trace of the PLONK circuit the
+    /// structure was converted from, every variable being read from a wire of its cycle. Returns an
+    /// error if the structure was not converted from a circuit with as many rows as the trace.
+    pub(crate) fn assignment(
+        &self,
+        trace: &TraceTable<F>,
+    ) -> Result<(Vec<F>, Vec<F>), SangriaError> {
+        self.wires.assignment(trace)
+    }
+
+    /// Returns `(Az, Bz, Cz)` for `z = (witness, u, public_inputs)`.
+    fn evaluate(&self, witness: &[F], u: F, public_inputs: &[F]) -> (Vec<F>, Vec<F>, Vec<F>) {
+        let z: Vec<F> = witness
+            .iter()
+            .copied()
+            .chain([u])
+            .chain(public_inputs.iter().copied())
+            .collect();
+        let multiply = |matrix: &SparseMatrix<F>| -> Vec<F> {
+            matrix
+                .iter()
+                .map(|row| row.iter().map(|&(column, value)| value * z[column]).sum())
+                .collect()
+        };
+
+        (multiply(&self.a), multiply(&self.b), multiply(&self.c))
+    }
+
+    /// Lists the sizes and the entries of the matrices as field elements, for transcripts.
+    fn transcript_elements(&self) -> Vec<F> {
+        let mut elements = vec![
+            F::from(self.number_of_public_inputs as u64),
+            F::from(self.number_of_variables as u64),
+        ];
+        for matrix in [&self.a, &self.b, &self.c] {
+            elements.push(F::from(matrix.len() as u64));
+            for row in matrix {
+                elements.push(F::from(row.len() as u64));
+                for &(column, value) in row {
+                    elements.extend([F::from(column as u64), value]);
+                }
+            }
+        }
+
+        elements
+    }
+}
+
+impl<F: PrimeField + Absorb> Absorb for R1CSShape<F> {
+    fn to_sponge_bytes(&self, dest: &mut Vec<u8>) {
+        self.transcript_elements().to_sponge_bytes(dest);
+    }
+
+    fn to_sponge_field_elements<SpongeF: PrimeField>(&self, dest: &mut Vec<SpongeF>) {
+        self.transcript_elements().to_sponge_field_elements(dest);
+    }
+}
+
+/// The variables of a PLONK circuit converted into a constraint system over `z = (W, u, x)`: every
+/// cycle of copied wires is one entry of `z`, which is the public input of the cycle if it holds the
+/// left wire of a public input row and a witness variable otherwise.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct WireVariables {
+    number_of_rows: usize,
+    number_of_variables: usize,
+    /// The entry of `z` of every wire position `column * rows + row`.
+    wire_variables: Vec<usize>,
+    /// A wire position of every witness variable, to read its value from a trace.
+    variable_positions: Vec<usize>,
+    /// The pairs of public inputs, as entries of `z`, that are copied into one another.
+    public_equalities: Vec<(usize, usize)>,
+}
+
+impl WireVariables {
+    /// Assigns the variables of a circuit with `number_of_public_inputs` public input rows. Returns
+    /// an error if the circuit has fewer rows than public inputs or if its copy constraint is not a
+    /// permutation of its wires.
+    pub(crate) fn new<F: PrimeField>(
+        circuit: &PLONKCircuit<F>,
+        number_of_public_inputs: usize,
+    ) -> Result<Self, SangriaError> {
+        let number_of_rows = circuit.number_of_rows();
+        let size = NUMBER_OF_WIRES * number_of_rows;
+        if number_of_public_inputs > number_of_rows {
+            return Err(SangriaError::PublicInputMismatch);
+        }
+        let permutation = circuit
+            .copy_constraint()
+            .into_iter()
+            .map(|entry| permutation_index(entry, size))
+            .collect::<Result<Vec<_>, _>>()?;
+        if permutation.len() != size {
+            return Err(SangriaError::InvalidWitness);
+        }
+
+        // every cycle starts at its smallest position, and the left wires of the public input rows
+        // are the first positions, so a cycle holding a public input starts with one
+        let mut cycle_of: Vec<Option<usize>> = vec![None; size];
+        let mut cycles: Vec<Vec<usize>> = Vec::new();
+        for start in 0..size {
+            if cycle_of[start].is_some() {
+                continue;
+            }
+            let mut cycle = Vec::new();
+            let mut position = start;
+            while cycle_of[position].is_none() {
+                cycle_of[position] = Some(cycles.len());
+                cycle.push(position);
+                position = permutation[position];
+            }
+            if position != start {
+                return Err(SangriaError::InvalidWitness);
+            }
+            cycles.push(cycle);
+        }
+
+        let number_of_variables = cycles
+            .iter()
+            .filter(|cycle| cycle[0] >= number_of_public_inputs)
+            .count();
+        let public_input = |position: usize| number_of_variables + 1 + position;
+        let mut variable_positions = Vec::with_capacity(number_of_variables);
+        let mut public_equalities = Vec::new();
+        let cycle_variables: Vec<usize> = cycles
+            .iter()
+            .map(|cycle| {
+                let mut public_inputs = cycle
+                    .iter()
+                    .filter(|&&position| position < number_of_public_inputs);
+                match public_inputs.next() {
+                    Some(&first) => {
+                        public_equalities.extend(
+                            public_inputs.map(|&other| (public_input(first), public_input(other))),
+                        );
+                        public_input(first)
+                    }
+                    None => {
+                        variable_positions.push(cycle[0]);
+                        variable_positions.len() - 1
+                    }
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            number_of_rows,
+            number_of_variables,
+            wire_variables: cycle_of
+                .into_iter()
+                .map(|cycle| cycle_variables[cycle.expect("every position belongs to a cycle")])
+                .collect(),
+            variable_positions,
+            public_equalities,
+        })
+    }
+
+    /// Returns the number of witness variables.
+    pub(crate) fn number_of_variables(&self) -> usize {
+        self.number_of_variables
+    }
+
+    /// Returns the entry of `z` holding the scaling factor.
+    pub(crate) fn scaling_factor(&self) -> usize {
+        self.number_of_variables
+    }
+
+    /// Returns the entry of `z` of the wire at (`column`, `row`).
+    pub(crate) fn variable(&self, column: usize, row: usize) -> usize {
+        self.wire_variables[column * self.number_of_rows + row]
+    }
+
+    /// Returns the pairs of public inputs, as entries of `z`, that are copied into one another.
+    pub(crate) fn public_equalities(&self) -> &[(usize, usize)] {
+        &self.public_equalities
+    }
+
+    /// Reads the witness variables and the public inputs of a trace of the converted circuit, every
+    /// variable being read from a wire of its cycle. Returns an error if the circuit does not have as
+    /// many rows as the trace.
+    pub(crate) fn assignment<F: PrimeField>(
+        &self,
+        trace: &TraceTable<F>,
+    ) -> Result<(Vec<F>, Vec<F>), SangriaError> {
+        if self.number_of_rows == 0 || trace.number_of_rows() != self.number_of_rows {
+            return Err(SangriaError::InvalidTrace(
+                "the trace does not have the rows of the converted circuit",
+            ));
+        }
+
+        let witness = self.read_variables(|column, row| trace.wire(column, row))?;
+
+        Ok((witness, trace.public_inputs()))
+    }
+
+    /// Reads the witness variables from the full trace columns of a relaxed PLONK pair of the
+    /// converted circuit, and the public inputs from the left wires of its public input rows, then
+    /// checks that every wire holds the value of its variable, i.e. that the copy constraint holds.
+    /// Returns an error otherwise or if the columns do not span the rows of the circuit.
+    pub(crate) fn relaxed_assignment<F: PrimeField>(
+        &self,
+        columns: &[Vec<F>],
+        number_of_public_inputs: usize,
+        scaling_factor: F,
+    ) -> Result<(Vec<F>, Vec<F>), SangriaError> {
+        if self.number_of_rows == 0
+            || columns
+                .iter()
+                .any(|column| column.len() != self.number_of_rows)
+        {
+            return Err(SangriaError::InvalidWitness);
+        }
+
+        let witness = self.read_variables(|column, row| Ok(columns[column][row]))?;
+        let public_inputs = columns[0][..number_of_public_inputs].to_vec();
+        let z: Vec<F> = witness
+            .iter()
+            .copied()
+            .chain([scaling_factor])
+            .chain(public_inputs.iter().copied())
+            .collect();
+        let copied = (0..NUMBER_OF_WIRES).all(|column| {
+            (0..self.number_of_rows)
+                .all(|row| columns[column][row] == z[self.variable(column, row)])
+        });
+        if !copied {
+            return Err(SangriaError::InvalidWitness);
+        }
+
+        Ok((witness, public_inputs))
+    }
+
+    /// Reads every witness variable from a wire of its cycle with `wire`.
+    fn read_variables<F: PrimeField>(
+        &self,
+        wire: impl Fn(usize, usize) -> Result<F, SangriaError>,
+    ) -> Result<Vec<F>, SangriaError> {
+        self.variable_positions
+            .iter()
+            .map(|&position| {
+                wire(
+                    position / self.number_of_rows,
+                    position % self.number_of_rows,
+                )
+            })
+            .collect()
+    }
+}
+
+/// Returns the gate rows of a circuit with `number_of_public_inputs` public input rows that enable a
+/// selector, which become the constraints of the converted R1CS after the public input equalities.
+fn constraint_rows<F: PrimeField>(
+    circuit: &PLONKCircuit<F>,
+    number_of_public_inputs: usize,
+) -> impl Iterator<Item = usize> {
+    let selectors = circuit.selectors();
+    (number_of_public_inputs..circuit.number_of_rows())
+        .filter(move |&row| selectors.iter().any(|selector| !selector[row].is_zero()))
+}
+
+/// Returns the sparse row with the given entries, summing the values of repeated columns and
+/// dropping zero values.
+pub(crate) fn sparse_row<F: PrimeField>(
+    entries: impl IntoIterator<Item = (usize, F)>,
+) -> Vec<(usize, F)> {
+    let mut row = BTreeMap::new();
+    for (column, value) in entries {
+        *row.entry(column).or_insert_with(F::zero) += value;
+    }
+
+    row.into_iter()
+        .filter(|(_, value)| !value.is_zero())
+        .collect()
+}
+
+/// A committed relaxed R1CS instance: commitments to the witness variables and to the slack vector,
+/// the scaling factor and the public inputs.
+pub struct RelaxedR1CSInstance<F: PrimeField, Comm: FoldingCommitmentConfig<F>> {
+    witness_commitment: WitnessCommitment<F, Comm>,
+    slack_commitment: SlackCommitment<F, Comm>,
+    scaling_factor: F,
+    public_inputs: Vec<F>,
+}
+
+impl<F: PrimeField, Comm: FoldingCommitmentConfig<F>> RelaxedR1CSInstance<F, Comm> {
+    /// Returns the commitment to the witness variables.
+    pub fn witness_commitment(&self) -> WitnessCommitment<F, Comm> {
+        self.witness_commitment
+    }
+
+    /// Returns the commitment to the slack vector.
+    pub fn slack_commitment(&self) -> SlackCommitment<F, Comm> {
+        self.slack_commitment
+    }
+
+    /// Returns the scaling factor.
+    pub fn scaling_factor(&self) -> F {
+        self.scaling_factor
+    }
+
+    /// Returns the public inputs. Those of a folded instance are the linear combination of the
+    /// folded public inputs.
+    pub fn public_inputs(&self) -> &[F] {
+        &self.public_inputs
+    }
+
+    /// Folds `other` into this instance with the commitment to the cross term `T` and the folding
+    /// challenge `r`: every component is folded linearly as `self + r * other`, except the slack
+    /// commitment which becomes `E_self + r T + r^2 E_other`.
+    pub fn fold(
+        &self,
+        other: &Self,
+        cross_term_commitment: SlackCommitment<F, Comm>,
+        challenge: FoldChallenge<F>,
+    ) -> Self {
+        let r = challenge.value();
+        let mut folded = other.clone() * r + self;
+        folded.slack_commitment =
+            self.slack_commitment + (cross_term_commitment + other.slack_commitment * r) * r;
+
+        folded
+    }
+
+    /// Folds several instances at once with the commitments to the quotient terms `K_t` and the
+    /// folding challenge `r`, like [`crate::RelaxedPLONKInstance`]s: the instances are combined as
+    /// `sum_j L_j(r) instance_j` and `Z(r) sum_t L'_t(r) K_t` is added to the slack commitment.
+    /// Returns an error if there is no instance.
+    fn fold_many(
+        instances: &[Self],
+        quotient_commitments: &[SlackCommitment<F, Comm>],
+        challenge: FoldChallenge<F>,
+    ) -> Result<Self, SangriaError> {
+        let (coefficients, quotient_coefficients) = fold_many_coefficients(
+            instances.len(),
+            quotient_commitments.len(),
+            challenge.value(),
+        );
+        let (first, others) = instances.split_first().ok_or(SangriaError::InvalidProof)?;
+        let mut folded = others.iter().zip(&coefficients[1..]).fold(
+            first.clone() * coefficients[0],
+            |folded, (instance, &coefficient)| folded + &(instance.clone() * coefficient),
+        );
+        folded.slack_commitment = quotient_commitments.iter().zip(quotient_coefficients).fold(
+            folded.slack_commitment,
+            |folded, (&quotient, coefficient)| folded + quotient * coefficient,
+        );
+
+        Ok(folded)
+    }
+}
+
+impl<F, Comm> Absorb for RelaxedR1CSInstance<F, Comm>
+where
+    F: PrimeField + Absorb,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    fn to_sponge_bytes(&self, dest: &mut Vec<u8>) {
+        self.witness_commitment.to_sponge_bytes(dest);
+        self.slack_commitment.to_sponge_bytes(dest);
+        self.scaling_factor.to_sponge_bytes(dest);
+        self.public_inputs.to_sponge_bytes(dest);
+    }
+
+    fn to_sponge_field_elements<SpongeF: PrimeField>(&self, dest: &mut Vec<SpongeF>) {
+        self.witness_commitment.to_sponge_field_elements(dest);
+        self.slack_commitment.to_sponge_field_elements(dest);
+        self.scaling_factor.to_sponge_field_elements(dest);
+        self.public_inputs.to_sponge_field_elements(dest);
+    }
+}
+
+impl<F, Comm> Add<&Self> for RelaxedR1CSInstance<F, Comm>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    type Output = Self;
+
+    fn add(self, rhs: &Self) -> Self::Output {
+        Self {
+            witness_commitment: self.witness_commitment + rhs.witness_commitment,
+            slack_commitment: self.slack_commitment + rhs.slack_commitment,
+            scaling_factor: self.scaling_factor + rhs.scaling_factor,
+            public_inputs: self
+                .public_inputs
+                .iter()
+                .zip(&rhs.public_inputs)
+                .map(|(&left, &right)| left + right)
+                .collect(),
+        }
+    }
+}
+
+impl<F, Comm> Mul<F> for RelaxedR1CSInstance<F, Comm>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    type Output = Self;
+
+    fn mul(self, rhs: F) -> Self::Output {
+        Self {
+            witness_commitment: self.witness_commitment * rhs,
+            slack_commitment: self.slack_commitment * rhs,
+            scaling_factor: self.scaling_factor * rhs,
+            public_inputs: self
+                .public_inputs
+                .into_iter()
+                .map(|input| input * rhs)
+                .collect(),
+        }
+    }
+}
+
+impl<F, Comm> Clone for RelaxedR1CSInstance<F, Comm>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    fn clone(&self) -> Self {
+        Self {
+            witness_commitment: self.witness_commitment,
+            slack_commitment: self.slack_commitment,
+            scaling_factor: self.scaling_factor,
+            public_inputs: self.public_inputs.clone(),
+        }
+    }
+}
+
+impl<F, Comm> PartialEq for RelaxedR1CSInstance<F, Comm>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.witness_commitment == other.witness_commitment
+            && self.slack_commitment == other.slack_commitment
+            && self.scaling_factor == other.scaling_factor
+            && self.public_inputs == other.public_inputs
+    }
+}
+
+/// Instances are serialized in the order of the fields of the relaxed R1CS instances of Nova: the
+/// witness commitment, the slack commitment, the public inputs behind a `u64` length prefix and the
+/// scaling factor. Field elements are written in their canonical little-endian encoding (see
+/// [`crate::fe_to_bytes_canonical`]), as Nova writes its scalars, while commitments are written with
+/// the encoding of their scheme, which pipelines exchanging instances with Nova must translate to
+/// the point encoding of its curves.
+impl<F, Comm> CanonicalSerialize for RelaxedR1CSInstance<F, Comm>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        self.witness_commitment
+            .serialize_with_mode(&mut writer, compress)?;
+        self.slack_commitment
+            .serialize_with_mode(&mut writer, compress)?;
+        self.public_inputs
+            .serialize_with_mode(&mut writer, compress)?;
+        self.scaling_factor
+            .serialize_with_mode(&mut writer, compress)
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        self.witness_commitment.serialized_size(compress)
+            + self.slack_commitment.serialized_size(compress)
+            + self.public_inputs.serialized_size(compress)
+            + self.scaling_factor.serialized_size(compress)
+    }
+}
+
+impl<F, Comm> RelaxedR1CSInstance<F, Comm>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    /// Deserializes an instance from untrusted input, rejecting more than `limits.max_rows` public
+    /// inputs before reading them.
+    pub fn deserialize_bounded<R: Read>(
+        mut reader: R,
+        limits: &SizeLimits,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let witness_commitment =
+            CanonicalDeserialize::deserialize_with_mode(&mut reader, compress, validate)?;
+        let slack_commitment =
+            CanonicalDeserialize::deserialize_with_mode(&mut reader, compress, validate)?;
+        let public_inputs =
+            deserialize_bounded_vec(&mut reader, limits.max_rows, compress, validate)?;
+        let scaling_factor = F::deserialize_with_mode(&mut reader, compress, validate)?;
+
+        Ok(Self {
+            witness_commitment,
+            slack_commitment,
+            scaling_factor,
+            public_inputs,
+        })
+    }
+}
+
+/// A committed relaxed R1CS witness: the witness variables, the slack vector and the randomness
+/// used to commit to them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RelaxedR1CSWitness<F: PrimeField> {
+    witness: Vec<F>,
+    slack_vector: Vec<F>,
+    witness_hiding: F,
+    slack_hiding: F,
+}
+
+impl<F: PrimeField> RelaxedR1CSWitness<F> {
+    /// Creates a fresh witness of an R1CS with `number_of_constraints` constraints from its witness
+    /// variables and the randomness to commit to them. The slack vector of a fresh witness is zero,
+    /// so it is committed to without randomness.
+    pub fn new(witness: Vec<F>, number_of_constraints: usize, witness_hiding: F) -> Self {
+        Self {
+            witness,
+            slack_vector: vec![F::zero(); number_of_constraints],
+            witness_hiding,
+            slack_hiding: F::zero(),
+        }
+    }
+
+    /// Returns the witness variables.
+    pub fn witness(&self) -> &[F] {
+        &self.witness
+    }
+
+    /// Returns the slack vector.
+    pub fn slack_vector(&self) -> &[F] {
+        &self.slack_vector
+    }
+
+    /// Folds `other` into this witness with the cross term `T`, the randomness used to commit to it
+    /// and the folding challenge `r`, mirroring [`RelaxedR1CSInstance::fold`].
+    fn fold(&self, other: &Self, cross_term: &[F], cross_term_hiding: F, r: F) -> Self {
+        let fold_linearly = |left: &[F], right: &[F]| -> Vec<F> {
+            left.iter()
+                .zip(right)
+                .map(|(&left, &right)| left + r * right)
+                .collect()
+        };
+
+        Self {
+            witness: fold_linearly(&self.witness, &other.witness),
+            slack_vector: self
+                .slack_vector
+                .iter()
+                .zip(cross_term)
+                .zip(&other.slack_vector)
+                .map(|((&left, &cross_term), &right)| left + r * (cross_term + r * right))
+                .collect(),
+            witness_hiding: self.witness_hiding + r * other.witness_hiding,
+            slack_hiding: self.slack_hiding + r * (cross_term_hiding + r * other.slack_hiding),
+        }
+    }
+
+    /// Folds several witnesses at once with the quotient terms `K_t`, the randomness used to commit
+    /// to them and the folding challenge `r`, mirroring [`RelaxedR1CSInstance::fold_many`].
+    fn fold_many(witnesses: &[Self], quotients: &[Vec<F>], quotient_hidings: &[F], r: F) -> Self {
+        let (coefficients, quotient_coefficients) =
+            fold_many_coefficients(witnesses.len(), quotients.len(), r);
+        let hidings = |hiding: fn(&Self) -> F| -> F {
+            witnesses
+                .iter()
+                .zip(&coefficients)
+                .map(|(witness, &coefficient)| coefficient * hiding(witness))
+                .sum()
+        };
+
+        Self {
+            witness: linear_combination(
+                &coefficients,
+                witnesses.iter().map(|witness| &witness.witness[..]),
+            ),
+            slack_vector: linear_combination(
+                &[&coefficients[..], &quotient_coefficients[..]].concat(),
+                witnesses
+                    .iter()
+                    .map(|witness| &witness.slack_vector[..])
+                    .chain(quotients.iter().map(|quotient| &quotient[..])),
+            ),
+            witness_hiding: hidings(|witness| witness.witness_hiding),
+            slack_hiding: hidings(|witness| witness.slack_hiding)
+                + quotient_hidings
+                    .iter()
+                    .zip(&quotient_coefficients)
+                    .map(|(&hiding, &coefficient)| coefficient * hiding)
+                    .sum::<F>(),
+        }
+    }
+}
+
+/// A folding scheme for relaxed R1CS, in the manner of Nova, deriving its challenges from
+/// transcripts of type `RO`. It folds the R1CS flavour of the relation folded by
+/// [`crate::PLONKFoldingScheme`], for interoperability with Nova circuits: the witness variables
+/// are committed to with the witness scheme of `Comm` and the slack vector and cross terms with its
+/// slack scheme.
+pub struct R1CSFoldingScheme<F: PrimeField, Comm: FoldingCommitmentConfig<F>, RO: Transcript<F>>(
+    PhantomData<(F, Comm, RO)>,
+);
+
+/// Public parameters for the relaxed R1CS folding scheme: the sizes of the R1CS and commitment
+/// parameters for the witness variables and the slack vector.
+pub struct PublicParameters<F: PrimeField, Comm: FoldingCommitmentConfig<F>> {
+    /// The number of public inputs of the R1CS.
+    pub number_of_public_inputs: usize,
+    /// The commit key for the witness variables.
+    pub commit_key_witness: <Comm::CommitmentWitness as HomomorphicCommitmentScheme<F>>::CommitKey,
+    /// The commit key for the slack vector and the cross terms.
+    pub commit_key_slack: <Comm::CommitmentSlack as HomomorphicCommitmentScheme<F>>::CommitKey,
+    /// The constants of the Poseidon sponge, used by Poseidon transcripts.
+    pub poseidon_constants: PoseidonConfig<F>,
+    /// The deployment configuration.
+    pub config: SangriaConfig,
+}
+
+impl<F, Comm> Clone for PublicParameters<F, Comm>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    fn clone(&self) -> Self {
+        Self {
+            number_of_public_inputs: self.number_of_public_inputs,
+            commit_key_witness: self.commit_key_witness.clone(),
+            commit_key_slack: self.commit_key_slack.clone(),
+            poseidon_constants: self.poseidon_constants.clone(),
+            config: self.config.clone(),
+        }
+    }
+}
+
+impl<F, Comm> PublicParameters<F, Comm>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    /// Sets up commit keys for the constraint systems converted from PLONK circuits with the public
+    /// inputs and gates of `info`: one variable per wire and one constraint per row at most. Returns
+    /// an error if the configuration is invalid.
+    pub(crate) fn new<R: Rng>(info: &SetupInfo<F>, rng: &mut R) -> Result<Self, SangriaError> {
+        info.config.validate::<F>()?;
+
+        let number_of_rows = info.number_of_public_inputs + info.number_of_gates + 1;
+        Ok(Self {
+            number_of_public_inputs: info.number_of_public_inputs,
+            commit_key_witness: <Comm::CommitmentWitness as HomomorphicCommitmentScheme<F>>::setup(
+                rng,
+                NUMBER_OF_WIRES * number_of_rows,
+            ),
+            commit_key_slack: <Comm::CommitmentSlack as HomomorphicCommitmentScheme<F>>::setup(
+                rng,
+                number_of_rows,
+            ),
+            poseidon_constants: info.poseidon_constants.clone(),
+            config: info.config.clone(),
+        })
+    }
+
+    /// Checks that the parameters fit a constraint system `structure` with the given numbers of
+    /// public inputs, witness variables and constraints, and returns the verifier key binding the
+    /// transcripts to the system and to the parameters. Returns an error if the system does not have
+    /// the public inputs of the parameters or if a commit key is too short.
+    pub(crate) fn verifier_key<RO: Transcript<F>>(
+        &self,
+        structure: &impl Absorb,
+        number_of_public_inputs: usize,
+        number_of_variables: usize,
+        number_of_constraints: usize,
+    ) -> Result<VerifierKey<F>, SangriaError> {
+        if number_of_public_inputs != self.number_of_public_inputs {
+            return Err(SangriaError::PublicInputMismatch);
+        }
+        for (needed, available) in [
+            (
+                number_of_variables,
+                <Comm::CommitmentWitness as HomomorphicCommitmentScheme<F>>::max_len(
+                    &self.commit_key_witness,
+                ),
+            ),
+            (
+                number_of_constraints,
+                <Comm::CommitmentSlack as HomomorphicCommitmentScheme<F>>::max_len(
+                    &self.commit_key_slack,
+                ),
+            ),
+        ] {
+            if needed > available {
+                return Err(SangriaError::ParametersTooSmall { needed, available });
+            }
+        }
+
+        let mut sponge = RO::start(&self.poseidon_constants);
+        sponge.absorb(structure);
+        sponge.absorb(&self.transcript_bytes());
+
+        Ok(VerifierKey {
+            transcript_seed: sponge.squeeze_field_elements(1)[0],
+        })
+    }
+
+    /// Checks that the instance has the public inputs of the parameters, that the witness has the
+    /// given numbers of variables and constraints, and that the commitments of the instance open to
+    /// the witness. Returns an error otherwise.
+    pub(crate) fn check_commitments(
+        &self,
+        instance: &RelaxedR1CSInstance<F, Comm>,
+        witness: &RelaxedR1CSWitness<F>,
+        number_of_variables: usize,
+        number_of_constraints: usize,
+    ) -> Result<(), SangriaError> {
+        check_public_inputs(self, [instance])?;
+        if witness.witness.len() != number_of_variables
+            || witness.slack_vector.len() != number_of_constraints
+        {
+            return Err(SangriaError::InvalidWitness);
+        }
+
+        let witness_commitment =
+            <Comm::CommitmentWitness as HomomorphicCommitmentScheme<F>>::commit(
+                &self.commit_key_witness,
+                &witness.witness,
+                witness.witness_hiding,
+            )?;
+        let slack_commitment = <Comm::CommitmentSlack as HomomorphicCommitmentScheme<F>>::commit(
+            &self.commit_key_slack,
+            &witness.slack_vector,
+            witness.slack_hiding,
+        )?;
+        if witness_commitment != instance.witness_commitment
+            || slack_commitment != instance.slack_commitment
+        {
+            return Err(SangriaError::InvalidWitness);
+        }
+
+        Ok(())
+    }
+
+    /// Serializes every component of the parameters into the bytes absorbed by transcripts.
+    fn transcript_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.serialize_for_transcript(&mut bytes)
+            .expect("serializing into a vector cannot fail");
+
+        bytes
+    }
+
+    fn serialize_for_transcript(&self, bytes: &mut Vec<u8>) -> Result<(), SerializationError> {
+        let poseidon = &self.poseidon_constants;
+
+        self.number_of_public_inputs
+            .serialize_compressed(&mut *bytes)?;
+        self.commit_key_witness.serialize_compressed(&mut *bytes)?;
+        self.commit_key_slack.serialize_compressed(&mut *bytes)?;
+        self.config.serialize_compressed(&mut *bytes)?;
+        poseidon.full_rounds.serialize_compressed(&mut *bytes)?;
+        poseidon.partial_rounds.serialize_compressed(&mut *bytes)?;
+        poseidon.alpha.serialize_compressed(&mut *bytes)?;
+        poseidon.ark.serialize_compressed(&mut *bytes)?;
+        poseidon.mds.serialize_compressed(&mut *bytes)?;
+        poseidon.rate.serialize_compressed(&mut *bytes)?;
+        poseidon.capacity.serialize_compressed(&mut *bytes)
+    }
+}
+
+/// The verifier key for the relaxed R1CS folding scheme: a seed binding the transcripts to the
+/// R1CS and the public parameters.
+#[derive(Clone)]
+pub struct VerifierKey<F: PrimeField> {
+    /// The digest of the R1CS and the public parameters.
+    pub transcript_seed: F,
+}
+
+impl<F: PrimeField + Absorb> Absorb for VerifierKey<F> {
+    fn to_sponge_bytes(&self, dest: &mut Vec<u8>) {
+        self.transcript_seed.to_sponge_bytes(dest);
+    }
+
+    fn to_sponge_field_elements<SpongeF: PrimeField>(&self, dest: &mut Vec<SpongeF>) {
+        self.transcript_seed.to_sponge_field_elements(dest);
+    }
+}
+
+/// The prover key for the relaxed R1CS folding scheme: the verifier key and the R1CS, needed to
+/// compute cross terms.
+pub struct ProverKey<F: PrimeField> {
+    /// The verifier key.
+    pub verifier_key: VerifierKey<F>,
+    /// The structure of the R1CS.
+    pub shape: R1CSShape<F>,
+}
+
+impl<F, Comm, RO> NonInteractiveFoldingScheme for R1CSFoldingScheme<F, Comm, RO>
+where
+    F: PrimeField + Absorb,
+    Comm: FoldingCommitmentConfig<F>,
+    RO: Transcript<F>,
+{
+    type SetupInfo = SetupInfo<F>;
+    type PublicParameters = PublicParameters<F, Comm>;
+    type Structure = R1CSShape<F>;
+    type Instance = RelaxedR1CSInstance<F, Comm>;
+    type Witness = RelaxedR1CSWitness<F>;
+    type ProverKey = ProverKey<F>;
+    type VerifierKey = VerifierKey<F>;
+    type ProverMessage = Vec<SlackCommitment<F, Comm>>;
+
+    /// Sizes the commit keys for the R1CS conversion (see [`R1CSShape::from_plonk`]) of PLONK
+    /// circuits with the public inputs and gates of `info`: one variable per wire and one
+    /// constraint per row at most. An R1CS not converted from PLONK fits if it has at most three
+    /// witness variables per row and one constraint per row. Returns an error if the configuration
+    /// is invalid or its gate degree is not 2, the degree of R1CS constraints.
+    fn setup<R: Rng>(
+        info: &SetupInfo<F>,
+        rng: &mut R,
+    ) -> Result<Self::PublicParameters, SangriaError> {
+        if info.config.gate_degree != 2 {
+            return Err(SangriaError::InvalidConfig(
+                "relaxed R1CS constraints have degree 2",
+            ));
+        }
+
+        PublicParameters::new(info, rng)
+    }
+
+    fn encode<R: Rng>(
+        pp: &Self::PublicParameters,
+        shape: &Self::Structure,
+        _rng: &mut R,
+    ) -> Result<(Self::ProverKey, Self::VerifierKey), SangriaError> {
+        let verifier_key = pp.verifier_key::<RO>(
+            shape,
+            shape.number_of_public_inputs,
+            shape.number_of_variables,
+            shape.number_of_constraints(),
+        )?;
+
+        Ok((
+            ProverKey {
+                verifier_key: verifier_key.clone(),
+                shape: shape.clone(),
+            },
+            verifier_key,
+        ))
+    }
+
+    /// Folds two instance-witness pairs with the cross term `T = Az_1 ∘ Bz_2 + Az_2 ∘ Bz_1 - u_1
+    /// Cz_2 - u_2 Cz_1`, the prover message being its commitment.
+    fn prover<R: Rng>(
+        public_parameters: &Self::PublicParameters,
+        prover_key: &Self::ProverKey,
+        left_instance: &Self::Instance,
+        left_witness: &Self::Witness,
+        right_instance: &Self::Instance,
+        right_witness: &Self::Witness,
+        rng: &mut R,
+    ) -> Result<(Self::Instance, Self::Witness, Self::ProverMessage), SangriaError> {
+        let shape = &prover_key.shape;
+        let (left_a, left_b, left_c) = shape.evaluate(
+            &left_witness.witness,
+            left_instance.scaling_factor,
+            &left_instance.public_inputs,
+        );
+        let (right_a, right_b, right_c) = shape.evaluate(
+            &right_witness.witness,
+            right_instance.scaling_factor,
+            &right_instance.public_inputs,
+        );
+        let cross_term: Vec<F> = (0..shape.number_of_constraints())
+            .map(|row| {
+                left_a[row] * right_b[row] + right_a[row] * left_b[row]
+                    - left_instance.scaling_factor * right_c[row]
+                    - right_instance.scaling_factor * left_c[row]
+            })
+            .collect();
+        let cross_term_hiding = hiding(&public_parameters.config, rng);
+        let cross_term_commitment =
+            <Comm::CommitmentSlack as HomomorphicCommitmentScheme<F>>::commit(
+                &public_parameters.commit_key_slack,
+                &cross_term,
+                cross_term_hiding,
+            )?;
+
+        // the challenge is squeezed from the same transcript as in the verifier
+        let mut sponge = RO::start(&public_parameters.poseidon_constants);
+        sponge.absorb(&prover_key.verifier_key);
+        sponge.absorb(&left_instance);
+        sponge.absorb(&right_instance);
+        sponge.absorb(&cross_term_commitment);
+        let challenge =
+            FoldChallenge::squeeze(&mut sponge, public_parameters.config.challenge_bits, 0);
+
+        let folded_witness = left_witness.fold(
+            right_witness,
+            &cross_term,
+            cross_term_hiding,
+            challenge.value(),
+        );
+        let folded_instance = left_instance.fold(right_instance, cross_term_commitment, challenge);
+
+        Ok((folded_instance, folded_witness, vec![cross_term_commitment]))
+    }
+
+    fn verifier(
+        public_parameters: &Self::PublicParameters,
+        verifier_key: &Self::VerifierKey,
+        left_instance: &Self::Instance,
+        right_instance: &Self::Instance,
+        prover_message: &Self::ProverMessage,
+    ) -> Result<Self::Instance, SangriaError> {
+        check_public_inputs(public_parameters, [left_instance, right_instance])?;
+        let &[cross_term_commitment] = &prover_message[..] else {
+            return Err(SangriaError::InvalidProof);
+        };
+
+        let mut sponge = RO::start(&public_parameters.poseidon_constants);
+        sponge.absorb(&verifier_key);
+        sponge.absorb(&left_instance);
+        sponge.absorb(&right_instance);
+        sponge.absorb(&cross_term_commitment);
+        let challenge =
+            FoldChallenge::squeeze(&mut sponge, public_parameters.config.challenge_bits, 0);
+
+        Ok(left_instance.fold(right_instance, cross_term_commitment, challenge))
+    }
+
+    /// Folds `k` instances in one round like [`crate::PLONKFoldingScheme`]: interpolating the
+    /// instances at the points `0, ..., k - 1` gives `Az ∘ Bz - u Cz` of degree `2 (k - 1)`, and
+    /// the prover commits to the `k - 1` evaluations of its quotient `K` by the vanishing
+    /// polynomial, once the interpolated slack vectors are subtracted, on the next points.
+    fn prover_many<R: Rng>(
+        public_parameters: &Self::PublicParameters,
+        prover_key: &Self::ProverKey,
+        instances: &[Self::Instance],
+        witnesses: &[Self::Witness],
+        rng: &mut R,
+    ) -> Result<(Self::Instance, Self::Witness, Self::ProverMessage), SangriaError> {
+        let shape = &prover_key.shape;
+        prove_many::<F, Comm, RO, R>(
+            public_parameters,
+            &prover_key.verifier_key,
+            instances,
+            witnesses,
+            2,
+            |witness, u, public_inputs| {
+                let (a, b, c) = shape.evaluate(witness, u, public_inputs);
+                a.into_iter()
+                    .zip(b)
+                    .zip(c)
+                    .map(|((a, b), c)| a * b - u * c)
+                    .collect()
+            },
+            rng,
+        )
+    }
+
+    fn verifier_many(
+        public_parameters: &Self::PublicParameters,
+        verifier_key: &Self::VerifierKey,
+        instances: &[Self::Instance],
+        prover_message: &Self::ProverMessage,
+    ) -> Result<Self::Instance, SangriaError> {
+        verify_many::<F, Comm, RO>(
+            public_parameters,
+            verifier_key,
+            instances,
+            prover_message,
+            2,
+        )
+    }
+}
+
+/// A folded instance-witness pair and the prover message folding it.
+type FoldOutput<F, Comm> = (
+    RelaxedR1CSInstance<F, Comm>,
+    RelaxedR1CSWitness<F>,
+    Vec<SlackCommitment<F, Comm>>,
+);
+
+/// Folds `k` instance-witness pairs of a relaxed relation of degree `d` over `z = (W, u, x)` in one
+/// round, `relation` evaluating its homogeneous left-hand side on `(W, u, x)`: interpolating the
+/// instances at the points `0, ..., k - 1` gives a left-hand side of degree `d (k - 1)`, and the
+/// prover commits to the `(d - 1)(k - 1)` evaluations on the next points of its quotient `K` by the
+/// vanishing polynomial, once the interpolated slack vectors are subtracted.
+pub(crate) fn prove_many<F, Comm, RO, R>(
+    public_parameters: &PublicParameters<F, Comm>,
+    verifier_key: &VerifierKey<F>,
+    instances: &[RelaxedR1CSInstance<F, Comm>],
+    witnesses: &[RelaxedR1CSWitness<F>],
+    degree: usize,
+    relation: impl Fn(&[F], F, &[F]) -> Vec<F>,
+    rng: &mut R,
+) -> Result<FoldOutput<F, Comm>, SangriaError>
+where
+    F: PrimeField + Absorb,
+    Comm: FoldingCommitmentConfig<F>,
+    RO: Transcript<F>,
+    R: Rng,
+{
+    if instances.is_empty() || instances.len() != witnesses.len() {
+        return Err(SangriaError::InvalidWitness);
+    }
+
+    let number_of_instances = instances.len();
+    let number_of_quotients = (degree - 1) * (number_of_instances - 1);
+    let quotients: Vec<Vec<F>> = (number_of_instances..number_of_instances + number_of_quotients)
+        .map(|point| {
+            let point = F::from(point as u64);
+            let basis = lagrange_basis(0..number_of_instances, point);
+            let vanishing_inverse = (0..number_of_instances)
+                .map(|other| point - F::from(other as u64))
+                .product::<F>()
+                .inverse()
+                .expect("the points of the quotients are not points of the instances");
+            let witness =
+                linear_combination(&basis, witnesses.iter().map(|witness| &witness.witness[..]));
+            let public_inputs = linear_combination(
+                &basis,
+                instances.iter().map(|instance| &instance.public_inputs[..]),
+            );
+            let scaling_factor: F = instances
+                .iter()
+                .zip(&basis)
+                .map(|(instance, &basis)| basis * instance.scaling_factor)
+                .sum();
+            let slack_vector = linear_combination(
+                &basis,
+                witnesses.iter().map(|witness| &witness.slack_vector[..]),
+            );
+            relation(&witness, scaling_factor, &public_inputs)
+                .into_iter()
+                .zip(slack_vector)
+                .map(|(value, slack)| (value - slack) * vanishing_inverse)
+                .collect()
+        })
+        .collect();
+    let quotient_hidings: Vec<F> = quotients
+        .iter()
+        .map(|_| hiding(&public_parameters.config, rng))
+        .collect();
+    let quotient_commitments =
+        <Comm::CommitmentSlack as HomomorphicCommitmentScheme<F>>::batch_commit(
+            &public_parameters.commit_key_slack,
+            &quotients,
+            &quotient_hidings,
+        )?;
+
+    // the challenge is squeezed from the same transcript as in the verifier
+    let mut sponge = RO::start(&public_parameters.poseidon_constants);
+    sponge.absorb(verifier_key);
+    sponge.absorb(&F::from(number_of_instances as u64));
+    sponge.absorb(&instances);
+    sponge.absorb(&quotient_commitments);
+    let challenge = FoldChallenge::squeeze(&mut sponge, public_parameters.config.challenge_bits, 0);
+
+    let folded_witness =
+        RelaxedR1CSWitness::fold_many(witnesses, &quotients, &quotient_hidings, challenge.value());
+    let folded_instance =
+        RelaxedR1CSInstance::fold_many(instances, &quotient_commitments, challenge)?;
+
+    Ok((folded_instance, folded_witness, quotient_commitments))
+}
+
+/// Folds `k` instances of a relaxed relation of degree `d` in one round with the commitments to
+/// the `(d - 1)(k - 1)` quotient terms computed by [`prove_many`].
+pub(crate) fn verify_many<F, Comm, RO>(
+    public_parameters: &PublicParameters<F, Comm>,
+    verifier_key: &VerifierKey<F>,
+    instances: &[RelaxedR1CSInstance<F, Comm>],
+    quotient_commitments: &[SlackCommitment<F, Comm>],
+    degree: usize,
+) -> Result<RelaxedR1CSInstance<F, Comm>, SangriaError>
+where
+    F: PrimeField + Absorb,
+    Comm: FoldingCommitmentConfig<F>,
+    RO: Transcript<F>,
+{
+    check_public_inputs(public_parameters, instances)?;
+    if instances.is_empty() || quotient_commitments.len() != (degree - 1) * (instances.len() - 1) {
+        return Err(SangriaError::InvalidProof);
+    }
+
+    let mut sponge = RO::start(&public_parameters.poseidon_constants);
+    sponge.absorb(verifier_key);
+    sponge.absorb(&F::from(instances.len() as u64));
+    sponge.absorb(&instances);
+    sponge.absorb(&quotient_commitments);
+    let challenge = FoldChallenge::squeeze(&mut sponge, public_parameters.config.challenge_bits, 0);
+
+    RelaxedR1CSInstance::fold_many(instances, quotient_commitments, challenge)
+}
+
+impl<F, Comm, RO> R1CSFoldingScheme<F, Comm, RO>
+where
+    F: PrimeField + Absorb,
+    Comm: FoldingCommitmentConfig<F>,
+    RO: Transcript<F>,
+{
+    /// Commits to the variables of a fresh witness and returns its instance, with the given public
+    /// inputs.
+    pub fn commit_witness(
+        public_parameters: &PublicParameters<F, Comm>,
+        public_inputs: Vec<F>,
+        witness: &RelaxedR1CSWitness<F>,
+    ) -> Result<RelaxedR1CSInstance<F, Comm>, SangriaError> {
+        let instance = RelaxedR1CSInstance {
+            witness_commitment:
+                <Comm::CommitmentWitness as HomomorphicCommitmentScheme<F>>::commit(
+                    &public_parameters.commit_key_witness,
+                    &witness.witness,
+                    witness.witness_hiding,
+                )?,
+            slack_commitment: Zero::zero(),
+            scaling_factor: F::one(),
+            public_inputs,
+        };
+        check_public_inputs(public_parameters, [&instance])?;
+
+        Ok(instance)
+    }
+
+    /// Converts a relaxed PLONK instance-witness pair of `circuit`, of gate degree 2, into a relaxed
+    /// R1CS pair of [`R1CSShape::from_plonk`] with the public inputs of the public parameters, e.g.
+    /// to hand a running accumulator over to a Nova pipeline. The variables and the public inputs
+    /// are read from the wires of the pair and the scaling factor is kept. The slack vector is zero
+    /// on the public input equalities and that of the pair on the constraint of each gate, so the
+    /// converted pair satisfies the R1CS relation exactly when the pair satisfies the PLONK
+    /// relation. The variables and the slack vector are committed to anew, the commitments of the
+    /// pair being to other vectors. Returns the structure and the pair, or an error if the copy
+    /// constraint does not hold or if the slack vector of the pair is not zero on the rows that are
+    /// not converted into constraints.
+    ///
+    /// There is no conversion back: a relaxed R1CS of Nova is not a PLONK circuit in general.
+    #[allow(clippy::type_complexity)]
+    pub fn from_relaxed_plonk<PLONKComm, R>(
+        public_parameters: &PublicParameters<F, Comm>,
+        circuit: &PLONKCircuit<F>,
+        instance: &RelaxedPLONKInstance<F, PLONKComm>,
+        witness: &RelaxedPLONKWitness<F>,
+        rng: &mut R,
+    ) -> Result<
+        (
+            R1CSShape<F>,
+            RelaxedR1CSInstance<F, Comm>,
+            RelaxedR1CSWitness<F>,
+        ),
+        SangriaError,
+    >
+    where
+        PLONKComm: FoldingCommitmentConfig<F>,
+        R: Rng,
+    {
+        let number_of_public_inputs = public_parameters.number_of_public_inputs;
+        let shape = R1CSShape::from_plonk(circuit, number_of_public_inputs)?;
+        let (variables, public_inputs) = shape.wires.relaxed_assignment(
+            &trace_columns(instance, witness)?,
+            number_of_public_inputs,
+            instance.scaling_factor(),
+        )?;
+
+        let plonk_slack = witness.slack_vector();
+        let mut is_constraint = vec![false; plonk_slack.len()];
+        let mut slack_vector = vec![F::zero(); shape.wires.public_equalities().len()];
+        for row in constraint_rows(circuit, number_of_public_inputs) {
+            let slack = plonk_slack.get(row).ok_or(SangriaError::InvalidWitness)?;
+            is_constraint[row] = true;
+            slack_vector.push(*slack);
+        }
+        if plonk_slack
+            .iter()
+            .zip(&is_constraint)
+            .any(|(slack, &is_constraint)| !is_constraint && !slack.is_zero())
+        {
+            return Err(SangriaError::InvalidWitness);
+        }
+
+        let config = &public_parameters.config;
+        let r1cs_witness = RelaxedR1CSWitness {
+            witness: variables,
+            slack_vector,
+            witness_hiding: hiding(config, rng),
+            slack_hiding: hiding(config, rng),
+        };
+        let r1cs_instance = RelaxedR1CSInstance {
+            witness_commitment:
+                <Comm::CommitmentWitness as HomomorphicCommitmentScheme<F>>::commit(
+                    &public_parameters.commit_key_witness,
+                    &r1cs_witness.witness,
+                    r1cs_witness.witness_hiding,
+                )?,
+            slack_commitment: <Comm::CommitmentSlack as HomomorphicCommitmentScheme<F>>::commit(
+                &public_parameters.commit_key_slack,
+                &r1cs_witness.slack_vector,
+                r1cs_witness.slack_hiding,
+            )?,
+            scaling_factor: instance.scaling_factor(),
+            public_inputs,
+        };
+
+        Ok((shape, r1cs_instance, r1cs_witness))
+    }
+
+    /// Checks that `witness` satisfies the relaxed R1CS relation for `instance` and `shape`: the
+    /// commitments of the instance open to the witness and `Az ∘ Bz = u Cz + E`. Returns an error
+    /// otherwise.
+    pub fn check_relation(
+        public_parameters: &PublicParameters<F, Comm>,
+        shape: &R1CSShape<F>,
+        instance: &RelaxedR1CSInstance<F, Comm>,
+        witness: &RelaxedR1CSWitness<F>,
+    ) -> Result<(), SangriaError> {
+        public_parameters.check_commitments(
+            instance,
+            witness,
+            shape.number_of_variables,
+            shape.number_of_constraints(),
+        )?;
+
+        let (a, b, c) = shape.evaluate(
+            &witness.witness,
+            instance.scaling_factor,
+            &instance.public_inputs,
+        );
+        let satisfied = (0..shape.number_of_constraints()).all(|row| {
+            a[row] * b[row] == instance.scaling_factor * c[row] + witness.slack_vector[row]
+        });
+        if !satisfied {
+            return Err(SangriaError::InvalidWitness);
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the linear combination of the vectors with the coefficients.
+fn linear_combination<'a, F: PrimeField>(
+    coefficients: &[F],
+    vectors: impl IntoIterator<Item = &'a [F]>,
+) -> Vec<F> {
+    vectors.into_iter().zip(coefficients).fold(
+        Vec::new(),
+        |mut combination, (vector, &coefficient)| {
+            combination.resize(vector.len(), F::zero());
+            for (combined, &value) in combination.iter_mut().zip(vector) {
+                *combined += coefficient * value;
+            }
+            combination
+        },
+    )
+}
+
+/// Samples the randomness to commit to a prover message, which is zero unless the configuration
+/// is zero-knowledge.
+fn hiding<F: PrimeField, R: Rng>(config: &SangriaConfig, rng: &mut R) -> F {
+    if config.zero_knowledge {
+        F::rand(rng)
+    } else {
+        F::zero()
+    }
+}
+
+/// Checks that every instance has the number of public inputs of the public parameters, or returns
+/// an error.
+fn check_public_inputs<'a, F, Comm>(
+    public_parameters: &PublicParameters<F, Comm>,
+    instances: impl IntoIterator<Item = &'a RelaxedR1CSInstance<F, Comm>>,
+) -> Result<(), SangriaError>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F> + 'a,
+{
+    if instances
+        .into_iter()
+        .any(|instance| instance.public_inputs.len() != public_parameters.number_of_public_inputs)
+    {
+        return Err(SangriaError::PublicInputMismatch);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{R1CSFoldingScheme, R1CSShape, RelaxedR1CSInstance, RelaxedR1CSWitness};
+    use crate::{
+        utils::{
+            circuits::square_plus_five, inner_product::InnerProductConfig,
+            poseidon::poseidon_parameters_for_test,
+        },
+        NonInteractiveFoldingScheme, PLONKFoldingScheme, SangriaConfig, SangriaError, SetupInfo,
+    };
+    use ark_bn254::Fr;
+    use ark_crypto_primitives::sponge::poseidon::PoseidonSponge;
+    use ark_ff::{One, UniformRand};
+    use ark_serialize::{CanonicalSerialize, Compress, Validate};
+    use ark_std::test_rng;
+
+    type Scheme = R1CSFoldingScheme<Fr, InnerProductConfig, PoseidonSponge<Fr>>;
+
+    #[test]
+    fn converted_plonk_circuits_fold() {
+        let rng = &mut test_rng();
+        let info = SetupInfo {
+            number_of_public_inputs: 1,
+            number_of_gates: 2,
+            config: SangriaConfig::default(),
+            poseidon_constants: poseidon_parameters_for_test(),
+        };
+        let pp = Scheme::setup(&info, rng).unwrap();
+        let circuit = square_plus_five(0).circuit(Vec::new()).unwrap();
+        let shape = R1CSShape::from_plonk(&circuit, 1).unwrap();
+        // x^2 and y, and the five wires copied nowhere; x is the public input
+        assert_eq!(
+            (shape.number_of_variables(), shape.number_of_constraints()),
+            (2 + 5, 2)
+        );
+        let (pk, vk) = Scheme::encode(&pp, &shape, rng).unwrap();
+
+        let pairs: Vec<_> = (1..=4u64)
+            .map(|x| {
+                let (witness, public_inputs) = shape.assignment(&square_plus_five(x)).unwrap();
+                let witness =
+                    RelaxedR1CSWitness::new(witness, shape.number_of_constraints(), Fr::rand(rng));
+                let instance = Scheme::commit_witness(&pp, public_inputs, &witness).unwrap();
+                assert_eq!(
+                    Scheme::check_relation(&pp, &shape, &instance, &witness),
+                    Ok(())
+                );
+                (instance, witness)
+            })
+            .collect();
+
+        let (folded_instance, folded_witness, message) = Scheme::prover(
+            &pp,
+            &pk,
+            &pairs[0].0,
+            &pairs[0].1,
+            &pairs[1].0,
+            &pairs[1].1,
+            rng,
+        )
+        .unwrap();
+        assert!(
+            Scheme::verifier(&pp, &vk, &pairs[0].0, &pairs[1].0, &message).unwrap()
+                == folded_instance
+        );
+        assert_eq!(
+            Scheme::check_relation(&pp, &shape, &folded_instance, &folded_witness),
+            Ok(())
+        );
+
+        let (instances, witnesses): (Vec<_>, Vec<_>) = pairs
+            .into_iter()
+            .skip(2)
+            .chain([(folded_instance, folded_witness)])
+            .unzip();
+        let (folded_instance, folded_witness, message) =
+            Scheme::prover_many(&pp, &pk, &instances, &witnesses, rng).unwrap();
+        assert_eq!(message.len(), 2);
+        assert!(Scheme::verifier_many(&pp, &vk, &instances, &message).unwrap() == folded_instance);
+        assert_eq!(
+            Scheme::check_relation(&pp, &shape, &folded_instance, &folded_witness),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn folded_plonk_pairs_convert_to_nova_instances() {
+        type PLONKScheme = PLONKFoldingScheme<Fr, InnerProductConfig, PoseidonSponge<Fr>>;
+
+        let rng = &mut test_rng();
+        let info = SetupInfo {
+            number_of_public_inputs: 1,
+            number_of_gates: 2,
+            config: SangriaConfig::default(),
+            poseidon_constants: poseidon_parameters_for_test(),
+        };
+        let plonk_pp = PLONKScheme::setup(&info, rng).unwrap();
+        let pp = Scheme::setup(&info, rng).unwrap();
+        let pairs = [3u64, 7].map(|x| {
+            let trace = square_plus_five(x);
+            let instance_columns = trace.instance_columns();
+            let (circuit, witness) = trace.into_circuit_and_witness(Vec::new(), rng).unwrap();
+            let instance =
+                PLONKScheme::commit_witness(&plonk_pp, instance_columns, &witness).unwrap();
+            (circuit, instance, witness)
+        });
+        let circuit = &pairs[0].0;
+        let (plonk_pk, _) = PLONKScheme::encode(&plonk_pp, circuit, rng).unwrap();
+        let (instance, witness, _) = PLONKScheme::prover(
+            &plonk_pp,
+            &plonk_pk,
+            &pairs[0].1,
+            &pairs[0].2,
+            &pairs[1].1,
+            &pairs[1].2,
+            rng,
+        )
+        .unwrap();
+
+        let (shape, r1cs_instance, r1cs_witness) =
+            Scheme::from_relaxed_plonk(&pp, circuit, &instance, &witness, rng).unwrap();
+        assert_eq!(r1cs_instance.scaling_factor(), instance.scaling_factor());
+        assert_eq!(
+            r1cs_instance.public_inputs(),
+            instance.public_inputs().unwrap()
+        );
+        assert_eq!(
+            Scheme::check_relation(&pp, &shape, &r1cs_instance, &r1cs_witness),
+            Ok(())
+        );
+
+        let mut bytes = Vec::new();
+        r1cs_instance.serialize_compressed(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), r1cs_instance.compressed_size());
+        let limits = plonk_pp.size_limits();
+        let parsed = RelaxedR1CSInstance::<Fr, InnerProductConfig>::deserialize_bounded(
+            &bytes[..],
+            &limits,
+            Compress::Yes,
+            Validate::Yes,
+        )
+        .unwrap();
+        assert!(parsed == r1cs_instance);
+
+        // the public input rows of one trace and the gate rows of the other break the copies
+        assert_eq!(
+            Scheme::from_relaxed_plonk(&pp, circuit, &pairs[0].1, &pairs[1].2, rng).err(),
+            Some(SangriaError::InvalidWitness)
+        );
+    }
+
+    #[test]
+    fn unsatisfying_witness_is_rejected() {
+        let rng = &mut test_rng();
+        let info = SetupInfo {
+            number_of_public_inputs: 1,
+            number_of_gates: 2,
+            config: SangriaConfig::default(),
+            poseidon_constants: poseidon_parameters_for_test(),
+        };
+        let pp = Scheme::setup(&info, rng).unwrap();
+        let shape =
+            R1CSShape::from_plonk(&square_plus_five(0).circuit(Vec::new()).unwrap(), 1).unwrap();
+
+        let (mut witness, public_inputs) = shape.assignment(&square_plus_five(3)).unwrap();
+        witness
+            .iter_mut()
+            .for_each(|variable| *variable += Fr::one());
+        let witness = RelaxedR1CSWitness::new(witness, shape.number_of_constraints(), Fr::one());
+        let instance = Scheme::commit_witness(&pp, public_inputs, &witness).unwrap();
+
+        assert_eq!(
+            Scheme::check_relation(&pp, &shape, &instance, &witness),
+            Err(SangriaError::InvalidWitness)
+        );
+        assert_eq!(
+            Scheme::setup(
+                &SetupInfo {
+                    config: SangriaConfig {
+                        gate_degree: 5,
+                        ..SangriaConfig::default()
+                    },
+                    ..info
+                },
+                rng
+            )
+            .err(),
+            Some(SangriaError::InvalidConfig(
+                "relaxed R1CS constraints have degree 2"
+            ))
+        );
+    }
+}
@@ -1,2 +1,173 @@
-/// The Sangria IVC scheme with proof compression and zero-knowledge
-pub struct Sangria {}
+use ark_crypto_primitives::sponge::{poseidon::PoseidonConfig, Absorb};
+use ark_ff::PrimeField;
+use ark_std::marker::PhantomData;
+
+use crate::{poseidon_hash, SangriaError, StepCircuit};
+
+/// The Sangria IVC scheme, folding the steps as instances of the relation selected by `Relation`
+/// ([`PLONKRelation`] by default, or [`R1CSRelation`]) and committing to them with the schemes of
+/// `Comm`. The helper functions below do not depend on the commitment schemes and are called as
+/// `Sangria::replay`, `Sangria::io_hash`, etc.
+///
+/// Its proofs are experimental [batched proofs](crate::BatchedIVCProof), whose verifier re-folds
+/// every step, so it does not implement [`crate::IVC`] and proves them with inherent functions.
+pub struct Sangria<Comm = (), Relation = PLONKRelation>(PhantomData<(Comm, Relation)>);
+
+/// Selects relaxed PLONK as the relation folded by [`Sangria`], with the
+/// [`crate::PLONKFoldingScheme`].
+pub struct PLONKRelation;
+
+/// Selects relaxed R1CS as the relation folded by [`Sangria`], with the
+/// [`crate::R1CSFoldingScheme`]: the augmented circuit of every step is converted to R1CS with
+/// [`crate::R1CSShape::from_plonk`], so the step circuits are written as for PLONK but must have
+/// gate degree 2.
+pub struct R1CSRelation;
+
+impl Sangria {
+    /// Execute the step circuit natively on each witness in turn, starting from `origin_state`, and
+    /// return the final state. Nothing is proved: this is a cheap way to pre-validate a batch of
+    /// witnesses and to know which final state a proof over them should attest to.
+    pub fn replay<F: PrimeField, SC: StepCircuit<F>>(
+        step_circuit: &SC,
+        origin_state: SC::State,
+        witnesses: &[SC::Witness],
+    ) -> Result<SC::State, SangriaError> {
+        witnesses.iter().try_fold(origin_state, |state, witness| {
+            step_circuit.execute(&state, witness)
+        })
+    }
+
+    /// Compute the hash binding the public IO of a proof to the application: the
+    /// [application identifier](crate::SangriaConfig::application_id), the number of steps `i`, the
+    /// origin state `z0`, the current state `zi` and the digest of the running accumulator. This is
+    /// the value a proof exposes as its IO hash. It is hashed with [`poseidon_hash`], so that the augmented
+    /// circuit can recompute it with [`crate::poseidon_hash_gadget`].
+    pub fn io_hash<F: PrimeField + Absorb, S: Absorb>(
+        poseidon_constants: &PoseidonConfig<F>,
+        application_id: &[u8],
+        number_of_steps: u64,
+        origin_state: &S,
+        current_state: &S,
+        accumulator_digest: F,
+    ) -> F {
+        // the length keeps identifiers that only differ by trailing zero bytes apart once packed
+        let mut inputs = vec![F::from(application_id.len() as u64)];
+        inputs.extend(application_id.to_sponge_field_elements_as_vec::<F>());
+        inputs.push(F::from(number_of_steps));
+        inputs.extend(origin_state.to_sponge_field_elements_as_vec::<F>());
+        inputs.extend(current_state.to_sponge_field_elements_as_vec::<F>());
+        inputs.push(accumulator_digest);
+
+        poseidon_hash(poseidon_constants, &inputs)
+    }
+
+    /// Check that a claimed `(i, z0, zi)` and accumulator digest match the IO hash of a proof, or
+    /// return an error. This lets a light client that trusts an earlier full verification of the
+    /// proof check a claimed state without running the verifier again.
+    pub fn check_io_hash<F: PrimeField + Absorb, S: Absorb>(
+        poseidon_constants: &PoseidonConfig<F>,
+        application_id: &[u8],
+        number_of_steps: u64,
+        origin_state: &S,
+        current_state: &S,
+        accumulator_digest: F,
+        io_hash: F,
+    ) -> Result<(), SangriaError> {
+        let expected = Self::io_hash(
+            poseidon_constants,
+            application_id,
+            number_of_steps,
+            origin_state,
+            current_state,
+            accumulator_digest,
+        );
+        if expected != io_hash {
+            return Err(SangriaError::PublicInputMismatch);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Sangria;
+    use crate::{utils::poseidon::poseidon_parameters_for_test, SangriaError, StepCircuit};
+    use ark_bn254::Fr;
+    use ark_ff::Zero;
+
+    /// Adds the witness to the state, rejecting zero increments.
+    struct Counter;
+
+    impl StepCircuit<Fr> for Counter {
+        type State = Fr;
+        type Witness = Fr;
+
+        fn execute(&self, current_state: &Fr, witness: &Fr) -> Result<Fr, SangriaError> {
+            if witness.is_zero() {
+                return Err(SangriaError::InvalidWitness);
+            }
+
+            Ok(*current_state + witness)
+        }
+    }
+
+    #[test]
+    fn replay_runs_every_step() {
+        let witnesses: Vec<Fr> = (1..=10u64).map(Fr::from).collect();
+
+        let final_state = Sangria::replay(&Counter, Fr::from(5u64), &witnesses).unwrap();
+
+        assert_eq!(final_state, Fr::from(60u64));
+    }
+
+    #[test]
+    fn replay_without_witnesses_returns_origin() {
+        let final_state = Sangria::replay(&Counter, Fr::from(5u64), &[]).unwrap();
+
+        assert_eq!(final_state, Fr::from(5u64));
+    }
+
+    #[test]
+    fn replay_stops_at_invalid_witness() {
+        let witnesses = vec![Fr::from(1u64), Fr::zero(), Fr::from(2u64)];
+
+        assert_eq!(
+            Sangria::replay(&Counter, Fr::zero(), &witnesses),
+            Err(SangriaError::InvalidWitness)
+        );
+    }
+
+    #[test]
+    fn io_hash_binds_every_component() {
+        let params = poseidon_parameters_for_test::<Fr>();
+        let (origin, current, digest) = (Fr::from(5u64), Fr::from(60u64), Fr::from(7u64));
+        let io_hash = Sangria::io_hash(&params, b"mainnet", 10, &origin, &current, digest);
+
+        assert_eq!(
+            Sangria::check_io_hash(&params, b"mainnet", 10, &origin, &current, digest, io_hash),
+            Ok(())
+        );
+        for (application_id, steps, origin, current, digest) in [
+            (&b"testnet"[..], 10, origin, current, digest),
+            (b"mainnet\0", 10, origin, current, digest),
+            (b"mainnet", 11, origin, current, digest),
+            (b"mainnet", 10, current, current, digest),
+            (b"mainnet", 10, origin, origin, digest),
+            (b"mainnet", 10, origin, current, Fr::zero()),
+        ] {
+            assert_eq!(
+                Sangria::check_io_hash(
+                    &params,
+                    application_id,
+                    steps,
+                    &origin,
+                    &current,
+                    digest,
+                    io_hash
+                ),
+                Err(SangriaError::PublicInputMismatch)
+            );
+        }
+    }
+}
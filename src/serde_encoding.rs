@@ -0,0 +1,294 @@
+//! `serde` support for proofs, archived prover messages, instances and verifier keys, enabled by
+//! the `serde` feature. Every value is encoded as its compressed canonical serialization: a
+//! lowercase hex string in human-readable formats (e.g. JSON) and a byte string in binary formats
+//! (e.g. CBOR). Reading a value checks that its group elements are valid and that no byte follows
+//! its encoding.
+
+use ark_ff::PrimeField;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use serde::{
+    de::{self, Visitor},
+    ser, Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::fmt;
+
+use crate::{
+    folding_scheme::{self, FoldingCommitmentConfig},
+    ivc, BatchedIVCProof, CompressedBatchedIVCProof, InnerProductOpening, RelaxedPLONKInstance,
+    StepMessage,
+};
+
+fn serialize_canonical<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: CanonicalSerialize,
+    S: Serializer,
+{
+    let mut bytes = Vec::with_capacity(value.compressed_size());
+    value
+        .serialize_compressed(&mut bytes)
+        .map_err(ser::Error::custom)?;
+
+    if serializer.is_human_readable() {
+        let hex: String = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+        serializer.serialize_str(&hex)
+    } else {
+        serializer.serialize_bytes(&bytes)
+    }
+}
+
+fn deserialize_canonical<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: CanonicalDeserialize,
+    D: Deserializer<'de>,
+{
+    let bytes = if deserializer.is_human_readable() {
+        deserializer.deserialize_str(HexVisitor)?
+    } else {
+        deserializer.deserialize_byte_buf(BytesVisitor)?
+    };
+
+    let mut reader = &bytes[..];
+    let value = T::deserialize_compressed(&mut reader).map_err(de::Error::custom)?;
+    if !reader.is_empty() {
+        return Err(de::Error::custom("trailing bytes after the encoded value"));
+    }
+
+    Ok(value)
+}
+
+/// Reads a hex string, in either case, into bytes.
+struct HexVisitor;
+
+impl<'de> Visitor<'de> for HexVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a hex string")
+    }
+
+    fn visit_str<E: de::Error>(self, hex: &str) -> Result<Vec<u8>, E> {
+        if !hex.len().is_multiple_of(2) {
+            return Err(E::invalid_length(hex.len(), &self));
+        }
+
+        let digit = |digit: u8| (digit as char).to_digit(16);
+        hex.as_bytes()
+            .chunks(2)
+            .map(|pair| match (digit(pair[0]), digit(pair[1])) {
+                (Some(high), Some(low)) => Ok((high * 16 + low) as u8),
+                _ => Err(E::invalid_value(de::Unexpected::Str(hex), &self)),
+            })
+            .collect()
+    }
+}
+
+/// Reads a byte string, or a sequence of bytes for formats without byte strings.
+struct BytesVisitor;
+
+impl<'de> Visitor<'de> for BytesVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a byte string")
+    }
+
+    fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Vec<u8>, E> {
+        Ok(bytes.to_vec())
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, bytes: Vec<u8>) -> Result<Vec<u8>, E> {
+        Ok(bytes)
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<u8>, A::Error> {
+        let mut bytes = Vec::new();
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
+        }
+
+        Ok(bytes)
+    }
+}
+
+impl<F, Comm> Serialize for RelaxedPLONKInstance<F, Comm>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_canonical(self, serializer)
+    }
+}
+
+impl<'de, F, Comm> Deserialize<'de> for RelaxedPLONKInstance<F, Comm>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_canonical(deserializer)
+    }
+}
+
+impl<F, Comm> Serialize for folding_scheme::VerifierKey<F, Comm>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_canonical(self, serializer)
+    }
+}
+
+impl<'de, F, Comm> Deserialize<'de> for folding_scheme::VerifierKey<F, Comm>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_canonical(deserializer)
+    }
+}
+
+impl<F, Comm, SC> Serialize for ivc::VerifierKey<F, Comm, SC>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+    SC: CanonicalSerialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_canonical(self, serializer)
+    }
+}
+
+impl<'de, F, Comm, SC> Deserialize<'de> for ivc::VerifierKey<F, Comm, SC>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+    SC: CanonicalDeserialize,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_canonical(deserializer)
+    }
+}
+
+impl<F, Comm> Serialize for BatchedIVCProof<F, Comm>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_canonical(self, serializer)
+    }
+}
+
+impl<'de, F, Comm> Deserialize<'de> for BatchedIVCProof<F, Comm>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_canonical(deserializer)
+    }
+}
+
+impl<F, Comm> Serialize for StepMessage<F, Comm>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_canonical(self, serializer)
+    }
+}
+
+impl<'de, F, Comm> Deserialize<'de> for StepMessage<F, Comm>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_canonical(deserializer)
+    }
+}
+
+impl<F, Comm> Serialize for CompressedBatchedIVCProof<F, Comm>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+    Comm::CommitmentSlack: InnerProductOpening<F>,
+    Comm::CommitmentWitness: InnerProductOpening<F>,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_canonical(self, serializer)
+    }
+}
+
+impl<'de, F, Comm> Deserialize<'de> for CompressedBatchedIVCProof<F, Comm>
+where
+    F: PrimeField,
+    Comm: FoldingCommitmentConfig<F>,
+    Comm::CommitmentSlack: InnerProductOpening<F>,
+    Comm::CommitmentWitness: InnerProductOpening<F>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_canonical(deserializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ivc::VerifierKey,
+        utils::{
+            circuits::Counter, inner_product::InnerProductConfig,
+            poseidon::poseidon_parameters_for_test,
+        },
+        BatchedIVCProof, Sangria, SangriaConfig, SetupInfo,
+    };
+    use ark_bn254::Fr;
+    use ark_ff::{One, Zero};
+    use ark_std::test_rng;
+
+    type Scheme = Sangria<InnerProductConfig>;
+
+    #[test]
+    fn proofs_and_keys_round_trip_through_json() {
+        let rng = &mut test_rng();
+        let info = SetupInfo {
+            number_of_public_inputs: 2,
+            number_of_gates: 2,
+            config: SangriaConfig::default(),
+            poseidon_constants: poseidon_parameters_for_test(),
+        };
+        let public_parameters = Scheme::setup(&info, rng).unwrap();
+        let (pk, vk) = Scheme::encode(&public_parameters, &Counter, rng).unwrap();
+        let (state, proof) =
+            Scheme::prove_step(&pk, &Fr::zero(), Fr::zero(), None, &Fr::one(), rng).unwrap();
+        let compressed_proof = Scheme::compress(&pk, proof.clone()).unwrap();
+
+        let vk: VerifierKey<Fr, InnerProductConfig, Counter> =
+            serde_json::from_str(&serde_json::to_string(&vk).unwrap()).unwrap();
+        let json = serde_json::to_string(&proof).unwrap();
+        let hex = json.trim_matches('"');
+        assert!(hex.bytes().all(|digit| digit.is_ascii_hexdigit()));
+        let proof: BatchedIVCProof<Fr, InnerProductConfig> = serde_json::from_str(&json).unwrap();
+        assert_eq!(Scheme::verify(&vk, &Fr::zero(), state, Some(proof)), Ok(()));
+        let compressed_proof =
+            serde_json::from_value(serde_json::to_value(&compressed_proof).unwrap()).unwrap();
+        assert_eq!(
+            Scheme::verify_compressed(&vk, &Fr::zero(), state, compressed_proof,),
+            Ok(())
+        );
+
+        // odd lengths, non-hex digits and trailing bytes are rejected
+        for hex in [&hex[1..], &format!("+f{}", &hex[2..]), &format!("{hex}00")] {
+            assert!(
+                serde_json::from_str::<BatchedIVCProof<Fr, InnerProductConfig>>(&format!(
+                    "\"{hex}\""
+                ))
+                .is_err()
+            );
+        }
+    }
+}
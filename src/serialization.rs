@@ -0,0 +1,183 @@
+use ark_ff::{BigInteger, PrimeField};
+use ark_serialize::{CanonicalDeserialize, Compress, SerializationError, Validate};
+use ark_std::io::Read;
+
+use crate::SangriaError;
+
+/// Upper bounds on the lengths accepted when parsing untrusted serialized structures. Every length
+/// prefix is checked against its bound before any entry is read, so malformed input cannot trigger
+/// large allocations.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SizeLimits {
+    /// The maximum number of columns of an instance, which is also the maximum number of witness
+    /// commitments.
+    pub max_columns: usize,
+    /// The maximum number of rows of an instance.
+    pub max_rows: usize,
+    /// The maximum number of application-defined extra commitments.
+    pub max_extra_commitments: usize,
+}
+
+impl SizeLimits {
+    /// Allows `max_extra_commitments` extra commitments per instance.
+    pub fn with_extra_commitments(self, max_extra_commitments: usize) -> Self {
+        Self {
+            max_extra_commitments,
+            ..self
+        }
+    }
+}
+
+/// Returns the length in bytes of the canonical encoding of the elements of `F`.
+pub fn fe_canonical_length<F: PrimeField>() -> usize {
+    (F::MODULUS_BIT_SIZE as usize).div_ceil(8)
+}
+
+/// Encodes `fe` as the little-endian bytes of its canonical representative, in
+/// [`fe_canonical_length`] bytes. The encoding does not depend on the limb size or the endianness
+/// of the platform, and matches the compressed serialization and the sponge bytes of `ark-ff`
+/// elements, so it is the encoding absorbed by hash transcripts and written in serialized proofs.
+/// Verifiers in other languages reading 32-byte big-endian words reverse the bytes.
+pub fn fe_to_bytes_canonical<F: PrimeField>(fe: &F) -> Vec<u8> {
+    let mut bytes = fe.into_bigint().to_bytes_le();
+    bytes.truncate(fe_canonical_length::<F>());
+    bytes
+}
+
+/// Decodes a field element from its canonical encoding, as output by [`fe_to_bytes_canonical`].
+/// Returns an error if `bytes` does not have the canonical length or encodes an integer that is not
+/// smaller than the modulus, so that every element has a single accepted encoding.
+pub fn fe_from_bytes_canonical<F: PrimeField>(bytes: &[u8]) -> Result<F, SangriaError> {
+    if bytes.len() != fe_canonical_length::<F>() {
+        return Err(SangriaError::InvalidEncoding);
+    }
+
+    let bits: Vec<bool> = bytes
+        .iter()
+        .flat_map(|byte| (0..8).map(move |index| (byte >> index) & 1 == 1))
+        .collect();
+    F::from_bigint(F::BigInt::from_bits_le(&bits)).ok_or(SangriaError::InvalidEncoding)
+}
+
+/// Reads a `u64` length prefix, rejects it if it exceeds `max_len`, then reads that many entries
+/// with `read_entry`.
+pub(crate) fn deserialize_bounded_vec_with<R, T>(
+    mut reader: R,
+    max_len: usize,
+    compress: Compress,
+    validate: Validate,
+    mut read_entry: impl FnMut(&mut R) -> Result<T, SerializationError>,
+) -> Result<Vec<T>, SerializationError>
+where
+    R: Read,
+{
+    let len = u64::deserialize_with_mode(&mut reader, compress, validate)?;
+    if len > max_len as u64 {
+        return Err(SerializationError::InvalidData);
+    }
+
+    let mut entries = Vec::with_capacity(len as usize);
+    for _ in 0..len {
+        entries.push(read_entry(&mut reader)?);
+    }
+
+    Ok(entries)
+}
+
+/// Reads a length-prefixed vector of at most `max_len` entries.
+pub(crate) fn deserialize_bounded_vec<R, T>(
+    reader: R,
+    max_len: usize,
+    compress: Compress,
+    validate: Validate,
+) -> Result<Vec<T>, SerializationError>
+where
+    R: Read,
+    T: CanonicalDeserialize,
+{
+    deserialize_bounded_vec_with(reader, max_len, compress, validate, |reader| {
+        T::deserialize_with_mode(reader, compress, validate)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{deserialize_bounded_vec, fe_from_bytes_canonical, fe_to_bytes_canonical};
+    use crate::SangriaError;
+    use ark_bn254::Fr;
+    use ark_crypto_primitives::sponge::Absorb;
+    use ark_ff::{One, Zero};
+    use ark_serialize::{CanonicalSerialize, Compress, SerializationError, Validate};
+
+    /// The canonical encoding of `p - 1` for the scalar field of BN254.
+    const MINUS_ONE: [u8; 32] = [
+        0x00, 0x00, 0x00, 0xf0, 0x93, 0xf5, 0xe1, 0x43, 0x91, 0x70, 0xb9, 0x79, 0x48, 0xe8, 0x33,
+        0x28, 0x5d, 0x58, 0x81, 0x81, 0xb6, 0x45, 0x50, 0xb8, 0x29, 0xa0, 0x31, 0xe1, 0x72, 0x4e,
+        0x64, 0x30,
+    ];
+
+    #[test]
+    fn canonical_encoding_matches_test_vectors() {
+        let mut one = [0u8; 32];
+        one[0] = 1;
+        let mut large = [0u8; 32];
+        large[..8].copy_from_slice(&0x0123_4567_89ab_cdefu64.to_le_bytes());
+        let vectors = [
+            (Fr::zero(), [0u8; 32]),
+            (Fr::one(), one),
+            (-Fr::one(), MINUS_ONE),
+            (Fr::from(0x0123_4567_89ab_cdefu64), large),
+        ];
+
+        for (fe, bytes) in vectors {
+            assert_eq!(fe_to_bytes_canonical(&fe), bytes);
+            assert_eq!(fe_from_bytes_canonical::<Fr>(&bytes), Ok(fe));
+
+            // serialized proofs and hash transcripts use the same encoding
+            let mut serialized = Vec::new();
+            fe.serialize_compressed(&mut serialized).unwrap();
+            assert_eq!(serialized, bytes);
+            assert_eq!(fe.to_sponge_bytes_as_vec(), bytes);
+        }
+    }
+
+    #[test]
+    fn non_canonical_encodings_are_rejected() {
+        // the modulus, p - 1 + 1
+        let mut modulus = MINUS_ONE;
+        modulus[0] = 1;
+
+        for bytes in [&modulus[..], &[0xff; 32], &MINUS_ONE[..31], &[0; 33]] {
+            assert_eq!(
+                fe_from_bytes_canonical::<Fr>(bytes),
+                Err(SangriaError::InvalidEncoding)
+            );
+        }
+    }
+
+    #[test]
+    fn oversized_length_prefix_is_rejected_before_reading() {
+        // a length prefix of 2^64 - 1 followed by nothing
+        let bytes = u64::MAX.to_le_bytes();
+
+        assert!(matches!(
+            deserialize_bounded_vec::<_, u8>(&bytes[..], 16, Compress::Yes, Validate::Yes),
+            Err(SerializationError::InvalidData)
+        ));
+    }
+
+    #[test]
+    fn bounded_vector_round_trips() {
+        let values = vec![1u32, 2, 3];
+        let mut bytes = Vec::new();
+        values.serialize_compressed(&mut bytes).unwrap();
+
+        assert_eq!(
+            deserialize_bounded_vec::<_, u32>(&bytes[..], 3, Compress::Yes, Validate::Yes).unwrap(),
+            values
+        );
+        assert!(
+            deserialize_bounded_vec::<_, u32>(&bytes[..], 2, Compress::Yes, Validate::Yes).is_err()
+        );
+    }
+}
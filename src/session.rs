@@ -0,0 +1,335 @@
+use ark_ff::PrimeField;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{
+    io::{Read, Write},
+    marker::PhantomData,
+    rand::{rngs::StdRng, SeedableRng},
+};
+
+use crate::{SangriaError, StepCircuit, IVC};
+
+/// Records a proving session of the IVC scheme `I`, so that it can be reproduced with [`replay`]
+/// after a prover crash or a verification failure, possibly days into a long chain. The recorder
+/// runs the setup, the encoding and every step itself, drawing all randomness from a generator
+/// seeded with the recorded seed. The recording starts with the seed, the setup arguments and the
+/// origin state, and every witness is appended and flushed before its step is proved, so that the
+/// recording is complete up to the step that crashed.
+pub struct SessionRecorder<F, SC, I, W>
+where
+    F: PrimeField,
+    SC: StepCircuit<F>,
+    I: IVC<F, SC>,
+{
+    writer: W,
+    rng: StdRng,
+    prover_key: I::ProverKey,
+    verifier_key: I::VerifierKey,
+    origin_state: SC::State,
+    state: SC::State,
+    proof: Option<I::Proof>,
+    _field: PhantomData<F>,
+}
+
+impl<F, SC, I, W> SessionRecorder<F, SC, I, W>
+where
+    F: PrimeField,
+    SC: StepCircuit<F>,
+    SC::State: Clone + CanonicalSerialize,
+    SC::Witness: CanonicalSerialize,
+    I: IVC<F, SC>,
+    I::SetupInfo: CanonicalSerialize,
+    I::Proof: Clone,
+    W: Write,
+{
+    /// Writes the header of the recording to `writer`, then runs the setup with `setup_info` and
+    /// encodes `step_circuit`, with randomness seeded by `seed`.
+    pub fn start(
+        mut writer: W,
+        seed: u64,
+        setup_info: &I::SetupInfo,
+        step_circuit: &SC,
+        origin_state: SC::State,
+    ) -> Result<Self, SangriaError> {
+        seed.serialize_compressed(&mut writer)
+            .and_then(|_| setup_info.serialize_compressed(&mut writer))
+            .and_then(|_| origin_state.serialize_compressed(&mut writer))
+            .map_err(|_| SangriaError::SessionRecording)?;
+        writer.flush().map_err(|_| SangriaError::SessionRecording)?;
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let public_parameters = I::setup(setup_info, &mut rng)?;
+        let (prover_key, verifier_key) = I::encode(&public_parameters, step_circuit, &mut rng)?;
+
+        Ok(Self {
+            writer,
+            rng,
+            prover_key,
+            verifier_key,
+            state: origin_state.clone(),
+            origin_state,
+            proof: None,
+            _field: PhantomData,
+        })
+    }
+
+    /// Appends `witness` to the recording, then proves the next step with it. The witness is
+    /// recorded even if the step fails, so that the failure can be replayed.
+    pub fn prove_step(&mut self, witness: &SC::Witness) -> Result<(), SangriaError> {
+        witness
+            .serialize_compressed(&mut self.writer)
+            .map_err(|_| SangriaError::SessionRecording)?;
+        self.writer
+            .flush()
+            .map_err(|_| SangriaError::SessionRecording)?;
+
+        let (state, proof) = I::prove_step(
+            &self.prover_key,
+            &self.origin_state,
+            self.state.clone(),
+            self.proof.clone(),
+            witness,
+            &mut self.rng,
+        )?;
+        (self.state, self.proof) = (state, Some(proof));
+
+        Ok(())
+    }
+
+    /// Returns the state reached by the last successful step.
+    pub fn state(&self) -> &SC::State {
+        &self.state
+    }
+
+    /// Returns the proof of the last successful step, or `None` before the first one.
+    pub fn proof(&self) -> Option<&I::Proof> {
+        self.proof.as_ref()
+    }
+
+    /// Returns the verifier key of the session.
+    pub fn verifier_key(&self) -> &I::VerifierKey {
+        &self.verifier_key
+    }
+
+    /// Ends the recording and returns its writer.
+    pub fn into_writer(self) -> W {
+        self.writer
+    }
+}
+
+/// The outcome of replaying a recorded proving session with [`replay`].
+pub struct ReplayedSession<F, SC, I>
+where
+    F: PrimeField,
+    SC: StepCircuit<F>,
+    I: IVC<F, SC>,
+{
+    /// The verifier key of the session.
+    pub verifier_key: I::VerifierKey,
+    /// The origin state of the session.
+    pub origin_state: SC::State,
+    /// The state reached by the last successful step.
+    pub state: SC::State,
+    /// The proof of the last successful step, or `None` if no step succeeded.
+    pub proof: Option<I::Proof>,
+    /// The number of steps proved successfully.
+    pub number_of_steps: usize,
+    /// The error of the step following the successful ones, if the recorded run failed there.
+    pub error: Option<SangriaError>,
+}
+
+/// Reproduces the proving session recorded by a [`SessionRecorder`] in `reader`: the setup, the
+/// encoding of `step_circuit` and the recorded steps are run again with the same randomness, so the
+/// keys, states and proofs are those of the recorded run. The replay stops at the first failing
+/// step, whose error is returned in the outcome, or at the end of the recording. A trailing witness
+/// that cannot be read, as left by a crash while it was recorded, ends the recording.
+pub fn replay<F, SC, I, R>(
+    mut reader: R,
+    step_circuit: &SC,
+) -> Result<ReplayedSession<F, SC, I>, SangriaError>
+where
+    F: PrimeField,
+    SC: StepCircuit<F>,
+    SC::State: Clone + CanonicalDeserialize,
+    SC::Witness: CanonicalDeserialize,
+    I: IVC<F, SC>,
+    I::SetupInfo: CanonicalDeserialize,
+    I::Proof: Clone,
+    R: Read,
+{
+    let seed =
+        u64::deserialize_compressed(&mut reader).map_err(|_| SangriaError::SessionRecording)?;
+    let setup_info = I::SetupInfo::deserialize_compressed(&mut reader)
+        .map_err(|_| SangriaError::SessionRecording)?;
+    let origin_state = SC::State::deserialize_compressed(&mut reader)
+        .map_err(|_| SangriaError::SessionRecording)?;
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let public_parameters = I::setup(&setup_info, &mut rng)?;
+    let (prover_key, verifier_key) = I::encode(&public_parameters, step_circuit, &mut rng)?;
+
+    let mut session = ReplayedSession::<F, SC, I> {
+        verifier_key,
+        state: origin_state.clone(),
+        origin_state,
+        proof: None,
+        number_of_steps: 0,
+        error: None,
+    };
+    while let Ok(witness) = SC::Witness::deserialize_compressed(&mut reader) {
+        match I::prove_step(
+            &prover_key,
+            &session.origin_state,
+            session.state.clone(),
+            session.proof.clone(),
+            &witness,
+            &mut rng,
+        ) {
+            Ok((state, proof)) => {
+                (session.state, session.proof) = (state, Some(proof));
+                session.number_of_steps += 1;
+            }
+            Err(error) => {
+                session.error = Some(error);
+                break;
+            }
+        }
+    }
+
+    Ok(session)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{replay, SessionRecorder};
+    use crate::{
+        folding_scheme::PublicParameters,
+        ivc::{ProverKey, VerifierKey},
+        utils::{
+            circuits::Counter, inner_product::InnerProductConfig,
+            poseidon::poseidon_parameters_for_test,
+        },
+        BatchedIVCProof, Sangria, SangriaConfig, SangriaError, SetupInfo, IVC,
+    };
+    use ark_bn254::Fr;
+    use ark_std::rand::Rng;
+
+    type Batched = Sangria<InnerProductConfig>;
+    type Recorder = SessionRecorder<Fr, Counter, Scheme, Vec<u8>>;
+
+    /// Runs the batched proofs of Sangria, which does not implement [`IVC`], through the interface
+    /// the recorder drives, as their steps are much cheaper to prove than recursive ones.
+    struct Scheme;
+
+    impl IVC<Fr, Counter> for Scheme {
+        type SetupInfo = SetupInfo<Fr>;
+        type PublicParameters = PublicParameters<Fr, InnerProductConfig>;
+        type ProverKey = ProverKey<Fr, InnerProductConfig, Counter>;
+        type VerifierKey = VerifierKey<Fr, InnerProductConfig, Counter>;
+        type Proof = BatchedIVCProof<Fr, InnerProductConfig>;
+
+        fn setup<R: Rng>(
+            info: &Self::SetupInfo,
+            rng: &mut R,
+        ) -> Result<Self::PublicParameters, SangriaError> {
+            Batched::setup(info, rng)
+        }
+
+        fn encode<R: Rng>(
+            public_parameters: &Self::PublicParameters,
+            step_circuit: &Counter,
+            rng: &mut R,
+        ) -> Result<(Self::ProverKey, Self::VerifierKey), SangriaError> {
+            Batched::encode(public_parameters, step_circuit, rng)
+        }
+
+        fn prove_step<R: Rng>(
+            prover_key: &Self::ProverKey,
+            origin_state: &Fr,
+            current_state: Fr,
+            current_proof: Option<Self::Proof>,
+            current_witness: &Fr,
+            rng: &mut R,
+        ) -> Result<(Fr, Self::Proof), SangriaError> {
+            Batched::prove_step(
+                prover_key,
+                origin_state,
+                current_state,
+                current_proof,
+                current_witness,
+                rng,
+            )
+        }
+
+        fn verify(
+            verifier_key: &Self::VerifierKey,
+            origin_state: &Fr,
+            current_state: Fr,
+            current_proof: Option<Self::Proof>,
+        ) -> Result<(), SangriaError> {
+            Batched::verify(verifier_key, origin_state, current_state, current_proof)
+        }
+    }
+
+    fn setup_info() -> SetupInfo<Fr> {
+        SetupInfo {
+            number_of_public_inputs: 2,
+            number_of_gates: 2,
+            config: SangriaConfig::default(),
+            poseidon_constants: poseidon_parameters_for_test(),
+        }
+    }
+
+    /// Records a session from 5 proving the steps of `increments` in turn, ignoring their errors.
+    fn record(increments: &[u64]) -> Recorder {
+        let mut recorder =
+            Recorder::start(Vec::new(), 7, &setup_info(), &Counter, Fr::from(5u64)).unwrap();
+        for &increment in increments {
+            let _ = recorder.prove_step(&Fr::from(increment));
+        }
+
+        recorder
+    }
+
+    #[test]
+    fn replayed_sessions_reproduce_the_recorded_run() {
+        let recorder = record(&[1, 2, 0]);
+        let recorded_proof = recorder.proof().unwrap().clone();
+        assert_eq!(recorder.state(), &Fr::from(8u64));
+
+        let replayed =
+            replay::<Fr, Counter, Scheme, _>(&record(&[1, 2, 0]).into_writer()[..], &Counter)
+                .unwrap();
+        assert_eq!(replayed.number_of_steps, 2);
+        assert_eq!(replayed.error, Some(SangriaError::InvalidWitness));
+        assert_eq!(replayed.state, Fr::from(8u64));
+
+        let replayed_proof = replayed.proof.unwrap();
+        assert_eq!(recorded_proof.check_ancestor(&replayed_proof), Ok(()));
+        assert_eq!(replayed_proof.check_ancestor(&recorded_proof), Ok(()));
+        assert_eq!(
+            Scheme::verify(
+                recorder.verifier_key(),
+                &replayed.origin_state,
+                replayed.state,
+                Some(replayed_proof)
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn truncated_recordings_end_at_the_last_complete_witness() {
+        let mut recording = record(&[1, 2, 3]).into_writer();
+        recording.truncate(recording.len() - 1);
+
+        let replayed = replay::<Fr, Counter, Scheme, _>(&recording[..], &Counter).unwrap();
+        assert_eq!(replayed.number_of_steps, 2);
+        assert_eq!(replayed.error, None);
+        assert_eq!(replayed.state, Fr::from(8u64));
+
+        assert_eq!(
+            replay::<Fr, Counter, Scheme, _>(&recording[..4], &Counter).err(),
+            Some(SangriaError::SessionRecording)
+        );
+    }
+}
@@ -0,0 +1,334 @@
+//! Test vectors for the Fiat-Shamir transcript of the PLONK folding scheme, for implementations of
+//! the NIFS verifier in other languages (e.g. a Solidity verifier or a Python reference) to check
+//! their transcript against without reverse-engineering this crate. The vectors are pinned by the
+//! tests of this module, so a change to the transcript fails the tests until the vectors, and
+//! [`SPEC_VERSION`], are updated with it.
+//!
+//! # Transcript of a fold
+//!
+//! The verifier of a fold of a running instance (the left instance) with a fresh instance (the
+//! right instance) starts a transcript and absorbs, in order and without labels, separators or
+//! length prefixes:
+//!
+//! 1. the verifier key: the commitment to the q_C selector, then the transcript seed;
+//! 2. the left instance: its instance columns one after the other (each holding the public inputs
+//!    then the output row), the scaling factor, the slack commitment, the witness commitments and
+//!    the extra commitments;
+//! 3. the right instance, in the same order;
+//! 4. the commitments to the cross terms, in order of increasing power of the challenge.
+//!
+//! It then squeezes the digest of the transcript from a copy of it that also absorbs the field
+//! element of the little-endian bytes of [`crate::TRANSCRIPT_DIGEST_LABEL`], and squeezes the challenge
+//! from the transcript itself. The challenge is
+//! squeezed as an element of `challenge_bits` bits with `FieldElementSize::Truncated`, which
+//! `ark-crypto-primitives` 0.4 reads from as many bits as a full element: `MODULUS_BIT_SIZE - 1`
+//! bits, least significant bit first, reduced modulo the field (253 bits over BN254). The challenge
+//! size is not absorbed.
+//!
+//! Poseidon transcripts start from the Poseidon constants of the public parameters and absorb
+//! every value as native field elements. The challenge takes the low bits of the first element
+//! squeezed from the transcript, and the digest is the first element squeezed from the copy.
+//!
+//! Hash transcripts start from a state of 32 zero bytes and absorb every field element in its
+//! [canonical encoding](crate::fe_to_bytes_canonical). Squeezing hashes the state followed by the
+//! bytes absorbed since the last squeeze into a seed, expands it into 32-byte blocks
+//! `H(seed || 0x01 || block)`, with the block index as a little-endian `u64`, and replaces the state
+//! with `H(seed || 0x00)`. The challenge and the digest are each squeezed from the low bits of the
+//! little-endian integer of the first block squeezed from their transcript.
+//!
+//! The vectors commit with field elements, so they list the absorbed values as field elements;
+//! commitments to curve points are absorbed by hash transcripts in the encoding of their `Absorb`
+//! implementation.
+
+use ark_crypto_primitives::sponge::{
+    poseidon::{find_poseidon_ark_and_mds, PoseidonConfig},
+    Absorb,
+};
+use ark_ff::PrimeField;
+
+use crate::{FoldChallenge, Transcript};
+
+/// The version of the transcript specification the vectors follow, bumped whenever the absorbed
+/// values, their order or their encoding change.
+pub const SPEC_VERSION: u32 = 2;
+
+/// A fold recorded by the NIFS verifier: the values absorbed by its transcript and the challenge
+/// and digest squeezed from it.
+#[derive(Clone, Copy, Debug)]
+pub struct TranscriptVector {
+    /// The transcript: `"poseidon"` (with [`poseidon_constants`]), `"blake3"` or `"keccak256"`.
+    pub transcript: &'static str,
+    /// The size in bits of the challenge.
+    pub challenge_bits: usize,
+    /// The absorbed values in order, labelled, each as the field elements it is absorbed as.
+    pub absorbed: &'static [(&'static str, &'static [u64])],
+    /// The challenge, in hexadecimal canonical encoding.
+    pub challenge: &'static str,
+    /// The digest of the transcript, in hexadecimal canonical encoding.
+    pub transcript_digest: &'static str,
+}
+
+/// A fold of a running instance with a fresh instance (scaling factor one and a zero slack
+/// commitment), with one public input and one cross term.
+const FOLD: &[(&str, &[u64])] = &[
+    ("verifier key", &[37, 41]),
+    ("left instance", &[3, 5, 0, 0, 0, 8, 7, 11, 13, 17, 19]),
+    ("right instance", &[5, 9, 0, 0, 0, 0, 1, 0, 23, 29, 31]),
+    ("cross term commitments", &[43]),
+];
+
+/// The vectors over the scalar field of BN254.
+pub const BN254_VECTORS: [TranscriptVector; 3] = [
+    TranscriptVector {
+        transcript: "poseidon",
+        challenge_bits: 128,
+        absorbed: FOLD,
+        challenge: "05592361719fc362dd755f842354a32770a66d11d85f82147a3fbfba137ea614",
+        transcript_digest: "3a2155c2d027d01d3cfec9230d316a7b867fb5498be0a562479662e4ca9f2228",
+    },
+    TranscriptVector {
+        transcript: "blake3",
+        challenge_bits: 128,
+        absorbed: FOLD,
+        challenge: "c907fffa0e07cd80938945b244341e29b70de6e5e3dd752341adace2f9c36716",
+        transcript_digest: "57e1cd22992ba3d756b6572fb614ac921ae998be0a6911dcb9f2287c16815b1a",
+    },
+    TranscriptVector {
+        transcript: "keccak256",
+        challenge_bits: 128,
+        absorbed: FOLD,
+        challenge: "ba5a751da4ea9d97f041e2e5fa80114c9cd0a4f30b39314a91886a0c18bf291e",
+        transcript_digest: "6500727b46a68d1fabc1b2b49a9de09e34756dc9e4e88fbd2fc1b9941fe5b205",
+    },
+];
+
+/// Returns the Poseidon constants of the vectors: a state of three elements (rate two, capacity
+/// one), the S-box `x^5`, 8 full rounds and 57 partial rounds, with the round constants and the
+/// MDS matrix generated by the Grain LFSR of the Poseidon paper.
+pub fn poseidon_constants<F: PrimeField>() -> PoseidonConfig<F> {
+    let (full_rounds, partial_rounds) = (8, 57);
+    let (ark, mds) = find_poseidon_ark_and_mds(
+        F::MODULUS_BIT_SIZE as u64,
+        2,
+        full_rounds as u64,
+        partial_rounds as u64,
+        0,
+    );
+
+    PoseidonConfig::new(full_rounds, partial_rounds, 5, mds, ark, 2, 1)
+}
+
+/// Absorbs the values of `vector` into a transcript of type `T`, started from `poseidon_constants`,
+/// and squeezes the challenge and the digest the NIFS verifier derives from them.
+pub fn derive_challenge<F, T>(
+    vector: &TranscriptVector,
+    poseidon_constants: &PoseidonConfig<F>,
+) -> FoldChallenge<F>
+where
+    F: PrimeField + Absorb,
+    T: Transcript<F>,
+{
+    let mut transcript = T::start(poseidon_constants);
+    for (_, values) in vector.absorbed {
+        let elements: Vec<F> = values.iter().map(|&value| F::from(value)).collect();
+        transcript.absorb(&elements);
+    }
+
+    FoldChallenge::squeeze(&mut transcript, vector.challenge_bits, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{derive_challenge, poseidon_constants, TranscriptVector, BN254_VECTORS};
+    use crate::{
+        fe_to_bytes_canonical,
+        folding_scheme::{PLONKFoldingScheme, PublicParameters, SetupInfo, VerifierKey},
+        utils::inner_product::InnerProductConfig,
+        Blake3Transcript, InstanceSchema, KeccakTranscript, NonInteractiveFoldingScheme,
+        RelaxedPLONKInstance, SangriaConfig, Transcript, TRANSCRIPT_DIGEST_LABEL,
+    };
+    use ark_bn254::Fr;
+    use ark_crypto_primitives::sponge::poseidon::PoseidonSponge;
+    use ark_ff::PrimeField;
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+    use ark_std::test_rng;
+    use sha3::{Digest, Keccak256};
+
+    fn hex(element: Fr) -> String {
+        fe_to_bytes_canonical(&element)
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+
+    fn elements(values: &[u64]) -> Vec<Fr> {
+        values.iter().map(|&value| Fr::from(value)).collect()
+    }
+
+    /// Reads an instance from its instance columns, scaling factor, slack commitment and witness
+    /// commitments, in the layout of [`InstanceSchema`].
+    fn instance(
+        columns: &[&[u64]],
+        scaling_factor: u64,
+        slack: u64,
+        witness: &[u64],
+    ) -> RelaxedPLONKInstance<Fr, InnerProductConfig> {
+        let columns: Vec<Vec<Fr>> = columns.iter().map(|column| elements(column)).collect();
+        let mut bytes = Vec::new();
+        columns.serialize_compressed(&mut bytes).unwrap();
+        [scaling_factor, slack]
+            .map(Fr::from)
+            .serialize_compressed(&mut bytes)
+            .unwrap();
+        (elements(witness), Vec::<Fr>::new())
+            .serialize_compressed(&mut bytes)
+            .unwrap();
+
+        RelaxedPLONKInstance::deserialize_compressed(&bytes[..]).unwrap()
+    }
+
+    type Scheme<T> = PLONKFoldingScheme<Fr, InnerProductConfig, T>;
+
+    /// The public parameters, verifier key, instances and cross terms of the fold of the vectors.
+    #[allow(clippy::type_complexity)]
+    fn fold<T: Transcript<Fr>>(
+        challenge_bits: usize,
+    ) -> (
+        PublicParameters<Fr, InnerProductConfig>,
+        VerifierKey<Fr, InnerProductConfig>,
+        [RelaxedPLONKInstance<Fr, InnerProductConfig>; 2],
+        Vec<Fr>,
+    ) {
+        let info = SetupInfo {
+            number_of_public_inputs: 1,
+            number_of_gates: 2,
+            config: SangriaConfig {
+                challenge_bits,
+                ..Default::default()
+            },
+            poseidon_constants: poseidon_constants(),
+        };
+        let verifier_key = VerifierKey {
+            selector_c_commitment: Fr::from(37u64),
+            transcript_seed: Fr::from(41u64),
+            instance_schema: InstanceSchema {
+                number_of_public_inputs: 1,
+                instance_columns: 3,
+                instance_rows: 2,
+                witness_commitments: 3,
+                extra_commitments: 0,
+                cross_term_commitments: 1,
+            },
+            number_of_rows: 4,
+            gate_degree: 2,
+            circuit_digest: [0; 32],
+            transcript_config_digest: [0; 32],
+        };
+
+        (
+            Scheme::<T>::setup(&info, &mut test_rng()).unwrap(),
+            verifier_key,
+            [
+                instance(&[&[3, 5], &[0, 0], &[0, 8]], 7, 11, &[13, 17, 19]),
+                instance(&[&[5, 9], &[0, 0], &[0, 0]], 1, 0, &[23, 29, 31]),
+            ],
+            vec![Fr::from(43u64)],
+        )
+    }
+
+    /// Checks that the NIFS verifier over the transcript `T` folds the instances of the vectors
+    /// with the challenge of `vector`.
+    fn check_vector<T: Transcript<Fr>>(vector: &TranscriptVector) {
+        let (public_parameters, verifier_key, [left, right], cross_terms) =
+            fold::<T>(vector.challenge_bits);
+
+        let challenge = derive_challenge::<Fr, T>(vector, &poseidon_constants());
+        assert_eq!(hex(challenge.value()), vector.challenge);
+        assert_eq!(hex(challenge.transcript_digest()), vector.transcript_digest);
+
+        let folded = Scheme::<T>::verifier(
+            &public_parameters,
+            &verifier_key,
+            &left,
+            &right,
+            &cross_terms,
+        )
+        .unwrap();
+        assert!(folded == left.fold(&right, &cross_terms, challenge).unwrap());
+    }
+
+    #[test]
+    fn bn254_vectors_match_the_verifier() {
+        let [poseidon, blake3, keccak] = &BN254_VECTORS;
+        check_vector::<PoseidonSponge<Fr>>(poseidon);
+        check_vector::<Blake3Transcript>(blake3);
+        check_vector::<KeccakTranscript>(keccak);
+    }
+
+    /// Derives the challenge and the digest of `vector` over a hash transcript hashed with `hash`,
+    /// following the description of the module rather than [`crate::HashTranscript`].
+    fn hash_transcript(vector: &TranscriptVector, hash: fn(&[u8]) -> [u8; 32]) -> [String; 2] {
+        let absorbed: Vec<u8> = vector
+            .absorbed
+            .iter()
+            .flat_map(|(_, values)| elements(values))
+            .flat_map(|element| fe_to_bytes_canonical(&element))
+            .collect();
+
+        // both are squeezed first from a transcript in its initial state
+        let squeeze = |absorbed: &[u8]| {
+            let seed = hash(&[&[0u8; 32][..], absorbed].concat());
+            let mut block = hash(&[&seed[..], &[1], &0u64.to_le_bytes()].concat());
+            // keep the low 253 bits
+            block[31] &= 0x1f;
+            hex(Fr::from_le_bytes_mod_order(&block))
+        };
+
+        // the digest is squeezed from a copy of the transcript that absorbs the label
+        let label = fe_to_bytes_canonical(&Fr::from_le_bytes_mod_order(TRANSCRIPT_DIGEST_LABEL));
+        [
+            squeeze(&absorbed),
+            squeeze(&[&absorbed[..], &label].concat()),
+        ]
+    }
+
+    #[test]
+    fn hash_vectors_follow_the_specification() {
+        let [_, blake3, keccak] = &BN254_VECTORS;
+        let keccak256 = |input: &[u8]| -> [u8; 32] { Keccak256::digest(input).into() };
+
+        assert_eq!(
+            hash_transcript(blake3, |input| *blake3::hash(input).as_bytes()),
+            [blake3.challenge, blake3.transcript_digest]
+        );
+        assert_eq!(
+            hash_transcript(keccak, keccak256),
+            [keccak.challenge, keccak.transcript_digest]
+        );
+    }
+
+    #[test]
+    fn vectors_list_the_absorbed_values() {
+        let (public_parameters, verifier_key, [left, right], cross_terms) =
+            fold::<PoseidonSponge<Fr>>(BN254_VECTORS[0].challenge_bits);
+        let audit = Scheme::<PoseidonSponge<Fr>>::verifier_with_audit(
+            &public_parameters,
+            &verifier_key,
+            &left,
+            &right,
+            &cross_terms,
+            0,
+        )
+        .unwrap();
+
+        for vector in BN254_VECTORS {
+            let absorbed: Vec<(&str, Vec<Fr>)> = vector
+                .absorbed
+                .iter()
+                .map(|&(label, values)| (label, elements(values)))
+                .collect();
+            assert_eq!(audit.absorbed, absorbed);
+        }
+        assert_eq!(hex(audit.challenge), BN254_VECTORS[0].challenge);
+    }
+}
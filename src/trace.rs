@@ -0,0 +1,767 @@
+use ark_ff::PrimeField;
+use ark_std::rand::Rng;
+
+use crate::{
+    relaxed_plonk::NUMBER_OF_WIRES, PLONKCircuit, RelaxedPLONKWitness, SangriaError,
+    CONSTANT_SELECTOR_INDEX, LEFT_SELECTOR_INDEX, MULTIPLICATION_SELECTOR_INDEX,
+    OUTPUT_SELECTOR_INDEX, POWER_SELECTOR_INDEX, RIGHT_SELECTOR_INDEX,
+};
+
+/// The number of selector columns of a circuit.
+pub(crate) const NUMBER_OF_SELECTORS: usize = 6;
+
+/// The role of a row of a trace table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RowTag<F: PrimeField> {
+    /// A public input row. Its left wire holds the public input.
+    PublicInput,
+    /// A gate row with its selectors, indexed by the `*_SELECTOR_INDEX` constants.
+    Gate([F; NUMBER_OF_SELECTORS]),
+    /// The output row checking the final output, held by its left wire.
+    Output,
+}
+
+impl<F: PrimeField> RowTag<F> {
+    /// Returns the selectors of the row. Public input and output rows enable no selector: their
+    /// wires are supplied by the instance and tied to the gates by copy constraints.
+    fn selectors(&self) -> [F; NUMBER_OF_SELECTORS] {
+        match self {
+            Self::Gate(selectors) => *selectors,
+            Self::PublicInput | Self::Output => [F::zero(); NUMBER_OF_SELECTORS],
+        }
+    }
+}
+
+/// The savings of [`TraceTable::optimize`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OptimizationReport {
+    /// The number of gate rows before the pass.
+    pub gates_before: usize,
+    /// The number of gate rows removed.
+    pub removed_gates: usize,
+    /// The number of gate wires replaced by the constant they were copied from.
+    pub folded_constants: usize,
+}
+
+/// An execution trace laid out as a table of wire columns, one tagged row per public input, per gate
+/// and for the output, in this order. It lets AIR-style trace generators produce a circuit and its
+/// witness without going through a gate-by-gate builder.
+pub struct TraceTable<F: PrimeField> {
+    columns: Vec<Vec<F>>,
+    tags: Vec<RowTag<F>>,
+    copies: Vec<((usize, usize), (usize, usize))>,
+}
+
+impl<F: PrimeField> TraceTable<F> {
+    /// Creates an empty trace table.
+    pub fn new() -> Self {
+        Self {
+            columns: vec![Vec::new(); NUMBER_OF_WIRES],
+            tags: Vec::new(),
+            copies: Vec::new(),
+        }
+    }
+
+    /// Appends a row with its left, right and output wires.
+    pub fn push_row(&mut self, tag: RowTag<F>, wires: [F; NUMBER_OF_WIRES]) {
+        for (column, wire) in self.columns.iter_mut().zip(wires) {
+            column.push(wire);
+        }
+        self.tags.push(tag);
+    }
+
+    /// Constrains the wire at `from` to be equal to the wire at `to`, both given as (column, row).
+    pub fn copy(&mut self, from: (usize, usize), to: (usize, usize)) {
+        self.copies.push((from, to));
+    }
+
+    /// Computes non-deterministic advice, such as an inverse, a quotient or a decomposition, by
+    /// running `hint` on the values of the wires `inputs`, given as (column, row). The advice is laid
+    /// out three values per row in new gate rows enabling no selector, and the wires holding it are
+    /// returned in order. The advice is unconstrained: the gadget requesting it must constrain it,
+    /// typically by copying its wires into gates checking it.
+    pub fn hint<H>(
+        &mut self,
+        inputs: &[(usize, usize)],
+        hint: H,
+    ) -> Result<Vec<(usize, usize)>, SangriaError>
+    where
+        H: FnOnce(&[F]) -> Result<Vec<F>, SangriaError>,
+    {
+        let values = inputs
+            .iter()
+            .map(|&(column, row)| self.wire(column, row))
+            .collect::<Result<Vec<_>, _>>()?;
+        let advice = hint(&values)?;
+
+        let mut wires = Vec::with_capacity(advice.len());
+        for chunk in advice.chunks(NUMBER_OF_WIRES) {
+            let row = self.number_of_rows();
+            let mut row_wires = [F::zero(); NUMBER_OF_WIRES];
+            row_wires[..chunk.len()].copy_from_slice(chunk);
+            self.push_row(RowTag::Gate([F::zero(); NUMBER_OF_SELECTORS]), row_wires);
+            wires.extend((0..chunk.len()).map(|column| (column, row)));
+        }
+
+        Ok(wires)
+    }
+
+    /// Returns the number of rows of the trace.
+    pub fn number_of_rows(&self) -> usize {
+        self.tags.len()
+    }
+
+    /// Returns the tags of the rows, in order.
+    pub(crate) fn tags(&self) -> &[RowTag<F>] {
+        &self.tags
+    }
+
+    /// Returns the number of copy constraints added to the trace.
+    pub(crate) fn number_of_copies(&self) -> usize {
+        self.copies.len()
+    }
+
+    /// Returns the public inputs of the trace, read from the left wire of the public input rows.
+    pub fn public_inputs(&self) -> Vec<F> {
+        self.tags
+            .iter()
+            .zip(self.columns[0].iter())
+            .filter(|(tag, _)| matches!(tag, RowTag::PublicInput))
+            .map(|(_, &value)| value)
+            .collect()
+    }
+
+    /// Returns the value of the wire at (column, row) or an error if it is outside the trace.
+    pub fn wire(&self, column: usize, row: usize) -> Result<F, SangriaError> {
+        self.columns
+            .get(column)
+            .and_then(|column| column.get(row))
+            .copied()
+            .ok_or(SangriaError::IndexOutOfBounds)
+    }
+
+    /// Sets the public input held by the left wire of the public input row `row`, for circuits
+    /// whose public inputs are computed by gates laid out after them. Returns an error if `row` is
+    /// not a public input row.
+    pub(crate) fn set_public_input(&mut self, row: usize, value: F) -> Result<(), SangriaError> {
+        if !matches!(self.tags.get(row), Some(RowTag::PublicInput)) {
+            return Err(SangriaError::IndexOutOfBounds);
+        }
+        self.columns[0][row] = value;
+
+        Ok(())
+    }
+
+    /// Returns the public input and output rows, column by column, as held by the instance of the
+    /// trace.
+    pub fn instance_columns(&self) -> Vec<Vec<F>> {
+        self.columns
+            .iter()
+            .map(|column| {
+                column
+                    .iter()
+                    .zip(self.tags.iter())
+                    .filter(|(_, tag)| !matches!(tag, RowTag::Gate(_)))
+                    .map(|(&value, _)| value)
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Builds the circuit of the trace with the given fixed columns, ignoring the values of the
+    /// wires. Returns an error if the rows are not laid out as public inputs, gates and the output
+    /// row, or if a copy constraint points outside the trace.
+    pub fn circuit(&self, fixed_columns: Vec<Vec<F>>) -> Result<PLONKCircuit<F>, SangriaError> {
+        self.check_layout()?;
+        let copy_constraint = self.copy_constraint()?;
+
+        let selectors = (0..NUMBER_OF_SELECTORS)
+            .map(|selector_index| {
+                self.tags
+                    .iter()
+                    .map(|tag| tag.selectors()[selector_index])
+                    .collect()
+            })
+            .collect();
+
+        Ok(PLONKCircuit::new(selectors, fixed_columns, copy_constraint))
+    }
+
+    /// Converts the trace into a circuit with the given fixed columns and a fresh relaxed witness
+    /// holding the gate rows, with a zero slack vector and commitment randomness sampled from `rng`.
+    /// Returns an error if the rows are not laid out as public inputs, gates and the output row, if
+    /// a copy constraint points outside the trace or if the copied wires differ.
+    pub fn into_circuit_and_witness<R: Rng>(
+        self,
+        fixed_columns: Vec<Vec<F>>,
+        rng: &mut R,
+    ) -> Result<(PLONKCircuit<F>, RelaxedPLONKWitness<F>), SangriaError> {
+        let circuit = self.circuit(fixed_columns)?;
+        self.check_copies()?;
+        let gate_rows = self.check_layout()?;
+
+        let number_of_rows = self.number_of_rows();
+        let witness_columns = self
+            .columns
+            .into_iter()
+            .map(|column| column[gate_rows.clone()].to_vec())
+            .collect();
+        let commitment_hidings = (0..NUMBER_OF_WIRES).map(|_| F::rand(rng)).collect();
+
+        Ok((
+            circuit,
+            RelaxedPLONKWitness::new(
+                witness_columns,
+                vec![F::zero(); number_of_rows],
+                commitment_hidings,
+            ),
+        ))
+    }
+
+    /// Removes the gates that constrain nothing and folds constants into the gates using them,
+    /// repeating both until neither applies, and returns the savings. A gate defining a constant
+    /// (a single wire with a linear selector, and the constant selector) lets every other gate with
+    /// a wire copied from that wire use the constant through its selectors instead, the wire no
+    /// longer being copied. A gate is removed if it enables no selector and none of its wires is
+    /// copied, or if a wire copied nowhere appears in its equation with a constant non-zero
+    /// coefficient (e.g. an output nobody uses), since any values of its other wires then satisfy it.
+    ///
+    /// The pass only reads the selectors and the copy constraints, so every trace of a circuit is
+    /// optimized into a trace of the same circuit. Gate rows are renumbered: wires and fixed columns
+    /// addressed by row before the pass must be addressed anew. Returns an error if the rows are not
+    /// laid out as public inputs, gates and the output row, or if a copy constraint points outside
+    /// the trace.
+    pub fn optimize(&mut self) -> Result<OptimizationReport, SangriaError> {
+        let gate_rows = self.check_layout()?;
+        let number_of_rows = self.number_of_rows();
+        let size = NUMBER_OF_WIRES * number_of_rows;
+        let position = |column: usize, row: usize| column * number_of_rows + row;
+
+        // every wire position is labelled with its class of copied wires, a wire no longer copied
+        // being moved to a class of its own
+        let mut class_of = self.copy_classes()?;
+        let mut next_class = size;
+        let mut removed = vec![false; number_of_rows];
+        let mut report = OptimizationReport {
+            gates_before: gate_rows.len(),
+            ..OptimizationReport::default()
+        };
+
+        loop {
+            let mut class_sizes = vec![0usize; next_class];
+            for row in (0..number_of_rows).filter(|&row| !removed[row]) {
+                for column in 0..NUMBER_OF_WIRES {
+                    class_sizes[class_of[position(column, row)]] += 1;
+                }
+            }
+
+            let mut constants: Vec<Option<(usize, F)>> = vec![None; next_class];
+            for row in gate_rows.clone().filter(|&row| !removed[row]) {
+                if let Some((column, constant)) = defined_constant(&self.tags[row].selectors()) {
+                    constants[class_of[position(column, row)]].get_or_insert((row, constant));
+                }
+            }
+
+            let mut changed = false;
+            for row in gate_rows.clone().filter(|&row| !removed[row]) {
+                for column in 0..NUMBER_OF_WIRES {
+                    let wire = position(column, row);
+                    let Some((defining_row, constant)) = constants[class_of[wire]] else {
+                        continue;
+                    };
+                    let RowTag::Gate(selectors) = &mut self.tags[row] else {
+                        continue;
+                    };
+                    if defining_row == row || !fold_constant(selectors, column, constant) {
+                        continue;
+                    }
+
+                    class_sizes[class_of[wire]] -= 1;
+                    class_of[wire] = next_class;
+                    class_sizes.push(1);
+                    constants.push(None);
+                    next_class += 1;
+                    report.folded_constants += 1;
+                    changed = true;
+                }
+            }
+
+            for row in gate_rows.clone() {
+                if removed[row] {
+                    continue;
+                }
+                let copied: Vec<bool> = (0..NUMBER_OF_WIRES)
+                    .map(|column| class_sizes[class_of[position(column, row)]] > 1)
+                    .collect();
+                if !constrains_nothing(&self.tags[row].selectors(), &copied) {
+                    continue;
+                }
+
+                for column in 0..NUMBER_OF_WIRES {
+                    class_sizes[class_of[position(column, row)]] -= 1;
+                }
+                removed[row] = true;
+                report.removed_gates += 1;
+                changed = true;
+            }
+
+            if !changed {
+                break;
+            }
+        }
+
+        let kept_rows: Vec<usize> = (0..number_of_rows).filter(|&row| !removed[row]).collect();
+        let mut renumbered = vec![None; number_of_rows];
+        for (index, &row) in kept_rows.iter().enumerate() {
+            renumbered[row] = Some(index);
+        }
+        let mut classes: Vec<Vec<(usize, usize)>> = vec![Vec::new(); next_class];
+        for column in 0..NUMBER_OF_WIRES {
+            for &row in kept_rows.iter() {
+                let kept_row = renumbered[row].expect("the row is kept");
+                classes[class_of[position(column, row)]].push((column, kept_row));
+            }
+        }
+
+        self.columns = self
+            .columns
+            .iter()
+            .map(|column| kept_rows.iter().map(|&row| column[row]).collect())
+            .collect();
+        self.tags = kept_rows.iter().map(|&row| self.tags[row]).collect();
+        self.copies = classes
+            .iter()
+            .flat_map(|class| class.windows(2).map(|pair| (pair[0], pair[1])))
+            .collect();
+
+        Ok(report)
+    }
+
+    /// Labels every wire position `column * rows + row` with the smallest position it is copied
+    /// with, directly or not, or returns an error if a copy constraint points outside the trace.
+    fn copy_classes(&self) -> Result<Vec<usize>, SangriaError> {
+        let number_of_rows = self.number_of_rows();
+        let size = NUMBER_OF_WIRES * number_of_rows;
+        let mut neighbours = vec![Vec::new(); size];
+        for &(from, to) in self.copies.iter() {
+            let [from, to] = [from, to].map(|(column, row)| {
+                if column >= NUMBER_OF_WIRES || row >= number_of_rows {
+                    return Err(SangriaError::IndexOutOfBounds);
+                }
+                Ok(column * number_of_rows + row)
+            });
+            let (from, to) = (from?, to?);
+            neighbours[from].push(to);
+            neighbours[to].push(from);
+        }
+
+        let mut class_of: Vec<Option<usize>> = vec![None; size];
+        for start in 0..size {
+            if class_of[start].is_some() {
+                continue;
+            }
+            class_of[start] = Some(start);
+            let mut stack = vec![start];
+            while let Some(position) = stack.pop() {
+                for &next in neighbours[position].iter() {
+                    if class_of[next].is_none() {
+                        class_of[next] = Some(start);
+                        stack.push(next);
+                    }
+                }
+            }
+        }
+
+        Ok(class_of
+            .into_iter()
+            .map(|class| class.expect("every position is labelled"))
+            .collect())
+    }
+
+    /// Checks that the public input rows come first, then the gates, then a single output row, and
+    /// returns the range of the gate rows.
+    fn check_layout(&self) -> Result<std::ops::Range<usize>, SangriaError> {
+        let first_gate = self
+            .tags
+            .iter()
+            .position(|tag| !matches!(tag, RowTag::PublicInput))
+            .unwrap_or(self.tags.len());
+        let output = self.tags.len().saturating_sub(1);
+
+        if !matches!(self.tags.last(), Some(RowTag::Output)) {
+            return Err(SangriaError::InvalidTrace(
+                "the last row must be the output row",
+            ));
+        }
+        if !self.tags[first_gate..output]
+            .iter()
+            .all(|tag| matches!(tag, RowTag::Gate(_)))
+        {
+            return Err(SangriaError::InvalidTrace(
+                "gate rows must follow the public inputs and precede the output row",
+            ));
+        }
+
+        Ok(first_gate..output)
+    }
+
+    /// Checks that every pair of copied wires holds the same value.
+    pub(crate) fn check_copies(&self) -> Result<(), SangriaError> {
+        for &(from, to) in self.copies.iter() {
+            if self.wire(from.0, from.1)? != self.wire(to.0, to.1)? {
+                return Err(SangriaError::InvalidTrace(
+                    "copied wires hold different values",
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Builds the permutation of the wire positions, position `column * rows + row` being mapped to
+    /// the next position in its cycle of copied wires.
+    fn copy_constraint(&self) -> Result<Vec<F>, SangriaError> {
+        let number_of_rows = self.number_of_rows();
+        let position = |(column, row): (usize, usize)| -> Result<usize, SangriaError> {
+            if column >= NUMBER_OF_WIRES || row >= number_of_rows {
+                return Err(SangriaError::IndexOutOfBounds);
+            }
+            Ok(column * number_of_rows + row)
+        };
+
+        let size = NUMBER_OF_WIRES * number_of_rows;
+        let mut cycle_of: Vec<usize> = (0..size).collect();
+        for &(from, to) in self.copies.iter() {
+            let (left, right) = (position(from)?, position(to)?);
+
+            let (left_cycle, right_cycle) = (cycle_of[left], cycle_of[right]);
+            if left_cycle == right_cycle {
+                continue;
+            }
+
+            cycle_of
+                .iter_mut()
+                .filter(|cycle| **cycle == right_cycle)
+                .for_each(|cycle| *cycle = left_cycle);
+        }
+
+        // every cycle visits its positions in increasing order, so that the circuit only depends on
+        // which wires are copied into one another and not on the order the copies were declared in
+        let mut cycles = vec![Vec::new(); size];
+        for (position, &cycle) in cycle_of.iter().enumerate() {
+            cycles[cycle].push(position);
+        }
+        let mut permutation: Vec<usize> = (0..size).collect();
+        for cycle in cycles.iter().filter(|cycle| cycle.len() > 1) {
+            for (index, &position) in cycle.iter().enumerate() {
+                permutation[position] = cycle[(index + 1) % cycle.len()];
+            }
+        }
+
+        Ok(permutation
+            .into_iter()
+            .map(|position| F::from(position as u64))
+            .collect())
+    }
+}
+
+/// Returns the wire and the value of the constant defined by a gate, if the gate enables a single
+/// linear selector and possibly the constant selector.
+fn defined_constant<F: PrimeField>(selectors: &[F; NUMBER_OF_SELECTORS]) -> Option<(usize, F)> {
+    if !selectors[MULTIPLICATION_SELECTOR_INDEX].is_zero()
+        || !selectors[POWER_SELECTOR_INDEX].is_zero()
+    {
+        return None;
+    }
+
+    let mut linear = [
+        LEFT_SELECTOR_INDEX,
+        RIGHT_SELECTOR_INDEX,
+        OUTPUT_SELECTOR_INDEX,
+    ]
+    .into_iter()
+    .enumerate()
+    .filter(|&(_, index)| !selectors[index].is_zero());
+    match (linear.next(), linear.next()) {
+        (Some((column, index)), None) => Some((
+            column,
+            -selectors[CONSTANT_SELECTOR_INDEX]
+                * selectors[index]
+                    .inverse()
+                    .expect("the selector is not zero"),
+        )),
+        _ => None,
+    }
+}
+
+/// Rewrites the selectors of a gate whose wire in `column` holds `constant` so that the gate no
+/// longer reads the wire. Returns whether it did, which it does not if the gate does not read the
+/// wire or raises it to a power, the degree of the power gate being unknown to the trace.
+fn fold_constant<F: PrimeField>(
+    selectors: &mut [F; NUMBER_OF_SELECTORS],
+    column: usize,
+    constant: F,
+) -> bool {
+    let multiplication = selectors[MULTIPLICATION_SELECTOR_INDEX];
+    let (linear, other) = match column {
+        0 if selectors[POWER_SELECTOR_INDEX].is_zero() => {
+            (LEFT_SELECTOR_INDEX, Some(RIGHT_SELECTOR_INDEX))
+        }
+        1 => (RIGHT_SELECTOR_INDEX, Some(LEFT_SELECTOR_INDEX)),
+        2 => (OUTPUT_SELECTOR_INDEX, None),
+        _ => return false,
+    };
+    if selectors[linear].is_zero() && (other.is_none() || multiplication.is_zero()) {
+        return false;
+    }
+
+    selectors[CONSTANT_SELECTOR_INDEX] += selectors[linear] * constant;
+    selectors[linear] = F::zero();
+    if let Some(other) = other {
+        selectors[other] += multiplication * constant;
+        selectors[MULTIPLICATION_SELECTOR_INDEX] = F::zero();
+    }
+
+    true
+}
+
+/// Returns whether a gate constrains none of its wires, given which of them are copied: it enables
+/// no selector and copies no wire, or one of its wires is copied nowhere and appears with a
+/// constant non-zero coefficient, so that it can be solved for whatever the other wires hold.
+fn constrains_nothing<F: PrimeField>(
+    selectors: &[F; NUMBER_OF_SELECTORS],
+    copied: &[bool],
+) -> bool {
+    let selector = |index: usize| !selectors[index].is_zero();
+    let linear_in_left =
+        !selector(MULTIPLICATION_SELECTOR_INDEX) && !selector(POWER_SELECTOR_INDEX);
+    let linear_in_right = !selector(MULTIPLICATION_SELECTOR_INDEX);
+
+    (selectors.iter().all(F::is_zero) && !copied.contains(&true))
+        || (!copied[0] && linear_in_left && selector(LEFT_SELECTOR_INDEX))
+        || (!copied[1] && linear_in_right && selector(RIGHT_SELECTOR_INDEX))
+        || (!copied[2] && selector(OUTPUT_SELECTOR_INDEX))
+}
+
+impl<F: PrimeField> Default for TraceTable<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OptimizationReport, RowTag, TraceTable, NUMBER_OF_SELECTORS};
+    use crate::{
+        utils::poseidon::poseidon_parameters_for_test, SangriaError, CONSTANT_SELECTOR_INDEX,
+        LEFT_SELECTOR_INDEX, MULTIPLICATION_SELECTOR_INDEX, OUTPUT_SELECTOR_INDEX,
+        RIGHT_SELECTOR_INDEX,
+    };
+    use ark_bn254::Fr;
+    use ark_crypto_primitives::sponge::{poseidon::PoseidonSponge, CryptographicSponge};
+    use ark_ff::{One, PrimeField, Zero};
+    use ark_std::test_rng;
+
+    /// Returns the selectors of a gate with the given q_L, q_R, q_O, q_M and q_C.
+    fn gate<F: PrimeField>(q_l: F, q_r: F, q_o: F, q_m: F, q_c: F) -> RowTag<F> {
+        let mut selectors = [F::zero(); NUMBER_OF_SELECTORS];
+        selectors[LEFT_SELECTOR_INDEX] = q_l;
+        selectors[RIGHT_SELECTOR_INDEX] = q_r;
+        selectors[OUTPUT_SELECTOR_INDEX] = q_o;
+        selectors[MULTIPLICATION_SELECTOR_INDEX] = q_m;
+        selectors[CONSTANT_SELECTOR_INDEX] = q_c;
+        RowTag::Gate(selectors)
+    }
+
+    /// The trace of `y = x^2 + 5` for `x = 3`.
+    fn square_plus_five() -> TraceTable<Fr> {
+        let (x, x2, y) = (Fr::from(3u64), Fr::from(9u64), Fr::from(14u64));
+        let (zero, one) = (Fr::zero(), Fr::one());
+
+        let mut trace = TraceTable::new();
+        trace.push_row(RowTag::PublicInput, [x, zero, zero]);
+        trace.push_row(gate(zero, zero, -one, one, zero), [x, x, x2]);
+        trace.push_row(gate(one, zero, -one, zero, Fr::from(5u64)), [x2, zero, y]);
+        trace.push_row(RowTag::Output, [y, zero, zero]);
+        trace.copy((0, 0), (0, 1));
+        trace.copy((0, 1), (1, 1));
+        trace.copy((2, 1), (0, 2));
+        trace.copy((2, 2), (0, 3));
+        trace
+    }
+
+    #[test]
+    fn trace_converts_to_satisfied_circuit() {
+        let trace = square_plus_five();
+        assert_eq!(trace.public_inputs(), vec![Fr::from(3u64)]);
+        assert_eq!(
+            trace.instance_columns()[0],
+            vec![Fr::from(3u64), Fr::from(14u64)]
+        );
+
+        let (circuit, witness) = trace
+            .into_circuit_and_witness(Vec::new(), &mut test_rng())
+            .unwrap();
+
+        assert_eq!(circuit.number_of_rows(), 4);
+        assert_eq!(witness.slack_vector(), vec![Fr::zero(); 4]);
+        assert_eq!(witness.hiding_randomnesses().len(), 3);
+
+        let selectors = circuit.selectors();
+        for gate_index in 0..2 {
+            let row = gate_index + 1;
+            let wires = witness.witness_row(gate_index).unwrap();
+            let value = selectors[LEFT_SELECTOR_INDEX][row] * wires[0]
+                + selectors[RIGHT_SELECTOR_INDEX][row] * wires[1]
+                + selectors[OUTPUT_SELECTOR_INDEX][row] * wires[2]
+                + selectors[MULTIPLICATION_SELECTOR_INDEX][row] * wires[0] * wires[1]
+                + selectors[CONSTANT_SELECTOR_INDEX][row];
+            assert!(value.is_zero());
+        }
+
+        // x is copied from the public input into both inputs of the first gate
+        let permutation = circuit.copy_constraint();
+        let x_cycle = [0u64, 1, 5];
+        for position in x_cycle {
+            assert!(x_cycle
+                .iter()
+                .any(|&next| permutation[position as usize] == Fr::from(next)));
+            assert_ne!(permutation[position as usize], Fr::from(position));
+        }
+        assert_eq!(permutation[4], Fr::from(4u64));
+    }
+
+    #[test]
+    fn rebuilt_circuits_have_identical_digests() {
+        let digest = |trace: &TraceTable<Fr>| {
+            let mut sponge = PoseidonSponge::<Fr>::new(&poseidon_parameters_for_test());
+            sponge.absorb(&trace.circuit(Vec::new()).unwrap());
+            sponge.squeeze_field_elements::<Fr>(1)[0]
+        };
+
+        // `square_plus_five` with its copies declared in reverse order and direction
+        let (x, x2, y) = (Fr::from(3u64), Fr::from(9u64), Fr::from(14u64));
+        let (zero, one) = (Fr::zero(), Fr::one());
+        let mut rebuilt = TraceTable::new();
+        rebuilt.push_row(RowTag::PublicInput, [x, zero, zero]);
+        rebuilt.push_row(gate(zero, zero, -one, one, zero), [x, x, x2]);
+        rebuilt.push_row(gate(one, zero, -one, zero, Fr::from(5u64)), [x2, zero, y]);
+        rebuilt.push_row(RowTag::Output, [y, zero, zero]);
+        rebuilt.copy((0, 3), (2, 2));
+        rebuilt.copy((0, 2), (2, 1));
+        rebuilt.copy((1, 1), (0, 1));
+        rebuilt.copy((1, 1), (0, 0));
+
+        assert_eq!(digest(&rebuilt), digest(&square_plus_five()));
+        assert_eq!(
+            rebuilt.circuit(Vec::new()),
+            square_plus_five().circuit(Vec::new())
+        );
+
+        // the cycle of x visits its wires in increasing order
+        let permutation = rebuilt.circuit(Vec::new()).unwrap().copy_constraint();
+        assert_eq!(
+            [0, 1, 5].map(|position| permutation[position]),
+            [1u64, 5, 0].map(Fr::from)
+        );
+    }
+
+    #[test]
+    fn misplaced_output_row_is_rejected() {
+        let mut trace = square_plus_five();
+        trace.push_row(RowTag::PublicInput, [Fr::one(), Fr::zero(), Fr::zero()]);
+
+        assert!(matches!(
+            trace.into_circuit_and_witness(Vec::new(), &mut test_rng()),
+            Err(SangriaError::InvalidTrace(_))
+        ));
+    }
+
+    #[test]
+    fn inconsistent_copy_is_rejected() {
+        let mut trace = square_plus_five();
+        trace.copy((0, 0), (2, 2));
+
+        assert!(matches!(
+            trace.into_circuit_and_witness(Vec::new(), &mut test_rng()),
+            Err(SangriaError::InvalidTrace(_))
+        ));
+
+        let mut trace = square_plus_five();
+        trace.copy((0, 0), (3, 0));
+
+        assert!(matches!(
+            trace.into_circuit_and_witness(Vec::new(), &mut test_rng()),
+            Err(SangriaError::IndexOutOfBounds)
+        ));
+    }
+
+    /// The trace of `y = x^2 + 5` with the constant 5 defined by its own gate and a dead gate
+    /// squaring `x` again.
+    fn square_plus_five_unoptimized(x: u64) -> TraceTable<Fr> {
+        let (x, zero, one, five) = (Fr::from(x), Fr::zero(), Fr::one(), Fr::from(5u64));
+
+        let mut trace = TraceTable::new();
+        trace.push_row(RowTag::PublicInput, [x, zero, zero]);
+        trace.push_row(gate(zero, zero, -one, zero, five), [zero, zero, five]);
+        trace.push_row(gate(zero, zero, -one, one, zero), [x, x, x * x]);
+        trace.push_row(gate(zero, zero, -one, one, zero), [x, x, x * x]);
+        trace.push_row(
+            gate(one, one, -one, zero, zero),
+            [x * x, five, x * x + five],
+        );
+        trace.push_row(RowTag::Output, [x * x + five, zero, zero]);
+        trace.copy((0, 0), (0, 2));
+        trace.copy((0, 2), (1, 2));
+        trace.copy((0, 0), (0, 3));
+        trace.copy((0, 0), (1, 3));
+        trace.copy((2, 2), (0, 4));
+        trace.copy((2, 1), (1, 4));
+        trace.copy((2, 4), (0, 5));
+        trace
+    }
+
+    #[test]
+    fn optimization_folds_constants_and_removes_dead_gates() {
+        let mut trace = square_plus_five_unoptimized(3);
+
+        assert_eq!(
+            trace.optimize(),
+            Ok(OptimizationReport {
+                gates_before: 4,
+                removed_gates: 2,
+                folded_constants: 1,
+            })
+        );
+        assert_eq!(trace.number_of_rows(), 4);
+        assert_eq!(
+            trace
+                .optimize()
+                .map(|report| report.removed_gates + report.folded_constants),
+            Ok(0)
+        );
+
+        let mut other_trace = square_plus_five_unoptimized(4);
+        other_trace.optimize().unwrap();
+        assert_eq!(other_trace.circuit(Vec::new()), trace.circuit(Vec::new()));
+        assert_eq!(
+            trace.circuit(Vec::new()),
+            square_plus_five().circuit(Vec::new())
+        );
+
+        let (circuit, witness) = trace
+            .into_circuit_and_witness(Vec::new(), &mut test_rng())
+            .unwrap();
+        let selectors = circuit.selectors();
+        for gate_index in 0..2 {
+            let row = gate_index + 1;
+            let wires = witness.witness_row(gate_index).unwrap();
+            let value = selectors[LEFT_SELECTOR_INDEX][row] * wires[0]
+                + selectors[RIGHT_SELECTOR_INDEX][row] * wires[1]
+                + selectors[OUTPUT_SELECTOR_INDEX][row] * wires[2]
+                + selectors[MULTIPLICATION_SELECTOR_INDEX][row] * wires[0] * wires[1]
+                + selectors[CONSTANT_SELECTOR_INDEX][row];
+            assert!(value.is_zero());
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use ark_crypto_primitives::sponge::{
+    poseidon::{PoseidonConfig, PoseidonSponge},
+    Absorb, CryptographicSponge,
+};
+use ark_ff::PrimeField;
+use ark_std::marker::PhantomData;
+use sha3::{Digest, Keccak256 as Keccak256Hasher};
+
+/// A Fiat-Shamir transcript deriving the folding challenges: a cryptographic sponge that can be
+/// started from the public parameters. Commitments and instances are absorbed with
+/// [`CryptographicSponge::absorb`] and challenges are squeezed as field elements.
+pub trait Transcript<F: PrimeField>: CryptographicSponge {
+    /// Starts an empty transcript. Field-based transcripts use the Poseidon constants of the public
+    /// parameters, which hash-based transcripts ignore.
+    fn start(poseidon_constants: &PoseidonConfig<F>) -> Self;
+}
+
+impl<F: PrimeField + Absorb> Transcript<F> for PoseidonSponge<F> {
+    fn start(poseidon_constants: &PoseidonConfig<F>) -> Self {
+        PoseidonSponge::new(poseidon_constants)
+    }
+}
+
+/// A hash function with 32-byte digests that a [`HashTranscript`] can be built on.
+pub trait TranscriptHash {
+    /// Hashes `input`.
+    fn hash(input: &[u8]) -> [u8; 32];
+}
+
+/// The BLAKE3 hash function.
+#[derive(Clone, Debug)]
+pub struct Blake3;
+
+impl TranscriptHash for Blake3 {
+    fn hash(input: &[u8]) -> [u8; 32] {
+        *blake3::hash(input).as_bytes()
+    }
+}
+
+/// The Keccak-256 hash function, as used by Ethereum.
+#[derive(Clone, Debug)]
+pub struct Keccak256;
+
+impl TranscriptHash for Keccak256 {
+    fn hash(input: &[u8]) -> [u8; 32] {
+        Keccak256Hasher::digest(input).into()
+    }
+}
+
+/// A transcript built on a hash function, e.g. to derive challenges that are cheap to recompute
+/// outside of a circuit. The bytes absorbed since the last squeeze are hashed with the current
+/// state into a seed, which is expanded into the squeezed bytes and ratcheted into the next state.
+/// Field elements are absorbed in their [canonical encoding](crate::fe_to_bytes_canonical).
+#[derive(Clone, Debug)]
+pub struct HashTranscript<H> {
+    state: [u8; 32],
+    absorbed: Vec<u8>,
+    hash: PhantomData<H>,
+}
+
+/// A transcript hashed with BLAKE3.
+pub type Blake3Transcript = HashTranscript<Blake3>;
+
+/// A transcript hashed with Keccak-256.
+pub type KeccakTranscript = HashTranscript<Keccak256>;
+
+impl<H: TranscriptHash + Clone> CryptographicSponge for HashTranscript<H> {
+    type Config = ();
+
+    fn new(_: &()) -> Self {
+        Self {
+            state: [0; 32],
+            absorbed: Vec::new(),
+            hash: PhantomData,
+        }
+    }
+
+    fn absorb(&mut self, input: &impl Absorb) {
+        input.to_sponge_bytes(&mut self.absorbed);
+    }
+
+    fn squeeze_bytes(&mut self, num_bytes: usize) -> Vec<u8> {
+        let seed = H::hash(&[&self.state[..], &self.absorbed].concat());
+        let mut bytes: Vec<u8> = (0u64..)
+            .map(|block| H::hash(&[&seed[..], &[1], &block.to_le_bytes()].concat()))
+            .take(num_bytes.div_ceil(32))
+            .flatten()
+            .collect();
+        bytes.truncate(num_bytes);
+
+        self.state = H::hash(&[&seed[..], &[0]].concat());
+        self.absorbed.clear();
+
+        bytes
+    }
+
+    fn squeeze_bits(&mut self, num_bits: usize) -> Vec<bool> {
+        let mut bits: Vec<bool> = self
+            .squeeze_bytes(num_bits.div_ceil(8))
+            .into_iter()
+            .flat_map(|byte| (0..8).map(move |i| (byte >> i) & 1 == 1))
+            .collect();
+        bits.truncate(num_bits);
+
+        bits
+    }
+}
+
+impl<F: PrimeField, H: TranscriptHash + Clone> Transcript<F> for HashTranscript<H> {
+    fn start(_: &PoseidonConfig<F>) -> Self {
+        Self::new(&())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Blake3Transcript, KeccakTranscript, Transcript};
+    use crate::utils::poseidon::poseidon_parameters_for_test;
+    use ark_bn254::Fr;
+    use ark_crypto_primitives::sponge::{poseidon::PoseidonSponge, CryptographicSponge};
+
+    /// Squeezes two challenges after absorbing `values`, one at a time.
+    fn challenges<T: Transcript<Fr>>(values: &[u64]) -> Vec<Fr> {
+        let mut transcript = T::start(&poseidon_parameters_for_test());
+        for value in values {
+            transcript.absorb(&Fr::from(*value));
+        }
+
+        transcript.squeeze_field_elements(2)
+    }
+
+    fn check_transcript<T: Transcript<Fr>>() {
+        let expected = challenges::<T>(&[1, 2]);
+
+        assert_eq!(challenges::<T>(&[1, 2]), expected);
+        assert_ne!(expected[0], expected[1]);
+        for other in [&[2, 1][..], &[1, 3], &[1]] {
+            assert_ne!(challenges::<T>(other), expected);
+        }
+    }
+
+    #[test]
+    fn transcripts_bind_the_absorbed_values() {
+        check_transcript::<PoseidonSponge<Fr>>();
+        check_transcript::<Blake3Transcript>();
+        check_transcript::<KeccakTranscript>();
+    }
+
+    #[test]
+    fn hash_transcripts_ratchet_after_squeezing() {
+        let mut transcript = Blake3Transcript::new(&());
+        transcript.absorb(&Fr::from(1u64));
+        let first: Fr = transcript.squeeze_field_elements(1)[0];
+        let second: Fr = transcript.squeeze_field_elements(1)[0];
+
+        assert_ne!(first, second);
+        assert_ne!(
+            Blake3Transcript::new(&()).squeeze_bytes(32),
+            KeccakTranscript::new(&()).squeeze_bytes(32)
+        );
+    }
+}
@@ -0,0 +1,90 @@
+use std::{sync::Mutex, time::SystemTime};
+
+/// Statistics of the proofs produced under a prover key, for capacity planning and for rotating
+/// parameters on schedule. Sizes are those of the compressed encoding of the proofs, in bytes.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct UsageStatistics {
+    /// The number of proofs produced, one per proved step.
+    pub number_of_proofs: u64,
+    /// The total size of the proofs produced.
+    pub total_size: u64,
+    /// The size of the smallest proof produced, or zero if none was.
+    pub smallest_size: usize,
+    /// The size of the largest proof produced, or zero if none was.
+    pub largest_size: usize,
+    /// When the first proof was produced.
+    pub first_proof_at: Option<SystemTime>,
+    /// When the last proof was produced.
+    pub last_proof_at: Option<SystemTime>,
+}
+
+impl UsageStatistics {
+    /// Returns the mean size of the proofs produced, or zero if none was.
+    pub fn mean_size(&self) -> u64 {
+        self.total_size
+            .checked_div(self.number_of_proofs)
+            .unwrap_or(0)
+    }
+}
+
+/// The log of the proofs produced under a prover key. It is shared by every prover holding the key,
+/// across threads.
+#[derive(Debug, Default)]
+pub(crate) struct UsageLog {
+    statistics: Mutex<UsageStatistics>,
+}
+
+impl UsageLog {
+    /// Records a proof of `size` bytes produced now.
+    pub(crate) fn record(&self, size: usize) {
+        let now = SystemTime::now();
+        let mut statistics = self
+            .statistics
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        statistics.smallest_size = match statistics.number_of_proofs {
+            0 => size,
+            _ => statistics.smallest_size.min(size),
+        };
+        statistics.largest_size = statistics.largest_size.max(size);
+        statistics.number_of_proofs += 1;
+        statistics.total_size += size as u64;
+        statistics.first_proof_at.get_or_insert(now);
+        statistics.last_proof_at = Some(now);
+    }
+
+    /// Returns a snapshot of the statistics.
+    pub(crate) fn statistics(&self) -> UsageStatistics {
+        self.statistics
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{UsageLog, UsageStatistics};
+
+    #[test]
+    fn logs_aggregate_proof_sizes() {
+        let log = UsageLog::default();
+        assert_eq!(log.statistics(), UsageStatistics::default());
+
+        for size in [300, 100, 200] {
+            log.record(size);
+        }
+        let statistics = log.statistics();
+        assert_eq!(
+            (
+                statistics.number_of_proofs,
+                statistics.total_size,
+                statistics.smallest_size,
+                statistics.largest_size,
+                statistics.mean_size()
+            ),
+            (3, 600, 100, 300, 200)
+        );
+        assert!(statistics.first_proof_at <= statistics.last_proof_at);
+    }
+}
@@ -0,0 +1,120 @@
+use ark_bn254::Fr;
+use ark_ff::{Field, One, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+
+use crate::{
+    PLONKStepCircuit, RowTag, SangriaError, StateWires, StepCircuit, TraceTable,
+    CONSTANT_SELECTOR_INDEX, LEFT_SELECTOR_INDEX, MULTIPLICATION_SELECTOR_INDEX,
+    OUTPUT_SELECTOR_INDEX, POWER_SELECTOR_INDEX, RIGHT_SELECTOR_INDEX,
+};
+
+/// The trace of `y = x^2 + 5`, with `x` as public input and `y` as output.
+pub(crate) fn square_plus_five(x: u64) -> TraceTable<Fr> {
+    let (x, zero, one) = (Fr::from(x), Fr::zero(), Fr::one());
+    let mut square = [zero; 6];
+    square[MULTIPLICATION_SELECTOR_INDEX] = one;
+    square[OUTPUT_SELECTOR_INDEX] = -one;
+    let mut add_five = [zero; 6];
+    add_five[LEFT_SELECTOR_INDEX] = one;
+    add_five[OUTPUT_SELECTOR_INDEX] = -one;
+    add_five[CONSTANT_SELECTOR_INDEX] = Fr::from(5u64);
+
+    let mut trace = TraceTable::new();
+    trace.push_row(RowTag::PublicInput, [x, zero, zero]);
+    trace.push_row(RowTag::Gate(square), [x, x, x * x]);
+    trace.push_row(
+        RowTag::Gate(add_five),
+        [x * x, zero, x * x + Fr::from(5u64)],
+    );
+    trace.push_row(RowTag::Output, [x * x + Fr::from(5u64), zero, zero]);
+    trace.copy((0, 0), (0, 1));
+    trace.copy((0, 1), (1, 1));
+    trace.copy((2, 1), (0, 2));
+    trace.copy((2, 2), (0, 3));
+    trace
+}
+
+/// The trace of `y = x^5 + 5` with a power gate, for a gate equation of degree 5.
+pub(crate) fn fifth_power_plus_five(x: u64) -> TraceTable<Fr> {
+    let (x, zero, one) = (Fr::from(x), Fr::zero(), Fr::one());
+    let fifth_power = x.pow([5]);
+    let mut power = [zero; 6];
+    power[POWER_SELECTOR_INDEX] = one;
+    power[OUTPUT_SELECTOR_INDEX] = -one;
+    let mut add_five = [zero; 6];
+    add_five[LEFT_SELECTOR_INDEX] = one;
+    add_five[OUTPUT_SELECTOR_INDEX] = -one;
+    add_five[CONSTANT_SELECTOR_INDEX] = Fr::from(5u64);
+
+    let mut trace = TraceTable::new();
+    trace.push_row(RowTag::PublicInput, [x, zero, zero]);
+    trace.push_row(RowTag::Gate(power), [x, zero, fifth_power]);
+    trace.push_row(
+        RowTag::Gate(add_five),
+        [fifth_power, zero, fifth_power + Fr::from(5u64)],
+    );
+    trace.push_row(RowTag::Output, [fifth_power + Fr::from(5u64), zero, zero]);
+    trace.copy((0, 0), (0, 1));
+    trace.copy((2, 1), (0, 2));
+    trace.copy((2, 2), (0, 3));
+    trace
+}
+
+/// Adds the witness to the state, rejecting zero increments. The circuit checks the increment
+/// is non-zero by multiplying it with its inverse.
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub(crate) struct Counter;
+
+impl StepCircuit<Fr> for Counter {
+    type State = Fr;
+    type Witness = Fr;
+
+    fn execute(&self, current_state: &Fr, witness: &Fr) -> Result<Fr, SangriaError> {
+        if witness.is_zero() {
+            return Err(SangriaError::InvalidWitness);
+        }
+
+        Ok(*current_state + witness)
+    }
+}
+
+impl PLONKStepCircuit<Fr> for Counter {
+    fn state_len(&self) -> usize {
+        1
+    }
+
+    fn encode_state(&self, state: &Fr) -> Vec<Fr> {
+        vec![*state]
+    }
+
+    fn synthesize(
+        &self,
+        trace: &mut TraceTable<Fr>,
+        current_state: &[Fr],
+        next_state: &[Fr],
+        witness: &Fr,
+    ) -> Result<StateWires, SangriaError> {
+        let row = trace.number_of_rows();
+
+        let mut addition = [Fr::zero(); 6];
+        addition[LEFT_SELECTOR_INDEX] = Fr::one();
+        addition[RIGHT_SELECTOR_INDEX] = Fr::one();
+        addition[OUTPUT_SELECTOR_INDEX] = -Fr::one();
+        trace.push_row(
+            RowTag::Gate(addition),
+            [current_state[0], *witness, next_state[0]],
+        );
+
+        let mut non_zero = [Fr::zero(); 6];
+        non_zero[MULTIPLICATION_SELECTOR_INDEX] = Fr::one();
+        non_zero[CONSTANT_SELECTOR_INDEX] = -Fr::one();
+        let inverse = witness.inverse().unwrap_or_default();
+        trace.push_row(RowTag::Gate(non_zero), [*witness, inverse, Fr::zero()]);
+        trace.copy((1, row), (0, row + 1));
+
+        Ok(StateWires {
+            current_state: vec![(0, row)],
+            next_state: vec![(2, row)],
+        })
+    }
+}
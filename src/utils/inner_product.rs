@@ -0,0 +1,121 @@
+use ark_bn254::Fr;
+use ark_crypto_primitives::sponge::Absorb;
+use ark_ff::PrimeField;
+use ark_std::{marker::PhantomData, rand::Rng};
+
+use crate::{
+    fold_scalars, folding_scheme::SingleCommitment, vector_commitment::InnerProductOpening,
+    CommitmentGadget, HomomorphicCommitmentScheme, PCSError, SangriaError, TraceTable,
+};
+
+/// An insecure inner-product "commitment" over the field itself. It is homomorphic, which is enough
+/// to exercise code that is generic over the commitment scheme.
+pub(crate) struct InnerProduct<F = Fr>(PhantomData<F>);
+
+impl<F: PrimeField + Absorb> HomomorphicCommitmentScheme<F> for InnerProduct<F> {
+    type CommitKey = Vec<F>;
+    type Commitment = F;
+
+    fn setup<R: Rng>(public_randomness: &mut R, len: usize) -> Vec<F> {
+        (0..=len).map(|_| F::rand(public_randomness)).collect()
+    }
+
+    fn max_len(commit_key: &Vec<F>) -> usize {
+        commit_key.len() - 1
+    }
+
+    fn commit(commit_key: &Vec<F>, x: &[F], r: F) -> Result<F, SangriaError> {
+        if x.len() > Self::max_len(commit_key) {
+            return Err(PCSError::DegreeBound {
+                end: x.len(),
+                max_len: Self::max_len(commit_key),
+            }
+            .into());
+        }
+
+        Ok(x.iter()
+            .zip(commit_key.iter())
+            .map(|(&x, &g)| x * g)
+            .sum::<F>()
+            + r * commit_key[commit_key.len() - 1])
+    }
+
+    fn commit_chunk(commit_key: &Vec<F>, offset: usize, x: &[F]) -> Result<F, SangriaError> {
+        if offset + x.len() > Self::max_len(commit_key) {
+            return Err(PCSError::DegreeBound {
+                end: offset + x.len(),
+                max_len: Self::max_len(commit_key),
+            }
+            .into());
+        }
+
+        Ok(x.iter()
+            .zip(commit_key[offset..].iter())
+            .map(|(&x, &g)| x * g)
+            .sum())
+    }
+}
+
+/// The commitments are field elements, allocated as witness wires and folded with [`fold_scalars`].
+impl<F: PrimeField + Absorb> CommitmentGadget<F> for InnerProduct<F> {
+    fn allocate(
+        trace: &mut TraceTable<F>,
+        commitment: &F,
+    ) -> Result<Vec<(usize, usize)>, SangriaError> {
+        trace.hint(&[], |_| Ok(vec![*commitment]))
+    }
+
+    fn fold(
+        trace: &mut TraceTable<F>,
+        running: &[(usize, usize)],
+        fresh: &[(usize, usize)],
+        challenge: (usize, usize),
+    ) -> Result<Vec<(usize, usize)>, SangriaError> {
+        fold_scalars(trace, running, fresh, challenge)
+    }
+}
+
+/// Opens a commitment by revealing the committed vector and its randomness. The proof is as long as
+/// the vector, which is enough to exercise code that is generic over the opening scheme.
+impl InnerProductOpening<Fr> for InnerProduct {
+    type Proof = (Vec<Fr>, Fr);
+    type Accumulator = ();
+
+    fn open(
+        commit_key: &Vec<Fr>,
+        x: &[Fr],
+        r: Fr,
+        y: &[Fr],
+    ) -> Result<(Fr, (Vec<Fr>, Fr)), SangriaError> {
+        if x.len() > Self::max_len(commit_key) {
+            return Err(PCSError::DegreeBound {
+                end: x.len(),
+                max_len: Self::max_len(commit_key),
+            }
+            .into());
+        }
+
+        let value = x.iter().zip(y.iter()).map(|(&x, &y)| x * y).sum();
+        Ok((value, (x.to_vec(), r)))
+    }
+
+    fn verify_opening(
+        commit_key: &Vec<Fr>,
+        commitment: &Fr,
+        y: &[Fr],
+        value: Fr,
+        proof: &(Vec<Fr>, Fr),
+    ) -> Result<(), SangriaError> {
+        let (x, r) = proof;
+        if Self::commit(commit_key, x, *r)? != *commitment
+            || x.iter().zip(y.iter()).map(|(&x, &y)| x * y).sum::<Fr>() != value
+        {
+            return Err(SangriaError::InvalidProof);
+        }
+
+        Ok(())
+    }
+}
+
+/// Uses [`InnerProduct`] for both the witness and the slack commitments.
+pub(crate) type InnerProductConfig = SingleCommitment<InnerProduct>;
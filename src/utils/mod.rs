@@ -0,0 +1,4 @@
+pub(crate) mod circuits;
+pub(crate) mod inner_product;
+pub(crate) mod poseidon;
+pub(crate) mod rand;
@@ -0,0 +1,20 @@
+use ark_crypto_primitives::sponge::poseidon::PoseidonConfig;
+use ark_ff::PrimeField;
+use ark_std::test_rng;
+
+/// Poseidon parameters with random round constants and MDS matrix, for tests only.
+pub(crate) fn poseidon_parameters_for_test<F: PrimeField>() -> PoseidonConfig<F> {
+    let rng = &mut test_rng();
+    let full_rounds = 8;
+    let partial_rounds = 31;
+    let alpha = 17;
+
+    let mds = (0..3)
+        .map(|_| (0..3).map(|_| F::rand(rng)).collect())
+        .collect();
+    let ark = (0..full_rounds + partial_rounds)
+        .map(|_| (0..3).map(|_| F::rand(rng)).collect())
+        .collect();
+
+    PoseidonConfig::new(full_rounds, partial_rounds, alpha, mds, ark, 2, 1)
+}
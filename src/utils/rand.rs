@@ -0,0 +1,7 @@
+use ark_ff::UniformRand;
+use ark_std::rand::Rng;
+
+/// Samples a vector of `len` uniformly random elements.
+pub(crate) fn sample_vector<T: UniformRand, R: Rng>(rng: &mut R, len: usize) -> Vec<T> {
+    (0..len).map(|_| T::rand(rng)).collect()
+}
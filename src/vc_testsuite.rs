@@ -0,0 +1,231 @@
+//! Conformance checks for [`HomomorphicCommitmentScheme`] implementations. Third-party backends
+//! (e.g. GPU or HSM-backed) can call these from their own tests to check that they behave as the
+//! folding scheme expects. Every check panics with a description of the failed property.
+
+use ark_ff::{PrimeField, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::rand::Rng;
+use std::fmt::Debug;
+
+use crate::HomomorphicCommitmentScheme;
+
+fn random_vector<F: PrimeField, R: Rng>(rng: &mut R, len: usize) -> Vec<F> {
+    (0..len).map(|_| F::rand(rng)).collect()
+}
+
+/// Checks that committing is additively homomorphic and preserves scalar multiplication, in both
+/// the committed vector and the randomness.
+pub fn check_homomorphism<F, C, R>(rng: &mut R, len: usize)
+where
+    F: PrimeField,
+    C: HomomorphicCommitmentScheme<F>,
+    C::Commitment: Debug,
+    R: Rng,
+{
+    let commit_key = C::setup(rng, len);
+    let (x, y): (Vec<F>, Vec<F>) = (random_vector(rng, len), random_vector(rng, len));
+    let (r, s, scalar) = (F::rand(rng), F::rand(rng), F::rand(rng));
+
+    let sum: Vec<F> = x.iter().zip(y.iter()).map(|(&x, &y)| x + y).collect();
+    let scaled: Vec<F> = x.iter().map(|&x| x * scalar).collect();
+    let commit_x = C::commit(&commit_key, &x, r).unwrap();
+    let commit_y = C::commit(&commit_key, &y, s).unwrap();
+
+    assert_eq!(
+        commit_x + commit_y,
+        C::commit(&commit_key, &sum, r + s).unwrap(),
+        "the sum of commitments must commit to the sum of the vectors"
+    );
+    assert_eq!(
+        commit_x * scalar,
+        C::commit(&commit_key, &scaled, r * scalar).unwrap(),
+        "scaling a commitment must commit to the scaled vector"
+    );
+    assert_eq!(
+        [commit_x, commit_y].into_iter().sum::<C::Commitment>(),
+        commit_x + commit_y,
+        "summing commitments must agree with adding them"
+    );
+}
+
+/// Smoke-tests binding and hiding: distinct vectors or distinct randomness must give distinct
+/// commitments, and committing must be deterministic.
+pub fn check_binding<F, C, R>(rng: &mut R, len: usize)
+where
+    F: PrimeField,
+    C: HomomorphicCommitmentScheme<F>,
+    C::Commitment: Debug,
+    R: Rng,
+{
+    let commit_key = C::setup(rng, len);
+    let x: Vec<F> = random_vector(rng, len);
+    let r = F::rand(rng);
+    let mut other = x.clone();
+    other[rng.gen_range(0..len)] += F::one();
+
+    let commitment = C::commit(&commit_key, &x, r).unwrap();
+
+    assert_eq!(
+        commitment,
+        C::commit(&commit_key, &x, r).unwrap(),
+        "committing must be deterministic"
+    );
+    assert_ne!(
+        commitment,
+        C::commit(&commit_key, &other, r).unwrap(),
+        "distinct vectors must have distinct commitments"
+    );
+    assert_ne!(
+        commitment,
+        C::commit(&commit_key, &x, r + F::one()).unwrap(),
+        "distinct randomness must give distinct commitments"
+    );
+}
+
+/// Checks that commit keys and commitments survive a serialization round-trip, compressed and
+/// uncompressed.
+pub fn check_serialization<F, C, R>(rng: &mut R, len: usize)
+where
+    F: PrimeField,
+    C: HomomorphicCommitmentScheme<F>,
+    C::Commitment: Debug,
+    R: Rng,
+{
+    let commit_key = C::setup(rng, len);
+    let commitment = C::commit(&commit_key, &random_vector(rng, len), F::rand(rng)).unwrap();
+
+    let mut bytes = Vec::new();
+    commit_key.serialize_compressed(&mut bytes).unwrap();
+    let commit_key = C::CommitKey::deserialize_compressed(&bytes[..]).unwrap();
+    let mut bytes = Vec::new();
+    commit_key.serialize_uncompressed(&mut bytes).unwrap();
+    let commit_key = C::CommitKey::deserialize_uncompressed(&bytes[..]).unwrap();
+    assert_eq!(
+        C::max_len(&commit_key),
+        len,
+        "the commit key must round-trip"
+    );
+
+    for compressed in [true, false] {
+        let mut bytes = Vec::new();
+        let commitment_again = if compressed {
+            commitment.serialize_compressed(&mut bytes).unwrap();
+            C::Commitment::deserialize_compressed(&bytes[..]).unwrap()
+        } else {
+            commitment.serialize_uncompressed(&mut bytes).unwrap();
+            C::Commitment::deserialize_uncompressed(&bytes[..]).unwrap()
+        };
+        assert_eq!(
+            commitment_again, commitment,
+            "the commitment must round-trip"
+        );
+    }
+}
+
+/// Checks the properties the folding scheme relies on: a fold `C_1 + r * C_2` of commitments commits
+/// to the fold of the vectors and randomness, the zero vector with zero randomness commits to zero,
+/// batch commitments match individual ones and vectors longer than the key are rejected.
+pub fn check_fold_compatibility<F, C, R>(rng: &mut R, len: usize)
+where
+    F: PrimeField,
+    C: HomomorphicCommitmentScheme<F>,
+    C::Commitment: Debug,
+    R: Rng,
+{
+    let commit_key = C::setup(rng, len);
+    assert!(C::max_len(&commit_key) >= len, "the key must cover `len`");
+
+    let (x, y): (Vec<F>, Vec<F>) = (random_vector(rng, len), random_vector(rng, len));
+    let (r_x, r_y, challenge) = (F::rand(rng), F::rand(rng), F::rand(rng));
+    let folded: Vec<F> = x
+        .iter()
+        .zip(y.iter())
+        .map(|(&x, &y)| x + challenge * y)
+        .collect();
+
+    let commitments = C::batch_commit(&commit_key, &[x.clone(), y.clone()], &[r_x, r_y]).unwrap();
+    assert_eq!(
+        commitments,
+        vec![
+            C::commit(&commit_key, &x, r_x).unwrap(),
+            C::commit(&commit_key, &y, r_y).unwrap()
+        ],
+        "batch commitments must match individual commitments"
+    );
+    assert_eq!(
+        commitments[0] + commitments[1] * challenge,
+        C::commit(&commit_key, &folded, r_x + challenge * r_y).unwrap(),
+        "folded commitments must commit to the folded vector"
+    );
+    assert!(
+        C::commit(&commit_key, &vec![F::zero(); len], F::zero())
+            .unwrap()
+            .is_zero(),
+        "the zero vector with zero randomness must commit to zero"
+    );
+    assert!(
+        C::commit(
+            &commit_key,
+            &random_vector(rng, C::max_len(&commit_key) + 1),
+            F::rand(rng)
+        )
+        .is_err(),
+        "vectors longer than the commit key must be rejected"
+    );
+}
+
+/// Checks that the commitments of the chunks of a vector, committed at their offsets, add up with a
+/// commitment to the randomness to the commitment of the whole vector, and that chunks running past
+/// the end of the commit key are rejected.
+pub fn check_chunked_commitments<F, C, R>(rng: &mut R, len: usize)
+where
+    F: PrimeField,
+    C: HomomorphicCommitmentScheme<F>,
+    C::Commitment: Debug,
+    R: Rng,
+{
+    let commit_key = C::setup(rng, len);
+    let x: Vec<F> = random_vector(rng, len);
+    let r = F::rand(rng);
+    let split = rng.gen_range(0..=len);
+
+    assert_eq!(
+        C::commit_chunk(&commit_key, 0, &x[..split]).unwrap()
+            + C::commit_chunk(&commit_key, split, &x[split..]).unwrap()
+            + C::commit(&commit_key, &[], r).unwrap(),
+        C::commit(&commit_key, &x, r).unwrap(),
+        "the commitments of the chunks must add up to the commitment of the vector"
+    );
+    assert!(
+        C::commit_chunk(&commit_key, C::max_len(&commit_key), &[F::one()]).is_err(),
+        "chunks past the end of the commit key must be rejected"
+    );
+}
+
+/// Runs every conformance check for vectors of length `len`, which must be positive.
+pub fn check_all<F, C, R>(rng: &mut R, len: usize)
+where
+    F: PrimeField,
+    C: HomomorphicCommitmentScheme<F>,
+    C::Commitment: Debug,
+    R: Rng,
+{
+    check_homomorphism::<F, C, R>(rng, len);
+    check_binding::<F, C, R>(rng, len);
+    check_serialization::<F, C, R>(rng, len);
+    check_fold_compatibility::<F, C, R>(rng, len);
+    check_chunked_commitments::<F, C, R>(rng, len);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check_all;
+    use crate::utils::inner_product::InnerProduct;
+    use ark_bn254::Fr;
+    use ark_std::test_rng;
+
+    #[test]
+    fn inner_product_scheme_conforms() {
+        check_all::<Fr, InnerProduct, _>(&mut test_rng(), 8);
+    }
+}
@@ -0,0 +1,754 @@
+//! Multilinear KZG commitments (in the manner of Papamanthou, Shi and Tamassia) over a
+//! pairing-friendly curve, whose inner-product openings take a logarithmic number of group elements,
+//! so that compressed proofs stop depending on the size of the step circuit.
+
+use crate::{
+    errors::{PCSError, SangriaError},
+    pedersen::Commitment,
+    piop::{eq_extension, prove_sumcheck, verify_sumcheck, SumcheckProof, VirtualPolynomial},
+    vector_commitment::{
+        check_batch_lengths, interleaved_msm, HomomorphicCommitmentScheme, InnerProductOpening,
+    },
+    Blake3Transcript, MultilinearExtension,
+};
+
+use ark_crypto_primitives::sponge::{Absorb, CryptographicSponge};
+use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup, VariableBaseMSM};
+use ark_ff::{One, Zero};
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, SerializationError, Valid, Validate,
+};
+use ark_std::{
+    fmt::{self, Debug, Formatter},
+    io::{Read, Write},
+    marker::PhantomData,
+    rand::Rng,
+    UniformRand,
+};
+
+/// The multilinear KZG commitment over the pairing `E`: a vector `x` of length at most `2^n`,
+/// blinded with `r`, is committed to as `x̃(τ) g + r h`, where `x̃` is the multilinear extension of
+/// `x` and `τ` a secret point of `n` coordinates. The commitments are points of the first group of
+/// the pairing, which add and scale as [Pedersen commitments](crate::pedersen) do.
+///
+/// An inner product with a public vector `y` is proved with a sumcheck reducing it to the
+/// evaluation `x̃(ρ)` at a random point, opened with one commitment to a quotient per variable.
+/// The opening reveals the randomness of the commitment, so an opened commitment no longer hides
+/// the vector.
+///
+/// The sumchecks of the openings derive their challenges from hash transcripts of type `S`, BLAKE3
+/// by default, or Keccak-256 (see [`crate::KeccakTranscript`]) for verifiers running on Ethereum.
+///
+/// WARNING: [`HomomorphicCommitmentScheme::setup`] samples the secret point from the given
+/// randomness, whoever holds it can forge openings. Production deployments must derive the commit
+/// key from a trusted setup ceremony instead.
+pub struct MultilinearKZG<E: Pairing, S = Blake3Transcript> {
+    _pairing: PhantomData<(E, S)>,
+}
+
+/// The commit key of multilinear KZG commitments with a secret point `τ` of `n` coordinates.
+///
+/// The points of the second group are also kept prepared for pairings, so that verifying an opening
+/// does not prepare them again. They are prepared once when the key is sampled or deserialized, and
+/// are not serialized.
+#[derive(Clone)]
+pub struct CommitKey<E: Pairing> {
+    /// For every `m` from 0 to `n`, the points `eq(τ_{n-m}, ..., τ_{n-1}; i) g` of the Lagrange
+    /// basis in the last `m` coordinates of `τ`, committing to the quotients of the openings.
+    lagrange_bases: Vec<Vec<E::G1Affine>>,
+    g: E::G1Affine,
+    h: E::G1Affine,
+    /// The generator of the second group of the pairing.
+    g2: E::G2Affine,
+    /// The points `τ_k g2`.
+    tau_g2: Vec<E::G2Affine>,
+    /// `g2` then the points `τ_k g2`, prepared for pairings.
+    prepared_g2: Vec<E::G2Prepared>,
+}
+
+impl<E: Pairing> CommitKey<E> {
+    fn new(
+        lagrange_bases: Vec<Vec<E::G1Affine>>,
+        g: E::G1Affine,
+        h: E::G1Affine,
+        g2: E::G2Affine,
+        tau_g2: Vec<E::G2Affine>,
+    ) -> Self {
+        let prepared_g2 = [g2]
+            .iter()
+            .chain(&tau_g2)
+            .map(E::G2Prepared::from)
+            .collect();
+        Self {
+            lagrange_bases,
+            g,
+            h,
+            g2,
+            tau_g2,
+            prepared_g2,
+        }
+    }
+
+    fn number_of_variables(&self) -> usize {
+        self.tau_g2.len()
+    }
+
+    fn bases(&self) -> &[E::G1Affine] {
+        self.lagrange_bases
+            .last()
+            .expect("a commit key has the bases of every number of variables")
+    }
+}
+
+impl<E: Pairing> Debug for CommitKey<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CommitKey")
+            .field("lagrange_bases", &self.lagrange_bases)
+            .field("g", &self.g)
+            .field("h", &self.h)
+            .field("g2", &self.g2)
+            .field("tau_g2", &self.tau_g2)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<E: Pairing> PartialEq for CommitKey<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.lagrange_bases == other.lagrange_bases
+            && self.g == other.g
+            && self.h == other.h
+            && self.g2 == other.g2
+            && self.tau_g2 == other.tau_g2
+    }
+}
+
+impl<E: Pairing> Eq for CommitKey<E> {}
+
+impl<E: Pairing> CanonicalSerialize for CommitKey<E> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        self.lagrange_bases
+            .serialize_with_mode(&mut writer, compress)?;
+        self.g.serialize_with_mode(&mut writer, compress)?;
+        self.h.serialize_with_mode(&mut writer, compress)?;
+        self.g2.serialize_with_mode(&mut writer, compress)?;
+        self.tau_g2.serialize_with_mode(&mut writer, compress)
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        self.lagrange_bases.serialized_size(compress)
+            + self.g.serialized_size(compress)
+            + self.h.serialized_size(compress)
+            + self.g2.serialized_size(compress)
+            + self.tau_g2.serialized_size(compress)
+    }
+}
+
+impl<E: Pairing> Valid for CommitKey<E> {
+    fn check(&self) -> Result<(), SerializationError> {
+        self.lagrange_bases.check()?;
+        self.g.check()?;
+        self.h.check()?;
+        self.g2.check()?;
+        self.tau_g2.check()
+    }
+}
+
+impl<E: Pairing> CanonicalDeserialize for CommitKey<E> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        Ok(Self::new(
+            Vec::deserialize_with_mode(&mut reader, compress, validate)?,
+            E::G1Affine::deserialize_with_mode(&mut reader, compress, validate)?,
+            E::G1Affine::deserialize_with_mode(&mut reader, compress, validate)?,
+            E::G2Affine::deserialize_with_mode(&mut reader, compress, validate)?,
+            Vec::deserialize_with_mode(&mut reader, compress, validate)?,
+        ))
+    }
+}
+
+/// A proof of an inner product with a multilinear KZG commitment: the sumcheck reducing it to an
+/// evaluation of the committed extension, the evaluation, its quotient commitments and the
+/// randomness of the commitment.
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct OpeningProof<E: Pairing> {
+    sumcheck_proof: SumcheckProof<E::ScalarField>,
+    evaluation: E::ScalarField,
+    quotients: Vec<E::G1Affine>,
+    hiding: E::ScalarField,
+}
+
+/// The pairing equations of openings whose check was deferred with
+/// [`InnerProductOpening::verify_opening_deferred`], grouped by commit key.
+/// [`InnerProductOpening::decide`] checks all of them with a single multi-pairing, of `n + 1` pairs
+/// for every commit key with a secret point of `n` coordinates however many openings were deferred:
+/// the equations of a key are combined with the powers of a challenge drawn from a transcript of
+/// type `S` that absorbed them all.
+pub struct PairingAccumulator<E: Pairing, S = Blake3Transcript> {
+    keys: Vec<DeferredPairings<E>>,
+    _transcript: PhantomData<S>,
+}
+
+/// The equations deferred for the commit key with the given points of the second group, each of
+/// them `Π_k e(terms[k], prepared_g2[k]) = 1`.
+struct DeferredPairings<E: Pairing> {
+    g2: E::G2Affine,
+    tau_g2: Vec<E::G2Affine>,
+    prepared_g2: Vec<E::G2Prepared>,
+    equations: Vec<Vec<E::G1Affine>>,
+}
+
+impl<E: Pairing, S> Default for PairingAccumulator<E, S> {
+    fn default() -> Self {
+        Self {
+            keys: Vec::new(),
+            _transcript: PhantomData,
+        }
+    }
+}
+
+impl<E: Pairing, S> PairingAccumulator<E, S> {
+    /// Returns the number of deferred equations, one per opening.
+    pub fn number_of_equations(&self) -> usize {
+        self.keys
+            .iter()
+            .map(|deferred| deferred.equations.len())
+            .sum()
+    }
+
+    /// Returns the number of pairings [`InnerProductOpening::decide`] computes.
+    pub fn number_of_pairings(&self) -> usize {
+        self.keys
+            .iter()
+            .map(|deferred| deferred.prepared_g2.len())
+            .sum()
+    }
+
+    fn defer(&mut self, commit_key: &CommitKey<E>, terms: Vec<E::G1Affine>) {
+        let position = self.keys.iter().position(|deferred| {
+            deferred.g2 == commit_key.g2 && deferred.tau_g2 == commit_key.tau_g2
+        });
+        let deferred = match position {
+            Some(position) => &mut self.keys[position],
+            None => {
+                self.keys.push(DeferredPairings {
+                    g2: commit_key.g2,
+                    tau_g2: commit_key.tau_g2.clone(),
+                    prepared_g2: commit_key.prepared_g2.clone(),
+                    equations: Vec::new(),
+                });
+                self.keys.last_mut().expect("a key was just pushed")
+            }
+        };
+        deferred.equations.push(terms);
+    }
+}
+
+impl<E: Pairing, S> HomomorphicCommitmentScheme<E::ScalarField> for MultilinearKZG<E, S> {
+    type CommitKey = CommitKey<E>;
+    type Commitment = Commitment<E::G1Affine>;
+
+    /// Samples a commit key for vectors of length `len`, rounded up to a power of two, from a secret
+    /// point drawn from `public_randomness` (see the warning of [`MultilinearKZG`]).
+    fn setup<R: Rng>(public_randomness: &mut R, len: usize) -> CommitKey<E> {
+        let number_of_variables = len.max(1).next_power_of_two().trailing_zeros() as usize;
+        let tau: Vec<E::ScalarField> = (0..number_of_variables)
+            .map(|_| E::ScalarField::rand(public_randomness))
+            .collect();
+        let g = E::G1::rand(public_randomness);
+        let h = E::G1::rand(public_randomness);
+        let g2 = E::G2::rand(public_randomness);
+
+        let lagrange_bases = (0..=number_of_variables)
+            .map(|m| {
+                let bases: Vec<E::G1> = eq_extension(&tau[number_of_variables - m..])
+                    .evaluations()
+                    .iter()
+                    .map(|&scalar| g * scalar)
+                    .collect();
+                E::G1::normalize_batch(&bases)
+            })
+            .collect();
+        let tau_g2: Vec<E::G2> = tau.iter().map(|&coordinate| g2 * coordinate).collect();
+
+        CommitKey::new(
+            lagrange_bases,
+            g.into_affine(),
+            h.into_affine(),
+            g2.into_affine(),
+            E::G2::normalize_batch(&tau_g2),
+        )
+    }
+
+    fn max_len(commit_key: &CommitKey<E>) -> usize {
+        commit_key.bases().len()
+    }
+
+    fn commit(
+        commit_key: &CommitKey<E>,
+        x: &[E::ScalarField],
+        r: E::ScalarField,
+    ) -> Result<Self::Commitment, SangriaError> {
+        let bases = commit_key.bases();
+        if x.len() > bases.len() {
+            return Err(PCSError::DegreeBound {
+                end: x.len(),
+                max_len: bases.len(),
+            }
+            .into());
+        }
+
+        let commitment = E::G1::msm_unchecked(bases, x) + commit_key.h * r;
+        Ok(Commitment(commitment.into_affine()))
+    }
+
+    /// Commits to all the vectors in one pass over the Lagrange bases with an interleaved MSM
+    /// and normalizes the commitments together.
+    fn batch_commit(
+        commit_key: &CommitKey<E>,
+        xs: &[Vec<E::ScalarField>],
+        r: &[E::ScalarField],
+    ) -> Result<Vec<Self::Commitment>, SangriaError> {
+        check_batch_lengths(xs, r, commit_key.bases().len())?;
+
+        let commitments: Vec<E::G1> = interleaved_msm(commit_key.bases(), xs)
+            .into_iter()
+            .zip(r)
+            .map(|(commitment, &r)| commitment + commit_key.h * r)
+            .collect();
+        Ok(E::G1::normalize_batch(&commitments)
+            .into_iter()
+            .map(Commitment)
+            .collect())
+    }
+
+    fn commit_chunk(
+        commit_key: &CommitKey<E>,
+        offset: usize,
+        x: &[E::ScalarField],
+    ) -> Result<Self::Commitment, SangriaError> {
+        let bases = commit_key.bases();
+        if offset + x.len() > bases.len() {
+            return Err(PCSError::DegreeBound {
+                end: offset + x.len(),
+                max_len: bases.len(),
+            }
+            .into());
+        }
+
+        let commitment = E::G1::msm_unchecked(&bases[offset..], x);
+        Ok(Commitment(commitment.into_affine()))
+    }
+}
+
+impl<E: Pairing, S> InnerProductOpening<E::ScalarField> for MultilinearKZG<E, S>
+where
+    E::ScalarField: Absorb,
+    S: CryptographicSponge<Config = ()>,
+{
+    type Proof = OpeningProof<E>;
+    type Accumulator = PairingAccumulator<E, S>;
+
+    fn open(
+        commit_key: &CommitKey<E>,
+        x: &[E::ScalarField],
+        r: E::ScalarField,
+        y: &[E::ScalarField],
+    ) -> Result<(E::ScalarField, OpeningProof<E>), SangriaError> {
+        let len = Self::max_len(commit_key);
+        if y.len() > len {
+            return Err(PCSError::DegreeBound {
+                end: y.len(),
+                max_len: len,
+            }
+            .into());
+        }
+        let commitment = Self::commit(commit_key, x, r)?;
+        let value = x.iter().zip(y).map(|(&x, &y)| x * y).sum();
+
+        let x = padded_extension(x, len);
+        let mut product = VirtualPolynomial::new(x.number_of_variables());
+        product.add_product(
+            E::ScalarField::one(),
+            vec![x.clone(), padded_extension(y, len)],
+        )?;
+        let mut sponge: S = opening_transcript(&commitment, y, value);
+        let (sumcheck_proof, point) = prove_sumcheck(&product, &mut sponge);
+        let (evaluation, quotients) = quotients(commit_key, &x, &point);
+
+        Ok((
+            value,
+            OpeningProof {
+                sumcheck_proof,
+                evaluation,
+                quotients,
+                hiding: r,
+            },
+        ))
+    }
+
+    fn verify_opening(
+        commit_key: &CommitKey<E>,
+        commitment: &Self::Commitment,
+        y: &[E::ScalarField],
+        value: E::ScalarField,
+        proof: &OpeningProof<E>,
+    ) -> Result<(), SangriaError> {
+        let mut accumulator = PairingAccumulator::default();
+        Self::verify_opening_deferred(commit_key, commitment, y, value, proof, &mut accumulator)?;
+
+        Self::decide(accumulator)
+    }
+
+    /// Checks the sumcheck of the opening and defers the pairing equation of the evaluation.
+    fn verify_opening_deferred(
+        commit_key: &CommitKey<E>,
+        commitment: &Self::Commitment,
+        y: &[E::ScalarField],
+        value: E::ScalarField,
+        proof: &OpeningProof<E>,
+        accumulator: &mut PairingAccumulator<E, S>,
+    ) -> Result<(), SangriaError> {
+        let len = Self::max_len(commit_key);
+        let number_of_variables = commit_key.number_of_variables();
+        if y.len() > len || proof.quotients.len() != number_of_variables {
+            return Err(SangriaError::InvalidProof);
+        }
+
+        let mut sponge: S = opening_transcript(commitment, y, value);
+        let subclaim = verify_sumcheck(
+            value,
+            2,
+            number_of_variables,
+            &proof.sumcheck_proof,
+            &mut sponge,
+        )?;
+        if proof.evaluation * padded_extension(y, len).evaluate(&subclaim.point)?
+            != subclaim.expected_evaluation
+        {
+            return Err(SangriaError::InvalidProof);
+        }
+
+        defer_evaluation(
+            commit_key,
+            commitment,
+            &subclaim.point,
+            proof.evaluation,
+            proof.hiding,
+            &proof.quotients,
+            accumulator,
+        );
+
+        Ok(())
+    }
+
+    /// Combines the equations of every commit key with the powers of a challenge and checks them
+    /// all with one multi-pairing.
+    fn decide(accumulator: PairingAccumulator<E, S>) -> Result<(), SangriaError> {
+        let mut left = Vec::new();
+        let mut right = Vec::new();
+        for deferred in accumulator.keys {
+            let mut sponge = S::new(&());
+            for &term in deferred.equations.iter().flatten() {
+                sponge.absorb(&Commitment(term));
+            }
+            let challenge: E::ScalarField = sponge.squeeze_field_elements(1)[0];
+            let powers: Vec<E::ScalarField> =
+                ark_std::iter::successors(Some(E::ScalarField::one()), |&power| {
+                    Some(power * challenge)
+                })
+                .take(deferred.equations.len())
+                .collect();
+
+            for k in 0..deferred.prepared_g2.len() {
+                let terms: Vec<E::G1Affine> =
+                    deferred.equations.iter().map(|terms| terms[k]).collect();
+                left.push(E::G1::msm_unchecked(&terms, &powers));
+            }
+            right.extend(deferred.prepared_g2);
+        }
+        if !E::multi_pairing(E::G1::normalize_batch(&left), right).is_zero() {
+            return Err(SangriaError::InvalidProof);
+        }
+
+        Ok(())
+    }
+}
+
+impl<E: Pairing, S> MultilinearKZG<E, S> {
+    /// Returns the evaluation at `point` of the multilinear extension of `x`, committed to with
+    /// randomness `r`, and a proof of it made of the quotient commitments alone: unlike an inner
+    /// product opening, there is no sumcheck, so the verifier does nothing but a pairing check. The
+    /// proof reveals the randomness of the commitment. `point` must have one coordinate per variable
+    /// of the commit key.
+    pub fn open_evaluation(
+        commit_key: &CommitKey<E>,
+        x: &[E::ScalarField],
+        r: E::ScalarField,
+        point: &[E::ScalarField],
+    ) -> Result<(E::ScalarField, EvaluationProof<E>), SangriaError> {
+        let len = Self::max_len(commit_key);
+        if x.len() > len {
+            return Err(PCSError::DegreeBound {
+                end: x.len(),
+                max_len: len,
+            }
+            .into());
+        }
+        if point.len() != commit_key.number_of_variables() {
+            return Err(SangriaError::IndexOutOfBounds);
+        }
+
+        let (value, quotients) = quotients(commit_key, &padded_extension(x, len), point);
+        Ok((
+            value,
+            EvaluationProof {
+                quotients,
+                hiding: r,
+            },
+        ))
+    }
+
+    /// Checks the shape of a proof of [`Self::open_evaluation`] that the vector committed in
+    /// `commitment` evaluates to `value` at `point`, and defers its pairing equation to
+    /// `accumulator`, or returns an error.
+    pub fn verify_evaluation_deferred(
+        commit_key: &CommitKey<E>,
+        commitment: &Commitment<E::G1Affine>,
+        point: &[E::ScalarField],
+        value: E::ScalarField,
+        proof: &EvaluationProof<E>,
+        accumulator: &mut PairingAccumulator<E, S>,
+    ) -> Result<(), SangriaError> {
+        let number_of_variables = commit_key.number_of_variables();
+        if point.len() != number_of_variables || proof.quotients.len() != number_of_variables {
+            return Err(SangriaError::InvalidProof);
+        }
+
+        defer_evaluation(
+            commit_key,
+            commitment,
+            point,
+            value,
+            proof.hiding,
+            &proof.quotients,
+            accumulator,
+        );
+        Ok(())
+    }
+}
+
+/// A proof of an evaluation of the extension committed with a multilinear KZG commitment: the
+/// commitments to its quotients and the randomness of the commitment.
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct EvaluationProof<E: Pairing> {
+    quotients: Vec<E::G1Affine>,
+    hiding: E::ScalarField,
+}
+
+/// Returns `x̃(ρ)` for the point `ρ` and the commitments to the quotients of
+/// `x̃(X) - x̃(ρ) = Σ_k (X_k - ρ_k) q_k(X_{k+1}, ..., X_{n-1})`.
+fn quotients<E: Pairing>(
+    commit_key: &CommitKey<E>,
+    x: &MultilinearExtension<E::ScalarField>,
+    point: &[E::ScalarField],
+) -> (E::ScalarField, Vec<E::G1Affine>) {
+    let mut remainder = x.evaluations().to_vec();
+    let mut quotients = Vec::with_capacity(point.len());
+    for (k, &coordinate) in point.iter().enumerate() {
+        let (low, high): (Vec<_>, Vec<_>) =
+            remainder.chunks(2).map(|pair| (pair[0], pair[1])).unzip();
+        let quotient: Vec<_> = low
+            .iter()
+            .zip(&high)
+            .map(|(&low, &high)| high - low)
+            .collect();
+        let bases = &commit_key.lagrange_bases[point.len() - k - 1];
+        quotients.push(E::G1::msm_unchecked(bases, &quotient));
+        remainder = low
+            .iter()
+            .zip(&quotient)
+            .map(|(&low, &quotient)| low + coordinate * quotient)
+            .collect();
+    }
+
+    (remainder[0], E::G1::normalize_batch(&quotients))
+}
+
+/// Defers to `accumulator` the pairing equation of an evaluation `x̃(ρ)` of the extension committed
+/// in `commitment` with randomness `hiding`,
+/// `e(C - x̃(ρ) g - r h, g2) = Π_k e(q_k, (τ_k - ρ_k) g2)`, with the terms in `ρ_k` moved to the
+/// first group so that the points of the second group are the prepared ones of the key.
+fn defer_evaluation<E: Pairing, S>(
+    commit_key: &CommitKey<E>,
+    commitment: &Commitment<E::G1Affine>,
+    point: &[E::ScalarField],
+    evaluation: E::ScalarField,
+    hiding: E::ScalarField,
+    quotients: &[E::G1Affine],
+    accumulator: &mut PairingAccumulator<E, S>,
+) {
+    let opened = commitment.0.into_group() - commit_key.g * evaluation
+        + E::G1::msm_unchecked(quotients, point)
+        - commit_key.h * hiding;
+    let terms: Vec<E::G1> = [opened]
+        .into_iter()
+        .chain(quotients.iter().map(|&quotient| -quotient.into_group()))
+        .collect();
+    accumulator.defer(commit_key, E::G1::normalize_batch(&terms));
+}
+
+/// Returns the extension of `vector` padded with zeros to the length `len` of the commit key.
+fn padded_extension<F: ark_ff::PrimeField>(vector: &[F], len: usize) -> MultilinearExtension<F> {
+    let mut padded = vector.to_vec();
+    padded.resize(len, F::zero());
+
+    MultilinearExtension::from_column(&padded)
+}
+
+/// Starts the transcript of the sumcheck of an opening, bound to the commitment, the public vector
+/// and the claimed inner product.
+fn opening_transcript<G, F, S>(commitment: &Commitment<G>, y: &[F], value: F) -> S
+where
+    G: AffineRepr,
+    F: Absorb,
+    S: CryptographicSponge<Config = ()>,
+{
+    let mut sponge = S::new(&());
+    sponge.absorb(commitment);
+    sponge.absorb(&y);
+    sponge.absorb(&value);
+
+    sponge
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CommitKey, MultilinearKZG, OpeningProof, PairingAccumulator};
+    use crate::{
+        pedersen::Commitment, utils::rand::sample_vector, vc_testsuite::check_all,
+        HomomorphicCommitmentScheme, InnerProductOpening, SangriaError,
+    };
+    use ark_bn254::{Bn254, Fr, G1Affine};
+    use ark_ec::CurveGroup;
+    use ark_ff::One;
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+    use ark_std::{test_rng, UniformRand};
+
+    type Scheme = MultilinearKZG<Bn254>;
+
+    #[test]
+    fn kzg_scheme_conforms() {
+        check_all::<Fr, Scheme, _>(&mut test_rng(), 8);
+    }
+
+    #[test]
+    fn inner_products_open() {
+        let rng = &mut test_rng();
+        let commit_key = Scheme::setup(rng, 13);
+        let (x, y): (Vec<Fr>, Vec<Fr>) = (sample_vector(rng, 11), sample_vector(rng, 7));
+        let r = Fr::rand(rng);
+        let commitment = Scheme::commit(&commit_key, &x, r).unwrap();
+
+        let (value, proof) = Scheme::open(&commit_key, &x, r, &y).unwrap();
+        assert_eq!(value, x.iter().zip(&y).map(|(&x, &y)| x * y).sum::<Fr>());
+        assert_eq!(
+            Scheme::verify_opening(&commit_key, &commitment, &y, value, &proof),
+            Ok(())
+        );
+        assert_eq!(
+            Scheme::verify_opening(&commit_key, &commitment, &y, value + Fr::one(), &proof),
+            Err(SangriaError::InvalidProof)
+        );
+
+        let other = Scheme::commit(&commit_key, &y, r).unwrap();
+        assert_eq!(
+            Scheme::verify_opening(&commit_key, &other, &y, value, &proof),
+            Err(SangriaError::InvalidProof)
+        );
+    }
+
+    #[test]
+    fn deserialized_keys_verify_with_prepared_points() {
+        let rng = &mut test_rng();
+        let commit_key = Scheme::setup(rng, 8);
+        let mut bytes = Vec::new();
+        commit_key.serialize_compressed(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), commit_key.compressed_size());
+        let deserialized = CommitKey::<Bn254>::deserialize_compressed(&bytes[..]).unwrap();
+        assert_eq!(deserialized, commit_key);
+
+        // the prepared points are recomputed from the deserialized ones
+        let (x, y): (Vec<Fr>, Vec<Fr>) = (sample_vector(rng, 8), sample_vector(rng, 8));
+        let r = Fr::rand(rng);
+        let commitment = Scheme::commit(&commit_key, &x, r).unwrap();
+        let (value, proof) = Scheme::open(&commit_key, &x, r, &y).unwrap();
+        assert_eq!(
+            Scheme::verify_opening(&deserialized, &commitment, &y, value, &proof),
+            Ok(())
+        );
+        assert_eq!(
+            Scheme::verify_opening(&deserialized, &commitment, &y, value + Fr::one(), &proof),
+            Err(SangriaError::InvalidProof)
+        );
+    }
+
+    #[test]
+    fn deferred_openings_are_decided_with_one_multi_pairing() {
+        type Opening<'a> = (
+            &'a CommitKey<Bn254>,
+            Commitment<G1Affine>,
+            Vec<Fr>,
+            Fr,
+            OpeningProof<Bn254>,
+        );
+
+        let rng = &mut test_rng();
+        let (commit_key, other_key) = (Scheme::setup(rng, 8), Scheme::setup(rng, 4));
+        let mut openings: Vec<_> = [&commit_key, &commit_key, &commit_key, &other_key]
+            .into_iter()
+            .map(|key| {
+                let (x, y): (Vec<Fr>, Vec<Fr>) = (sample_vector(rng, 4), sample_vector(rng, 4));
+                let r = Fr::rand(rng);
+                let commitment = Scheme::commit(key, &x, r).unwrap();
+                let (value, proof) = Scheme::open(key, &x, r, &y).unwrap();
+                (key, commitment, y, value, proof)
+            })
+            .collect();
+        let defer = |openings: &[Opening]| {
+            let mut accumulator = PairingAccumulator::default();
+            for (key, commitment, y, value, proof) in openings {
+                Scheme::verify_opening_deferred(
+                    key,
+                    commitment,
+                    y,
+                    *value,
+                    proof,
+                    &mut accumulator,
+                )
+                .unwrap();
+            }
+            accumulator
+        };
+
+        let accumulator = defer(&openings);
+        assert_eq!(accumulator.number_of_equations(), 4);
+        // 3 + 1 pairings for the first key and 2 + 1 for the other
+        assert_eq!(accumulator.number_of_pairings(), 7);
+        assert_eq!(Scheme::decide(accumulator), Ok(()));
+
+        // a wrong quotient is only caught by the pairings
+        let quotient = &mut openings[1].4.quotients[0];
+        *quotient = (*quotient + commit_key.g).into_affine();
+        assert_eq!(
+            Scheme::decide(defer(&openings)),
+            Err(SangriaError::InvalidProof)
+        );
+    }
+}
@@ -1,38 +1,354 @@
-// pub mod pedersen;
+pub mod kzg;
+pub mod pedersen;
 
-use crate::errors::SangriaError;
-use ark_ff::{Field, ToBytes, Zero};
+use crate::errors::{PCSError, SangriaError};
+use ark_crypto_primitives::sponge::{Absorb, CryptographicSponge};
+use ark_ec::{AffineRepr, Group};
+use ark_ff::{BigInteger, Field, PrimeField, Zero};
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
-use ark_sponge::Absorb;
 use ark_std::rand::Rng;
 use std::{iter::Sum, ops};
 
 /// Trait defining the types and functions needed for an additively homomorphic commitment scheme.
 /// The scheme is defined with respect to a finite field `F` for which scalar multiplication is preserved.
 pub trait HomomorphicCommitmentScheme<F: Field> {
-    type CommitKey: Clone + CanonicalSerialize + CanonicalDeserialize + ToBytes;
+    /// The key used to commit to vectors, produced by the setup.
+    type CommitKey: Clone + CanonicalSerialize + CanonicalDeserialize;
 
     /// Represent a ciphertext from a generic homomorphic encryption scheme. To manifest the homomorphic
     /// property of the scheme, we require that some arithmetic operations (add and multiply by scalar) are implemented.
     type Commitment: PartialEq
         + Copy
         + Clone
-        + ops::Add
+        + ops::Add<Output = Self::Commitment>
         + ops::Mul<F, Output = Self::Commitment>
         + CanonicalSerialize
         + CanonicalDeserialize
         + Zero
         + Sum
-        + ToBytes
         + Absorb;
 
     /// Generate a commit key using the provided length
     fn setup<R: Rng>(public_randomness: &mut R, len: usize) -> Self::CommitKey;
 
+    /// Returns the maximum length of the vectors that can be committed to with the commit key
+    fn max_len(commit_key: &Self::CommitKey) -> usize;
+
     /// Commit to a vector of scalars using the commit key
     fn commit(
         commit_key: &Self::CommitKey,
         x: &[F],
         r: F,
     ) -> Result<Self::Commitment, SangriaError>;
+
+    /// Commit to several vectors of scalars at once, the i-th vector being blinded with `r[i]`.
+    /// Schemes that can share work between the commitments (e.g. the passes of an MSM over the same
+    /// bases) should override the default, which commits to each vector separately.
+    fn batch_commit(
+        commit_key: &Self::CommitKey,
+        xs: &[Vec<F>],
+        r: &[F],
+    ) -> Result<Vec<Self::Commitment>, SangriaError> {
+        check_batch_lengths(xs, r, usize::MAX)?;
+
+        xs.iter()
+            .zip(r.iter())
+            .map(|(x, &r)| Self::commit(commit_key, x, r))
+            .collect()
+    }
+
+    /// Commit without randomness to the vector holding `x` from position `offset` on and zeros
+    /// before it, so that a long vector can be committed to one chunk at a time by adding up the
+    /// commitments of its chunks. Schemes that commit position by position (e.g. Pedersen) should
+    /// override the default, which commits to the zero-padded vector.
+    fn commit_chunk(
+        commit_key: &Self::CommitKey,
+        offset: usize,
+        x: &[F],
+    ) -> Result<Self::Commitment, SangriaError> {
+        let mut padded = vec![F::zero(); offset];
+        padded.extend_from_slice(x);
+
+        Self::commit(commit_key, &padded, F::zero())
+    }
+}
+
+/// A homomorphic commitment scheme that can prove the inner product of a committed vector with a
+/// public vector. Opening the multilinear extension of a committed vector at a point is the inner
+/// product with the evaluations of `eq` at that point.
+pub trait InnerProductOpening<F: Field>: HomomorphicCommitmentScheme<F> {
+    /// A proof that a committed vector has a given inner product with a public vector.
+    type Proof: Clone + CanonicalSerialize + CanonicalDeserialize;
+
+    /// The checks of openings left by [`Self::verify_opening_deferred`] to [`Self::decide`], e.g.
+    /// pairing equations, so that the openings of a proof are decided together. Schemes checking
+    /// their openings at once use `()`.
+    type Accumulator: Default;
+
+    /// Returns the inner product of `x`, committed to with randomness `r`, with `y` and a proof of
+    /// it, or an error if the vectors are longer than the commit key supports.
+    fn open(
+        commit_key: &Self::CommitKey,
+        x: &[F],
+        r: F,
+        y: &[F],
+    ) -> Result<(F, Self::Proof), SangriaError>;
+
+    /// Checks that the vector committed in `commitment` has inner product `value` with `y`, or
+    /// returns an error.
+    fn verify_opening(
+        commit_key: &Self::CommitKey,
+        commitment: &Self::Commitment,
+        y: &[F],
+        value: F,
+        proof: &Self::Proof,
+    ) -> Result<(), SangriaError>;
+
+    /// Checks an opening as [`Self::verify_opening`] does, but may add some of the checks to
+    /// `accumulator` instead of making them, in which case the opening only holds once
+    /// [`Self::decide`] accepts the accumulator. The default checks the opening at once.
+    fn verify_opening_deferred(
+        commit_key: &Self::CommitKey,
+        commitment: &Self::Commitment,
+        y: &[F],
+        value: F,
+        proof: &Self::Proof,
+        _accumulator: &mut Self::Accumulator,
+    ) -> Result<(), SangriaError> {
+        Self::verify_opening(commit_key, commitment, y, value, proof)
+    }
+
+    /// Makes the checks deferred to `accumulator`, or returns an error if one of them fails.
+    fn decide(_accumulator: Self::Accumulator) -> Result<(), SangriaError> {
+        Ok(())
+    }
+}
+
+/// An opening of a commitment that reveals nothing about the committed vector `x` beyond its inner
+/// product with `y`, whatever the underlying proof reveals: the prover commits to a random mask `m`
+/// as long as `x`, sends the commitment `M` and `<m, y>`, and opens `C + c M` for a challenge `c`
+/// drawn after them, so that the vector it opens, `x + c m`, is uniformly random. Without a mask,
+/// the commitment itself is opened.
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct HidingOpening<F: PrimeField, C: InnerProductOpening<F>> {
+    mask: Option<(C::Commitment, F)>,
+    proof: C::Proof,
+}
+
+impl<F: PrimeField, C: InnerProductOpening<F>> HidingOpening<F, C> {
+    /// Returns whether the opening is masked.
+    pub fn is_hiding(&self) -> bool {
+        self.mask.is_some()
+    }
+
+    /// Returns the opening of the masked commitment, or of the commitment itself without a mask.
+    pub fn proof(&self) -> &C::Proof {
+        &self.proof
+    }
+}
+
+/// Returns the inner product of `x`, committed to with randomness `r`, with `y` and a proof of it,
+/// masked with randomness drawn from `rng` if there is one. The mask and the value are absorbed
+/// into `sponge` before the challenge is squeezed; nothing is absorbed without a mask.
+pub fn open_hiding<F, C, S, R>(
+    commit_key: &C::CommitKey,
+    x: &[F],
+    r: F,
+    y: &[F],
+    sponge: &mut S,
+    rng: Option<&mut R>,
+) -> Result<(F, HidingOpening<F, C>), SangriaError>
+where
+    F: PrimeField + Absorb,
+    C: InnerProductOpening<F>,
+    S: CryptographicSponge,
+    R: Rng,
+{
+    let Some(rng) = rng else {
+        let (value, proof) = C::open(commit_key, x, r, y)?;
+        return Ok((value, HidingOpening { mask: None, proof }));
+    };
+
+    let inner_product = |x: &[F]| x.iter().zip(y).map(|(&x, &y)| x * y).sum::<F>();
+    let mask: Vec<F> = (0..x.len()).map(|_| F::rand(rng)).collect();
+    let mask_hiding = F::rand(rng);
+    let mask_commitment = C::commit(commit_key, &mask, mask_hiding)?;
+    let (value, mask_value) = (inner_product(x), inner_product(&mask));
+    sponge.absorb(&mask_commitment);
+    sponge.absorb(&value);
+    sponge.absorb(&mask_value);
+    let challenge: F = sponge.squeeze_field_elements(1)[0];
+
+    let masked: Vec<F> = x
+        .iter()
+        .zip(&mask)
+        .map(|(&x, &mask)| x + challenge * mask)
+        .collect();
+    let (_, proof) = C::open(commit_key, &masked, r + challenge * mask_hiding, y)?;
+
+    Ok((
+        value,
+        HidingOpening {
+            mask: Some((mask_commitment, mask_value)),
+            proof,
+        },
+    ))
+}
+
+/// Checks an opening of [`open_hiding`] that the vector committed in `commitment` has inner product
+/// `value` with `y`, absorbing its mask into `sponge` like the prover, or returns an error. The
+/// checks the scheme defers are added to `accumulator` (see
+/// [`InnerProductOpening::verify_opening_deferred`]).
+pub fn verify_hiding_opening<F, C, S>(
+    commit_key: &C::CommitKey,
+    commitment: &C::Commitment,
+    y: &[F],
+    value: F,
+    opening: &HidingOpening<F, C>,
+    sponge: &mut S,
+    accumulator: &mut C::Accumulator,
+) -> Result<(), SangriaError>
+where
+    F: PrimeField + Absorb,
+    C: InnerProductOpening<F>,
+    S: CryptographicSponge,
+{
+    let Some((mask_commitment, mask_value)) = opening.mask else {
+        return C::verify_opening_deferred(
+            commit_key,
+            commitment,
+            y,
+            value,
+            &opening.proof,
+            accumulator,
+        );
+    };
+
+    sponge.absorb(&mask_commitment);
+    sponge.absorb(&value);
+    sponge.absorb(&mask_value);
+    let challenge: F = sponge.squeeze_field_elements(1)[0];
+
+    C::verify_opening_deferred(
+        commit_key,
+        &(*commitment + mask_commitment * challenge),
+        y,
+        value + challenge * mask_value,
+        &opening.proof,
+        accumulator,
+    )
+}
+
+/// Checks that there is one blinding factor per vector of a batch and that no vector is longer than
+/// `max_len`.
+pub(crate) fn check_batch_lengths<F>(
+    xs: &[Vec<F>],
+    r: &[F],
+    max_len: usize,
+) -> Result<(), SangriaError> {
+    if xs.len() != r.len() {
+        return Err(PCSError::BatchLength {
+            vectors: xs.len(),
+            randomness: r.len(),
+        }
+        .into());
+    }
+    if let Some(end) = xs.iter().map(Vec::len).find(|&len| len > max_len) {
+        return Err(PCSError::DegreeBound { end, max_len }.into());
+    }
+
+    Ok(())
+}
+
+/// Computes the multi-scalar multiplications of several vectors of scalars with the same bases,
+/// `Σ_i xs[k][i] bases[i]` for every `k`, in one pass of Pippenger's bucket method with signed
+/// digits: every window walks the bases once and adds or subtracts each of them in the buckets of
+/// all the vectors. The vectors may be shorter than the bases.
+pub(crate) fn interleaved_msm<G: AffineRepr>(
+    bases: &[G],
+    xs: &[Vec<G::ScalarField>],
+) -> Vec<G::Group> {
+    let size = xs.iter().map(Vec::len).max().unwrap_or(0).min(bases.len());
+    // the window size of arkworks' MSM, about ln(size) + 2 bits
+    let window_bits = if size < 32 {
+        3
+    } else {
+        (usize::BITS - size.leading_zeros() - 1) as usize * 69 / 100 + 2
+    };
+    let number_of_bits = G::ScalarField::MODULUS_BIT_SIZE as usize;
+    let number_of_windows = number_of_bits.div_ceil(window_bits);
+    let digits: Vec<Vec<i64>> = xs
+        .iter()
+        .map(|x| {
+            x[..x.len().min(size)]
+                .iter()
+                .flat_map(|scalar| {
+                    signed_digits(&scalar.into_bigint(), window_bits, number_of_windows)
+                })
+                .collect()
+        })
+        .collect();
+    let number_of_buckets = digits
+        .iter()
+        .flatten()
+        .map(|digit| digit.unsigned_abs() as usize)
+        .max()
+        .unwrap_or(0);
+
+    let mut sums = vec![G::Group::zero(); xs.len()];
+    let mut buckets = vec![vec![G::Group::zero(); number_of_buckets]; xs.len()];
+    for window in (0..number_of_windows).rev() {
+        for (i, base) in bases[..size].iter().enumerate() {
+            for (digits, buckets) in digits.iter().zip(buckets.iter_mut()) {
+                match digits.get(i * number_of_windows + window) {
+                    Some(&digit) if digit > 0 => buckets[digit as usize - 1] += base,
+                    Some(&digit) if digit < 0 => buckets[(-digit) as usize - 1] -= base,
+                    _ => (),
+                }
+            }
+        }
+
+        // Σ_d d B_d, as the sum of the running sums of the buckets from the top one
+        for (sum, buckets) in sums.iter_mut().zip(buckets.iter_mut()) {
+            for _ in 0..window_bits {
+                sum.double_in_place();
+            }
+            let mut running = G::Group::zero();
+            for bucket in buckets.iter_mut().rev() {
+                running += *bucket;
+                *sum += running;
+                *bucket = G::Group::zero();
+            }
+        }
+    }
+
+    sums
+}
+
+/// Writes `scalar` in `number_of_windows` digits of `window_bits` bits, from the lowest, in the
+/// range `[-2^(window_bits - 1), 2^(window_bits - 1))` but for the highest one, which takes the last
+/// carry.
+fn signed_digits<B: BigInteger>(
+    scalar: &B,
+    window_bits: usize,
+    number_of_windows: usize,
+) -> Vec<i64> {
+    let limbs = scalar.as_ref();
+    let radix = 1u64 << window_bits;
+    let mut carry = 0;
+    let mut digits: Vec<i64> = (0..number_of_windows)
+        .map(|window| {
+            let (limb, shift) = (window * window_bits / 64, window * window_bits % 64);
+            let mut bits = limbs[limb] >> shift;
+            if shift + window_bits > 64 && limb + 1 < limbs.len() {
+                bits |= limbs[limb + 1] << (64 - shift);
+            }
+            let coefficient = carry + (bits & (radix - 1));
+            carry = (coefficient + radix / 2) >> window_bits;
+            coefficient as i64 - (carry << window_bits) as i64
+        })
+        .collect();
+    digits[number_of_windows - 1] += (carry << window_bits) as i64;
+
+    digits
 }
@@ -1,43 +1,64 @@
 use super::super::Commitment;
-use ark_ec::{AffineCurve, ProjectiveCurve};
-use ark_ff::Zero;
-use ark_std::UniformRand;
-use rand::Rng;
+use ark_crypto_primitives::sponge::Absorb;
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::{PrimeField, Zero};
+use ark_std::{rand::Rng, UniformRand};
 use std::ops::Mul;
 
-impl<C: ProjectiveCurve> Mul<C::ScalarField> for Commitment<C> {
+impl<G: AffineRepr> Mul<G::ScalarField> for Commitment<G> {
     type Output = Self;
-    fn mul(self, x: C::ScalarField) -> Self::Output {
-        Self(self.0.mul(x).into_affine())
+
+    fn mul(self, x: G::ScalarField) -> Self::Output {
+        Self((self.0 * x).into_affine())
     }
 }
 
-impl<C: ProjectiveCurve> std::ops::Add for Commitment<C> {
+impl<G: AffineRepr> std::ops::Add for Commitment<G> {
     type Output = Self;
 
-    fn add(self, _rhs: Self) -> Self {
-        Self(self.0 + _rhs.0)
+    fn add(self, rhs: Self) -> Self {
+        Self((self.0 + rhs.0).into_affine())
     }
 }
 
-impl<C: ProjectiveCurve> std::iter::Sum for Commitment<C> {
+impl<G: AffineRepr> std::iter::Sum for Commitment<G> {
     fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
-        iter.fold(Self::zero(), |a, b| a + b)
+        Self(
+            iter.map(|commitment| commitment.0)
+                .sum::<G::Group>()
+                .into_affine(),
+        )
     }
 }
 
-impl<C: ProjectiveCurve> Zero for Commitment<C> {
+impl<G: AffineRepr> Zero for Commitment<G> {
     fn zero() -> Self {
-        Self(C::Affine::zero())
+        Self(G::zero())
     }
 
     fn is_zero(&self) -> bool {
-        *self == Self(C::Affine::zero())
+        self.0.is_zero()
     }
 }
 
-impl<C: ProjectiveCurve> UniformRand for Commitment<C> {
+impl<G: AffineRepr> UniformRand for Commitment<G> {
     fn rand<R: Rng + ?Sized>(rng: &mut R) -> Self {
-        Self(C::rand(rng).into_affine())
+        Self(G::Group::rand(rng).into_affine())
+    }
+}
+
+/// Points are absorbed as their compressed encoding, whose coordinates live in the base field of the
+/// curve rather than the field of the transcript.
+impl<G: AffineRepr> Absorb for Commitment<G> {
+    fn to_sponge_bytes(&self, dest: &mut Vec<u8>) {
+        self.0
+            .serialize_compressed(dest)
+            .expect("serializing into a vector cannot fail")
+    }
+
+    fn to_sponge_field_elements<F: PrimeField>(&self, dest: &mut Vec<F>) {
+        let mut bytes = Vec::new();
+        self.to_sponge_bytes(&mut bytes);
+        bytes.to_sponge_field_elements(dest)
     }
 }
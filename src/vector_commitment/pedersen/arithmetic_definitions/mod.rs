@@ -1 +1 @@
-pub mod commitment;
+mod commitment;
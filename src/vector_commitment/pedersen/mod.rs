@@ -1,90 +1,114 @@
-use crate::errors::SangriaError;
-use crate::vector_commitment::HomomorphicCommitmentScheme;
-
-use ark_ec::{msm::VariableBaseMSM, ProjectiveCurve};
-use ark_ff::{PrimeField, ToBytes};
-use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
-use ark_std::{
-    io::{Read, Write},
-    marker::PhantomData,
-};
-use ark_std::rand::Rng;
-
-pub mod arithmetic_definitions;
+//! Pedersen vector commitments over any elliptic curve, with no pairing, so that Sangria can run on
+//! curves such as the Pasta cycle.
+
+use crate::errors::{PCSError, SangriaError};
+use crate::vector_commitment::{check_batch_lengths, interleaved_msm, HomomorphicCommitmentScheme};
+
+use ark_ec::{AffineRepr, CurveGroup, VariableBaseMSM};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{marker::PhantomData, rand::Rng, UniformRand};
+
+mod arithmetic_definitions;
+#[cfg(test)]
 mod tests;
 
-pub struct PedersenCommitment<C: ProjectiveCurve> {
-    _curve: PhantomData<C>,
+/// The Pedersen vector commitment over the points `G` of any elliptic curve, with no pairing: a
+/// vector `x` blinded with `r` is committed to as `r h + x_1 g_1 + ... + x_n g_n` for random bases
+/// `g_i` and `h`. Commitments can be added and scaled by the verifier to fold instances, and are
+/// absorbed by transcripts over any field.
+pub struct PedersenCommitment<G: AffineRepr> {
+    _curve: PhantomData<G>,
 }
 
-#[derive(Clone, CanonicalSerialize, CanonicalDeserialize, Debug)]
-pub struct CommitKey<C: ProjectiveCurve> {
-    g: Vec<C::Affine>,
-    h: C::Affine,
+/// The bases of a Pedersen commitment: one per position of the committed vectors and one for the
+/// randomness.
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct CommitKey<G: AffineRepr> {
+    g: Vec<G>,
+    h: G,
 }
 
-impl<C: ProjectiveCurve> CommitKey<C> {
-    pub fn new(g: Vec<C::Affine>, h: C::Affine) -> Self {
+impl<G: AffineRepr> CommitKey<G> {
+    /// Creates a commit key from the bases `g` of the vector positions and the base `h` of the
+    /// randomness.
+    pub fn new(g: Vec<G>, h: G) -> Self {
         Self { g, h }
     }
 }
 
-impl<C: ProjectiveCurve> ToBytes for CommitKey<C> {
-    fn write<W: Write>(&self, mut w: W) -> ark_std::io::Result<()> {
-        self.g.write(&mut w)?;
-        self.h.write(&mut w)?;
+/// A Pedersen commitment, in affine form.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Commitment<G: AffineRepr>(pub G);
 
-        Ok(())
-    }
-}
-
-#[derive(Clone, Copy, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
-pub struct Commitment<C: ProjectiveCurve>(pub C::Affine);
+impl<G: AffineRepr> HomomorphicCommitmentScheme<G::ScalarField> for PedersenCommitment<G> {
+    type CommitKey = CommitKey<G>;
+    type Commitment = Commitment<G>;
 
-impl<C: ProjectiveCurve> ToBytes for Commitment<C> {
-    fn write<W: Write>(&self, mut w: W) -> ark_std::io::Result<()> {
-        self.0.write(&mut w)?;
+    fn setup<R: Rng>(public_randomness: &mut R, len: usize) -> CommitKey<G> {
+        let bases: Vec<G::Group> = (0..=len)
+            .map(|_| G::Group::rand(public_randomness))
+            .collect();
+        let mut g = G::Group::normalize_batch(&bases);
+        let h = g.pop().expect("the randomness has a base");
 
-        Ok(())
+        CommitKey { g, h }
     }
-}
 
-impl<C: ProjectiveCurve> HomomorphicCommitmentScheme<C::ScalarField> for PedersenCommitment<C> {
-    type CommitKey = CommitKey<C>;
-    type Commitment = Commitment<C>;
-
-    fn setup<R: Rng>(public_randomess: &mut R, len: usize) -> CommitKey<C> {
-        let mut g = Vec::with_capacity(len);
-        for _ in 0..len {
-            g.push(C::rand(public_randomess).into_affine());
-        }
-        let h = C::rand(public_randomess).into_affine();
-        CommitKey::<C> { g, h }
+    fn max_len(commit_key: &CommitKey<G>) -> usize {
+        commit_key.g.len()
     }
 
     fn commit(
-        commit_key: &CommitKey<C>,
-        x: &Vec<C::ScalarField>,
-        r: C::ScalarField,
-    ) -> Result<Self::Commitment, CryptoError> {
+        commit_key: &CommitKey<G>,
+        x: &[G::ScalarField],
+        r: G::ScalarField,
+    ) -> Result<Self::Commitment, SangriaError> {
         if x.len() > commit_key.g.len() {
-            return Err(CryptoError::CommitmentLengthError(
-                String::from("Pedersen"),
-                x.len(),
-                commit_key.g.len(),
-            ));
+            return Err(PCSError::DegreeBound {
+                end: x.len(),
+                max_len: commit_key.g.len(),
+            }
+            .into());
         }
 
-        let scalars = [&[r], x.as_slice()]
-            .concat()
-            .iter()
-            .map(|x| x.into_repr())
-            .collect::<Vec<_>>();
+        let commitment = G::Group::msm_unchecked(&commit_key.g, x) + commit_key.h * r;
+        Ok(Commitment(commitment.into_affine()))
+    }
+
+    /// Commits to all the vectors in one pass over the bases with an interleaved MSM and
+    /// normalizes the commitments together.
+    fn batch_commit(
+        commit_key: &CommitKey<G>,
+        xs: &[Vec<G::ScalarField>],
+        r: &[G::ScalarField],
+    ) -> Result<Vec<Self::Commitment>, SangriaError> {
+        check_batch_lengths(xs, r, commit_key.g.len())?;
+
+        let commitments: Vec<G::Group> = interleaved_msm(&commit_key.g, xs)
+            .into_iter()
+            .zip(r)
+            .map(|(commitment, &r)| commitment + commit_key.h * r)
+            .collect();
+        Ok(G::Group::normalize_batch(&commitments)
+            .into_iter()
+            .map(Commitment)
+            .collect())
+    }
 
-        let bases = [&[commit_key.h], &commit_key.g[..]].concat();
+    fn commit_chunk(
+        commit_key: &CommitKey<G>,
+        offset: usize,
+        x: &[G::ScalarField],
+    ) -> Result<Self::Commitment, SangriaError> {
+        if offset + x.len() > commit_key.g.len() {
+            return Err(PCSError::DegreeBound {
+                end: offset + x.len(),
+                max_len: commit_key.g.len(),
+            }
+            .into());
+        }
 
-        Ok(Commitment(
-            VariableBaseMSM::multi_scalar_mul(&bases, &scalars[..]).into_affine(),
-        ))
+        let commitment = G::Group::msm_unchecked(&commit_key.g[offset..], x);
+        Ok(Commitment(commitment.into_affine()))
     }
 }
@@ -1,85 +1,131 @@
-#[cfg(test)]
-mod test {
-    use crate::utils::rand::sample_vector;
-    use crate::vector_commitment::{pedersen, HomomorphicCommitmentScheme};
-    use ark_ff::Zero;
-    use ark_std::{rand::thread_rng, UniformRand};
-    use starknet_curve;
-    use std::ops::Mul;
-
-    // Define type aliases for succinctness
-    type Curve = starknet_curve::Projective;
-    type Scalar = starknet_curve::Fr;
-    type Pedersen = pedersen::PedersenCommitment<Curve>;
-
-    #[test]
-    fn additive_homomorphism() {
-        let rng = &mut thread_rng();
-        let n = 52;
-
-        let commit_key = Pedersen::setup(rng, n);
-
-        let r1 = Scalar::rand(rng);
-        let r2 = Scalar::rand(rng);
-
-        let v1: Vec<Scalar> = sample_vector(rng, n);
-        let v2: Vec<Scalar> = sample_vector(rng, n);
-
-        let alpha = Scalar::rand(rng);
-        let beta = Scalar::rand(rng);
-
-        let v3 = v1
-            .iter()
-            .zip(v2.iter())
-            .map(|(&a, &b)| a * alpha + b * beta)
-            .collect::<Vec<_>>();
-        let r3 = alpha * r1 + beta * r2;
-
-        let commit_v1 = Pedersen::commit(&commit_key, &v1, r1).unwrap();
-        let commit_v2 = Pedersen::commit(&commit_key, &v2, r2).unwrap();
-        let commit_v3 = Pedersen::commit(&commit_key, &v3, r3).unwrap();
-
-        let expected = commit_v1.mul(alpha) + commit_v2.mul(beta);
-
-        assert_eq!(expected, commit_v3)
-    }
+use crate::utils::rand::sample_vector;
+use crate::vc_testsuite::check_all;
+use crate::vector_commitment::{pedersen, HomomorphicCommitmentScheme};
+use crate::{PCSError, SangriaError};
+use ark_ff::Zero;
+use ark_std::{test_rng, UniformRand};
+use std::ops::Mul;
 
-    #[test]
-    fn short_commitment() {
-        let rng = &mut thread_rng();
-        let n = 10;
+// Define type aliases for succinctness
+type Point = ark_bn254::G1Affine;
+type Scalar = ark_bn254::Fr;
+type Pedersen = pedersen::PedersenCommitment<Point>;
 
-        let commit_key = Pedersen::setup(rng, n);
+#[test]
+fn additive_homomorphism() {
+    let rng = &mut test_rng();
+    let n = 52;
 
-        let r = Scalar::rand(rng);
+    let commit_key = Pedersen::setup(rng, n);
 
-        let s1 = Scalar::rand(rng);
-        let zero = Scalar::zero();
+    let r1 = Scalar::rand(rng);
+    let r2 = Scalar::rand(rng);
 
-        let v1 = vec![s1, zero, zero, zero];
+    let v1: Vec<Scalar> = sample_vector(rng, n);
+    let v2: Vec<Scalar> = sample_vector(rng, n);
 
-        let commit_v1 = Pedersen::commit(&commit_key, &v1, r).unwrap();
+    let alpha = Scalar::rand(rng);
+    let beta = Scalar::rand(rng);
 
-        let commit_s1 = Pedersen::commit(&commit_key, &vec![s1], r).unwrap();
+    let v3 = v1
+        .iter()
+        .zip(v2.iter())
+        .map(|(&a, &b)| a * alpha + b * beta)
+        .collect::<Vec<_>>();
+    let r3 = alpha * r1 + beta * r2;
 
-        assert_eq!(v1[0], s1);
-        assert_eq!(commit_v1, commit_s1);
-    }
+    let commit_v1 = Pedersen::commit(&commit_key, &v1, r1).unwrap();
+    let commit_v2 = Pedersen::commit(&commit_key, &v2, r2).unwrap();
+    let commit_v3 = Pedersen::commit(&commit_key, &v3, r3).unwrap();
+
+    let expected = commit_v1.mul(alpha) + commit_v2.mul(beta);
+
+    assert_eq!(expected, commit_v3)
+}
+
+#[test]
+fn short_commitment() {
+    let rng = &mut test_rng();
+    let n = 10;
+
+    let commit_key = Pedersen::setup(rng, n);
+
+    let r = Scalar::rand(rng);
+
+    let s1 = Scalar::rand(rng);
+    let zero = Scalar::zero();
+
+    let v1 = vec![s1, zero, zero, zero];
+
+    let commit_v1 = Pedersen::commit(&commit_key, &v1, r).unwrap();
 
-    #[test]
-    #[should_panic]
-    fn too_many_values() {
-        let rng = &mut thread_rng();
-        let n = 5;
+    let commit_s1 = Pedersen::commit(&commit_key, &[s1], r).unwrap();
 
-        let commit_key = Pedersen::setup(rng, n);
+    assert_eq!(commit_v1, commit_s1);
+}
+
+#[test]
+fn too_many_values() {
+    let rng = &mut test_rng();
+    let n = 5;
+
+    let commit_key = Pedersen::setup(rng, n);
 
-        let r = Scalar::rand(rng);
+    let r = Scalar::rand(rng);
 
-        let s1 = Scalar::rand(rng);
+    let s1 = Scalar::rand(rng);
 
-        let too_long = vec![s1; n + 2];
+    let too_long = vec![s1; n + 2];
 
-        let _commit = Pedersen::commit(&commit_key, &too_long, r).unwrap();
+    assert_eq!(
+        Pedersen::commit(&commit_key, &too_long, r),
+        Err(SangriaError::Commitment(PCSError::DegreeBound {
+            end: n + 2,
+            max_len: n
+        }))
+    );
+}
+
+#[test]
+fn pedersen_scheme_conforms() {
+    check_all::<Scalar, Pedersen, _>(&mut test_rng(), 8);
+}
+
+#[test]
+fn batch_commitments_match_single_commitments() {
+    let rng = &mut test_rng();
+    let n = 100;
+
+    let commit_key = Pedersen::setup(rng, n);
+
+    // vectors of several lengths, so that the windows of the interleaved MSM are wider than 3 bits
+    let xs: Vec<Vec<Scalar>> = [n, 40, 0, 7]
+        .into_iter()
+        .map(|len| sample_vector(rng, len))
+        .collect();
+    let r: Vec<Scalar> = (0..xs.len()).map(|_| Scalar::rand(rng)).collect();
+
+    let commitments = Pedersen::batch_commit(&commit_key, &xs, &r).unwrap();
+    for ((x, &r), commitment) in xs.iter().zip(&r).zip(commitments) {
+        assert_eq!(commitment, Pedersen::commit(&commit_key, x, r).unwrap());
     }
+
+    assert_eq!(
+        Pedersen::batch_commit(
+            &commit_key,
+            &[vec![Scalar::zero(); n + 1]],
+            &[Scalar::zero()]
+        ),
+        Err(SangriaError::Commitment(PCSError::DegreeBound {
+            end: n + 1,
+            max_len: n
+        }))
+    );
+    assert_eq!(
+        Pedersen::batch_commit(&commit_key, &xs, &r[1..]),
+        Err(SangriaError::Commitment(PCSError::BatchLength {
+            vectors: 4,
+            randomness: 3
+        }))
+    );
 }
@@ -0,0 +1,306 @@
+use ark_ff::PrimeField;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+use ark_std::io::{Read, Write};
+
+use crate::{fe_canonical_length, fe_from_bytes_canonical, fe_to_bytes_canonical};
+
+/// A run of zero elements.
+const ZEROS: u8 = 0;
+/// A run of elements equal to zero or one, packed eight per byte (least significant bit first).
+const BITS: u8 = 1;
+/// A run of elements each written as a length byte followed by their canonical little-endian bytes
+/// without the trailing zeros.
+const TRIMMED: u8 = 2;
+
+/// Zeros inside a run of bits are kept in the run unless there are at least this many of them, in
+/// which case a run of zeros is cheaper.
+const MIN_ZERO_RUN: usize = 16;
+
+/// The maximum number of elements allocated before they are read, so that decoding with a loose
+/// bound cannot be made to allocate a large vector by a forged element count.
+const MAX_PREALLOCATED: usize = 1 << 16;
+
+/// Writes `elements` in a compact encoding for witness vectors, which are dominated by zeros (unused
+/// wires and slack rows of satisfied gates), by bits (booleans and selectors) and by small integers.
+/// The encoding starts with the number of elements and splits the elements into runs of zeros, of
+/// bit-packed zeros and ones, and of elements written with the canonical encoding of
+/// [`fe_to_bytes_canonical`] without its trailing zero bytes. The encoding is deterministic, so equal
+/// vectors have equal encodings.
+pub fn encode_field_elements<F, W>(elements: &[F], mut writer: W) -> Result<(), SerializationError>
+where
+    F: PrimeField,
+    W: Write,
+{
+    (elements.len() as u64).serialize_compressed(&mut writer)?;
+
+    let mut start = 0;
+    while start < elements.len() {
+        let (tag, len) = next_run(&elements[start..]);
+        writer.write_all(&[tag])?;
+        write_varint(&mut writer, len as u64)?;
+
+        let run = &elements[start..start + len];
+        match tag {
+            ZEROS => {}
+            BITS => {
+                for byte in run.chunks(8) {
+                    let packed = byte.iter().enumerate().fold(0u8, |packed, (index, &bit)| {
+                        packed | (u8::from(bit.is_one()) << index)
+                    });
+                    writer.write_all(&[packed])?;
+                }
+            }
+            _ => {
+                for element in run {
+                    let bytes = trimmed_bytes(element);
+                    writer.write_all(&[bytes.len() as u8])?;
+                    writer.write_all(&bytes)?;
+                }
+            }
+        }
+        start += len;
+    }
+
+    Ok(())
+}
+
+/// Returns the length in bytes of the encoding of `elements` by [`encode_field_elements`].
+pub fn encoded_len<F: PrimeField>(elements: &[F]) -> usize {
+    let mut bytes = Vec::new();
+    encode_field_elements(elements, &mut bytes).expect("writing to a vector does not fail");
+
+    bytes.len()
+}
+
+/// Reads elements encoded by [`encode_field_elements`] from untrusted input, rejecting more than
+/// `max_len` elements before allocating them, empty or overlong runs, unknown run tags and
+/// non-canonical encodings of elements.
+pub fn decode_field_elements<F, R>(
+    mut reader: R,
+    max_len: usize,
+) -> Result<Vec<F>, SerializationError>
+where
+    F: PrimeField,
+    R: Read,
+{
+    let len = u64::deserialize_compressed(&mut reader)?;
+    if len > max_len as u64 {
+        return Err(SerializationError::InvalidData);
+    }
+
+    let len = len as usize;
+    let mut elements = Vec::with_capacity(len.min(MAX_PREALLOCATED));
+    while elements.len() < len {
+        let tag = read_byte(&mut reader)?;
+        let run_len = read_varint(&mut reader)?;
+        if run_len == 0 || run_len > (len - elements.len()) as u64 {
+            return Err(SerializationError::InvalidData);
+        }
+
+        let run_len = run_len as usize;
+        match tag {
+            ZEROS => elements.extend((0..run_len).map(|_| F::zero())),
+            BITS => {
+                for start in (0..run_len).step_by(8) {
+                    let packed = read_byte(&mut reader)?;
+                    let bits = (run_len - start).min(8);
+                    // the padding bits of the last byte must be zero
+                    if bits < 8 && packed >> bits != 0 {
+                        return Err(SerializationError::InvalidData);
+                    }
+                    elements.extend((0..bits).map(|index| F::from((packed >> index) & 1 == 1)));
+                }
+            }
+            TRIMMED => {
+                for _ in 0..run_len {
+                    elements.push(read_trimmed(&mut reader)?);
+                }
+            }
+            _ => return Err(SerializationError::InvalidData),
+        }
+    }
+
+    Ok(elements)
+}
+
+/// Returns the tag and the length of the run starting at the first of `elements`.
+fn next_run<F: PrimeField>(elements: &[F]) -> (u8, usize) {
+    let is_bit = |element: &F| element.is_zero() || element.is_one();
+    let zeros_from = |start: usize| {
+        elements[start..]
+            .iter()
+            .take_while(|element| element.is_zero())
+            .count()
+    };
+
+    if elements[0].is_zero() {
+        return (ZEROS, zeros_from(0));
+    }
+    if !elements[0].is_one() {
+        return (
+            TRIMMED,
+            elements
+                .iter()
+                .take_while(|element| !is_bit(element))
+                .count(),
+        );
+    }
+
+    let mut len = 0;
+    while len < elements.len() && is_bit(&elements[len]) {
+        let zeros = zeros_from(len);
+        if zeros >= MIN_ZERO_RUN || len + zeros == elements.len() {
+            break;
+        }
+        len += zeros.max(1);
+    }
+
+    (BITS, len)
+}
+
+/// Returns the canonical encoding of `element` without its trailing zero bytes.
+fn trimmed_bytes<F: PrimeField>(element: &F) -> Vec<u8> {
+    let mut bytes = fe_to_bytes_canonical(element);
+    let len = bytes
+        .iter()
+        .rposition(|&byte| byte != 0)
+        .map_or(0, |last| last + 1);
+    bytes.truncate(len);
+
+    bytes
+}
+
+/// Reads an element written by [`trimmed_bytes`] after its length, rejecting empty encodings and
+/// encodings exceeding the canonical length or ending with a zero byte.
+fn read_trimmed<F: PrimeField, R: Read>(mut reader: R) -> Result<F, SerializationError> {
+    let len = read_byte(&mut reader)? as usize;
+    if len == 0 || len > fe_canonical_length::<F>() {
+        return Err(SerializationError::InvalidData);
+    }
+
+    let mut bytes = vec![0u8; fe_canonical_length::<F>()];
+    reader.read_exact(&mut bytes[..len])?;
+    if bytes[len - 1] == 0 {
+        return Err(SerializationError::InvalidData);
+    }
+
+    fe_from_bytes_canonical(&bytes).map_err(|_| SerializationError::InvalidData)
+}
+
+fn read_byte<R: Read>(mut reader: R) -> Result<u8, SerializationError> {
+    let mut byte = [0u8];
+    reader.read_exact(&mut byte)?;
+
+    Ok(byte[0])
+}
+
+/// Writes `value` as a LEB128 variable-length integer.
+fn write_varint<W: Write>(mut writer: W, mut value: u64) -> Result<(), SerializationError> {
+    while value >= 0x80 {
+        writer.write_all(&[(value as u8) | 0x80])?;
+        value >>= 7;
+    }
+    writer.write_all(&[value as u8])?;
+
+    Ok(())
+}
+
+/// Reads a LEB128 variable-length integer written by [`write_varint`], rejecting encodings that
+/// overflow a `u64` or end with a redundant zero byte.
+fn read_varint<R: Read>(mut reader: R) -> Result<u64, SerializationError> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = read_byte(&mut reader)?;
+        let bits = u64::from(byte & 0x7f);
+        if bits << shift >> shift != bits || (shift > 0 && byte == 0) {
+            return Err(SerializationError::InvalidData);
+        }
+
+        value |= bits << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+
+    Err(SerializationError::InvalidData)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_field_elements, encode_field_elements, encoded_len};
+    use crate::fe_canonical_length;
+    use ark_bn254::Fr;
+    use ark_ff::{One, Zero};
+    use ark_serialize::SerializationError;
+    use ark_std::{test_rng, UniformRand};
+
+    #[test]
+    fn witness_like_vectors_round_trip_compactly() {
+        let rng = &mut test_rng();
+        let mut elements = vec![Fr::zero(); 1000];
+        elements.extend((0..200u64).map(|index| Fr::from(index % 3 == 0)));
+        elements.extend((0..100u64).map(|index| Fr::from(index * 1000)));
+        elements.extend((0..20).map(|_| Fr::rand(rng)));
+        elements.extend([-Fr::one(), Fr::one(), Fr::zero(), Fr::from(2u64)]);
+
+        let mut bytes = Vec::new();
+        encode_field_elements(&elements, &mut bytes).unwrap();
+        assert_eq!(bytes.len(), encoded_len(&elements));
+        assert_eq!(
+            decode_field_elements::<Fr, _>(&bytes[..], elements.len()).unwrap(),
+            elements
+        );
+
+        // less than a tenth of the canonical encoding of every element
+        assert!(bytes.len() * 10 < elements.len() * fe_canonical_length::<Fr>());
+
+        for elements in [vec![], vec![Fr::one()], vec![Fr::one(), Fr::zero()]] {
+            let mut bytes = Vec::new();
+            encode_field_elements(&elements, &mut bytes).unwrap();
+            assert_eq!(
+                decode_field_elements::<Fr, _>(&bytes[..], 2).unwrap(),
+                elements
+            );
+        }
+    }
+
+    #[test]
+    fn malformed_encodings_are_rejected() {
+        let header = |len: u64| len.to_le_bytes().to_vec();
+        let with_runs = |len: u64, runs: &[u8]| [header(len), runs.to_vec()].concat();
+
+        let malformed = [
+            // more elements than allowed
+            header(5),
+            // an empty run, and a run longer than the vector
+            with_runs(2, &[0, 0]),
+            with_runs(2, &[0, 3]),
+            // an unknown tag
+            with_runs(1, &[7, 1]),
+            // a bit run with a padding bit set
+            with_runs(1, &[1, 1, 0b10]),
+            // a trimmed element with a trailing zero byte, and one longer than the canonical length
+            with_runs(1, &[2, 1, 2, 5, 0]),
+            with_runs(1, &[2, 1, 33]),
+            // the modulus
+            with_runs(
+                1,
+                &[
+                    2, 1, 32, 0x01, 0x00, 0x00, 0xf0, 0x93, 0xf5, 0xe1, 0x43, 0x91, 0x70, 0xb9,
+                    0x79, 0x48, 0xe8, 0x33, 0x28, 0x5d, 0x58, 0x81, 0x81, 0xb6, 0x45, 0x50, 0xb8,
+                    0x29, 0xa0, 0x31, 0xe1, 0x72, 0x4e, 0x64, 0x30,
+                ],
+            ),
+            // a run length with a redundant byte
+            with_runs(1, &[0, 0x81, 0x00]),
+        ];
+
+        for bytes in malformed {
+            assert!(matches!(
+                decode_field_elements::<Fr, _>(&bytes[..], 4),
+                Err(SerializationError::InvalidData)
+            ));
+        }
+        assert!(decode_field_elements::<Fr, _>(&with_runs(2, &[0, 1])[..], 4).is_err());
+    }
+}